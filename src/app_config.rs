@@ -1,10 +1,15 @@
 //src/app_config.rs
 
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::env;
-use config::{Config, ConfigError, File};
+use std::fmt;
+use std::fs;
+use config::{Config, File};
 use std::convert::TryFrom;
 use std::path::PathBuf;
+use std::str::FromStr;
+use crate::core::models::{RevenueTier, RewardRounding, TieBreak};
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct AppConfig {
@@ -15,11 +20,87 @@ pub struct AppConfig {
     pub default_total_counted_seats: usize,
     pub default_max_earner_seats: usize,
     pub default_qualified_majority_threshold: f64,
+    pub default_quorum: f64,
     pub counted_vote_points: u32,
     pub uncounted_vote_points: u32,
+    pub strict_state_checksum: bool,
+    pub unique_representatives: bool,
+    pub undo_history_size: usize,
+    pub storage_backend: StorageBackend,
+    /// Base URL for attachment links, e.g. "https://ipfs.io/ipfs"; empty renders `ipfs://` links
+    pub ipfs_gateway: String,
+    /// How raffles order teams whose tickets tie
+    pub raffle_tie_break: TieBreak,
+    /// How long a raffle waits for its randomness block before giving up
+    pub raffle_block_timeout_secs: u64,
+    /// Sponsoring teams a proposal needs before a formal vote can be opened
+    pub min_sponsors: usize,
+    /// Days after announcement by which a proposal without an explicit deadline should be decided
+    pub default_decision_sla_days: u64,
+    /// Raffle tickets for earner teams by revenue; empty keeps the square-root scale
+    pub revenue_tiers: Vec<RevenueTier>,
+    /// Hash-chained log of state-changing commands; empty disables it
+    pub audit_log: String,
+    /// Signs reward payouts: empty disables them, `env` reads PAYOUT_PRIVATE_KEY, anything else is a keystore path
+    pub payout_signer: String,
+    /// How team reward amounts are rounded when an epoch closes
+    pub reward_rounding: RewardRounding,
+    /// Whether reward withheld from adjusted teams goes to the other teams or stays unallocated
+    pub redistribute_withheld_rewards: bool,
+    /// Encrypts the JSON state file: empty keeps it in plaintext, `env` reads STATE_ENCRYPTION_KEY, anything else is a key file path
+    pub state_encryption_key: String,
+    /// USD value of one unit of each token, used to total requests across tokens
+    pub token_rates: HashMap<String, f64>,
     pub telegram: TelegramConfig,
 }
 
+/// How the state is persisted; both backends use `state_file` as the path
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    #[default]
+    Json,
+    Sqlite,
+}
+
+impl FromStr for StorageBackend {
+    type Err = config::ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(StorageBackend::Json),
+            "sqlite" => Ok(StorageBackend::Sqlite),
+            other => Err(config::ConfigError::Message(format!("Unknown storage_backend: {}", other))),
+        }
+    }
+}
+
+/// A setting, or combination of settings, that would only fail later in a raffle or vote
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConfigError {
+    NoCountedSeats,
+    EarnerSeatsExceedCounted { max_earner_seats: usize, total_counted_seats: usize },
+    FractionOutOfRange { setting: &'static str, value: f64 },
+    MissingTelegramToken,
+    UnreadableIpcPath { path: String, reason: String },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::NoCountedSeats => write!(f, "default_total_counted_seats must be at least 1"),
+            ConfigError::EarnerSeatsExceedCounted { max_earner_seats, total_counted_seats } => write!(
+                f, "default_max_earner_seats ({}) cannot exceed default_total_counted_seats ({})", max_earner_seats, total_counted_seats
+            ),
+            ConfigError::FractionOutOfRange { setting, value } => write!(f, "{} must be between 0 and 1, got {}", setting, value),
+            ConfigError::MissingTelegramToken => write!(f, "TELEGRAM_BOT_TOKEN is empty"),
+            ConfigError::UnreadableIpcPath { path, reason } => write!(f, "ipc_path {} cannot be read: {}", path, reason),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
 #[derive(Clone, Debug, Deserialize)]
 pub struct TelegramConfig {
     pub chat_id: String,
@@ -28,7 +109,7 @@ pub struct TelegramConfig {
 }
 
 impl AppConfig {
-    pub fn new() -> Result<Self, ConfigError> {
+    pub fn new() -> Result<Self, config::ConfigError> {
         let mut settings = Config::default();
 
         // Start off with default values
@@ -39,8 +120,25 @@ impl AppConfig {
         settings.set_default("default_total_counted_seats", 7)?;
         settings.set_default("default_max_earner_seats", 5)?;
         settings.set_default("default_qualified_majority_threshold", 0.7)?;
+        settings.set_default("default_quorum", 0.0)?;
         settings.set_default("counted_vote_points", 5)?;
         settings.set_default("uncounted_vote_points", 2)?;
+        settings.set_default("strict_state_checksum", false)?;
+        settings.set_default("unique_representatives", false)?;
+        settings.set_default("undo_history_size", 10)?;
+        settings.set_default("storage_backend", "json")?;
+        settings.set_default("ipfs_gateway", "")?;
+        settings.set_default("raffle_tie_break", "lowest_team_id")?;
+        settings.set_default("raffle_block_timeout_secs", 300)?;
+        settings.set_default("min_sponsors", 0)?;
+        settings.set_default("default_decision_sla_days", 14)?;
+        settings.set_default("revenue_tiers", Vec::<String>::new())?;
+        settings.set_default("audit_log", "")?;
+        settings.set_default("payout_signer", "")?;
+        settings.set_default("reward_rounding", "none")?;
+        settings.set_default("redistribute_withheld_rewards", true)?;
+        settings.set_default("state_encryption_key", "")?;
+        settings.set_default("token_rates", HashMap::<String, f64>::new())?;
         settings.set_default("telegram.chat_id", "")?;
 
         // Add in the current environment file
@@ -54,7 +152,7 @@ impl AppConfig {
         
         // Expand the tilde in the state_file path
         if config.state_file.starts_with('~') {
-            let home = dirs::home_dir().ok_or(ConfigError::Message("Unable to determine home directory".to_string()))?;
+            let home = dirs::home_dir().ok_or(config::ConfigError::Message("Unable to determine home directory".to_string()))?;
             config.state_file = home.join(config.state_file.strip_prefix("~/").unwrap_or(&config.state_file)).to_string_lossy().into_owned();
         }
 
@@ -65,10 +163,41 @@ impl AppConfig {
 
         Ok(config)
     }
+
+    /// Checks the settings that are only used deep inside raffles and votes, returning every problem found
+    pub fn validate(&self) -> Result<(), Vec<ConfigError>> {
+        let mut errors = Vec::new();
+
+        if self.default_total_counted_seats == 0 {
+            errors.push(ConfigError::NoCountedSeats);
+        }
+        if self.default_max_earner_seats > self.default_total_counted_seats {
+            errors.push(ConfigError::EarnerSeatsExceedCounted {
+                max_earner_seats: self.default_max_earner_seats,
+                total_counted_seats: self.default_total_counted_seats,
+            });
+        }
+        for (setting, value) in [
+            ("default_qualified_majority_threshold", self.default_qualified_majority_threshold),
+            ("default_quorum", self.default_quorum),
+        ] {
+            if !(0.0..=1.0).contains(&value) {
+                errors.push(ConfigError::FractionOutOfRange { setting, value });
+            }
+        }
+        if self.telegram.token.trim().is_empty() {
+            errors.push(ConfigError::MissingTelegramToken);
+        }
+        if let Err(e) = fs::metadata(&self.ipc_path) {
+            errors.push(ConfigError::UnreadableIpcPath { path: self.ipc_path.clone(), reason: e.to_string() });
+        }
+
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
 }
 
 impl TryFrom<Config> for AppConfig {
-    type Error = ConfigError;
+    type Error = config::ConfigError;
 
     fn try_from(config: Config) -> Result<Self, Self::Error> {
         Ok(Self {
@@ -79,8 +208,27 @@ impl TryFrom<Config> for AppConfig {
             default_total_counted_seats: config.get_int("default_total_counted_seats")? as usize,
             default_max_earner_seats: config.get_int("default_max_earner_seats")? as usize,
             default_qualified_majority_threshold: config.get_float("default_qualified_majority_threshold")?,
+            default_quorum: config.get_float("default_quorum")?,
             counted_vote_points: config.get_int("counted_vote_points")? as u32,
             uncounted_vote_points: config.get_int("uncounted_vote_points")? as u32,
+            strict_state_checksum: config.get_bool("strict_state_checksum")?,
+            unique_representatives: config.get_bool("unique_representatives")?,
+            undo_history_size: config.get_int("undo_history_size")? as usize,
+            storage_backend: config.get_string("storage_backend")?.parse()?,
+            ipfs_gateway: config.get_string("ipfs_gateway")?,
+            raffle_tie_break: config.get_string("raffle_tie_break")?.parse()
+                .map_err(|e: &str| config::ConfigError::Message(e.to_string()))?,
+            raffle_block_timeout_secs: config.get_int("raffle_block_timeout_secs")? as u64,
+            min_sponsors: config.get_int("min_sponsors")? as usize,
+            default_decision_sla_days: config.get_int("default_decision_sla_days")? as u64,
+            revenue_tiers: config.get("revenue_tiers")?,
+            audit_log: config.get_string("audit_log")?,
+            payout_signer: config.get_string("payout_signer")?,
+            reward_rounding: config.get_string("reward_rounding")?.parse()
+                .map_err(|e: &str| config::ConfigError::Message(e.to_string()))?,
+            redistribute_withheld_rewards: config.get_bool("redistribute_withheld_rewards")?,
+            state_encryption_key: config.get_string("state_encryption_key")?,
+            token_rates: config.get("token_rates")?,
             telegram: TelegramConfig {
                 chat_id: config.get_string("telegram.chat_id")?,
                 token: String::new(),
@@ -99,8 +247,25 @@ impl Default for AppConfig {
             default_total_counted_seats: 7,
             default_max_earner_seats: 5,
             default_qualified_majority_threshold: 0.7,
+            default_quorum: 0.0,
             counted_vote_points: 5,
             uncounted_vote_points: 2,
+            strict_state_checksum: false,
+            unique_representatives: false,
+            undo_history_size: 10,
+            storage_backend: StorageBackend::Json,
+            ipfs_gateway: String::new(),
+            raffle_tie_break: TieBreak::LowestTeamId,
+            raffle_block_timeout_secs: 300,
+            min_sponsors: 0,
+            default_decision_sla_days: 14,
+            revenue_tiers: Vec::new(),
+            audit_log: String::new(),
+            payout_signer: String::new(),
+            reward_rounding: RewardRounding::None,
+            redistribute_withheld_rewards: true,
+            state_encryption_key: String::new(),
+            token_rates: HashMap::new(),
             telegram: TelegramConfig {
                 chat_id: String::new(),
                 token: String::new(),
@@ -124,8 +289,32 @@ mod tests {
         assert_eq!(config.default_total_counted_seats, 7);
         assert_eq!(config.default_max_earner_seats, 5);
         assert_eq!(config.default_qualified_majority_threshold, 0.7);
+        assert_eq!(config.default_quorum, 0.0);
         assert_eq!(config.counted_vote_points, 5);
         assert_eq!(config.uncounted_vote_points, 2);
+        assert!(!config.strict_state_checksum);
+        assert!(!config.unique_representatives);
+        assert_eq!(config.undo_history_size, 10);
+        assert_eq!(config.storage_backend, StorageBackend::Json);
+        assert_eq!(config.ipfs_gateway, "");
+        assert_eq!(config.raffle_tie_break, TieBreak::LowestTeamId);
+        assert_eq!(config.raffle_block_timeout_secs, 300);
+        assert_eq!(config.min_sponsors, 0);
+        assert_eq!(config.default_decision_sla_days, 14);
+        assert!(config.revenue_tiers.is_empty());
+        assert_eq!(config.audit_log, "");
+        assert_eq!(config.payout_signer, "");
+        assert_eq!(config.reward_rounding, RewardRounding::None);
+        assert!(config.redistribute_withheld_rewards);
+        assert_eq!(config.state_encryption_key, "");
+        assert!(config.token_rates.is_empty());
+    }
+
+    #[test]
+    fn test_storage_backend_from_str() {
+        assert_eq!("SQLite".parse::<StorageBackend>().unwrap(), StorageBackend::Sqlite);
+        assert_eq!("json".parse::<StorageBackend>().unwrap(), StorageBackend::Json);
+        assert!("postgres".parse::<StorageBackend>().is_err());
     }
 
     #[test]
@@ -147,4 +336,65 @@ mod tests {
         env::remove_var("APP_STATE_FILE");
         env::remove_var("TELEGRAM_BOT_TOKEN");
     }
-}
\ No newline at end of file
+
+    fn valid_config(dir: &tempfile::TempDir) -> AppConfig {
+        let ipc_path = dir.path().join("reth.ipc");
+        fs::write(&ipc_path, "").unwrap();
+        let mut config = AppConfig::default();
+        config.ipc_path = ipc_path.to_string_lossy().into_owned();
+        config.telegram.token = "test_token".to_string();
+        config
+    }
+
+    #[test]
+    fn test_validate_accepts_valid_config() {
+        let dir = tempfile::TempDir::new().unwrap();
+        assert_eq!(valid_config(&dir).validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_seats() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut config = valid_config(&dir);
+        config.default_max_earner_seats = 8;
+        assert_eq!(config.validate(), Err(vec![ConfigError::EarnerSeatsExceedCounted { max_earner_seats: 8, total_counted_seats: 7 }]));
+
+        config.default_max_earner_seats = 0;
+        config.default_total_counted_seats = 0;
+        assert_eq!(config.validate(), Err(vec![ConfigError::NoCountedSeats]));
+    }
+
+    #[test]
+    fn test_validate_fractions() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut config = valid_config(&dir);
+        config.default_qualified_majority_threshold = 1.5;
+        config.default_quorum = -0.1;
+        assert_eq!(config.validate(), Err(vec![
+            ConfigError::FractionOutOfRange { setting: "default_qualified_majority_threshold", value: 1.5 },
+            ConfigError::FractionOutOfRange { setting: "default_quorum", value: -0.1 },
+        ]));
+
+        config.default_qualified_majority_threshold = f64::NAN;
+        config.default_quorum = 0.0;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_telegram_token() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut config = valid_config(&dir);
+        config.telegram.token = " ".to_string();
+        assert_eq!(config.validate(), Err(vec![ConfigError::MissingTelegramToken]));
+    }
+
+    #[test]
+    fn test_validate_ipc_path() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut config = valid_config(&dir);
+        config.ipc_path = dir.path().join("missing.ipc").to_string_lossy().into_owned();
+        let errors = config.validate().unwrap_err();
+        assert!(matches!(errors.as_slice(), [ConfigError::UnreadableIpcPath { .. }]));
+        assert!(errors[0].to_string().starts_with(&format!("ipc_path {} cannot be read", config.ipc_path)));
+    }
+}
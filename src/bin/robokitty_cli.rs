@@ -1,26 +1,82 @@
 // src/bin/robokitty_cli.rs
 
 use robokitty::{initialize_environment, initialize_system};
-use robokitty::commands::cli::{parse_cli_args, execute_command};
+use robokitty::app_config::AppConfig;
+use robokitty::commands::cli::{parse_cli_args_with_format, execute_command_with_format, CliError};
+use robokitty::commands::common::{Command, OutputFormat};
+use robokitty::core::file_system::FileSystem;
+use robokitty::core::encryption::StateKey;
 use robokitty::lock;
 use std::{env, io};
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+async fn main() {
+    if let Err(error) = run().await {
+        let error = CliError::from(error);
+        eprintln!("Error: {}", error);
+        std::process::exit(error.exit_code());
+    }
+}
+
+async fn run() -> Result<(), Box<dyn std::error::Error>> {
     initialize_environment();
-    
+
     let args: Vec<String> = env::args().collect();
-    let command = parse_cli_args(&args)?;
+    let (command, format) = parse_cli_args_with_format(&args)?;
+
+    // Verify before loading, since saving afterwards would rewrite the checksum
+    if let Command::VerifyState = command {
+        let config = AppConfig::new()?;
+        let message = FileSystem::verify_state_file(&config.state_file)?;
+        match format {
+            OutputFormat::Json => println!("{}", serde_json::json!({ "command": "VerifyState", "message": message })),
+            OutputFormat::Text => println!("{}", message),
+        }
+        return Ok(());
+    }
+
+    // Also before loading, since it rewrites the file that the save after a command would overwrite
+    if let Command::MigrateState { path, dry_run } = &command {
+        let config = AppConfig::new()?;
+        let key = StateKey::from_setting(&config.state_encryption_key)?;
+        lock::create_lock_file().map_err(CliError::from_lock_error)?;
+        let result = FileSystem::migrate_state_file(path, key.as_ref(), *dry_run);
+        lock::remove_lock_file()?;
+        let message = result?;
+        match format {
+            OutputFormat::Json => println!("{}", serde_json::json!({ "command": "MigrateState", "message": message })),
+            OutputFormat::Text => println!("{}", message),
+        }
+        return Ok(());
+    }
 
     let (mut budget_system, config) = initialize_system().await?;
-    
-    lock::create_lock_file()?;
-    
+
+    // Printed like any other command, but a dirty state has to fail the process for scripts and CI
+    if let Command::ValidateState = command {
+        let failed = !budget_system.validate_state().is_empty();
+        execute_command_with_format(&mut budget_system, command, &config, format, &mut io::stdout()).await?;
+        if failed {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // Read-only, so it runs without taking the lock file and never saves
+    if let Command::Serve { port, bind } = command {
+        #[cfg(feature = "http-api")]
+        return robokitty::services::http_api::serve(budget_system, std::net::SocketAddr::new(bind, port)).await;
+        #[cfg(not(feature = "http-api"))]
+        return Err(format!("Cannot serve on {}:{}: robokitty was built without the http-api feature", bind, port).into());
+    }
+
+    lock::create_lock_file().map_err(CliError::from_lock_error)?;
+
     let mut stdout = io::stdout();
-    let result = execute_command(&mut budget_system, command, &config, &mut stdout).await;
-    
+    let result = execute_command_with_format(&mut budget_system, command, &config, format, &mut stdout).await;
+
     budget_system.save_state()?;
     lock::remove_lock_file()?;
-    
-    result
-}
\ No newline at end of file
+
+    Ok(result?)
+}
@@ -1,10 +1,10 @@
 use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, io::Write};
+use std::{collections::HashMap, io::Write, net::{IpAddr, Ipv4Addr}, str::FromStr};
 use uuid::Uuid;
 use async_trait::async_trait;
 
-use crate::core::models::VoteChoice;
+use crate::core::models::{Contact, ProposalKind, Representative, VoteChoice};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", content = "params")]
@@ -15,6 +15,13 @@ pub enum Command {
         DateTime<Utc>,
         end_date: DateTime<Utc>,
     },
+    /// Creates `count` back-to-back planned epochs named `{base_name} Q1`, `{base_name} Q2`, ...
+    CreateRecurringEpochs {
+        base_name: String,
+        start_date: DateTime<Utc>,
+        period_days: u32,
+        count: u32,
+    },
     ActivateEpoch {
         name: String
     },
@@ -25,13 +32,24 @@ pub enum Command {
     AddTeam {
         name: String,
         representative: String,
-        trailing_monthly_revenue: Option<Vec<u64>>,
+        trailing_monthly_revenue: Option<Vec<f64>>,
         address: Option<String>,
+        /// Primary contact first; stands in for an empty representative
+        #[serde(default)]
+        contacts: Vec<Contact>,
     },
     UpdateTeam {
         team_name: String,
         updates: UpdateTeamDetails,
     },
+    RenameTeam {
+        old_name: String,
+        new_name: String,
+    },
+    SetRepresentatives {
+        team_name: String,
+        representatives: Vec<Representative>,
+    },
     AddProposal {
         title: String,
         url: Option<String>,
@@ -39,6 +57,19 @@ pub enum Command {
         announced_at: Option<NaiveDate>,
         published_at: Option<NaiveDate>,
         is_historical: Option<bool>,
+        #[serde(default)]
+        tags: Vec<String>,
+        #[serde(default)]
+        is_draft: bool,
+        /// Inferred from `budget_request_details` when not given
+        #[serde(default)]
+        kind: Option<ProposalKind>,
+        /// Titles of proposals that must be approved first
+        #[serde(default)]
+        depends_on: Vec<String>,
+    },
+    PublishProposal {
+        proposal_name: String,
     },
     UpdateProposal {
         proposal_name: String,
@@ -56,6 +87,8 @@ pub enum Command {
         passed: bool,
         participating_teams: Vec<String>,
         non_participating_teams: Vec<String>,
+        #[serde(default)]
+        abstaining_teams: Vec<String>,
         counted_points: Option<u32>,
         uncounted_points: Option<u32>,
     },
@@ -67,6 +100,12 @@ pub enum Command {
         excluded_teams: Option<Vec<String>>,
         total_counted_seats: Option<usize>,
         max_earner_seats: Option<usize>,
+        /// When the randomness block was produced, if known without a live node
+        #[serde(default)]
+        randomness_timestamp: Option<DateTime<Utc>>,
+        /// The block's randomness, used instead of asking the node for it
+        #[serde(default)]
+        randomness: Option<String>,
     },
     PrintTeamReport,
     PrintEpochState,
@@ -82,6 +121,33 @@ pub enum Command {
         proposal_name: String,
         block_offset: Option<u64>,
         excluded_teams: Option<Vec<String>>,
+        /// Defaults to true; when false, inactive teams are listed as excluded in the snapshot
+        #[serde(default)]
+        exclude_inactive: Option<bool>,
+    },
+    /// Estimates a team's chance of a counted seat without creating a raffle
+    EstimateOdds {
+        team_name: String,
+        excluded_teams: Option<Vec<String>>,
+    },
+    /// What each team would receive if the epoch closed now, without closing it
+    PreviewEpochRewards {
+        epoch_name: Option<String>,
+    },
+    /// Lists the teams a raffle prepared now would include, with their ticket counts, without creating it
+    PreviewRaffleEligibility {
+        proposal_name: String,
+        excluded_teams: Option<Vec<String>>,
+    },
+    /// Scores a raffle from `seed` right away instead of waiting for a randomness block
+    CreateRaffleWithSeed {
+        proposal_name: String,
+        seed: String,
+    },
+    /// Replaces the proposal's raffle with a new draw, keeping the old one as superseded
+    RedrawRaffle {
+        proposal_name: String,
+        randomness_block: u64,
     },
     CreateAndProcessVote {
         proposal_name: String,
@@ -89,9 +155,26 @@ pub enum Command {
         uncounted_votes: HashMap<String, VoteChoice>,
         vote_opened: Option<NaiveDate>,
         vote_closed: Option<NaiveDate>,
+        /// Overrides the configured points for this vote
+        counted_points: Option<u32>,
+        uncounted_points: Option<u32>,
+        /// Overrides `default_qualified_majority_threshold` for this vote
+        #[serde(default)]
+        threshold: Option<f64>,
+    },
+    CreateAndProcessMultiOptionVote {
+        proposal_name: String,
+        options: Vec<String>,
+        counted_votes: HashMap<String, VoteChoice>,
+        uncounted_votes: HashMap<String, VoteChoice>,
+        vote_opened: Option<NaiveDate>,
+        vote_closed: Option<NaiveDate>,
     },
     GenerateReportsForClosedProposals {
-        epoch_name: String
+        epoch_name: String,
+        /// Only regenerate proposals resolved on or after this date
+        #[serde(default)]
+        since: Option<NaiveDate>,
     },
     GenerateReportForProposal {
         proposal_name: String
@@ -100,18 +183,349 @@ pub enum Command {
         epoch_name: Option<String>
      },
     CloseEpoch {
-        epoch_name: Option<String>
+        epoch_name: Option<String>,
+        #[serde(default)]
+        confirm_no_reward: bool,
+        #[serde(default)]
+        carry_over_to: Option<String>,
     },
     GenerateEndOfEpochReport {
-        epoch_name: String
+        epoch_name: String,
+        #[serde(default)]
+        format: ReportFormat,
+    },
+    /// Closes the epoch, generates all of its reports and zips them
+    FinalizeEpoch {
+        epoch_name: String,
     },
     RunScript {
         script_file_path: Option<String>,
+        /// Turns AddTeam and AddProposal into upserts keyed by name
+        #[serde(default)]
+        idempotent: bool,
+    },
+    /// Starts the read-only HTTP API; handled by the CLI binary
+    Serve {
+        port: u16,
+        /// The API has no authentication, so it binds to loopback unless told otherwise
+        #[serde(default = "default_serve_bind")]
+        bind: IpAddr,
+    },
+    /// Runs a script against an in-memory copy of the state and reports each command's outcome
+    SandboxScript {
+        script_file_path: Option<String>,
     },
     GenerateUnpaidRequestsReport {
         output_path: Option<String>,
         epoch_name: Option<String>,
     },
+    ActionItems,
+    VerifyRaffle {
+        proposal_name: String,
+    },
+    ExportRaffleTickets {
+        proposal_name: String,
+        #[serde(default)]
+        format: ExportFormat,
+    },
+    ExportEpochRewardsCsv {
+        epoch_name: String,
+    },
+    DilutionReport {
+        epoch_name: Option<String>,
+    },
+    VerifyState,
+    /// Upgrades a state file to the current schema version, keeping a backup of the original
+    MigrateState {
+        path: String,
+        #[serde(default)]
+        dry_run: bool,
+    },
+    ValidateState,
+    DumpScript,
+    RecomputeVoteOutcome {
+        vote_id: Uuid,
+        commit: bool,
+    },
+    /// Opens a formal vote for the proposal of a closed informal vote, linked to it
+    PromoteInformalVote {
+        vote_id: Uuid,
+        raffle_id: Uuid,
+    },
+    /// Clears the votes cast on an open vote so they can be cast again
+    ResetVote {
+        vote_id: Uuid,
+    },
+    CashflowForecast {
+        epoch_name: Option<String>,
+    },
+    TeamApprovalVelocity {
+        team_name: String,
+    },
+    RecordPayment {
+        proposal_name: String,
+        tx_hash: String,
+    },
+    CheckPayment {
+        proposal_name: String,
+    },
+    CheckRepresentatives,
+    Undo,
+    CompetitionRatio {
+        epoch_name: Option<String>,
+    },
+    ReopenProposal {
+        proposal_name: String,
+    },
+    RemoveProposal {
+        proposal_name: String,
+    },
+    MoveProposal {
+        proposal_name: String,
+        epoch_name: String,
+    },
+    ImportTeamsCsv {
+        file_path: String,
+    },
+    ImportProposalsCsv {
+        file_path: String,
+    },
+    RedactTeam {
+        team_name: String,
+    },
+    /// Takes a team off the roster, keeping it for historical reports
+    ArchiveTeam {
+        team_name: String,
+    },
+    /// Moves the source team's vote and raffle history onto the target team and archives the source
+    MergeTeams {
+        source_name: String,
+        target_name: String,
+    },
+    EffectiveTeamCount {
+        epoch_name: Option<String>,
+    },
+    AddAttachment {
+        proposal_name: String,
+        label: String,
+        cid: String,
+    },
+    AddSponsor {
+        proposal_name: String,
+        team_name: String,
+    },
+    TopUpReward {
+        epoch_name: String,
+        amount: f64,
+        token: String,
+    },
+    /// Sets the reward of a planned or active epoch by name
+    UpdateEpochReward {
+        epoch_name: String,
+        token: String,
+        amount: f64,
+    },
+    /// Scales a team's reward for an epoch when it closes; 1.0 removes the adjustment
+    SetRewardAdjustment {
+        epoch_name: String,
+        team_name: String,
+        multiplier: f64,
+        #[serde(default)]
+        reason: String,
+    },
+    /// Sends a team's outstanding reward for a closed epoch on chain
+    PayTeamReward {
+        epoch_name: String,
+        team_name: String,
+    },
+    EpochAmendmentLog {
+        epoch_name: Option<String>,
+    },
+    SetEpochBudgetCap {
+        epoch_name: Option<String>,
+        token: String,
+        amount: f64,
+    },
+    EpochFunnel {
+        epoch_name: Option<String>,
+    },
+    CompareEpochs {
+        epoch_a: String,
+        epoch_b: String,
+    },
+    OpenVoteStatus {
+        vote_id: Uuid,
+    },
+    /// Teams with a raffle seat that have not voted on the proposal's open (or latest) formal vote
+    OutstandingVoters {
+        proposal_name: String,
+    },
+    Search {
+        query: String,
+    },
+    OverdueProposals {
+        epoch_name: Option<String>,
+    },
+    /// Replays the epoch's formal votes under a hypothetical threshold, without changing anything
+    SimulateThreshold {
+        epoch_name: Option<String>,
+        threshold: f64,
+    },
+    AddMilestonePayment {
+        proposal_name: String,
+        amount: f64,
+        token: String,
+        date: NaiveDate,
+        tx: Option<String>,
+    },
+    ListProposals {
+        epoch_name: Option<String>,
+        status: Option<String>,
+        team_name: Option<String>,
+        announced_after: Option<NaiveDate>,
+        announced_before: Option<NaiveDate>,
+        tag: Option<String>,
+    },
+    ProjectedCompletion,
+}
+
+/// A script file: either a bare array of commands, or an object with options and a `commands` array
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Script {
+    /// Turns AddTeam and AddProposal into upserts keyed by name, so a partially applied script can be re-run
+    #[serde(default)]
+    pub idempotent: bool,
+    pub commands: Vec<Command>,
+}
+
+impl Script {
+    pub fn from_json(content: &str) -> Result<Self, serde_json::Error> {
+        if content.trim_start().starts_with('[') {
+            Ok(Script { idempotent: false, commands: serde_json::from_str(content)? })
+        } else {
+            serde_json::from_str(content)
+        }
+    }
+}
+
+fn default_serve_bind() -> IpAddr {
+    IpAddr::V4(Ipv4Addr::LOCALHOST)
+}
+
+impl Command {
+    /// Variant name, as used for the `type` tag in scripts
+    pub fn name(&self) -> String {
+        serde_json::to_value(self).ok()
+            .and_then(|value| value.get("type").and_then(|t| t.as_str()).map(String::from))
+            .unwrap_or_default()
+    }
+
+    /// Whether the command can change the state, and so can be undone.
+    /// Scripts are not listed, since each of their commands is recorded on its own.
+    pub fn is_mutating(&self) -> bool {
+        match self {
+            Command::RecomputeVoteOutcome { commit, .. } => *commit,
+            Command::PrintTeamReport
+            | Command::PrintEpochState
+            | Command::PrintTeamVoteParticipation { .. }
+            | Command::GenerateReportsForClosedProposals { .. }
+            | Command::GenerateReportForProposal { .. }
+            | Command::PrintPointReport { .. }
+            | Command::GenerateEndOfEpochReport { .. }
+            | Command::RunScript { .. }
+            | Command::Serve { .. }
+            | Command::SandboxScript { .. }
+            | Command::MigrateState { .. }
+            | Command::GenerateUnpaidRequestsReport { .. }
+            | Command::ActionItems
+            | Command::VerifyRaffle { .. }
+            | Command::ExportEpochRewardsCsv { .. }
+            | Command::ExportRaffleTickets { .. }
+            | Command::DilutionReport { .. }
+            | Command::VerifyState
+            | Command::ValidateState
+            | Command::DumpScript
+            | Command::CashflowForecast { .. }
+            | Command::TeamApprovalVelocity { .. }
+            | Command::CheckRepresentatives
+            | Command::CheckPayment { .. }
+            | Command::Undo
+            | Command::CompetitionRatio { .. }
+            | Command::EffectiveTeamCount { .. }
+            | Command::EpochAmendmentLog { .. }
+            | Command::ListProposals { .. }
+            | Command::EpochFunnel { .. }
+            | Command::CompareEpochs { .. }
+            | Command::OpenVoteStatus { .. }
+            | Command::OutstandingVoters { .. }
+            | Command::Search { .. }
+            | Command::OverdueProposals { .. }
+            | Command::SimulateThreshold { .. }
+            | Command::ProjectedCompletion
+            | Command::EstimateOdds { .. }
+            | Command::PreviewRaffleEligibility { .. }
+            | Command::PreviewEpochRewards { .. } => false,
+            _ => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+/// File format of a generated report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    #[default]
+    Markdown,
+    Html,
+}
+
+impl FromStr for ReportFormat {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "markdown" | "md" => Ok(ReportFormat::Markdown),
+            "html" => Ok(ReportFormat::Html),
+            _ => Err("Unknown report format. Expected markdown or html"),
+        }
+    }
+}
+
+/// File format of exported raw data
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "csv" => Ok(ExportFormat::Csv),
+            "json" => Ok(ExportFormat::Json),
+            _ => Err("Unknown export format. Expected csv or json"),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -119,8 +533,10 @@ pub struct UpdateTeamDetails {
     pub name: Option<String>,
     pub representative: Option<String>,
     pub status: Option<String>,
-    pub trailing_monthly_revenue: Option<Vec<u64>>,
+    pub trailing_monthly_revenue: Option<Vec<f64>>,
     pub address: Option<String>,
+    #[serde(default)]
+    pub contacts: Option<Vec<Contact>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -131,6 +547,8 @@ pub struct BudgetRequestDetailsCommand {
     pub end_date: Option<NaiveDate>,
     pub is_loan: Option<bool>,
     pub payment_address: Option<String>,
+    #[serde(default)]
+    pub monthly_breakdown: Option<Vec<HashMap<String, f64>>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -141,6 +559,14 @@ pub struct UpdateProposalDetails {
     pub announced_at: Option<NaiveDate>,
     pub published_at: Option<NaiveDate>,
     pub resolved_at: Option<NaiveDate>,
+    /// Replaces the proposal's tags when set
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
+    #[serde(default)]
+    pub decision_deadline: Option<NaiveDate>,
+    /// Replaces the proposal's dependencies, given as proposal titles, when set
+    #[serde(default)]
+    pub depends_on: Option<Vec<String>>,
 }
 
 #[async_trait]
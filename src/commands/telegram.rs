@@ -1,7 +1,7 @@
 use teloxide::utils::command::BotCommands;
 use crate::escape_markdown;
 use crate::core::budget_system::BudgetSystem;
-use crate::core::models::VoteChoice;
+use crate::core::models::{Contact, VoteChoice};
 use crate::commands::common::{Command, CommandExecutor, BudgetRequestDetailsCommand, UpdateProposalDetails, UpdateTeamDetails};
 use chrono::{NaiveDate, DateTime, Utc, TimeZone};
 use std::collections::HashMap;
@@ -59,7 +59,7 @@ pub enum TelegramCommand {
     },
 
     /// Add a new team. 
-    /// Usage: /add_team name:TeamName rep:Representative [rev:1000,2000,3000]
+    /// Usage: /add_team name:TeamName rep:Representative [rev:1000,2000,3000] [contact:telegram:@handle]
     /// For supporter teams, omit the rev parameter
     /// 
     AddTeam {
@@ -67,7 +67,7 @@ pub enum TelegramCommand {
     },
 
     /// Update a team's details. 
-    /// Usage: /update_team team:TeamName [name:NewName] [rep:NewRep] [status:Earner|Supporter|Inactive] [rev:1000,2000,3000]
+    /// Usage: /update_team team:TeamName [name:NewName] [rep:NewRep] [status:Earner|Supporter|Inactive] [rev:1000,2000,3000] [contact:email:me@example.com]
     /// Note: Earner status requires revenue data
     /// 
         UpdateTeam {
@@ -115,14 +115,25 @@ pub enum TelegramCommand {
         args: String,
     },
 
+    /// Show all open items requiring action.
+    /// 
+    Todo,
+
+    /// List the teams that have not voted on a proposal yet. Usage: /outstanding <proposal_name>
+    /// 
+    Outstanding {
+        proposal_name: String,
+    },
+
 }
 
 #[derive(Debug)]
 struct AddTeamArgs {
     name: String,
     representative: String,
-    revenue: Option<Vec<u64>>,
+    revenue: Option<Vec<f64>>,
     address: Option<String>,
+    contacts: Vec<Contact>,
 }
 
 #[derive(Debug)]
@@ -131,8 +142,9 @@ struct UpdateTeamArgs {
     new_name: Option<String>,
     representative: Option<String>,
     status: Option<String>,
-    revenue: Option<Vec<u64>>,
+    revenue: Option<Vec<f64>>,
     address: Option<String>,
+    contacts: Option<Vec<Contact>>,
 }
 
 #[derive(Debug)]
@@ -241,15 +253,17 @@ impl TelegramCommand {
         let mut representative = None;
         let mut revenue = None;
         let mut address = None;
+        let mut contacts = Vec::new();
 
         for arg in args {
             if let Some((key, value)) = arg.split_once(':') {
                 match key {
                     "name" => name = Some(value.to_string()),
                     "rep" => representative = Some(value.to_string()),
+                    "contact" => contacts.push(value.parse::<Contact>()?),
                     "rev" => {
                         revenue = Some(value.split(',')
-                            .map(|v| v.parse::<u64>())
+                            .map(|v| v.parse::<f64>())
                             .collect::<Result<Vec<_>, _>>()
                             .map_err(|e| format!("Invalid revenue format: {}", e))?)
                     },
@@ -259,11 +273,19 @@ impl TelegramCommand {
             }
         }
 
+        // A contact can stand in for the representative
+        let representative = match representative {
+            Some(representative) => representative,
+            None if !contacts.is_empty() => String::new(),
+            None => return Err("Missing rep parameter".to_string()),
+        };
+
         Ok(AddTeamArgs {
             name: name.ok_or("Missing name parameter")?,
-            representative: representative.ok_or("Missing rep parameter")?,
+            representative,
             revenue,
             address,
+            contacts,
         })
     }
 
@@ -274,6 +296,7 @@ impl TelegramCommand {
         let mut status = None;
         let mut revenue = None;
         let mut address = None;
+        let mut contacts: Option<Vec<Contact>> = None;
 
         for arg in args {
             if let Some((key, value)) = arg.split_once(':') {
@@ -290,11 +313,12 @@ impl TelegramCommand {
                     },
                     "rev" => {
                         revenue = Some(value.split(',')
-                            .map(|v| v.parse::<u64>())
+                            .map(|v| v.parse::<f64>())
                             .collect::<Result<Vec<_>, _>>()
                             .map_err(|e| format!("Invalid revenue format: {}", e))?)
                     },
                     "address" => address = Some(value.to_string()),
+                    "contact" => contacts.get_or_insert_with(Vec::new).push(value.parse::<Contact>()?),
                     _ => return Err(format!("Unknown parameter: {}", key))
                 }
             }
@@ -306,7 +330,8 @@ impl TelegramCommand {
             representative,
             status,
             revenue,
-            address
+            address,
+            contacts,
         })
     }
 
@@ -485,7 +510,8 @@ impl TelegramCommand {
                     let choice = match parts[1].to_lowercase().as_str() {
                         "yes" => VoteChoice::Yes,
                         "no" => VoteChoice::No,
-                        _ => return Err(format!("Invalid vote choice: {}. Must be Yes or No", parts[1])),
+                        "abstain" => VoteChoice::Abstain,
+                        _ => return Err(format!("Invalid vote choice: {}. Must be Yes, No or Abstain", parts[1])),
                     };
                     Ok((parts[0].to_string(), choice))
                 })
@@ -569,6 +595,18 @@ pub async fn execute_command(
             .map_err(|e| format!("Command failed: {}", e))
         },
 
+        TelegramCommand::Todo => {
+            budget_system.execute_command(Command::ActionItems).await
+            .map(|s| escape_markdown(&s))
+            .map_err(|e| format!("Command failed: {}", e))
+        },
+
+        TelegramCommand::Outstanding { proposal_name } => {
+            budget_system.execute_command(Command::OutstandingVoters { proposal_name: proposal_name.trim().to_string() }).await
+            .map(|s| escape_markdown(&s))
+            .map_err(|e| format!("Command failed: {}", e))
+        },
+
         TelegramCommand::PrintTeamParticipation { team_name, epoch_name } => {
             budget_system.execute_command(Command::PrintTeamVoteParticipation { 
                 team_name, 
@@ -619,6 +657,7 @@ pub async fn execute_command(
                 representative: team_args.representative,
                 trailing_monthly_revenue: team_args.revenue,
                 address: team_args.address,
+                contacts: team_args.contacts,
             }).await
             .map(|s| escape_markdown(&s))
             .map_err(|e| format!("Command failed: {}", e))
@@ -649,6 +688,7 @@ pub async fn execute_command(
                     status: update_args.status,
                     trailing_monthly_revenue: update_args.revenue,
                     address: update_args.address,
+                    contacts: update_args.contacts,
                 }
             }).await
             .map(|s| escape_markdown(&s))
@@ -671,7 +711,8 @@ pub async fn execute_command(
                     end_date: proposal_args.end_date
                         .and_then(|d| NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok()),
                     is_loan: proposal_args.is_loan,
-                    payment_address: proposal_args.payment_address
+                    payment_address: proposal_args.payment_address,
+                    monthly_breakdown: None,
                 })
             } else {
                 None
@@ -686,6 +727,10 @@ pub async fn execute_command(
                 published_at: proposal_args.published_date
                     .and_then(|d| NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok()),
                 is_historical: None,
+                tags: Vec::new(),
+                is_draft: false,
+                kind: None,
+                depends_on: Vec::new(),
             }).await
             .map(|s| escape_markdown(&s))
             .map_err(|e| format!("Command failed: {}", e))
@@ -708,6 +753,7 @@ pub async fn execute_command(
                         .and_then(|d| NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok()),
                     is_loan: update_args.is_loan,
                     payment_address: update_args.payment_address,
+                    monthly_breakdown: None,
                 })
             } else {
                 None
@@ -725,6 +771,9 @@ pub async fn execute_command(
                         .and_then(|d| NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok()),
                     resolved_at: update_args.resolved_date
                         .and_then(|d| NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok()),
+                    tags: None,
+                    decision_deadline: None,
+                    depends_on: None,
                 }
             }).await
             .map(|s| escape_markdown(&s))
@@ -759,6 +808,9 @@ pub async fn execute_command(
                 uncounted_votes: parsed_args.uncounted_votes,
                 vote_opened: parsed_args.vote_opened,
                 vote_closed: parsed_args.vote_closed,
+                counted_points: None,
+                uncounted_points: None,
+                threshold: None,
             }).await
             .map(|s| escape_markdown(&s))
             .map_err(|e| format!("Command failed: {}", e))
@@ -775,6 +827,7 @@ pub async fn execute_command(
                 proposal_name: parsed_args.proposal_name, 
                 block_offset: parsed_args.block_offset, 
                 excluded_teams: parsed_args.excluded_teams, 
+                exclude_inactive: None,
             }).await
             .map(|s| escape_markdown(&s))
             .map_err(|e| format!("Command failed: {}", e))
@@ -839,8 +892,25 @@ mod tests {
             default_total_counted_seats: 7,
             default_max_earner_seats: 5,
             default_qualified_majority_threshold: 0.7,
+            default_quorum: 0.0,
             counted_vote_points: 5,
             uncounted_vote_points: 2,
+            strict_state_checksum: false,
+            unique_representatives: false,
+            undo_history_size: 10,
+            storage_backend: crate::app_config::StorageBackend::Json,
+            ipfs_gateway: String::new(),
+            raffle_tie_break: crate::core::models::TieBreak::LowestTeamId,
+            raffle_block_timeout_secs: 300,
+            min_sponsors: 0,
+            default_decision_sla_days: 14,
+            revenue_tiers: Vec::new(),
+            audit_log: String::new(),
+            payout_signer: String::new(),
+            reward_rounding: crate::core::models::RewardRounding::None,
+            redistribute_withheld_rewards: true,
+            state_encryption_key: String::new(),
+            token_rates: HashMap::new(),
             telegram: crate::app_config::TelegramConfig {
                 chat_id: "test_chat_id".to_string(),
                 token: "test_token".to_string(),
@@ -883,7 +953,7 @@ mod tests {
         let team_args = TelegramCommand::parse_add_team(&args).unwrap();
         assert_eq!(team_args.name, "Test Team");
         assert_eq!(team_args.representative, "John Doe");
-        assert_eq!(team_args.revenue, Some(vec![1000, 2000, 3000]));
+        assert_eq!(team_args.revenue, Some(vec![1000.0, 2000.0, 3000.0]));
     }
 
     #[test]
@@ -895,6 +965,22 @@ mod tests {
         assert_eq!(team_args.revenue, None);
     }
 
+    #[test]
+    fn test_parse_team_contacts() {
+        let args = TelegramCommand::parse_command("name:Contact Team contact:telegram:@jane_doe contact:email:jane@example.com").unwrap();
+        let team_args = TelegramCommand::parse_add_team(&args).unwrap();
+        assert_eq!(team_args.representative, "");
+        assert_eq!(team_args.contacts.len(), 2);
+        assert_eq!(team_args.contacts[0].value, "@jane_doe");
+
+        let args = TelegramCommand::parse_command("name:Contact Team contact:telegram:jane").unwrap();
+        assert!(TelegramCommand::parse_add_team(&args).is_err());
+
+        let args = TelegramCommand::parse_command("team:Contact Team contact:email:jane@example.com").unwrap();
+        let update_args = TelegramCommand::parse_update_team(&args).unwrap();
+        assert_eq!(update_args.contacts.unwrap().len(), 1);
+    }
+
     #[test]
     fn test_parse_add_proposal_args() {
         let input = "title:Test Proposal url:https://example.com team:Test Team amounts:ETH:100";
@@ -941,6 +1027,22 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_parse_todo_command() {
+        assert!(matches!(
+            TelegramCommand::parse("/todo", "bot_name").unwrap(),
+            TelegramCommand::Todo
+        ));
+    }
+
+    #[test]
+    fn test_parse_outstanding_command() {
+        match TelegramCommand::parse("/outstanding Grant Proposal", "bot_name").unwrap() {
+            TelegramCommand::Outstanding { proposal_name } => assert_eq!(proposal_name, "Grant Proposal"),
+            _ => panic!("Wrong command parsed"),
+        }
+    }
+
     #[test]
     fn test_parse_print_team_participation_command() {
         let cmd = TelegramCommand::parse("/print_team_participation TeamA EpochB", "bot_name").unwrap();
@@ -1120,7 +1222,7 @@ mod tests {
         assert_eq!(update_args.new_name, Some("New Team".to_string()));
         assert_eq!(update_args.representative, Some("New Rep".to_string()));
         assert_eq!(update_args.status, Some("Supporter".to_string()));
-        assert_eq!(update_args.revenue, Some(vec![1000, 2000, 3000]));
+        assert_eq!(update_args.revenue, Some(vec![1000.0, 2000.0, 3000.0]));
     }
 
     #[test]
@@ -1416,8 +1518,8 @@ mod tests {
     // //         None
     // //     ).unwrap();
 
-    // //     budget_system.create_team("Team1".to_string(), "Rep1".to_string(), Some(vec![1000])).unwrap();
-    // //     budget_system.create_team("Team2".to_string(), "Rep2".to_string(), Some(vec![2000])).unwrap();
+    // //     budget_system.create_team("Team1".to_string(), "Rep1".to_string(), Some(vec![1000.0])).unwrap();
+    // //     budget_system.create_team("Team2".to_string(), "Rep2".to_string(), Some(vec![2000.0])).unwrap();
 
     // //      // Setup block progression before executing command
     // //     if let Some(mock_service) = get_mock_service(&budget_system) {
@@ -1462,8 +1564,8 @@ mod tests {
     //     ).unwrap();
 
     //     // Add some teams
-    //     budget_system.create_team("Team1".to_string(), "Rep1".to_string(), Some(vec![1000])).unwrap();
-    //     budget_system.create_team("Team2".to_string(), "Rep2".to_string(), Some(vec![2000])).unwrap();
+    //     budget_system.create_team("Team1".to_string(), "Rep1".to_string(), Some(vec![1000.0])).unwrap();
+    //     budget_system.create_team("Team2".to_string(), "Rep2".to_string(), Some(vec![2000.0])).unwrap();
 
     //     // Setup block progression with completion notification
     //     if let Some(mock_service) = get_mock_service(&budget_system) {
@@ -1616,7 +1718,7 @@ mod tests {
         let team_id = budget_system.create_team(
             "Test Team".to_string(),
             "Representative".to_string(),
-            Some(vec![1000]),
+            Some(vec![1000.0]),
             None
         ).unwrap();
 
@@ -0,0 +1,104 @@
+// src/core/audit.rs
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::{
+    error::Error,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+};
+
+/// One line of the append-only audit log. `hash` covers the previous line's hash and this
+/// entry, so editing, reordering or removing a line breaks every link after it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: String,
+    pub operation: String,
+    pub arguments: Value,
+    pub hash: String,
+}
+
+impl AuditEntry {
+    fn compute_hash(previous_hash: &str, timestamp: &str, operation: &str, arguments: &Value) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(previous_hash.as_bytes());
+        hasher.update(json!({ "timestamp": timestamp, "operation": operation, "arguments": arguments }).to_string().as_bytes());
+        format!("{:x}", hasher.finalize())
+    }
+}
+
+/// Appends an entry chained to the last line of the log, creating the file if needed
+pub fn append(path: &str, operation: &str, arguments: Value) -> Result<AuditEntry, Box<dyn Error>> {
+    let previous_hash = last_hash(path)?;
+    let timestamp = Utc::now().to_rfc3339();
+    let hash = AuditEntry::compute_hash(&previous_hash, &timestamp, operation, &arguments);
+    let entry = AuditEntry { timestamp, operation: operation.to_string(), arguments, hash };
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+    Ok(entry)
+}
+
+fn last_hash(path: &str) -> Result<String, Box<dyn Error>> {
+    if !Path::new(path).exists() {
+        return Ok(String::new());
+    }
+    let content = fs::read_to_string(path)?;
+    match content.lines().rev().find(|line| !line.trim().is_empty()) {
+        Some(line) => Ok(serde_json::from_str::<AuditEntry>(line)?.hash),
+        None => Ok(String::new()),
+    }
+}
+
+/// Recomputes every hash link and returns the number of entries, or an error naming the first bad line
+pub fn verify_chain(path: &str) -> Result<usize, Box<dyn Error>> {
+    let content = fs::read_to_string(path)?;
+    let mut previous_hash = String::new();
+    let mut count = 0;
+
+    for (index, line) in content.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+        let entry: AuditEntry = serde_json::from_str(line)
+            .map_err(|e| format!("Audit log line {} is not a valid entry: {}", index + 1, e))?;
+        let expected = AuditEntry::compute_hash(&previous_hash, &entry.timestamp, &entry.operation, &entry.arguments);
+        if entry.hash != expected {
+            return Err(format!("Audit log hash mismatch on line {}", index + 1).into());
+        }
+        previous_hash = entry.hash;
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_audit_chain() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("audit.log").to_str().unwrap().to_string();
+
+        let first = append(&path, "AddTeam", json!({ "name": "Team A" })).unwrap();
+        let second = append(&path, "CreateEpoch", json!({ "name": "Q1", "amount": 1.5 })).unwrap();
+        append(&path, "Undo", Value::Null).unwrap();
+        assert_ne!(first.hash, second.hash);
+        assert_eq!(verify_chain(&path).unwrap(), 3);
+
+        // Editing an entry breaks its own link
+        let content = fs::read_to_string(&path).unwrap();
+        fs::write(&path, content.replace("Team A", "Team B")).unwrap();
+        let err = verify_chain(&path).unwrap_err().to_string();
+        assert!(err.contains("line 1"));
+
+        // Dropping an entry breaks the link of the one after it
+        let lines: Vec<&str> = content.lines().collect();
+        fs::write(&path, format!("{}\n{}\n", lines[0], lines[2])).unwrap();
+        let err = verify_chain(&path).unwrap_err().to_string();
+        assert!(err.contains("line 2"));
+    }
+}
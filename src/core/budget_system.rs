@@ -2,36 +2,42 @@
 
 use crate::core::state::BudgetSystemState;
 use crate::core::models::{
-    Team, TeamStatus, Epoch, EpochStatus, EpochReward, TeamReward,
-    Proposal, ProposalStatus, Resolution, BudgetRequestDetails,
-    Raffle, RaffleConfig, RaffleResult, RaffleTicket,
-    Vote, VoteType, VoteStatus, VoteChoice, VoteCount, VoteParticipation, VoteResult, get_id_by_name
+    Team, TeamStatus, Representative, Contact, TREASURER_ROLE, Epoch, EpochStatus, EpochReward, TeamReward,
+    Proposal, ProposalKind, ProposalStatus, Resolution, BudgetRequestDetails, Amendment, ProposalFilter, Payment,
+    Raffle, RaffleConfig, RaffleResult, RaffleTicket, RaffleParticipationStatus, TeamSnapshot,
+    Vote, VoteType, VoteStatus, VoteChoice, VoteCount, VoteParticipation, VoteResult, InformalWeighting, get_id_by_name
 };
 use crate::core::progress::raffle::{RaffleProgress, RaffleCreationError};
-use crate::core::models::common::{NameMatches, UnpaidRequest, UnpaidRequestsReport};
-use crate::services::ethereum::EthereumServiceTrait;
+use crate::core::models::common::{
+    NameMatches, UnpaidRequest, UnpaidRequestsReport, ActionItem, ActionItems, ActionPriority, Funnel, IntegrityError, CsvImportReport,
+    SearchResults, SearchHitKind, NotFoundError, VoteParticipationEntry, ProposalReportsSummary
+};
+use crate::services::ethereum::{EthereumServiceTrait, MockEthereumService, TxStatus};
 use crate::commands::common::{ 
-    UpdateProposalDetails, UpdateTeamDetails, Command, CommandExecutor
+    BudgetRequestDetailsCommand, UpdateProposalDetails, UpdateTeamDetails, Command, CommandExecutor, ReportFormat, ExportFormat
 };
 use crate::app_config::AppConfig;
-use crate::core::file_system::FileSystem;
-use crate::escape_markdown;
+use crate::core::file_system::{FileSystem, StateChecksum};
+use crate::core::store::{open_store, NullStore, StateStore};
+use crate::core::audit;
+use crate::{escape_html, escape_markdown};
 
 use chrono::{DateTime, NaiveDate, Utc, TimeZone};
 use uuid::Uuid;
 use std::{
     cell::RefCell,
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
     error::Error, fmt,
     fs,
     io::Write,
     path::{Path, PathBuf},
     str,
-    sync::Arc,
+    sync::{Arc, Mutex},
     task::{Context, Poll},
     pin::Pin
 };
-use log::{info, debug, error};
+use log::{info, debug, warn, error};
+use serde_json::json;
 use async_trait::async_trait;
 use tokio::{time::Duration, sync::mpsc};
 use futures::{pin_mut, Stream, StreamExt, stream::unfold};
@@ -43,6 +49,23 @@ pub struct BudgetSystem {
     state: BudgetSystemState,
     ethereum_service: Arc<dyn EthereumServiceTrait>,
     config: AppConfig,
+    store: Box<dyn StateStore>,
+    /// States from before recent mutating commands, oldest first; never persisted
+    undo_history: VecDeque<BudgetSystemState>,
+    /// Receives notifications such as closed votes; CLI-only usage leaves it unset
+    event_sender: Option<mpsc::Sender<SystemEvent>>,
+    /// Points per (team, epoch), filled on demand and cleared whenever the state is saved or replaced
+    team_points: Mutex<HashMap<(Uuid, Uuid), u32>>,
+    /// Operation and arguments of the running command, written to the audit log by its first save
+    pending_audit: Mutex<Option<(String, serde_json::Value)>>,
+    /// Makes AddTeam and AddProposal update an existing team or proposal of the same name; never persisted
+    idempotent: bool,
+}
+
+/// Notifications sent once the change they describe has been saved
+#[derive(Debug, Clone, PartialEq)]
+pub enum SystemEvent {
+    VoteClosed { vote_id: Uuid, passed: bool },
 }
 
 
@@ -58,17 +81,66 @@ impl fmt::Display for BudgetSystemError {
 
 impl Error for BudgetSystemError {}
 
+/// Nominal length of a vote, counted from the day it was opened
+const VOTE_WINDOW_DAYS: i64 = 7;
+/// Open votes with this many days or fewer left in their window are flagged
+const VOTE_DEADLINE_WARNING_DAYS: i64 = 2;
+/// Bucket for proposals without tags in spend breakdowns
+const UNTAGGED: &str = "untagged";
+/// Seeded draws used to estimate a team's raffle odds
+const RAFFLE_ODDS_SIMULATIONS: u32 = 1000;
+/// Largest gap between a stored team reward and its share of the pool put down to rounding
+const REWARD_ROUNDING_TOLERANCE: f64 = 1e-6;
+/// Column headers for the HTML end of epoch report tables
+const PROPOSAL_TABLE_COLUMNS: [&str; 9] = ["Name", "URL", "Team", "Amounts", "Start Date", "End Date", "Announced", "Resolved", "Report"];
+/// Columns for policy and informational proposals, which have no budget fields
+const NON_BUDGET_TABLE_COLUMNS: [&str; 6] = ["Name", "URL", "Kind", "Announced", "Resolved", "Report"];
+const TEAM_SUMMARY_COLUMNS: [&str; 7] = ["Team Name", "Status", "Counted Votes", "Uncounted Votes", "Total Points", "% of Total Points", "Reward Amount"];
+/// Inline stylesheet so the HTML report renders on its own, without external assets
+const HTML_REPORT_STYLE: &str = "\
+body { font-family: sans-serif; margin: 2em; color: #222; }
+table { border-collapse: collapse; margin-bottom: 1.5em; }
+th, td { border: 1px solid #ccc; padding: 4px 8px; text-align: left; }
+th { background: #f0f0f0; }
+";
+
 impl BudgetSystem {
     pub async fn new(
         config: AppConfig, 
         ethereum_service: Arc<dyn EthereumServiceTrait>,
         state: Option<BudgetSystemState>
+    ) -> Result<Self, Box<dyn Error>> {
+        let store = open_store(&config)?;
+        Self::with_store(config, ethereum_service, state, store)
+    }
+
+    /// Loads the state from `store`, which is then used for saving
+    pub async fn from_store(
+        config: AppConfig,
+        ethereum_service: Arc<dyn EthereumServiceTrait>,
+        store: Box<dyn StateStore>
+    ) -> Result<Self, Box<dyn Error>> {
+        let state = store.load()?;
+        Self::with_store(config, ethereum_service, state, store)
+    }
+
+    fn with_store(
+        config: AppConfig,
+        ethereum_service: Arc<dyn EthereumServiceTrait>,
+        state: Option<BudgetSystemState>,
+        store: Box<dyn StateStore>
     ) -> Result<Self, Box<dyn Error>> {
         let state = state.unwrap_or_else(BudgetSystemState::new);
         Ok(Self {
             state,
             ethereum_service,
             config,
+            store,
+            undo_history: VecDeque::new(),
+            event_sender: None,
+            team_points: Mutex::new(HashMap::new()),
+            pending_audit: Mutex::new(None),
+            idempotent: false,
         })
     }
 
@@ -80,8 +152,36 @@ impl BudgetSystem {
         &self.config
     }
 
-    pub fn set_config(&mut self, config: AppConfig) {
+    pub fn is_idempotent(&self) -> bool {
+        self.idempotent
+    }
+
+    pub fn set_idempotent(&mut self, idempotent: bool) {
+        self.idempotent = idempotent;
+    }
+
+    pub fn set_event_sender(&mut self, sender: Option<mpsc::Sender<SystemEvent>>) {
+        self.event_sender = sender;
+    }
+
+    fn emit_event(&self, event: SystemEvent) {
+        if let Some(sender) = &self.event_sender {
+            if let Err(e) = sender.try_send(event) {
+                warn!("Failed to send system event: {}", e);
+            }
+        }
+    }
+
+    /// Replaces the config, reopening the store if the storage settings changed
+    pub fn set_config(&mut self, config: AppConfig) -> Result<(), Box<dyn Error>> {
+        if config.state_file != self.config.state_file
+            || config.storage_backend != self.config.storage_backend
+            || config.strict_state_checksum != self.config.strict_state_checksum
+        {
+            self.store = open_store(&config)?;
+        }
         self.config = config;
+        Ok(())
     }
 
     pub fn get_team(&self, id: &Uuid) -> Option<&Team> {
@@ -104,21 +204,249 @@ impl BudgetSystem {
         self.state.votes().get(id)
     }
 
-    pub fn create_team(&mut self, name: String, representative: String, trailing_monthly_revenue: Option<Vec<u64>>, address: Option<String>) -> Result<Uuid, Box<dyn Error>> {
-        let team = Team::new(name, representative, trailing_monthly_revenue, address)?;
+    pub fn create_team(&mut self, name: String, representative: String, trailing_monthly_revenue: Option<Vec<f64>>, address: Option<String>) -> Result<Uuid, Box<dyn Error>> {
+        self.create_team_with_contacts(name, representative, trailing_monthly_revenue, address, Vec::new())
+    }
+
+    /// Like `create_team`, with contacts for the representative. An empty representative
+    /// takes the primary contact's value.
+    pub fn create_team_with_contacts(
+        &mut self,
+        name: String,
+        representative: String,
+        trailing_monthly_revenue: Option<Vec<f64>>,
+        address: Option<String>,
+        contacts: Vec<Contact>,
+    ) -> Result<Uuid, Box<dyn Error>> {
+        let mut builder = Team::builder(name).representative(representative).contacts(contacts);
+        if let Some(revenue) = trailing_monthly_revenue {
+            builder = builder.trailing_monthly_revenue(revenue);
+        }
+        if let Some(address) = address {
+            builder = builder.payment_address(address);
+        }
+        let team = builder.build()?;
+
+        if self.config.unique_representatives {
+            if let Some(other) = self.representative_conflict(team.representative(), None) {
+                return Err(format!("Representative {} is already used by team {}", team.representative(), other).into());
+            }
+        }
         let id = self.state.add_team(team);
         self.save_state()?;
         Ok(id)
     }
 
+    /// Creates the team, or updates the team with the same name: its representative and payment address
+    /// are overwritten, and its status becomes Earner with the given revenue or Supporter without one.
+    /// Contacts are replaced when any are given. Returns the team ID and whether the team was created.
+    pub fn add_or_update_team(
+        &mut self,
+        name: String,
+        representative: String,
+        trailing_monthly_revenue: Option<Vec<f64>>,
+        address: Option<String>,
+        contacts: Vec<Contact>,
+    ) -> Result<(Uuid, bool), Box<dyn Error>> {
+        let Some(team_id) = self.get_team_id_by_name(&name) else {
+            return Ok((self.create_team_with_contacts(name, representative, trailing_monthly_revenue, address, contacts)?, true));
+        };
+        let representative = if representative.trim().is_empty() {
+            contacts.first().map(|contact| contact.value.clone())
+        } else {
+            Some(representative)
+        };
+
+        let status = if trailing_monthly_revenue.is_some() { "earner" } else { "supporter" };
+        self.update_team(team_id, UpdateTeamDetails {
+            name: None,
+            representative,
+            status: Some(status.to_string()),
+            trailing_monthly_revenue,
+            address,
+            contacts: (!contacts.is_empty()).then_some(contacts),
+        })?;
+        Ok((team_id, false))
+    }
+
+    /// Deletes a team outright. Teams that took part in raffles, votes, rewards or proposals
+    /// must be archived instead, so historical reports can still name them.
     pub fn remove_team(&mut self, team_id: Uuid) -> Result<(), Box<dyn Error>> {
+        if self.state.get_team(&team_id).is_none() {
+            return Err("Team not found".into());
+        }
+        if self.has_participation(team_id) {
+            return Err("Team has historical participation; archive it instead".into());
+        }
         self.state.remove_team(team_id).ok_or("Team not found")?;
         self.save_state()?;
         Ok(())
     }
 
+    /// Takes a team off the roster so it is left out of new raffles and votes,
+    /// while reports on past raffles, votes and rewards keep its name.
+    pub fn archive_team(&mut self, team_id: Uuid) -> Result<(), Box<dyn Error>> {
+        self.state.archive_team(team_id)?;
+        self.save_state()?;
+        Ok(())
+    }
+
+    /// Folds the source team into the target: the source's places and weights in votes, raffle results,
+    /// proposal sponsorships, budget requests and epoch rewards move to the target, its revenue is added
+    /// to the target's, and the source is archived. Raffle tickets and snapshots are left as they were drawn.
+    pub fn merge_teams(&mut self, source_name: &str, target_name: &str) -> Result<(), Box<dyn Error>> {
+        let source_id = self.require_team_id(source_name)?;
+        let target_id = self.require_team_id(target_name)?;
+        if source_id == target_id {
+            return Err("Cannot merge a team into itself".into());
+        }
+
+        // Checked up front so a refused merge changes nothing
+        if let Some(vote) = self.state.votes().values()
+            .find(|vote| vote.has_participant(source_id) && vote.has_participant(target_id)) {
+            return Err(format!(
+                "Teams {} and {} both took part in vote {}; merging them would count it twice",
+                source_name, target_name, vote.id()
+            ).into());
+        }
+
+        let merged_status = match (self.state.get_team(&source_id).map(|team| team.status()), self.state.get_team(&target_id).map(|team| team.status())) {
+            (Some(TeamStatus::Earner { trailing_monthly_revenue: source }), Some(TeamStatus::Earner { trailing_monthly_revenue: target })) => {
+                Some(TeamStatus::Earner { trailing_monthly_revenue: sum_trailing_revenue(source, target) })
+            },
+            (Some(TeamStatus::Earner { trailing_monthly_revenue }), Some(TeamStatus::Supporter)) => {
+                Some(TeamStatus::Earner { trailing_monthly_revenue: trailing_monthly_revenue.clone() })
+            },
+            _ => None,
+        };
+        if let Some(status) = merged_status {
+            self.state.get_team_mut(&target_id).ok_or("Team not found")?.set_status(status)?;
+        }
+
+        // Every vote, since weights are snapshotted for teams that never voted
+        let vote_ids: Vec<Uuid> = self.state.votes().keys().copied().collect();
+        for vote_id in vote_ids {
+            self.state.get_vote_mut(&vote_id).ok_or("Vote not found")?.reassign_participant(source_id, target_id)?;
+        }
+
+        let raffle_ids: Vec<Uuid> = self.state.raffles().values()
+            .filter(|raffle| raffle.result().is_some_and(|result| result.contains(source_id)))
+            .map(|raffle| raffle.id())
+            .collect();
+        for raffle_id in raffle_ids {
+            let raffle = self.state.get_raffle_mut(&raffle_id).ok_or("Raffle not found")?;
+            if let Some(mut result) = raffle.result().cloned() {
+                result.reassign_team(source_id, target_id);
+                raffle.set_result(result);
+            }
+        }
+
+        let proposal_ids: Vec<Uuid> = self.state.proposals().keys().copied().collect();
+        for proposal_id in proposal_ids {
+            self.state.get_proposal_mut(&proposal_id).ok_or("Proposal not found")?.reassign_team(source_id, target_id);
+        }
+        let epoch_ids: Vec<Uuid> = self.state.epochs().keys().copied().collect();
+        for epoch_id in epoch_ids {
+            self.state.get_epoch_mut(&epoch_id).ok_or("Epoch not found")?.reassign_team(source_id, target_id);
+        }
+
+        self.state.archive_team(source_id)?;
+        self.invalidate_team_points();
+        self.save_state()?;
+        Ok(())
+    }
+
+    /// Whether any raffle, vote, epoch reward or proposal refers to the team
+    fn has_participation(&self, team_id: Uuid) -> bool {
+        self.state.raffles().values()
+            .any(|raffle| raffle.team_snapshots().iter().any(|snapshot| snapshot.id() == team_id))
+        || self.state.votes().values().any(|vote| match vote.participation() {
+            VoteParticipation::Formal { counted, uncounted } => counted.contains(&team_id) || uncounted.contains(&team_id),
+            VoteParticipation::Informal(teams) => teams.contains(&team_id),
+        })
+        || self.state.epochs().values().any(|epoch| epoch.team_rewards().contains_key(&team_id))
+        || self.state.proposals().values().any(|proposal| {
+            proposal.sponsors().contains(&team_id)
+                || proposal.budget_request_details().and_then(|details| details.team()) == Some(team_id)
+        })
+    }
+
+    /// Renames a team in place, keeping its ID so votes and points history stay attached.
+    /// Raffles store team snapshots, so historical raffle reports keep the old name.
+    pub fn rename_team(&mut self, team_id: Uuid, new_name: String) -> Result<(), &'static str> {
+        if new_name.trim().is_empty() {
+            return Err("Team name cannot be empty");
+        }
+        if self.get_team_id_by_name(&new_name).is_some_and(|id| id != team_id) {
+            return Err("A team with that name already exists");
+        }
+
+        let team = self.state.get_team_mut(&team_id).ok_or("Team not found")?;
+        team.set_name(new_name);
+        self.save_state().map_err(|_| "Failed to save state")?;
+        Ok(())
+    }
+
+    /// Replaces a team's representatives; the lead also becomes its legacy representative
+    pub fn set_team_representatives(&mut self, team_id: Uuid, representatives: Vec<Representative>) -> Result<(), &'static str> {
+        let team = self.state.get_team_mut(&team_id).ok_or("Team not found")?;
+        team.set_representatives(representatives)?;
+        self.save_state().map_err(|_| "Failed to save state")?;
+        Ok(())
+    }
+
+    /// Removes a team's personal data on request. The team record and its ID stay, so votes,
+    /// points and rewards are unchanged; reports mask the name stored in older raffle snapshots.
+    pub fn redact_team(&mut self, team_id: Uuid) -> Result<(), &'static str> {
+        let team = self.state.get_team(&team_id).ok_or("Team not found")?;
+        if team.is_redacted() {
+            return Err("Team is already redacted");
+        }
+        // Log the earner-to-supporter change before redaction drops the revenue
+        if let TeamStatus::Earner { .. } = team.status() {
+            self.state.update_team_status(team_id, TeamStatus::Supporter)?;
+        }
+        self.state.get_team_mut(&team_id).ok_or("Team not found")?.redact();
+        self.state.clear_team_revenue(team_id);
+        self.save_state().map_err(|_| "Failed to save state")?;
+        Ok(())
+    }
+
+    /// Status the team had at the given time, replayed from the team change log
+    pub fn team_status_at(&self, team_id: Uuid, at: DateTime<Utc>) -> Option<TeamStatus> {
+        self.state.team_status_at(team_id, at)
+    }
+
+    /// Name to show for a raffle snapshot, masked if the team has since been redacted
+    fn snapshot_display_name(&self, snapshot: &TeamSnapshot) -> String {
+        match self.state.find_team(&snapshot.id()) {
+            Some(team) if team.is_redacted() => team.name().to_string(),
+            _ => snapshot.name().to_string(),
+        }
+    }
+
     pub fn update_team(&mut self, team_id: Uuid, updates: UpdateTeamDetails) -> Result<(), Box<dyn Error>> {
+        if self.config.unique_representatives {
+            let current = self.state.get_team(&team_id).ok_or("Team not found")?;
+            let representative = updates.representative.clone()
+                .unwrap_or_else(|| current.representative().to_string());
+            let stays_active = match updates.status.as_deref() {
+                Some(status) => !status.eq_ignore_ascii_case("inactive"),
+                None => current.is_active(),
+            };
+            if stays_active {
+                if let Some(other) = self.representative_conflict(&representative, Some(team_id)) {
+                    return Err(format!("Representative {} is already used by team {}", representative, other).into());
+                }
+            }
+        }
+
         let team = self.state.get_team_mut(&team_id).ok_or("Team not found")?;
+
+        // First, so invalid contacts leave the team untouched
+        if let Some(contacts) = updates.contacts {
+            team.set_contacts(contacts)?;
+        }
         
         if let Some(name) = updates.name {
             team.set_name(name);
@@ -128,8 +456,8 @@ impl BudgetSystem {
             team.set_representative(representative);
         }
         
-        if let Some(status) = updates.status {
-            let new_status = match status.to_lowercase().as_str() {
+        let new_status = if let Some(status) = updates.status {
+            Some(match status.to_lowercase().as_str() {
                 "earner" => {
                     let revenue = updates.trailing_monthly_revenue
                         .ok_or("Trailing monthly revenue is required for Earner status")?;
@@ -138,24 +466,63 @@ impl BudgetSystem {
                 "supporter" => TeamStatus::Supporter,
                 "inactive" => TeamStatus::Inactive,
                 _ => return Err(format!("Invalid status: {}", status).into()),
-            };
-            team.set_status(new_status)?;
+            })
         } else if let Some(revenue) = updates.trailing_monthly_revenue {
             if let TeamStatus::Earner { .. } = team.status() {
-                team.set_status(TeamStatus::Earner { trailing_monthly_revenue: revenue })?;
+                Some(TeamStatus::Earner { trailing_monthly_revenue: revenue })
             } else {
                 return Err("Cannot update trailing monthly revenue for non-Earner status".into());
             }
-        }
+        } else {
+            None
+        };
 
         if let Some(address) = updates.address {
             team.set_payment_address(Some(address));
         }
+
+        if let Some(status) = new_status {
+            self.state.update_team_status(team_id, status)?;
+        }
         
         self.save_state()?;
         Ok(())
     }
 
+    /// Name of another active team with the same representative, ignoring case and surrounding whitespace
+    fn representative_conflict(&self, representative: &str, exclude: Option<Uuid>) -> Option<String> {
+        let representative = representative.trim().to_lowercase();
+        self.state.current_state().teams().values()
+            .filter(|team| team.is_active() && Some(team.id()) != exclude)
+            .find(|team| team.representative().trim().to_lowercase() == representative)
+            .map(|team| team.name().to_string())
+    }
+
+    /// Representatives used by more than one active team, with the sorted team names
+    pub fn shared_representatives(&self) -> Vec<(String, Vec<String>)> {
+        let mut by_representative: HashMap<String, (String, Vec<String>)> = HashMap::new();
+        for team in self.state.current_state().teams().values().filter(|team| team.is_active()) {
+            let representative = team.representative().trim();
+            let entry = by_representative.entry(representative.to_lowercase())
+                .or_insert_with(|| (representative.to_string(), Vec::new()));
+            // Keep the spelling stable regardless of map order
+            if representative < entry.0.as_str() {
+                entry.0 = representative.to_string();
+            }
+            entry.1.push(team.name().to_string());
+        }
+
+        let mut shared: Vec<(String, Vec<String>)> = by_representative.into_values()
+            .filter(|(_, teams)| teams.len() > 1)
+            .map(|(representative, mut teams)| {
+                teams.sort();
+                (representative, teams)
+            })
+            .collect();
+        shared.sort();
+        shared
+    }
+
     pub fn ethereum_service(&self) -> &Arc<dyn EthereumServiceTrait> {
         &self.ethereum_service
     }
@@ -172,8 +539,63 @@ impl BudgetSystem {
         self.ethereum_service.get_raffle_randomness().await
     }
 
+    pub async fn get_block_timestamp(&self, block_number: u64) -> Result<DateTime<Utc>, Box<dyn Error>> {
+        self.ethereum_service.get_block_timestamp(block_number).await
+    }
+
     pub fn save_state(&self) -> Result<(), Box<dyn std::error::Error>> {
-        FileSystem::save_state(&self.state, &self.config.state_file)
+        // Every vote mutation is saved, so this is where cached points go stale
+        self.invalidate_team_points();
+        if let Some((operation, arguments)) = self.pending_audit.lock().unwrap().take() {
+            audit::append(&self.config.audit_log, &operation, arguments)?;
+        }
+        self.store.save_incremental(&self.state)
+    }
+
+    /// Queues an audit entry for a state-changing command, to be written just before its first save
+    fn begin_audit(&self, command: &Command) {
+        if self.config.audit_log.is_empty() || !(command.is_mutating() || matches!(command, Command::Undo)) {
+            return;
+        }
+        let Ok(value) = serde_json::to_value(command) else { return };
+        let operation = value["type"].as_str().unwrap_or_default().to_string();
+        let arguments = value.get("params").cloned().unwrap_or(serde_json::Value::Null);
+        *self.pending_audit.lock().unwrap() = Some((operation, arguments));
+    }
+
+    /// Checks the hash chain of the configured audit log and returns how many entries it holds
+    pub fn verify_audit_chain(&self) -> Result<usize, Box<dyn Error>> {
+        if self.config.audit_log.is_empty() {
+            return Err("No audit log configured".into());
+        }
+        audit::verify_chain(&self.config.audit_log)
+    }
+
+    fn invalidate_team_points(&self) {
+        self.team_points.lock().unwrap().clear();
+    }
+
+    fn push_undo_state(&mut self, state: BudgetSystemState) {
+        if self.config.undo_history_size == 0 {
+            return;
+        }
+        while self.undo_history.len() >= self.config.undo_history_size {
+            self.undo_history.pop_front();
+        }
+        self.undo_history.push_back(state);
+    }
+
+    pub fn undo_depth(&self) -> usize {
+        self.undo_history.len()
+    }
+
+    /// Restores the state from before the last mutating command and saves it
+    pub fn undo(&mut self) -> Result<(), &'static str> {
+        let previous = self.undo_history.pop_back().ok_or("Nothing to undo")?;
+        self.state = previous;
+        self.invalidate_team_points();
+        self.save_state().map_err(|_| "Failed to save state")?;
+        Ok(())
     }
 
     pub fn add_proposal(
@@ -184,11 +606,25 @@ impl BudgetSystem {
         announced_at: Option<NaiveDate>,
         published_at: Option<NaiveDate>,
         is_historical: Option<bool>
+    ) -> Result<Uuid, &'static str> {
+        self.add_proposal_with_draft(title, url, budget_request_details, announced_at, published_at, is_historical, false)
+    }
+
+    /// Like `add_proposal`; with `is_draft` the proposal is staged until `publish_proposal` opens it
+    pub fn add_proposal_with_draft(
+        &mut self,
+        title: String,
+        url: Option<String>,
+        budget_request_details: Option<BudgetRequestDetails>,
+        announced_at: Option<NaiveDate>,
+        published_at: Option<NaiveDate>,
+        is_historical: Option<bool>,
+        is_draft: bool,
     ) -> Result<Uuid, &'static str> {
         let current_epoch_id = self.state.current_epoch()
             .ok_or("No active epoch")?;
 
-        let proposal = Proposal::new(
+        let mut proposal = Proposal::new(
             current_epoch_id,
             title,
             url,
@@ -197,6 +633,9 @@ impl BudgetSystem {
             published_at,
             is_historical
         );
+        if is_draft {
+            proposal.set_status(ProposalStatus::Draft);
+        }
 
         let proposal_id = self.state.add_proposal(&proposal);
         
@@ -210,7 +649,19 @@ impl BudgetSystem {
         Ok(proposal_id)
     }
 
+    /// Opens a draft proposal, announcing it today unless it already has an announcement date
+    pub fn publish_proposal(&mut self, proposal_name: &str) -> Result<(), Box<dyn Error>> {
+        let proposal_id = self.require_proposal_id(proposal_name)?;
+        let proposal = self.state.get_proposal_mut(&proposal_id).ok_or("Proposal not found")?;
+        proposal.publish(Utc::now().date_naive())?;
+        self.save_state()?;
+        Ok(())
+    }
+
     pub fn close_with_reason(&mut self, id: Uuid, resolution: &Resolution) -> Result<(), &'static str> {
+        if *resolution == Resolution::Approved {
+            self.check_budget_cap(id)?;
+        }
         if let Some(proposal) = self.state.get_proposal_mut(&id) {
             if proposal.is_closed() {
                 return Err("Proposal is already closed");
@@ -229,3297 +680,11061 @@ impl BudgetSystem {
         }
     }
 
-    pub fn generate_and_save_proposal_report(&self, proposal_id: Uuid, epoch_name: &str) -> Result<PathBuf, Box<dyn Error>> {
-        let proposal = self.get_proposal(&proposal_id)
-            .ok_or_else(|| format!("Proposal not found: {:?}", proposal_id))?;
+    /// Approved request amounts per token across the epoch's proposals
+    pub fn committed_amounts(&self, epoch_id: Uuid) -> HashMap<String, f64> {
+        let mut committed = HashMap::new();
+        for proposal in self.get_proposals_for_epoch(epoch_id) {
+            if !proposal.is_approved() {
+                continue;
+            }
+            if let Some(details) = proposal.budget_request_details() {
+                for (token, amount) in details.request_amounts() {
+                    *committed.entry(token.clone()).or_insert(0.0) += amount;
+                }
+            }
+        }
+        committed
+    }
 
-        let report_content = self.generate_proposal_report(proposal_id)?;
-        
-        FileSystem::generate_and_save_proposal_report(
-            proposal,
-            &report_content,
-            epoch_name,
-            Path::new(&self.config.state_file)
-        )
+    /// Fails if approving the proposal would push any token over its epoch's budget cap
+    fn check_budget_cap(&self, proposal_id: Uuid) -> Result<(), &'static str> {
+        let proposal = self.state.get_proposal(&proposal_id).ok_or("Proposal not found")?;
+        let (Some(details), Some(epoch)) = (proposal.budget_request_details(), self.state.get_epoch(&proposal.epoch_id())) else {
+            return Ok(());
+        };
+
+        let committed = self.committed_amounts(epoch.id());
+        for (token, amount) in details.request_amounts() {
+            if let Some(cap) = epoch.budget_cap().get(token) {
+                if committed.get(token).unwrap_or(&0.0) + amount > *cap {
+                    return Err("Approving this proposal would exceed the epoch budget cap");
+                }
+            }
+        }
+        Ok(())
     }
 
-    pub fn create_formal_vote(&mut self, proposal_id: Uuid, raffle_id: Uuid, threshold: Option<f64>) -> Result<Uuid, &'static str> {
-        let proposal = self.state.get_proposal_mut(&proposal_id)
-            .ok_or("Proposal not found")?;
+    pub fn set_epoch_budget_cap(&mut self, epoch_name: Option<&str>, token: &str, amount: f64) -> Result<(), Box<dyn Error>> {
+        let epoch_id = match epoch_name {
+            Some(name) => self.require_epoch_id(name)?,
+            None => self.state.current_epoch().ok_or("No active epoch")?,
+        };
+        let epoch = self.state.get_epoch_mut(&epoch_id).ok_or("Epoch not found")?;
+        epoch.set_budget_cap(token.to_string(), amount)?;
+        self.save_state()?;
+        Ok(())
+    }
 
-        if !proposal.is_actionable() {
-            return Err("Proposal is not in a votable state");
+    /// Reopens a closed proposal so it can be voted on or closed again. Votes already held
+    /// for it are kept as records, but no longer decide its resolution.
+    pub fn reopen_proposal(&mut self, proposal_name: &str) -> Result<(), Box<dyn Error>> {
+        let proposal_id = self.require_proposal_id(proposal_name)?;
+        let proposal = self.state.get_proposal_mut(&proposal_id).ok_or("Proposal not found")?;
+
+        if proposal.is_historical() {
+            return Err("Cannot reopen a historical proposal".into());
+        }
+        if proposal.budget_request_details().is_some_and(|details| details.is_paid()) {
+            return Err("Cannot reopen: Proposal is already paid".into());
         }
 
-        let epoch_id = proposal.epoch_id();
+        proposal.reopen()?;
+        self.save_state()?;
+        Ok(())
+    }
 
-        let raffle = self.state.get_raffle(&raffle_id)
-            .ok_or("Raffle not found")?;
+    /// Deletes a proposal added by mistake. Refused once a raffle or vote refers to it,
+    /// or once it is closed or has payments recorded.
+    pub fn remove_proposal(&mut self, proposal_name: &str) -> Result<(), Box<dyn Error>> {
+        let proposal_id = self.require_proposal_id(proposal_name)?;
+        let proposal = self.state.get_proposal(&proposal_id).ok_or("Proposal not found")?;
 
-        if raffle.result().is_none() {
-            return Err("Raffle results have not been generated");
+        if proposal.is_closed() {
+            return Err("Cannot remove a closed proposal".into());
+        }
+        if proposal.budget_request_details().is_some_and(|details| details.is_paid() || details.is_partially_paid()) {
+            return Err("Cannot remove a proposal with recorded payments".into());
+        }
+        if self.state.raffles().values().any(|raffle| raffle.config().proposal_id() == proposal_id) {
+            return Err("Cannot remove a proposal referenced by a raffle".into());
+        }
+        if self.state.votes().values().any(|vote| vote.proposal_id() == proposal_id) {
+            return Err("Cannot remove a proposal referenced by a vote".into());
+        }
+        if self.state.proposals().values().any(|other| other.depends_on().contains(&proposal_id)) {
+            return Err("Cannot remove a proposal that other proposals depend on".into());
         }
 
-        let config = raffle.config();
+        let epoch_id = proposal.epoch_id();
+        if let Some(epoch) = self.state.get_epoch_mut(&epoch_id) {
+            epoch.remove_proposal(proposal_id);
+        }
+        self.state.remove_proposal(proposal_id);
+        self.save_state()?;
+        Ok(())
+    }
 
-        let vote_type = VoteType::Formal { 
-            raffle_id,
-            total_eligible_seats: config.total_counted_seats() as u32,
-            threshold: self.config.default_qualified_majority_threshold,
-            counted_points: self.config.counted_vote_points,
-            uncounted_points: self.config.uncounted_vote_points
-        };
+    /// Moves a proposal filed under the wrong epoch, unless it was already voted on with a raffle of its current epoch
+    pub fn move_proposal(&mut self, proposal_name: &str, target_epoch_name: &str) -> Result<(), Box<dyn Error>> {
+        let proposal_id = self.require_proposal_id(proposal_name)?;
+        let target_epoch_id = self.require_epoch_id(target_epoch_name)?;
+        let source_epoch_id = self.state.get_proposal(&proposal_id).ok_or("Proposal not found")?.epoch_id();
 
-        let vote = Vote::new(proposal_id, epoch_id, vote_type, false);
+        if source_epoch_id == target_epoch_id {
+            return Err("Proposal is already in the target epoch".into());
+        }
+        if self.state.get_epoch(&target_epoch_id).ok_or("Target epoch not found")?.is_closed() {
+            return Err("Cannot move a proposal into a closed epoch".into());
+        }
 
+        let voted_with_source_raffle = self.state.votes().values()
+            .filter(|vote| vote.proposal_id() == proposal_id)
+            .filter_map(|vote| vote.raffle_id().and_then(|raffle_id| self.state.get_raffle(&raffle_id)))
+            .any(|raffle| raffle.config().epoch_id() == source_epoch_id);
+        if voted_with_source_raffle {
+            return Err("Cannot move a proposal with a vote tied to a raffle of its current epoch".into());
+        }
 
-        let vote_id = self.state.add_vote(&vote);
-        self.save_state();
-        Ok(vote_id)
+        if let Some(epoch) = self.state.get_epoch_mut(&source_epoch_id) {
+            epoch.remove_proposal(proposal_id);
+        }
+        self.state.get_epoch_mut(&target_epoch_id).ok_or("Target epoch not found")?.add_proposal(proposal_id);
+        self.state.get_proposal_mut(&proposal_id).ok_or("Proposal not found")?.set_epoch_id(target_epoch_id);
+        self.save_state()?;
+        Ok(())
     }
 
-    pub fn create_informal_vote(&mut self, proposal_id: Uuid) -> Result<Uuid, &'static str> {
+    /// Marks an approved budget request as paid with the given transaction hash, dated today
+    pub fn record_payment(&mut self, proposal_name: &str, tx_hash: String) -> Result<(), Box<dyn Error>> {
+        let proposal_id = self.require_proposal_id(proposal_name)?;
         let proposal = self.state.get_proposal_mut(&proposal_id)
             .ok_or("Proposal not found")?;
 
-        if !proposal.is_actionable() {
-            return Err("Proposal is not in a votable state");
+        if !proposal.is_approved() {
+            return Err(format!("Proposal is not approved: {}", proposal_name).into());
         }
 
-        let epoch_id = proposal.epoch_id();
+        let mut details = proposal.budget_request_details().cloned()
+            .ok_or_else(|| format!("Proposal has no budget request: {}", proposal_name))?;
+        if details.is_paid() {
+            return Err(format!("Proposal is already paid: {}", proposal_name).into());
+        }
 
-        let vote = Vote::new(proposal_id, epoch_id, VoteType::Informal, false);
+        details.record_payment(tx_hash, Utc::now().date_naive())?;
+        proposal.set_budget_request_details(Some(details));
+        self.save_state()?;
+        Ok(())
+    }
 
-        let vote_id = self.state.add_vote(&vote);
-        self.save_state();
-        Ok(vote_id)
+    /// Looks up the recorded payment transaction of a proposal and asks the chain how far it has confirmed
+    pub async fn check_payment(&self, proposal_name: &str) -> Result<(String, TxStatus), Box<dyn Error>> {
+        let proposal_id = self.require_proposal_id(proposal_name)?;
+        let tx_hash = self.state.get_proposal(&proposal_id)
+            .and_then(|proposal| proposal.budget_request_details())
+            .and_then(|details| details.payment_tx())
+            .map(|tx| format!("{:?}", tx))
+            .ok_or_else(|| format!("No payment transaction recorded for proposal: {}", proposal_name))?;
+
+        let status = self.ethereum_service.get_transaction_status(&tx_hash).await?;
+        Ok((tx_hash, status))
     }
 
-    pub fn cast_votes(&mut self, vote_id: Uuid, votes: Vec<(Uuid, VoteChoice)>) -> Result<(), &'static str> {
-        let raffle_result = {
-            let vote = self.state.get_vote(&vote_id).ok_or("Vote not found")?;
-            match vote.vote_type() {
-                VoteType::Formal { raffle_id, .. } => {
-                    self.state.get_raffle(&raffle_id)
-                        .and_then(|raffle| raffle.result().cloned())
-                },
-                VoteType::Informal => None,
-            }
-        };
-    
-        {
-            let vote = self.state.get_vote_mut(&vote_id).ok_or("Vote not found")?;
-            for (team_id, choice) in votes {
-                vote.cast_vote(team_id, choice, raffle_result.as_ref())?;
-            }
+    /// Sends a team's outstanding reward for a closed epoch to its payment address and records
+    /// the transaction on the epoch. Returns the transaction hash and the amount sent.
+    pub async fn pay_team_reward(&mut self, epoch_name: &str, team_name: &str) -> Result<(String, f64), Box<dyn Error>> {
+        let epoch_id = self.require_epoch_id(epoch_name)?;
+        let team_id = self.require_team_id(team_name)?;
+
+        let epoch = self.state.get_epoch(&epoch_id).ok_or("Epoch not found")?;
+        if !epoch.is_closed() {
+            return Err("Rewards can only be paid for closed epochs".into());
+        }
+        let token = epoch.reward()
+            .ok_or_else(|| format!("Epoch {} has no reward", epoch_name))?
+            .token()
+            .to_string();
+        let amount = epoch.team_rewards().get(&team_id)
+            .ok_or_else(|| format!("Team {} has no reward in epoch {}", team_name, epoch_name))?
+            .outstanding();
+        if amount == 0.0 {
+            return Err(format!("Reward for team {} in epoch {} is already paid", team_name, epoch_name).into());
         }
-    
-        self.save_state();
-        Ok(())
-    }
 
-    pub fn close_vote(&mut self, vote_id: Uuid) -> Result<bool, &'static str> {
-        let vote = self.state.get_vote_mut(&vote_id).ok_or("Vote not found")?;
-        
-        if vote.is_closed() {
-            return Err("Vote is already closed");
+        let to = *self.get_team(&team_id)
+            .and_then(|team| team.payment_address())
+            .ok_or_else(|| format!("Team {} has no payment address", team_name))?;
+
+        let tx_hash = self.ethereum_service.send_payment(to, &token, amount).await?;
+        info!("Paid {} {} to team {} for epoch {}: {}", amount, token, team_name, epoch_name, tx_hash);
+
+        self.state.get_epoch_mut(&epoch_id)
+            .ok_or("Epoch not found")?
+            .record_team_payout(&team_id, amount, tx_hash.clone())?;
+        self.save_state()?;
+        Ok((tx_hash, amount))
+    }
+
+    /// Records one milestone payment of an approved budget request
+    pub fn add_milestone_payment(
+        &mut self,
+        proposal_name: &str,
+        amount: f64,
+        token: &str,
+        date: NaiveDate,
+        tx: Option<String>,
+    ) -> Result<(), Box<dyn Error>> {
+        let proposal_id = self.require_proposal_id(proposal_name)?;
+        let proposal = self.state.get_proposal_mut(&proposal_id)
+            .ok_or("Proposal not found")?;
+
+        if !proposal.is_approved() {
+            return Err(format!("Proposal is not approved: {}", proposal_name).into());
         }
 
-        vote.close()?;
+        let mut details = proposal.budget_request_details().cloned()
+            .ok_or_else(|| format!("Proposal has no budget request: {}", proposal_name))?;
+        if details.is_paid() {
+            return Err(format!("Proposal is already paid: {}", proposal_name).into());
+        }
 
-        let result = match vote.result() {
-            Some(VoteResult::Formal { passed, .. }) => *passed,
-            Some(VoteResult::Informal { .. }) => false,
-            None => return Err("Vote result not available"),
-        };
+        details.add_payment(Payment { amount, token: token.to_string(), date, tx })?;
+        proposal.set_budget_request_details(Some(details));
+        self.save_state()?;
+        Ok(())
+    }
 
-        self.save_state();
-        Ok(result)
+    pub fn generate_and_save_proposal_report(&self, proposal_id: Uuid, epoch_name: &str) -> Result<PathBuf, Box<dyn Error>> {
+        Ok(self.save_proposal_report_if_changed(proposal_id, epoch_name)?.0)
     }
 
-    pub fn create_epoch(&mut self, name: &str, start_date:DateTime<Utc>, end_date: DateTime<Utc>) -> Result<Uuid, &'static str> {
-        let new_epoch = Epoch::new(name.to_string(), start_date, end_date)?;
+    /// Regenerates the reports of the epoch's closed proposals whose content changed. With `since`,
+    /// only proposals resolved on or after it are looked at.
+    pub fn regenerate_closed_proposal_reports(&self, epoch_name: &str, since: Option<NaiveDate>) -> Result<ProposalReportsSummary, Box<dyn Error>> {
+        let epoch_id = self.require_epoch_id(epoch_name)?;
 
-        // Check for overlapping epochs
-        for epoch in self.state.epochs().values() {
-            if (start_date < epoch.end_date() && end_date > epoch.start_date()) ||
-            (epoch.start_date() < end_date && epoch.end_date() > start_date) {
-                return Err("New epoch overlaps with an existing epoch");
+        let mut summary = ProposalReportsSummary::default();
+        for proposal in self.get_proposals_for_epoch(epoch_id).into_iter().filter(|p| p.is_closed()) {
+            if since.is_some_and(|since| proposal.resolved_at().map_or(true, |resolved| resolved < since)) {
+                summary.skipped += 1;
+                continue;
+            }
+            match self.save_proposal_report_if_changed(proposal.id(), epoch_name) {
+                Ok((file_path, true)) => summary.generated.push((proposal.title().to_string(), file_path)),
+                Ok((_, false)) => summary.skipped += 1,
+                Err(e) => summary.failed.push((proposal.title().to_string(), e.to_string())),
             }
         }
-
-        let epoch_id = self.state.add_epoch(&new_epoch);
-        self.save_state();
-        Ok(epoch_id)
+        Ok(summary)
     }
 
-    pub fn activate_epoch(&mut self, epoch_id: Uuid) -> Result<(), &'static str> {
-        if self.state.current_epoch().is_some() {
-            return Err("Another epoch is currently active");
-        }
+    /// Writes the report of a proposal in the active epoch, returning its title and the report path
+    pub fn generate_report_for_current_proposal(&self, proposal_name: &str) -> Result<(String, PathBuf), Box<dyn Error>> {
+        let current_epoch = self.get_current_epoch()
+            .ok_or("No active epoch")?;
 
-        let epoch = self.state.get_epoch_mut(&epoch_id).ok_or("Epoch not found")?;
+        let proposal = self.get_proposals_for_epoch(current_epoch.id())
+            .into_iter()
+            .find(|p| p.name_matches(proposal_name))
+            .ok_or_else(|| format!("Proposal not found in current epoch: {}", proposal_name))?;
 
-        epoch.activate();
-        self.state.set_current_epoch(Some(epoch_id));
-        self.save_state();
-        Ok(())
+        match self.generate_and_save_proposal_report(proposal.id(), current_epoch.name()) {
+            Ok(file_path) => Ok((proposal.title().to_string(), file_path)),
+            Err(e) => Err(format!("Failed to generate report for proposal '{}': {}", proposal.title(), e).into()),
+        }
     }
 
-    pub fn set_epoch_reward(&mut self, token: &str, amount: f64) -> Result<(), &'static str> {
-        let epoch_id = self.state.current_epoch().ok_or("No active epoch")?;
-        let epoch = self.state.get_epoch_mut(&epoch_id).ok_or("Epoch not found")?;
-        
-        epoch.set_reward(token.to_string(), amount);
-        self.save_state();
-        Ok(())
+    /// Like `generate_and_save_proposal_report`; also returns false when the report on disk was
+    /// already up to date and left untouched
+    pub fn save_proposal_report_if_changed(&self, proposal_id: Uuid, epoch_name: &str) -> Result<(PathBuf, bool), Box<dyn Error>> {
+        let proposal = self.get_proposal(&proposal_id)
+            .ok_or_else(|| format!("Proposal not found: {:?}", proposal_id))?;
+
+        let report_content = self.generate_proposal_report(proposal_id)?;
+        let file_path = FileSystem::generate_report_file_path(proposal, epoch_name, Path::new(&self.config.state_file));
+        let written = FileSystem::save_report_to_file(&file_path, &report_content)?;
+        Ok((file_path, written))
     }
 
-    pub fn get_current_epoch(&self) -> Option<&Epoch> {
-        self.state.current_epoch().and_then(|id| self.state.epochs().get(&id))
+    pub fn create_formal_vote(&mut self, proposal_id: Uuid, raffle_id: Uuid, threshold: Option<f64>) -> Result<Uuid, &'static str> {
+        self.create_formal_vote_with_points(proposal_id, raffle_id, threshold, None, None)
     }
 
-    pub fn get_proposals_for_epoch(&self, epoch_id: Uuid) -> Vec<&Proposal> {
-        if let Some(epoch) = self.state.epochs().get(&epoch_id) {
-            epoch.associated_proposals().iter()
-                .filter_map(|&id| self.state.proposals().get(&id))
-                .collect()
-        } else {
-            vec![]
+    /// Like `create_formal_vote`, with participation points that override the configured ones for this vote only
+    pub fn create_formal_vote_with_points(
+        &mut self,
+        proposal_id: Uuid,
+        raffle_id: Uuid,
+        threshold: Option<f64>,
+        counted_points: Option<u32>,
+        uncounted_points: Option<u32>,
+    ) -> Result<Uuid, &'static str> {
+        let proposal = self.state.get_proposal(&proposal_id)
+            .ok_or("Proposal not found")?;
+
+        if !proposal.is_actionable() {
+            return Err("Proposal is not in a votable state");
         }
-    }
+        self.check_sponsors(proposal)?;
+        self.check_dependencies(proposal)?;
 
-    pub fn update_epoch_dates(&mut self, epoch_id: Uuid, new_start: DateTime<Utc>, new_end: DateTime<Utc>) -> Result<(), &'static str> {
-        // Check for overlaps with other epochs
-        for other_epoch in self.state.epochs().values() {
-            if other_epoch.id() != epoch_id &&
-               ((new_start < other_epoch.end_date() && new_end > other_epoch.start_date()) ||
-                (other_epoch.start_date() < new_end && other_epoch.end_date() > new_start)) {
-                return Err("New dates overlap with an existing epoch");
-            }
+        let epoch_id = proposal.epoch_id();
+
+        let raffle = self.state.get_raffle(&raffle_id)
+            .ok_or("Raffle not found")?;
+
+        if raffle.result().is_none() {
+            return Err("Raffle results have not been generated");
         }
-        
-        let epoch = self.state.get_epoch_mut(&epoch_id).ok_or("Epoch not found")?;
 
-        if !epoch.is_planned() {
-            return Err("Can only modify dates of planned epochs");
+        if threshold.is_some_and(|threshold| threshold <= 0.0 || threshold > 1.0) {
+            return Err("Threshold must be greater than 0 and at most 1");
         }
 
-        epoch.set_dates(new_start, new_end);
+        let config = raffle.config();
+
+        let vote_type = VoteType::Formal { 
+            raffle_id,
+            total_eligible_seats: config.total_counted_seats() as u32,
+            threshold: threshold.unwrap_or(self.config.default_qualified_majority_threshold),
+            quorum: self.config.default_quorum,
+            counted_points: counted_points.unwrap_or(self.config.counted_vote_points),
+            uncounted_points: uncounted_points.unwrap_or(self.config.uncounted_vote_points)
+        };
+
+        let vote = Vote::new(proposal_id, epoch_id, vote_type, false);
+
+
+        let vote_id = self.state.add_vote(&vote);
+        self.save_state();
+        Ok(vote_id)
+    }
 
+    fn check_sponsors(&self, proposal: &Proposal) -> Result<(), &'static str> {
+        if proposal.sponsors().len() < self.config.min_sponsors {
+            return Err("Proposal does not have enough sponsors for a formal vote");
+        }
         Ok(())
     }
 
-    pub fn get_team_id_by_name(&self, name: &str) -> Option<Uuid> {
-        get_id_by_name(&self.state.current_state().teams(), name)
+    fn check_dependencies(&self, proposal: &Proposal) -> Result<(), &'static str> {
+        let unmet = proposal.depends_on().iter()
+            .any(|id| !self.state.get_proposal(id).is_some_and(|dependency| dependency.is_approved()));
+        if unmet {
+            return Err("Proposal depends on proposals that are not approved");
+        }
+        Ok(())
     }
 
-    pub fn get_epoch_id_by_name(&self, name: &str) -> Option<Uuid> {
-        get_id_by_name(&self.state.epochs(), name)
+    fn resolve_dependencies(&self, names: &[String]) -> Result<Vec<Uuid>, NotFoundError> {
+        names.iter()
+            .map(|name| self.require_proposal_id(name))
+            .collect()
     }
 
-    pub fn get_proposal_id_by_name(&self, name: &str) -> Option<Uuid> {
-        get_id_by_name(&self.state.proposals(), name)
-    } 
+    /// Replaces the proposals `proposal_id` depends on, refusing dependencies that would form a cycle
+    pub fn set_proposal_dependencies(&mut self, proposal_id: Uuid, depends_on: Vec<Uuid>) -> Result<(), &'static str> {
+        self.check_dependency_cycle(proposal_id, &depends_on)?;
+        self.state.get_proposal_mut(&proposal_id).ok_or("Proposal not found")?.set_depends_on(depends_on)?;
+        self.save_state().map_err(|_| "Failed to save state")?;
+        Ok(())
+    }
 
-    pub fn import_predefined_raffle(
-        &mut self,
-        proposal_name: &str,
-        counted_teams: Vec<String>,
-        uncounted_teams: Vec<String>,
-        total_counted_seats: usize,
-        max_earner_seats: usize
-    ) -> Result<Uuid, Box<dyn Error>> {
-        let proposal_id = self.get_proposal_id_by_name(proposal_name)
-            .ok_or_else(|| format!("Proposal not found: {}", proposal_name))?;
-        
-        let epoch_id = self.state.current_epoch()
-            .ok_or("No active epoch")?;
+    fn check_dependency_cycle(&self, proposal_id: Uuid, depends_on: &[Uuid]) -> Result<(), &'static str> {
+        let mut stack = depends_on.to_vec();
+        let mut visited = HashSet::new();
+        while let Some(id) = stack.pop() {
+            if id == proposal_id {
+                return Err("Proposal dependencies would form a cycle");
+            }
+            if visited.insert(id) {
+                let dependency = self.state.get_proposal(&id).ok_or("Dependency not found")?;
+                stack.extend_from_slice(dependency.depends_on());
+            }
+        }
+        Ok(())
+    }
 
-        let counted_team_ids: Vec<Uuid> = counted_teams.iter()
-            .filter_map(|name| self.get_team_id_by_name(name))
+    /// The epoch's proposals ordered so that each comes after the ones it depends on,
+    /// ties broken by title. Dependencies outside the epoch are ignored.
+    pub fn proposal_dependency_order(&self, epoch_id: Uuid) -> Result<Vec<Uuid>, &'static str> {
+        let proposals: HashMap<Uuid, &Proposal> = self.state.proposals().values()
+            .filter(|proposal| proposal.epoch_id() == epoch_id)
+            .map(|proposal| (proposal.id(), proposal))
             .collect();
-        
-        let uncounted_team_ids: Vec<Uuid> = uncounted_teams.iter()
-            .filter_map(|name| self.get_team_id_by_name(name))
+
+        let mut remaining: HashMap<Uuid, usize> = proposals.values()
+            .map(|proposal| (proposal.id(), proposal.depends_on().iter().filter(|id| proposals.contains_key(id)).count()))
             .collect();
 
-        // Check if total_counted_seats matches the number of counted teams
-        if counted_team_ids.len() != total_counted_seats {
-            return Err(format!(
-                "Mismatch between specified total_counted_seats ({}) and actual number of counted teams ({})",
-                total_counted_seats, counted_team_ids.len()
-            ).into());
+        let mut order = Vec::with_capacity(proposals.len());
+        while !remaining.is_empty() {
+            let next = remaining.iter()
+                .filter(|(_, &count)| count == 0)
+                .map(|(&id, _)| proposals[&id])
+                .min_by(|a, b| a.title().cmp(b.title()).then(a.id().cmp(&b.id())))
+                .ok_or("Proposal dependencies contain a cycle")?
+                .id();
+            remaining.remove(&next);
+            for (id, count) in remaining.iter_mut() {
+                if proposals[id].depends_on().contains(&next) {
+                    *count -= 1;
+                }
+            }
+            order.push(next);
         }
+        Ok(order)
+    }
 
-        // Additional check to ensure max_earner_seats is not greater than total_counted_seats
-        if max_earner_seats > total_counted_seats {
-            return Err(format!(
-                "max_earner_seats ({}) cannot be greater than total_counted_seats ({})",
-                max_earner_seats, total_counted_seats
-            ).into());
+    /// Records an active team's endorsement of a proposal, counted towards `min_sponsors`
+    pub fn add_sponsor(&mut self, proposal_name: &str, team_name: &str) -> Result<(), Box<dyn Error>> {
+        let proposal_id = self.require_proposal_id(proposal_name)?;
+        let team_id = self.require_team_id(team_name)?;
+        if !self.state.get_team(&team_id).ok_or("Team not found")?.is_active() {
+            return Err("Only active teams can sponsor proposals".into());
         }
 
-        let raffle_config = RaffleConfig::new(
-            proposal_id,
-            epoch_id,
-            total_counted_seats,
-            max_earner_seats,
-            Some(0),
-            Some(0),
-            Some("N/A".to_string()),
-            Some(Vec::new()),
-            None,
-            Some(counted_team_ids.iter().chain(uncounted_team_ids.iter()).cloned().collect()),
-            true,
-        );
+        self.state.get_proposal_mut(&proposal_id).ok_or("Proposal not found")?.add_sponsor(team_id)?;
+        self.save_state()?;
+        Ok(())
+    }
 
-        let mut raffle = Raffle::new(raffle_config, self.state.current_state().teams())?;
-        raffle.set_result(RaffleResult::new(counted_team_ids, uncounted_team_ids));
+    /// Formal vote among `options`, cast with `VoteChoice::Option` indices
+    pub fn create_multi_option_vote(&mut self, proposal_id: Uuid, raffle_id: Uuid, options: Vec<String>) -> Result<Uuid, &'static str> {
+        if options.len() < 2 {
+            return Err("A multi-option vote needs at least two options");
+        }
+        if options.iter().any(|option| option.trim().is_empty()) {
+            return Err("Options cannot be empty");
+        }
 
-        let raffle_id = self.state.add_raffle(&raffle);
-        self.save_state()?;
+        let proposal = self.state.get_proposal(&proposal_id)
+            .ok_or("Proposal not found")?;
 
-        Ok(raffle_id)
-    }
+        if !proposal.is_actionable() {
+            return Err("Proposal is not in a votable state");
+        }
+        self.check_sponsors(proposal)?;
+        self.check_dependencies(proposal)?;
 
-    pub fn import_historical_vote(
-        &mut self,
-        proposal_name: &str,
-        passed: bool,
-        participating_teams: Vec<String>,
-        non_participating_teams: Vec<String>,
-        counted_points: Option<u32>,
-        uncounted_points: Option<u32>
-    ) -> Result<Uuid, Box<dyn Error>> {
-        let proposal_id = self.get_proposal_id_by_name(proposal_name)
-            .ok_or_else(|| format!("Proposal not found: {}", proposal_name))?;
-    
-        let raffle_id = self.state.raffles().iter()
-            .find(|(_, raffle)| raffle.config().proposal_id() == proposal_id)
-            .map(|(id, _)| *id)
-            .ok_or_else(|| format!("No raffle found for proposal: {}", proposal_name))?;
+        let epoch_id = proposal.epoch_id();
 
         let raffle = self.state.get_raffle(&raffle_id)
-            .ok_or_else(|| format!("Raffle not found: {}", raffle_id))?;
-    
-        let epoch_id = raffle.config().epoch_id();
-    
-        let vote_type = VoteType::Formal {
+            .ok_or("Raffle not found")?;
+
+        if raffle.result().is_none() {
+            return Err("Raffle results have not been generated");
+        }
+
+        let vote_type = VoteType::MultiOption {
             raffle_id,
+            options,
             total_eligible_seats: raffle.config().total_counted_seats() as u32,
-            threshold: self.config.default_qualified_majority_threshold,
-            counted_points: counted_points.unwrap_or(self.config.counted_vote_points),
-            uncounted_points: uncounted_points.unwrap_or(self.config.uncounted_vote_points)
-        };
-    
-        let mut vote = Vote::new(proposal_id, epoch_id, vote_type, true);
-    
-        // Determine participation
-        let (participating_ids, _) = self.determine_participation(
-            raffle,
-            &participating_teams,
-            &non_participating_teams
-        )?;
-    
-        let raffle_result = raffle.result().ok_or("Raffle result not found")?;
-    
-        // Set participation without casting actual votes
-        for &team_id in &participating_ids {
-            if raffle_result.counted().contains(&team_id) {
-                vote.add_participant(team_id, true)?;
-            } else if raffle_result.uncounted().contains(&team_id) {
-                vote.add_participant(team_id, false)?;
-            }
-        }
-    
-        // Close the vote
-        vote.close()?;
-    
-        // Set the result manually for historical votes
-        let result = VoteResult::Formal {
-            counted: VoteCount::new(),  // All zeros
-            uncounted: VoteCount::new(),  // All zeros
-            passed,
+            counted_points: self.config.counted_vote_points,
+            uncounted_points: self.config.uncounted_vote_points,
         };
-        vote.set_result(Some(result));
-    
-        // Set dates (using current time as a placeholder)
-        let now = Utc::now();
-        vote.set_opened_at(now);
-        vote.set_closed_at(Some(now));
-    
+
+        let vote = Vote::new(proposal_id, epoch_id, vote_type, false);
         let vote_id = self.state.add_vote(&vote);
-    
-        // Update proposal status based on vote result
-        let proposal = self.state.get_proposal_mut(&proposal_id)
-            .ok_or_else(|| format!("Proposal not found: {}", proposal_id))?;
-        
-        if passed {
-            proposal.approve()?;
-        } else {
-            proposal.reject()?;
-        }
-        proposal.set_status(ProposalStatus::Closed);
-    
-        self.save_state()?;
-    
+        self.save_state();
         Ok(vote_id)
     }
 
-    pub fn determine_participation(
-        &self,
-        raffle: &Raffle,
-        participating_teams: &[String],
-        non_participating_teams: &[String]
-    ) -> Result<(Vec<Uuid>, Vec<Uuid>), Box<dyn Error>> {
-        let raffle_result = raffle.result()
-            .ok_or("Raffle result not found")?;
-
-        let all_team_ids: Vec<Uuid> = raffle_result.counted().iter()
-            .chain(raffle_result.uncounted().iter())
-            .cloned()
-            .collect();
+    /// Like `create_formal_vote`, but each counted team votes with its points in `epoch_id`
+    /// as of now, and the vote passes on the share of the counted teams' total weight
+    pub fn create_weighted_vote(&mut self, proposal_id: Uuid, raffle_id: Uuid, epoch_id: Uuid) -> Result<Uuid, &'static str> {
+        let proposal = self.state.get_proposal(&proposal_id)
+            .ok_or("Proposal not found")?;
 
-        if !participating_teams.is_empty() {
-            let participating_ids: Vec<Uuid> = participating_teams.iter()
-                .filter_map(|name| self.get_team_id_by_name(name))
-                .collect();
-            let non_participating_ids: Vec<Uuid> = all_team_ids.into_iter()
-                .filter(|id| !participating_ids.contains(id))
-                .collect();
-            Ok((participating_ids, non_participating_ids))
-        } else if !non_participating_teams.is_empty() {
-            let non_participating_ids: Vec<Uuid> = non_participating_teams.iter()
-                .filter_map(|name| self.get_team_id_by_name(name))
-                .collect();
-            let participating_ids: Vec<Uuid> = all_team_ids.into_iter()
-                .filter(|id| !non_participating_ids.contains(id))
-                .collect();
-            Ok((participating_ids, non_participating_ids))
-        } else {
-            Ok((all_team_ids, Vec::new()))
+        if !proposal.is_actionable() {
+            return Err("Proposal is not in a votable state");
         }
-    }
+        self.check_sponsors(proposal)?;
+        self.check_dependencies(proposal)?;
 
-    pub fn print_team_report(&self) -> String {
-        let mut teams: Vec<&Team> = self.state.current_state().teams().values().collect();
-        teams.sort_by(|a, b| a.name().cmp(&b.name()));
+        let proposal_epoch_id = proposal.epoch_id();
 
-        let mut report = String::from("Team Report:\n\n");
+        let raffle = self.state.get_raffle(&raffle_id)
+            .ok_or("Raffle not found")?;
+        let raffle_result = raffle.result()
+            .ok_or("Raffle results have not been generated")?;
 
-        for team in teams {
-            report.push_str(&format!("Name: {}\n", team.name()));
-            report.push_str(&format!("ID: {}\n", team.id()));
-            report.push_str(&format!("Representative: {}\n", team.representative()));
-            report.push_str(&format!("Status: {:?}\n", team.status()));
+        let mut weights = HashMap::new();
+        for &team_id in raffle_result.counted() {
+            weights.insert(team_id, self.get_team_points_for_epoch(team_id, epoch_id)?);
+        }
 
-            if let TeamStatus::Earner { trailing_monthly_revenue } = &team.status() {
-                report.push_str(&format!("Trailing Monthly Revenue: {:?}\n", trailing_monthly_revenue));
-            }
+        let vote_type = VoteType::Weighted {
+            raffle_id,
+            threshold: self.config.default_qualified_majority_threshold,
+            weights,
+            counted_points: self.config.counted_vote_points,
+            uncounted_points: self.config.uncounted_vote_points,
+        };
 
-            // Add a breakdown of points per epoch
-            report.push_str("Points per Epoch:\n");
-            for epoch in self.state.epochs().values() {
-                let epoch_points = self.get_team_points_for_epoch(team.id(), epoch.id()).unwrap_or(0);
-                report.push_str(&format!("  {}: {} points\n", epoch.name(), epoch_points));
-            }
+        let vote = Vote::new(proposal_id, proposal_epoch_id, vote_type, false);
 
-            report.push_str("\n");
-        }
+        let vote_id = self.state.add_vote(&vote);
+        self.save_state();
+        Ok(vote_id)
+    }
 
-        report
+    pub fn create_informal_vote(&mut self, proposal_id: Uuid) -> Result<Uuid, &'static str> {
+        self.create_informal_vote_with_weighting(proposal_id, InformalWeighting::Unweighted)
     }
 
-    pub fn print_epoch_state(&self) -> Result<String, Box<dyn Error>> {
-        let epoch = self.get_current_epoch().ok_or("No active epoch")?;
-        let proposals = self.get_proposals_for_epoch(epoch.id());
+    /// Informal vote that also reports counts weighted by each earner's average trailing
+    /// monthly revenue. Supporters still show up in the raw count but carry no weight.
+    pub fn create_revenue_weighted_informal_vote(&mut self, proposal_id: Uuid) -> Result<Uuid, &'static str> {
+        let weights = self.state.current_state().teams().values()
+            .filter_map(|team| match team.status() {
+                TeamStatus::Earner { trailing_monthly_revenue } if !trailing_monthly_revenue.is_empty() => {
+                    let average = trailing_monthly_revenue.iter().sum::<f64>() / trailing_monthly_revenue.len() as f64;
+                    Some((team.id(), average.round() as u64))
+                },
+                _ => None,
+            })
+            .collect();
 
-        let mut report = String::new();
+        self.create_informal_vote_with_weighting(proposal_id, InformalWeighting::Revenue { weights })
+    }
 
-        // Epoch overview
-        report.push_str(&format!("*State of Epoch {}*\n\n", escape_markdown(&epoch.name())));
-        report.push_str("🌍 *Overview*\n");
-        report.push_str(&format!("ID: `{}`\n", epoch.id()));
-        report.push_str(&format!("Start Date: `{}`\n", epoch.start_date().format("%Y-%m-%d %H:%M:%S UTC")));
-        report.push_str(&format!("End Date: `{}`\n", epoch.end_date().format("%Y-%m-%d %H:%M:%S UTC")));
-        report.push_str(&format!("Status: `{:?}`\n", epoch.status()));
+    fn create_informal_vote_with_weighting(&mut self, proposal_id: Uuid, weighting: InformalWeighting) -> Result<Uuid, &'static str> {
+        let proposal = self.state.get_proposal_mut(&proposal_id)
+            .ok_or("Proposal not found")?;
 
-        if let Some(reward) = epoch.reward() {
-            report.push_str(&format!("Epoch Reward: `{} {}`\n", reward.amount(), escape_markdown(reward.token())));
-        } else {
-            report.push_str("Epoch Reward: `Not set`\n");
+        if !proposal.is_actionable() {
+            return Err("Proposal is not in a votable state");
         }
 
-        report.push_str("\n");
-
-        // Proposal counts
-        let mut open_proposals = Vec::new();
-        let mut approved_count = 0;
-        let mut rejected_count = 0;
-        let mut retracted_count = 0;
+        let epoch_id = proposal.epoch_id();
 
-        for proposal in &proposals {
-            match proposal.resolution() {
-                Some(Resolution::Approved) => approved_count += 1,
-                Some(Resolution::Rejected) => rejected_count += 1,
-                Some(Resolution::Retracted) => retracted_count += 1,
-                _ => {
-                    if proposal.is_actionable() {
-                        open_proposals.push(proposal);
-                    }
-                }
-            }
-        }
+        let vote = Vote::new(proposal_id, epoch_id, VoteType::Informal { weighting }, false);
 
-        report.push_str("📊 *Proposals*\n");
-        report.push_str(&format!("Total: `{}`\n", proposals.len()));
-        report.push_str(&format!("Open: `{}`\n", open_proposals.len()));
-        report.push_str(&format!("Approved: `{}`\n", approved_count));
-        report.push_str(&format!("Rejected: `{}`\n", rejected_count));
-        report.push_str(&format!("Retracted: `{}`\n", retracted_count));
+        let vote_id = self.state.add_vote(&vote);
+        self.save_state();
+        Ok(vote_id)
+    }
 
-        report.push_str("\n");
+    /// Opens a formal vote on the proposal of a closed informal vote, linking the two so the
+    /// proposal report can show the informal tally next to the formal outcome
+    pub fn promote_informal_vote(&mut self, informal_vote_id: Uuid, raffle_id: Uuid) -> Result<Uuid, &'static str> {
+        let informal = self.state.get_vote(&informal_vote_id).ok_or("Vote not found")?;
+        if !matches!(informal.vote_type(), VoteType::Informal { .. }) {
+            return Err("Only informal votes can be promoted");
+        }
+        if !informal.is_closed() {
+            return Err("Informal vote must be closed before it is promoted");
+        }
+        if informal.promoted_to().is_some() {
+            return Err("Informal vote has already been promoted");
+        }
 
-        // Open proposals
-        if !open_proposals.is_empty() {
-            report.push_str("📬 *Open proposals*\n\n");
-        
-            for proposal in open_proposals {
-                report.push_str(&format!("*{}*\n", escape_markdown(proposal.title())));
-                if let Some(url) = proposal.url() {
-                    report.push_str(&format!("🔗 {}\n", escape_markdown(url)));
-                }
-                if let Some(details) = proposal.budget_request_details() {
-                    if let (Some(start), Some(end)) = (details.start_date(), details.end_date()) {
-                        report.push_str(&format!("📆 {} \\- {}\n", 
-                            escape_markdown(&start.format("%b %d").to_string()),
-                            escape_markdown(&end.format("%b %d").to_string())
-                        ));
-                    }
-                    if !details.request_amounts().is_empty() {
-                        let amounts: Vec<String> = details.request_amounts().iter()
-                            .map(|(token, amount)| format!("{} {}", 
-                                escape_markdown(&amount.to_string()), 
-                                escape_markdown(token)
-                            ))
-                            .collect();
-                        report.push_str(&format!("💰 {}\n", amounts.join(", ")));
-                    }
-                }
-                let days_open = self.days_open(proposal);
-                report.push_str(&format!("⏳ _{} days open_\n\n", escape_markdown(&days_open.to_string())));
-            }
+        let proposal_id = informal.proposal_id();
+        let has_formal_vote = self.state.votes().values()
+            .any(|vote| vote.proposal_id() == proposal_id && matches!(vote.participation(), VoteParticipation::Formal { .. }));
+        if has_formal_vote {
+            return Err("Proposal already has a formal vote");
+        }
+        let raffle = self.state.get_raffle(&raffle_id).ok_or("Raffle not found")?;
+        if raffle.config().proposal_id() != proposal_id {
+            return Err("Raffle belongs to a different proposal");
         }
 
-        Ok(report)
+        let formal_vote_id = self.create_formal_vote(proposal_id, raffle_id, None)?;
+        self.state.get_vote_mut(&formal_vote_id).ok_or("Vote not found")?.set_promoted_from(Some(informal_vote_id));
+        self.state.get_vote_mut(&informal_vote_id).ok_or("Vote not found")?.set_promoted_to(Some(formal_vote_id));
+        self.save_state().map_err(|_| "Failed to save state")?;
+        Ok(formal_vote_id)
     }
 
-    pub fn print_team_vote_participation(&self, team_name: &str, epoch_name: Option<&str>) -> Result<String, Box<dyn Error>> {
-        let team_id = self.get_team_id_by_name(team_name)
-            .ok_or_else(|| format!("Team not found: {}", team_name))?;
-    
-        let epoch = if let Some(name) = epoch_name {
-            self.state.epochs().values()
-                .find(|e| e.name() == name)
-                .ok_or_else(|| format!("Epoch not found: {}", name))?
-        } else {
-            self.get_current_epoch()
-                .ok_or("No active epoch and no epoch specified")?
+    pub fn cast_votes(&mut self, vote_id: Uuid, votes: Vec<(Uuid, VoteChoice)>) -> Result<(), &'static str> {
+        let raffle_result = {
+            let vote = self.state.get_vote(&vote_id).ok_or("Vote not found")?;
+            match vote.vote_type() {
+                VoteType::Formal { raffle_id, .. } | VoteType::Weighted { raffle_id, .. } | VoteType::MultiOption { raffle_id, .. } => {
+                    self.state.get_raffle(&raffle_id)
+                        .and_then(|raffle| raffle.result().cloned())
+                },
+                VoteType::Informal { .. } => None,
+            }
         };
     
-        let mut report = format!("Vote Participation Report for Team: {}\n", team_name);
-        report.push_str(&format!("Epoch: {} ({})\n\n", epoch.name(), epoch.id()));
-        let mut vote_reports = Vec::new();
-        let mut total_points = 0;
-    
-        for vote_id in epoch.associated_proposals().iter()
-            .filter_map(|proposal_id| self.state.votes().values()
-                .find(|v| v.proposal_id() == *proposal_id)
-                .map(|v| v.id())) 
         {
-            let vote = self.state.get_vote(&vote_id).expect("Could not get Vote");
-            let (participation_status, points) = match (vote.vote_type(), vote.participation()) {
-                (VoteType::Formal { counted_points, uncounted_points, .. }, VoteParticipation::Formal { counted, uncounted }) => {
-                    if counted.contains(&team_id) {
-                        (Some("Counted"), *counted_points)
-                    } else if uncounted.contains(&team_id) {
-                        (Some("Uncounted"), *uncounted_points)
-                    } else {
-                        (None, 0)
-                    }
-                },
-                (VoteType::Informal, VoteParticipation::Informal(participants)) => {
-                    if participants.contains(&team_id) {
-                        (Some("N/A (Informal)"), 0)
-                    } else {
-                        (None, 0)
-                    }
-                },
-                _ => (None, 0),
-            };
-    
-            if let Some(status) = participation_status {
-                let proposal = self.state.proposals().get(&vote.proposal_id())
-                    .ok_or_else(|| format!("Proposal not found for vote: {}", vote_id))?;
-    
-                let vote_type = match vote.vote_type() {
-                    VoteType::Formal { .. } => "Formal",
-                    VoteType::Informal => "Informal",
-                };
-    
-                let result = match vote.result() {
-                    Some(VoteResult::Formal { passed, .. }) => if *passed { "Passed" } else { "Failed" },
-                    Some(VoteResult::Informal { .. }) => "N/A (Informal)",
-                    None => "Pending",
-                };
-    
-                total_points += points;
-    
-                vote_reports.push((
-                    vote.opened_at(),
-                    format!(
-                        "Vote ID: {}\n\
-                        Proposal: {}\n\
-                        Type: {}\n\
-                        Participation: {}\n\
-                        Result: {}\n\
-                        Points Earned: {}\n\n",
-                        vote_id, proposal.title(), vote_type, status, result, points
-                    )
-                ));
+            let vote = self.state.get_vote_mut(&vote_id).ok_or("Vote not found")?;
+            for (team_id, choice) in votes {
+                vote.cast_vote(team_id, choice, raffle_result.as_ref())?;
             }
         }
     
-        // Sort vote reports by date, most recent first
-        vote_reports.sort_by(|a, b| b.0.cmp(&a.0));
-    
-        // Add total points to the report
-        report.push_str(&format!("Total Points Earned: {}\n\n", total_points));
-    
-        // Add individual vote reports
-        for (_, vote_report) in &vote_reports {
-            report.push_str(vote_report);
-        }
-    
-        if vote_reports.is_empty() {
-            report.push_str("This team has not participated in any votes during this epoch.\n");
-        }
-    
-        Ok(report)
+        self.save_state();
+        Ok(())
     }
 
-    pub fn days_open(&self, proposal: &Proposal) -> i64 {
-        let announced_date = proposal.announced_at()
-            .unwrap_or_else(|| Utc::now().date_naive());
-        Utc::now().date_naive().signed_duration_since(announced_date).num_days()
+    /// Discards the votes cast so far on an open vote so they can be cast again
+    pub fn reset_vote(&mut self, vote_id: Uuid) -> Result<(), &'static str> {
+        let vote = self.state.get_vote_mut(&vote_id).ok_or("Vote not found")?;
+        vote.reset()?;
+
+        self.save_state();
+        Ok(())
     }
 
-    pub fn prepare_raffle(&mut self, proposal_name: &str, excluded_teams: Option<Vec<String>>, app_config: &AppConfig) -> Result<(Uuid, Vec<RaffleTicket>), Box<dyn Error>> {
-        let proposal_id = self.get_proposal_id_by_name(proposal_name)
-            .ok_or_else(|| format!("Proposal not found: {}", proposal_name))?;
-        let epoch_id = self.state.current_epoch()
-            .ok_or("No active epoch")?;
+    pub fn close_vote(&mut self, vote_id: Uuid) -> Result<bool, &'static str> {
+        let passed = self.close_vote_silently(vote_id)?;
+        self.emit_event(SystemEvent::VoteClosed { vote_id, passed });
+        Ok(passed)
+    }
 
-        let excluded_team_ids = excluded_teams.map(|names| {
-            names.into_iter()
-                .filter_map(|name| self.get_team_id_by_name(&name))
-                .collect::<Vec<Uuid>>()
-        }).unwrap_or_else(Vec::new);
-
-        let raffle_config = RaffleConfig::new(
-            proposal_id,
-            epoch_id,
-            app_config.default_total_counted_seats,
-            app_config.default_max_earner_seats,
-            Some(0),
-            Some(0),
-            Some(String::new()),
-            Some(excluded_team_ids),
-            None,
-            None,
-            false
-        );
+    fn close_vote_silently(&mut self, vote_id: Uuid) -> Result<bool, &'static str> {
+        let vote = self.state.get_vote_mut(&vote_id).ok_or("Vote not found")?;
+        
+        if vote.is_closed() {
+            return Err("Vote is already closed");
+        }
 
-        let raffle = Raffle::new(raffle_config, &self.state.current_state().teams())?;
-        let tickets = raffle.tickets().to_vec();
-        let raffle_id = self.state.add_raffle(&raffle);
-        self.save_state()?;
+        vote.close()?;
+        let result = Self::vote_passed(vote)?;
 
-        Ok((raffle_id, tickets))
+        self.save_state();
+        Ok(result)
     }
 
-    pub async fn import_historical_raffle(
-        &mut self,
-        proposal_name: &str,
-        initiation_block: u64,
-        randomness_block: u64,
-        team_order: Option<Vec<String>>,
-        excluded_teams: Option<Vec<String>>,
-        total_counted_seats: Option<usize>,
-        max_earner_seats: Option<usize>
-    ) -> Result<(Uuid, Raffle), Box<dyn Error>> {
-        let proposal_id = self.get_proposal_id_by_name(proposal_name)
-            .ok_or_else(|| format!("Proposal not found: {}", proposal_name))?;
-    
-        let epoch_id = self.state.current_epoch()
-            .ok_or("No active epoch")?;
-    
-        let randomness = self.ethereum_service.get_randomness(randomness_block).await?;
-    
-        let custom_team_order = team_order.map(|order| {
-            order.into_iter()
-                .filter_map(|name| self.get_team_id_by_name(&name))
-                .collect::<Vec<Uuid>>()
-        });
-    
-        let excluded_team_ids = excluded_teams.map(|names| {
-            names.into_iter()
-                .filter_map(|name| self.get_team_id_by_name(&name))
-                .collect::<Vec<Uuid>>()
-        }).unwrap_or_else(Vec::new);
-    
-        let total_counted_seats = total_counted_seats.unwrap_or(self.config.default_total_counted_seats);
-        let max_earner_seats = max_earner_seats.unwrap_or(self.config.default_max_earner_seats);
-    
-        if max_earner_seats > total_counted_seats {
-            return Err("max_earner_seats cannot be greater than total_counted_seats".into());
+    fn vote_passed(vote: &Vote) -> Result<bool, &'static str> {
+        match vote.result() {
+            Some(VoteResult::Formal { passed, .. } | VoteResult::Weighted { passed, .. }) => Ok(*passed),
+            Some(VoteResult::Informal { .. }) => Ok(false),
+            // A multi-option vote carries when any counted seat picked an option
+            Some(VoteResult::MultiOption { tallies, .. }) => Ok(tallies.iter().any(|&tally| tally > 0)),
+            None => Err("Vote result not available"),
         }
+    }
 
-        let raffle_config = RaffleConfig::new(
-            proposal_id,
-            epoch_id,
-            total_counted_seats,
-            max_earner_seats,
-            Some(initiation_block),
-            Some(randomness_block),
-            Some(randomness),
-            Some(excluded_team_ids),
-            None,
-            custom_team_order,
-            true
-        );
-    
-        let mut raffle = Raffle::new(raffle_config, self.state.current_state().teams())?;
-        raffle.generate_ticket_scores()?;
-        raffle.select_deciding_teams();
-    
-        let raffle_id = self.state.add_raffle(&raffle);
-        self.save_state()?;
-    
-        Ok((raffle_id, raffle))
+    /// Whether the open vote would pass if it were closed now
+    fn vote_would_pass(&self, vote_id: Uuid) -> Result<bool, &'static str> {
+        let mut vote = self.state.get_vote(&vote_id).ok_or("Vote not found")?.clone();
+        if vote.is_closed() {
+            return Err("Vote is already closed");
+        }
+        vote.close()?;
+        Self::vote_passed(&vote)
     }
 
-    pub async fn finalize_raffle(&mut self, raffle_id: Uuid, initiation_block: u64, randomness_block: u64, randomness: String) -> Result<Raffle, Box<dyn Error>> {
-        let raffle = self.state.get_raffle_mut(&raffle_id)
-            .ok_or_else(|| format!("Raffle not found: {}", raffle_id))?;
-    
-        raffle.config_mut().set_initiation_block(initiation_block);
-        raffle.config_mut().set_randomness_block(randomness_block);
-        raffle.config_mut().set_block_randomness(randomness);
-    
-        raffle.generate_ticket_scores()?;
-        raffle.select_deciding_teams();
-    
-        let raffle_clone = raffle.clone();
-        self.save_state()?;
-    
-        Ok(raffle_clone)
+    /// Flags an open vote as cast against a raffle that is pending a re-draw
+    pub fn mark_vote_provisional(&mut self, vote_id: Uuid) -> Result<(), &'static str> {
+        let vote = self.state.get_vote_mut(&vote_id).ok_or("Vote not found")?;
+
+        if vote.is_closed() {
+            return Err("Vote is already closed");
+        }
+
+        vote.set_provisional(true);
+        self.save_state();
+        Ok(())
     }
 
-    pub fn group_tickets_by_team(&self, tickets: &[RaffleTicket]) -> Vec<(String, u64, u64)> {
-        let mut grouped_tickets: Vec<(String, u64, u64)> = Vec::new();
-        let mut current_team: Option<(String, u64, u64)> = None;
+    /// Re-associates a provisional vote with a re-drawn raffle for the same proposal.
+    /// Returns the names of teams whose votes were dropped because they lost their seat.
+    pub fn rebind_vote_to_raffle(&mut self, vote_id: Uuid, new_raffle_id: Uuid) -> Result<Vec<String>, &'static str> {
+        let proposal_id = self.state.get_vote(&vote_id).ok_or("Vote not found")?.proposal_id();
 
-        for ticket in tickets {
-            let team_name = self.state.current_state().teams().get(&ticket.team_id())
+        let raffle = self.state.get_raffle(&new_raffle_id).ok_or("Raffle not found")?;
+        if raffle.config().proposal_id() != proposal_id {
+            return Err("Raffle belongs to a different proposal");
+        }
+        let raffle_result = raffle.result().cloned().ok_or("Raffle results have not been generated")?;
+        let total_eligible_seats = raffle.config().total_counted_seats() as u32;
+
+        let vote = self.state.get_vote_mut(&vote_id).ok_or("Vote not found")?;
+        let dropped = vote.rebind_to_raffle(new_raffle_id, total_eligible_seats, &raffle_result)?;
+
+        let dropped_names: Vec<String> = dropped.iter()
+            .map(|team_id| self.state.find_team(team_id)
                 .map(|team| team.name().to_string())
-                .unwrap_or_else(|| format!("Unknown Team ({})", ticket.team_id()));
+                .unwrap_or_else(|| team_id.to_string()))
+            .collect();
 
-            match &mut current_team {
-                Some((name, _, end)) if *name == team_name => {
-                    *end = ticket.index();
-                }
-                _ => {
-                    if let Some(team) = current_team.take() {
-                        grouped_tickets.push(team);
-                    }
-                    current_team = Some((team_name, ticket.index(), ticket.index()));
-                }
+        if !dropped_names.is_empty() {
+            warn!("Dropped votes from teams no longer eligible after re-draw: {}", dropped_names.join(", "));
+        }
+
+        self.save_state();
+        Ok(dropped_names)
+    }
+
+    pub fn create_epoch(&mut self, name: &str, start_date:DateTime<Utc>, end_date: DateTime<Utc>) -> Result<Uuid, &'static str> {
+        let new_epoch = Epoch::new(name.to_string(), start_date, end_date)?;
+        self.check_epoch_overlap(start_date, end_date)?;
+
+        let epoch_id = self.state.add_epoch(&new_epoch);
+        self.save_state();
+        Ok(epoch_id)
+    }
+
+    /// Creates `count` back-to-back planned epochs of `period_days` each, named `{base_name} Q1`,
+    /// `{base_name} Q2` and so on. If any of them would overlap an existing epoch, none is created.
+    pub fn create_recurring_epochs(&mut self, base_name: &str, start: DateTime<Utc>, period_days: u32, count: u32) -> Result<Vec<Uuid>, &'static str> {
+        if period_days == 0 {
+            return Err("Epoch period must be at least one day");
+        }
+        if count == 0 {
+            return Err("At least one epoch must be created");
+        }
+
+        let period = chrono::Duration::days(period_days as i64);
+        let mut new_epochs = Vec::new();
+        for i in 0..count {
+            let start_date = start + period * i as i32;
+            let end_date = start_date + period;
+            self.check_epoch_overlap(start_date, end_date)?;
+            new_epochs.push(Epoch::new(format!("{} Q{}", base_name, i + 1), start_date, end_date)?);
+        }
+
+        let epoch_ids = new_epochs.iter()
+            .map(|epoch| self.state.add_epoch(epoch))
+            .collect();
+        self.save_state();
+        Ok(epoch_ids)
+    }
+
+    fn check_epoch_overlap(&self, start_date: DateTime<Utc>, end_date: DateTime<Utc>) -> Result<(), &'static str> {
+        for epoch in self.state.epochs().values() {
+            if (start_date < epoch.end_date() && end_date > epoch.start_date()) ||
+            (epoch.start_date() < end_date && epoch.end_date() > start_date) {
+                return Err("New epoch overlaps with an existing epoch");
             }
         }
+        Ok(())
+    }
 
-        if let Some(team) = current_team {
-            grouped_tickets.push(team);
+    pub fn activate_epoch(&mut self, epoch_id: Uuid) -> Result<(), &'static str> {
+        if self.state.current_epoch().is_some() {
+            return Err("Another epoch is currently active");
         }
 
-        grouped_tickets
+        let epoch = self.state.get_epoch_mut(&epoch_id).ok_or("Epoch not found")?;
+
+        epoch.activate();
+        self.state.set_current_epoch(Some(epoch_id));
+        self.save_state();
+        Ok(())
     }
 
-    pub fn create_and_process_vote(
-        &mut self,
-        proposal_name: &str,
-        counted_votes: HashMap<String, VoteChoice>,
-        uncounted_votes: HashMap<String, VoteChoice>,
-        vote_opened: Option<NaiveDate>,
-        vote_closed: Option<NaiveDate>,
-    ) -> Result<String, Box<dyn Error>> {
-        // Find proposal and raffle
-        let (proposal_id, raffle_id) = self.find_proposal_and_raffle(proposal_name)
-            .map_err(|e| format!("Failed to find proposal or raffle: {}", e))?;
+    pub fn set_epoch_reward(&mut self, token: &str, amount: f64) -> Result<(), &'static str> {
+        let epoch_id = self.state.current_epoch().ok_or("No active epoch")?;
+        let epoch = self.state.get_epoch_mut(&epoch_id).ok_or("Epoch not found")?;
         
-        // Check if the proposal already has a resolution
-        let proposal = self.state.get_proposal_mut(&proposal_id)
-            .ok_or_else(|| "Proposal not found after ID lookup".to_string())?;
-        if proposal.resolution().is_some() {
-            return Err("Cannot create vote: Proposal already has a resolution".into());
+        epoch.set_reward(token.to_string(), amount);
+        self.save_state();
+        Ok(())
+    }
+
+    /// Sets the reward of a planned or active epoch by name. Closed epochs are refused because
+    /// their allocations are final; use `top_up_epoch_reward` for those.
+    pub fn update_epoch_reward(&mut self, epoch_name: &str, token: &str, amount: f64) -> Result<(), Box<dyn Error>> {
+        let epoch_id = self.require_epoch_id(epoch_name)?;
+        let epoch = self.state.get_epoch_mut(&epoch_id).ok_or("Epoch not found")?;
+        if epoch.is_closed() {
+            return Err("Cannot change the reward of a closed epoch".into());
         }
 
-        // Validate votes
-        self.validate_votes(raffle_id, &counted_votes, &uncounted_votes)
-            .map_err(|e| format!("Vote validation failed: {}", e))?;
-    
-        // Create vote
-        let vote_id = self.create_formal_vote(proposal_id, raffle_id, None)
-            .map_err(|e| format!("Failed to create formal vote: {}", e))?;
-    
-        // Cast votes
-        let all_votes: Vec<(Uuid, VoteChoice)> = counted_votes.into_iter()
-            .chain(uncounted_votes)
-            .filter_map(|(team_name, choice)| {
-                self.get_team_id_by_name(&team_name).map(|id| (id, choice))
+        epoch.set_reward(token.to_string(), amount)?;
+        self.save_state()?;
+        Ok(())
+    }
+
+    /// Withholds part of a team's reward for the epoch when it closes, e.g. for misconduct.
+    /// A multiplier of 1.0 removes the adjustment.
+    pub fn set_reward_adjustment(&mut self, epoch_name: &str, team_name: &str, multiplier: f64, reason: String) -> Result<(), Box<dyn Error>> {
+        let epoch_id = self.require_epoch_id(epoch_name)?;
+        let team_id = self.require_team_id(team_name)?;
+        self.state.get_epoch_mut(&epoch_id).ok_or("Epoch not found")?
+            .set_reward_adjustment(team_id, multiplier, reason)?;
+        self.save_state()?;
+        Ok(())
+    }
+
+    /// Distributes an addition to a closed epoch's reward by the team percentages stored at close.
+    /// Returns the amount added per team name.
+    pub fn top_up_epoch_reward(&mut self, epoch_name: &str, additional_amount: f64, token: &str) -> Result<Vec<(String, f64)>, Box<dyn Error>> {
+        let epoch_id = self.require_epoch_id(epoch_name)?;
+        let epoch = self.state.get_epoch_mut(&epoch_id).ok_or("Epoch not found")?;
+        let added = epoch.top_up_reward(token, additional_amount)?;
+
+        let mut top_ups: Vec<(String, f64)> = added.into_iter()
+            .map(|(team_id, amount)| {
+                let name = self.state.find_team(&team_id).map_or_else(|| team_id.to_string(), |team| team.name().to_string());
+                (name, amount)
             })
             .collect();
-        self.cast_votes(vote_id, all_votes)
-            .map_err(|e| format!("Failed to cast votes: {}", e))?;
-    
-        // Update vote dates
-        self.update_vote_dates(vote_id, vote_opened, vote_closed)
-            .map_err(|e| format!("Failed to update vote dates: {}", e))?;
-    
-        // Close vote and update proposal
-        let passed = self.close_vote_and_update_proposal(vote_id, proposal_id, vote_closed)
-            .map_err(|e| format!("Failed to close vote or update proposal: {}", e))?;
+        top_ups.sort_by(|a, b| a.0.cmp(&b.0));
 
-        // Generate report
-        self.generate_vote_report(vote_id)
+        self.save_state()?;
+        Ok(top_ups)
     }
-    
-    pub fn find_proposal_and_raffle(&self, proposal_name: &str) -> Result<(Uuid, Uuid), Box<dyn Error>> {
-        let proposal_id = self.get_proposal_id_by_name(proposal_name)
-            .ok_or_else(|| format!("Proposal not found: {}", proposal_name))?;
-        
-        let raffle_id = self.state.raffles().iter()
-            .find(|(_, raffle)| raffle.config().proposal_id() == proposal_id)
-            .map(|(id, _)| *id)
-            .ok_or_else(|| format!("No raffle found for proposal: {}", proposal_name))?;
-        Ok((proposal_id, raffle_id))
+
+    /// Amendments to the named proposal, oldest first; empty when the proposal is unknown
+    pub fn get_proposal_amendments(&self, proposal_name: &str) -> Vec<&Amendment> {
+        let Some(proposal) = self.get_proposal_id_by_name(proposal_name).and_then(|id| self.state.get_proposal(&id)) else {
+            return Vec::new();
+        };
+        let mut amendments: Vec<&Amendment> = proposal.amendments().iter().collect();
+        amendments.sort_by_key(|a| a.timestamp);
+        amendments
     }
-    
-    pub fn validate_votes(
-        &self,
-        raffle_id: Uuid,
-        counted_votes: &HashMap<String, VoteChoice>,
-        uncounted_votes: &HashMap<String, VoteChoice>,
-    ) -> Result<(), Box<dyn Error>> {
-        let raffle = self.state.raffles().get(&raffle_id)
-            .ok_or_else(|| format!("Raffle not found: {}", raffle_id))?;
-    
-        if !raffle.is_completed() {
-            return Err("Raffle has not been conducted yet".into());
-        }
-    
-        self.validate_votes_against_raffle(raffle, counted_votes, uncounted_votes)
+
+    /// Amendments to the epoch's proposals, per proposal title, ordered by first amendment.
+    /// Proposals that were never amended are left out.
+    pub fn epoch_amendment_log(&self, epoch_id: Uuid) -> Vec<(String, Vec<Amendment>)> {
+        let mut log: Vec<(String, Vec<Amendment>)> = self.get_proposals_for_epoch(epoch_id).into_iter()
+            .filter(|proposal| !proposal.amendments().is_empty())
+            .map(|proposal| {
+                let mut amendments = proposal.amendments().to_vec();
+                amendments.sort_by_key(|a| a.timestamp);
+                (proposal.title().to_string(), amendments)
+            })
+            .collect();
+        log.sort_by(|a, b| a.1[0].timestamp.cmp(&b.1[0].timestamp).then_with(|| a.0.cmp(&b.0)));
+        log
     }
-    
-    pub fn update_vote_dates(
-        &mut self,
-        vote_id: Uuid,
-        vote_opened: Option<NaiveDate>,
-        vote_closed: Option<NaiveDate>,
-    ) -> Result<(), Box<dyn Error>> {
-        let vote = self.state.get_vote_mut(&vote_id).ok_or("Vote not found")?;
-        
-        if let Some(opened) = vote_opened {
-            let opened_datetime = opened.and_hms_opt(0, 0, 0)
-                .map(|naive| Utc.from_utc_datetime(&naive))
-                .ok_or("Invalid opened date")?;
-            vote.set_opened_at(opened_datetime);
-        }
-        
-        if let Some(closed) = vote_closed {
-            let closed_datetime = closed.and_hms_opt(23, 59, 59)
-                .map(|naive| Utc.from_utc_datetime(&naive))
-                .ok_or("Invalid closed date")?;
-            vote.set_closed_at(Some(closed_datetime));
-        }
-        
-        Ok(())
+
+    pub fn get_current_epoch(&self) -> Option<&Epoch> {
+        self.state.current_epoch().and_then(|id| self.state.epochs().get(&id))
     }
-    
-    pub fn close_vote_and_update_proposal(
-        &mut self,
-        vote_id: Uuid,
-        proposal_id: Uuid,
-        vote_closed: Option<NaiveDate>,
-    ) -> Result<bool, Box<dyn Error>> {
-        let passed = self.close_vote(vote_id)?;
-        
-        let proposal = self.state.get_proposal_mut(&proposal_id)
-            .ok_or_else(|| format!("Proposal not found: {}", proposal_id))?;
-        
-        println!("Proposal status before update: {:?}", proposal.status());
-        println!("Proposal resolution before update: {:?}", proposal.resolution());
-        
-        let result = if passed {
-            proposal.approve()
-        } else {
-            proposal.reject()
-        };
-    
-        match result {
-            Ok(()) => {
-                if let Some(closed) = vote_closed {
-                    proposal.set_resolved_at(Some(closed));
-                }
-                println!("Proposal status after update: {:?}", proposal.status());
-                println!("Proposal resolution after update: {:?}", proposal.resolution());
-                self.save_state()?;
-                Ok(passed)
-            },
-            Err(e) => {
-                println!("Error updating proposal: {}", e);
-                println!("Current proposal state: {:?}", proposal);
-                Err(format!("Failed to update proposal: {}", e).into())
-            }
-        }
+
+    pub fn add_proposal_attachment(&mut self, proposal_name: &str, label: String, cid: String) -> Result<(), Box<dyn Error>> {
+        let proposal_id = self.require_proposal_id(proposal_name)?;
+        let proposal = self.state.get_proposal_mut(&proposal_id).ok_or("Proposal not found")?;
+        proposal.add_attachment(label, cid)?;
+        self.save_state()?;
+        Ok(())
     }
 
-    pub fn generate_vote_report(&self, vote_id: Uuid) -> Result<String, Box<dyn Error>> {
-        let vote = self.state.get_vote(&vote_id).ok_or("Vote not found")?;
-        let proposal = self.state.proposals().get(&vote.proposal_id()).ok_or("Proposal not found")?;
-        let raffle = self.state.raffles().values()
-            .find(|r| r.config().proposal_id() == vote.proposal_id())
-            .ok_or("Associated raffle not found")?;
-    
-        let (counted, uncounted) = vote.vote_counts().ok_or("Vote counts not available")?;
-        let counted_yes = counted.yes();
-        let counted_no = counted.no();
-        let total_counted_votes = counted_yes + counted_no;
-        
-        let total_eligible_seats = match vote.vote_type() {
-            VoteType::Formal { total_eligible_seats, .. } => total_eligible_seats,
-            _ => &0,
-        };
-    
-        // Calculate absent votes for counted seats only
-        let absent = total_eligible_seats.saturating_sub(total_counted_votes as u32);
+    pub fn set_proposal_tags(&mut self, proposal_id: Uuid, tags: Vec<String>) -> Result<(), &'static str> {
+        let proposal = self.state.get_proposal_mut(&proposal_id).ok_or("Proposal not found")?;
+        proposal.set_tags(tags);
+        self.save_state().map_err(|_| "Failed to save state")?;
+        Ok(())
+    }
 
-        let status = match vote.result() {
-            Some(VoteResult::Formal { passed, .. }) => if *passed { "Approved" } else { "Not Approved" },
-            Some(VoteResult::Informal { .. }) => "N/A (Informal)",
-            None => "Pending",
-        };
-    
-        let deciding_teams: Vec<String> = raffle.deciding_teams().iter()
-            .filter_map(|&team_id| {
-                self.state.current_state().teams().get(&team_id).map(|team| team.name().to_string())
-            })
-            .collect();
-    
-        // Calculate uncounted votes
-        let total_uncounted_votes = uncounted.yes() + uncounted.no();
-        let total_uncounted_seats = raffle.result()
-            .map(|result| result.uncounted().len())
-            .unwrap_or(0) as u32;
+    pub fn set_proposal_kind(&mut self, proposal_id: Uuid, kind: ProposalKind) -> Result<(), &'static str> {
+        let proposal = self.state.get_proposal_mut(&proposal_id).ok_or("Proposal not found")?;
+        proposal.set_kind(kind)?;
+        self.save_state().map_err(|_| "Failed to save state")?;
+        Ok(())
+    }
 
-        let (counted_votes_info, uncounted_votes_info) = if let VoteParticipation::Formal { counted, uncounted } = &vote.participation() {
-            let absent_counted: Vec<String> = raffle.result().expect("Raffle result not found").counted().iter()
-                .filter(|&team_id| !counted.contains(team_id))
-                .filter_map(|&team_id| self.state.current_state().teams().get(&team_id).map(|team| team.name().to_string()))
-                .collect();
+    /// Proposals matching all set fields of the filter, across all epochs when no epoch is given,
+    /// ordered by announcement date and then title
+    pub fn query_proposals(&self, filter: ProposalFilter) -> Vec<&Proposal> {
+        let mut proposals: Vec<&Proposal> = self.state.proposals().values().collect();
+        if let Some(epoch_id) = filter.epoch_id {
+            proposals.retain(|p| p.epoch_id() == epoch_id);
+        }
+        if let Some(status) = &filter.status {
+            proposals.retain(|p| p.has_status(status));
+        }
+        if let Some(team_name) = &filter.team_name {
+            let team_id = self.get_team_id_by_name(team_name);
+            proposals.retain(|p| team_id.is_some() && p.budget_request_details().and_then(|d| d.team()) == team_id);
+        }
+        if let Some(date) = filter.announced_after {
+            proposals.retain(|p| p.announced_at().is_some_and(|a| a >= date));
+        }
+        if let Some(date) = filter.announced_before {
+            proposals.retain(|p| p.announced_at().is_some_and(|a| a <= date));
+        }
+        if let Some(tag) = &filter.tag {
+            proposals.retain(|p| p.has_tag(tag));
+        }
+        proposals.sort_by(|a, b| a.announced_at().cmp(&b.announced_at()).then_with(|| a.title().cmp(b.title())));
+        proposals
+    }
 
-            let absent_uncounted: Vec<String> = raffle.result().expect("Raffle result not found").uncounted().iter()
-                .filter(|&team_id| !uncounted.contains(team_id))
-                .filter_map(|&team_id| self.state.current_state().teams().get(&team_id).map(|team| team.name().to_string()))
-                .collect();
+    /// Proposals in the epoch with the given tag; "untagged" matches proposals without tags
+    pub fn get_proposals_by_tag(&self, epoch_id: Uuid, tag: &str) -> Vec<&Proposal> {
+        self.get_proposals_for_epoch(epoch_id).into_iter()
+            .filter(|proposal| {
+                if tag.trim().eq_ignore_ascii_case(UNTAGGED) {
+                    proposal.tags().is_empty()
+                } else {
+                    proposal.has_tag(tag)
+                }
+            })
+            .collect()
+    }
 
-            let counted_info = if absent_counted.is_empty() {
-                format!("Counted votes cast: {}/{}", total_counted_votes, total_eligible_seats)
-            } else {
-                format!("Counted votes cast: {}/{} ({} absent)", total_counted_votes, total_eligible_seats, absent_counted.join(", "))
-            };
+    /// Current teams by name, so reports list them in the same order on every run
+    pub fn sorted_teams(&self) -> Vec<&Team> {
+        let mut teams: Vec<&Team> = self.state.current_state().teams().values().collect();
+        teams.sort_by(|a, b| a.name().cmp(b.name()).then_with(|| a.id().cmp(&b.id())));
+        teams
+    }
 
-            let uncounted_info = if absent_uncounted.is_empty() {
-                format!("Uncounted votes cast: {}/{}", total_uncounted_votes, total_uncounted_seats)
-            } else {
-                format!("Uncounted votes cast: {}/{} ({} absent)", total_uncounted_votes, total_uncounted_seats, absent_uncounted.join(", "))
-            };
+    /// All epochs, oldest first
+    pub fn epochs_by_start_date(&self) -> Vec<&Epoch> {
+        let mut epochs: Vec<&Epoch> = self.state.epochs().values().collect();
+        epochs.sort_by(|a, b| a.start_date().cmp(&b.start_date()).then_with(|| a.name().cmp(b.name())));
+        epochs
+    }
 
-            (counted_info, uncounted_info)
+    pub fn get_proposals_for_epoch(&self, epoch_id: Uuid) -> Vec<&Proposal> {
+        if let Some(epoch) = self.state.epochs().get(&epoch_id) {
+            epoch.associated_proposals().iter()
+                .filter_map(|&id| self.state.proposals().get(&id))
+                .collect()
         } else {
-            (
-                format!("Counted votes cast: {}/{}", total_counted_votes, total_eligible_seats),
-                format!("Uncounted votes cast: {}/{}", total_uncounted_votes, total_uncounted_seats)
-            )
-        };
-    
-    
-        let report = format!(
-            "**{}**\n{}\n\n**Status: {}**\n__{} in favor, {} against, {} absent__\n\n**Deciding teams**\n`{:?}`\n\n{}\n{}",
-            proposal.title(),
-            proposal.url().as_deref().unwrap_or(""),
-            status,
-            counted_yes,
-            counted_no,
-            absent,
-            deciding_teams,
-            counted_votes_info,
-            uncounted_votes_info
-        );
-    
-        Ok(report)
+            vec![]
+        }
     }
 
-    pub fn validate_votes_against_raffle(
-        &self,
-        raffle: &Raffle,
-        counted_votes: &HashMap<String, VoteChoice>,
-        uncounted_votes: &HashMap<String, VoteChoice>,
-    ) -> Result<(), Box<dyn Error>> {
-        let raffle_result = raffle.result().ok_or("Raffle result not found")?;
-    
-        let counted_team_ids: HashSet<_> = raffle_result.counted().iter().cloned().collect();
-        let uncounted_team_ids: HashSet<_> = raffle_result.uncounted().iter().cloned().collect();
-    
-        for team_name in counted_votes.keys() {
-            let team_id = self.get_team_id_by_name(team_name)
-                .ok_or_else(|| format!("Team not found: {}", team_name))?;
-            if !counted_team_ids.contains(&team_id) {
-                return Err(format!("Team {} is not eligible for counted vote", team_name).into());
+    pub fn update_epoch_dates(&mut self, epoch_id: Uuid, new_start: DateTime<Utc>, new_end: DateTime<Utc>) -> Result<(), &'static str> {
+        // Check for overlaps with other epochs
+        for other_epoch in self.state.epochs().values() {
+            if other_epoch.id() != epoch_id &&
+               ((new_start < other_epoch.end_date() && new_end > other_epoch.start_date()) ||
+                (other_epoch.start_date() < new_end && other_epoch.end_date() > new_start)) {
+                return Err("New dates overlap with an existing epoch");
             }
         }
-    
-        for team_name in uncounted_votes.keys() {
-            let team_id = self.get_team_id_by_name(team_name)
-                .ok_or_else(|| format!("Team not found: {}", team_name))?;
-            if !uncounted_team_ids.contains(&team_id) {
-                return Err(format!("Team {} is not eligible for uncounted vote", team_name).into());
-            }
+        
+        let epoch = self.state.get_epoch_mut(&epoch_id).ok_or("Epoch not found")?;
+
+        if !epoch.is_planned() {
+            return Err("Can only modify dates of planned epochs");
         }
-    
+
+        epoch.set_dates(new_start, new_end);
+
         Ok(())
     }
 
-    pub fn update_proposal(&mut self, proposal_name: &str, updates: UpdateProposalDetails) -> Result<(), &'static str> {
-        // Find the team_id if it's needed
-        let team_id = if let Some(budget_details) = &updates.budget_request_details {
-            if let Some(team_name) = &budget_details.team {
-                self.get_team_id_by_name(team_name)
-            } else {
-                None
-            }
-        } else {
-            None
-        };
-    
-        // Update the proposal
-        let proposal_id = self.get_proposal_id_by_name(proposal_name).ok_or("Name not matching a proposal")?;
-        let proposal = self.state.get_proposal_mut(&proposal_id).ok_or("Proposal not found")?;
-    
-        proposal.update(updates, team_id)?;
-    
-        self.save_state();
-        Ok(())
+    pub fn get_team_id_by_name(&self, name: &str) -> Option<Uuid> {
+        get_id_by_name(&self.state.current_state().teams(), name)
     }
 
-    pub fn generate_markdown_test(&self) -> String {
-        let test_message = r#"
-*Bold text*
-_Italic text_
-__Underline__
-~Strikethrough~
-*Bold _italic bold ~italic bold strikethrough~ __underline italic bold___ bold*
-[inline URL](http://www.example.com/)
-[inline mention of a user](tg://user?id=123456789)
-`inline fixed-width code`
-```python
-def hello_world():
-    print("Hello, World!")
-```
-"#;
-        test_message.to_string()
+    pub fn get_epoch_id_by_name(&self, name: &str) -> Option<Uuid> {
+        get_id_by_name(&self.state.epochs(), name)
     }
 
-    pub fn generate_proposal_report(&self, proposal_id: Uuid) -> Result<String, Box<dyn Error>> {
-        debug!("Generating proposal report for ID: {:?}", proposal_id);
-    
-        let proposal = self.state.get_proposal(&proposal_id)
-            .ok_or_else(|| format!("Proposal not found: {:?}", proposal_id))?;
-    
-        debug!("Found proposal: {:?}", proposal.title());
-    
-        let mut report = String::new();
-    
-        // Main title (moved outside of Summary)
-        report.push_str(&format!("# Proposal Report: {}\n\n", proposal.title()));
-    
-        // Summary
-        report.push_str("## Summary\n\n");
-        if let (Some(announced), Some(resolved)) = (proposal.announced_at(), proposal.resolved_at()) {
-            let resolution_days = self.calculate_days_between(announced, resolved);
-            report.push_str(&format!("This proposal was resolved in {} days from its announcement date. ", resolution_days));
-        }
-    
-        if let Some(vote) = self.state.votes().values().find(|v| v.proposal_id() == proposal_id) {
-            if let Some(result) = vote.result() {
-                match result {
-                    VoteResult::Formal { counted, uncounted, passed } => {
-                        report.push_str(&format!("The proposal was {} with {} votes in favor and {} votes against. ", 
-                            if *passed { "approved" } else { "not approved" }, 
-                            counted.yes(), counted.yes() + uncounted.yes()));
-                    },
-                    VoteResult::Informal { count } => {
-                        report.push_str(&format!("This was an informal vote with {} votes in favor and {} votes against. ", 
-                            count.yes(), count.no()));
-                    }
-                }
-            }
-        } else {
-            report.push_str("No voting information is available for this proposal. ");
+    pub fn get_proposal_id_by_name(&self, name: &str) -> Option<Uuid> {
+        get_id_by_name(&self.state.proposals(), name)
+    } 
+
+    /// Like `get_team_id_by_name`, but fails with a `NotFoundError` naming the team
+    pub fn require_team_id(&self, name: &str) -> Result<Uuid, NotFoundError> {
+        self.get_team_id_by_name(name).ok_or_else(|| NotFoundError { kind: "Team", name: name.to_string() })
+    }
+
+    pub fn require_epoch_id(&self, name: &str) -> Result<Uuid, NotFoundError> {
+        self.get_epoch_id_by_name(name).ok_or_else(|| NotFoundError { kind: "Epoch", name: name.to_string() })
+    }
+
+    pub fn require_proposal_id(&self, name: &str) -> Result<Uuid, NotFoundError> {
+        self.get_proposal_id_by_name(name).ok_or_else(|| NotFoundError { kind: "Proposal", name: name.to_string() })
+    }
+
+    pub fn import_predefined_raffle(
+        &mut self,
+        proposal_name: &str,
+        counted_teams: Vec<String>,
+        uncounted_teams: Vec<String>,
+        total_counted_seats: usize,
+        max_earner_seats: usize
+    ) -> Result<Uuid, Box<dyn Error>> {
+        let proposal_id = self.require_proposal_id(proposal_name)?;
+        
+        let epoch_id = self.state.current_epoch()
+            .ok_or("No active epoch")?;
+
+        let counted_team_ids: Vec<Uuid> = counted_teams.iter()
+            .filter_map(|name| self.get_team_id_by_name(name))
+            .collect();
+        
+        let uncounted_team_ids: Vec<Uuid> = uncounted_teams.iter()
+            .filter_map(|name| self.get_team_id_by_name(name))
+            .collect();
+
+        // Check if total_counted_seats matches the number of counted teams
+        if counted_team_ids.len() != total_counted_seats {
+            return Err(format!(
+                "Mismatch between specified total_counted_seats ({}) and actual number of counted teams ({})",
+                total_counted_seats, counted_team_ids.len()
+            ).into());
         }
-    
-        if let Some(budget_details) = proposal.budget_request_details() {
-            report.push_str(&format!("The budget request was for {} {} for the period from {} to {}. ",
-                budget_details.request_amounts().values().sum::<f64>(),
-                budget_details.request_amounts().keys().next().unwrap_or(&String::new()),
-                budget_details.start_date().map_or("N/A".to_string(), |d| d.format("%Y-%m-%d").to_string()),
-                budget_details.end_date().map_or("N/A".to_string(), |d| d.format("%Y-%m-%d").to_string())
-            ));
+
+        // Additional check to ensure max_earner_seats is not greater than total_counted_seats
+        if max_earner_seats > total_counted_seats {
+            return Err(format!(
+                "max_earner_seats ({}) cannot be greater than total_counted_seats ({})",
+                max_earner_seats, total_counted_seats
+            ).into());
+        }
+
+        let raffle_config = RaffleConfig::new(
+            proposal_id,
+            epoch_id,
+            total_counted_seats,
+            max_earner_seats,
+            Some(0),
+            Some(0),
+            Some("N/A".to_string()),
+            Some(Vec::new()),
+            None,
+            Some(counted_team_ids.iter().chain(uncounted_team_ids.iter()).cloned().collect()),
+            true,
+        );
+
+        let mut raffle = Raffle::new(raffle_config, self.state.current_state().teams())?;
+        raffle.set_result(RaffleResult::new(counted_team_ids, uncounted_team_ids));
+
+        let raffle_id = self.state.add_raffle(&raffle);
+        self.save_state()?;
+
+        Ok(raffle_id)
+    }
+
+    pub fn import_historical_vote(
+        &mut self,
+        proposal_name: &str,
+        passed: bool,
+        participating_teams: Vec<String>,
+        non_participating_teams: Vec<String>,
+        abstaining_teams: Vec<String>,
+        counted_points: Option<u32>,
+        uncounted_points: Option<u32>
+    ) -> Result<Uuid, Box<dyn Error>> {
+        let proposal_id = self.require_proposal_id(proposal_name)?;
+        if passed {
+            self.check_budget_cap(proposal_id)?;
         }
     
-        report.push_str("\n\n");
-    
-        // Proposal Details
-        report.push_str("## Proposal Details\n\n");
-        report.push_str(&format!("- **ID**: {}\n", proposal.id()));
-        report.push_str(&format!("- **Title**: {}\n", proposal.title()));
-        report.push_str(&format!("- **URL**: {}\n", proposal.url().as_deref().unwrap_or("N/A")));
-        report.push_str(&format!("- **Status**: {:?}\n", proposal.status()));
-        report.push_str(&format!("- **Resolution**: {}\n", proposal.resolution().as_ref().map_or("N/A".to_string(), |r| format!("{:?}", r))));
-        report.push_str(&format!("- **Announced**: {}\n", proposal.announced_at().map_or("N/A".to_string(), |d| d.format("%Y-%m-%d").to_string())));
-        report.push_str(&format!("- **Published**: {}\n", proposal.published_at().map_or("N/A".to_string(), |d| d.format("%Y-%m-%d").to_string())));
-        report.push_str(&format!("- **Resolved**: {}\n", proposal.resolved_at().map_or("N/A".to_string(), |d| d.format("%Y-%m-%d").to_string())));
-        report.push_str(&format!("- **Is Historical**: {}\n\n", proposal.is_historical()));
+        let raffle = self.active_raffle_for_proposal(proposal_id)
+            .ok_or_else(|| format!("No raffle found for proposal: {}", proposal_name))?;
+        let raffle_id = raffle.id();
     
-        // Budget Request Details
-        if let Some(budget_details) = proposal.budget_request_details() {
-            report.push_str("## Budget Request Details\n\n");
-            
-            // Team info
-            report.push_str(&format!("- **Requesting Team**: {}\n", 
-                budget_details.team()
-                    .and_then(|id| self.state.current_state().teams().get(&id))
-                    .map_or("N/A".to_string(), |team| team.name().to_string())));
-            
-            // Sort amounts by token for consistent output
-            let mut amounts: Vec<_> = budget_details.request_amounts().iter().collect();
-            amounts.sort_by(|(a, _), (b, _)| a.cmp(b));
-            
-            report.push_str("- **Requested Amount(s)**:\n");
-            for (token, amount) in amounts {
-                report.push_str(&format!("  - {}: {}\n", token, amount));
-            }
- 
-            report.push_str(&format!("- **Start Date**: {}\n", 
-                budget_details.start_date()
-                    .map_or("N/A".to_string(), |d| d.format("%Y-%m-%d").to_string())));
-            report.push_str(&format!("- **End Date**: {}\n", 
-                budget_details.end_date()
-                    .map_or("N/A".to_string(), |d| d.format("%Y-%m-%d").to_string())));
-            report.push_str(&format!("- **Is Loan**: {}\n", 
-                budget_details.is_loan()));
-            report.push_str(&format!("- **Payment Address**: {}\n", 
-                budget_details.payment_address()
-                    .map_or("N/A".to_string(), |addr| format!("{:?}", addr))));
-            if budget_details.is_paid() {
-                report.push_str(&format!("- **Payment Transaction**: {}\n",
-                    budget_details.payment_tx().map_or("N/A".to_string(), |tx| format!("{:?}", tx))));
-                report.push_str(&format!("- **Payment Date**: {}\n",
-                    budget_details.payment_date().map_or("N/A".to_string(), |d| d.format("%Y-%m-%d").to_string())));
-            }
-            report.push_str("\n");
-        }
+        let epoch_id = raffle.config().epoch_id();
     
-        // Raffle Information
-        if let Some(raffle) = self.state.raffles().values().find(|r| r.config().proposal_id() == proposal_id) {
-            report.push_str("## Raffle Information\n\n");
-            report.push_str(&format!("- **Raffle ID**: {}\n", raffle.id()));
-            report.push_str(&format!("- **Initiation Block**: {}\n", raffle.config().initiation_block()));
-            report.push_str(&format!("- **Randomness Block**: [{}]({})\n", 
-                raffle.config().randomness_block(), raffle.etherscan_url()));
-            report.push_str(&format!("- **Block Randomness**: {}\n", raffle.config().block_randomness()));
-            report.push_str(&format!("- **Total Counted Seats**: {}\n", raffle.config().total_counted_seats()));
-            report.push_str(&format!("- **Max Earner Seats**: {}\n", raffle.config().max_earner_seats()));
-            report.push_str(&format!("- **Is Historical**: {}\n\n", raffle.config().is_historical()));
+        let vote_type = VoteType::Formal {
+            raffle_id,
+            total_eligible_seats: raffle.config().total_counted_seats() as u32,
+            threshold: self.config.default_qualified_majority_threshold,
+            quorum: self.config.default_quorum,
+            counted_points: counted_points.unwrap_or(self.config.counted_vote_points),
+            uncounted_points: uncounted_points.unwrap_or(self.config.uncounted_vote_points)
+        };
     
-            // Team Snapshots
-            report.push_str(&self.generate_team_snapshots_table(raffle));
+        let mut vote = Vote::new(proposal_id, epoch_id, vote_type, true);
     
-            // Raffle Outcome
-            if let Some(result) = raffle.result() {
-                report.push_str("### Raffle Outcome\n\n");
-                self.generate_raffle_outcome(&mut report, raffle, result);
+        // Determine participation
+        let (mut participating_ids, _) = self.determine_participation(
+            raffle,
+            &participating_teams,
+            &non_participating_teams
+        )?;
+
+        // Teams that explicitly abstained were present, so they count as participants
+        let abstaining_ids: Vec<Uuid> = abstaining_teams.iter()
+            .map(|name| self.require_team_id(name))
+            .collect::<Result<_, _>>()?;
+        for &team_id in &abstaining_ids {
+            if !participating_ids.contains(&team_id) {
+                participating_ids.push(team_id);
             }
-        } else {
-            report.push_str("## Raffle Information\n\nNo raffle was conducted for this proposal.\n\n");
         }
     
-        // Voting Information
-        if let Some(vote) = self.state.votes().values().find(|v| v.proposal_id() == proposal_id) {
-            report.push_str("## Voting Information\n\n");
-            report.push_str("### Vote Details\n\n");
-            report.push_str(&format!("- **Vote ID**: {}\n", vote.id()));
-            report.push_str(&format!("- **Type**: {:?}\n", vote.vote_type()));
-            report.push_str(&format!("- **Status**: {:?}\n", vote.status()));
-            report.push_str(&format!("- **Opened**: {}\n", vote.opened_at().format("%Y-%m-%d %H:%M:%S")));
-            if let Some(closed_at) = vote.closed_at() {
-                report.push_str(&format!("- **Closed**: {}\n", closed_at.format("%Y-%m-%d %H:%M:%S")));
-            }
-            if let Some(result) = vote.result() {
-                match result {
-                    VoteResult::Formal { passed, .. } => {
-                        report.push_str(&format!("- **Result**: {}\n\n", if *passed { "Passed" } else { "Not Passed" }));
-                    },
-                    VoteResult::Informal { .. } => {
-                        report.push_str("- **Result**: Informal (No Pass/Fail)\n\n");
-                    }
+        let raffle_result = raffle.result().ok_or("Raffle result not found")?;
+    
+        // Set participation without casting actual votes
+        let mut counted_count = VoteCount::new();
+        let mut uncounted_count = VoteCount::new();
+        for &team_id in &participating_ids {
+            let abstained = abstaining_ids.contains(&team_id);
+            if raffle_result.counted().contains(&team_id) {
+                vote.add_participant(team_id, true)?;
+                if abstained {
+                    counted_count.increment_abstain();
+                }
+            } else if raffle_result.uncounted().contains(&team_id) {
+                vote.add_participant(team_id, false)?;
+                if abstained {
+                    uncounted_count.increment_abstain();
                 }
             }
+        }
     
-            // Participation
-            report.push_str("### Participation\n\n");
-            report.push_str(&self.generate_vote_participation_tables(vote));
+        // Close the vote
+        vote.close()?;
     
-            // Vote Counts
-            if !vote.is_historical() {
-                report.push_str("### Vote Counts\n");
-                match vote.vote_type() {
-                    VoteType::Formal { total_eligible_seats, .. } => {
-                        if let Some(VoteResult::Formal { counted, uncounted, .. }) = vote.result() {
-                            let absent = *total_eligible_seats as i32 - (counted.yes() + counted.no()) as i32;
-                            
-                            report.push_str("#### Counted Votes\n");
-                            report.push_str(&format!("- **Yes**: {}\n", counted.yes()));
-                            report.push_str(&format!("- **No**: {}\n", counted.no()));
-                            if absent > 0 {
-                                report.push_str(&format!("- **Absent**: {}\n", absent));
-                            }
+        // Set the result manually for historical votes; only abstentions are known
+        let result = VoteResult::Formal {
+            counted: counted_count,
+            uncounted: uncounted_count,
+            passed,
+            quorum_reached: true,
+            at_threshold: false,
+        };
+        vote.set_result(Some(result));
     
-                            report.push_str("\n#### Uncounted Votes\n");
-                            report.push_str(&format!("- **Yes**: {}\n", uncounted.yes()));
-                            report.push_str(&format!("- **No**: {}\n", uncounted.no()));
-                        }
-                    },
-                    VoteType::Informal => {
-                        if let Some(VoteResult::Informal { count }) = vote.result() {
-                            report.push_str(&format!("- **Yes**: {}\n", count.yes()));
-                            report.push_str(&format!("- **No**: {}\n", count.no()));
-                        }
-                    }
-                }
-            } else {
-                report.push_str("Vote counts not available for historical votes.\n");
-            }
+        // Set dates (using current time as a placeholder)
+        let now = Utc::now();
+        vote.set_opened_at(now);
+        vote.set_closed_at(Some(now));
+    
+        let vote_id = self.state.add_vote(&vote);
+    
+        // Update proposal status based on vote result
+        let proposal = self.state.get_proposal_mut(&proposal_id)
+            .ok_or_else(|| format!("Proposal not found: {}", proposal_id))?;
+        
+        if passed {
+            proposal.approve()?;
         } else {
-            report.push_str("## Voting Information\n\nNo vote was conducted for this proposal.\n\n");
+            proposal.reject()?;
         }
+        proposal.set_status(ProposalStatus::Closed);
     
-        Ok(report)
+        self.save_state()?;
+    
+        Ok(vote_id)
     }
 
-    pub fn generate_team_snapshots_table(&self, raffle: &Raffle) -> String {
-        let mut table = String::from("### Team Snapshots\n\n");
-        table.push_str("| Team Name | Status | Revenue | Ballot Range | Ticket Count |\n");
-        table.push_str("|-----------|--------|---------|--------------|--------------|\n");
-
-        for snapshot in raffle.team_snapshots() {
-            let team_name = snapshot.name();
-            
-            let status = match &snapshot.status() {
-                TeamStatus::Earner { .. } => "Earner",
-                TeamStatus::Supporter => "Supporter",
-                TeamStatus::Inactive => "Inactive",
-            };
+    pub fn determine_participation(
+        &self,
+        raffle: &Raffle,
+        participating_teams: &[String],
+        non_participating_teams: &[String]
+    ) -> Result<(Vec<Uuid>, Vec<Uuid>), Box<dyn Error>> {
+        let raffle_result = raffle.result()
+            .ok_or("Raffle result not found")?;
 
-            let revenue = match &snapshot.status() {
-                TeamStatus::Earner { trailing_monthly_revenue } => 
-                    trailing_monthly_revenue.iter()
-                        .map(|r| r.to_string())
-                        .collect::<Vec<_>>()
-                        .join(", "),
-                _ => "N/A".to_string(),
-            };
+        let all_team_ids: Vec<Uuid> = raffle_result.counted().iter()
+            .chain(raffle_result.uncounted().iter())
+            .cloned()
+            .collect();
 
-            let tickets: Vec<_> = raffle.tickets().iter()
-                .filter(|t| t.team_id() == snapshot.id())
+        if !participating_teams.is_empty() {
+            let participating_ids: Vec<Uuid> = participating_teams.iter()
+                .filter_map(|name| self.get_team_id_by_name(name))
                 .collect();
-            
-            let ballot_range = if !tickets.is_empty() {
-                format!("{} - {}", 
-                    tickets.first().unwrap().index(), 
-                    tickets.last().unwrap().index())
-            } else {
-                "N/A".to_string()
-            };
+            let non_participating_ids: Vec<Uuid> = all_team_ids.into_iter()
+                .filter(|id| !participating_ids.contains(id))
+                .collect();
+            Ok((participating_ids, non_participating_ids))
+        } else if !non_participating_teams.is_empty() {
+            let non_participating_ids: Vec<Uuid> = non_participating_teams.iter()
+                .filter_map(|name| self.get_team_id_by_name(name))
+                .collect();
+            let participating_ids: Vec<Uuid> = all_team_ids.into_iter()
+                .filter(|id| !non_participating_ids.contains(id))
+                .collect();
+            Ok((participating_ids, non_participating_ids))
+        } else {
+            Ok((all_team_ids, Vec::new()))
+        }
+    }
 
-            let ticket_count = tickets.len();
+    pub fn print_team_report(&self) -> String {
+        let teams = self.sorted_teams();
 
-            table.push_str(&format!("| {} | {} | {} | {} | {} |\n",
-                team_name, status, revenue, ballot_range, ticket_count));
-        }
+        let mut report = String::from("Team Report:\n\n");
 
-        table.push_str("\n");
-        table
-    }
+        for team in teams {
+            report.push_str(&format!("Name: {}\n", team.name()));
+            report.push_str(&format!("ID: {}\n", team.id()));
+            report.push_str(&format!("Representative: {}\n", team.representative()));
+            for rep in team.representatives() {
+                match &rep.handle {
+                    Some(handle) => report.push_str(&format!("  {}: {} ({})\n", rep.role, rep.name, handle)),
+                    None => report.push_str(&format!("  {}: {}\n", rep.role, rep.name)),
+                }
+            }
+            if !team.contacts().is_empty() {
+                report.push_str("Contacts:\n");
+                for contact in team.contacts() {
+                    report.push_str(&format!("  {}\n", contact));
+                }
+            }
+            report.push_str(&format!("Status: {:?}\n", team.status()));
 
-    pub fn generate_raffle_outcome(&self, report: &mut String, raffle: &Raffle, result: &RaffleResult) {
-        let counted_earners: Vec<_> = result.counted().iter()
-            .filter(|&team_id| raffle.team_snapshots().iter().any(|s| s.id() == *team_id && matches!(s.status(), TeamStatus::Earner { .. })))
-            .collect();
-        let counted_supporters: Vec<_> = result.counted().iter()
-            .filter(|&team_id| raffle.team_snapshots().iter().any(|s| s.id() == *team_id && matches!(s.status(), TeamStatus::Supporter)))
-            .collect();
-    
-        report.push_str(&format!("#### Counted Seats (Total: {})\n\n", result.counted().len()));
-        
-        report.push_str(&format!("##### Earner Seats ({})\n", counted_earners.len()));
-        for team_id in counted_earners {
-            if let Some(snapshot) = raffle.team_snapshots().iter().find(|s| s.id() == *team_id) {
-                let best_score = raffle.tickets().iter()
-                    .filter(|t| t.team_id() == *team_id)
-                    .map(|t| t.score())
-                    .max_by(|a, b| a.partial_cmp(b).unwrap())
-                    .unwrap_or(0.0);
-                report.push_str(&format!("- {} (Best Score: {:.4})\n", snapshot.name(), best_score));
+            if let TeamStatus::Earner { trailing_monthly_revenue } = &team.status() {
+                report.push_str(&format!("Trailing Monthly Revenue: {:?}\n", trailing_monthly_revenue));
             }
-        }
-    
-        report.push_str(&format!("\n##### Supporter Seats ({})\n", counted_supporters.len()));
-        for team_id in counted_supporters {
-            if let Some(snapshot) = raffle.team_snapshots().iter().find(|s| s.id() == *team_id) {
-                let best_score = raffle.tickets().iter()
-                    .filter(|t| t.team_id() == *team_id)
-                    .map(|t| t.score())
-                    .max_by(|a, b| a.partial_cmp(b).unwrap())
-                    .unwrap_or(0.0);
-                report.push_str(&format!("- {} (Best Score: {:.4})\n", snapshot.name(), best_score));
+
+            if team.revenue_history().len() > 1 {
+                report.push_str("Revenue History:\n");
+                for (effective, revenue) in team.revenue_history() {
+                    report.push_str(&format!("  {}: {:?}\n", effective.format("%Y-%m-%d %H:%M UTC"), revenue));
+                }
             }
-        }
-    
-        report.push_str("\n#### Uncounted Seats\n");
-        for team_id in result.uncounted() {
-            if let Some(snapshot) = raffle.team_snapshots().iter().find(|s| s.id() == *team_id) {
-                let best_score = raffle.tickets().iter()
-                    .filter(|t| t.team_id() == *team_id)
-                    .map(|t| t.score())
-                    .max_by(|a, b| a.partial_cmp(b).unwrap())
-                    .unwrap_or(0.0);
-                report.push_str(&format!("- {} (Best Score: {:.4})\n", snapshot.name(), best_score));
+
+            // Add a breakdown of points per epoch
+            report.push_str("Points per Epoch:\n");
+            for epoch in self.epochs_by_start_date() {
+                let epoch_points = self.get_team_points_for_epoch(team.id(), epoch.id()).unwrap_or(0);
+                report.push_str(&format!("  {}: {} points\n", epoch.name(), epoch_points));
             }
+
+            report.push_str("\n");
         }
+
+        report
     }
 
-    pub fn generate_vote_participation_tables(&self, vote: &Vote) -> String {
-        let mut tables = String::new();
+    pub fn print_epoch_state(&self) -> Result<String, Box<dyn Error>> {
+        let epoch = self.get_current_epoch().ok_or("No active epoch")?;
+        let proposals: Vec<&Proposal> = self.get_proposals_for_epoch(epoch.id()).into_iter()
+            .filter(|p| !p.is_draft())
+            .collect();
 
-        match &vote.participation() {
-            VoteParticipation::Formal { counted, uncounted } => {
-                tables.push_str("#### Counted Votes\n");
-                tables.push_str("| Team | Points Credited |\n");
-                tables.push_str("|------|------------------|\n");
-                for &team_id in counted {
-                    if let Some(team) = self.state.current_state().teams().get(&team_id) {
-                        tables.push_str(&format!("| {} | {} |\n", team.name(), self.config.counted_vote_points));
+        let mut report = String::new();
+
+        // Epoch overview
+        report.push_str(&format!("*State of Epoch {}*\n\n", escape_markdown(&epoch.name())));
+        report.push_str("🌍 *Overview*\n");
+        report.push_str(&format!("ID: `{}`\n", epoch.id()));
+        report.push_str(&format!("Start Date: `{}`\n", epoch.start_date().format("%Y-%m-%d %H:%M:%S UTC")));
+        report.push_str(&format!("End Date: `{}`\n", epoch.end_date().format("%Y-%m-%d %H:%M:%S UTC")));
+        report.push_str(&format!("Status: `{:?}`\n", epoch.status()));
+
+        if let Some(reward) = epoch.reward() {
+            report.push_str(&format!("Epoch Reward: `{} {}`\n", reward.amount(), escape_markdown(reward.token())));
+        } else {
+            report.push_str("Epoch Reward: `Not set`\n");
+        }
+
+        let committed = self.committed_amounts(epoch.id());
+        let mut caps: Vec<_> = epoch.budget_cap().iter().collect();
+        caps.sort_by(|a, b| a.0.cmp(b.0));
+        for (token, cap) in caps {
+            report.push_str(&format!("Budget: `{}/{} {} committed`\n",
+                committed.get(token).unwrap_or(&0.0), cap, escape_markdown(token)));
+        }
+
+        report.push_str("\n");
+
+        // Proposal counts
+        let mut open_proposals = Vec::new();
+        let mut approved_count = 0;
+        let mut rejected_count = 0;
+        let mut retracted_count = 0;
+
+        for proposal in &proposals {
+            match proposal.resolution() {
+                Some(Resolution::Approved) => approved_count += 1,
+                Some(Resolution::Rejected) => rejected_count += 1,
+                Some(Resolution::Retracted) => retracted_count += 1,
+                _ => {
+                    if proposal.is_actionable() {
+                        open_proposals.push(proposal);
                     }
                 }
+            }
+        }
 
-                tables.push_str("\n#### Uncounted Votes\n");
-                tables.push_str("| Team | Points Credited |\n");
-                tables.push_str("|------|------------------|\n");
-                for &team_id in uncounted {
-                    if let Some(team) = self.state.current_state().teams().get(&team_id) {
-                        tables.push_str(&format!("| {} | {} |\n", team.name(), self.config.uncounted_vote_points));
-                    }
+        report.push_str("📊 *Proposals*\n");
+        report.push_str(&format!("Total: `{}`\n", proposals.len()));
+        report.push_str(&format!("Open: `{}`\n", open_proposals.len()));
+        report.push_str(&format!("Approved: `{}`\n", approved_count));
+        report.push_str(&format!("Rejected: `{}`\n", rejected_count));
+        report.push_str(&format!("Retracted: `{}`\n", retracted_count));
+
+        report.push_str("\n");
+
+        // Open proposals
+        if !open_proposals.is_empty() {
+            report.push_str("📬 *Open proposals*\n\n");
+        
+            for proposal in open_proposals {
+                report.push_str(&format!("*{}*\n", escape_markdown(proposal.title())));
+                if let Some(url) = proposal.url() {
+                    report.push_str(&format!("🔗 {}\n", escape_markdown(url)));
                 }
-            },
-            VoteParticipation::Informal(participants) => {
-                tables.push_str("#### Participants\n");
-                tables.push_str("| Team | Points Credited |\n");
-                tables.push_str("|------|------------------|\n");
-                for &team_id in participants {
-                    if let Some(team) = self.state.current_state().teams().get(&team_id) {
-                        tables.push_str(&format!("| {} | 0 |\n", team.name()));
+                if let Some(details) = proposal.budget_request_details() {
+                    if let (Some(start), Some(end)) = (details.start_date(), details.end_date()) {
+                        report.push_str(&format!("📆 {} \\- {}\n", 
+                            escape_markdown(&start.format("%b %d").to_string()),
+                            escape_markdown(&end.format("%b %d").to_string())
+                        ));
+                    }
+                    if !details.request_amounts().is_empty() {
+                        let amounts: Vec<String> = details.request_amounts().iter()
+                            .map(|(token, amount)| format!("{} {}", 
+                                escape_markdown(&amount.to_string()), 
+                                escape_markdown(token)
+                            ))
+                            .collect();
+                        report.push_str(&format!("💰 {}\n", amounts.join(", ")));
                     }
                 }
-            },
+                let days_open = self.days_open(proposal);
+                match self.days_overdue(proposal) {
+                    Some(days_overdue) => report.push_str(&format!("⏳ _{} days open_ ⚠️ *Overdue* by {} days\n\n",
+                        escape_markdown(&days_open.to_string()),
+                        escape_markdown(&days_overdue.to_string())
+                    )),
+                    None => report.push_str(&format!("⏳ _{} days open_\n\n", escape_markdown(&days_open.to_string()))),
+                }
+            }
         }
 
-        tables
+        Ok(report)
     }
 
-    pub fn calculate_days_between(&self, start: NaiveDate, end: NaiveDate) -> i64 {
-        (end - start).num_days()
+    /// Closed raffle-gated votes of the epoch where the team held a seat, oldest first,
+    /// each paired with whether the team voted
+    fn eligible_vote_history(&self, team_id: Uuid, epoch_id: Uuid) -> Vec<bool> {
+        let mut history: Vec<(DateTime<Utc>, bool)> = self.state.votes().values()
+            .filter(|vote| vote.epoch_id() == epoch_id && vote.is_closed())
+            .filter_map(|vote| {
+                let result = vote.raffle_id()
+                    .and_then(|raffle_id| self.state.get_raffle(&raffle_id))
+                    .and_then(|raffle| raffle.result())?;
+                if !result.counted().contains(&team_id) && !result.uncounted().contains(&team_id) {
+                    return None;
+                }
+                let participated = match vote.participation() {
+                    VoteParticipation::Formal { counted, uncounted } => counted.contains(&team_id) || uncounted.contains(&team_id),
+                    VoteParticipation::Informal(_) => false,
+                };
+                Some((vote.opened_at(), participated))
+            })
+            .collect();
+        history.sort_by_key(|(opened_at, _)| *opened_at);
+        history.into_iter().map(|(_, participated)| participated).collect()
     }
 
-    pub fn get_current_or_specified_epoch(&self, epoch_name: Option<&str>) -> Result<(&Epoch, Uuid), &'static str> {
-        match epoch_name {
-            Some(name) => {
-                let (id, epoch) = self.state.epochs().iter()
-                    .find(|(_, e)| e.name() == name)
-                    .ok_or("Specified epoch not found")?;
-                Ok((epoch, *id))
-            },
-            None => {
-                let current_epoch_id = self.state.current_epoch().ok_or("No active epoch")?;
-                let epoch = self.state.epochs().get(&current_epoch_id).ok_or("Current epoch not found")?;
-                Ok((epoch, current_epoch_id))
-            }
+    /// Share of the epoch's closed votes the team was raffle-eligible for that it voted in; 0.0 when it was eligible for none
+    pub fn team_participation_rate(&self, team_id: Uuid, epoch_id: Uuid) -> f64 {
+        let history = self.eligible_vote_history(team_id, epoch_id);
+        if history.is_empty() {
+            return 0.0;
         }
+        history.iter().filter(|&&participated| participated).count() as f64 / history.len() as f64
     }
 
-    pub fn generate_point_report(&self, epoch_name: Option<&str>) -> Result<String, &'static str> {
-        let (epoch, epoch_id) = self.get_current_or_specified_epoch(epoch_name)?;
-        self.generate_point_report_for_epoch(epoch_id)
+    /// Consecutive eligible votes the team voted in, counting back from the most recent
+    pub fn team_vote_streak(&self, team_id: Uuid, epoch_id: Uuid) -> usize {
+        self.eligible_vote_history(team_id, epoch_id).iter().rev()
+            .take_while(|&&participated| participated)
+            .count()
     }
 
-    pub fn generate_point_report_for_epoch(&self, epoch_id: Uuid) -> Result<String, &'static str> {
-        let epoch = self.state.epochs().get(&epoch_id).ok_or("Epoch not found")?;
-        let mut report = String::new();
+    /// Dangling references between votes, raffles, epochs, proposals and teams, sorted by kind.
+    /// Snapshots in historical raffles may name teams that were never added, so those aren't checked.
+    pub fn validate_state(&self) -> Vec<IntegrityError> {
+        let proposals = self.state.proposals();
+        let mut errors = Vec::new();
 
-        for (team_id, team) in self.state.current_state().teams() {
-            let mut team_report = format!("{}, ", team.name());
-            let mut total_points = 0;
-            let mut allocations = Vec::new();
+        for vote in self.state.votes().values() {
+            if !proposals.contains_key(&vote.proposal_id()) {
+                errors.push(IntegrityError::VoteMissingProposal { vote_id: vote.id(), proposal_id: vote.proposal_id() });
+            }
+        }
 
-            for proposal_id in epoch.associated_proposals() {
-                if let Some(proposal) = self.state.get_proposal(&proposal_id) {
-                    if let Some(vote) = self.state.votes().values().find(|v| v.proposal_id() == *proposal_id) {
-                        let (participation_type, points) = match (vote.vote_type(), vote.participation()) {
-                            (VoteType::Formal { counted_points, uncounted_points, .. }, VoteParticipation::Formal { counted, uncounted }) => {
-                                if counted.contains(team_id) {
-                                    ("Counted", *counted_points)
-                                } else if uncounted.contains(team_id) {
-                                    ("Uncounted", *uncounted_points)
-                                } else {
-                                    continue;
-                                }
-                            },
-                            (VoteType::Informal, VoteParticipation::Informal(participants)) => {
-                                if participants.contains(team_id) {
-                                    ("Informal", 0)
-                                } else {
-                                    continue;
-                                }
-                            },
-                            _ => continue,
-                        };
-
-                        total_points += points;
-                        allocations.push(format!("{}: {} voter, {} points", 
-                            proposal.title(), participation_type, points));
+        for raffle in self.state.raffles().values() {
+            let proposal_id = raffle.config().proposal_id();
+            if !proposals.contains_key(&proposal_id) {
+                errors.push(IntegrityError::RaffleMissingProposal { raffle_id: raffle.id(), proposal_id });
+            }
+            if !raffle.is_historical() {
+                for snapshot in raffle.team_snapshots() {
+                    if !self.state.current_state().teams().contains_key(&snapshot.id()) {
+                        errors.push(IntegrityError::RaffleUnknownTeam { raffle_id: raffle.id(), team_id: snapshot.id() });
                     }
                 }
             }
+        }
 
-            team_report.push_str(&format!("{} points\n", total_points));
-            for allocation in allocations {
-                team_report.push_str(&format!("{}\n", allocation));
+        for epoch in self.state.epochs().values() {
+            for proposal_id in epoch.associated_proposals() {
+                if !proposals.contains_key(proposal_id) {
+                    errors.push(IntegrityError::EpochMissingProposal { epoch_id: epoch.id(), proposal_id: *proposal_id });
+                }
             }
-            team_report.push('\n');
+        }
 
-            report.push_str(&team_report);
+        for proposal in proposals.values() {
+            if self.state.get_epoch(&proposal.epoch_id()).is_none() {
+                errors.push(IntegrityError::ProposalMissingEpoch { proposal_id: proposal.id(), epoch_id: proposal.epoch_id() });
+            }
+            for dependency_id in proposal.depends_on() {
+                if !proposals.contains_key(dependency_id) {
+                    errors.push(IntegrityError::ProposalMissingDependency { proposal_id: proposal.id(), dependency_id: *dependency_id });
+                }
+            }
         }
 
-        Ok(report)
+        errors.sort();
+        errors
     }
 
-    pub fn get_team_points_history(&self, team_id: Uuid) -> Result<Vec<(Uuid, u32)>, &'static str> {
-        self.state.epochs().iter()
-            .map(|(&epoch_id, _)| {
-                self.get_team_points_for_epoch(team_id, epoch_id)
-                    .map(|points| (epoch_id, points))
-            })
-            .collect()
+    pub fn print_team_vote_participation(&self, team_name: &str, epoch_name: Option<&str>) -> Result<String, Box<dyn Error>> {
+        let team_id = self.require_team_id(team_name)?;
+    
+        let epoch = if let Some(name) = epoch_name {
+            self.state.epochs().values()
+                .find(|e| e.name() == name)
+                .ok_or_else(|| NotFoundError { kind: "Epoch", name: name.to_string() })?
+        } else {
+            self.get_current_epoch()
+                .ok_or("No active epoch and no epoch specified")?
+        };
+    
+        let mut report = format!("Vote Participation Report for Team: {}\n", team_name);
+        report.push_str(&format!("Epoch: {} ({})\n", epoch.name(), epoch.id()));
+        let eligible_votes = self.eligible_vote_history(team_id, epoch.id()).len();
+        if eligible_votes > 0 {
+            report.push_str(&format!(
+                "Participation Rate: {:.0}% of {} eligible votes\n",
+                self.team_participation_rate(team_id, epoch.id()) * 100.0,
+                eligible_votes
+            ));
+        } else {
+            report.push_str("Participation Rate: N/A (no eligible votes)\n");
+        }
+        report.push_str(&format!("Current Streak: {}\n\n", self.team_vote_streak(team_id, epoch.id())));
+
+        let entries = self.team_vote_participation(team_id, epoch)?;
+        let total_points: u32 = entries.iter().map(|entry| entry.points).sum();
+        report.push_str(&format!("Total Points Earned: {}\n\n", total_points));
+
+        for entry in &entries {
+            report.push_str(&format!(
+                "Vote ID: {}\n\
+                Proposal: {}\n\
+                Type: {}\n\
+                Participation: {}\n\
+                Result: {}\n\
+                Points Earned: {}\n\n",
+                entry.vote_id, entry.proposal, entry.vote_type, entry.participation, entry.result, entry.points
+            ));
+        }
+
+        if entries.is_empty() {
+            report.push_str("This team has not participated in any votes during this epoch.\n");
+        }
+
+        Ok(report)
     }
 
-    pub fn get_team_points_for_epoch(&self, team_id: Uuid, epoch_id: Uuid) -> Result<u32, &'static str> {
-        let epoch = self.state.epochs().get(&epoch_id).ok_or("Epoch not found")?;
-        let mut total_points = 0;
+    /// The epoch's votes the team took part in, most recent first
+    pub fn team_vote_participation(&self, team_id: Uuid, epoch: &Epoch) -> Result<Vec<VoteParticipationEntry>, Box<dyn Error>> {
+        let mut entries = Vec::new();
 
-        for proposal_id in epoch.associated_proposals() {
-            if let Some(vote) = self.state.votes().values().find(|v| v.proposal_id() == *proposal_id) {
-                if let (VoteType::Formal { counted_points, uncounted_points, .. }, VoteParticipation::Formal { counted, uncounted }) = (vote.vote_type(), vote.participation()) {
+        for vote_id in epoch.associated_proposals().iter()
+            .filter_map(|proposal_id| self.state.votes().values()
+                .find(|v| v.proposal_id() == *proposal_id)
+                .map(|v| v.id())) 
+        {
+            let vote = self.state.get_vote(&vote_id).expect("Could not get Vote");
+            let (participation_status, points) = match (vote.vote_type(), vote.participation()) {
+                (VoteType::Formal { counted_points, uncounted_points, .. } | VoteType::Weighted { counted_points, uncounted_points, .. } | VoteType::MultiOption { counted_points, uncounted_points, .. }, VoteParticipation::Formal { counted, uncounted }) => {
                     if counted.contains(&team_id) {
-                        total_points += counted_points;
+                        (Some("Counted"), *counted_points)
                     } else if uncounted.contains(&team_id) {
-                        total_points += uncounted_points;
+                        (Some("Uncounted"), *uncounted_points)
+                    } else {
+                        (None, 0)
                     }
-                }
+                },
+                (VoteType::Informal { .. }, VoteParticipation::Informal(participants)) => {
+                    if participants.contains(&team_id) {
+                        (Some("N/A (Informal)"), 0)
+                    } else {
+                        (None, 0)
+                    }
+                },
+                _ => (None, 0),
+            };
+    
+            if let Some(status) = participation_status {
+                let proposal = self.state.proposals().get(&vote.proposal_id())
+                    .ok_or_else(|| format!("Proposal not found for vote: {}", vote_id))?;
+    
+                let vote_type = match vote.vote_type() {
+                    VoteType::Formal { .. } => "Formal",
+                    VoteType::Weighted { .. } => "Weighted",
+                    VoteType::Informal { .. } => "Informal",
+                    VoteType::MultiOption { .. } => "Multi-option",
+                };
+    
+                let result = match vote.result() {
+                    Some(VoteResult::Formal { passed, .. } | VoteResult::Weighted { passed, .. }) => if *passed { "Passed" } else { "Failed" },
+                    Some(VoteResult::Informal { .. }) => "N/A (Informal)",
+                    Some(VoteResult::MultiOption { .. }) => "Decided",
+                    None => "Pending",
+                };
+    
+                entries.push(VoteParticipationEntry {
+                    vote_id,
+                    proposal: proposal.title().to_string(),
+                    opened_at: vote.opened_at(),
+                    vote_type: vote_type.to_string(),
+                    participation: status.to_string(),
+                    result: result.to_string(),
+                    points,
+                });
             }
         }
 
-        Ok(total_points)
+        entries.sort_by(|a, b| b.opened_at.cmp(&a.opened_at));
+        Ok(entries)
     }
 
-    pub fn close_epoch(&mut self, epoch_name: Option<&str>) -> Result<(), Box<dyn Error>> {
-        let epoch_id = match epoch_name {
+    pub fn days_open(&self, proposal: &Proposal) -> i64 {
+        let announced_date = proposal.announced_at()
+            .unwrap_or_else(|| Utc::now().date_naive());
+        Utc::now().date_naive().signed_duration_since(announced_date).num_days()
+    }
+
+    /// The proposal's explicit deadline, or `default_decision_sla_days` after its announcement
+    pub fn decision_deadline(&self, proposal: &Proposal) -> Option<NaiveDate> {
+        proposal.decision_deadline().or_else(|| proposal.announced_at()
+            .map(|announced| announced + chrono::Duration::days(self.config.default_decision_sla_days as i64)))
+    }
+
+    /// Days an actionable proposal is past its decision deadline, or `None` if it is not overdue
+    pub fn days_overdue(&self, proposal: &Proposal) -> Option<i64> {
+        if !proposal.is_actionable() {
+            return None;
+        }
+        let deadline = self.decision_deadline(proposal)?;
+        let days = Utc::now().date_naive().signed_duration_since(deadline).num_days();
+        (days > 0).then_some(days)
+    }
+
+    /// Actionable proposals of the epoch past their decision deadline, most overdue first
+    pub fn overdue_proposals(&self, epoch_id: Uuid) -> Vec<&Proposal> {
+        let mut overdue: Vec<(&Proposal, i64)> = self.get_proposals_for_epoch(epoch_id).into_iter()
+            .filter_map(|proposal| self.days_overdue(proposal).map(|days| (proposal, days)))
+            .collect();
+        overdue.sort_by(|(a, a_days), (b, b_days)| b_days.cmp(a_days).then_with(|| a.title().cmp(b.title())));
+        overdue.into_iter().map(|(proposal, _)| proposal).collect()
+    }
+
+    pub fn format_overdue_proposals(&self, epoch_name: Option<&str>) -> Result<String, Box<dyn Error>> {
+        let epoch = match epoch_name {
             Some(name) => self.get_epoch_id_by_name(name)
-                .ok_or_else(|| format!("Epoch not found: {}", name))?,
-            None => self.state.current_epoch()
-                .ok_or("No active epoch")?
+                .and_then(|id| self.state.get_epoch(&id))
+                .ok_or_else(|| NotFoundError { kind: "Epoch", name: name.to_string() })?,
+            None => self.get_current_epoch().ok_or("No active epoch")?,
         };
-    
-        // Check for actionable proposals
-        let actionable_proposals = self.get_proposals_for_epoch(epoch_id)
-            .iter()
-            .filter(|p| p.is_actionable())
-            .count();
-    
-        if actionable_proposals > 0 {
-            return Err(format!("Cannot close epoch: {} actionable proposals remaining", actionable_proposals).into());
+
+        let overdue = self.overdue_proposals(epoch.id());
+        if overdue.is_empty() {
+            return Ok(format!("No overdue proposals in epoch {}\n", epoch.name()));
         }
-    
-        let total_points = self.get_total_points_for_epoch(epoch_id);
-        let mut team_rewards = HashMap::new();
-    
-        // Calculate rewards
-        {
-            let epoch = self.state.get_epoch(&epoch_id)
-                .ok_or("Epoch not found")?;
 
-            if epoch.is_closed() {
-                return Err("Epoch is already closed".into());
-            }
+        let mut output = format!("Overdue proposals in epoch {} ({}):\n", epoch.name(), overdue.len());
+        for proposal in overdue {
+            output.push_str(&format!("- {}: {} days overdue (deadline {})\n",
+                proposal.title(),
+                self.days_overdue(proposal).unwrap_or(0),
+                self.decision_deadline(proposal).map_or_else(String::new, |d| d.to_string()),
+            ));
+        }
+        Ok(output)
+    }
 
-            if let Some(reward) = epoch.reward() {
-                if total_points == 0 {
-                    return Err("No points earned in this epoch".into());
-                }
+    /// Replays the epoch's closed formal votes under a hypothetical threshold, returning
+    /// (proposal title, actual passed, simulated passed) ordered by title. Quorums are unchanged.
+    /// Historical imports and votes without recorded counts are left out.
+    pub fn simulate_threshold(&self, epoch_id: Uuid, threshold: f64) -> Vec<(String, bool, bool)> {
+        self.threshold_simulation(epoch_id, threshold).0
+    }
 
-                for team_id in self.state.current_state().teams().keys() {
-                    let team_points = self.calculate_team_points_for_epoch(*team_id, epoch_id);
-                    let percentage = team_points as f64 / total_points as f64 * 100.0;
-                    let amount = reward.amount() * (percentage / 100.0);
+    /// Simulated outcomes, plus the titles of proposals whose votes could not be replayed
+    fn threshold_simulation(&self, epoch_id: Uuid, threshold: f64) -> (Vec<(String, bool, bool)>, Vec<String>) {
+        let mut outcomes = Vec::new();
+        let mut skipped = Vec::new();
 
-                    match TeamReward::new(percentage, amount) {
-                        Ok(team_reward) => {
-                            team_rewards.insert(*team_id, team_reward);
-                        },
-                        Err(e) => return Err(format!("Failed to create team reward: {}", e).into()),
-                    }
-                }
+        for proposal in self.get_proposals_for_epoch(epoch_id) {
+            let Some(vote) = self.state.votes().values().find(|v| v.proposal_id() == proposal.id()) else { continue };
+            let Some(VoteResult::Formal { counted, passed, .. }) = vote.result() else { continue };
+            if !matches!(vote.vote_type(), VoteType::Formal { .. }) {
+                continue;
             }
-        }
-    
-         // Update epoch
-        {
-            let epoch = self.state.get_epoch_mut(&epoch_id)
-                .ok_or("Epoch not found")?;
 
-            epoch.set_status(EpochStatus::Closed);
-            for (team_id, team_reward) in team_rewards {
-                epoch.set_team_reward(team_id, team_reward.percentage(), team_reward.amount())?;
+            if vote.is_historical() || counted.total_cast() == 0 {
+                skipped.push(proposal.title().to_string());
+                continue;
+            }
+            match vote.simulate_outcome(threshold) {
+                Ok(simulated) => outcomes.push((proposal.title().to_string(), *passed, simulated)),
+                Err(_) => skipped.push(proposal.title().to_string()),
             }
         }
 
-        // Clear current_epoch if this was the active epoch
-        if self.state.current_epoch() == Some(epoch_id) {
-            self.state.set_current_epoch(None);
+        outcomes.sort_by(|a, b| a.0.cmp(&b.0));
+        skipped.sort();
+        (outcomes, skipped)
+    }
+
+    pub fn format_threshold_simulation(&self, epoch_name: Option<&str>, threshold: f64) -> Result<String, Box<dyn Error>> {
+        if !(0.0..=1.0).contains(&threshold) {
+            return Err("Threshold must be between 0 and 1".into());
         }
+        let epoch = match epoch_name {
+            Some(name) => self.get_epoch_id_by_name(name)
+                .and_then(|id| self.state.get_epoch(&id))
+                .ok_or_else(|| NotFoundError { kind: "Epoch", name: name.to_string() })?,
+            None => self.get_current_epoch().ok_or("No active epoch")?,
+        };
 
-        self.save_state()?;
+        let (outcomes, skipped) = self.threshold_simulation(epoch.id(), threshold);
+        let flipped = outcomes.iter().filter(|(_, actual, simulated)| actual != simulated).count();
+        let outcome = |passed: bool| if passed { "Passed" } else { "Failed" };
 
-        Ok(())
+        let mut output = format!(
+            "Threshold {:.2} in epoch {}: {} of {} formal votes would flip\n",
+            threshold, epoch.name(), flipped, outcomes.len()
+        );
+        for (title, actual, simulated) in &outcomes {
+            let marker = if actual != simulated { " (flipped)" } else { "" };
+            output.push_str(&format!("- {}: {} -> {}{}\n", title, outcome(*actual), outcome(*simulated), marker));
+        }
+        if !skipped.is_empty() {
+            output.push_str(&format!("Skipped without recorded counts: {}\n", skipped.join(", ")));
+        }
+        Ok(output)
     }
 
-    pub fn get_total_points_for_epoch(&self, epoch_id: Uuid) -> u32 {
-        self.state.current_state().teams().keys()
-            .map(|team_id| self.calculate_team_points_for_epoch(*team_id, epoch_id))
-            .sum()
+    pub fn prepare_raffle(&mut self, proposal_name: &str, excluded_teams: Option<Vec<String>>, app_config: &AppConfig) -> Result<(Uuid, Vec<RaffleTicket>), Box<dyn Error>> {
+        self.prepare_raffle_with_options(proposal_name, excluded_teams, true, app_config)
     }
 
-    pub fn calculate_team_points_for_epoch(&self, team_id: Uuid, epoch_id: Uuid) -> u32 {
-        let epoch = match self.state.epochs().get(&epoch_id) {
-            Some(e) => e,
-            None => return 0,
-        };
+    /// Like `prepare_raffle`; with `exclude_inactive` false, inactive teams stay in the snapshot as excluded
+    pub fn prepare_raffle_with_options(
+        &mut self,
+        proposal_name: &str,
+        excluded_teams: Option<Vec<String>>,
+        exclude_inactive: bool,
+        app_config: &AppConfig,
+    ) -> Result<(Uuid, Vec<RaffleTicket>), Box<dyn Error>> {
+        let raffle = self.build_raffle(proposal_name, excluded_teams, exclude_inactive, app_config)?;
+        let tickets = raffle.tickets().to_vec();
+        let raffle_id = self.state.add_raffle(&raffle);
+        self.save_state()?;
 
-        epoch.associated_proposals().iter()
-            .filter_map(|proposal_id| self.state.votes().values().find(|v| v.proposal_id() == *proposal_id))
-            .map(|vote| match (vote.vote_type(), vote.participation()) {
-                (VoteType::Formal { counted_points, uncounted_points, .. }, VoteParticipation::Formal { counted, uncounted }) => {
-                    if counted.contains(&team_id) {
-                        *counted_points
-                    } else if uncounted.contains(&team_id) {
-                        *uncounted_points
-                    } else {
-                        0
-                    }
-                },
-                _ => 0,
-            })
-            .sum()
+        Ok((raffle_id, tickets))
     }
 
-    pub fn generate_end_of_epoch_report(&self, epoch_name: &str) -> Result<(), Box<dyn Error>> {
-        let epoch = self.state.epochs().values()
-            .find(|e| e.name() == epoch_name)
-            .ok_or_else(|| format!("Epoch not found: {}", epoch_name))?;
+    /// Teams that a raffle prepared now would include, with their status and ticket count.
+    /// Nothing is stored, so the roster can be checked before `prepare_raffle`.
+    pub fn preview_raffle_eligibility(&self, proposal_name: &str, excluded_teams: Option<Vec<String>>) -> Result<Vec<(String, TeamStatus, u64)>, Box<dyn Error>> {
+        let raffle = self.build_raffle(proposal_name, excluded_teams, true, &self.config)?;
 
-        if !epoch.is_closed() {
-            return Err("Cannot generate report: Epoch is not closed".into());
+        let mut ticket_counts: HashMap<Uuid, u64> = HashMap::new();
+        for ticket in raffle.tickets() {
+            *ticket_counts.entry(ticket.team_id()).or_insert(0) += 1;
         }
 
-        let mut report = String::new();
+        Ok(raffle.team_snapshots().iter()
+            .filter(|snapshot| matches!(snapshot.raffle_status(), RaffleParticipationStatus::Included))
+            .map(|snapshot| (
+                snapshot.name().to_string(),
+                snapshot.status().clone(),
+                ticket_counts.get(&snapshot.id()).copied().unwrap_or(0),
+            ))
+            .collect())
+    }
 
-        // Generate epoch summary
-        report.push_str(&self.generate_epoch_summary(epoch)?);
+    fn build_raffle(
+        &self,
+        proposal_name: &str,
+        excluded_teams: Option<Vec<String>>,
+        exclude_inactive: bool,
+        app_config: &AppConfig,
+    ) -> Result<Raffle, Box<dyn Error>> {
+        let proposal_id = self.require_proposal_id(proposal_name)?;
+        if self.state.get_proposal(&proposal_id).is_some_and(|p| p.is_draft()) {
+            return Err("Cannot create a raffle for a draft proposal; publish it first".into());
+        }
+        let epoch_id = self.state.current_epoch()
+            .ok_or("No active epoch")?;
 
-        // Generate proposal tables and individual reports
-        report.push_str(&self.generate_proposal_tables(epoch)?);
+        let excluded_team_ids = excluded_teams.map(|names| {
+            names.into_iter()
+                .filter_map(|name| self.get_team_id_by_name(&name))
+                .collect::<Vec<Uuid>>()
+        }).unwrap_or_else(Vec::new);
 
-        // Generate team summary
-        report.push_str(&self.generate_team_summary(epoch)?);
+        let mut raffle_config = RaffleConfig::new(
+            proposal_id,
+            epoch_id,
+            app_config.default_total_counted_seats,
+            app_config.default_max_earner_seats,
+            Some(0),
+            Some(0),
+            Some(String::new()),
+            Some(excluded_team_ids),
+            None,
+            None,
+            false
+        );
+        raffle_config.set_exclude_inactive(exclude_inactive);
+        raffle_config.set_tie_break(app_config.raffle_tie_break);
+        raffle_config.set_revenue_tiers(app_config.revenue_tiers.clone());
 
-        // Save the report
-        let file_name = format!("{}-epoch_report.md", Utc::now().format("%Y%m%d"));
-        let sanitized_epoch_name = FileSystem::sanitize_filename(epoch_name);
-        let report_path = PathBuf::from(&self.config.state_file)
-            .parent()
-            .unwrap()
-            .join("reports")
-            .join(sanitized_epoch_name)
-            .join(file_name);
+        Ok(Raffle::new(raffle_config, self.state.current_state().teams())?)
+    }
 
-        fs::create_dir_all(report_path.parent().unwrap())?;
-        fs::write(&report_path, report)?;
+    pub async fn import_historical_raffle(
+        &mut self,
+        proposal_name: &str,
+        initiation_block: u64,
+        randomness_block: u64,
+        team_order: Option<Vec<String>>,
+        excluded_teams: Option<Vec<String>>,
+        total_counted_seats: Option<usize>,
+        max_earner_seats: Option<usize>,
+        randomness_timestamp: Option<DateTime<Utc>>,
+        randomness: Option<String>,
+    ) -> Result<(Uuid, Raffle), Box<dyn Error>> {
+        let proposal_id = self.require_proposal_id(proposal_name)?;
+    
+        let epoch_id = self.state.current_epoch()
+            .ok_or("No active epoch")?;
+    
+        // Supplied randomness lets old epochs be backfilled against a node that has pruned the block
+        let (randomness, randomness_timestamp) = match randomness {
+            Some(randomness) => (parse_block_randomness(&randomness)?, randomness_timestamp),
+            None => {
+                let randomness = self.ethereum_service.get_randomness(randomness_block).await?;
+                let randomness_timestamp = match randomness_timestamp {
+                    Some(timestamp) => Some(timestamp),
+                    None => self.ethereum_service.get_block_timestamp(randomness_block).await.ok(),
+                };
+                (randomness, randomness_timestamp)
+            },
+        };
+    
+        let custom_team_order = team_order.map(|order| {
+            order.into_iter()
+                .filter_map(|name| self.get_team_id_by_name(&name))
+                .collect::<Vec<Uuid>>()
+        });
+    
+        let excluded_team_ids = excluded_teams.map(|names| {
+            names.into_iter()
+                .filter_map(|name| self.get_team_id_by_name(&name))
+                .collect::<Vec<Uuid>>()
+        }).unwrap_or_else(Vec::new);
+    
+        let total_counted_seats = total_counted_seats.unwrap_or(self.config.default_total_counted_seats);
+        let max_earner_seats = max_earner_seats.unwrap_or(self.config.default_max_earner_seats);
+    
+        if max_earner_seats > total_counted_seats {
+            return Err("max_earner_seats cannot be greater than total_counted_seats".into());
+        }
 
-        println!("End of Epoch Report generated: {:?}", report_path);
+        let mut raffle_config = RaffleConfig::new(
+            proposal_id,
+            epoch_id,
+            total_counted_seats,
+            max_earner_seats,
+            Some(initiation_block),
+            Some(randomness_block),
+            Some(randomness),
+            Some(excluded_team_ids),
+            None,
+            custom_team_order,
+            true
+        );
+        raffle_config.set_randomness_timestamp(randomness_timestamp);
+        raffle_config.set_tie_break(self.config.raffle_tie_break);
+        raffle_config.set_revenue_tiers(self.config.revenue_tiers.clone());
+    
+        let mut raffle = Raffle::new(raffle_config, self.state.current_state().teams())?;
+        raffle.generate_ticket_scores()?;
+        raffle.select_deciding_teams();
+    
+        let raffle_id = self.state.add_raffle(&raffle);
+        self.save_state()?;
+    
+        Ok((raffle_id, raffle))
+    }
 
-        Ok(())
+    /// Creates and scores a raffle from a caller-supplied seed instead of waiting for a block.
+    /// Meant for testing and dry runs; reports mark it as seed-based.
+    pub fn create_seeded_raffle(&mut self, proposal_name: &str, seed: &str) -> Result<(Uuid, Raffle), Box<dyn Error>> {
+        if seed.trim().is_empty() {
+            return Err("Seed cannot be empty".into());
+        }
+
+        let proposal_id = self.require_proposal_id(proposal_name)?;
+        let epoch_id = self.state.current_epoch()
+            .ok_or("No active epoch")?;
+
+        let mut raffle_config = RaffleConfig::with_seed(
+            proposal_id,
+            epoch_id,
+            self.config.default_total_counted_seats,
+            self.config.default_max_earner_seats,
+            seed.to_string(),
+            None,
+        );
+        raffle_config.set_tie_break(self.config.raffle_tie_break);
+        raffle_config.set_revenue_tiers(self.config.revenue_tiers.clone());
+
+        let mut raffle = Raffle::new(raffle_config, self.state.current_state().teams())?;
+        raffle.generate_ticket_scores()?;
+        raffle.select_deciding_teams();
+
+        let raffle_id = self.state.add_raffle(&raffle);
+        self.save_state()?;
+
+        Ok((raffle_id, raffle))
     }
 
-    pub fn generate_epoch_summary(&self, epoch: &Epoch) -> Result<String, Box<dyn Error>> {
-        let proposals = self.get_proposals_for_epoch(epoch.id());
-        let approved = proposals.iter().filter(|p| matches!(p.resolution(), Some(Resolution::Approved))).count();
-        let rejected = proposals.iter().filter(|p| matches!(p.resolution(), Some(Resolution::Rejected))).count();
-        let retracted = proposals.iter().filter(|p| matches!(p.resolution(), Some(Resolution::Retracted))).count();
+    /// Share of simulated raffles, drawn over the current teams with the default seat config,
+    /// in which the team lands a counted seat. Nothing is stored.
+    pub fn estimate_raffle_odds(&self, team_name: &str, excluded_teams: Option<Vec<String>>) -> Result<f64, Box<dyn Error>> {
+        let team_id = self.require_team_id(team_name)?;
 
-        let summary = format!(
-            "# End of Epoch Report: {}\n\n\
-            ## Epoch Summary\n\
-            - **Period**: {} to {}\n\
-            - **Total Proposals**: {}\n\
-            - **Approved Proposals**: {}\n\
-            - **Rejected Proposals**: {}\n\
-            - **Retracted Proposals**: {}\n\
-            - **Total Reward**: {}\n\n",
-            epoch.name(),
-            epoch.start_date().format("%Y-%m-%d"),
-            epoch.end_date().format("%Y-%m-%d"),
-            proposals.len(),
-            approved,
-            rejected,
-            retracted,
-            epoch.reward().map_or("N/A".to_string(), |r| format!("{} {}", r.amount(), r.token())),
+        let excluded_team_ids: Vec<Uuid> = excluded_teams.unwrap_or_default().into_iter()
+            .filter_map(|name| self.get_team_id_by_name(&name))
+            .collect();
+        if excluded_team_ids.contains(&team_id) {
+            return Ok(0.0);
+        }
+
+        let mut raffle_config = RaffleConfig::with_seed(
+            Uuid::nil(),
+            Uuid::nil(),
+            self.config.default_total_counted_seats,
+            self.config.default_max_earner_seats,
+            String::new(),
+            Some(excluded_team_ids),
         );
+        raffle_config.set_revenue_tiers(self.config.revenue_tiers.clone());
+        let template = Raffle::new(raffle_config, self.state.current_state().teams())?;
+
+        let mut seated = 0;
+        for run in 0..RAFFLE_ODDS_SIMULATIONS {
+            let mut raffle = template.clone();
+            raffle.config_mut().set_block_randomness(format!("odds_{}", run));
+            raffle.generate_ticket_scores()?;
+            raffle.select_deciding_teams();
+            if raffle.result().is_some_and(|result| result.counted().contains(&team_id)) {
+                seated += 1;
+            }
+        }
 
-        Ok(summary)
+        Ok(seated as f64 / RAFFLE_ODDS_SIMULATIONS as f64)
     }
 
-    pub fn generate_proposal_tables(&self, epoch: &Epoch) -> Result<String, Box<dyn Error>> {
-        let mut tables = String::new();
-        let proposals = self.get_proposals_for_epoch(epoch.id());
+    pub async fn finalize_raffle(&mut self, raffle_id: Uuid, initiation_block: u64, randomness_block: u64, randomness: String) -> Result<Raffle, Box<dyn Error>> {
+        let raffle = self.state.get_raffle_mut(&raffle_id)
+            .ok_or_else(|| format!("Raffle not found: {}", raffle_id))?;
     
-        let statuses = vec![
-            ("Approved", Resolution::Approved),
-            ("Rejected", Resolution::Rejected),
-            ("Retracted", Resolution::Retracted),
-        ];
+        raffle.config_mut().set_initiation_block(initiation_block);
+        raffle.config_mut().set_randomness_block(randomness_block);
+        raffle.config_mut().set_block_randomness(randomness);
     
-        for (status, resolution) in statuses {
-            let filtered_proposals: Vec<&Proposal> = proposals.iter()
-                .filter(|p| matches!(p.resolution(), Some(r) if r == resolution))
-                .map(|p| *p)  // Dereference once to go from &&Proposal to &Proposal
-                .collect();
+        raffle.generate_ticket_scores()?;
+        raffle.select_deciding_teams();
     
-            if !filtered_proposals.is_empty() {
-                tables.push_str(&format!("### {} Proposals\n", status));
-                tables.push_str("| Name | URL | Team | Amounts | Start Date | End Date | Announced | Resolved | Report |\n");
-                tables.push_str("|------|-----|------|---------|------------|----------|-----------|----------|---------|\n");
-    
-                for proposal in &filtered_proposals {
-                    // Generate individual proposal report
-                    let report_path = self.generate_and_save_proposal_report(proposal.id(), epoch.name())?;
-                    let report_link = report_path.file_name().unwrap().to_str().unwrap();
-    
-                    let team_name = proposal.budget_request_details()
-                        .and_then(|d| d.team())
-                        .and_then(|id| self.state.current_state().teams().get(&id))
-                        .map_or("N/A".to_string(), |t| t.name().to_string());
-    
-                    let amounts = proposal.budget_request_details()
-                        .map(|d| d.request_amounts().iter()
-                            .map(|(token, amount)| format!("{} {}", amount, token))
-                            .collect::<Vec<_>>()
-                            .join(", "))
-                        .unwrap_or_else(|| "N/A".to_string());
-    
-                    tables.push_str(&format!(
-                        "| {} | {} | {} | {} | {} | {} | {} | {} | [Report]({}) |\n",
-                        proposal.title(),
-                        proposal.url().as_deref().unwrap_or("N/A"),
-                        team_name,
-                        amounts,
-                        proposal.budget_request_details().and_then(|d| d.start_date()).map_or("N/A".to_string(), |d| d.format("%Y-%m-%d").to_string()),
-                        proposal.budget_request_details().and_then(|d| d.end_date()).map_or("N/A".to_string(), |d| d.format("%Y-%m-%d").to_string()),
-                        proposal.announced_at().map_or("N/A".to_string(), |d| d.format("%Y-%m-%d").to_string()),
-                        proposal.resolved_at().map_or("N/A".to_string(), |d| d.format("%Y-%m-%d").to_string()),
-                        report_link
-                    ));
-                }
-                tables.push_str("\n");
-            }
-        }
+        let raffle_clone = raffle.clone();
+        self.save_state()?;
     
-        Ok(tables)
+        Ok(raffle_clone)
     }
-    
-
-    pub fn generate_team_summary(&self, epoch: &Epoch) -> Result<String, Box<dyn Error>> {
-        let mut summary = String::from("## Team Summary\n");
-        summary.push_str("| Team Name | Status | Counted Votes | Uncounted Votes | Total Points | % of Total Points | Reward Amount |\n");
-        summary.push_str("|-----------|--------|---------------|-----------------|--------------|-------------------|---------------|\n");
 
-        let total_points: u32 = self.state.current_state().teams().keys()
-            .map(|team_id| self.get_team_points_for_epoch(*team_id, epoch.id()).unwrap_or(0))
-            .sum();
+    /// Recomputes the proposal's raffle from its stored randomness. Returns the raffle ID and
+    /// the first team whose seat differs from the stored result, if any.
+    pub fn check_raffle(&self, proposal_name: &str) -> Result<(Uuid, Option<Uuid>), Box<dyn Error>> {
+        let (_, raffle_id) = self.find_proposal_and_raffle(proposal_name)?;
+        let raffle = self.state.get_raffle(&raffle_id)
+            .ok_or_else(|| format!("Raffle not found: {}", raffle_id))?;
 
-        for (team_id, team) in self.state.current_state().teams() {
-            let team_points = self.get_team_points_for_epoch(*team_id, epoch.id()).unwrap_or(0);
-            let percentage = if total_points > 0 {
-                (team_points as f64 / total_points as f64) * 100.0
-            } else {
-                0.0
-            };
+        let stored = raffle.result().ok_or("Raffle has no result to verify")?;
+        let recomputed = raffle.recompute_result()?;
+        Ok((raffle_id, stored.first_divergence(&recomputed)))
+    }
 
-            let (counted_votes, uncounted_votes) = self.get_team_vote_counts(*team_id, epoch.id());
+    pub fn verify_raffle(&self, proposal_name: &str) -> Result<String, Box<dyn Error>> {
+        let (raffle_id, divergence) = self.check_raffle(proposal_name)?;
+        let raffle = self.state.get_raffle(&raffle_id)
+            .ok_or_else(|| format!("Raffle not found: {}", raffle_id))?;
 
-            let reward_amount = epoch.team_rewards().get(team_id)
-                .map(|reward| format!("{} {}", reward.amount(), epoch.reward().as_ref().map_or("".to_string(), |r| r.token().to_string())))
-                .unwrap_or_else(|| "N/A".to_string());
+        let mut output = format!("Raffle verification for '{}' (Raffle ID: {})\n", proposal_name, raffle_id);
+        output += &format!("Randomness block: {}\n", raffle.config().randomness_block());
+        output += &format!("Block randomness: {}\n", raffle.config().block_randomness());
+        if raffle.config().is_seeded() {
+            output += &format!("Randomness source: {}\n", raffle.randomness_source());
+        }
 
-            summary.push_str(&format!(
-                "| {} | {:?} | {} | {} | {} | {:.2}% | {} |\n",
-                team.name(),
-                team.status(),
-                counted_votes,
-                uncounted_votes,
-                team_points,
-                percentage,
-                reward_amount
-            ));
+        match divergence {
+            None => output += "Result: PASS\n",
+            Some(team_id) => {
+                let team_name = raffle.team_snapshots().iter()
+                    .find(|s| s.id() == team_id)
+                    .map_or("Unknown team".to_string(), |s| self.snapshot_display_name(s));
+                output += "Result: FAIL\n";
+                output += &format!("First divergent team: {} ({})\n", team_name, team_id);
+            }
         }
 
-        Ok(summary)
+        Ok(output)
     }
 
-    pub fn get_team_vote_counts(&self, team_id: Uuid, epoch_id: Uuid) -> (u32, u32) {
-        let mut counted = 0;
-        let mut uncounted = 0;
+    pub fn group_tickets_by_team(&self, tickets: &[RaffleTicket]) -> Vec<(String, u64, u64)> {
+        let mut grouped_tickets: Vec<(String, u64, u64)> = Vec::new();
+        let mut current_team: Option<(String, u64, u64)> = None;
 
-        for vote in self.state.votes().values() {
-            if vote.epoch_id() == epoch_id {
-                match vote.participation() {
-                    VoteParticipation::Formal { counted: c, uncounted: u } => {
-                        if c.contains(&team_id) {
-                            counted += 1;
-                        } else if u.contains(&team_id) {
-                            uncounted += 1;
-                        }
-                    },
-                    VoteParticipation::Informal(_) => {}  // Informal votes are not counted here
+        for ticket in tickets {
+            let team_name = self.state.find_team(&ticket.team_id())
+                .map(|team| team.name().to_string())
+                .unwrap_or_else(|| format!("Unknown Team ({})", ticket.team_id()));
+
+            match &mut current_team {
+                Some((name, _, end)) if *name == team_name => {
+                    *end = ticket.index();
+                }
+                _ => {
+                    if let Some(team) = current_team.take() {
+                        grouped_tickets.push(team);
+                    }
+                    current_team = Some((team_name, ticket.index(), ticket.index()));
                 }
             }
         }
 
-        (counted, uncounted)
+        if let Some(team) = current_team {
+            grouped_tickets.push(team);
+        }
+
+        grouped_tickets
     }
 
-    /// Creates a new raffle with progress updates streamed as an async stream
-    ///
-    /// # Arguments
-    /// * `proposal_name` - Name of the proposal to create raffle for
-    /// * `block_offset` - Optional override for the default block offset
-    /// * `excluded_teams` - Optional list of team names to exclude
-    ///
-    /// # Returns
-    /// A stream of RaffleProgress updates that can be consumed asynchronously
-    pub async fn create_raffle_with_progress<'a>(
-        &'a mut self,
-        proposal_name: String,
-        block_offset: Option<u64>,
-        excluded_teams: Option<Vec<String>>,
-    ) -> impl Stream<Item = Result<RaffleProgress, RaffleCreationError>> + Send + 'a {
-        let config = self.config.clone();
-        let eth_service = Arc::clone(&self.ethereum_service);
+    pub fn create_and_process_vote(
+        &mut self,
+        proposal_name: &str,
+        counted_votes: HashMap<String, VoteChoice>,
+        uncounted_votes: HashMap<String, VoteChoice>,
+        vote_opened: Option<NaiveDate>,
+        vote_closed: Option<NaiveDate>,
+        counted_points: Option<u32>,
+        uncounted_points: Option<u32>,
+        threshold: Option<f64>,
+    ) -> Result<String, Box<dyn Error>> {
+        // Find proposal and raffle
+        let (proposal_id, raffle_id) = self.find_proposal_and_raffle(proposal_name)
+            .map_err(|e| format!("Failed to find proposal or raffle: {}", e))?;
         
-        try_stream! {
-            // Do setup inside the stream
-            let (raffle_id, tickets) = self.prepare_raffle(&proposal_name, excluded_teams.clone(), &config)
-                .map_err(|e| RaffleCreationError(format!("Failed to prepare raffle: {}", e)))?;
-    
-            let ticket_ranges = self.group_tickets_by_team(&tickets);
-    
-            yield RaffleProgress::Preparing {
-                proposal_name: proposal_name.clone(),
-                raffle_id,
-                ticket_ranges,
-            };
+        // Check if the proposal already has a resolution
+        let proposal = self.state.get_proposal_mut(&proposal_id)
+            .ok_or_else(|| "Proposal not found after ID lookup".to_string())?;
+        if proposal.resolution().is_some() {
+            return Err("Cannot create vote: Proposal already has a resolution".into());
+        }
+
+        // Validate votes
+        self.validate_votes(raffle_id, &counted_votes, &uncounted_votes)
+            .map_err(|e| format!("Vote validation failed: {}", e))?;
     
-            let current_block = eth_service.get_current_block()
-                .await
-                .map_err(|e| RaffleCreationError(format!("Failed to get current block: {}", e)))?;
-                
-            let target_block = current_block + block_offset.unwrap_or(config.future_block_offset);
+        // Create vote
+        let vote_id = self.create_formal_vote_with_points(proposal_id, raffle_id, threshold, counted_points, uncounted_points)
+            .map_err(|e| format!("Failed to create formal vote: {}", e))?;
     
-            while eth_service.get_current_block()
-                .await
-                .map_err(|e| RaffleCreationError(format!("Failed to get current block: {}", e)))? < target_block 
-            {
-                yield RaffleProgress::WaitingForBlock {
-                    proposal_name: proposal_name.clone(),
-                    raffle_id,
-                    current_block,
-                    target_block,
-                };
-                
-                tokio::time::sleep(Duration::from_secs(1)).await;
-            }
+        // Cast votes
+        let all_votes: Vec<(Uuid, VoteChoice)> = counted_votes.into_iter()
+            .chain(uncounted_votes)
+            .filter_map(|(team_name, choice)| {
+                self.get_team_id_by_name(&team_name).map(|id| (id, choice))
+            })
+            .collect();
+        self.cast_votes(vote_id, all_votes)
+            .map_err(|e| format!("Failed to cast votes: {}", e))?;
     
-            let randomness = eth_service.get_randomness(target_block)
-                .await
-                .map_err(|e| RaffleCreationError(format!("Failed to get randomness: {}", e)))?;
+        // Update vote dates
+        self.update_vote_dates(vote_id, vote_opened, vote_closed)
+            .map_err(|e| format!("Failed to update vote dates: {}", e))?;
     
-            yield RaffleProgress::RandomnessAcquired {
-                proposal_name: proposal_name.clone(),
-                raffle_id,
-                current_block,
-                target_block,
-                randomness: randomness.clone(),
-            };
-    
-            let raffle = self.finalize_raffle(raffle_id, current_block, target_block, randomness)
-                .await
-                .map_err(|e| RaffleCreationError(format!("Failed to finalize raffle: {}", e)))?;
-    
-            let (counted, uncounted) = if let Some(result) = raffle.result() {
-                let format_team_with_score = |team_id: &Uuid| {
-                    let snapshot = raffle.team_snapshots().iter()
-                        .find(|s| s.id() == *team_id)
-                        .unwrap();
-                    let best_score = raffle.tickets().iter()
-                        .filter(|t| t.team_id() == *team_id)
-                        .map(|t| t.score())
-                        .max_by(|a, b| a.partial_cmp(b).unwrap())
-                        .unwrap_or(0.0);
-                    (snapshot.status().clone(), format!("{} (score: {})", snapshot.name(), best_score))
-                };
-        
-                let counted: Vec<(TeamStatus, String)> = result.counted().iter()
-                    .map(|team_id| format_team_with_score(team_id))
-                    .collect();
-                let uncounted: Vec<(TeamStatus, String)> = result.uncounted().iter()
-                    .map(|team_id| format_team_with_score(team_id))
-                    .collect();
-                (counted, uncounted)
-            } else {
-                (Vec::new(), Vec::new())
-            };
-        
-            yield RaffleProgress::Completed {
-                proposal_name: proposal_name.clone(),
-                raffle_id,
-                counted,
-                uncounted,
-            };
-        }
-    }
+        // Close vote and update proposal
+        let passed = self.close_vote_and_update_proposal(vote_id, proposal_id, vote_closed)
+            .map_err(|e| format!("Failed to close vote or update proposal: {}", e))?;
 
-    pub fn generate_unpaid_requests_report(
-        &self,
-        output_path: Option<&str>,
-        epoch_name: Option<&str>,
+        // Generate report
+        self.generate_vote_report(vote_id)
+    }
+    
+    /// Multi-option sibling of `create_and_process_vote`; votes are `VoteChoice::Option` indices
+    pub fn create_and_process_multi_option_vote(
+        &mut self,
+        proposal_name: &str,
+        options: Vec<String>,
+        counted_votes: HashMap<String, VoteChoice>,
+        uncounted_votes: HashMap<String, VoteChoice>,
+        vote_opened: Option<NaiveDate>,
+        vote_closed: Option<NaiveDate>,
     ) -> Result<String, Box<dyn Error>> {
-        // Collect unpaid requests
-        let unpaid_requests: Vec<UnpaidRequest> = self
-            .state
-            .proposals()
-            .iter()
-            .filter_map(|(proposal_id, proposal)| {
-                // Check if proposal is approved
-                if !proposal.is_approved() {
-                    return None;
-                }
+        let (proposal_id, raffle_id) = self.find_proposal_and_raffle(proposal_name)
+            .map_err(|e| format!("Failed to find proposal or raffle: {}", e))?;
 
-                // Check if it has budget details
-                let budget_details = match proposal.budget_request_details() {
-                    Some(details) => details,
-                    None => return None,
-                };
+        let proposal = self.state.get_proposal(&proposal_id)
+            .ok_or_else(|| "Proposal not found after ID lookup".to_string())?;
+        if proposal.resolution().is_some() {
+            return Err("Cannot create vote: Proposal already has a resolution".into());
+        }
 
-                // Skip if already paid
-                if budget_details.is_paid() {
-                    return None;
-                }
+        self.validate_votes(raffle_id, &counted_votes, &uncounted_votes)
+            .map_err(|e| format!("Vote validation failed: {}", e))?;
 
-                // Get team name
-                let team_name = budget_details
-                    .team()
-                    .and_then(|team_id| self.state.current_state().teams().get(&team_id))
-                    .map(|team| team.name().to_string())
-                    .unwrap_or_else(|| "No Team".to_string());
+        let vote_id = self.create_multi_option_vote(proposal_id, raffle_id, options)
+            .map_err(|e| format!("Failed to create multi-option vote: {}", e))?;
 
-                // Get epoch name
-                let epoch = self.state.epochs().get(&proposal.epoch_id());
-                
-                // Filter by epoch if specified
-                if let Some(target_epoch) = epoch_name {
-                    if let Some(epoch) = epoch {
-                        if epoch.name() != target_epoch {
-                            return None;
-                        }
-                    }
-                }
+        let all_votes: Vec<(Uuid, VoteChoice)> = counted_votes.into_iter()
+            .chain(uncounted_votes)
+            .filter_map(|(team_name, choice)| {
+                self.get_team_id_by_name(&team_name).map(|id| (id, choice))
+            })
+            .collect();
+        self.cast_votes(vote_id, all_votes)
+            .map_err(|e| format!("Failed to cast votes: {}", e))?;
 
-                let epoch_name = epoch
-                    .map(|e| e.name().to_string())
-                    .unwrap_or_else(|| "Unknown Epoch".to_string());
+        self.update_vote_dates(vote_id, vote_opened, vote_closed)
+            .map_err(|e| format!("Failed to update vote dates: {}", e))?;
 
-                // Get approval date
-                let approved_date = proposal.resolved_at()
-                    .unwrap_or_else(|| Utc::now().date_naive());
+        self.close_vote_and_update_proposal(vote_id, proposal_id, vote_closed)
+            .map_err(|e| format!("Failed to close vote or update proposal: {}", e))?;
 
-                Some(UnpaidRequest::new(
-                    *proposal_id,
-                    proposal.title().to_string(),
-                    team_name,
-                    budget_details.request_amounts().clone(),
-                    budget_details.payment_address().map(|addr| format!("{:?}", addr)),
-                    approved_date,
-                    budget_details.is_loan(),
-                    epoch_name,
-                    proposal.url().map(|u| u.to_string()),
-                    budget_details.start_date(),
-                ))
-            })
-            .collect();
+        self.generate_vote_report(vote_id)
+    }
 
-        let report = UnpaidRequestsReport::new(unpaid_requests);
+    pub fn find_proposal_and_raffle(&self, proposal_name: &str) -> Result<(Uuid, Uuid), Box<dyn Error>> {
+        let proposal_id = self.require_proposal_id(proposal_name)?;
+        
+        let raffle_id = self.active_raffle_for_proposal(proposal_id)
+            .map(|raffle| raffle.id())
+            .ok_or_else(|| format!("No raffle found for proposal: {}", proposal_name))?;
+        Ok((proposal_id, raffle_id))
+    }
 
-        // Generate output path if not provided
-        let output_path = output_path.map(PathBuf::from).unwrap_or_else(|| {
-            let date = Utc::now().format("%Y%m%d");
-            PathBuf::from(&self.config.state_file)
-                .parent()
-                .unwrap()
-                .join("reports")
-                .join(format!("unpaid_requests_{}.json", date))
-        });
+    /// The raffle that decides a proposal's vote: the latest one that has not been re-drawn
+    pub fn active_raffle_for_proposal(&self, proposal_id: Uuid) -> Option<&Raffle> {
+        self.state.raffles().values()
+            .filter(|raffle| raffle.config().proposal_id() == proposal_id && !raffle.is_superseded())
+            .max_by_key(|raffle| (raffle.config().initiation_block(), raffle.id()))
+    }
 
-        // Create directory if it doesn't exist
-        if let Some(parent) = output_path.parent() {
-            fs::create_dir_all(parent)?;
+    /// Raffles replaced by re-draws leading up to `raffle_id`, most recent first
+    pub fn superseded_raffles(&self, raffle_id: Uuid) -> Vec<&Raffle> {
+        let mut chain = Vec::new();
+        let mut previous = self.state.get_raffle(&raffle_id).and_then(|raffle| raffle.supersedes());
+        while let Some(raffle) = previous.and_then(|id| self.state.get_raffle(&id)) {
+            if chain.iter().any(|seen: &&Raffle| seen.id() == raffle.id()) {
+                break;
+            }
+            chain.push(raffle);
+            previous = raffle.supersedes();
         }
+        chain
+    }
 
-        // Write report to file
-        let json = serde_json::to_string_pretty(&report)?;
-        fs::write(&output_path, json)?;
+    /// Re-runs a proposal's raffle on the current roster with the randomness of `new_randomness_block`,
+    /// for when the targeted block was reorged or the roster was wrong. The previous raffle is kept and
+    /// marked as superseded, and open votes on it are moved to the new raffle.
+    pub async fn redraw_raffle(&mut self, proposal_name: &str, new_randomness_block: u64) -> Result<Uuid, Box<dyn Error>> {
+        let proposal_id = self.require_proposal_id(proposal_name)?;
+        let previous = self.active_raffle_for_proposal(proposal_id)
+            .ok_or_else(|| format!("No raffle found for proposal: {}", proposal_name))?;
+        if !previous.is_completed() {
+            return Err("The current raffle has not been drawn yet".into());
+        }
+        let previous_id = previous.id();
+        let previous_config = previous.config().clone();
+        if self.state.votes().values().any(|vote| vote.raffle_id() == Some(previous_id) && vote.is_closed()) {
+            return Err("Cannot re-draw a raffle whose vote is closed".into());
+        }
 
-        Ok(format!("Generated unpaid requests report at: {:?}", output_path))
-    }
+        let initiation_block = self.ethereum_service.get_current_block().await?;
+        let randomness = self.ethereum_service.get_randomness(new_randomness_block).await?;
+        let randomness_timestamp = self.ethereum_service.get_block_timestamp(new_randomness_block).await.ok();
 
-}
+        let mut raffle_config = RaffleConfig::new(
+            proposal_id,
+            previous_config.epoch_id(),
+            previous_config.total_counted_seats(),
+            previous_config.max_earner_seats(),
+            Some(initiation_block),
+            Some(new_randomness_block),
+            Some(randomness),
+            Some(previous_config.excluded_teams().to_vec()),
+            None,
+            None,
+            false
+        );
+        raffle_config.set_exclude_inactive(previous_config.exclude_inactive());
+        raffle_config.set_randomness_timestamp(randomness_timestamp);
+        raffle_config.set_tie_break(previous_config.tie_break());
+        raffle_config.set_revenue_tiers(previous_config.revenue_tiers().to_vec());
 
-#[async_trait]
-impl CommandExecutor for BudgetSystem {
-    async fn execute_command(&mut self, command: Command) -> Result<String, Box<dyn std::error::Error>> {
-        match command {
-            Command::CreateEpoch { name, start_date, end_date } => {
-                let epoch_id = self.create_epoch(&name, start_date, end_date)?;
-                Ok(format!("Created epoch: {} ({})", name, epoch_id))
-            },
-            Command::ActivateEpoch { name } => {
-                let epoch_id = self.get_epoch_id_by_name(&name)
-                    .ok_or_else(|| format!("Epoch not found: {}", name))?;
-                self.activate_epoch(epoch_id)?;
-                Ok(format!("Activated epoch: {} ({})", name, epoch_id))
-            },
-            Command::SetEpochReward { token, amount } => {
-                self.set_epoch_reward(&token, amount)?;
-                Ok(format!("Set epoch reward: {} {}", amount, token))
-            },
-            Command::AddTeam { name, representative, trailing_monthly_revenue, address} => {
-                let team_id = self.create_team(name.clone(), representative, trailing_monthly_revenue, address)?;
-                Ok(format!("Added team: {} ({})", name, team_id))
-            },
-            Command::UpdateTeam { team_name, updates } => {
-                let team_id = self.get_team_id_by_name(&team_name)
-                    .ok_or_else(|| format!("Team not found: {}", team_name))?;
-                self.update_team(team_id, updates)?;
-                Ok(format!("Updated team: {}", team_name))
-            },
-            Command::AddProposal { title, url, budget_request_details, announced_at, published_at, is_historical } => {
-                let budget_request_details = budget_request_details.map(|details| {
-                    BudgetRequestDetails::new(
-                        details.team.and_then(|name| self.get_team_id_by_name(&name)),
-                        details.request_amounts.unwrap_or_default(),
-                        details.start_date,
-                        details.end_date,
-                        details.is_loan,
-                        details.payment_address,
-                    )
-                }).transpose()?;
-             
-                let proposal_id = self.add_proposal(title.clone(), url, budget_request_details, announced_at, published_at, is_historical)?;
-                Ok(format!("Added proposal: {} ({})", title, proposal_id))
-             },
-            Command::UpdateProposal { proposal_name, updates } => {
-                self.update_proposal(&proposal_name, updates)?;
-                Ok(format!("Updated proposal: {}", proposal_name))
-            },
-            Command::ImportPredefinedRaffle { 
-                proposal_name, 
-                counted_teams, 
-                uncounted_teams, 
-                total_counted_seats, 
-                max_earner_seats 
-            } => {
-                let raffle_id = self.import_predefined_raffle(
-                    &proposal_name, 
-                    counted_teams.clone(), 
-                    uncounted_teams.clone(), 
-                    total_counted_seats, 
-                    max_earner_seats
-                )?;
-                
-                let raffle = self.state().raffles().get(&raffle_id).unwrap();
-            
-                let mut output = format!("Imported predefined raffle for proposal '{}' (Raffle ID: {})\n", proposal_name, raffle_id);
-                output += &format!("  Counted teams: {:?}\n", counted_teams);
-                output += &format!("  Uncounted teams: {:?}\n", uncounted_teams);
-                output += &format!("  Total counted seats: {}\n", total_counted_seats);
-                output += &format!("  Max earner seats: {}\n", max_earner_seats);
-            
-                output += "\nTeam Snapshots:\n";
-                for snapshot in raffle.team_snapshots() {
-                    output += &format!("  {} ({}): {:?}\n", snapshot.name(), snapshot.id(), snapshot.status());
-                }
-            
-                if let Some(result) = raffle.result() {
-                    output += "\nRaffle Result:\n";
-                    output += &format!("  Counted teams: {:?}\n", result.counted());
-                    output += &format!("  Uncounted teams: {:?}\n", result.uncounted());
-                } else {
-                    output += "\nRaffle result not available\n";
-                }
-            
-                Ok(output)
-            },
-            Command::ImportHistoricalVote { 
-                proposal_name, 
-                passed, 
-                participating_teams,
-                non_participating_teams,
-                counted_points,
-                uncounted_points,
-            } => {
-                let vote_id = self.import_historical_vote(
-                    &proposal_name,
-                    passed,
-                    participating_teams.clone(),
-                    non_participating_teams.clone(),
-                    counted_points,
-                    uncounted_points
-                )?;
-            
-                let vote = self.state().votes().get(&vote_id).unwrap();
-                let proposal = self.state().proposals().get(&vote.proposal_id()).unwrap();
-            
-                let mut output = format!("Imported historical vote for proposal '{}' (Vote ID: {})\n", proposal_name, vote_id);
-                output += &format!("Vote passed: {}\n", passed);
-            
-                output += "\nNon-participating teams:\n";
-                for team_name in &non_participating_teams {
-                    output += &format!("  {}\n", team_name);
+        let mut raffle = Raffle::new(raffle_config, self.state.current_state().teams())?;
+        raffle.generate_ticket_scores()?;
+        raffle.select_deciding_teams();
+        raffle.set_supersedes(Some(previous_id));
+        let raffle_result = raffle.result().cloned().ok_or("Failed to draw the new raffle")?;
+        let total_eligible_seats = raffle.config().total_counted_seats() as u32;
+        let raffle_id = self.state.add_raffle(&raffle);
+        self.state.get_raffle_mut(&previous_id).ok_or("Raffle not found")?.set_superseded_by(Some(raffle_id));
+
+        let open_votes: Vec<Uuid> = self.state.votes().values()
+            .filter(|vote| vote.raffle_id() == Some(previous_id))
+            .map(|vote| vote.id())
+            .collect();
+        for vote_id in open_votes {
+            let vote = self.state.get_vote_mut(&vote_id).ok_or("Vote not found")?;
+            vote.set_provisional(true);
+            let dropped = vote.rebind_to_raffle(raffle_id, total_eligible_seats, &raffle_result)?;
+            if !dropped.is_empty() {
+                warn!("Re-draw dropped {} votes from teams that lost their seat on vote {}", dropped.len(), vote_id);
+            }
+        }
+
+        self.save_state()?;
+        Ok(raffle_id)
+    }
+    
+    pub fn validate_votes(
+        &self,
+        raffle_id: Uuid,
+        counted_votes: &HashMap<String, VoteChoice>,
+        uncounted_votes: &HashMap<String, VoteChoice>,
+    ) -> Result<(), Box<dyn Error>> {
+        let raffle = self.state.raffles().get(&raffle_id)
+            .ok_or_else(|| format!("Raffle not found: {}", raffle_id))?;
+    
+        if !raffle.is_completed() {
+            return Err("Raffle has not been conducted yet".into());
+        }
+    
+        self.validate_votes_against_raffle(raffle, counted_votes, uncounted_votes)
+    }
+    
+    pub fn update_vote_dates(
+        &mut self,
+        vote_id: Uuid,
+        vote_opened: Option<NaiveDate>,
+        vote_closed: Option<NaiveDate>,
+    ) -> Result<(), Box<dyn Error>> {
+        let vote = self.state.get_vote_mut(&vote_id).ok_or("Vote not found")?;
+        
+        if let Some(opened) = vote_opened {
+            let opened_datetime = opened.and_hms_opt(0, 0, 0)
+                .map(|naive| Utc.from_utc_datetime(&naive))
+                .ok_or("Invalid opened date")?;
+            vote.set_opened_at(opened_datetime);
+        }
+        
+        if let Some(closed) = vote_closed {
+            let closed_datetime = closed.and_hms_opt(23, 59, 59)
+                .map(|naive| Utc.from_utc_datetime(&naive))
+                .ok_or("Invalid closed date")?;
+            vote.set_closed_at(Some(closed_datetime));
+        }
+        
+        Ok(())
+    }
+    
+    pub fn close_vote_and_update_proposal(
+        &mut self,
+        vote_id: Uuid,
+        proposal_id: Uuid,
+        vote_closed: Option<NaiveDate>,
+    ) -> Result<bool, Box<dyn Error>> {
+        // Checked before the vote closes, so a capped approval leaves both untouched
+        if self.vote_would_pass(vote_id)? {
+            self.check_budget_cap(proposal_id)?;
+        }
+        let passed = self.close_vote_silently(vote_id)?;
+        
+        let proposal = self.state.get_proposal_mut(&proposal_id)
+            .ok_or_else(|| format!("Proposal not found: {}", proposal_id))?;
+        
+        println!("Proposal status before update: {:?}", proposal.status());
+        println!("Proposal resolution before update: {:?}", proposal.resolution());
+        
+        let result = if passed {
+            proposal.approve()
+        } else {
+            proposal.reject()
+        };
+    
+        match result {
+            Ok(()) => {
+                if let Some(closed) = vote_closed {
+                    proposal.set_resolved_at(Some(closed));
                 }
-            
-                if let VoteType::Formal { raffle_id, .. } = vote.vote_type() {
-                    if let Some(raffle) = self.state().raffles().get(&raffle_id) {
-                        if let VoteParticipation::Formal { counted, uncounted } = vote.participation() {
-                            output += "\nCounted seats:\n";
-                            for &team_id in counted {
-                                if let Some(team) = raffle.team_snapshots().iter().find(|s| s.id() == team_id) {
-                                    output += &format!("  {} (+{} points)\n", team.name(), self.config.counted_vote_points);
-                                }
-                            }
-            
-                            output += "\nUncounted seats:\n";
-                            for &team_id in uncounted {
-                                if let Some(team) = raffle.team_snapshots().iter().find(|s| s.id() == team_id) {
-                                    output += &format!("  {} (+{} points)\n", team.name(), self.config.uncounted_vote_points);
-                                }
-                            }
-                        }
-                    } else {
-                        output += "\nAssociated raffle not found. Cannot display seat breakdowns.\n";
-                    }
+                println!("Proposal status after update: {:?}", proposal.status());
+                println!("Proposal resolution after update: {:?}", proposal.resolution());
+                self.save_state()?;
+                self.emit_event(SystemEvent::VoteClosed { vote_id, passed });
+                Ok(passed)
+            },
+            Err(e) => {
+                println!("Error updating proposal: {}", e);
+                println!("Current proposal state: {:?}", proposal);
+                Err(format!("Failed to update proposal: {}", e).into())
+            }
+        }
+    }
+
+    /// Recomputes a closed formal vote's outcome after its participation was corrected,
+    /// returning `(old, new)`. The stored result and proposal resolution change only on `commit`.
+    pub fn recompute_vote_outcome(&mut self, vote_id: Uuid, commit: bool) -> Result<(bool, bool), Box<dyn Error>> {
+        let vote = self.state.get_vote(&vote_id).ok_or("Vote not found")?;
+
+        if !vote.is_closed() {
+            return Err("Vote is still open".into());
+        }
+
+        let epoch = self.state.get_epoch(&vote.epoch_id()).ok_or("Epoch not found")?;
+        if epoch.is_closed() {
+            return Err(format!("Cannot recompute vote in closed epoch: {}", epoch.name()).into());
+        }
+
+        let old_passed = match vote.result() {
+            Some(VoteResult::Formal { passed, .. }) => *passed,
+            _ => return Err("Only formal votes with a result can be recomputed".into()),
+        };
+        let new_result = vote.recompute_outcome()?;
+        let new_passed = matches!(new_result, VoteResult::Formal { passed: true, .. });
+        let proposal_id = vote.proposal_id();
+
+        if commit {
+            let proposal = self.state.get_proposal(&proposal_id)
+                .ok_or_else(|| format!("Proposal not found: {}", proposal_id))?;
+            if new_passed && proposal.is_rejected() {
+                self.check_budget_cap(proposal_id)?;
+            }
+
+            let vote = self.state.get_vote_mut(&vote_id).ok_or("Vote not found")?;
+            vote.set_result(Some(new_result));
+
+            let proposal = self.state.get_proposal_mut(&proposal_id)
+                .ok_or_else(|| format!("Proposal not found: {}", proposal_id))?;
+            // Only resolutions that came from the vote follow the new outcome
+            if proposal.is_approved() || proposal.is_rejected() {
+                proposal.set_resolution(Some(if new_passed { Resolution::Approved } else { Resolution::Rejected }));
+            }
+
+            self.save_state()?;
+        }
+
+        Ok((old_passed, new_passed))
+    }
+
+    /// Teams with a counted or uncounted seat in the raffle that are missing from the vote's participation,
+    /// in raffle order. Informal votes have no seats, so nobody is absent from them.
+    fn absent_teams(vote: &Vote, raffle_result: &RaffleResult) -> (Vec<Uuid>, Vec<Uuid>) {
+        match vote.participation() {
+            VoteParticipation::Formal { counted, uncounted } => (
+                raffle_result.counted().iter().filter(|&id| !counted.contains(id)).copied().collect(),
+                raffle_result.uncounted().iter().filter(|&id| !uncounted.contains(id)).copied().collect(),
+            ),
+            VoteParticipation::Informal(_) => (Vec::new(), Vec::new()),
+        }
+    }
+
+    /// Sorted names of the teams, falling back to the ID for teams that no longer exist
+    fn sorted_team_names(&self, team_ids: &[Uuid]) -> Vec<String> {
+        let mut names: Vec<String> = team_ids.iter()
+            .map(|id| self.state.find_team(id).map_or_else(|| id.to_string(), |team| team.name().to_string()))
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Names of the counted and uncounted teams that hold a seat in the vote's raffle but have not voted
+    pub fn outstanding_voters(&self, vote_id: Uuid) -> Result<(Vec<String>, Vec<String>), Box<dyn Error>> {
+        let vote = self.state.get_vote(&vote_id).ok_or("Vote not found")?;
+        if let VoteParticipation::Informal(_) = vote.participation() {
+            return Err("Informal votes have no raffle seats".into());
+        }
+        let raffle_result = vote.raffle_id()
+            .and_then(|id| self.state.get_raffle(&id))
+            .or_else(|| self.active_raffle_for_proposal(vote.proposal_id()))
+            .and_then(|raffle| raffle.result())
+            .ok_or("Raffle results have not been generated")?;
+
+        let (counted, uncounted) = Self::absent_teams(vote, raffle_result);
+        Ok((self.sorted_team_names(&counted), self.sorted_team_names(&uncounted)))
+    }
+
+    /// The proposal's open formal vote if there is one, otherwise its latest
+    pub fn current_formal_vote_id(&self, proposal_name: &str) -> Result<Uuid, Box<dyn Error>> {
+        let proposal_id = self.require_proposal_id(proposal_name)?;
+        let vote_id = self.state.votes().values()
+            .filter(|vote| vote.proposal_id() == proposal_id && matches!(vote.participation(), VoteParticipation::Formal { .. }))
+            .max_by_key(|vote| (!vote.is_closed(), vote.opened_at()))
+            .map(|vote| vote.id())
+            .ok_or_else(|| format!("No formal vote found for proposal: {}", proposal_name))?;
+        Ok(vote_id)
+    }
+
+    /// Votes cast so far on an open vote, the seats that have not voted yet, and the outcome
+    /// if the vote were closed now
+    pub fn open_vote_status(&self, vote_id: Uuid) -> Result<String, Box<dyn Error>> {
+        let vote = self.state.get_vote(&vote_id).ok_or("Vote not found")?;
+        let proposal = self.state.get_proposal(&vote.proposal_id()).ok_or("Proposal not found")?;
+        let mut report = format!("**{}**\n", proposal.title());
+        if vote.is_closed() {
+            report.push_str("The vote is closed; see the vote report for its result.");
+            return Ok(report);
+        }
+        report.push_str(&format!("Open since {}\n\n", vote.opened_at().format("%Y-%m-%d %H:%M UTC")));
+
+        let seats = |label: &str, seats: usize, absent: &[Uuid]| {
+            let outstanding = self.sorted_team_names(absent);
+            let mut line = format!("{} votes cast: {}/{}", label, seats - outstanding.len(), seats);
+            if !outstanding.is_empty() {
+                line.push_str(&format!(" (outstanding: {})", outstanding.join(", ")));
+            }
+            line.push('\n');
+            line
+        };
+
+        let raffle_result = vote.raffle_id()
+            .and_then(|id| self.state.get_raffle(&id))
+            .and_then(|raffle| raffle.result());
+        match (vote.participation(), raffle_result) {
+            (VoteParticipation::Formal { .. }, Some(result)) => {
+                let (absent_counted, absent_uncounted) = Self::absent_teams(vote, result);
+                report.push_str(&seats("Counted", result.counted().len(), &absent_counted));
+                report.push_str(&seats("Uncounted", result.uncounted().len(), &absent_uncounted));
+            },
+            (VoteParticipation::Formal { counted, uncounted }, None) => {
+                report.push_str(&format!("Counted votes cast: {}\nUncounted votes cast: {}\n", counted.len(), uncounted.len()));
+            },
+            (VoteParticipation::Informal(participants), _) => {
+                report.push_str(&format!("Votes cast: {}\n", participants.len()));
+            },
+        }
+
+        let outcome = |passed: bool| if passed { "Approved" } else { "Not Approved" };
+        report.push('\n');
+        match vote.projected_result()? {
+            VoteResult::Formal { counted, passed, quorum_reached, .. } => {
+                let (total_eligible_seats, threshold) = match vote.vote_type() {
+                    VoteType::Formal { total_eligible_seats, threshold, .. } => (*total_eligible_seats, *threshold),
+                    _ => (0, 0.0),
+                };
+                report.push_str(&format!("__{} in favor, {} against, {} abstained__\n", counted.yes(), counted.no(), counted.abstain()));
+                report.push_str(&format!("{} of {} counted seats must vote yes\n",
+                    (threshold * total_eligible_seats as f64).ceil() as u32, total_eligible_seats));
+                let status = if quorum_reached {
+                    outcome(passed).to_string()
                 } else {
-                    output += "\nThis is an informal vote, no counted/uncounted breakdown available.\n";
-                }
-            
-                output += "\nNote: Detailed vote counts are not available for historical votes.\n";
-            
-                Ok(output)
+                    format!("Failed to reach quorum ({}/{} required)", vote.quorum_required().unwrap_or(0), total_eligible_seats)
+                };
+                report.push_str(&format!("**If closed now: {}**", status));
             },
-            Command::ImportHistoricalRaffle { 
-                proposal_name, 
-                initiation_block, 
-                randomness_block, 
-                team_order, 
-                excluded_teams,
-                total_counted_seats, 
-                max_earner_seats 
-            } => {
-                let (raffle_id, raffle) = self.import_historical_raffle(
-                    &proposal_name,
-                    initiation_block,
-                    randomness_block,
-                    team_order.clone(),
-                    excluded_teams.clone(),
-                    total_counted_seats.or(Some(self.config.default_total_counted_seats)),
-                    max_earner_seats.or(Some(self.config.default_max_earner_seats)),
-                ).await?;
-            
-                let mut output = format!("Imported historical raffle for proposal '{}' (Raffle ID: {})\n", proposal_name, raffle_id);
-                output += &format!("Randomness: {}\n", raffle.config().block_randomness());
-            
-                if let Some(excluded) = excluded_teams {
-                    output += &format!("Excluded teams: {:?}\n", excluded);
-                }
-            
-                for snapshot in raffle.team_snapshots() {
-                    let tickets: Vec<_> = raffle.tickets().iter()
-                        .filter(|t| t.team_id() == snapshot.id())
-                        .collect();
-                    
-                    if !tickets.is_empty() {
-                        let start = tickets.first().unwrap().index();
-                        let end = tickets.last().unwrap().index();
-                        output += &format!("Team '{}' ballot range: {} - {}\n", snapshot.name(), start, end);
-                    }
-                }
-            
-                if let Some(result) = raffle.result() {
-                    output += "Counted seats:\n";
-                    output += "Earner seats:\n";
-                    let mut earner_count = 0;
-                    for &team_id in result.counted() {
-                        if let Some(snapshot) = raffle.team_snapshots().iter().find(|s| s.id() == team_id) {
-                            if let TeamStatus::Earner { .. } = snapshot.status() {
-                                earner_count += 1;
-                                let best_score = raffle.tickets().iter()
-                                    .filter(|t| t.team_id() == team_id)
-                                    .map(|t| t.score())
-                                    .max_by(|a, b| a.partial_cmp(b).unwrap())
-                                    .unwrap_or(0.0);
-                                output += &format!("  {} (score: {})\n", snapshot.name(), best_score);
-                            }
-                        }
-                    }
-                    output += "Supporter seats:\n";
-                    for &team_id in result.counted() {
-                        if let Some(snapshot) = raffle.team_snapshots().iter().find(|s| s.id() == team_id) {
-                            if let TeamStatus::Supporter = snapshot.status() {
-                                let best_score = raffle.tickets().iter()
-                                    .filter(|t| t.team_id() == team_id)
-                                    .map(|t| t.score())
-                                    .max_by(|a, b| a.partial_cmp(b).unwrap())
-                                    .unwrap_or(0.0);
-                                output += &format!("  {} (score: {})\n", snapshot.name(), best_score);
-                            }
-                        }
-                    }
-                    output += &format!("Total counted seats: {} (Earners: {}, Supporters: {})\n", 
-                                result.counted().len(), earner_count, result.counted().len() - earner_count);
-            
-                    output += "Uncounted seats:\n";
-                    output += "Earner seats:\n";
-                    for &team_id in result.uncounted() {
-                        if let Some(snapshot) = raffle.team_snapshots().iter().find(|s| s.id() == team_id) {
-                            if let TeamStatus::Earner { .. } = snapshot.status() {
-                                let best_score = raffle.tickets().iter()
-                                    .filter(|t| t.team_id() == team_id)
-                                    .map(|t| t.score())
-                                    .max_by(|a, b| a.partial_cmp(b).unwrap())
-                                    .unwrap_or(0.0);
-                                output += &format!("  {} (score: {})\n", snapshot.name(), best_score);
-                            }
+            VoteResult::Weighted { counted, passed, .. } => {
+                report.push_str(&format!("__{} of {} points in favor, {} against__\n", counted.yes(), counted.total_weight(), counted.no()));
+                report.push_str(&format!("**If closed now: {}**", outcome(passed)));
+            },
+            VoteResult::MultiOption { tallies, winner, .. } => {
+                let option = match vote.vote_type() {
+                    VoteType::MultiOption { options, .. } => options.get(winner).cloned().unwrap_or_default(),
+                    _ => String::new(),
+                };
+                report.push_str(&format!("**If closed now: '{}' wins with {} of {} counted votes**",
+                    option, tallies.get(winner).copied().unwrap_or(0), tallies.iter().sum::<u32>()));
+            },
+            VoteResult::Informal { count, .. } => {
+                report.push_str(&format!("__{} in favor, {} against__\nInformal votes have no outcome", count.yes(), count.no()));
+            },
+        }
+
+        Ok(report)
+    }
+
+    pub fn generate_vote_report(&self, vote_id: Uuid) -> Result<String, Box<dyn Error>> {
+        let vote = self.state.get_vote(&vote_id).ok_or("Vote not found")?;
+        let proposal = self.state.proposals().get(&vote.proposal_id()).ok_or("Proposal not found")?;
+        let raffle = vote.raffle_id()
+            .and_then(|id| self.state.get_raffle(&id))
+            .or_else(|| self.active_raffle_for_proposal(vote.proposal_id()))
+            .ok_or("Associated raffle not found")?;
+
+        match vote.result() {
+            Some(VoteResult::Weighted { .. }) => return self.generate_weighted_vote_report(vote, proposal, raffle),
+            Some(VoteResult::MultiOption { .. }) => return self.generate_multi_option_vote_report(vote, proposal),
+            _ => {},
+        }
+    
+        let (counted, uncounted) = vote.vote_counts().ok_or("Vote counts not available")?;
+        let counted_yes = counted.yes();
+        let counted_no = counted.no();
+        let counted_abstain = counted.abstain();
+        let total_counted_votes = counted.total_cast();
+        
+        let total_eligible_seats = match vote.vote_type() {
+            VoteType::Formal { total_eligible_seats, .. } => total_eligible_seats,
+            _ => &0,
+        };
+    
+        // Calculate absent votes for counted seats only; teams that abstained were present
+        let absent = total_eligible_seats
+            .saturating_sub(counted_yes + counted_no)
+            .saturating_sub(counted_abstain);
+
+        let abstain_info = if counted_abstain > 0 {
+            format!(", {} abstained", counted_abstain)
+        } else {
+            String::new()
+        };
+
+        let status = match vote.result() {
+            Some(VoteResult::Formal { quorum_reached: false, .. }) => format!(
+                "Failed to reach quorum ({}/{} required)",
+                vote.quorum_required().unwrap_or(0),
+                total_eligible_seats
+            ),
+            Some(VoteResult::Formal { passed, .. } | VoteResult::Weighted { passed, .. }) => if *passed { "Approved" } else { "Not Approved" }.to_string(),
+            Some(VoteResult::Informal { .. }) => "N/A (Informal)".to_string(),
+            Some(VoteResult::MultiOption { .. }) => "Decided".to_string(),
+            None => "Pending".to_string(),
+        };
+    
+        let deciding_teams: Vec<String> = raffle.deciding_teams().iter()
+            .filter_map(|&team_id| {
+                self.state.find_team(&team_id).map(|team| team.name().to_string())
+            })
+            .collect();
+    
+        // Calculate uncounted votes
+        let total_uncounted_votes = uncounted.total_cast();
+        let total_uncounted_seats = raffle.result()
+            .map(|result| result.uncounted().len())
+            .unwrap_or(0) as u32;
+
+        let (counted_votes_info, uncounted_votes_info) = if let VoteParticipation::Formal { .. } = vote.participation() {
+            let (absent_counted, absent_uncounted) = Self::absent_teams(vote, raffle.result().expect("Raffle result not found"));
+            let names = |team_ids: Vec<Uuid>| -> Vec<String> {
+                team_ids.iter()
+                    .filter_map(|team_id| self.state.find_team(team_id).map(|team| team.name().to_string()))
+                    .collect()
+            };
+            let absent_counted = names(absent_counted);
+            let absent_uncounted = names(absent_uncounted);
+
+            let counted_info = if absent_counted.is_empty() {
+                format!("Counted votes cast: {}/{}", total_counted_votes, total_eligible_seats)
+            } else {
+                format!("Counted votes cast: {}/{} ({} absent)", total_counted_votes, total_eligible_seats, absent_counted.join(", "))
+            };
+
+            let uncounted_info = if absent_uncounted.is_empty() {
+                format!("Uncounted votes cast: {}/{}", total_uncounted_votes, total_uncounted_seats)
+            } else {
+                format!("Uncounted votes cast: {}/{} ({} absent)", total_uncounted_votes, total_uncounted_seats, absent_uncounted.join(", "))
+            };
+
+            (counted_info, uncounted_info)
+        } else {
+            (
+                format!("Counted votes cast: {}/{}", total_counted_votes, total_eligible_seats),
+                format!("Uncounted votes cast: {}/{}", total_uncounted_votes, total_uncounted_seats)
+            )
+        };
+    
+    
+        let report = format!(
+            "**{}**\n{}\n\n**Status: {}**\n__{} in favor, {} against{}, {} absent__\n\n**Deciding teams**\n`{:?}`\n\n{}\n{}",
+            proposal.title(),
+            proposal.url().as_deref().unwrap_or(""),
+            status,
+            counted_yes,
+            counted_no,
+            abstain_info,
+            absent,
+            deciding_teams,
+            counted_votes_info,
+            uncounted_votes_info
+        );
+    
+        Ok(report)
+    }
+
+    fn generate_weighted_vote_report(&self, vote: &Vote, proposal: &Proposal, raffle: &Raffle) -> Result<String, Box<dyn Error>> {
+        let (counted, uncounted, passed, choices) = match vote.result() {
+            Some(VoteResult::Weighted { counted, uncounted, passed, choices }) => (counted, uncounted, *passed, choices),
+            _ => return Err("Not a weighted vote".into()),
+        };
+        let weights = match vote.vote_type() {
+            VoteType::Weighted { weights, .. } => weights,
+            _ => return Err("Not a weighted vote".into()),
+        };
+        let raffle_result = raffle.result().ok_or("Raffle result not found")?;
+        let team_name = |team_id: &Uuid| self.state.find_team(team_id)
+            .map_or_else(|| team_id.to_string(), |team| team.name().to_string());
+
+        let mut counted_lines: Vec<String> = raffle_result.counted().iter()
+            .map(|team_id| {
+                let choice = choices.get(team_id).map_or("Absent".to_string(), |choice| format!("{:?}", choice));
+                format!("{}: {} (weight {})", team_name(team_id), choice, weights.get(team_id).copied().unwrap_or(0))
+            })
+            .collect();
+        counted_lines.sort();
+
+        let mut uncounted_lines: Vec<String> = raffle_result.uncounted().iter()
+            .map(|team_id| {
+                let choice = choices.get(team_id).map_or("Absent".to_string(), |choice| format!("{:?}", choice));
+                format!("{}: {}", team_name(team_id), choice)
+            })
+            .collect();
+        uncounted_lines.sort();
+
+        let absent_weight = counted.total_weight()
+            .saturating_sub(counted.yes() + counted.no() + counted.abstain());
+
+        Ok(format!(
+            "**{}**\n{}\n\n**Status: {}**\n__{} points in favor, {} against, {} abstained, {} absent (of {})__\n\n**Counted votes**\n{}\n\n**Uncounted votes** ({} in favor, {} against)\n{}",
+            proposal.title(),
+            proposal.url().unwrap_or(""),
+            if passed { "Approved" } else { "Not Approved" },
+            counted.yes(),
+            counted.no(),
+            counted.abstain(),
+            absent_weight,
+            counted.total_weight(),
+            counted_lines.join("\n"),
+            uncounted.yes(),
+            uncounted.no(),
+            uncounted_lines.join("\n")
+        ))
+    }
+
+    fn generate_multi_option_vote_report(&self, vote: &Vote, proposal: &Proposal) -> Result<String, Box<dyn Error>> {
+        let (tallies, uncounted_tallies, winner) = match vote.result() {
+            Some(VoteResult::MultiOption { tallies, uncounted_tallies, winner }) => (tallies, uncounted_tallies, *winner),
+            _ => return Err("Not a multi-option vote".into()),
+        };
+        let (options, total_eligible_seats) = match vote.vote_type() {
+            VoteType::MultiOption { options, total_eligible_seats, .. } => (options, *total_eligible_seats),
+            _ => return Err("Not a multi-option vote".into()),
+        };
+
+        let cast: u32 = tallies.iter().sum();
+        let option_lines: Vec<String> = options.iter().enumerate()
+            .map(|(index, option)| format!(
+                "{}. {}: {} counted, {} uncounted",
+                index + 1,
+                option,
+                tallies.get(index).copied().unwrap_or(0),
+                uncounted_tallies.get(index).copied().unwrap_or(0)
+            ))
+            .collect();
+
+        let status = if cast > 0 {
+            format!("Winner: {}", options.get(winner).map_or("Unknown option", |option| option.as_str()))
+        } else {
+            "No counted votes".to_string()
+        };
+
+        Ok(format!(
+            "**{}**\n{}\n\n**Status: {}**\n__{} of {} counted seats voted__\n\n**Options**\n{}",
+            proposal.title(),
+            proposal.url().unwrap_or(""),
+            status,
+            cast,
+            total_eligible_seats,
+            option_lines.join("\n")
+        ))
+    }
+
+    pub fn validate_votes_against_raffle(
+        &self,
+        raffle: &Raffle,
+        counted_votes: &HashMap<String, VoteChoice>,
+        uncounted_votes: &HashMap<String, VoteChoice>,
+    ) -> Result<(), Box<dyn Error>> {
+        let raffle_result = raffle.result().ok_or("Raffle result not found")?;
+    
+        let counted_team_ids: HashSet<_> = raffle_result.counted().iter().cloned().collect();
+        let uncounted_team_ids: HashSet<_> = raffle_result.uncounted().iter().cloned().collect();
+    
+        for team_name in counted_votes.keys() {
+            let team_id = self.require_team_id(team_name)?;
+            if !counted_team_ids.contains(&team_id) {
+                return Err(format!("Team {} is not eligible for counted vote", team_name).into());
+            }
+        }
+    
+        for team_name in uncounted_votes.keys() {
+            let team_id = self.require_team_id(team_name)?;
+            if !uncounted_team_ids.contains(&team_id) {
+                return Err(format!("Team {} is not eligible for uncounted vote", team_name).into());
+            }
+        }
+    
+        Ok(())
+    }
+
+    pub fn update_proposal(&mut self, proposal_name: &str, mut updates: UpdateProposalDetails) -> Result<(), Box<dyn Error>> {
+        let depends_on = updates.depends_on.take()
+            .map(|names| self.resolve_dependencies(&names))
+            .transpose()?;
+
+        // Find the team_id if it's needed
+        let team_id = if let Some(budget_details) = &updates.budget_request_details {
+            if let Some(team_name) = &budget_details.team {
+                self.get_team_id_by_name(team_name)
+            } else {
+                None
+            }
+        } else {
+            None
+        };
+    
+        // Update the proposal
+        let proposal_id = self.require_proposal_id(proposal_name)?;
+        if let Some(depends_on) = &depends_on {
+            self.check_dependency_cycle(proposal_id, depends_on)?;
+        }
+
+        // Applied to a copy, so a failing update leaves the proposal and its dependencies untouched
+        let mut proposal = self.state.get_proposal(&proposal_id).ok_or("Proposal not found")?.clone();
+        proposal.update(updates, team_id)?;
+        if let Some(depends_on) = depends_on {
+            proposal.set_depends_on(depends_on)?;
+        }
+        *self.state.get_proposal_mut(&proposal_id).ok_or("Proposal not found")? = proposal;
+    
+        self.save_state();
+        Ok(())
+    }
+
+    pub fn generate_markdown_test(&self) -> String {
+        let test_message = r#"
+*Bold text*
+_Italic text_
+__Underline__
+~Strikethrough~
+*Bold _italic bold ~italic bold strikethrough~ __underline italic bold___ bold*
+[inline URL](http://www.example.com/)
+[inline mention of a user](tg://user?id=123456789)
+`inline fixed-width code`
+```python
+def hello_world():
+    print("Hello, World!")
+```
+"#;
+        test_message.to_string()
+    }
+
+    /// The vote a proposal report describes: its formal vote when it has one, otherwise its latest vote
+    fn proposal_report_vote(&self, proposal_id: Uuid) -> Option<&Vote> {
+        self.state.votes().values()
+            .filter(|vote| vote.proposal_id() == proposal_id)
+            .max_by_key(|vote| (matches!(vote.participation(), VoteParticipation::Formal { .. }), vote.opened_at(), vote.id()))
+    }
+
+    /// The informal temperature check a formal vote was promoted from, with its tally
+    fn informal_stage_section(informal: &Vote) -> String {
+        let mut section = String::from("### Informal Stage\n\n");
+        section.push_str(&format!("- **Vote ID**: {}\n", informal.id()));
+        section.push_str(&format!("- **Opened**: {}\n", informal.opened_at().format("%Y-%m-%d %H:%M:%S")));
+        if let Some(closed_at) = informal.closed_at() {
+            section.push_str(&format!("- **Closed**: {}\n", closed_at.format("%Y-%m-%d %H:%M:%S")));
+        }
+        if let Some(VoteResult::Informal { count, weighted }) = informal.result() {
+            section.push_str(&format!("- **Yes**: {}\n", count.yes()));
+            section.push_str(&format!("- **No**: {}\n", count.no()));
+            if count.abstain() > 0 {
+                section.push_str(&format!("- **Abstain**: {}\n", count.abstain()));
+            }
+            if let Some(weighted) = weighted {
+                section.push_str(&format!("- **Revenue Weighted**: {} in favor, {} against\n", weighted.yes(), weighted.no()));
+            }
+        }
+        section.push_str("\nThe formal vote below was promoted from this informal vote.\n\n");
+        section
+    }
+
+    pub fn generate_proposal_report(&self, proposal_id: Uuid) -> Result<String, Box<dyn Error>> {
+        debug!("Generating proposal report for ID: {:?}", proposal_id);
+    
+        let proposal = self.state.get_proposal(&proposal_id)
+            .ok_or_else(|| format!("Proposal not found: {:?}", proposal_id))?;
+    
+        debug!("Found proposal: {:?}", proposal.title());
+    
+        let mut report = String::new();
+    
+        // Main title (moved outside of Summary)
+        report.push_str(&format!("# Proposal Report: {}\n\n", proposal.title()));
+    
+        // Summary
+        report.push_str("## Summary\n\n");
+        if let (Some(announced), Some(resolved)) = (proposal.announced_at(), proposal.resolved_at()) {
+            let resolution_days = self.calculate_days_between(announced, resolved);
+            report.push_str(&format!("This proposal was resolved in {} days from its announcement date. ", resolution_days));
+        }
+    
+        let report_vote = self.proposal_report_vote(proposal_id);
+        if let Some(vote) = report_vote {
+            if let Some(result) = vote.result() {
+                match result {
+                    VoteResult::Formal { counted, uncounted, passed, at_threshold, .. } => {
+                        report.push_str(&format!("The proposal was {} with {} votes in favor and {} votes against. ", 
+                            if *passed { "approved" } else { "not approved" }, 
+                            counted.yes(), counted.yes() + uncounted.yes()));
+                        if *at_threshold {
+                            report.push_str("It passed at exactly the threshold. ");
                         }
-                    }
-                    output += "Supporter seats:\n";
-                    for &team_id in result.uncounted() {
-                        if let Some(snapshot) = raffle.team_snapshots().iter().find(|s| s.id() == team_id) {
-                            if let TeamStatus::Supporter = snapshot.status() {
-                                let best_score = raffle.tickets().iter()
-                                    .filter(|t| t.team_id() == team_id)
-                                    .map(|t| t.score())
-                                    .max_by(|a, b| a.partial_cmp(b).unwrap())
-                                    .unwrap_or(0.0);
-                                output += &format!("  {} (score: {})\n", snapshot.name(), best_score);
-                            }
+                    },
+                    VoteResult::Weighted { counted, passed, .. } => {
+                        report.push_str(&format!("The proposal was {} in a weighted vote with {} of {} points in favor. ",
+                            if *passed { "approved" } else { "not approved" },
+                            counted.yes(), counted.total_weight()));
+                    },
+                    VoteResult::MultiOption { tallies, winner, .. } => {
+                        let option = match vote.vote_type() {
+                            VoteType::MultiOption { options, .. } => options.get(*winner).cloned().unwrap_or_default(),
+                            _ => String::new(),
+                        };
+                        report.push_str(&format!("This was a multi-option vote won by '{}' with {} of {} counted votes. ",
+                            option, tallies.get(*winner).copied().unwrap_or(0), tallies.iter().sum::<u32>()));
+                    },
+                    VoteResult::Informal { count, weighted } => {
+                        report.push_str(&format!("This was an informal vote with {} votes in favor and {} votes against. ", 
+                            count.yes(), count.no()));
+                        if let Some(weighted) = weighted {
+                            report.push_str(&format!("Weighted by revenue, {} in favor and {} against. ",
+                                weighted.yes(), weighted.no()));
                         }
                     }
-                } else {
-                    output += "Raffle result not available\n";
                 }
-            
-                Ok(output)
-            },
-            Command::PrintTeamReport => {
-                Ok(self.print_team_report())
-            },
-            Command::PrintEpochState => {
-                self.print_epoch_state()
-            },
-            Command::PrintTeamVoteParticipation { team_name, epoch_name } => {
-                self.print_team_vote_participation(&team_name, epoch_name.as_deref())
+            }
+        } else {
+            report.push_str("No voting information is available for this proposal. ");
+        }
+        if let Some(informal) = report_vote.and_then(|vote| vote.promoted_from()).and_then(|id| self.state.get_vote(&id)) {
+            if let Some(VoteResult::Informal { count, .. }) = informal.result() {
+                report.push_str(&format!("It was promoted from an informal vote with {} votes in favor and {} votes against. ",
+                    count.yes(), count.no()));
+            }
+        }
+    
+        if let Some(budget_details) = proposal.budget_request_details() {
+            let amounts: BTreeMap<String, f64> = budget_details.request_amounts().iter()
+                .map(|(token, amount)| (token.clone(), *amount))
+                .collect();
+            let mut requested = Self::format_token_amounts(&amounts);
+            if amounts.len() > 1 {
+                if let Some(usd) = self.amounts_in_usd(&amounts) {
+                    requested.push_str(&format!(" (about {:.2} USD)", usd));
+                }
+            }
+            report.push_str(&format!("The budget request was for {} for the period from {} to {}. ",
+                requested,
+                budget_details.start_date().map_or("N/A".to_string(), |d| d.format("%Y-%m-%d").to_string()),
+                budget_details.end_date().map_or("N/A".to_string(), |d| d.format("%Y-%m-%d").to_string())
+            ));
+        }
+    
+        report.push_str("\n\n");
+    
+        // Proposal Details
+        report.push_str("## Proposal Details\n\n");
+        report.push_str(&format!("- **ID**: {}\n", proposal.id()));
+        report.push_str(&format!("- **Title**: {}\n", proposal.title()));
+        report.push_str(&format!("- **Kind**: {}\n", proposal.kind()));
+        report.push_str(&format!("- **URL**: {}\n", proposal.url().as_deref().unwrap_or("N/A")));
+        report.push_str(&format!("- **Status**: {:?}\n", proposal.status()));
+        report.push_str(&format!("- **Resolution**: {}\n", proposal.resolution().as_ref().map_or("N/A".to_string(), |r| format!("{:?}", r))));
+        report.push_str(&format!("- **Announced**: {}\n", proposal.announced_at().map_or("N/A".to_string(), |d| d.format("%Y-%m-%d").to_string())));
+        report.push_str(&format!("- **Published**: {}\n", proposal.published_at().map_or("N/A".to_string(), |d| d.format("%Y-%m-%d").to_string())));
+        report.push_str(&format!("- **Resolved**: {}\n", proposal.resolved_at().map_or("N/A".to_string(), |d| d.format("%Y-%m-%d").to_string())));
+        report.push_str(&format!("- **Is Historical**: {}\n", proposal.is_historical()));
+        let mut sponsors: Vec<&str> = proposal.sponsors().iter()
+            .filter_map(|id| self.state.find_team(id).map(|team| team.name()))
+            .collect();
+        sponsors.sort();
+        report.push_str(&format!("- **Sponsors**: {}\n", if sponsors.is_empty() { "None".to_string() } else { sponsors.join(", ") }));
+        if !proposal.depends_on().is_empty() {
+            let dependency_titles = |approved: bool| -> Vec<String> {
+                proposal.depends_on().iter()
+                    .filter_map(|id| match self.state.get_proposal(id) {
+                        Some(dependency) if approved || !dependency.is_approved() => Some(dependency.title().to_string()),
+                        Some(_) => None,
+                        // A removed dependency can never be met
+                        None => Some(format!("Missing proposal {}", id)),
+                    })
+                    .collect()
+            };
+            let unmet = dependency_titles(false);
+            report.push_str(&format!("- **Dependencies**: {}\n", dependency_titles(true).join(", ")));
+            report.push_str(&format!("- **Unmet Dependencies**: {}\n", if unmet.is_empty() { "None".to_string() } else { unmet.join(", ") }));
+        }
+        report.push('\n');
+
+        // Attachments
+        if !proposal.attachments().is_empty() {
+            report.push_str("## Attachments\n\n");
+            for attachment in proposal.attachments() {
+                report.push_str(&format!("- [{}]({})\n", attachment.label, attachment.url(&self.config.ipfs_gateway)));
+            }
+            report.push('\n');
+        }
+
+        // Amendment history
+        let amendments = self.get_proposal_amendments(proposal.title());
+        if !amendments.is_empty() {
+            report.push_str("## Amendments\n\n");
+            for amendment in amendments {
+                report.push_str(&format!("- {}: {}\n", amendment.timestamp.format("%Y-%m-%d %H:%M:%S"), amendment.describe()));
+            }
+            report.push('\n');
+        }
+    
+        // Budget Request Details
+        if let Some(budget_details) = proposal.budget_request_details() {
+            report.push_str("## Budget Request Details\n\n");
+            
+            // Team info
+            report.push_str(&format!("- **Requesting Team**: {}\n", 
+                budget_details.team()
+                    .and_then(|id| self.state.find_team(&id))
+                    .map_or("N/A".to_string(), |team| team.name().to_string())));
+            
+            // Sort amounts by token for consistent output
+            let mut amounts: Vec<_> = budget_details.request_amounts().iter().collect();
+            amounts.sort_by(|(a, _), (b, _)| a.cmp(b));
+            
+            report.push_str("- **Requested Amount(s)**:\n");
+            for (token, amount) in amounts {
+                report.push_str(&format!("  - {}: {}\n", token, amount));
+            }
+ 
+            report.push_str(&format!("- **Start Date**: {}\n", 
+                budget_details.start_date()
+                    .map_or("N/A".to_string(), |d| d.format("%Y-%m-%d").to_string())));
+            report.push_str(&format!("- **End Date**: {}\n", 
+                budget_details.end_date()
+                    .map_or("N/A".to_string(), |d| d.format("%Y-%m-%d").to_string())));
+            report.push_str(&format!("- **Is Loan**: {}\n", 
+                budget_details.is_loan()));
+            report.push_str(&format!("- **Payment Address**: {}\n", 
+                budget_details.payment_address()
+                    .map_or("N/A".to_string(), |addr| format!("{:?}", addr))));
+            if budget_details.is_paid() {
+                report.push_str(&format!("- **Payment Transaction**: {}\n",
+                    budget_details.payment_tx().zip(budget_details.payment_tx_url())
+                        .map_or("N/A".to_string(), |(tx, url)| format!("[{:?}]({})", tx, url))));
+                report.push_str(&format!("- **Payment Date**: {}\n",
+                    budget_details.payment_date().map_or("N/A".to_string(), |d| d.format("%Y-%m-%d").to_string())));
+            }
+            if !budget_details.payments().is_empty() {
+                report.push_str(&format!("- **Payment Status**: {}\n\n", budget_details.payment_status()));
+                report.push_str("| Date | Amount | Token | Transaction |\n|------|--------|-------|-------------|\n");
+                for payment in budget_details.payments() {
+                    report.push_str(&format!("| {} | {} | {} | {} |\n",
+                        payment.date.format("%Y-%m-%d"), payment.amount, payment.token, payment.tx.as_deref().unwrap_or("N/A")));
+                }
+            }
+            report.push_str("\n");
+        }
+    
+        // Raffle Information
+        if let Some(raffle) = self.active_raffle_for_proposal(proposal_id) {
+            report.push_str("## Raffle Information\n\n");
+            report.push_str(&format!("- **Raffle ID**: {}\n", raffle.id()));
+            for superseded in self.superseded_raffles(raffle.id()) {
+                report.push_str(&format!("- **Supersedes**: {} (randomness block {}, superseded by {})\n",
+                    superseded.id(),
+                    superseded.config().randomness_block(),
+                    superseded.superseded_by().map_or("N/A".to_string(), |id| id.to_string())));
+            }
+            report.push_str(&format!("- **Initiation Block**: {}\n", raffle.config().initiation_block()));
+            report.push_str(&format!("- **Randomness Block**: [{}]({})\n", 
+                raffle.config().randomness_block(), raffle.etherscan_url()));
+            report.push_str(&format!("- **Block Randomness**: {}\n", raffle.config().block_randomness()));
+            if raffle.config().is_seeded() {
+                report.push_str(&format!("- **Randomness Source**: {}\n", raffle.randomness_source()));
+            }
+            if let Some(timestamp) = raffle.config().randomness_timestamp() {
+                report.push_str(&format!("- **Randomness Drawn At**: {}\n", timestamp.format("%Y-%m-%d %H:%M:%S UTC")));
+            }
+            report.push_str(&format!("- **Total Counted Seats**: {}\n", raffle.config().total_counted_seats()));
+            report.push_str(&format!("- **Max Earner Seats**: {}\n", raffle.config().max_earner_seats()));
+            report.push_str(&format!("- **Competition**: {} teams competed for {} counted seats ({:.1}x)\n",
+                raffle.eligible_team_count(), raffle.config().total_counted_seats(), raffle.competition_ratio()));
+            report.push_str(&format!("- **Is Historical**: {}\n\n", raffle.config().is_historical()));
+    
+            // Team Snapshots
+            report.push_str(&self.generate_team_snapshots_table(raffle));
+    
+            // Raffle Outcome
+            if let Some(result) = raffle.result() {
+                report.push_str("### Raffle Outcome\n\n");
+                self.generate_raffle_outcome(&mut report, raffle, result);
+            }
+        } else {
+            report.push_str("## Raffle Information\n\nNo raffle was conducted for this proposal.\n\n");
+        }
+    
+        // Voting Information
+        if let Some(vote) = report_vote {
+            report.push_str("## Voting Information\n\n");
+            if let Some(informal) = vote.promoted_from().and_then(|id| self.state.get_vote(&id)) {
+                report.push_str(&Self::informal_stage_section(informal));
+            }
+            report.push_str("### Vote Details\n\n");
+            report.push_str(&format!("- **Vote ID**: {}\n", vote.id()));
+            report.push_str(&format!("- **Type**: {:?}\n", vote.vote_type()));
+            report.push_str(&format!("- **Status**: {:?}\n", vote.status()));
+            report.push_str(&format!("- **Opened**: {}\n", vote.opened_at().format("%Y-%m-%d %H:%M:%S")));
+            if let Some(closed_at) = vote.closed_at() {
+                report.push_str(&format!("- **Closed**: {}\n", closed_at.format("%Y-%m-%d %H:%M:%S")));
+            }
+            if let Some(result) = vote.result() {
+                match result {
+                    VoteResult::Formal { passed: true, at_threshold: true, .. } => {
+                        report.push_str("- **Result**: Passed (at exactly the threshold)\n\n");
+                    },
+                    VoteResult::Formal { passed, .. } | VoteResult::Weighted { passed, .. } => {
+                        report.push_str(&format!("- **Result**: {}\n\n", if *passed { "Passed" } else { "Not Passed" }));
+                    },
+                    VoteResult::Informal { .. } => {
+                        report.push_str("- **Result**: Informal (No Pass/Fail)\n\n");
+                    },
+                    VoteResult::MultiOption { winner, .. } => {
+                        report.push_str(&format!("- **Result**: Option {} won\n\n", winner + 1));
+                    }
+                }
+            }
+    
+            // Participation
+            report.push_str("### Participation\n\n");
+            report.push_str(&self.generate_vote_participation_tables(vote));
+    
+            // Vote Counts
+            if !vote.is_historical() {
+                report.push_str("### Vote Counts\n");
+                match vote.vote_type() {
+                    VoteType::Formal { total_eligible_seats, .. } => {
+                        if let Some(VoteResult::Formal { counted, uncounted, .. }) = vote.result() {
+                            let absent = *total_eligible_seats as i32 - counted.total_cast() as i32;
+                            
+                            report.push_str("#### Counted Votes\n");
+                            report.push_str(&format!("- **Yes**: {}\n", counted.yes()));
+                            report.push_str(&format!("- **No**: {}\n", counted.no()));
+                            if counted.abstain() > 0 {
+                                report.push_str(&format!("- **Abstain**: {}\n", counted.abstain()));
+                            }
+                            if absent > 0 {
+                                report.push_str(&format!("- **Absent**: {}\n", absent));
+                            }
+    
+                            report.push_str("\n#### Uncounted Votes\n");
+                            report.push_str(&format!("- **Yes**: {}\n", uncounted.yes()));
+                            report.push_str(&format!("- **No**: {}\n", uncounted.no()));
+                            if uncounted.abstain() > 0 {
+                                report.push_str(&format!("- **Abstain**: {}\n", uncounted.abstain()));
+                            }
+                        }
+                    },
+                    VoteType::Weighted { .. } => {
+                        if let Some(VoteResult::Weighted { counted, uncounted, .. }) = vote.result() {
+                            report.push_str("#### Counted Votes (points)\n");
+                            report.push_str(&format!("- **Yes**: {}\n", counted.yes()));
+                            report.push_str(&format!("- **No**: {}\n", counted.no()));
+                            if counted.abstain() > 0 {
+                                report.push_str(&format!("- **Abstain**: {}\n", counted.abstain()));
+                            }
+                            report.push_str(&format!("- **Total Weight**: {}\n", counted.total_weight()));
+
+                            report.push_str("\n#### Uncounted Votes\n");
+                            report.push_str(&format!("- **Yes**: {}\n", uncounted.yes()));
+                            report.push_str(&format!("- **No**: {}\n", uncounted.no()));
+                            if uncounted.abstain() > 0 {
+                                report.push_str(&format!("- **Abstain**: {}\n", uncounted.abstain()));
+                            }
+                        }
+                    },
+                    VoteType::MultiOption { options, .. } => {
+                        if let Some(VoteResult::MultiOption { tallies, uncounted_tallies, .. }) = vote.result() {
+                            report.push_str("| Option | Counted | Uncounted |\n");
+                            report.push_str("|--------|---------|-----------|\n");
+                            for (index, option) in options.iter().enumerate() {
+                                report.push_str(&format!("| {} | {} | {} |\n",
+                                    option,
+                                    tallies.get(index).copied().unwrap_or(0),
+                                    uncounted_tallies.get(index).copied().unwrap_or(0)));
+                            }
+                        }
+                    },
+                    VoteType::Informal { .. } => {
+                        if let Some(VoteResult::Informal { count, weighted }) = vote.result() {
+                            report.push_str(&format!("- **Yes**: {}\n", count.yes()));
+                            report.push_str(&format!("- **No**: {}\n", count.no()));
+                            if count.abstain() > 0 {
+                                report.push_str(&format!("- **Abstain**: {}\n", count.abstain()));
+                            }
+                            if let Some(weighted) = weighted {
+                                report.push_str("\n#### Revenue Weighted\n");
+                                report.push_str(&format!("- **Yes**: {}\n", weighted.yes()));
+                                report.push_str(&format!("- **No**: {}\n", weighted.no()));
+                                if weighted.abstain() > 0 {
+                                    report.push_str(&format!("- **Abstain**: {}\n", weighted.abstain()));
+                                }
+                            }
+                        }
+                    }
+                }
+            } else {
+                report.push_str("Vote counts not available for historical votes.\n");
+            }
+        } else {
+            report.push_str("## Voting Information\n\nNo vote was conducted for this proposal.\n\n");
+        }
+    
+        Ok(report)
+    }
+
+    pub fn generate_team_snapshots_table(&self, raffle: &Raffle) -> String {
+        let mut table = String::from("### Team Snapshots\n\n");
+        table.push_str("| Team Name | Status | Revenue | Ballot Range | Ticket Count |\n");
+        table.push_str("|-----------|--------|---------|--------------|--------------|\n");
+
+        for snapshot in raffle.team_snapshots() {
+            let team_name = self.snapshot_display_name(snapshot);
+            let redacted = self.state.find_team(&snapshot.id()).is_some_and(|team| team.is_redacted());
+            
+            let status = match &snapshot.status() {
+                TeamStatus::Earner { .. } => "Earner",
+                TeamStatus::Supporter => "Supporter",
+                TeamStatus::Inactive => "Inactive",
+            };
+
+            let revenue = match &snapshot.status() {
+                _ if redacted => "Redacted".to_string(),
+                TeamStatus::Earner { trailing_monthly_revenue } => 
+                    trailing_monthly_revenue.iter()
+                        .map(|r| r.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                _ => "N/A".to_string(),
+            };
+
+            let tickets: Vec<_> = raffle.tickets().iter()
+                .filter(|t| t.team_id() == snapshot.id())
+                .collect();
+            
+            let ballot_range = if !tickets.is_empty() {
+                format!("{} - {}", 
+                    tickets.first().unwrap().index(), 
+                    tickets.last().unwrap().index())
+            } else {
+                "N/A".to_string()
+            };
+
+            let ticket_count = tickets.len();
+
+            table.push_str(&format!("| {} | {} | {} | {} | {} |\n",
+                team_name, status, revenue, ballot_range, ticket_count));
+        }
+
+        table.push_str("\n");
+        table
+    }
+
+    pub fn generate_raffle_outcome(&self, report: &mut String, raffle: &Raffle, result: &RaffleResult) {
+        let counted_earners: Vec<_> = result.counted().iter()
+            .filter(|&team_id| raffle.team_snapshots().iter().any(|s| s.id() == *team_id && matches!(s.status(), TeamStatus::Earner { .. })))
+            .collect();
+        let counted_supporters: Vec<_> = result.counted().iter()
+            .filter(|&team_id| raffle.team_snapshots().iter().any(|s| s.id() == *team_id && matches!(s.status(), TeamStatus::Supporter)))
+            .collect();
+    
+        report.push_str(&format!("Score ties resolved by: {}\n\n", raffle.config().tie_break()));
+        report.push_str(&format!("#### Counted Seats (Total: {})\n\n", result.counted().len()));
+        
+        report.push_str(&format!("##### Earner Seats ({})\n", counted_earners.len()));
+        for team_id in counted_earners {
+            if let Some(snapshot) = raffle.team_snapshots().iter().find(|s| s.id() == *team_id) {
+                let best_score = raffle.tickets().iter()
+                    .filter(|t| t.team_id() == *team_id)
+                    .map(|t| t.score())
+                    .max_by(|a, b| a.partial_cmp(b).unwrap())
+                    .unwrap_or(0.0);
+                report.push_str(&format!("- {} (Best Score: {:.4})\n", self.snapshot_display_name(snapshot), best_score));
+            }
+        }
+    
+        report.push_str(&format!("\n##### Supporter Seats ({})\n", counted_supporters.len()));
+        for team_id in counted_supporters {
+            if let Some(snapshot) = raffle.team_snapshots().iter().find(|s| s.id() == *team_id) {
+                let best_score = raffle.tickets().iter()
+                    .filter(|t| t.team_id() == *team_id)
+                    .map(|t| t.score())
+                    .max_by(|a, b| a.partial_cmp(b).unwrap())
+                    .unwrap_or(0.0);
+                report.push_str(&format!("- {} (Best Score: {:.4})\n", self.snapshot_display_name(snapshot), best_score));
+            }
+        }
+    
+        report.push_str("\n#### Uncounted Seats\n");
+        for team_id in result.uncounted() {
+            if let Some(snapshot) = raffle.team_snapshots().iter().find(|s| s.id() == *team_id) {
+                let best_score = raffle.tickets().iter()
+                    .filter(|t| t.team_id() == *team_id)
+                    .map(|t| t.score())
+                    .max_by(|a, b| a.partial_cmp(b).unwrap())
+                    .unwrap_or(0.0);
+                report.push_str(&format!("- {} (Best Score: {:.4})\n", self.snapshot_display_name(snapshot), best_score));
+            }
+        }
+    }
+
+    pub fn generate_vote_participation_tables(&self, vote: &Vote) -> String {
+        let mut tables = String::new();
+
+        match &vote.participation() {
+            VoteParticipation::Formal { counted, uncounted } => {
+                tables.push_str("#### Counted Votes\n");
+                tables.push_str("| Team | Points Credited |\n");
+                tables.push_str("|------|------------------|\n");
+                for &team_id in counted {
+                    if let Some(team) = self.state.find_team(&team_id) {
+                        tables.push_str(&format!("| {} | {} |\n", team.name(), self.config.counted_vote_points));
+                    }
+                }
+
+                tables.push_str("\n#### Uncounted Votes\n");
+                tables.push_str("| Team | Points Credited |\n");
+                tables.push_str("|------|------------------|\n");
+                for &team_id in uncounted {
+                    if let Some(team) = self.state.find_team(&team_id) {
+                        tables.push_str(&format!("| {} | {} |\n", team.name(), self.config.uncounted_vote_points));
+                    }
+                }
+            },
+            VoteParticipation::Informal(participants) => {
+                tables.push_str("#### Participants\n");
+                tables.push_str("| Team | Points Credited |\n");
+                tables.push_str("|------|------------------|\n");
+                for &team_id in participants {
+                    if let Some(team) = self.state.find_team(&team_id) {
+                        tables.push_str(&format!("| {} | 0 |\n", team.name()));
+                    }
+                }
+            },
+        }
+
+        tables
+    }
+
+    pub fn calculate_days_between(&self, start: NaiveDate, end: NaiveDate) -> i64 {
+        (end - start).num_days()
+    }
+
+    pub fn get_current_or_specified_epoch(&self, epoch_name: Option<&str>) -> Result<(&Epoch, Uuid), &'static str> {
+        match epoch_name {
+            Some(name) => {
+                let (id, epoch) = self.state.epochs().iter()
+                    .find(|(_, e)| e.name() == name)
+                    .ok_or("Specified epoch not found")?;
+                Ok((epoch, *id))
+            },
+            None => {
+                let current_epoch_id = self.state.current_epoch().ok_or("No active epoch")?;
+                let epoch = self.state.epochs().get(&current_epoch_id).ok_or("Current epoch not found")?;
+                Ok((epoch, current_epoch_id))
+            }
+        }
+    }
+
+    pub fn generate_point_report(&self, epoch_name: Option<&str>) -> Result<String, &'static str> {
+        let (epoch, epoch_id) = self.get_current_or_specified_epoch(epoch_name)?;
+        self.generate_point_report_for_epoch(epoch_id)
+    }
+
+    pub fn generate_point_report_for_epoch(&self, epoch_id: Uuid) -> Result<String, &'static str> {
+        let epoch = self.state.epochs().get(&epoch_id).ok_or("Epoch not found")?;
+        let mut report = String::new();
+
+        for team in self.sorted_teams() {
+            let team_id = &team.id();
+            let mut team_report = format!("{}, ", team.name());
+            let mut total_points = 0;
+            let mut allocations = Vec::new();
+
+            for proposal_id in epoch.associated_proposals() {
+                if let Some(proposal) = self.state.get_proposal(&proposal_id) {
+                    if let Some(vote) = self.state.votes().values().find(|v| v.proposal_id() == *proposal_id) {
+                        let (participation_type, points) = match (vote.vote_type(), vote.participation()) {
+                            (VoteType::Formal { counted_points, uncounted_points, .. } | VoteType::Weighted { counted_points, uncounted_points, .. } | VoteType::MultiOption { counted_points, uncounted_points, .. }, VoteParticipation::Formal { counted, uncounted }) => {
+                                if counted.contains(team_id) {
+                                    ("Counted", *counted_points)
+                                } else if uncounted.contains(team_id) {
+                                    ("Uncounted", *uncounted_points)
+                                } else {
+                                    continue;
+                                }
+                            },
+                            (VoteType::Informal { .. }, VoteParticipation::Informal(participants)) => {
+                                if participants.contains(team_id) {
+                                    ("Informal", 0)
+                                } else {
+                                    continue;
+                                }
+                            },
+                            _ => continue,
+                        };
+
+                        total_points += points;
+                        allocations.push(format!("{}: {} voter, {} points", 
+                            proposal.title(), participation_type, points));
+                    }
+                }
+            }
+
+            team_report.push_str(&format!("{} points\n", total_points));
+            for allocation in allocations {
+                team_report.push_str(&format!("{}\n", allocation));
+            }
+            team_report.push('\n');
+
+            report.push_str(&team_report);
+        }
+
+        Ok(report)
+    }
+
+    pub fn get_team_points_history(&self, team_id: Uuid) -> Result<Vec<(Uuid, u32)>, &'static str> {
+        self.epochs_by_start_date().into_iter()
+            .map(|epoch| {
+                self.get_team_points_for_epoch(team_id, epoch.id())
+                    .map(|points| (epoch.id(), points))
+            })
+            .collect()
+    }
+
+    pub fn get_team_points_for_epoch(&self, team_id: Uuid, epoch_id: Uuid) -> Result<u32, &'static str> {
+        if let Some(points) = self.team_points.lock().unwrap().get(&(team_id, epoch_id)) {
+            return Ok(*points);
+        }
+
+        let epoch = self.state.epochs().get(&epoch_id).ok_or("Epoch not found")?;
+        let mut total_points = 0;
+
+        for proposal_id in epoch.associated_proposals() {
+            if let Some(vote) = self.state.votes().values().find(|v| v.proposal_id() == *proposal_id) {
+                if let (VoteType::Formal { counted_points, uncounted_points, .. } | VoteType::Weighted { counted_points, uncounted_points, .. } | VoteType::MultiOption { counted_points, uncounted_points, .. }, VoteParticipation::Formal { counted, uncounted }) = (vote.vote_type(), vote.participation()) {
+                    if counted.contains(&team_id) {
+                        total_points += counted_points;
+                    } else if uncounted.contains(&team_id) {
+                        total_points += uncounted_points;
+                    }
+                }
+            }
+        }
+
+        self.team_points.lock().unwrap().insert((team_id, epoch_id), total_points);
+        Ok(total_points)
+    }
+
+    /// Rebuilds the cached points of every team for the epoch and returns the epoch's votes whose
+    /// participants were not drawn in the vote's raffle, which point to data-entry errors.
+    pub fn recompute_epoch_points(&mut self, epoch_id: Uuid) -> Result<Vec<IntegrityError>, &'static str> {
+        let epoch = self.state.get_epoch(&epoch_id).ok_or("Epoch not found")?;
+
+        let mut errors = Vec::new();
+        for proposal_id in epoch.associated_proposals() {
+            for vote in self.state.votes().values().filter(|v| v.proposal_id() == *proposal_id) {
+                let VoteParticipation::Formal { counted, uncounted } = vote.participation() else { continue };
+                let Some(result) = vote.raffle_id()
+                    .and_then(|raffle_id| self.state.get_raffle(&raffle_id))
+                    .and_then(|raffle| raffle.result()) else { continue };
+
+                for team_id in counted.iter().chain(uncounted) {
+                    if !result.counted().contains(team_id) && !result.uncounted().contains(team_id) {
+                        errors.push(IntegrityError::VoteUnknownParticipant { vote_id: vote.id(), team_id: *team_id });
+                    }
+                }
+            }
+        }
+        errors.sort();
+
+        self.team_points.lock().unwrap().retain(|(_, cached_epoch), _| *cached_epoch != epoch_id);
+        let team_ids: Vec<Uuid> = self.state.current_state().teams().keys().copied().collect();
+        for team_id in team_ids {
+            self.get_team_points_for_epoch(team_id, epoch_id)?;
+        }
+
+        Ok(errors)
+    }
+
+    pub fn close_epoch(&mut self, epoch_name: Option<&str>, carry_over_to: Option<Uuid>) -> Result<(), Box<dyn Error>> {
+        self.close_epoch_with_warnings(epoch_name, false, carry_over_to).map(|_| ())
+    }
+
+    /// Scales the amounts of adjusted teams by their multiplier and, with `redistribute_withheld_rewards`,
+    /// shares what was withheld among the unadjusted teams by points. Otherwise it stays unallocated.
+    fn apply_reward_adjustments(&self, epoch: &Epoch, team_ids: &[Uuid], points: &[u32], amounts: &mut [f64]) {
+        let rounding = self.config.reward_rounding;
+        let mut withheld = 0.0;
+        let mut unadjusted = Vec::new();
+        for (index, team_id) in team_ids.iter().enumerate() {
+            match epoch.reward_adjustments().get(team_id) {
+                Some(multiplier) => {
+                    let adjusted = rounding.round(amounts[index] * multiplier).min(amounts[index]);
+                    withheld += amounts[index] - adjusted;
+                    amounts[index] = adjusted;
+                },
+                None if points[index] > 0 => unadjusted.push(index),
+                None => {},
+            }
+        }
+
+        if !self.config.redistribute_withheld_rewards || unadjusted.is_empty() || withheld <= 0.0 {
+            return;
+        }
+        let pool = unadjusted.iter().map(|&index| amounts[index]).sum::<f64>() + withheld;
+        let unadjusted_points: Vec<u32> = unadjusted.iter().map(|&index| points[index]).collect();
+        for (index, amount) in unadjusted.into_iter().zip(rounding.allocate(pool, &unadjusted_points)) {
+            amounts[index] = amount;
+        }
+    }
+
+    /// Each rewarded team's share of the epoch and of `reward_amount` as `close_epoch` allocates them,
+    /// adjustments included, as `(team_id, percentage, amount)` in team id order.
+    /// `total_points` must be above zero.
+    fn allocate_epoch_rewards(&self, epoch: &Epoch, reward_amount: f64, total_points: u32) -> Vec<(Uuid, f64, f64)> {
+        let team_ids = self.epoch_reward_team_ids(epoch.id());
+        let points: Vec<u32> = team_ids.iter()
+            .map(|team_id| self.calculate_team_points_for_epoch(*team_id, epoch.id()))
+            .collect();
+        let mut amounts = self.config.reward_rounding.allocate(reward_amount, &points);
+        let adjusted = !epoch.reward_adjustments().is_empty();
+        if adjusted {
+            self.apply_reward_adjustments(epoch, &team_ids, &points, &mut amounts);
+        }
+
+        team_ids.into_iter().zip(points).zip(amounts)
+            .map(|((team_id, team_points), amount)| {
+                // Adjusted amounts no longer follow the points, so the share of the pool is used instead
+                let percentage = if adjusted && reward_amount > 0.0 {
+                    amount / reward_amount * 100.0
+                } else {
+                    team_points as f64 / total_points as f64 * 100.0
+                };
+                (team_id, percentage, amount)
+            })
+            .collect()
+    }
+
+    /// What each team would receive if the epoch closed now, as `(team name, percentage, amount)`
+    /// with the largest share first. Nothing is changed. Without a reward the amounts are 0, and
+    /// without any points the list is empty. A closed epoch returns the rewards it recorded.
+    pub fn preview_epoch_rewards(&self, epoch_id: Uuid) -> Result<Vec<(String, f64, f64)>, Box<dyn Error>> {
+        let epoch = self.state.get_epoch(&epoch_id).ok_or("Epoch not found")?;
+        let team_name = |team_id: &Uuid| self.state.find_team(team_id)
+            .map(|team| team.name().to_string())
+            .unwrap_or_else(|| team_id.to_string());
+
+        let mut preview: Vec<(String, f64, f64)> = if epoch.is_closed() {
+            epoch.team_rewards().iter()
+                .map(|(team_id, reward)| (team_name(team_id), reward.percentage(), reward.amount()))
+                .collect()
+        } else {
+            let total_points = self.get_total_points_for_epoch(epoch_id);
+            if total_points == 0 {
+                return Ok(Vec::new());
+            }
+            let reward_amount = epoch.reward().map_or(0.0, |reward| reward.amount());
+            self.allocate_epoch_rewards(epoch, reward_amount, total_points).into_iter()
+                .filter(|(_, percentage, amount)| *percentage > 0.0 || *amount > 0.0)
+                .map(|(team_id, percentage, amount)| (team_name(&team_id), percentage, amount))
+                .collect()
+        };
+        preview.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        Ok(preview)
+    }
+
+    /// Closes the epoch, returning a warning when teams earned points but no reward is set,
+    /// since those points will never be paid out. `confirm_no_reward` suppresses it.
+    /// Whatever part of the reward is not allocated to teams (the whole pool when no points
+    /// were earned) is recorded on the epoch and, with `carry_over_to`, added to that planned epoch's reward.
+    pub fn close_epoch_with_warnings(&mut self, epoch_name: Option<&str>, confirm_no_reward: bool, carry_over_to: Option<Uuid>) -> Result<Option<String>, Box<dyn Error>> {
+        let epoch_id = match epoch_name {
+            Some(name) => self.require_epoch_id(name)?,
+            None => self.state.current_epoch()
+                .ok_or("No active epoch")?
+        };
+    
+        // Check for actionable proposals
+        let actionable_proposals = self.get_proposals_for_epoch(epoch_id)
+            .iter()
+            .filter(|p| p.is_actionable())
+            .count();
+    
+        if actionable_proposals > 0 {
+            return Err(format!("Cannot close epoch: {} actionable proposals remaining", actionable_proposals).into());
+        }
+
+        if let Some(target_id) = carry_over_to {
+            if target_id == epoch_id {
+                return Err("Cannot carry over into the epoch being closed".into());
+            }
+            let target = self.state.get_epoch(&target_id)
+                .ok_or("Carry-over epoch not found")?;
+            if !target.is_planned() {
+                return Err(format!("Cannot carry over into '{}': only planned epochs can receive a carry-over", target.name()).into());
+            }
+            let source_token = self.state.get_epoch(&epoch_id).and_then(|e| e.reward()).map(|r| r.token());
+            if let (Some(source_token), Some(target_reward)) = (source_token, target.reward()) {
+                if source_token != target_reward.token() {
+                    return Err(format!(
+                        "Cannot carry over {} into '{}', whose reward is in {}",
+                        source_token, target.name(), target_reward.token()
+                    ).into());
+                }
+            }
+        }
+    
+        let total_points = self.get_total_points_for_epoch(epoch_id);
+        let mut team_rewards = HashMap::new();
+        let mut warning = None;
+    
+        // Calculate rewards
+        {
+            let epoch = self.state.get_epoch(&epoch_id)
+                .ok_or("Epoch not found")?;
+
+            if epoch.is_closed() {
+                return Err("Epoch is already closed".into());
+            }
+
+            if epoch.reward().is_none() && total_points > 0 && !confirm_no_reward {
+                let message = format!(
+                    "WARNING: epoch '{}' was closed without a reward, so the {} points teams earned will not be paid out. \
+                     Set a reward before closing, or pass --confirm-no-reward if this is intended.",
+                    epoch.name(), total_points
+                );
+                warn!("{}", message);
+                warning = Some(message);
+            }
+
+            if let Some(reward) = epoch.reward().filter(|_| total_points > 0) {
+                for (team_id, percentage, amount) in self.allocate_epoch_rewards(epoch, reward.amount(), total_points) {
+                    match TeamReward::new(percentage, amount) {
+                        Ok(team_reward) => {
+                            team_rewards.insert(team_id, team_reward);
+                        },
+                        Err(e) => return Err(format!("Failed to create team reward: {}", e).into()),
+                    }
+                }
+            }
+        }
+    
+         // Update epoch
+        {
+            let epoch = self.state.get_epoch_mut(&epoch_id)
+                .ok_or("Epoch not found")?;
+
+            epoch.set_status(EpochStatus::Closed);
+            epoch.set_reward_rounding(self.config.reward_rounding);
+            for (team_id, team_reward) in team_rewards {
+                epoch.set_team_reward(team_id, team_reward.percentage(), team_reward.amount())?;
+            }
+            if let Some(token) = epoch.reward().map(|r| r.token().to_string()) {
+                epoch.set_unallocated(token, self.config.reward_rounding.round(epoch.remaining_reward_amount()));
+            }
+        }
+
+        if let Some(target_id) = carry_over_to {
+            let epoch = self.state.get_epoch(&epoch_id)
+                .ok_or("Epoch not found")?;
+            let carry_over: Vec<(String, f64)> = epoch.unallocated().iter()
+                .filter(|(_, &amount)| amount > 0.0)
+                .map(|(token, &amount)| (token.clone(), amount))
+                .collect();
+
+            if !carry_over.is_empty() {
+                let target = self.state.get_epoch_mut(&target_id)
+                    .ok_or("Carry-over epoch not found")?;
+                for (token, amount) in carry_over {
+                    target.add_carry_over(&token, amount)?;
+                }
+                self.state.get_epoch_mut(&epoch_id)
+                    .ok_or("Epoch not found")?
+                    .set_carried_over_to(Some(target_id));
+            }
+        }
+
+        // Clear current_epoch if this was the active epoch
+        if self.state.current_epoch() == Some(epoch_id) {
+            self.state.set_current_epoch(None);
+        }
+
+        self.save_state()?;
+
+        Ok(warning)
+    }
+
+    pub fn get_total_points_for_epoch(&self, epoch_id: Uuid) -> u32 {
+        self.epoch_reward_team_ids(epoch_id).into_iter()
+            .map(|team_id| self.calculate_team_points_for_epoch(team_id, epoch_id))
+            .sum()
+    }
+
+    /// The roster plus archived teams that earned points in the epoch, so archiving a team
+    /// does not forfeit what it already earned. Sorted by id.
+    fn epoch_reward_team_ids(&self, epoch_id: Uuid) -> Vec<Uuid> {
+        let archived = self.state.archived_teams().keys()
+            .filter(|team_id| self.calculate_team_points_for_epoch(**team_id, epoch_id) > 0);
+        let mut team_ids: Vec<Uuid> = self.state.current_state().teams().keys().chain(archived).copied().collect();
+        team_ids.sort();
+        team_ids
+    }
+
+    pub fn calculate_team_points_for_epoch(&self, team_id: Uuid, epoch_id: Uuid) -> u32 {
+        let epoch = match self.state.epochs().get(&epoch_id) {
+            Some(e) => e,
+            None => return 0,
+        };
+
+        epoch.associated_proposals().iter()
+            .filter_map(|proposal_id| self.state.votes().values().find(|v| v.proposal_id() == *proposal_id))
+            .map(|vote| match (vote.vote_type(), vote.participation()) {
+                (VoteType::Formal { counted_points, uncounted_points, .. } | VoteType::Weighted { counted_points, uncounted_points, .. } | VoteType::MultiOption { counted_points, uncounted_points, .. }, VoteParticipation::Formal { counted, uncounted }) => {
+                    if counted.contains(&team_id) {
+                        *counted_points
+                    } else if uncounted.contains(&team_id) {
+                        *uncounted_points
+                    } else {
+                        0
+                    }
+                },
+                _ => 0,
+            })
+            .sum()
+    }
+
+    pub fn generate_end_of_epoch_report(&self, epoch_name: &str) -> Result<PathBuf, Box<dyn Error>> {
+        self.generate_end_of_epoch_report_with_format(epoch_name, ReportFormat::Markdown)
+    }
+
+    /// Writes the end of epoch report next to the state file, as Markdown or as a standalone HTML page,
+    /// and returns its path.
+    pub fn generate_end_of_epoch_report_with_format(&self, epoch_name: &str, format: ReportFormat) -> Result<PathBuf, Box<dyn Error>> {
+        let (report, extension) = match format {
+            ReportFormat::Markdown => (self.generate_end_of_epoch_report_markdown(epoch_name)?, "md"),
+            ReportFormat::Html => (self.generate_end_of_epoch_report_html(epoch_name)?, "html"),
+        };
+
+        // Save the report
+        let file_name = format!("{}-epoch_report.{}", Utc::now().format("%Y%m%d"), extension);
+        let sanitized_epoch_name = FileSystem::sanitize_filename(epoch_name);
+        let report_path = PathBuf::from(&self.config.state_file)
+            .parent()
+            .unwrap()
+            .join("reports")
+            .join(sanitized_epoch_name)
+            .join(file_name);
+
+        fs::create_dir_all(report_path.parent().unwrap())?;
+        fs::write(&report_path, report)?;
+
+        println!("End of Epoch Report generated: {:?}", report_path);
+
+        Ok(report_path)
+    }
+
+    /// Closes the epoch, writes its end of epoch report and the reports of its closed proposals,
+    /// and zips the epoch's reports directory into `{epoch}-archive.zip` beside it.
+    /// Nothing is written if the epoch cannot be closed.
+    pub fn finalize_epoch(&mut self, epoch_name: &str) -> Result<PathBuf, Box<dyn Error>> {
+        let epoch_id = self.require_epoch_id(epoch_name)?;
+
+        self.close_epoch(Some(epoch_name), None)?;
+        self.generate_end_of_epoch_report(epoch_name)?;
+
+        let closed_proposals: Vec<Uuid> = self.get_proposals_for_epoch(epoch_id)
+            .into_iter()
+            .filter(|p| p.is_closed())
+            .map(|p| p.id())
+            .collect();
+        for proposal_id in closed_proposals {
+            self.generate_and_save_proposal_report(proposal_id, epoch_name)?;
+        }
+
+        let sanitized_epoch_name = FileSystem::sanitize_filename(epoch_name);
+        let reports_dir = PathBuf::from(&self.config.state_file)
+            .parent()
+            .unwrap()
+            .join("reports");
+        let archive_path = reports_dir.join(format!("{}-archive.zip", sanitized_epoch_name));
+        FileSystem::zip_directory(&reports_dir.join(sanitized_epoch_name), &archive_path)?;
+
+        Ok(archive_path)
+    }
+
+    fn closed_epoch_by_name(&self, epoch_name: &str) -> Result<&Epoch, Box<dyn Error>> {
+        let epoch = self.state.epochs().values()
+            .find(|e| e.name() == epoch_name)
+            .ok_or_else(|| NotFoundError { kind: "Epoch", name: epoch_name.to_string() })?;
+
+        if !epoch.is_closed() {
+            return Err("Cannot generate report: Epoch is not closed".into());
+        }
+
+        Ok(epoch)
+    }
+
+    fn generate_end_of_epoch_report_markdown(&self, epoch_name: &str) -> Result<String, Box<dyn Error>> {
+        let epoch = self.closed_epoch_by_name(epoch_name)?;
+
+        let mut report = String::new();
+
+        // Generate epoch summary
+        report.push_str(&self.generate_epoch_summary(epoch)?);
+
+        // Generate spend subtotals by tag
+        report.push_str(&self.generate_tag_spend_summary(epoch));
+
+        // Generate proposal tables and individual reports
+        report.push_str(&self.generate_proposal_tables(epoch)?);
+
+        // Generate team summary
+        report.push_str(&self.generate_team_summary(epoch)?);
+
+        Ok(report)
+    }
+
+    /// Renders the end of epoch report as a self-contained HTML document with the same
+    /// sections as the Markdown report. Individual proposal reports are saved as a side effect.
+    pub fn generate_end_of_epoch_report_html(&self, epoch_name: &str) -> Result<String, Box<dyn Error>> {
+        let epoch = self.closed_epoch_by_name(epoch_name)?;
+        let title = format!("End of Epoch Report: {}", escape_html(epoch.name()));
+
+        let mut body = format!("<h1>{}</h1>\n<h2>Epoch Summary</h2>\n<ul>\n", title);
+        for (label, value) in self.epoch_summary_fields(epoch) {
+            body.push_str(&format!("<li><strong>{}</strong>: {}</li>\n", label, escape_html(&value)));
+        }
+        body.push_str("</ul>\n");
+
+        let tag_rows = self.tag_spend_rows(epoch);
+        if !tag_rows.is_empty() {
+            let rows: Vec<Vec<String>> = tag_rows.into_iter()
+                .map(|(tag, count, amounts)| vec![escape_html(&tag), count.to_string(), escape_html(&amounts)])
+                .collect();
+            body.push_str("<h2>Spend by Tag</h2>\n");
+            body.push_str(&html_table(&["Tag", "Proposals", "Amounts"], &rows));
+        }
+
+        for (status, resolution) in Self::report_statuses() {
+            for (heading, budget, columns) in [
+                (format!("{} Proposals", status), true, &PROPOSAL_TABLE_COLUMNS[..]),
+                (format!("{} Non-Budget Proposals", status), false, &NON_BUDGET_TABLE_COLUMNS[..]),
+            ] {
+                let proposal_rows = self.proposal_table_rows(epoch, resolution.clone(), budget)?;
+                if proposal_rows.is_empty() {
+                    continue;
+                }
+                let rows: Vec<Vec<String>> = proposal_rows.into_iter()
+                    .map(|(cells, report_link)| {
+                        let mut row: Vec<String> = cells.iter().map(|cell| escape_html(cell)).collect();
+                        row.push(format!("<a href=\"{}\">Report</a>", escape_html(&report_link)));
+                        row
+                    })
+                    .collect();
+                body.push_str(&format!("<h3>{}</h3>\n", heading));
+                body.push_str(&html_table(columns, &rows));
+            }
+        }
+
+        let team_rows: Vec<Vec<String>> = self.team_summary_rows(epoch).into_iter()
+            .map(|cells| cells.iter().map(|cell| escape_html(cell)).collect())
+            .collect();
+        body.push_str("<h2>Team Summary</h2>\n");
+        body.push_str(&html_table(&TEAM_SUMMARY_COLUMNS, &team_rows));
+
+        Ok(format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+            <style>\n{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+            title,
+            HTML_REPORT_STYLE,
+            body
+        ))
+    }
+
+    pub fn generate_epoch_summary(&self, epoch: &Epoch) -> Result<String, Box<dyn Error>> {
+        let mut summary = format!("# End of Epoch Report: {}\n\n## Epoch Summary\n", epoch.name());
+        for (label, value) in self.epoch_summary_fields(epoch) {
+            summary.push_str(&format!("- **{}**: {}\n", label, value));
+        }
+        summary.push('\n');
+
+        Ok(summary)
+    }
+
+    fn epoch_summary_fields(&self, epoch: &Epoch) -> Vec<(&'static str, String)> {
+        let proposals: Vec<&Proposal> = self.get_proposals_for_epoch(epoch.id()).into_iter()
+            .filter(|p| !p.is_draft())
+            .collect();
+        let approved = proposals.iter().filter(|p| matches!(p.resolution(), Some(Resolution::Approved))).count();
+        let rejected = proposals.iter().filter(|p| matches!(p.resolution(), Some(Resolution::Rejected))).count();
+        let retracted = proposals.iter().filter(|p| matches!(p.resolution(), Some(Resolution::Retracted))).count();
+        let by_kind = [ProposalKind::Budget, ProposalKind::Policy, ProposalKind::Informational].iter()
+            .map(|kind| format!("{} {}", proposals.iter().filter(|p| p.kind() == *kind).count(), kind))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut unallocated = if epoch.unallocated().is_empty() {
+            "N/A".to_string()
+        } else {
+            let mut amounts: Vec<String> = epoch.unallocated().iter()
+                .map(|(token, amount)| format!("{:.2} {}", amount, token))
+                .collect();
+            amounts.sort();
+            amounts.join(", ")
+        };
+        if let Some(target) = epoch.carried_over_to().and_then(|id| self.state.get_epoch(&id)) {
+            unallocated.push_str(&format!(" (carried over to {})", target.name()));
+        }
+
+        let mut fields = vec![
+            ("Period", format!("{} to {}", epoch.start_date().format("%Y-%m-%d"), epoch.end_date().format("%Y-%m-%d"))),
+            ("Total Proposals", proposals.len().to_string()),
+            ("Proposals by Kind", by_kind),
+            ("Approved Proposals", approved.to_string()),
+            ("Rejected Proposals", rejected.to_string()),
+            ("Retracted Proposals", retracted.to_string()),
+            ("Total Reward", epoch.reward().map_or("N/A".to_string(), |r| format!("{} {}", r.amount(), r.token()))),
+            ("Unallocated", unallocated),
+            ("Reward Rounding", epoch.reward_rounding().to_string()),
+        ];
+
+        let requested = self.requested_amounts(epoch.id());
+        if !requested.is_empty() {
+            fields.push(("Total Requested", Self::format_token_amounts(&requested)));
+            let usd = match self.amounts_in_usd(&requested) {
+                Some(total) => format!("{:.2} USD", total),
+                None => {
+                    let missing: Vec<&str> = requested.keys()
+                        .filter(|token| self.token_rate(token).is_none())
+                        .map(String::as_str)
+                        .collect();
+                    format!("N/A (no rate for {})", missing.join(", "))
+                }
+            };
+            fields.push(("Total Requested (USD)", usd));
+        }
+
+        if !epoch.reward_adjustments().is_empty() {
+            let mut adjustments: Vec<String> = epoch.reward_adjustments().iter()
+                .map(|(team_id, multiplier)| {
+                    let team_name = self.state.find_team(team_id).map_or_else(|| team_id.to_string(), |team| team.name().to_string());
+                    match epoch.reward_adjustment_reason(team_id).filter(|reason| !reason.is_empty()) {
+                        Some(reason) => format!("{} x{} ({})", team_name, multiplier, reason),
+                        None => format!("{} x{}", team_name, multiplier),
+                    }
+                })
+                .collect();
+            adjustments.sort();
+            fields.push(("Reward Adjustments", adjustments.join(", ")));
+        }
+        fields
+    }
+
+    /// Amounts requested by the epoch's non-draft budget proposals, summed per token
+    pub fn requested_amounts(&self, epoch_id: Uuid) -> BTreeMap<String, f64> {
+        let mut amounts = BTreeMap::new();
+        for proposal in self.get_proposals_for_epoch(epoch_id) {
+            if proposal.is_draft() {
+                continue;
+            }
+            let Some(details) = proposal.budget_request_details() else { continue };
+            for (token, amount) in details.request_amounts() {
+                *amounts.entry(token.clone()).or_insert(0.0) += amount;
+            }
+        }
+        amounts
+    }
+
+    /// The epoch's requested amounts converted at `token_rates` and summed, or `None` if a token has no rate
+    pub fn total_requested_usd(&self, epoch_id: Uuid) -> Option<f64> {
+        self.amounts_in_usd(&self.requested_amounts(epoch_id))
+    }
+
+    fn amounts_in_usd(&self, amounts: &BTreeMap<String, f64>) -> Option<f64> {
+        amounts.iter()
+            .map(|(token, amount)| self.token_rate(token).map(|rate| amount * rate))
+            .sum()
+    }
+
+    /// USD value of one unit of `token`, ignoring case; invalid rates count as missing
+    fn token_rate(&self, token: &str) -> Option<f64> {
+        self.config.token_rates.iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(token))
+            .map(|(_, rate)| *rate)
+            .filter(|rate| rate.is_finite() && *rate >= 0.0)
+    }
+
+    fn format_token_amounts(amounts: &BTreeMap<String, f64>) -> String {
+        amounts.iter()
+            .map(|(token, amount)| format!("{:.2} {}", amount, token))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Requested amounts of approved proposals summed per tag. A proposal with several tags
+    /// counts toward each of them, so subtotals can add up to more than the epoch's spend.
+    pub fn generate_tag_spend_summary(&self, epoch: &Epoch) -> String {
+        let rows = self.tag_spend_rows(epoch);
+        if rows.is_empty() {
+            return String::new();
+        }
+
+        let mut summary = String::from("## Spend by Tag\n| Tag | Proposals | Amounts |\n|-----|-----------|---------|\n");
+        for (tag, count, amounts) in rows {
+            summary.push_str(&format!("| {} | {} | {} |\n", tag, count, amounts));
+        }
+        summary.push('\n');
+        summary
+    }
+
+    /// One (tag, proposal count, formatted amounts) row per tag, alphabetical with the untagged bucket last.
+    fn tag_spend_rows(&self, epoch: &Epoch) -> Vec<(String, usize, String)> {
+        let mut by_tag: HashMap<String, (usize, HashMap<String, f64>)> = HashMap::new();
+
+        for proposal in self.get_proposals_for_epoch(epoch.id()) {
+            if !proposal.is_approved() {
+                continue;
+            }
+            let Some(details) = proposal.budget_request_details() else { continue };
+
+            let tags: Vec<String> = if proposal.tags().is_empty() {
+                vec![UNTAGGED.to_string()]
+            } else {
+                proposal.tags().to_vec()
+            };
+
+            for tag in tags {
+                let (count, amounts) = by_tag.entry(tag).or_default();
+                *count += 1;
+                for (token, amount) in details.request_amounts() {
+                    *amounts.entry(token.clone()).or_insert(0.0) += amount;
+                }
+            }
+        }
+
+        let mut tags: Vec<(String, (usize, HashMap<String, f64>))> = by_tag.into_iter().collect();
+        // Alphabetical, with the untagged bucket last
+        tags.sort_by(|(a, _), (b, _)| (a == UNTAGGED, a).cmp(&(b == UNTAGGED, b)));
+
+        tags.into_iter()
+            .map(|(tag, (count, amounts))| {
+                let mut amounts: Vec<(String, f64)> = amounts.into_iter().collect();
+                amounts.sort_by(|(a, _), (b, _)| a.cmp(b));
+                let amounts = amounts.iter()
+                    .map(|(token, amount)| format!("{} {}", amount, token))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                (tag, count, amounts)
+            })
+            .collect()
+    }
+
+    fn report_statuses() -> [(&'static str, Resolution); 3] {
+        [
+            ("Approved", Resolution::Approved),
+            ("Rejected", Resolution::Rejected),
+            ("Retracted", Resolution::Retracted),
+        ]
+    }
+
+    pub fn generate_proposal_tables(&self, epoch: &Epoch) -> Result<String, Box<dyn Error>> {
+        let mut tables = String::new();
+
+        for (header, rows) in self.proposal_tables(epoch)? {
+            tables.push_str(&header);
+            for row in rows {
+                tables.push_str(&row);
+            }
+            tables.push_str("\n");
+        }
+
+        Ok(tables)
+    }
+
+    /// The proposal tables split into pages of at most `page_size` bytes, for channels such as
+    /// Telegram that cap the message length. A table that continues on the next page repeats its
+    /// heading and header there. A single row too long for a page still gets a page of its own.
+    pub fn generate_proposal_tables_paged(&self, epoch: &Epoch, page_size: usize) -> Result<Vec<String>, Box<dyn Error>> {
+        let mut pages = Vec::new();
+        let mut page = String::new();
+
+        for (header, rows) in self.proposal_tables(epoch)? {
+            let mut table_open = false;
+
+            for row in rows {
+                let header_len = if table_open { 0 } else { header.len() };
+                // One byte is kept for the blank line that closes the table
+                if !page.is_empty() && page.len() + header_len + row.len() + 1 > page_size {
+                    if table_open {
+                        page.push('\n');
+                    }
+                    pages.push(std::mem::take(&mut page));
+                    table_open = false;
+                }
+                if !table_open {
+                    page.push_str(&header);
+                    table_open = true;
+                }
+                page.push_str(&row);
+            }
+
+            if table_open {
+                page.push('\n');
+            }
+        }
+
+        if !page.is_empty() {
+            pages.push(page);
+        }
+
+        Ok(pages)
+    }
+
+    /// Each non-empty proposal table as its heading and header lines, followed by its row lines
+    fn proposal_tables(&self, epoch: &Epoch) -> Result<Vec<(String, Vec<String>)>, Box<dyn Error>> {
+        let mut tables = Vec::new();
+
+        for (status, resolution) in Self::report_statuses() {
+            let rows = self.proposal_table_rows(epoch, resolution.clone(), true)?;
+            if !rows.is_empty() {
+                let header = format!(
+                    "### {} Proposals\n\
+                     | Name | URL | Team | Amounts | Start Date | End Date | Announced | Resolved | Report |\n\
+                     |------|-----|------|---------|------------|----------|-----------|----------|---------|\n",
+                    status
+                );
+                tables.push((header, Self::proposal_table_lines(rows)));
+            }
+
+            let rows = self.proposal_table_rows(epoch, resolution, false)?;
+            if !rows.is_empty() {
+                let header = format!(
+                    "### {} Non-Budget Proposals\n\
+                     | Name | URL | Kind | Announced | Resolved | Report |\n\
+                     |------|-----|------|-----------|----------|--------|\n",
+                    status
+                );
+                tables.push((header, Self::proposal_table_lines(rows)));
+            }
+        }
+
+        Ok(tables)
+    }
+
+    fn proposal_table_lines(rows: Vec<(Vec<String>, String)>) -> Vec<String> {
+        rows.into_iter()
+            .map(|(cells, report_link)| format!("| {} | [Report]({}) |\n", cells.join(" | "), report_link))
+            .collect()
+    }
+
+    /// Table cells for each budget (or non-budget) proposal with the given resolution, paired with the
+    /// file name of its individual report, which is generated and saved along the way.
+    fn proposal_table_rows(&self, epoch: &Epoch, resolution: Resolution, budget: bool) -> Result<Vec<(Vec<String>, String)>, Box<dyn Error>> {
+        let proposals = self.get_proposals_for_epoch(epoch.id());
+        let mut rows = Vec::new();
+
+        for proposal in proposals.into_iter()
+            .filter(|p| matches!(p.resolution(), Some(r) if r == resolution))
+            .filter(|p| p.kind().is_budget() == budget)
+        {
+            // Generate individual proposal report
+            let report_path = self.generate_and_save_proposal_report(proposal.id(), epoch.name())?;
+            let report_link = report_path.file_name().unwrap().to_str().unwrap().to_string();
+
+            if !budget {
+                rows.push((vec![
+                    proposal.title().to_string(),
+                    proposal.url().as_deref().unwrap_or("N/A").to_string(),
+                    proposal.kind().to_string(),
+                    proposal.announced_at().map_or("N/A".to_string(), |d| d.format("%Y-%m-%d").to_string()),
+                    proposal.resolved_at().map_or("N/A".to_string(), |d| d.format("%Y-%m-%d").to_string()),
+                ], report_link));
+                continue;
+            }
+
+            let team_name = proposal.budget_request_details()
+                .and_then(|d| d.team())
+                .and_then(|id| self.state.find_team(&id))
+                .map_or("N/A".to_string(), |t| t.name().to_string());
+
+            let amounts = proposal.budget_request_details()
+                .map(|d| d.request_amounts().iter()
+                    .map(|(token, amount)| format!("{} {}", amount, token))
+                    .collect::<Vec<_>>()
+                    .join(", "))
+                .unwrap_or_else(|| "N/A".to_string());
+
+            let cells = vec![
+                proposal.title().to_string(),
+                proposal.url().as_deref().unwrap_or("N/A").to_string(),
+                team_name,
+                amounts,
+                proposal.budget_request_details().and_then(|d| d.start_date()).map_or("N/A".to_string(), |d| d.format("%Y-%m-%d").to_string()),
+                proposal.budget_request_details().and_then(|d| d.end_date()).map_or("N/A".to_string(), |d| d.format("%Y-%m-%d").to_string()),
+                proposal.announced_at().map_or("N/A".to_string(), |d| d.format("%Y-%m-%d").to_string()),
+                proposal.resolved_at().map_or("N/A".to_string(), |d| d.format("%Y-%m-%d").to_string()),
+            ];
+            rows.push((cells, report_link));
+        }
+
+        Ok(rows)
+    }
+
+    pub fn generate_team_summary(&self, epoch: &Epoch) -> Result<String, Box<dyn Error>> {
+        let mut summary = String::from("## Team Summary\n");
+        summary.push_str("| Team Name | Status | Counted Votes | Uncounted Votes | Total Points | % of Total Points | Reward Amount |\n");
+        summary.push_str("|-----------|--------|---------------|-----------------|--------------|-------------------|---------------|\n");
+
+        for cells in self.team_summary_rows(epoch) {
+            summary.push_str(&format!("| {} |\n", cells.join(" | ")));
+        }
+
+        Ok(summary)
+    }
+
+    fn team_summary_rows(&self, epoch: &Epoch) -> Vec<Vec<String>> {
+        let total_points: u32 = self.state.current_state().teams().keys()
+            .map(|team_id| self.get_team_points_for_epoch(*team_id, epoch.id()).unwrap_or(0))
+            .sum();
+
+        self.sorted_teams().into_iter()
+            .map(|team| {
+                let team_id = &team.id();
+                let team_points = self.get_team_points_for_epoch(*team_id, epoch.id()).unwrap_or(0);
+                let percentage = if total_points > 0 {
+                    (team_points as f64 / total_points as f64) * 100.0
+                } else {
+                    0.0
+                };
+
+                let (counted_votes, uncounted_votes) = self.get_team_vote_counts(*team_id, epoch.id());
+
+                let reward_amount = epoch.team_rewards().get(team_id)
+                    .map(|reward| format!("{} {}", reward.amount(), epoch.reward().as_ref().map_or("".to_string(), |r| r.token().to_string())))
+                    .unwrap_or_else(|| "N/A".to_string());
+
+                vec![
+                    team.name().to_string(),
+                    format!("{:?}", team.status()),
+                    counted_votes.to_string(),
+                    uncounted_votes.to_string(),
+                    team_points.to_string(),
+                    format!("{:.2}%", percentage),
+                    reward_amount,
+                ]
+            })
+            .collect()
+    }
+
+    pub fn export_epoch_rewards_csv(&self, epoch_name: &str) -> Result<String, Box<dyn Error>> {
+        let epoch = self.state.epochs().values()
+            .find(|e| e.name() == epoch_name)
+            .ok_or_else(|| NotFoundError { kind: "Epoch", name: epoch_name.to_string() })?;
+
+        let total_points: u32 = self.state.current_state().teams().keys()
+            .map(|team_id| self.get_team_points_for_epoch(*team_id, epoch.id()).unwrap_or(0))
+            .sum();
+
+        let reward_token = epoch.reward().map_or("", |r| r.token());
+
+        let teams = self.sorted_teams();
+
+        let mut csv = String::from("team_name,status,counted_votes,uncounted_votes,total_points,percentage,reward_amount,reward_token\n");
+
+        for team in teams {
+            let team_points = self.get_team_points_for_epoch(team.id(), epoch.id()).unwrap_or(0);
+            let percentage = if total_points > 0 {
+                (team_points as f64 / total_points as f64) * 100.0
+            } else {
+                0.0
+            };
+
+            let (counted_votes, uncounted_votes) = self.get_team_vote_counts(team.id(), epoch.id());
+
+            let status = match team.status() {
+                TeamStatus::Earner { .. } => "Earner",
+                TeamStatus::Supporter => "Supporter",
+                TeamStatus::Inactive => "Inactive",
+            };
+
+            let (reward_amount, token) = match epoch.team_rewards().get(&team.id()) {
+                Some(reward) => (reward.amount().to_string(), reward_token),
+                None => (String::new(), ""),
+            };
+
+            csv.push_str(&format!(
+                "{},{},{},{},{},{:.2},{},{}\n",
+                Self::escape_csv(team.name()),
+                status,
+                counted_votes,
+                uncounted_votes,
+                team_points,
+                percentage,
+                reward_amount,
+                Self::escape_csv(token)
+            ));
+        }
+
+        Ok(csv)
+    }
+
+    pub fn save_epoch_rewards_csv(&self, epoch_name: &str) -> Result<PathBuf, Box<dyn Error>> {
+        let csv = self.export_epoch_rewards_csv(epoch_name)?;
+
+        let file_name = format!("{}-epoch_rewards.csv", Utc::now().format("%Y%m%d"));
+        let report_path = PathBuf::from(&self.config.state_file)
+            .parent()
+            .unwrap()
+            .join("reports")
+            .join(FileSystem::sanitize_filename(epoch_name))
+            .join(file_name);
+
+        fs::create_dir_all(report_path.parent().unwrap())?;
+        fs::write(&report_path, csv)?;
+
+        Ok(report_path)
+    }
+
+    /// Every ticket of the proposal's current raffle with its team, score and whether the team
+    /// won a counted seat. Teams are named as they are now, or as snapshotted if since removed.
+    pub fn export_raffle_tickets(&self, proposal_name: &str, format: ExportFormat) -> Result<String, Box<dyn Error>> {
+        let proposal_id = self.require_proposal_id(proposal_name)?;
+        let raffle = self.active_raffle_for_proposal(proposal_id)
+            .ok_or_else(|| format!("No raffle found for proposal: {}", proposal_name))?;
+
+        let names: HashMap<Uuid, String> = self.state.current_state().teams().values()
+            .chain(self.state.archived_teams().values())
+            .map(|team| (team.id(), team.name().to_string()))
+            .collect();
+
+        match format {
+            ExportFormat::Csv => Ok(raffle.tickets_csv_with_names(&names)),
+            ExportFormat::Json => Ok(serde_json::to_string_pretty(&raffle.ticket_records(&names))?),
+        }
+    }
+
+    pub fn save_raffle_tickets(&self, proposal_name: &str, format: ExportFormat) -> Result<PathBuf, Box<dyn Error>> {
+        let export = self.export_raffle_tickets(proposal_name, format)?;
+        let epoch_name = self.get_proposal_id_by_name(proposal_name)
+            .and_then(|id| self.state.get_proposal(&id))
+            .and_then(|proposal| self.state.get_epoch(&proposal.epoch_id()))
+            .map(|epoch| epoch.name().to_string())
+            .unwrap_or_default();
+
+        let file_name = format!("{}-{}-raffle_tickets.{}",
+            Utc::now().format("%Y%m%d"),
+            FileSystem::sanitize_filename(proposal_name),
+            format.extension()
+        );
+        let export_path = PathBuf::from(&self.config.state_file)
+            .parent()
+            .unwrap()
+            .join("reports")
+            .join(FileSystem::sanitize_filename(&epoch_name))
+            .join(file_name);
+
+        fs::create_dir_all(export_path.parent().unwrap())?;
+        fs::write(&export_path, export)?;
+
+        Ok(export_path)
+    }
+
+    fn escape_csv(field: &str) -> String {
+        crate::core::models::common::escape_csv(field)
+    }
+
+    /// Splits one CSV line, undoing the quoting `escape_csv` applies
+    fn split_csv_line(line: &str) -> Vec<String> {
+        let mut fields = Vec::new();
+        let mut field = String::new();
+        let mut in_quotes = false;
+        let mut chars = line.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '"' if in_quotes && chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                },
+                '"' => in_quotes = !in_quotes,
+                ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+                _ => field.push(c),
+            }
+        }
+        fields.push(field);
+        fields.into_iter().map(|f| f.trim().to_string()).collect()
+    }
+
+    /// Rows keyed by header name, with their line numbers. Fails only when a required column is missing.
+    fn parse_csv_rows(csv: &str, required: &[&str]) -> Result<Vec<(usize, HashMap<String, String>)>, Box<dyn Error>> {
+        let mut lines = csv.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+        let header: Vec<String> = lines.next()
+            .map(|(_, line)| Self::split_csv_line(line).into_iter().map(|h| h.to_lowercase()).collect())
+            .ok_or("CSV is empty")?;
+
+        for column in required {
+            if !header.iter().any(|h| h == column) {
+                return Err(format!("CSV is missing the '{}' column", column).into());
+            }
+        }
+
+        Ok(lines
+            .map(|(index, line)| {
+                let row = header.iter().cloned()
+                    .zip(Self::split_csv_line(line))
+                    .filter(|(_, value)| !value.is_empty())
+                    .collect();
+                (index + 1, row)
+            })
+            .collect())
+    }
+
+    /// Adds teams from CSV with columns `name,representative,trailing_monthly_revenue`, where revenue
+    /// is a pipe-separated list (empty for supporters). Invalid rows are skipped and reported.
+    pub fn import_teams_csv_report(&mut self, csv: &str) -> Result<CsvImportReport, Box<dyn Error>> {
+        let mut report = CsvImportReport::default();
+
+        for (line, row) in Self::parse_csv_rows(csv, &["name", "representative"])? {
+            match self.import_team_row(&row) {
+                Ok(team_id) => report.imported.push(team_id),
+                Err(e) => report.failed.push((line, e.to_string())),
+            }
+        }
+
+        Ok(report)
+    }
+
+    fn import_team_row(&mut self, row: &HashMap<String, String>) -> Result<Uuid, Box<dyn Error>> {
+        let name = row.get("name").ok_or("Missing name")?;
+        let representative = row.get("representative").ok_or("Missing representative")?;
+        if self.get_team_id_by_name(name).is_some() {
+            return Err(format!("Team already exists: {}", name).into());
+        }
+        let revenue = row.get("trailing_monthly_revenue")
+            .map(|revenue| revenue.split('|')
+                .map(|value| value.trim().parse::<f64>().map_err(|_| format!("Invalid revenue: {}", value)))
+                .collect::<Result<Vec<f64>, _>>())
+            .transpose()?;
+        self.create_team(name.clone(), representative.clone(), revenue, None)
+    }
+
+    fn format_csv_import_report(kind: &str, report: &CsvImportReport) -> String {
+        let mut output = format!("Imported {} {}\n", report.imported.len(), kind);
+        if !report.failed.is_empty() {
+            output.push_str(&format!("Skipped {} rows:\n", report.failed.len()));
+            for (line, reason) in &report.failed {
+                output.push_str(&format!("- line {}: {}\n", line, reason));
+            }
+        }
+        output
+    }
+
+    /// Like `import_teams_csv_report`, logging the rejected rows instead of returning them
+    pub fn import_teams_csv(&mut self, csv: &str) -> Result<Vec<Uuid>, Box<dyn Error>> {
+        let report = self.import_teams_csv_report(csv)?;
+        for (line, reason) in &report.failed {
+            warn!("Skipped team on line {}: {}", line, reason);
+        }
+        Ok(report.imported)
+    }
+
+    /// Adds proposals to the active epoch from CSV with columns `title,url,team,amounts,announced_at,published_at`.
+    /// Only `title` is required; `amounts` is a pipe-separated list of `TOKEN:amount` and makes the row a
+    /// budget request, and dates are YYYY-MM-DD. Invalid rows are skipped and reported.
+    pub fn import_proposals_csv_report(&mut self, csv: &str) -> Result<CsvImportReport, Box<dyn Error>> {
+        let mut report = CsvImportReport::default();
+
+        for (line, row) in Self::parse_csv_rows(csv, &["title"])? {
+            match self.import_proposal_row(&row) {
+                Ok(proposal_id) => report.imported.push(proposal_id),
+                Err(e) => report.failed.push((line, e.to_string())),
+            }
+        }
+
+        Ok(report)
+    }
+
+    fn import_proposal_row(&mut self, row: &HashMap<String, String>) -> Result<Uuid, Box<dyn Error>> {
+        let title = row.get("title").ok_or("Missing title")?;
+        if self.get_proposal_id_by_name(title).is_some() {
+            return Err(format!("Proposal already exists: {}", title).into());
+        }
+        let parse_date = |column: &str| row.get(column)
+            .map(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .map_err(|_| format!("Invalid {}: {}", column, date)))
+            .transpose();
+        let announced_at = parse_date("announced_at")?;
+        let published_at = parse_date("published_at")?;
+
+        let team_id = row.get("team")
+            .map(|team| self.require_team_id(team))
+            .transpose()?;
+        let details = match row.get("amounts") {
+            Some(amounts) => {
+                let amounts = amounts.split('|')
+                    .map(|entry| {
+                        let (token, amount) = entry.split_once(':')
+                            .ok_or_else(|| format!("Invalid amount: {}. Expected TOKEN:amount", entry))?;
+                        let amount = amount.trim().parse::<f64>()
+                            .map_err(|_| format!("Invalid amount: {}", entry))?;
+                        Ok((token.trim().to_string(), amount))
+                    })
+                    .collect::<Result<HashMap<String, f64>, String>>()?;
+                Some(BudgetRequestDetails::new(team_id, amounts, None, None, Some(false), None)?)
+            },
+            None if team_id.is_some() => return Err("A team needs amounts to form a budget request".into()),
+            None => None,
+        };
+
+        Ok(self.add_proposal(title.clone(), row.get("url").cloned(), details, announced_at, published_at, None)?)
+    }
+
+    /// Like `import_proposals_csv_report`, logging the rejected rows instead of returning them
+    pub fn import_proposals_csv(&mut self, csv: &str) -> Result<Vec<Uuid>, Box<dyn Error>> {
+        let report = self.import_proposals_csv_report(csv)?;
+        for (line, reason) in &report.failed {
+            warn!("Skipped proposal on line {}: {}", line, reason);
+        }
+        Ok(report.imported)
+    }
+
+    /// Markdown tables comparing two epochs' proposal counts, rewards and per-team points.
+    /// A team counts as present in an epoch when it took part in one of its formal votes.
+    pub fn generate_epoch_comparison(&self, epoch_a: &str, epoch_b: &str) -> Result<String, Box<dyn Error>> {
+        let find = |name: &str| self.get_epoch_id_by_name(name)
+            .and_then(|id| self.state.get_epoch(&id))
+            .ok_or_else(|| NotFoundError { kind: "Epoch", name: name.to_string() });
+        let (a, b) = (find(epoch_a)?, find(epoch_b)?);
+
+        let counts = |epoch: &Epoch| {
+            let (total, approved, rejected) = self.epoch_proposal_counts(epoch.id());
+            (total as i64, approved as i64, rejected as i64)
+        };
+        let (total_a, approved_a, rejected_a) = counts(a);
+        let (total_b, approved_b, rejected_b) = counts(b);
+
+        let mut report = format!("# Epoch Comparison: {} vs {}\n\n", a.name(), b.name());
+        report.push_str(&format!("| Metric | {} | {} | Change |\n|--------|---|---|--------|\n", a.name(), b.name()));
+        for (metric, value_a, value_b) in [
+            ("Total Proposals", total_a, total_b),
+            ("Approved Proposals", approved_a, approved_b),
+            ("Rejected Proposals", rejected_a, rejected_b),
+        ] {
+            report.push_str(&format!("| {} | {} | {} | {:+} |\n", metric, value_a, value_b, value_b - value_a));
+        }
+
+        let reward_change = match (a.reward(), b.reward()) {
+            (Some(ra), Some(rb)) if ra.token() == rb.token() => format!("{:+} {}", rb.amount() - ra.amount(), rb.token()),
+            _ => "N/A".to_string(),
+        };
+        let reward = |epoch: &Epoch| epoch.reward().map_or("N/A".to_string(), |r| format!("{} {}", r.amount(), r.token()));
+        report.push_str(&format!("| Total Reward | {} | {} | {} |\n\n", reward(a), reward(b), reward_change));
+
+        let teams = self.compared_team_points(a.id(), b.id());
+
+        report.push_str("## Team Points\n");
+        report.push_str(&format!("| Team | {} | {} | Change |\n|------|---|---|--------|\n", a.name(), b.name()));
+        let show = |points: Option<u32>| points.map_or("N/A".to_string(), |p| p.to_string());
+        for (name, points_a, points_b) in teams {
+            let change = match (points_a, points_b) {
+                (Some(pa), Some(pb)) => format!("{:+}", pb as i64 - pa as i64),
+                _ => "N/A".to_string(),
+            };
+            report.push_str(&format!("| {} | {} | {} | {} |\n", name, show(points_a), show(points_b), change));
+        }
+
+        Ok(report)
+    }
+
+    /// Total, approved and rejected proposals of the epoch
+    pub fn epoch_proposal_counts(&self, epoch_id: Uuid) -> (usize, usize, usize) {
+        let proposals = self.get_proposals_for_epoch(epoch_id);
+        let approved = proposals.iter().filter(|p| p.is_approved()).count();
+        let rejected = proposals.iter().filter(|p| p.is_rejected()).count();
+        (proposals.len(), approved, rejected)
+    }
+
+    /// Each team's points in the two epochs, `None` where it cast no votes, ordered by name.
+    /// Teams that voted in neither epoch are left out.
+    pub fn compared_team_points(&self, epoch_a: Uuid, epoch_b: Uuid) -> Vec<(String, Option<u32>, Option<u32>)> {
+        let points = |team_id: Uuid, epoch_id: Uuid| {
+            let (counted, uncounted) = self.get_team_vote_counts(team_id, epoch_id);
+            if counted + uncounted == 0 {
+                None
+            } else {
+                self.get_team_points_for_epoch(team_id, epoch_id).ok()
+            }
+        };
+        let mut teams: Vec<(String, Option<u32>, Option<u32>)> = self.state.current_state().teams().values()
+            .map(|team| (team.name().to_string(), points(team.id(), epoch_a), points(team.id(), epoch_b)))
+            .filter(|(_, points_a, points_b)| points_a.is_some() || points_b.is_some())
+            .collect();
+        teams.sort_by(|x, y| x.0.cmp(&y.0));
+        teams
+    }
+
+    /// Counts the epoch's proposals that were submitted, got a raffle, got a vote and were approved
+    pub fn epoch_funnel(&self, epoch_id: Uuid) -> Funnel {
+        let proposals = self.get_proposals_for_epoch(epoch_id);
+        let raffled = proposals.iter()
+            .filter(|p| self.state.raffles().values().any(|r| r.config().proposal_id() == p.id()))
+            .count();
+        let voted = proposals.iter()
+            .filter(|p| self.state.votes().values().any(|v| v.proposal_id() == p.id()))
+            .count();
+        let approved = proposals.iter().filter(|p| p.is_approved()).count();
+
+        Funnel {
+            submitted: proposals.len(),
+            raffled,
+            voted,
+            approved,
+        }
+    }
+
+    /// Monthly payment tranches of the epoch's approved proposals, summed per date and token
+    pub fn cashflow_forecast(&self, epoch_id: Uuid) -> Vec<(NaiveDate, HashMap<String, f64>)> {
+        let mut totals: HashMap<NaiveDate, HashMap<String, f64>> = HashMap::new();
+
+        for proposal in self.get_proposals_for_epoch(epoch_id) {
+            if !proposal.is_approved() {
+                continue;
+            }
+            if let Some(details) = proposal.budget_request_details() {
+                for (date, amounts) in details.monthly_schedule() {
+                    let month = totals.entry(date).or_default();
+                    for (token, amount) in amounts {
+                        *month.entry(token).or_insert(0.0) += amount;
+                    }
+                }
+            }
+        }
+
+        let mut forecast: Vec<(NaiveDate, HashMap<String, f64>)> = totals.into_iter().collect();
+        forecast.sort_by_key(|(date, _)| *date);
+        forecast
+    }
+
+    /// exp of the Shannon entropy of teams' shares of participation events across the
+    /// epoch's votes: 1.0 when one team does all the voting, the team count when all vote
+    /// equally often. 0.0 when nobody has voted.
+    pub fn effective_team_count(&self, epoch_id: Uuid) -> f64 {
+        let mut events: HashMap<Uuid, usize> = HashMap::new();
+        for vote in self.state.votes().values().filter(|vote| vote.epoch_id() == epoch_id) {
+            let participants: Vec<&Uuid> = match vote.participation() {
+                VoteParticipation::Formal { counted, uncounted } => counted.iter().chain(uncounted.iter()).collect(),
+                VoteParticipation::Informal(teams) => teams.iter().collect(),
+            };
+            for team_id in participants {
+                *events.entry(*team_id).or_insert(0) += 1;
+            }
+        }
+
+        let total: usize = events.values().sum();
+        if total == 0 {
+            return 0.0;
+        }
+        let entropy: f64 = events.values()
+            .map(|&count| {
+                let share = count as f64 / total as f64;
+                -share * share.ln()
+            })
+            .sum();
+        entropy.exp()
+    }
+
+    /// Mean competition ratio over the epoch's raffles; 0.0 when the epoch has none
+    pub fn avg_competition_ratio(&self, epoch_id: Uuid) -> f64 {
+        let ratios: Vec<f64> = self.state.raffles().values()
+            .filter(|raffle| raffle.config().epoch_id() == epoch_id)
+            .map(|raffle| raffle.competition_ratio())
+            .collect();
+        if ratios.is_empty() {
+            return 0.0;
+        }
+        ratios.iter().sum::<f64>() / ratios.len() as f64
+    }
+
+    /// Percentage points of projected reward share each pre-existing team loses to teams
+    /// that became active after the epoch started. Joiners are weighted by the fraction of
+    /// the epoch they are eligible for, at the average weight of the pre-existing teams.
+    pub fn dilution_report(&self, epoch_id: Uuid) -> Vec<(String, f64)> {
+        let epoch = match self.state.get_epoch(&epoch_id) {
+            Some(epoch) => epoch,
+            None => return Vec::new(),
+        };
+
+        let start = epoch.start_date();
+        let end = epoch.end_date();
+        let epoch_seconds = (end - start).num_seconds().max(1) as f64;
+
+        let mut existing: Vec<&Team> = self.state.current_state().teams().values()
+            .filter(|team| team.is_active_at(start))
+            .collect();
+        existing.sort_by(|a, b| a.name().cmp(b.name()));
+
+        if existing.is_empty() {
+            return Vec::new();
+        }
+
+        let points: Vec<f64> = existing.iter()
+            .map(|team| self.get_team_points_for_epoch(team.id(), epoch_id).unwrap_or(0) as f64)
+            .collect();
+        // Without any points yet, project an equal split between the existing teams
+        let weights: Vec<f64> = if points.iter().sum::<f64>() > 0.0 {
+            points
+        } else {
+            vec![1.0; existing.len()]
+        };
+        let existing_weight: f64 = weights.iter().sum();
+        let average_weight = existing_weight / existing.len() as f64;
+
+        let joiner_weight: f64 = self.state.current_state().teams().values()
+            .filter(|team| team.is_active() && !team.is_active_at(start))
+            .filter_map(|team| team.active_from())
+            .filter(|from| *from < end)
+            .map(|from| average_weight * (end - from).num_seconds() as f64 / epoch_seconds)
+            .sum();
+
+        existing.iter().zip(weights.iter())
+            .map(|(team, weight)| {
+                let before = weight / existing_weight * 100.0;
+                let after = weight / (existing_weight + joiner_weight) * 100.0;
+                (team.name().to_string(), before - after)
+            })
+            .collect()
+    }
+
+    /// Median days from announcement to resolution over the team's approved proposals.
+    /// Proposals missing either date are left out.
+    pub fn team_approval_velocity(&self, team_id: Uuid) -> Option<f64> {
+        let mut durations: Vec<i64> = self.state.proposals().values()
+            .filter(|proposal| proposal.is_approved())
+            .filter(|proposal| proposal.budget_request_details().and_then(|details| details.team()) == Some(team_id))
+            .filter_map(|proposal| match (proposal.announced_at(), proposal.resolved_at()) {
+                (Some(announced), Some(resolved)) => Some((resolved - announced).num_days()),
+                _ => None,
+            })
+            .collect();
+
+        if durations.is_empty() {
+            return None;
+        }
+
+        durations.sort_unstable();
+        let mid = durations.len() / 2;
+        if durations.len() % 2 == 0 {
+            Some((durations[mid - 1] + durations[mid]) as f64 / 2.0)
+        } else {
+            Some(durations[mid] as f64)
+        }
+    }
+
+    /// Estimates when the open proposals will all be resolved, from the median gap between
+    /// the most recent resolutions. None when fewer than two resolutions have dates.
+    pub fn projected_epoch_completion(&self) -> Option<NaiveDate> {
+        const RECENT_RESOLUTIONS: usize = 10;
+
+        let today = Utc::now().date_naive();
+        let open_count = self.state.proposals().values()
+            .filter(|proposal| proposal.is_actionable())
+            .count();
+
+        if open_count == 0 {
+            return Some(today);
+        }
+
+        let mut resolved: Vec<NaiveDate> = self.state.proposals().values()
+            .filter(|proposal| proposal.is_closed())
+            .filter_map(|proposal| proposal.resolved_at())
+            .collect();
+        resolved.sort_unstable();
+        let recent = &resolved[resolved.len().saturating_sub(RECENT_RESOLUTIONS)..];
+
+        if recent.len() < 2 {
+            return None;
+        }
+
+        let mut gaps: Vec<i64> = recent.windows(2)
+            .map(|pair| (pair[1] - pair[0]).num_days())
+            .collect();
+        gaps.sort_unstable();
+        let mid = gaps.len() / 2;
+        let days_per_resolution = if gaps.len() % 2 == 0 {
+            (gaps[mid - 1] + gaps[mid]) as f64 / 2.0
+        } else {
+            gaps[mid] as f64
+        };
+
+        let days = (days_per_resolution * open_count as f64).ceil() as i64;
+        Some(today + chrono::Duration::days(days))
+    }
+
+    pub fn get_team_vote_counts(&self, team_id: Uuid, epoch_id: Uuid) -> (u32, u32) {
+        let mut counted = 0;
+        let mut uncounted = 0;
+
+        for vote in self.state.votes().values() {
+            if vote.epoch_id() == epoch_id {
+                match vote.participation() {
+                    VoteParticipation::Formal { counted: c, uncounted: u } => {
+                        if c.contains(&team_id) {
+                            counted += 1;
+                        } else if u.contains(&team_id) {
+                            uncounted += 1;
+                        }
+                    },
+                    VoteParticipation::Informal(_) => {}  // Informal votes are not counted here
+                }
+            }
+        }
+
+        (counted, uncounted)
+    }
+
+    /// Creates a new raffle with progress updates streamed as an async stream
+    ///
+    /// # Arguments
+    /// * `proposal_name` - Name of the proposal to create raffle for
+    /// * `block_offset` - Optional override for the default block offset
+    /// * `excluded_teams` - Optional list of team names to exclude
+    ///
+    /// # Returns
+    /// A stream of RaffleProgress updates that can be consumed asynchronously
+    pub async fn create_raffle_with_progress<'a>(
+        &'a mut self,
+        proposal_name: String,
+        block_offset: Option<u64>,
+        excluded_teams: Option<Vec<String>>,
+        exclude_inactive: bool,
+    ) -> impl Stream<Item = Result<RaffleProgress, RaffleCreationError>> + Send + 'a {
+        let config = self.config.clone();
+        let eth_service = Arc::clone(&self.ethereum_service);
+        
+        try_stream! {
+            // Do setup inside the stream
+            let (raffle_id, tickets) = self.prepare_raffle_with_options(&proposal_name, excluded_teams.clone(), exclude_inactive, &config)
+                .map_err(|e| RaffleCreationError(format!("Failed to prepare raffle: {}", e)))?;
+    
+            let ticket_ranges = self.group_tickets_by_team(&tickets);
+    
+            yield RaffleProgress::Preparing {
+                proposal_name: proposal_name.clone(),
+                raffle_id,
+                ticket_ranges,
+            };
+    
+            let current_block = eth_service.get_current_block()
+                .await
+                .map_err(|e| RaffleCreationError(format!("Failed to get current block: {}", e)))?;
+                
+            let target_block = current_block + block_offset.unwrap_or(config.future_block_offset);
+    
+            yield RaffleProgress::WaitingForBlock {
+                proposal_name: proposal_name.clone(),
+                raffle_id,
+                current_block,
+                target_block,
+            };
+
+            let timeout = Duration::from_secs(config.raffle_block_timeout_secs);
+            if let Err(e) = eth_service.wait_for_block(target_block, timeout).await.map_err(|e| e.to_string()) {
+                // A raffle without randomness can't be finalized later, so don't leave it behind
+                self.state.remove_raffle(raffle_id);
+                self.save_state()
+                    .map_err(|e| RaffleCreationError(format!("Failed to discard raffle: {}", e)))?;
+                Err(RaffleCreationError(format!("Failed to reach randomness block: {}", e)))?;
+            }
+    
+            let randomness = eth_service.get_randomness(target_block)
+                .await
+                .map_err(|e| RaffleCreationError(format!("Failed to get randomness: {}", e)))?;
+    
+            yield RaffleProgress::RandomnessAcquired {
+                proposal_name: proposal_name.clone(),
+                raffle_id,
+                current_block,
+                target_block,
+                randomness: randomness.clone(),
+            };
+    
+            // Provenance only, so a node that can't serve the timestamp doesn't fail the raffle
+            let randomness_timestamp = eth_service.get_block_timestamp(target_block).await.ok();
+            if let Some(raffle) = self.state.get_raffle_mut(&raffle_id) {
+                raffle.config_mut().set_randomness_timestamp(randomness_timestamp);
+            }
+
+            let raffle = self.finalize_raffle(raffle_id, current_block, target_block, randomness)
+                .await
+                .map_err(|e| RaffleCreationError(format!("Failed to finalize raffle: {}", e)))?;
+    
+            let (counted, uncounted) = if let Some(result) = raffle.result() {
+                let format_team_with_score = |team_id: &Uuid| {
+                    let snapshot = raffle.team_snapshots().iter()
+                        .find(|s| s.id() == *team_id)
+                        .unwrap();
+                    let best_score = raffle.tickets().iter()
+                        .filter(|t| t.team_id() == *team_id)
+                        .map(|t| t.score())
+                        .max_by(|a, b| a.partial_cmp(b).unwrap())
+                        .unwrap_or(0.0);
+                    (snapshot.status().clone(), format!("{} (score: {})", snapshot.name(), best_score))
+                };
+        
+                let counted: Vec<(TeamStatus, String)> = result.counted().iter()
+                    .map(|team_id| format_team_with_score(team_id))
+                    .collect();
+                let uncounted: Vec<(TeamStatus, String)> = result.uncounted().iter()
+                    .map(|team_id| format_team_with_score(team_id))
+                    .collect();
+                (counted, uncounted)
+            } else {
+                (Vec::new(), Vec::new())
+            };
+        
+            yield RaffleProgress::Completed {
+                proposal_name: proposal_name.clone(),
+                raffle_id,
+                counted,
+                uncounted,
+            };
+        }
+    }
+
+    pub fn generate_unpaid_requests_report(
+        &self,
+        output_path: Option<&str>,
+        epoch_name: Option<&str>,
+    ) -> Result<String, Box<dyn Error>> {
+        let (output_path, _) = self.save_unpaid_requests_report(output_path, epoch_name)?;
+        Ok(format!("Generated unpaid requests report at: {:?}", output_path))
+    }
+
+    /// Writes the unpaid requests report as JSON and returns its path along with the report
+    pub fn save_unpaid_requests_report(
+        &self,
+        output_path: Option<&str>,
+        epoch_name: Option<&str>,
+    ) -> Result<(PathBuf, UnpaidRequestsReport), Box<dyn Error>> {
+        // Collect unpaid requests
+        let mut unpaid_requests: Vec<UnpaidRequest> = self
+            .state
+            .proposals()
+            .iter()
+            .filter_map(|(proposal_id, proposal)| {
+                // Check if proposal is approved
+                if !proposal.is_approved() {
+                    return None;
+                }
+
+                // Check if it has budget details
+                let budget_details = match proposal.budget_request_details() {
+                    Some(details) => details,
+                    None => return None,
+                };
+
+                // Skip if already paid
+                if budget_details.is_paid() {
+                    return None;
+                }
+
+                // Get team name
+                let team_name = budget_details
+                    .team()
+                    .and_then(|team_id| self.state.find_team(&team_id))
+                    .map(|team| team.name().to_string())
+                    .unwrap_or_else(|| "No Team".to_string());
+
+                // Get epoch name
+                let epoch = self.state.epochs().get(&proposal.epoch_id());
+                
+                // Filter by epoch if specified
+                if let Some(target_epoch) = epoch_name {
+                    if let Some(epoch) = epoch {
+                        if epoch.name() != target_epoch {
+                            return None;
+                        }
+                    }
+                }
+
+                let epoch_name = epoch
+                    .map(|e| e.name().to_string())
+                    .unwrap_or_else(|| "Unknown Epoch".to_string());
+
+                // Get approval date
+                let approved_date = proposal.resolved_at()
+                    .unwrap_or_else(|| Utc::now().date_naive());
+
+                Some(UnpaidRequest::new(
+                    *proposal_id,
+                    proposal.title().to_string(),
+                    team_name,
+                    budget_details.request_amounts().clone(),
+                    budget_details.payment_address().map(|addr| format!("{:?}", addr)),
+                    approved_date,
+                    budget_details.is_loan(),
+                    epoch_name,
+                    proposal.url().map(|u| u.to_string()),
+                    budget_details.start_date(),
+                ))
+            })
+            .collect();
+        unpaid_requests.sort_by(|a, b| a.epoch_name.cmp(&b.epoch_name).then_with(|| a.title.cmp(&b.title)));
+
+        let report = UnpaidRequestsReport::new(unpaid_requests);
+
+        // Generate output path if not provided
+        let output_path = output_path.map(PathBuf::from).unwrap_or_else(|| {
+            let date = Utc::now().format("%Y%m%d");
+            PathBuf::from(&self.config.state_file)
+                .parent()
+                .unwrap()
+                .join("reports")
+                .join(format!("unpaid_requests_{}.json", date))
+        });
+
+        // Create directory if it doesn't exist
+        if let Some(parent) = output_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // Write report to file
+        let json = serde_json::to_string_pretty(&report)?;
+        fs::write(&output_path, json)?;
+
+        Ok((output_path, report))
+    }
+
+    /// Teams, proposals and epochs whose names contain `query`, ignoring case, plus any
+    /// team, proposal, epoch, raffle or vote whose ID is `query`
+    pub fn search(&self, query: &str) -> SearchResults {
+        let query = query.trim();
+        let mut results = SearchResults::new(query);
+        if query.is_empty() {
+            return results;
+        }
+        let id = Uuid::parse_str(query).ok();
+        let proposal_title = |proposal_id: Uuid| self.state.get_proposal(&proposal_id)
+            .map_or_else(|| proposal_id.to_string(), |p| p.title().to_string());
+
+        for team in self.state.current_state().teams().values() {
+            if team.name_contains(query) || id == Some(team.id()) {
+                results.push(SearchHitKind::Team, team.id(), team.name());
+            }
+        }
+        for proposal in self.state.proposals().values() {
+            if proposal.name_contains(query) || id == Some(proposal.id()) {
+                results.push(SearchHitKind::Proposal, proposal.id(), proposal.title());
+            }
+        }
+        for epoch in self.state.epochs().values() {
+            if epoch.name_contains(query) || id == Some(epoch.id()) {
+                results.push(SearchHitKind::Epoch, epoch.id(), epoch.name());
+            }
+        }
+        if let Some(id) = id {
+            if let Some(raffle) = self.state.get_raffle(&id) {
+                results.push(SearchHitKind::Raffle, id, &format!("Raffle for {}", proposal_title(raffle.config().proposal_id())));
+            }
+            if let Some(vote) = self.state.get_vote(&id) {
+                results.push(SearchHitKind::Vote, id, &format!("Vote on {}", proposal_title(vote.proposal_id())));
+            }
+        }
+
+        results
+    }
+
+    pub fn action_items(&self) -> ActionItems {
+        let sorted = |mut items: Vec<ActionItem>| {
+            items.sort_by(|a, b| a.priority.cmp(&b.priority).then_with(|| a.description.cmp(&b.description)));
+            items
+        };
+
+        ActionItems {
+            overdue_proposals: sorted(self.overdue_proposal_items()),
+            votes_nearing_deadline: sorted(self.votes_nearing_deadline()),
+            unfinalized_raffles: sorted(self.unfinalized_raffles()),
+            placeholder_raffles: sorted(self.placeholder_raffles()),
+            approved_unpaid_proposals: sorted(self.approved_unpaid_proposals()),
+            stale_earner_revenue: sorted(self.stale_earner_revenue()),
+            reward_inconsistencies: sorted(self.reward_inconsistencies()),
+        }
+    }
+
+    pub fn overdue_proposal_items(&self) -> Vec<ActionItem> {
+        self.state.proposals().values()
+            .filter_map(|proposal| {
+                let days_overdue = self.days_overdue(proposal)?;
+                Some(ActionItem::new(
+                    ActionPriority::High,
+                    proposal.id(),
+                    format!("Proposal '{}' is {} days past its decision deadline", proposal.title(), days_overdue),
+                ))
+            })
+            .collect()
+    }
+
+    pub fn votes_nearing_deadline(&self) -> Vec<ActionItem> {
+        let now = Utc::now();
+
+        self.state.votes().values()
+            .filter(|v| !v.is_closed())
+            .filter_map(|vote| {
+                let deadline = vote.opened_at() + chrono::Duration::days(VOTE_WINDOW_DAYS);
+                let days_left = deadline.signed_duration_since(now).num_days();
+                if days_left > VOTE_DEADLINE_WARNING_DAYS {
+                    return None;
+                }
+
+                let title = self.state.get_proposal(&vote.proposal_id())
+                    .map_or("Unknown proposal", |p| p.title());
+
+                let (priority, description) = if deadline < now {
+                    let days_overdue = now.signed_duration_since(deadline).num_days();
+                    (ActionPriority::High, format!("Vote on '{}' is {} days past its deadline", title, days_overdue))
+                } else {
+                    (ActionPriority::Medium, format!("Vote on '{}' closes in {} days", title, days_left))
+                };
+                Some(ActionItem::new(priority, vote.id(), description))
+            })
+            .collect()
+    }
+
+    pub fn unfinalized_raffles(&self) -> Vec<ActionItem> {
+        self.state.raffles().values()
+            .filter(|r| !r.is_completed())
+            .map(|raffle| {
+                let title = self.state.get_proposal(&raffle.config().proposal_id())
+                    .map_or("Unknown proposal", |p| p.title());
+                ActionItem::new(
+                    ActionPriority::Medium,
+                    raffle.id(),
+                    format!("Raffle for '{}' has not been finalized", title),
+                )
+            })
+            .collect()
+    }
+
+    /// Finalized live raffles whose randomness or blocks look like placeholders.
+    /// Historical raffles, including predefined ones, are expected to have them.
+    pub fn placeholder_raffles(&self) -> Vec<ActionItem> {
+        self.state.raffles().values()
+            .filter(|r| r.is_completed() && !r.is_historical() && r.has_placeholder_randomness())
+            .map(|raffle| {
+                let title = self.state.get_proposal(&raffle.config().proposal_id())
+                    .map_or("Unknown proposal", |p| p.title());
+                ActionItem::new(
+                    ActionPriority::High,
+                    raffle.id(),
+                    format!(
+                        "Raffle for '{}' was finalized with placeholder randomness (blocks {}/{}, randomness '{}')",
+                        title,
+                        raffle.config().initiation_block(),
+                        raffle.config().randomness_block(),
+                        raffle.config().block_randomness()
+                    ),
+                )
+            })
+            .collect()
+    }
+
+    /// Team rewards in closed epochs whose amount no longer matches their percentage of the pool,
+    /// e.g. after a manual edit. Each entry is `(epoch_id, epoch, team, stored, expected)`.
+    fn reward_mismatches(&self) -> Vec<(Uuid, String, String, f64, f64)> {
+        let mut mismatches = Vec::new();
+        for epoch in self.state.epochs().values().filter(|epoch| epoch.is_closed()) {
+            let Some(reward) = epoch.reward() else { continue };
+            // Rounded allocations may be off by up to one unit of the rounding precision
+            let tolerance = epoch.reward_rounding().decimals()
+                .map_or(REWARD_ROUNDING_TOLERANCE, |decimals| 10f64.powi(-(decimals as i32)) + REWARD_ROUNDING_TOLERANCE);
+            for (team_id, team_reward) in epoch.team_rewards() {
+                let expected = reward.amount() * team_reward.percentage() / 100.0;
+                if (team_reward.amount() - expected).abs() > tolerance {
+                    let team_name = self.state.find_team(team_id)
+                        .map_or_else(|| team_id.to_string(), |team| team.name().to_string());
+                    mismatches.push((epoch.id(), epoch.name().to_string(), team_name, team_reward.amount(), expected));
+                }
+            }
+        }
+        mismatches.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.2.cmp(&b.2)));
+        mismatches
+    }
+
+    pub fn reward_inconsistencies(&self) -> Vec<ActionItem> {
+        self.reward_mismatches().into_iter()
+            .map(|(epoch_id, epoch_name, team_name, stored, expected)| ActionItem::new(
+                ActionPriority::High,
+                epoch_id,
+                format!("Reward for {} in epoch '{}' is {:.6} but its percentage gives {:.6}", team_name, epoch_name, stored, expected),
+            ))
+            .collect()
+    }
+
+    /// `(epoch, team, stored, expected)` for every team reward that deviates from its percentage of the pool
+    pub fn find_reward_inconsistencies(&self) -> Vec<(String, String, f64, f64)> {
+        self.reward_mismatches().into_iter()
+            .map(|(_, epoch_name, team_name, stored, expected)| (epoch_name, team_name, stored, expected))
+            .collect()
+    }
+
+    pub fn find_placeholder_raffles(&self) -> Vec<String> {
+        let mut found: Vec<String> = self.placeholder_raffles().into_iter()
+            .map(|item| item.description)
+            .collect();
+        found.sort();
+        found
+    }
+
+    pub fn approved_unpaid_proposals(&self) -> Vec<ActionItem> {
+        self.state.proposals().values()
+            .filter(|p| p.is_approved())
+            .filter_map(|proposal| {
+                let details = proposal.budget_request_details()?;
+                if details.is_paid() {
+                    return None;
+                }
+
+                let mut amounts: Vec<String> = details.request_amounts().iter()
+                    .map(|(token, amount)| format!("{} {}", amount, token))
+                    .collect();
+                amounts.sort();
+
+                let mut description = if details.is_partially_paid() {
+                    format!("Approved proposal '{}' is partially paid ({} requested)", proposal.title(), amounts.join(", "))
+                } else {
+                    format!("Approved proposal '{}' is awaiting payment ({})", proposal.title(), amounts.join(", "))
+                };
+                let treasurer = details.team()
+                    .and_then(|team_id| self.get_team(&team_id))
+                    .and_then(|team| team.representative_for_role(TREASURER_ROLE));
+                if let Some(treasurer) = treasurer {
+                    description.push_str(&format!(" - contact treasurer {}", treasurer.name));
+                }
+                Some(ActionItem::new(ActionPriority::High, proposal.id(), description))
+            })
+            .collect()
+    }
+
+    /// Earner teams whose trailing revenue is unchanged since a raffle held in an earlier epoch
+    pub fn stale_earner_revenue(&self) -> Vec<ActionItem> {
+        let current_epoch_id = match self.state.current_epoch() {
+            Some(id) => id,
+            None => return Vec::new(),
+        };
+
+        self.state.current_state().teams().values()
+            .filter(|t| t.is_earner())
+            .filter_map(|team| {
+                let oldest_epoch = self.state.raffles().values()
+                    .filter(|r| r.config().epoch_id() != current_epoch_id)
+                    .filter(|r| r.team_snapshots().iter()
+                        .any(|s| s.id() == team.id() && s.status() == team.status()))
+                    .filter_map(|r| self.state.get_epoch(&r.config().epoch_id()))
+                    .min_by_key(|e| e.start_date())?;
+
+                Some(ActionItem::new(
+                    ActionPriority::Low,
+                    team.id(),
+                    format!("Team '{}' has not updated its trailing revenue since epoch '{}'", team.name(), oldest_epoch.name()),
+                ))
+            })
+            .collect()
+    }
+
+    /// Runs a command and describes the outcome as a JSON object. Report commands
+    /// serialize their underlying data; other commands include the IDs they created.
+    pub async fn execute_command_json(&mut self, command: Command) -> Result<serde_json::Value, Box<dyn Error>> {
+        let command_name = command.name();
+
+        let mut value = match command {
+            Command::CreateEpoch { name, start_date, end_date } => {
+                let epoch_id = self.create_epoch(&name, start_date, end_date)?;
+                json!({ "epoch_id": epoch_id, "name": name })
+            },
+            Command::CreateRecurringEpochs { base_name, start_date, period_days, count } => {
+                let epoch_ids = self.create_recurring_epochs(&base_name, start_date, period_days, count)?;
+                json!({ "epoch_ids": epoch_ids })
+            },
+            Command::AddTeam { name, representative, trailing_monthly_revenue, address, contacts } => {
+                let team_id = if self.idempotent {
+                    self.add_or_update_team(name.clone(), representative, trailing_monthly_revenue, address, contacts)?.0
+                } else {
+                    self.create_team_with_contacts(name.clone(), representative, trailing_monthly_revenue, address, contacts)?
+                };
+                json!({ "team_id": team_id, "name": name })
+            },
+            Command::AddProposal { title, url, budget_request_details, announced_at, published_at, is_historical, tags, is_draft, kind, depends_on } => {
+                self.execute_command(Command::AddProposal {
+                    title: title.clone(), url, budget_request_details, announced_at, published_at, is_historical, tags, is_draft, kind, depends_on
+                }).await?;
+                json!({ "proposal_id": self.get_proposal_id_by_name(&title), "title": title })
+            },
+            Command::PrintTeamReport => {
+                let teams = self.sorted_teams();
+                let teams: Vec<serde_json::Value> = teams.into_iter()
+                    .map(|team| {
+                        let points: HashMap<String, u32> = self.state.epochs().values()
+                            .map(|epoch| (epoch.name().to_string(), self.get_team_points_for_epoch(team.id(), epoch.id()).unwrap_or(0)))
+                            .collect();
+                        json!({ "team": team, "points_per_epoch": points })
+                    })
+                    .collect();
+                json!({ "teams": teams })
+            },
+            Command::PrintEpochState => {
+                let epoch = self.get_current_epoch().ok_or("No active epoch")?;
+                let proposals = self.get_proposals_for_epoch(epoch.id());
+                json!({ "epoch": epoch, "proposals": proposals })
+            },
+            Command::PrintPointReport { epoch_name } => {
+                let (epoch, epoch_id) = self.get_current_or_specified_epoch(epoch_name.as_deref())?;
+                let teams = self.sorted_teams();
+                let points: Vec<serde_json::Value> = teams.into_iter()
+                    .map(|team| json!({
+                        "team_id": team.id(),
+                        "name": team.name(),
+                        "points": self.get_team_points_for_epoch(team.id(), epoch_id).unwrap_or(0),
+                    }))
+                    .collect();
+                json!({ "epoch": epoch.name(), "points": points })
+            },
+            Command::ActionItems => json!({ "action_items": self.action_items() }),
+            Command::DilutionReport { epoch_name } => {
+                let (_, epoch_id) = self.get_current_or_specified_epoch(epoch_name.as_deref())?;
+                let dilution: Vec<serde_json::Value> = self.dilution_report(epoch_id).into_iter()
+                    .map(|(team, points_lost)| json!({ "team": team, "percentage_points_lost": points_lost }))
+                    .collect();
+                json!({ "dilution": dilution })
+            },
+            Command::CashflowForecast { epoch_name } => {
+                let (_, epoch_id) = self.get_current_or_specified_epoch(epoch_name.as_deref())?;
+                let forecast: Vec<serde_json::Value> = self.cashflow_forecast(epoch_id).into_iter()
+                    .map(|(date, amounts)| json!({ "date": date, "amounts": amounts }))
+                    .collect();
+                json!({ "forecast": forecast })
+            },
+            Command::RecomputeVoteOutcome { vote_id, commit } => {
+                let (old_passed, new_passed) = self.recompute_vote_outcome(vote_id, commit)?;
+                json!({ "vote_id": vote_id, "old_passed": old_passed, "new_passed": new_passed, "committed": commit })
+            },
+            Command::PromoteInformalVote { vote_id, raffle_id } => {
+                let formal_vote_id = self.promote_informal_vote(vote_id, raffle_id)?;
+                json!({ "vote_id": formal_vote_id, "promoted_from": vote_id })
+            },
+            Command::ResetVote { vote_id } => {
+                self.reset_vote(vote_id)?;
+                json!({ "vote_id": vote_id, "reset": true })
+            },
+            Command::PreviewEpochRewards { epoch_name } => {
+                let (epoch, epoch_id) = self.get_current_or_specified_epoch(epoch_name.as_deref())?;
+                let token = epoch.reward().map(|reward| reward.token().to_string());
+                let rewards: Vec<serde_json::Value> = self.preview_epoch_rewards(epoch_id)?.into_iter()
+                    .map(|(team, percentage, amount)| json!({ "team": team, "percentage": percentage, "amount": amount }))
+                    .collect();
+                json!({ "epoch_id": epoch_id, "token": token, "rewards": rewards })
+            },
+            Command::PreviewRaffleEligibility { proposal_name, excluded_teams } => {
+                let eligible: Vec<serde_json::Value> = self.preview_raffle_eligibility(&proposal_name, excluded_teams)?.into_iter()
+                    .map(|(team, status, tickets)| json!({ "team": team, "status": team_status_label(&status), "tickets": tickets }))
+                    .collect();
+                json!({ "proposal": proposal_name, "eligible_teams": eligible })
+            },
+            Command::DumpScript => json!({ "script": self.to_script() }),
+            Command::ValidateState => json!({ "integrity_errors": self.validate_state() }),
+            Command::EpochFunnel { epoch_name } => {
+                let (_, epoch_id) = self.get_current_or_specified_epoch(epoch_name.as_deref())?;
+                json!(self.epoch_funnel(epoch_id))
+            },
+            Command::ProjectedCompletion => json!({ "projected_completion": self.projected_epoch_completion() }),
+            Command::EffectiveTeamCount { epoch_name } => {
+                let (_, epoch_id) = self.get_current_or_specified_epoch(epoch_name.as_deref())?;
+                json!({ "effective_team_count": self.effective_team_count(epoch_id) })
+            },
+            Command::CompetitionRatio { epoch_name } => {
+                let (_, epoch_id) = self.get_current_or_specified_epoch(epoch_name.as_deref())?;
+                json!({ "average_competition_ratio": self.avg_competition_ratio(epoch_id) })
+            },
+            Command::PrintTeamVoteParticipation { team_name, epoch_name } => {
+                let team_id = self.require_team_id(&team_name)?;
+                let (epoch, epoch_id) = self.get_current_or_specified_epoch(epoch_name.as_deref())?;
+                let eligible_votes = self.eligible_vote_history(team_id, epoch_id).len();
+                let votes = self.team_vote_participation(team_id, epoch)?;
+                json!({
+                    "team": team_name,
+                    "epoch": epoch.name(),
+                    "eligible_votes": eligible_votes,
+                    "participation_rate": (eligible_votes > 0).then(|| self.team_participation_rate(team_id, epoch_id)),
+                    "streak": self.team_vote_streak(team_id, epoch_id),
+                    "total_points": votes.iter().map(|vote| vote.points).sum::<u32>(),
+                    "votes": votes,
+                })
+            },
+            Command::GenerateEndOfEpochReport { epoch_name, format } => {
+                let path = self.generate_end_of_epoch_report_with_format(&epoch_name, format)?;
+                let epoch = self.closed_epoch_by_name(&epoch_name)?;
+                json!({ "epoch": epoch, "proposals": self.get_proposals_for_epoch(epoch.id()), "path": path })
+            },
+            Command::GenerateUnpaidRequestsReport { output_path, epoch_name } => {
+                let (path, report) = self.save_unpaid_requests_report(output_path.as_deref(), epoch_name.as_deref())?;
+                json!({ "path": path, "generated_at": report.generated_at, "unpaid_requests": report.unpaid_requests })
+            },
+            Command::GenerateReportsForClosedProposals { epoch_name, since } => {
+                let summary = self.regenerate_closed_proposal_reports(&epoch_name, since)?;
+                let generated: Vec<serde_json::Value> = summary.generated.into_iter()
+                    .map(|(title, path)| json!({ "proposal": title, "path": path }))
+                    .collect();
+                let failed: Vec<serde_json::Value> = summary.failed.into_iter()
+                    .map(|(title, error)| json!({ "proposal": title, "error": error }))
+                    .collect();
+                json!({ "epoch": epoch_name, "generated": generated, "failed": failed, "skipped": summary.skipped })
+            },
+            Command::GenerateReportForProposal { proposal_name } => {
+                let (title, path) = self.generate_report_for_current_proposal(&proposal_name)?;
+                json!({ "proposal": title, "path": path })
+            },
+            Command::ExportRaffleTickets { proposal_name, format } => {
+                json!({ "proposal": proposal_name.clone(), "path": self.save_raffle_tickets(&proposal_name, format)? })
+            },
+            Command::ExportEpochRewardsCsv { epoch_name } => {
+                json!({ "epoch": epoch_name.clone(), "path": self.save_epoch_rewards_csv(&epoch_name)? })
+            },
+            Command::CloseEpoch { epoch_name, confirm_no_reward, carry_over_to } => {
+                let carry_over_to = carry_over_to
+                    .map(|name| self.require_epoch_id(&name))
+                    .transpose()?;
+                let epoch_id = match &epoch_name {
+                    Some(name) => self.get_epoch_id_by_name(name),
+                    None => self.state.current_epoch(),
+                };
+                let warning = self.close_epoch_with_warnings(epoch_name.as_deref(), confirm_no_reward, carry_over_to)?;
+                json!({ "epoch": epoch_id.and_then(|id| self.state.get_epoch(&id)), "warning": warning })
+            },
+            Command::FinalizeEpoch { epoch_name } => {
+                let archive_path = self.finalize_epoch(&epoch_name)?;
+                json!({ "epoch": epoch_name, "archive_path": archive_path })
+            },
+            Command::TopUpReward { epoch_name, amount, token } => {
+                let top_ups: Vec<serde_json::Value> = self.top_up_epoch_reward(&epoch_name, amount, &token)?.into_iter()
+                    .map(|(team, added)| json!({ "team": team, "amount": added }))
+                    .collect();
+                json!({ "epoch": epoch_name, "token": token, "amount": amount, "top_ups": top_ups })
+            },
+            Command::PayTeamReward { epoch_name, team_name } => {
+                let token = self.get_epoch_id_by_name(&epoch_name)
+                    .and_then(|id| self.state.get_epoch(&id))
+                    .and_then(|epoch| epoch.reward())
+                    .map(|reward| reward.token().to_string());
+                let (tx_hash, amount) = self.pay_team_reward(&epoch_name, &team_name).await?;
+                json!({ "epoch": epoch_name, "team": team_name, "tx_hash": tx_hash, "amount": amount, "token": token })
+            },
+            Command::EstimateOdds { team_name, excluded_teams } => {
+                let odds = self.estimate_raffle_odds(&team_name, excluded_teams)?;
+                json!({ "team": team_name, "odds": odds })
+            },
+            Command::CreateRaffle { proposal_name, block_offset, excluded_teams, exclude_inactive } => {
+                self.execute_command(Command::CreateRaffle {
+                    proposal_name: proposal_name.clone(), block_offset, excluded_teams, exclude_inactive
+                }).await?;
+                let (_, raffle_id) = self.find_proposal_and_raffle(&proposal_name)?;
+                self.raffle_json(raffle_id)?
+            },
+            Command::CreateRaffleWithSeed { proposal_name, seed } => {
+                let (raffle_id, _) = self.create_seeded_raffle(&proposal_name, &seed)?;
+                self.raffle_json(raffle_id)?
+            },
+            Command::RedrawRaffle { proposal_name, randomness_block } => {
+                let (_, previous_id) = self.find_proposal_and_raffle(&proposal_name)?;
+                let raffle_id = self.redraw_raffle(&proposal_name, randomness_block).await?;
+                let mut value = self.raffle_json(raffle_id)?;
+                value["supersedes"] = json!(previous_id);
+                value
+            },
+            Command::VerifyRaffle { proposal_name } => {
+                let (raffle_id, divergence) = self.check_raffle(&proposal_name)?;
+                json!({ "proposal": proposal_name, "raffle_id": raffle_id, "passed": divergence.is_none(), "first_divergent_team": divergence })
+            },
+            Command::CreateAndProcessVote { proposal_name, counted_votes, uncounted_votes, vote_opened, vote_closed, counted_points, uncounted_points, threshold } => {
+                self.create_and_process_vote(
+                    &proposal_name, counted_votes, uncounted_votes, vote_opened, vote_closed, counted_points, uncounted_points, threshold,
+                )?;
+                self.latest_vote_json(&proposal_name)?
+            },
+            Command::CreateAndProcessMultiOptionVote { proposal_name, options, counted_votes, uncounted_votes, vote_opened, vote_closed } => {
+                self.create_and_process_multi_option_vote(&proposal_name, options, counted_votes, uncounted_votes, vote_opened, vote_closed)?;
+                self.latest_vote_json(&proposal_name)?
+            },
+            Command::OpenVoteStatus { vote_id } => {
+                let vote = self.state.get_vote(&vote_id).ok_or("Vote not found")?;
+                let proposal = self.state.get_proposal(&vote.proposal_id()).ok_or("Proposal not found")?;
+                if vote.is_closed() {
+                    json!({ "vote_id": vote_id, "proposal": proposal.title(), "closed": true, "result": vote.result() })
+                } else {
+                    let outstanding = self.outstanding_voters(vote_id).ok()
+                        .map(|(counted, uncounted)| json!({ "counted": counted, "uncounted": uncounted }));
+                    json!({
+                        "vote_id": vote_id,
+                        "proposal": proposal.title(),
+                        "closed": false,
+                        "opened_at": vote.opened_at(),
+                        "participation": vote.participation(),
+                        "outstanding": outstanding,
+                        "projected_result": vote.projected_result()?,
+                    })
+                }
+            },
+            Command::OutstandingVoters { proposal_name } => {
+                let vote_id = self.current_formal_vote_id(&proposal_name)?;
+                let (counted, uncounted) = self.outstanding_voters(vote_id)?;
+                json!({ "proposal": proposal_name, "vote_id": vote_id, "counted": counted, "uncounted": uncounted })
+            },
+            Command::CompareEpochs { epoch_a, epoch_b } => {
+                let find = |name: &str| self.get_epoch_id_by_name(name)
+                    .and_then(|id| self.state.get_epoch(&id))
+                    .ok_or_else(|| NotFoundError { kind: "Epoch", name: name.to_string() });
+                let (a, b) = (find(&epoch_a)?, find(&epoch_b)?);
+                let summary = |epoch: &Epoch| {
+                    let (total, approved, rejected) = self.epoch_proposal_counts(epoch.id());
+                    json!({ "name": epoch.name(), "proposals": total, "approved": approved, "rejected": rejected, "reward": epoch.reward() })
+                };
+                let teams: Vec<serde_json::Value> = self.compared_team_points(a.id(), b.id()).into_iter()
+                    .map(|(team, points_a, points_b)| json!({ "team": team, "points_a": points_a, "points_b": points_b }))
+                    .collect();
+                json!({ "epoch_a": summary(a), "epoch_b": summary(b), "teams": teams })
+            },
+            Command::SimulateThreshold { epoch_name, threshold } => {
+                if !(0.0..=1.0).contains(&threshold) {
+                    return Err("Threshold must be between 0 and 1".into());
+                }
+                let (_, epoch_id) = self.get_current_or_specified_epoch(epoch_name.as_deref())?;
+                let (outcomes, skipped) = self.threshold_simulation(epoch_id, threshold);
+                let outcomes: Vec<serde_json::Value> = outcomes.into_iter()
+                    .map(|(proposal, passed, simulated)| json!({ "proposal": proposal, "passed": passed, "simulated_passed": simulated }))
+                    .collect();
+                json!({ "threshold": threshold, "outcomes": outcomes, "skipped": skipped })
+            },
+            Command::OverdueProposals { epoch_name } => {
+                let (_, epoch_id) = self.get_current_or_specified_epoch(epoch_name.as_deref())?;
+                let overdue: Vec<serde_json::Value> = self.overdue_proposals(epoch_id).into_iter()
+                    .map(|proposal| json!({
+                        "proposal_id": proposal.id(),
+                        "title": proposal.title(),
+                        "days_overdue": self.days_overdue(proposal),
+                        "deadline": self.decision_deadline(proposal),
+                    }))
+                    .collect();
+                json!({ "overdue_proposals": overdue })
+            },
+            Command::Search { query } => json!(self.search(&query)),
+            Command::ListProposals { epoch_name, status, team_name, announced_after, announced_before, tag } => {
+                let epoch_id = match &epoch_name {
+                    Some(name) => Some(self.require_epoch_id(name)?),
+                    None => None,
+                };
+                json!({ "proposals": self.query_proposals(ProposalFilter { epoch_id, status, team_name, announced_after, announced_before, tag }) })
+            },
+            Command::EpochAmendmentLog { epoch_name } => {
+                let (_, epoch_id) = self.get_current_or_specified_epoch(epoch_name.as_deref())?;
+                let log: Vec<serde_json::Value> = self.epoch_amendment_log(epoch_id).into_iter()
+                    .map(|(proposal, amendments)| json!({ "proposal": proposal, "amendments": amendments }))
+                    .collect();
+                json!({ "amendment_log": log })
+            },
+            Command::CheckRepresentatives => {
+                let shared: Vec<serde_json::Value> = self.shared_representatives().into_iter()
+                    .map(|(representative, teams)| json!({ "representative": representative, "teams": teams }))
+                    .collect();
+                json!({ "shared_representatives": shared })
+            },
+            Command::TeamApprovalVelocity { team_name } => {
+                let team_id = self.require_team_id(&team_name)?;
+                json!({ "team": team_name, "median_days_to_approval": self.team_approval_velocity(team_id) })
+            },
+            Command::CheckPayment { proposal_name } => {
+                let (tx_hash, status) = self.check_payment(&proposal_name).await?;
+                let (status, block, confirmations) = match status {
+                    TxStatus::Pending => ("pending", None, None),
+                    TxStatus::Confirmed { block, confirmations } => ("confirmed", Some(block), Some(confirmations)),
+                    TxStatus::Failed => ("failed", None, None),
+                };
+                json!({ "proposal": proposal_name, "tx_hash": tx_hash, "status": status, "block": block, "confirmations": confirmations })
+            },
+            Command::VerifyState => {
+                let state_file = self.config.state_file.clone();
+                let checksum = match FileSystem::verify_state_checksum(&state_file)? {
+                    StateChecksum::Valid => "valid",
+                    StateChecksum::Missing => "missing",
+                    StateChecksum::Mismatch { .. } => return Err(FileSystem::verify_state_file(&state_file).unwrap_err()),
+                };
+                json!({ "state_file": state_file, "checksum": checksum })
+            },
+            // Everything else only acknowledges a change, so the text is all there is to report
+            other => json!({ "message": self.execute_command(other).await? }),
+        };
+
+        if let Some(object) = value.as_object_mut() {
+            object.insert("command".to_string(), json!(command_name));
+        }
+        Ok(value)
+    }
+
+    /// A raffle's randomness and seats, with teams named as they were snapshotted
+    fn raffle_json(&self, raffle_id: Uuid) -> Result<serde_json::Value, Box<dyn Error>> {
+        let raffle = self.state.get_raffle(&raffle_id).ok_or("Raffle not found")?;
+        let result = raffle.result().ok_or("Raffle has no result")?;
+        let seats = |ids: &[Uuid]| ids.iter()
+            .map(|id| json!({
+                "team_id": id,
+                "name": raffle.team_snapshots().iter().find(|s| s.id() == *id).map(|s| self.snapshot_display_name(s)),
+            }))
+            .collect::<Vec<_>>();
+        Ok(json!({
+            "raffle_id": raffle_id,
+            "proposal_id": raffle.config().proposal_id(),
+            "randomness_block": raffle.config().randomness_block(),
+            "block_randomness": raffle.config().block_randomness(),
+            "randomness_source": raffle.randomness_source(),
+            "counted": seats(result.counted()),
+            "uncounted": seats(result.uncounted()),
+        }))
+    }
+
+    /// The proposal's most recently opened vote, with its participation and result
+    fn latest_vote_json(&self, proposal_name: &str) -> Result<serde_json::Value, Box<dyn Error>> {
+        let proposal_id = self.require_proposal_id(proposal_name)?;
+        let vote = self.state.votes().values()
+            .filter(|vote| vote.proposal_id() == proposal_id)
+            .max_by_key(|vote| vote.opened_at())
+            .ok_or("Vote not found after processing")?;
+        Ok(json!({
+            "proposal": proposal_name,
+            "vote_id": vote.id(),
+            "participation": vote.participation(),
+            "result": vote.result(),
+        }))
+    }
+
+    /// Runs the commands against an in-memory copy of the state, with saves discarded and mock
+    /// Ethereum randomness, and returns each command's index and outcome. This system is untouched.
+    /// Report and export commands only write files, so they are skipped and count as successes.
+    pub async fn sandbox_run(&self, commands: &[Command]) -> Vec<(usize, Result<(), String>)> {
+        // Simulated commands must not reach the real audit log
+        let mut config = self.config.clone();
+        config.audit_log.clear();
+        let mut sandbox = match Self::with_store(
+            config,
+            Arc::new(MockEthereumService::new()),
+            Some(self.state.clone()),
+            Box::new(NullStore),
+        ) {
+            Ok(sandbox) => sandbox,
+            Err(e) => return commands.iter().enumerate().map(|(i, _)| (i, Err(e.to_string()))).collect(),
+        };
+        sandbox.idempotent = self.idempotent;
+
+        let mut results = Vec::new();
+        for (index, command) in commands.iter().enumerate() {
+            let result = match command {
+                Command::GenerateReportsForClosedProposals { .. }
+                | Command::GenerateReportForProposal { .. }
+                | Command::GenerateEndOfEpochReport { .. }
+                | Command::GenerateUnpaidRequestsReport { .. }
+                | Command::ExportRaffleTickets { .. }
+                | Command::ExportEpochRewardsCsv { .. } => Ok(()),
+                Command::FinalizeEpoch { epoch_name } => sandbox.close_epoch(Some(epoch_name.as_str()), None)
+                    .map_err(|e| e.to_string()),
+                command => sandbox.execute_command(command.clone()).await
+                    .map(|_| ())
+                    .map_err(|e| e.to_string()),
+            };
+            results.push((index, result));
+        }
+        results
+    }
+
+    /// Commands that rebuild the current state when replayed on an empty system.
+    /// Raffles and votes are emitted as imports of their recorded outcome, so weighted votes
+    /// come back as formal ones. Informal and multi-option votes are not included, and replayed payments are
+    /// dated on the day of the replay.
+    pub fn to_script(&self) -> Vec<Command> {
+        let mut script = Vec::new();
+        let mut dependencies = Vec::new();
+
+        let team_name = |id: &Uuid| self.state.get_team(id).map(|team| team.name().to_string());
+
+        let teams = self.sorted_teams();
+
+        for team in &teams {
+            let trailing_monthly_revenue = match team.status() {
+                TeamStatus::Earner { trailing_monthly_revenue } => Some(trailing_monthly_revenue.clone()),
+                _ => None,
+            };
+            script.push(Command::AddTeam {
+                name: team.name().to_string(),
+                representative: team.representative().to_string(),
+                trailing_monthly_revenue,
+                address: team.payment_address().map(|address| format!("{:?}", address)),
+                contacts: team.contacts().to_vec(),
+            });
+            if !team.representatives().is_empty() {
+                script.push(Command::SetRepresentatives {
+                    team_name: team.name().to_string(),
+                    representatives: team.representatives().to_vec(),
+                });
+            }
+        }
+
+        let epochs = self.epochs_by_start_date();
+
+        for epoch in epochs {
+            script.push(Command::CreateEpoch {
+                name: epoch.name().to_string(),
+                start_date: epoch.start_date(),
+                end_date: epoch.end_date(),
+            });
+
+            let mut budget_caps: Vec<_> = epoch.budget_cap().iter().collect();
+            budget_caps.sort_by(|a, b| a.0.cmp(b.0));
+            let budget_cap_commands: Vec<Command> = budget_caps.into_iter()
+                .map(|(token, amount)| Command::SetEpochBudgetCap {
+                    epoch_name: Some(epoch.name().to_string()),
+                    token: token.clone(),
+                    amount: *amount,
+                })
+                .collect();
+
+            if epoch.is_planned() {
+                script.extend(budget_cap_commands);
+                continue;
+            }
+
+            script.push(Command::ActivateEpoch { name: epoch.name().to_string() });
+
+            if let Some(reward) = epoch.reward() {
+                script.push(Command::SetEpochReward {
+                    token: reward.token().to_string(),
+                    amount: reward.amount(),
+                });
+            }
+
+            let mut adjustments: Vec<(String, f64, String)> = epoch.reward_adjustments().iter()
+                .filter_map(|(team_id, &multiplier)| team_name(team_id).map(|name| {
+                    (name, multiplier, epoch.reward_adjustment_reason(team_id).unwrap_or_default().to_string())
+                }))
+                .collect();
+            adjustments.sort_by(|a, b| a.0.cmp(&b.0));
+            for (team_name, multiplier, reason) in adjustments {
+                script.push(Command::SetRewardAdjustment {
+                    epoch_name: epoch.name().to_string(),
+                    team_name,
+                    multiplier,
+                    reason,
+                });
+            }
+
+            for proposal in self.get_proposals_for_epoch(epoch.id()) {
+                let title = proposal.title().to_string();
+
+                script.push(Command::AddProposal {
+                    title: title.clone(),
+                    url: proposal.url().map(String::from),
+                    budget_request_details: proposal.budget_request_details().map(|details| BudgetRequestDetailsCommand {
+                        team: details.team().and_then(|id| team_name(&id)),
+                        request_amounts: Some(details.request_amounts().clone()),
+                        start_date: details.start_date(),
+                        end_date: details.end_date(),
+                        is_loan: Some(details.is_loan()),
+                        payment_address: details.payment_address().map(|address| format!("{:?}", address)),
+                        monthly_breakdown: details.monthly_breakdown().cloned(),
+                    }),
+                    announced_at: proposal.announced_at(),
+                    published_at: proposal.published_at(),
+                    is_historical: Some(proposal.is_historical()),
+                    tags: proposal.tags().to_vec(),
+                    is_draft: proposal.is_draft(),
+                    kind: Some(proposal.kind()),
+                    depends_on: Vec::new(),
+                });
+
+                if !proposal.depends_on().is_empty() {
+                    dependencies.push((title.clone(), proposal.depends_on()));
+                }
+
+                let raffle = self.active_raffle_for_proposal(proposal.id());
+                let raffle_result = raffle.and_then(|raffle| raffle.result().map(|result| (raffle, result)));
+
+                if let Some((raffle, result)) = raffle_result {
+                    let counted_teams: Vec<String> = result.counted().iter().filter_map(team_name).collect();
+                    let uncounted_teams: Vec<String> = result.uncounted().iter().filter_map(team_name).collect();
+                    script.push(Command::ImportPredefinedRaffle {
+                        proposal_name: title.clone(),
+                        total_counted_seats: counted_teams.len(),
+                        max_earner_seats: raffle.config().max_earner_seats().min(counted_teams.len()),
+                        counted_teams: counted_teams.clone(),
+                        uncounted_teams: uncounted_teams.clone(),
+                    });
+
+                    let vote = self.state.votes().values()
+                        .find(|vote| vote.proposal_id() == proposal.id());
+
+                    if let Some(vote) = vote {
+                        if let (
+                            VoteType::Formal { counted_points, uncounted_points, .. }
+                                | VoteType::Weighted { counted_points, uncounted_points, .. },
+                            VoteParticipation::Formal { counted, uncounted },
+                            Some(VoteResult::Formal { passed, .. } | VoteResult::Weighted { passed, .. }),
+                        ) = (vote.vote_type(), vote.participation(), vote.result()) {
+                            let participating_teams: Vec<String> = counted.iter()
+                                .chain(uncounted.iter())
+                                .filter_map(team_name)
+                                .collect();
+                            // An empty participant list means everyone took part, so name the absentees instead
+                            let non_participating_teams = if participating_teams.is_empty() {
+                                counted_teams.iter().chain(uncounted_teams.iter()).cloned().collect()
+                            } else {
+                                Vec::new()
+                            };
+                            script.push(Command::ImportHistoricalVote {
+                                proposal_name: title.clone(),
+                                passed: *passed,
+                                participating_teams,
+                                non_participating_teams,
+                                abstaining_teams: Vec::new(),
+                                counted_points: Some(*counted_points),
+                                uncounted_points: Some(*uncounted_points),
+                            });
+                        }
+                    }
+                }
+
+                let closed_by_vote = self.state.votes().values()
+                    .any(|vote| vote.proposal_id() == proposal.id() && matches!(vote.result(), Some(VoteResult::Formal { .. } | VoteResult::Weighted { .. })))
+                    && raffle_result.is_some();
+
+                if proposal.is_closed() && !closed_by_vote {
+                    if let Some(resolution) = proposal.resolution() {
+                        script.push(Command::CloseProposal {
+                            proposal_name: title.clone(),
+                            resolution: format!("{:?}", resolution),
+                        });
+                    }
+                }
+
+                if proposal.resolved_at().is_some() || proposal.decision_deadline().is_some() {
+                    script.push(Command::UpdateProposal {
+                        proposal_name: title.clone(),
+                        updates: UpdateProposalDetails {
+                            title: None,
+                            url: None,
+                            budget_request_details: None,
+                            announced_at: None,
+                            published_at: None,
+                            resolved_at: proposal.resolved_at(),
+                            tags: None,
+                            decision_deadline: proposal.decision_deadline(),
+                            depends_on: None,
+                        },
+                    });
+                }
+
+                for payment in proposal.budget_request_details().map_or(&[][..], |details| details.payments()) {
+                    script.push(Command::AddMilestonePayment {
+                        proposal_name: title.clone(),
+                        amount: payment.amount,
+                        token: payment.token.clone(),
+                        date: payment.date,
+                        tx: payment.tx.clone(),
+                    });
+                }
+
+                if let Some(tx) = proposal.budget_request_details().and_then(|details| details.payment_tx()) {
+                    script.push(Command::RecordPayment {
+                        proposal_name: title.clone(),
+                        tx_hash: format!("{:?}", tx),
+                    });
+                }
+
+                let mut sponsors: Vec<String> = proposal.sponsors().iter().filter_map(team_name).collect();
+                sponsors.sort();
+                for sponsor in sponsors {
+                    script.push(Command::AddSponsor { proposal_name: title.clone(), team_name: sponsor });
+                }
+
+                for attachment in proposal.attachments() {
+                    script.push(Command::AddAttachment {
+                        proposal_name: title.clone(),
+                        label: attachment.label.clone(),
+                        cid: attachment.cid.clone(),
+                    });
+                }
+            }
+
+            // Caps go after the approvals they were checked against, which a lowered cap would otherwise reject
+            script.extend(budget_cap_commands);
+
+            if epoch.is_closed() {
+                // The target epoch's reward already includes any carry-over, so it is not replayed
+                script.push(Command::CloseEpoch { epoch_name: Some(epoch.name().to_string()), confirm_no_reward: true, carry_over_to: None });
+            }
+        }
+
+        // Dependencies once every proposal exists, since they can point forward or across epochs
+        for (title, depends_on) in dependencies {
+            script.push(Command::UpdateProposal {
+                proposal_name: title,
+                updates: UpdateProposalDetails {
+                    title: None,
+                    url: None,
+                    budget_request_details: None,
+                    announced_at: None,
+                    published_at: None,
+                    resolved_at: None,
+                    tags: None,
+                    decision_deadline: None,
+                    depends_on: Some(depends_on.iter()
+                        .filter_map(|id| self.state.get_proposal(id).map(|dependency| dependency.title().to_string()))
+                        .collect()),
+                },
+            });
+        }
+
+        // Status changes last, so inactive teams can still appear in earlier raffles
+        for team in teams.iter().filter(|team| team.is_inactive()) {
+            script.push(Command::UpdateTeam {
+                team_name: team.name().to_string(),
+                updates: UpdateTeamDetails {
+                    name: None,
+                    representative: None,
+                    status: Some("inactive".to_string()),
+                    trailing_monthly_revenue: None,
+                    address: None,
+                    contacts: None,
+                },
+            });
+        }
+
+        script
+    }
+
+}
+
+#[async_trait]
+impl CommandExecutor for BudgetSystem {
+    async fn execute_command(&mut self, command: Command) -> Result<String, Box<dyn std::error::Error>> {
+        let snapshot = command.is_mutating().then(|| self.state.clone());
+        self.begin_audit(&command);
+        let result = self.dispatch_command(command).await;
+        // Commands that failed before saving changed nothing and leave no entry
+        self.pending_audit.lock().unwrap().take();
+        if let (Ok(_), Some(snapshot)) = (&result, snapshot) {
+            self.push_undo_state(snapshot);
+        }
+        result
+    }
+
+    async fn execute_command_with_streaming<W: Write + Send + 'static>(
+        &mut self, 
+        command: Command, 
+        output: &mut W
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        match command {
+            Command::CreateRaffle { proposal_name, block_offset, excluded_teams, exclude_inactive } => {
+                let snapshot = self.state.clone();
+                self.begin_audit(&Command::CreateRaffle {
+                    proposal_name: proposal_name.clone(),
+                    block_offset,
+                    excluded_teams: excluded_teams.clone(),
+                    exclude_inactive,
+                });
+                let mut cancelled = false;
+                let mut failure = None;
+                {
+                    let progress_stream = self.create_raffle_with_progress(
+                        proposal_name,
+                        block_offset,
+                        excluded_teams,
+                        exclude_inactive.unwrap_or(true),
+                    ).await;
+                    
+                    pin_mut!(progress_stream);
+                    
+                    loop {
+                        let next = tokio::select! {
+                            next = progress_stream.next() => next,
+                            _ = tokio::signal::ctrl_c() => {
+                                cancelled = true;
+                                None
+                            }
+                        };
+                        match next {
+                            Some(Ok(progress)) => {
+                                writeln!(output, "{}", progress.format_message())?;
+                                output.flush()?;
+                                if progress.is_complete() {
+                                    break;
+                                }
+                            },
+                            Some(Err(e)) => {
+                                failure = Some(e);
+                                break;
+                            },
+                            None => break,
+                        }
+                    }
+                }
+                if let Some(e) = failure {
+                    self.pending_audit.lock().unwrap().take();
+                    return Err(Box::new(std::io::Error::new(
+                        std::io::ErrorKind::Other,
+                        e.0
+                    )));
+                }
+                if cancelled {
+                    // Roll back the prepared raffle so an aborted wait leaves the state as it was
+                    self.state = snapshot;
+                    self.save_state()?;
+                    return Err("Raffle creation cancelled".into());
+                }
+                self.pending_audit.lock().unwrap().take();
+                self.push_undo_state(snapshot);
+                Ok(())
+            },
+            // For commands that don't support streaming, fall back to the original implementation
+            _ => {
+                let result = self.execute_command(command).await?;
+                write!(output, "{}", result)?;
+                Ok(())
+            }
+        }
+    }
+}
+
+impl BudgetSystem {
+    async fn dispatch_command(&mut self, command: Command) -> Result<String, Box<dyn std::error::Error>> {
+        match command {
+            Command::CreateEpoch { name, start_date, end_date } => {
+                let epoch_id = self.create_epoch(&name, start_date, end_date)?;
+                Ok(format!("Created epoch: {} ({})", name, epoch_id))
+            },
+            Command::CreateRecurringEpochs { base_name, start_date, period_days, count } => {
+                let epoch_ids = self.create_recurring_epochs(&base_name, start_date, period_days, count)?;
+                let mut output = format!("Created {} epochs:\n", epoch_ids.len());
+                for epoch_id in epoch_ids {
+                    let epoch = self.state.get_epoch(&epoch_id).ok_or("Epoch not found")?;
+                    output.push_str(&format!("- {} ({} to {}, {})\n", epoch.name(), epoch.start_date().date_naive(), epoch.end_date().date_naive(), epoch_id));
+                }
+                Ok(output)
+            },
+            Command::ActivateEpoch { name } => {
+                let epoch_id = self.require_epoch_id(&name)?;
+                self.activate_epoch(epoch_id)?;
+                Ok(format!("Activated epoch: {} ({})", name, epoch_id))
+            },
+            Command::SetEpochReward { token, amount } => {
+                self.set_epoch_reward(&token, amount)?;
+                Ok(format!("Set epoch reward: {} {}", amount, token))
+            },
+            Command::AddTeam { name, representative, trailing_monthly_revenue, address, contacts } => {
+                if self.idempotent {
+                    let (team_id, created) = self.add_or_update_team(name.clone(), representative, trailing_monthly_revenue, address, contacts)?;
+                    let action = if created { "Added" } else { "Updated" };
+                    return Ok(format!("{} team: {} ({})", action, name, team_id));
+                }
+                let team_id = self.create_team_with_contacts(name.clone(), representative, trailing_monthly_revenue, address, contacts)?;
+                Ok(format!("Added team: {} ({})", name, team_id))
+            },
+            Command::UpdateTeam { team_name, updates } => {
+                let team_id = self.require_team_id(&team_name)?;
+                self.update_team(team_id, updates)?;
+                Ok(format!("Updated team: {}", team_name))
+            },
+            Command::RenameTeam { old_name, new_name } => {
+                let team_id = self.require_team_id(&old_name)?;
+                self.rename_team(team_id, new_name.clone())?;
+                Ok(format!("Renamed team: {} -> {}", old_name, new_name))
+            },
+            Command::SetRepresentatives { team_name, representatives } => {
+                let team_id = self.require_team_id(&team_name)?;
+                let count = representatives.len();
+                self.set_team_representatives(team_id, representatives)?;
+                Ok(format!("Set {} representatives for team: {}", count, team_name))
+            },
+            Command::AddProposal { title, url, budget_request_details, announced_at, published_at, is_historical, tags, is_draft, kind, depends_on } => {
+                if kind.is_some_and(|kind| !kind.is_budget()) && budget_request_details.is_some() {
+                    return Err("Only budget proposals can have budget request details".into());
+                }
+                if let Some(proposal_id) = self.get_proposal_id_by_name(&title).filter(|_| self.idempotent) {
+                    if let Some(kind) = kind {
+                        self.set_proposal_kind(proposal_id, kind)?;
+                    }
+                    self.update_proposal(&title, UpdateProposalDetails {
+                        title: None,
+                        url,
+                        budget_request_details,
+                        announced_at,
+                        published_at,
+                        resolved_at: None,
+                        tags: if tags.is_empty() { None } else { Some(tags) },
+                        decision_deadline: None,
+                        depends_on: if depends_on.is_empty() { None } else { Some(depends_on) },
+                    })?;
+                    return Ok(format!("Updated proposal: {} ({})", title, proposal_id));
+                }
+                let depends_on = self.resolve_dependencies(&depends_on)?;
+                let budget_request_details = budget_request_details.map(|details| {
+                    let mut brd = BudgetRequestDetails::new(
+                        details.team.and_then(|name| self.get_team_id_by_name(&name)),
+                        details.request_amounts.unwrap_or_default(),
+                        details.start_date,
+                        details.end_date,
+                        details.is_loan,
+                        details.payment_address,
+                    )?;
+                    if details.monthly_breakdown.is_some() {
+                        brd.set_monthly_breakdown(details.monthly_breakdown)?;
+                    }
+                    Ok::<_, &'static str>(brd)
+                }).transpose()?;
+             
+                let proposal_id = self.add_proposal_with_draft(title.clone(), url, budget_request_details, announced_at, published_at, is_historical, is_draft)?;
+                if !tags.is_empty() {
+                    self.set_proposal_tags(proposal_id, tags)?;
+                }
+                if let Some(kind) = kind {
+                    self.set_proposal_kind(proposal_id, kind)?;
+                }
+                if !depends_on.is_empty() {
+                    self.set_proposal_dependencies(proposal_id, depends_on)?;
+                }
+                let label = if is_draft { "draft proposal" } else { "proposal" };
+                Ok(format!("Added {}: {} ({})", label, title, proposal_id))
+             },
+            Command::UpdateProposal { proposal_name, updates } => {
+                self.update_proposal(&proposal_name, updates)?;
+                Ok(format!("Updated proposal: {}", proposal_name))
+            },
+            Command::ImportPredefinedRaffle { 
+                proposal_name, 
+                counted_teams, 
+                uncounted_teams, 
+                total_counted_seats, 
+                max_earner_seats 
+            } => {
+                let raffle_id = self.import_predefined_raffle(
+                    &proposal_name, 
+                    counted_teams.clone(), 
+                    uncounted_teams.clone(), 
+                    total_counted_seats, 
+                    max_earner_seats
+                )?;
+                
+                let raffle = self.state().raffles().get(&raffle_id).unwrap();
+            
+                let mut output = format!("Imported predefined raffle for proposal '{}' (Raffle ID: {})\n", proposal_name, raffle_id);
+                output += &format!("  Counted teams: {:?}\n", counted_teams);
+                output += &format!("  Uncounted teams: {:?}\n", uncounted_teams);
+                output += &format!("  Total counted seats: {}\n", total_counted_seats);
+                output += &format!("  Max earner seats: {}\n", max_earner_seats);
+            
+                output += "\nTeam Snapshots:\n";
+                for snapshot in raffle.team_snapshots() {
+                    output += &format!("  {} ({}): {:?}\n", snapshot.name(), snapshot.id(), snapshot.status());
+                }
+            
+                if let Some(result) = raffle.result() {
+                    output += "\nRaffle Result:\n";
+                    output += &format!("  Counted teams: {:?}\n", result.counted());
+                    output += &format!("  Uncounted teams: {:?}\n", result.uncounted());
+                } else {
+                    output += "\nRaffle result not available\n";
+                }
+            
+                Ok(output)
+            },
+            Command::ImportHistoricalVote { 
+                proposal_name, 
+                passed, 
+                participating_teams,
+                non_participating_teams,
+                abstaining_teams,
+                counted_points,
+                uncounted_points,
+            } => {
+                let vote_id = self.import_historical_vote(
+                    &proposal_name,
+                    passed,
+                    participating_teams.clone(),
+                    non_participating_teams.clone(),
+                    abstaining_teams.clone(),
+                    counted_points,
+                    uncounted_points
+                )?;
+            
+                let vote = self.state().votes().get(&vote_id).unwrap();
+                let proposal = self.state().proposals().get(&vote.proposal_id()).unwrap();
+            
+                let mut output = format!("Imported historical vote for proposal '{}' (Vote ID: {})\n", proposal_name, vote_id);
+                output += &format!("Vote passed: {}\n", passed);
+            
+                output += "\nNon-participating teams:\n";
+                for team_name in &non_participating_teams {
+                    output += &format!("  {}\n", team_name);
+                }
+
+                if !abstaining_teams.is_empty() {
+                    output += "\nAbstaining teams:\n";
+                    for team_name in &abstaining_teams {
+                        output += &format!("  {}\n", team_name);
+                    }
+                }
+            
+                if let VoteType::Formal { raffle_id, .. } = vote.vote_type() {
+                    if let Some(raffle) = self.state().raffles().get(&raffle_id) {
+                        if let VoteParticipation::Formal { counted, uncounted } = vote.participation() {
+                            output += "\nCounted seats:\n";
+                            for &team_id in counted {
+                                if let Some(team) = raffle.team_snapshots().iter().find(|s| s.id() == team_id) {
+                                    output += &format!("  {} (+{} points)\n", team.name(), self.config.counted_vote_points);
+                                }
+                            }
+            
+                            output += "\nUncounted seats:\n";
+                            for &team_id in uncounted {
+                                if let Some(team) = raffle.team_snapshots().iter().find(|s| s.id() == team_id) {
+                                    output += &format!("  {} (+{} points)\n", team.name(), self.config.uncounted_vote_points);
+                                }
+                            }
+                        }
+                    } else {
+                        output += "\nAssociated raffle not found. Cannot display seat breakdowns.\n";
+                    }
+                } else {
+                    output += "\nThis is an informal vote, no counted/uncounted breakdown available.\n";
+                }
+            
+                output += "\nNote: Detailed vote counts are not available for historical votes.\n";
+            
+                Ok(output)
+            },
+            Command::ImportHistoricalRaffle { 
+                proposal_name, 
+                initiation_block, 
+                randomness_block, 
+                team_order, 
+                excluded_teams,
+                total_counted_seats, 
+                max_earner_seats,
+                randomness_timestamp,
+                randomness,
+            } => {
+                let (raffle_id, raffle) = self.import_historical_raffle(
+                    &proposal_name,
+                    initiation_block,
+                    randomness_block,
+                    team_order.clone(),
+                    excluded_teams.clone(),
+                    total_counted_seats.or(Some(self.config.default_total_counted_seats)),
+                    max_earner_seats.or(Some(self.config.default_max_earner_seats)),
+                    randomness_timestamp,
+                    randomness,
+                ).await?;
+            
+                let mut output = format!("Imported historical raffle for proposal '{}' (Raffle ID: {})\n", proposal_name, raffle_id);
+                output += &format!("Randomness: {}\n", raffle.config().block_randomness());
+            
+                if let Some(excluded) = excluded_teams {
+                    output += &format!("Excluded teams: {:?}\n", excluded);
+                }
+            
+                for snapshot in raffle.team_snapshots() {
+                    let tickets: Vec<_> = raffle.tickets().iter()
+                        .filter(|t| t.team_id() == snapshot.id())
+                        .collect();
+                    
+                    if !tickets.is_empty() {
+                        let start = tickets.first().unwrap().index();
+                        let end = tickets.last().unwrap().index();
+                        output += &format!("Team '{}' ballot range: {} - {}\n", snapshot.name(), start, end);
+                    }
+                }
+            
+                if let Some(result) = raffle.result() {
+                    output += "Counted seats:\n";
+                    output += "Earner seats:\n";
+                    let mut earner_count = 0;
+                    for &team_id in result.counted() {
+                        if let Some(snapshot) = raffle.team_snapshots().iter().find(|s| s.id() == team_id) {
+                            if let TeamStatus::Earner { .. } = snapshot.status() {
+                                earner_count += 1;
+                                let best_score = raffle.tickets().iter()
+                                    .filter(|t| t.team_id() == team_id)
+                                    .map(|t| t.score())
+                                    .max_by(|a, b| a.partial_cmp(b).unwrap())
+                                    .unwrap_or(0.0);
+                                output += &format!("  {} (score: {})\n", snapshot.name(), best_score);
+                            }
+                        }
+                    }
+                    output += "Supporter seats:\n";
+                    for &team_id in result.counted() {
+                        if let Some(snapshot) = raffle.team_snapshots().iter().find(|s| s.id() == team_id) {
+                            if let TeamStatus::Supporter = snapshot.status() {
+                                let best_score = raffle.tickets().iter()
+                                    .filter(|t| t.team_id() == team_id)
+                                    .map(|t| t.score())
+                                    .max_by(|a, b| a.partial_cmp(b).unwrap())
+                                    .unwrap_or(0.0);
+                                output += &format!("  {} (score: {})\n", snapshot.name(), best_score);
+                            }
+                        }
+                    }
+                    output += &format!("Total counted seats: {} (Earners: {}, Supporters: {})\n", 
+                                result.counted().len(), earner_count, result.counted().len() - earner_count);
+            
+                    output += "Uncounted seats:\n";
+                    output += "Earner seats:\n";
+                    for &team_id in result.uncounted() {
+                        if let Some(snapshot) = raffle.team_snapshots().iter().find(|s| s.id() == team_id) {
+                            if let TeamStatus::Earner { .. } = snapshot.status() {
+                                let best_score = raffle.tickets().iter()
+                                    .filter(|t| t.team_id() == team_id)
+                                    .map(|t| t.score())
+                                    .max_by(|a, b| a.partial_cmp(b).unwrap())
+                                    .unwrap_or(0.0);
+                                output += &format!("  {} (score: {})\n", snapshot.name(), best_score);
+                            }
+                        }
+                    }
+                    output += "Supporter seats:\n";
+                    for &team_id in result.uncounted() {
+                        if let Some(snapshot) = raffle.team_snapshots().iter().find(|s| s.id() == team_id) {
+                            if let TeamStatus::Supporter = snapshot.status() {
+                                let best_score = raffle.tickets().iter()
+                                    .filter(|t| t.team_id() == team_id)
+                                    .map(|t| t.score())
+                                    .max_by(|a, b| a.partial_cmp(b).unwrap())
+                                    .unwrap_or(0.0);
+                                output += &format!("  {} (score: {})\n", snapshot.name(), best_score);
+                            }
+                        }
+                    }
+                } else {
+                    output += "Raffle result not available\n";
+                }
+            
+                Ok(output)
+            },
+            Command::PrintTeamReport => {
+                Ok(self.print_team_report())
+            },
+            Command::PrintEpochState => {
+                self.print_epoch_state()
+            },
+            Command::PrintTeamVoteParticipation { team_name, epoch_name } => {
+                self.print_team_vote_participation(&team_name, epoch_name.as_deref())
+            },
+            Command::CloseProposal { proposal_name, resolution } => {
+                let proposal_id = self.require_proposal_id(&proposal_name)?;
+                let resolution = match resolution.to_lowercase().as_str() {
+                    "approved" => Resolution::Approved,
+                    "rejected" => Resolution::Rejected,
+                    "invalid" => Resolution::Invalid,
+                    "duplicate" => Resolution::Duplicate,
+                    "retracted" => Resolution::Retracted,
+                    _ => return Err(format!("Invalid resolution type: {}", resolution).into()),
+                };
+                self.close_with_reason(proposal_id, &resolution)?;
+                Ok(format!("Closed proposal '{}' with resolution: {:?}", proposal_name, resolution))
+            },
+            // Command::CreateRaffle { proposal_name, block_offset, excluded_teams } => {
+            //     let mut output = Vec::new();
+            //     self.handle_create_raffle(proposal_name, block_offset, excluded_teams, &mut output).await?;
+            //     Ok(String::from_utf8(output)?)
+            // },
+            Command::CreateRaffle { proposal_name, block_offset, excluded_teams, exclude_inactive } => {
+                let progress_stream = self.create_raffle_with_progress(
+                    proposal_name,
+                    block_offset,
+                    excluded_teams,
+                    exclude_inactive.unwrap_or(true),
+                ).await;
+
+                let mut output = String::new();
+                pin_mut!(progress_stream);
+                
+                while let Some(progress) = progress_stream.next().await {
+                    match progress {
+                        Ok(progress) => {
+                            output.push_str(&format!("{}\n", progress.format_message()));
+                            if progress.is_complete() {
+                                break;
+                            }
+                        },
+                        Err(e) => return Err(Box::new(std::io::Error::new(std::io::ErrorKind::Other, e.0))),
+                    }
+                }
+                
+                Ok(output)
+            },
+            Command::EstimateOdds { team_name, excluded_teams } => {
+                let odds = self.estimate_raffle_odds(&team_name, excluded_teams)?;
+                Ok(format!("Estimated odds of a counted seat for {}: {:.1}%", team_name, odds * 100.0))
+            },
+            Command::PreviewEpochRewards { epoch_name } => {
+                let (epoch, epoch_id) = self.get_current_or_specified_epoch(epoch_name.as_deref())?;
+                let epoch_name = epoch.name().to_string();
+                let reward_token = epoch.reward().map(|reward| reward.token().to_string());
+                let closed = epoch.is_closed();
+                let preview = self.preview_epoch_rewards(epoch_id)?;
+
+                if preview.is_empty() {
+                    return Ok(format!("No team has earned points in epoch {} yet, so there is nothing to allocate", epoch_name));
+                }
+                let mut output = if closed {
+                    format!("Epoch {} is closed; these are its recorded rewards:\n", epoch_name)
+                } else {
+                    format!("Projected rewards if epoch {} closed now:\n", epoch_name)
+                };
+                if reward_token.is_none() {
+                    output.push_str("No reward is set for this epoch, so every amount is 0; shares follow the points earned so far.\n");
+                }
+                let token = reward_token.map(|token| format!(" {}", token)).unwrap_or_default();
+                for (team, percentage, amount) in preview {
+                    output.push_str(&format!("- {}: {:.2}% ({:.2}{})\n", team, percentage, amount, token));
+                }
+                Ok(output)
+            },
+            Command::PreviewRaffleEligibility { proposal_name, excluded_teams } => {
+                let eligible = self.preview_raffle_eligibility(&proposal_name, excluded_teams)?;
+                let mut output = format!("Teams eligible for a raffle on '{}': {}\n", proposal_name, eligible.len());
+                for (name, status, tickets) in &eligible {
+                    output.push_str(&format!("- {} ({}): {} tickets\n", name, team_status_label(status), tickets));
+                }
+                Ok(output)
+            },
+            Command::RedrawRaffle { proposal_name, randomness_block } => {
+                let previous_id = self.find_proposal_and_raffle(&proposal_name)?.1;
+                let raffle_id = self.redraw_raffle(&proposal_name, randomness_block).await?;
+                let raffle = self.state.get_raffle(&raffle_id).ok_or("Raffle not found")?;
+                let mut output = format!("Re-drew raffle for proposal '{}' (Raffle ID: {}, supersedes {})\n", proposal_name, raffle_id, previous_id);
+                output += &format!("Randomness block: {}\n", randomness_block);
+                self.generate_raffle_outcome(&mut output, raffle, raffle.result().ok_or("Raffle has no result")?);
+                Ok(output)
+            },
+            Command::CreateRaffleWithSeed { proposal_name, seed } => {
+                let (raffle_id, raffle) = self.create_seeded_raffle(&proposal_name, &seed)?;
+
+                let mut output = format!("Created raffle for proposal '{}' (Raffle ID: {})\n", proposal_name, raffle_id);
+                output += &format!("Randomness: {} ({})\n", seed, raffle.randomness_source());
+
+                if let Some(result) = raffle.result() {
+                    let team_names = |ids: &[Uuid]| ids.iter()
+                        .filter_map(|id| raffle.team_snapshots().iter().find(|s| s.id() == *id))
+                        .map(|s| s.name().to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    output += &format!("Counted teams: {}\n", team_names(result.counted()));
+                    output += &format!("Uncounted teams: {}\n", team_names(result.uncounted()));
+                }
+
+                Ok(output)
+            },
+            Command::CreateAndProcessVote { proposal_name, counted_votes, uncounted_votes, vote_opened, vote_closed, counted_points, uncounted_points, threshold } => {
+                let mut output = format!("Executing CreateAndProcessVote command for proposal: {}\n", proposal_name);
+                
+                match self.create_and_process_vote(
+                    &proposal_name,
+                    counted_votes,
+                    uncounted_votes,
+                    vote_opened,
+                    vote_closed,
+                    counted_points,
+                    uncounted_points,
+                    threshold,
+                ) {
+                    Ok(report) => {
+                        output += &format!("Vote processed successfully for proposal: {}\n", proposal_name);
+                        output += &format!("Vote report:\n{}\n", report);
+                    
+                        // Print point credits
+                        if let Some(vote_id) = self.state().votes().values()
+                            .find(|v| v.proposal_id() == self.get_proposal_id_by_name(&proposal_name).unwrap())
+                            .map(|v| v.id())
+                        {
+                            let vote = self.state().votes().get(&vote_id).unwrap();
+                            
+                            output += "\nPoints credited:\n";
+                            if let (
+                                VoteType::Formal { counted_points, uncounted_points, .. },
+                                VoteParticipation::Formal { counted, uncounted },
+                            ) = (vote.vote_type(), vote.participation()) {
+                                for &team_id in counted {
+                                    if let Some(team) = self.state().find_team(&team_id) {
+                                        output += &format!("  {} (+{} points)\n", team.name(), counted_points);
+                                    }
+                                }
+                                for &team_id in uncounted {
+                                    if let Some(team) = self.state().find_team(&team_id) {
+                                        output += &format!("  {} (+{} points)\n", team.name(), uncounted_points);
+                                    }
+                                }
+                            }
+                        } else {
+                            output += "Warning: Vote not found after processing\n";
+                        }
+                    },
+                    Err(e) => {
+                        output += &format!("Error: Failed to process vote for proposal '{}'. Reason: {}\n", proposal_name, e);
+                    }
+                }
+
+                Ok(output)
+            },
+            Command::CreateAndProcessMultiOptionVote { proposal_name, options, counted_votes, uncounted_votes, vote_opened, vote_closed } => {
+                let report = self.create_and_process_multi_option_vote(
+                    &proposal_name,
+                    options,
+                    counted_votes,
+                    uncounted_votes,
+                    vote_opened,
+                    vote_closed,
+                )?;
+                Ok(format!("Multi-option vote processed for proposal: {}\nVote report:\n{}\n", proposal_name, report))
+            },
+            Command::GenerateReportsForClosedProposals { epoch_name, since } => {
+                let summary = self.regenerate_closed_proposal_reports(&epoch_name, since)?;
+
+                let mut report = String::new();
+                for (title, file_path) in &summary.generated {
+                    report.push_str(&format!("Report generated for proposal '{}' at {:?}\n", title, file_path));
+                }
+                for (title, e) in &summary.failed {
+                    report.push_str(&format!("Failed to generate report for proposal '{}': {}\n", title, e));
+                }
+                report.push_str(&format!("Regenerated {} reports, skipped {}\n", summary.generated.len(), summary.skipped));
+                Ok(report)
+            },
+            Command::GenerateReportForProposal { proposal_name } => {
+                let (title, file_path) = self.generate_report_for_current_proposal(&proposal_name)?;
+                Ok(format!("Report generated for proposal '{}' at {:?}", title, file_path))
+            },
+            Command::PrintPointReport { epoch_name } => {
+                self.generate_point_report(epoch_name.as_deref())
+                    .map_err(|e| Box::new(BudgetSystemError(e.to_string())) as Box<dyn Error>)
+            },
+            Command::CloseEpoch { epoch_name, confirm_no_reward, carry_over_to } => {
+                let carry_over_to = carry_over_to
+                    .map(|name| self.require_epoch_id(&name))
+                    .transpose()?;
+                let warning = self.close_epoch_with_warnings(epoch_name.as_deref(), confirm_no_reward, carry_over_to)?;
+                let message = format!("Successfully closed epoch: {}", epoch_name.unwrap_or_else(|| "Active epoch".to_string()));
+                match warning {
+                    Some(warning) => Ok(format!("{}\n{}", warning, message)),
+                    None => Ok(message),
+                }
+            },
+            Command::GenerateEndOfEpochReport { epoch_name, format } => {
+                self.generate_end_of_epoch_report_with_format(&epoch_name, format)?;
+                Ok(format!("Generated End of Epoch Report for epoch: {}", epoch_name))
+            },
+            Command::FinalizeEpoch { epoch_name } => {
+                let archive_path = self.finalize_epoch(&epoch_name)?;
+                Ok(format!("Finalized epoch {}; reports archived at {:?}", epoch_name, archive_path))
+            },
+            Command::RunScript { .. } => {
+                Err("RunScript command should be handled by the CLI, not the BudgetSystem".into())
+            },
+            Command::Serve { .. } => {
+                Err("Serve command should be handled by the CLI, not the BudgetSystem".into())
+            },
+            Command::SandboxScript { .. } => {
+                Err("SandboxScript command should be handled by the CLI, not the BudgetSystem".into())
+            },
+            Command::MigrateState { .. } => {
+                Err("MigrateState command should be handled by the CLI, not the BudgetSystem".into())
+            },
+            Command::GenerateUnpaidRequestsReport { output_path, epoch_name } => {
+                self.generate_unpaid_requests_report(
+                    output_path.as_deref(),
+                    epoch_name.as_deref()
+                ).map(|s| format!("{}\n", s))
+            },
+            Command::ActionItems => {
+                Ok(self.action_items().format_checklist())
+            },
+            Command::VerifyRaffle { proposal_name } => {
+                self.verify_raffle(&proposal_name)
+            },
+            Command::ExportRaffleTickets { proposal_name, format } => {
+                let path = self.save_raffle_tickets(&proposal_name, format)?;
+                Ok(format!("Exported raffle tickets for {} to {:?}", proposal_name, path))
+            },
+            Command::ExportEpochRewardsCsv { epoch_name } => {
+                let path = self.save_epoch_rewards_csv(&epoch_name)?;
+                Ok(format!("Exported epoch rewards for {} to {:?}", epoch_name, path))
+            },
+            Command::CashflowForecast { epoch_name } => {
+                let epoch_id = match &epoch_name {
+                    Some(name) => self.require_epoch_id(name)?,
+                    None => self.state.current_epoch().ok_or("No active epoch")?,
+                };
+
+                let forecast = self.cashflow_forecast(epoch_id);
+                if forecast.is_empty() {
+                    return Ok("No scheduled payments for approved proposals".to_string());
+                }
+
+                let mut output = String::from("Cashflow forecast:\n");
+                for (date, amounts) in forecast {
+                    let mut tokens: Vec<_> = amounts.into_iter().collect();
+                    tokens.sort_by(|a, b| a.0.cmp(&b.0));
+                    let formatted: Vec<String> = tokens.iter()
+                        .map(|(token, amount)| format!("{:.2} {}", amount, token))
+                        .collect();
+                    output.push_str(&format!("{}: {}\n", date.format("%Y-%m-%d"), formatted.join(", ")));
+                }
+                Ok(output)
+            },
+            Command::RecomputeVoteOutcome { vote_id, commit } => {
+                let (old_passed, new_passed) = self.recompute_vote_outcome(vote_id, commit)?;
+                let outcome = |passed: bool| if passed { "passed" } else { "failed" };
+                let mut output = format!("Vote {}: was {}, recomputed as {}", vote_id, outcome(old_passed), outcome(new_passed));
+                if !commit && old_passed != new_passed {
+                    output.push_str(" (dry run, rerun with commit to apply)");
+                }
+                Ok(output)
+            },
+            Command::PromoteInformalVote { vote_id, raffle_id } => {
+                let formal_vote_id = self.promote_informal_vote(vote_id, raffle_id)?;
+                Ok(format!("Promoted informal vote {} to formal vote {}", vote_id, formal_vote_id))
+            },
+            Command::ResetVote { vote_id } => {
+                self.reset_vote(vote_id)?;
+                Ok(format!("Cleared all votes cast on vote {}", vote_id))
+            },
+            Command::DumpScript => {
+                Ok(serde_json::to_string_pretty(&self.to_script())?)
+            },
+            Command::VerifyState => {
+                FileSystem::verify_state_file(&self.config.state_file)
+            },
+            Command::ValidateState => {
+                let errors = self.validate_state();
+                if errors.is_empty() {
+                    return Ok("No integrity errors found".to_string());
+                }
+                let mut output = format!("Found {} integrity errors:\n", errors.len());
+                for error in errors {
+                    output.push_str(&format!("- {}\n", error));
+                }
+                Ok(output)
+            },
+            Command::DilutionReport { epoch_name } => {
+                let epoch_id = match &epoch_name {
+                    Some(name) => self.require_epoch_id(name)?,
+                    None => self.state.current_epoch().ok_or("No active epoch")?,
+                };
+
+                let dilution = self.dilution_report(epoch_id);
+                if dilution.is_empty() {
+                    return Ok("No pre-existing teams in this epoch".to_string());
+                }
+
+                let mut output = String::from("Reward share dilution from mid-epoch joiners:\n");
+                for (team_name, points_lost) in dilution {
+                    output.push_str(&format!("{}: -{:.2} percentage points\n", team_name, points_lost));
+                }
+                Ok(output)
+            },
+            Command::EffectiveTeamCount { epoch_name } => {
+                let epoch_id = match &epoch_name {
+                    Some(name) => self.require_epoch_id(name)?,
+                    None => self.state.current_epoch().ok_or("No active epoch")?,
+                };
+                Ok(format!("Effective number of participating teams: {:.2}", self.effective_team_count(epoch_id)))
+            },
+            Command::RedactTeam { team_name } => {
+                let team_id = self.require_team_id(&team_name)?;
+                self.redact_team(team_id)?;
+                let redacted_name = self.state.get_team(&team_id).map(|team| team.name().to_string()).unwrap_or_default();
+                Ok(format!("Redacted team: {} ({})", redacted_name, team_id))
+            },
+            Command::ArchiveTeam { team_name } => {
+                let team_id = self.require_team_id(&team_name)?;
+                self.archive_team(team_id)?;
+                Ok(format!("Archived team: {} ({})", team_name, team_id))
+            },
+            Command::MergeTeams { source_name, target_name } => {
+                self.merge_teams(&source_name, &target_name)?;
+                Ok(format!("Merged team {} into {}", source_name, target_name))
+            },
+            Command::ReopenProposal { proposal_name } => {
+                self.reopen_proposal(&proposal_name)?;
+                Ok(format!("Reopened proposal: {}", proposal_name))
+            },
+            Command::RemoveProposal { proposal_name } => {
+                self.remove_proposal(&proposal_name)?;
+                Ok(format!("Removed proposal: {}", proposal_name))
+            },
+            Command::ImportTeamsCsv { file_path } => {
+                let csv = fs::read_to_string(&file_path)?;
+                let report = self.import_teams_csv_report(&csv)?;
+                Ok(Self::format_csv_import_report("teams", &report))
+            },
+            Command::ImportProposalsCsv { file_path } => {
+                let csv = fs::read_to_string(&file_path)?;
+                let report = self.import_proposals_csv_report(&csv)?;
+                Ok(Self::format_csv_import_report("proposals", &report))
+            },
+            Command::MoveProposal { proposal_name, epoch_name } => {
+                self.move_proposal(&proposal_name, &epoch_name)?;
+                Ok(format!("Moved proposal '{}' to epoch: {}", proposal_name, epoch_name))
+            },
+            Command::CompetitionRatio { epoch_name } => {
+                let epoch_id = match &epoch_name {
+                    Some(name) => self.require_epoch_id(name)?,
+                    None => self.state.current_epoch().ok_or("No active epoch")?,
+                };
+
+                let mut raffles: Vec<&Raffle> = self.state.raffles().values()
+                    .filter(|raffle| raffle.config().epoch_id() == epoch_id)
+                    .collect();
+                if raffles.is_empty() {
+                    return Ok("No raffles in this epoch".to_string());
+                }
+                raffles.sort_by_key(|raffle| self.get_proposal(&raffle.config().proposal_id()).map(|p| p.title().to_string()));
+
+                let mut output = String::from("Seat competition per raffle:\n");
+                for raffle in raffles {
+                    let title = self.get_proposal(&raffle.config().proposal_id()).map_or("Unknown", |p| p.title());
+                    output.push_str(&format!("{}: {} teams competed for {} counted seats ({:.1}x)\n",
+                        title, raffle.eligible_team_count(), raffle.config().total_counted_seats(), raffle.competition_ratio()));
+                }
+                output.push_str(&format!("Average: {:.2}x\n", self.avg_competition_ratio(epoch_id)));
+                Ok(output)
+            },
+            Command::AddMilestonePayment { proposal_name, amount, token, date, tx } => {
+                self.add_milestone_payment(&proposal_name, amount, &token, date, tx)?;
+                let status = self.get_proposal_id_by_name(&proposal_name)
+                    .and_then(|id| self.get_proposal(&id))
+                    .and_then(|p| p.budget_request_details())
+                    .map_or("unpaid", |details| details.payment_status());
+                Ok(format!("Recorded payment of {} {} for {} ({})", amount, token, proposal_name, status))
+            },
+            Command::CompareEpochs { epoch_a, epoch_b } => {
+                self.generate_epoch_comparison(&epoch_a, &epoch_b)
+            },
+            Command::OpenVoteStatus { vote_id } => {
+                self.open_vote_status(vote_id)
+            },
+            Command::OutstandingVoters { proposal_name } => {
+                let vote_id = self.current_formal_vote_id(&proposal_name)?;
+                let (counted, uncounted) = self.outstanding_voters(vote_id)?;
+                let list = |names: Vec<String>| if names.is_empty() { "none".to_string() } else { names.join(", ") };
+                Ok(format!("Outstanding voters on '{}':\nCounted: {}\nUncounted: {}", proposal_name, list(counted), list(uncounted)))
+            },
+            Command::Search { query } => {
+                Ok(self.search(&query).format_grouped())
+            },
+            Command::SimulateThreshold { epoch_name, threshold } => {
+                self.format_threshold_simulation(epoch_name.as_deref(), threshold)
+            },
+            Command::OverdueProposals { epoch_name } => {
+                self.format_overdue_proposals(epoch_name.as_deref())
+            },
+            Command::EpochFunnel { epoch_name } => {
+                let epoch_id = match &epoch_name {
+                    Some(name) => self.require_epoch_id(name)?,
+                    None => self.state.current_epoch().ok_or("No active epoch")?,
+                };
+                Ok(self.epoch_funnel(epoch_id).format_report())
+            },
+            Command::ListProposals { epoch_name, status, team_name, announced_after, announced_before, tag } => {
+                let epoch_id = match &epoch_name {
+                    Some(name) => Some(self.require_epoch_id(name)?),
+                    None => None,
+                };
+                let proposals = self.query_proposals(ProposalFilter { epoch_id, status, team_name, announced_after, announced_before, tag });
+                if proposals.is_empty() {
+                    return Ok("No matching proposals".to_string());
+                }
+
+                let mut output = String::from("| Title | Epoch | Status | Team | Requested | Announced |\n|---|---|---|---|---|---|\n");
+                for proposal in proposals {
+                    let epoch = self.state.get_epoch(&proposal.epoch_id()).map_or("Unknown", |e| e.name());
+                    let status = match proposal.resolution() {
+                        Some(resolution) => format!("{:?}", resolution),
+                        None => format!("{:?}", proposal.status()),
+                    };
+                    let details = proposal.budget_request_details();
+                    let team = details.and_then(|d| d.team())
+                        .and_then(|id| self.state.find_team(&id))
+                        .map_or("-".to_string(), |team| team.name().to_string());
+                    let mut amounts: Vec<String> = details.map(|d| d.request_amounts().iter()
+                        .map(|(token, amount)| format!("{} {}", amount, token))
+                        .collect()).unwrap_or_default();
+                    amounts.sort();
+                    let requested = if amounts.is_empty() { "-".to_string() } else { amounts.join(", ") };
+                    let announced = proposal.announced_at().map_or("-".to_string(), |d| d.to_string());
+                    output.push_str(&format!("| {} | {} | {} | {} | {} | {} |\n", proposal.title(), epoch, status, team, requested, announced));
+                }
+                Ok(output)
+            },
+            Command::SetEpochBudgetCap { epoch_name, token, amount } => {
+                self.set_epoch_budget_cap(epoch_name.as_deref(), &token, amount)?;
+                Ok(format!("Set epoch budget cap: {} {}", amount, token))
+            },
+            Command::EpochAmendmentLog { epoch_name } => {
+                let epoch_id = match &epoch_name {
+                    Some(name) => self.require_epoch_id(name)?,
+                    None => self.state.current_epoch().ok_or("No active epoch")?,
+                };
+
+                let mut entries: Vec<(String, Amendment)> = self.epoch_amendment_log(epoch_id).into_iter()
+                    .flat_map(|(title, amendments)| amendments.into_iter().map(move |a| (title.clone(), a)))
+                    .collect();
+                if entries.is_empty() {
+                    return Ok("No proposal amendments in this epoch".to_string());
+                }
+                entries.sort_by(|a, b| a.1.timestamp.cmp(&b.1.timestamp));
+
+                let mut output = String::from("Proposal changelog:\n");
+                for (title, amendment) in entries {
+                    output.push_str(&format!("{} {}: {} changed from '{}' to '{}'\n",
+                        amendment.timestamp.format("%Y-%m-%d %H:%M:%S"), title, amendment.field, amendment.old_value, amendment.new_value));
+                }
+                Ok(output)
+            },
+            Command::TopUpReward { epoch_name, amount, token } => {
+                let top_ups = self.top_up_epoch_reward(&epoch_name, amount, &token)?;
+                let mut output = format!("Topped up reward for {} by {} {}:\n", epoch_name, amount, token);
+                for (team_name, added) in top_ups {
+                    output.push_str(&format!("{}: +{:.4} {}\n", team_name, added, token));
+                }
+                Ok(output)
+            },
+            Command::UpdateEpochReward { epoch_name, token, amount } => {
+                self.update_epoch_reward(&epoch_name, &token, amount)?;
+                Ok(format!("Set reward for {}: {} {}", epoch_name, amount, token))
+            },
+            Command::SetRewardAdjustment { epoch_name, team_name, multiplier, reason } => {
+                self.set_reward_adjustment(&epoch_name, &team_name, multiplier, reason)?;
+                Ok(format!("Set reward multiplier for {} in {}: {}", team_name, epoch_name, multiplier))
+            },
+            Command::PayTeamReward { epoch_name, team_name } => {
+                let token = self.get_epoch_id_by_name(&epoch_name)
+                    .and_then(|id| self.state.get_epoch(&id))
+                    .and_then(|epoch| epoch.reward())
+                    .map(|reward| reward.token().to_string())
+                    .unwrap_or_default();
+                let (tx_hash, amount) = self.pay_team_reward(&epoch_name, &team_name).await?;
+                Ok(format!("Sent {} {} to {} for epoch {}: {}", amount, token, team_name, epoch_name, tx_hash))
+            },
+            Command::PublishProposal { proposal_name } => {
+                self.publish_proposal(&proposal_name)?;
+                Ok(format!("Published proposal: {}", proposal_name))
+            },
+            Command::AddSponsor { proposal_name, team_name } => {
+                self.add_sponsor(&proposal_name, &team_name)?;
+                Ok(format!("{} now sponsors proposal: {}", team_name, proposal_name))
+            },
+            Command::AddAttachment { proposal_name, label, cid } => {
+                self.add_proposal_attachment(&proposal_name, label.clone(), cid)?;
+                Ok(format!("Added attachment '{}' to proposal: {}", label, proposal_name))
+            },
+            Command::RecordPayment { proposal_name, tx_hash } => {
+                self.record_payment(&proposal_name, tx_hash.clone())?;
+                Ok(format!("Recorded payment for {}: {}", proposal_name, tx_hash))
+            },
+            Command::CheckPayment { proposal_name } => {
+                let (tx_hash, status) = self.check_payment(&proposal_name).await?;
+                let status = match status {
+                    TxStatus::Pending => "pending".to_string(),
+                    TxStatus::Confirmed { block, confirmations } =>
+                        format!("confirmed in block {} ({} confirmations)", block, confirmations),
+                    TxStatus::Failed => "failed".to_string(),
+                };
+                Ok(format!("Payment for {} ({}): {}", proposal_name, tx_hash, status))
+            },
+            Command::CheckRepresentatives => {
+                let shared = self.shared_representatives();
+                if shared.is_empty() {
+                    return Ok("No representatives are shared between active teams".to_string());
+                }
+
+                let mut output = String::from("Representatives shared between active teams:\n");
+                for (representative, teams) in shared {
+                    output.push_str(&format!("{}: {}\n", representative, teams.join(", ")));
+                }
+                Ok(output)
+            },
+            Command::Undo => {
+                self.undo()?;
+                Ok("Reverted the last change".to_string())
+            },
+            Command::ProjectedCompletion => {
+                match self.projected_epoch_completion() {
+                    Some(date) => Ok(format!("Projected completion of open proposals: {}", date.format("%Y-%m-%d"))),
+                    None => Ok("Not enough resolved proposals to project completion".to_string()),
+                }
+            },
+            Command::TeamApprovalVelocity { team_name } => {
+                let team_id = self.require_team_id(&team_name)?;
+                match self.team_approval_velocity(team_id) {
+                    Some(days) => Ok(format!("{}: median {:.1} days from announcement to approval", team_name, days)),
+                    None => Ok(format!("{}: no approved proposals with announcement and resolution dates", team_name)),
+                }
+            },
+        }
+    }
+}
+
+/// Renders a table whose cells are already escaped (or deliberate markup such as links).
+fn html_table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut table = String::from("<table>\n<thead><tr>");
+    for header in headers {
+        table.push_str(&format!("<th>{}</th>", escape_html(header)));
+    }
+    table.push_str("</tr></thead>\n<tbody>\n");
+    for row in rows {
+        table.push_str("<tr>");
+        for cell in row {
+            table.push_str(&format!("<td>{}</td>", cell));
+        }
+        table.push_str("</tr>\n");
+    }
+    table.push_str("</tbody>\n</table>\n");
+    table
+}
+
+/// Adds two trailing revenue series month by month, aligned on the most recent month
+fn sum_trailing_revenue(a: &[f64], b: &[f64]) -> Vec<f64> {
+    let len = a.len().max(b.len());
+    let month = |revenue: &[f64], i: usize| (i + revenue.len()).checked_sub(len)
+        .and_then(|index| revenue.get(index))
+        .copied()
+        .unwrap_or(0.0);
+    (0..len).map(|i| month(a, i) + month(b, i)).collect()
+}
+
+fn team_status_label(status: &TeamStatus) -> &'static str {
+    match status {
+        TeamStatus::Earner { .. } => "Earner",
+        TeamStatus::Supporter => "Supporter",
+        TeamStatus::Inactive => "Inactive",
+    }
+}
+
+/// Block randomness as the node reports it: `0x` and the 32-byte mix hash in lowercase hex
+fn parse_block_randomness(randomness: &str) -> Result<String, &'static str> {
+    let digits = randomness.strip_prefix("0x").ok_or("Randomness must start with 0x")?;
+    if digits.len() != 64 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err("Randomness must be 32 bytes of hex");
+    }
+    Ok(randomness.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Utc, Duration};
+    use std::sync::Arc;
+    use tempfile::TempDir;
+    use uuid::Uuid;
+    use async_trait::async_trait;
+    use futures::pin_mut;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use crate::app_config::{StorageBackend, TelegramConfig};
+    use crate::services::ethereum::MockEthereumService;
+    use crate::core::models::RewardRounding;
+    use crate::core::models::ContactKind;
+    use tokio::time::Duration as Dur;
+
+    // Helpers
+
+    async fn create_test_budget_system(state_file: &str, initial_state: Option<BudgetSystemState>) -> BudgetSystem {
+        let config = AppConfig {
+            state_file: state_file.to_string(),
+            ipc_path: "/tmp/test_reth.ipc".to_string(),
+            future_block_offset: 10,
+            script_file: "test_script.json".to_string(),
+            default_total_counted_seats: 7,
+            default_max_earner_seats: 5,
+            default_qualified_majority_threshold: 0.7,
+            default_quorum: 0.0,
+            counted_vote_points: 5,
+            uncounted_vote_points: 2,
+            strict_state_checksum: false,
+            unique_representatives: false,
+            undo_history_size: 10,
+            storage_backend: StorageBackend::Json,
+            ipfs_gateway: String::new(),
+            raffle_tie_break: crate::core::models::TieBreak::LowestTeamId,
+            raffle_block_timeout_secs: 300,
+            min_sponsors: 0,
+            default_decision_sla_days: 14,
+            revenue_tiers: Vec::new(),
+            audit_log: String::new(),
+            payout_signer: String::new(),
+            reward_rounding: crate::core::models::RewardRounding::None,
+            redistribute_withheld_rewards: true,
+            state_encryption_key: String::new(),
+            token_rates: HashMap::new(),
+            telegram: TelegramConfig {
+                chat_id: "test_chat_id".to_string(),
+                token: "test_token".to_string(),
+            },
+        };
+        let ethereum_service = Arc::new(MockEthereumService::new());
+        BudgetSystem::new(config, ethereum_service, initial_state).await.unwrap()
+    }
+
+    async fn create_active_epoch(budget_system: &mut BudgetSystem) -> Uuid {
+        let start_date = Utc::now();
+        let end_date = start_date + Duration::days(30);
+        let epoch_id = budget_system.create_epoch("Test Epoch", start_date, end_date).unwrap();
+        budget_system.activate_epoch(epoch_id).unwrap();
+        epoch_id
+    }
+
+    async fn create_proposal_with_raffle(budget_system: &mut BudgetSystem, proposal_name: &str) -> (Uuid, Uuid) {
+        let proposal_id = budget_system.add_proposal(
+            proposal_name.to_string(),
+            None,
+            None,
+            Some(Utc::now().date_naive()),
+            Some(Utc::now().date_naive()),
+            None
+        ).unwrap();
+    
+        let config = budget_system.config().clone();
+        let (raffle_id, _) = budget_system.prepare_raffle(proposal_name, None, &config).unwrap();
+        budget_system.finalize_raffle(
+            raffle_id,
+            12345,
+            12355,
+            "mock_randomness".to_string()
+        ).await.unwrap();
+    
+        (proposal_id, raffle_id)
+    }
+
+    fn get_mock_service(budget_system: &BudgetSystem) -> Option<Arc<MockEthereumService>> {
+        budget_system.ethereum_service()
+            .clone() // Clone the Arc before downcasting
+            .downcast_arc::<MockEthereumService>()
+            .ok()
+    }
+
+    async fn setup_block_progression(mock_service: Arc<MockEthereumService>) {
+        let service = mock_service.clone();
+        tokio::spawn(async move {
+            for _ in 0..5 {
+                service.increment_block();
+                tokio::time::sleep(Dur::from_millis(100)).await;
+            }
+        });
+    }
+    
+    // Tests
+
+    #[tokio::test]
+    async fn test_state_management() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+
+        // Test creating a new BudgetSystem
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+        
+        // Modify state
+        let epoch_id = budget_system.create_epoch("Test Epoch", Utc::now(), Utc::now() + Duration::days(30)).unwrap();
+        let team_id = budget_system.create_team("Test Team".to_string(), "Representative".to_string(), Some(vec![1000.0, 2000.0, 3000.0]), None).unwrap();
+
+        // Save state
+        budget_system.save_state().unwrap();
+
+        // Test loading existing state
+        let loaded_state = FileSystem::try_load_state(&state_file).unwrap();
+        let loaded_system = create_test_budget_system(&state_file, Some(loaded_state)).await;
+
+        // Verify loaded state
+        assert_eq!(loaded_system.state().epochs().len(), 1);
+        assert!(loaded_system.state().epochs().contains_key(&epoch_id));
+        assert_eq!(loaded_system.state().current_state().teams().len(), 1);
+        assert!(loaded_system.state().current_state().teams().contains_key(&team_id));
+
+        // Test loading from non-existent file (should create new system)
+        let non_existent_file = temp_dir.path().join("non_existent.json").to_str().unwrap().to_string();
+        let new_system = create_test_budget_system(&non_existent_file, None).await;
+        assert!(new_system.state().epochs().is_empty());
+        assert!(new_system.state().current_state().teams().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_epoch_management() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        // Test creating a new epoch
+        let start_date = Utc::now();
+        let end_date = start_date + Duration::days(30);
+        let epoch_id = budget_system.create_epoch("Test Epoch", start_date, end_date).unwrap();
+        let epoch = budget_system.get_epoch(&epoch_id).unwrap();
+        assert_eq!(epoch.name(), "Test Epoch");
+        assert_eq!(epoch.start_date(), start_date);
+        assert_eq!(epoch.end_date(), end_date);
+
+        // Test activating an epoch
+        budget_system.activate_epoch(epoch_id).unwrap();
+        assert_eq!(budget_system.state().current_epoch(), Some(epoch_id));
+
+        // Test setting epoch reward
+        budget_system.set_epoch_reward("ETH", 100.0).unwrap();
+        let updated_epoch = budget_system.get_epoch(&epoch_id).unwrap();
+        assert_eq!(updated_epoch.reward().unwrap().token(), "ETH");
+        assert_eq!(updated_epoch.reward().unwrap().amount(), 100.0);
+
+        // Test creating overlapping epoch (should fail)
+        let overlapping_start = start_date + Duration::days(15);
+        let overlapping_end = end_date + Duration::days(15);
+        assert!(budget_system.create_epoch("Overlapping Epoch", overlapping_start, overlapping_end).is_err());
+
+        // Test activating an epoch when another is already active (should fail)
+        let another_epoch_id = budget_system.create_epoch("Another Epoch", end_date + Duration::days(1), end_date + Duration::days(31)).unwrap();
+        assert!(budget_system.activate_epoch(another_epoch_id).is_err());
+
+        // Ensure points are earned before closing an epoch
+        let team_id = budget_system.create_team("Test Team".to_string(), "Rep".to_string(), Some(vec![1000.0]), None).unwrap();
+        let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, "Test Proposal").await;
+        let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
+        budget_system.cast_votes(vote_id, vec![(team_id, VoteChoice::Yes)]).unwrap();
+        budget_system.close_vote(vote_id).unwrap();
+
+        // Close the proposal before closing the epoch
+        budget_system.close_with_reason(proposal_id, &Resolution::Approved).unwrap();
+
+        budget_system.close_epoch(Some("Test Epoch"), None).unwrap();
+        let closed_epoch = budget_system.get_epoch(&epoch_id).unwrap();
+        assert!(closed_epoch.is_closed());
+        assert_eq!(budget_system.state().current_epoch(), None);
+    }
+
+    #[tokio::test]
+    async fn test_team_management() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        // Test creating a new team
+        let team_id = budget_system.create_team(
+            "Test Team".to_string(),
+            "Representative".to_string(),
+            Some(vec![1000.0, 2000.0, 3000.0]),
+            None
+        ).unwrap();
+        let team = budget_system.get_team(&team_id).unwrap();
+        assert_eq!(team.name(), "Test Team");
+        assert_eq!(team.representative(), "Representative");
+        assert!(matches!(team.status(), TeamStatus::Earner { .. }));
+
+        // Test getting team by name
+        let team_id_by_name = budget_system.get_team_id_by_name("Test Team").unwrap();
+        assert_eq!(team_id_by_name, team_id);
+
+        // Test removing a team
+        budget_system.remove_team(team_id).unwrap();
+        assert!(budget_system.get_team(&team_id).is_none());
+
+        // Test creating a team with invalid data (should fail)
+        assert!(budget_system.create_team("".to_string(), "Representative".to_string(), None, None).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_update_team() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        let team_id = budget_system.create_team("Test Team".to_string(), "John Doe".to_string(), Some(vec![1000.0]), None).unwrap();
+
+        let updates = UpdateTeamDetails {
+            name: Some("Updated Team".to_string()),
+            representative: Some("Jane Doe".to_string()),
+            status: Some("Supporter".to_string()),
+            trailing_monthly_revenue: None,
+            address: None,
+            contacts: None,
+        };
+
+        budget_system.update_team(team_id, updates).unwrap();
+
+        let updated_team = budget_system.get_team(&team_id).unwrap();
+        assert_eq!(updated_team.name(), "Updated Team");
+        assert_eq!(updated_team.representative(), "Jane Doe");
+        assert!(matches!(updated_team.status(), TeamStatus::Supporter));
+    }
+
+    #[tokio::test]
+    async fn test_update_team_earner_status() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        let team_id = budget_system.create_team("Test Team".to_string(), "John Doe".to_string(), Some(vec![1000.0]), None).unwrap();
+
+        let updates = UpdateTeamDetails {
+            name: None,
+            representative: None,
+            status: Some("Earner".to_string()),
+            trailing_monthly_revenue: Some(vec![2000.0, 3000.0, 4000.0]),
+            address: None,
+            contacts: None,
+        };
+
+        budget_system.update_team(team_id, updates).unwrap();
+
+        let updated_team = budget_system.get_team(&team_id).unwrap();
+        if let TeamStatus::Earner { trailing_monthly_revenue } = updated_team.status() {
+            assert_eq!(trailing_monthly_revenue, &[2000.0, 3000.0, 4000.0]);
+        } else {
+            panic!("Expected Earner status");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_update_team_invalid_status() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        let team_id = budget_system.create_team("Test Team".to_string(), "John Doe".to_string(), Some(vec![1000.0]), None).unwrap();
+
+        let updates = UpdateTeamDetails {
+            name: None,
+            representative: None,
+            status: Some("InvalidStatus".to_string()),
+            trailing_monthly_revenue: None,
+            address: None,
+            contacts: None,
+        };
+
+        assert!(budget_system.update_team(team_id, updates).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_proposal_management() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        // Create an active epoch
+        let epoch_id = create_active_epoch(&mut budget_system).await;
+
+        // Test adding a new proposal
+        let proposal_id = budget_system.add_proposal(
+            "Test Proposal".to_string(),
+            Some("http://example.com".to_string()),
+            Some(BudgetRequestDetails::new(
+                None,
+                [("ETH".to_string(), 100.0)].iter().cloned().collect(),
+                Some(Utc::now().date_naive()),
+                Some((Utc::now() + Duration::days(30)).date_naive()),
+                Some(false),
+                None
+            ).unwrap()),
+            Some(Utc::now().date_naive()),
+            Some(Utc::now().date_naive()),
+            None
+        ).unwrap();
+
+        let proposal = budget_system.get_proposal(&proposal_id).unwrap();
+        assert_eq!(proposal.title(), "Test Proposal");
+
+        // Test updating a proposal
+        let updates = UpdateProposalDetails {
+            title: Some("Updated Proposal".to_string()),
+            url: None,
+            budget_request_details: None,
+            announced_at: None,
+            published_at: None,
+            resolved_at: None,
+            tags: None,
+            decision_deadline: None,
+            depends_on: None,
+        };
+        budget_system.update_proposal("Test Proposal", updates).unwrap();
+        let updated_proposal = budget_system.get_proposal(&proposal_id).unwrap();
+        assert_eq!(updated_proposal.title(), "Updated Proposal");
+
+        // Test closing a proposal
+        budget_system.close_with_reason(proposal_id, &Resolution::Approved).unwrap();
+        let closed_proposal = budget_system.get_proposal(&proposal_id).unwrap();
+        assert!(closed_proposal.is_closed());
+        assert_eq!(closed_proposal.resolution(), Some(Resolution::Approved));
+
+        // Test getting proposals for an epoch
+        let epoch_proposals = budget_system.get_proposals_for_epoch(epoch_id);
+        assert_eq!(epoch_proposals.len(), 1);
+        assert_eq!(epoch_proposals[0].id(), proposal_id);
+
+        // Test adding a proposal without an active epoch (should fail)
+        budget_system.close_epoch(None, None).unwrap();
+        assert!(budget_system.add_proposal(
+            "Failed Proposal".to_string(),
+            None,
+            None,
+            None,
+            None,
+            None
+        ).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_raffle_management() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        // Create an active epoch and a proposal
+        let epoch_id = create_active_epoch(&mut budget_system).await;
+        let proposal_id = budget_system.add_proposal(
+            "Test Proposal".to_string(),
+            None,
+            None,
+            None,
+            None,
+            None
+        ).unwrap();
+
+        // Create some teams
+        let team_id1 = budget_system.create_team("Team 1".to_string(), "Rep 1".to_string(), Some(vec![1000.0]), None).unwrap();
+        let team_id2 = budget_system.create_team("Team 2".to_string(), "Rep 2".to_string(), None, None).unwrap();
+
+        // Test preparing a raffle
+        let config = budget_system.config().clone();
+        let (raffle_id, tickets) = budget_system.prepare_raffle(
+            "Test Proposal",
+            None,
+            &config
+        ).unwrap();
+        assert!(!tickets.is_empty());
+
+        // Test finalizing a raffle
+        let raffle = budget_system.finalize_raffle(
+            raffle_id,
+            12345,
+            12355,
+            "mock_randomness".to_string()
+        ).await.unwrap();
+        assert!(raffle.result().is_some());
+
+        // Test importing a predefined raffle
+        let imported_raffle_id = budget_system.import_predefined_raffle(
+            "Test Proposal",
+            vec!["Team 1".to_string()],
+            vec!["Team 2".to_string()],
+            1,
+            1
+        ).unwrap();
+        let imported_raffle = budget_system.get_raffle(&imported_raffle_id).unwrap();
+        assert_eq!(imported_raffle.result().unwrap().counted(), &[team_id1]);
+        assert_eq!(imported_raffle.result().unwrap().uncounted(), &[team_id2]);
+
+        // Test importing a historical raffle
+        let (historical_raffle_id, historical_raffle) = budget_system.import_historical_raffle(
+            "Test Proposal",
+            12345,
+            12355,
+            Some(vec!["Team 1".to_string(), "Team 2".to_string()]),
+            None,
+            Some(2),
+            Some(1),
+            None,
+            None,
+        ).await.unwrap();
+        assert_eq!(historical_raffle.config().initiation_block(), 12345);
+        assert_eq!(
+            historical_raffle.config().randomness_timestamp(),
+            Some(budget_system.get_block_timestamp(12355).await.unwrap())
+        );
+        assert_eq!(historical_raffle.config().randomness_block(), 12355);
+        assert!(historical_raffle.result().is_some());
+
+        // Test raffle exclusions
+        let excluded_raffle_id = budget_system.import_predefined_raffle(
+            "Test Proposal",
+            vec!["Team 1".to_string()],
+            vec![],
+            1,
+            1
+        ).unwrap();
+        let excluded_raffle = budget_system.get_raffle(&excluded_raffle_id).unwrap();
+        assert_eq!(excluded_raffle.result().unwrap().counted(), &[team_id1]);
+        assert!(excluded_raffle.result().unwrap().uncounted().is_empty());
+
+        // Test invalid raffle creation (non-existent proposal)
+        assert!(budget_system.prepare_raffle(
+            "Non-existent Proposal",
+            None,
+            &config
+        ).is_err());
+
+        // Test invalid raffle finalization (non-existent raffle)
+        assert!(budget_system.finalize_raffle(
+            Uuid::new_v4(),
+            12345,
+            12355,
+            "mock_randomness".to_string()
+        ).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_import_historical_raffle_with_supplied_randomness() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        create_active_epoch(&mut budget_system).await;
+        budget_system.add_proposal("Backfilled".to_string(), None, None, None, None, None).unwrap();
+        budget_system.create_team("Team 1".to_string(), "Rep 1".to_string(), None, None).unwrap();
+        budget_system.create_team("Team 2".to_string(), "Rep 2".to_string(), None, None).unwrap();
+
+        let non_hex = format!("0x{}", "g".repeat(64));
+        for invalid in ["abcd", "0x1234", non_hex.as_str()] {
+            assert!(budget_system.import_historical_raffle(
+                "Backfilled", 100, 110, None, None, None, None, None, Some(invalid.to_string()),
+            ).await.is_err());
+        }
+        assert!(budget_system.state.raffles().is_empty());
+
+        let randomness = format!("0x{}", "AB".repeat(32));
+        let (_, raffle) = budget_system.import_historical_raffle(
+            "Backfilled", 100, 110, None, None, None, None, None, Some(randomness.clone()),
+        ).await.unwrap();
+        // The node is not asked, so the randomness is used as given and no timestamp is looked up
+        assert_eq!(raffle.config().block_randomness(), randomness.to_lowercase());
+        assert_eq!(raffle.config().randomness_timestamp(), None);
+        assert!(raffle.result().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_vote_management() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        create_active_epoch(&mut budget_system).await;
+        let proposal_id = budget_system.add_proposal("Test Proposal".to_string(), None, None, None, None, None).unwrap();
+
+        // Create teams
+        let team_id1 = budget_system.create_team("Team 1".to_string(), "Rep 1".to_string(), Some(vec![1000.0]), None).unwrap();
+        let team_id2 = budget_system.create_team("Team 2".to_string(), "Rep 2".to_string(), Some(vec![2000.0]), None).unwrap();
+
+        // Prepare and finalize raffle
+        let config = budget_system.config().clone();
+        let (raffle_id, _) = budget_system.prepare_raffle("Test Proposal", None, &config).unwrap();
+        let mock_randomness = "mock_randomness".to_string();
+        budget_system.finalize_raffle(raffle_id, 12345, 12355, mock_randomness).await.unwrap();
+
+        // Create and process a formal vote
+        let formal_vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
+        budget_system.cast_votes(formal_vote_id, vec![(team_id1, VoteChoice::Yes), (team_id2, VoteChoice::No)]).unwrap();
+
+        // Test closing a vote
+        let vote_result = budget_system.close_vote(formal_vote_id).unwrap();
+        let closed_vote = budget_system.get_vote(&formal_vote_id).unwrap();
+        assert!(closed_vote.is_closed());
+        assert!(matches!(closed_vote.result(), Some(VoteResult::Formal { .. })));
+
+        // Verify vote result
+        if let Some(VoteResult::Formal { counted, uncounted, passed, .. }) = closed_vote.result() {
+            assert_eq!(counted.yes() + counted.no(), 2);
+            assert_eq!(uncounted.yes() + uncounted.no(), 0);
+            assert_eq!(*passed, vote_result);
+        } else {
+            panic!("Expected Formal vote result");
+        }
+
+        // Test error case: closing an already closed vote
+        assert!(budget_system.close_vote(formal_vote_id).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_reporting() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+    
+        let epoch_id = create_active_epoch(&mut budget_system).await;
+        let team_id = budget_system.create_team("Test Team".to_string(), "Rep".to_string(), Some(vec![1000.0]), None).unwrap();
+        
+        // Create proposal and raffle
+        let proposal_id = budget_system.add_proposal("Test Proposal".to_string(), None, None, None, None, None).unwrap();
+        let config = budget_system.config().clone();
+        let (raffle_id, _) = budget_system.prepare_raffle("Test Proposal", None, &config).unwrap();
+        
+        // Finalize raffle with the team included
+        let mock_randomness = "mock_randomness".to_string();
+        budget_system.finalize_raffle(raffle_id, 12345, 12355, mock_randomness).await.unwrap();
+    
+        // Create and process a vote
+        let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
+        budget_system.cast_votes(vote_id, vec![(team_id, VoteChoice::Yes)]).unwrap();
+        budget_system.close_vote(vote_id).unwrap();
+    
+        // Generate reports
+        let team_report = budget_system.print_team_report();
+        assert!(team_report.contains("Test Team"));
+    
+        let epoch_state = budget_system.print_epoch_state().unwrap();
+        assert!(epoch_state.contains("Test Proposal"));
+    
+        let proposal_report = budget_system.generate_proposal_report(proposal_id).unwrap();
+        assert!(proposal_report.contains("Test Proposal"));
+    
+        let point_report = budget_system.generate_point_report(None).unwrap();
+        assert!(point_report.contains("Test Team"));
+    
+        // Close proposal before closing epoch
+        budget_system.close_with_reason(proposal_id, &Resolution::Approved).unwrap();
+    
+        budget_system.close_epoch(None, None).unwrap();
+        budget_system.generate_end_of_epoch_report(&budget_system.get_epoch(&epoch_id).unwrap().name()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_integration() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        // Create and activate an epoch
+        let epoch_id = create_active_epoch(&mut budget_system).await;
+        budget_system.set_epoch_reward("ETH", 1000.0).unwrap();
+
+        // Create teams
+        let team_id1 = budget_system.create_team("Team 1".to_string(), "Rep 1".to_string(), Some(vec![1000.0]), None).unwrap();
+        let team_id2 = budget_system.create_team("Team 2".to_string(), "Rep 2".to_string(), Some(vec![2000.0]), None).unwrap();
+        let team_id3 = budget_system.create_team("Team 3".to_string(), "Rep 3".to_string(), None, None).unwrap();
+
+        // Create a proposal
+        let proposal_id = budget_system.add_proposal(
+            "Test Proposal".to_string(),
+            Some("http://example.com".to_string()),
+            Some(BudgetRequestDetails::new(
+                Some(team_id1),
+                [("ETH".to_string(), 100.0)].iter().cloned().collect(),
+                Some(Utc::now().date_naive()),
+                Some((Utc::now() + Duration::days(30)).date_naive()),
+                Some(false),
+                None,
+            ).unwrap()),
+            Some(Utc::now().date_naive()),
+            Some(Utc::now().date_naive()),
+            None
+        ).unwrap();
+
+        // Conduct a raffle
+        let config = budget_system.config().clone();
+        let (raffle_id, _) = budget_system.prepare_raffle("Test Proposal", None, &config).unwrap();
+        budget_system.finalize_raffle(raffle_id, 12345, 12355, "mock_randomness".to_string()).await.unwrap();
+        
+        // Generate epoch report
+        let epoch_state = budget_system.print_epoch_state().unwrap();
+        assert!(epoch_state.contains("Test Proposal"));
+
+        // Create and process a vote
+        let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
+        budget_system.cast_votes(vote_id, vec![
+            (team_id1, VoteChoice::Yes),
+            (team_id2, VoteChoice::Yes),
+            (team_id3, VoteChoice::No)
+        ]).unwrap();
+        let vote_result = budget_system.close_vote(vote_id).unwrap();
+        
+        // Verify the actual vote result
+        let vote = budget_system.get_vote(&vote_id).unwrap();
+        if let Some(VoteResult::Formal { passed, .. }) = vote.result() {
+            assert_eq!(*passed, vote_result);
+        } else {
+            panic!("Expected Formal vote result");
+        }
+
+        // Close the proposal
+        budget_system.close_with_reason(proposal_id, &Resolution::Approved).unwrap();
+        
+
+        // Close the epoch
+        budget_system.close_epoch(None, None).unwrap();
+
+        // Generate other report
+        let team_report = budget_system.print_team_report();
+        let proposal_report = budget_system.generate_proposal_report(proposal_id).unwrap();
+        let point_report = budget_system.generate_point_report(Some("Test Epoch")).unwrap();
+        budget_system.generate_end_of_epoch_report(&budget_system.get_epoch(&epoch_id).unwrap().name()).unwrap();
+
+        // Verify the integrations
+        assert!(team_report.contains("Team 1") && team_report.contains("Team 2") && team_report.contains("Team 3"));
+        assert!(proposal_report.contains("Approved"));
+        assert!(point_report.contains("Team 1") && point_report.contains("Team 2") && point_report.contains("Team 3"));
+
+        // Verify the final state
+        let closed_epoch = budget_system.get_epoch(&epoch_id).unwrap();
+        assert!(closed_epoch.is_closed());
+        let closed_proposal = budget_system.get_proposal(&proposal_id).unwrap();
+        assert!(closed_proposal.is_closed());
+        assert_eq!(closed_proposal.resolution(), Some(Resolution::Approved));
+    }
+
+    #[tokio::test]
+    async fn test_error_handling_and_edge_cases() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        // Test handling of non-existent entities
+        assert!(budget_system.get_team(&Uuid::new_v4()).is_none());
+        assert!(budget_system.get_proposal(&Uuid::new_v4()).is_none());
+        assert!(budget_system.get_epoch(&Uuid::new_v4()).is_none());
+        assert!(budget_system.get_raffle(&Uuid::new_v4()).is_none());
+        assert!(budget_system.get_vote(&Uuid::new_v4()).is_none());
+
+        // Test behavior with empty state
+        assert!(budget_system.print_epoch_state().is_err());
+        assert!(budget_system.generate_point_report(None).is_err());
+
+        // Test invalid inputs
+        assert!(budget_system.create_epoch("", Utc::now(), Utc::now()).is_err());
+        assert!(budget_system.create_team("".to_string(), "Rep".to_string(), None, None).is_err());
+        assert!(budget_system.set_epoch_reward("ETH", -100.0).is_err());
+
+        // Test overlapping epochs
+        let epoch1_id = budget_system.create_epoch("Epoch 1", Utc::now(), Utc::now() + Duration::days(30)).unwrap();
+        assert!(budget_system.create_epoch("Epoch 2", Utc::now() + Duration::days(15), Utc::now() + Duration::days(45)).is_err());
+
+        // Test activating multiple epochs
+        budget_system.activate_epoch(epoch1_id).unwrap();
+        let epoch2_id = budget_system.create_epoch("Epoch 2", Utc::now() + Duration::days(31), Utc::now() + Duration::days(61)).unwrap();
+        assert!(budget_system.activate_epoch(epoch2_id).is_err());
+
+        // Test closing an epoch with open proposals
+        let proposal_id = budget_system.add_proposal("Test Proposal".to_string(), None, None, None, None, None).unwrap();
+        assert!(budget_system.close_epoch(None, None).is_err());
+
+        // Test updating a non-existent proposal
+        let updates = UpdateProposalDetails {
+            title: Some("Updated Title".to_string()),
+            url: None,
+            budget_request_details: None,
+            announced_at: None,
+            published_at: None,
+            resolved_at: None,
+            tags: None,
+            decision_deadline: None,
+            depends_on: None,
+        };
+        assert!(budget_system.update_proposal("Non-existent Proposal", updates).is_err());
+
+        // Test creating a raffle for a non-existent proposal
+        let config = budget_system.config().clone();
+        assert!(budget_system.prepare_raffle("Non-existent Proposal", None, &config).is_err());
+
+        // Test casting votes for a non-existent vote
+        assert!(budget_system.cast_votes(Uuid::new_v4(), vec![(Uuid::new_v4(), VoteChoice::Yes)]).is_err());
+
+        // Test closing a non-existent vote
+        assert!(budget_system.close_vote(Uuid::new_v4()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_ethereum_service_interaction() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        // Test successful interactions
+        assert_eq!(budget_system.get_current_block().await.unwrap(), 12345);
+        assert_eq!(budget_system.get_randomness(12355).await.unwrap(), "mock_randomness_for_block_12355");
+        
+        let (init_block, rand_block, randomness) = budget_system.get_raffle_randomness().await.unwrap();
+        assert_eq!(init_block, 12345);
+        assert_eq!(rand_block, 12355);
+        assert_eq!(randomness, "mock_randomness_for_block_12355");
+
+        // Test raffle creation with Ethereum service interaction
+        create_active_epoch(&mut budget_system).await;
+        budget_system.add_proposal("Test Proposal".to_string(), None, None, None, None, None).unwrap();
+        
+        let config = budget_system.config().clone();
+        let (raffle_id, _) = budget_system.prepare_raffle("Test Proposal", None, &config).unwrap();
+        
+        let raffle = budget_system.finalize_raffle(raffle_id, 12345, 12355, "mock_randomness".to_string()).await.unwrap();
+        
+        assert_eq!(raffle.config().initiation_block(), 12345);
+        assert_eq!(raffle.config().randomness_block(), 12355);
+        assert_eq!(raffle.config().block_randomness(), "mock_randomness");
+    }
+
+    #[tokio::test]
+    async fn test_raffle_creation_stream() {
+        use futures::pin_mut;
+        use std::sync::atomic::{AtomicU64, Ordering};
+        use std::time::Duration;
+        use std::sync::Arc;
+
+        // Create mock service
+        let mock_service = Arc::new(MockEthereumService::new());
+        let mock_service_clone = Arc::clone(&mock_service);
+        
+
+        let temp_dir = TempDir::new().unwrap();
+        
+        // Create budget system with our mock service
+        let mut budget_system = {
+            let config = AppConfig {
+                state_file: temp_dir.path().join("test_state.json").to_str().unwrap().to_string(),
+                ipc_path: "/tmp/test_reth.ipc".to_string(),
+                future_block_offset: 2, // Small offset for testing
+                script_file: "test_script.json".to_string(),
+                default_total_counted_seats: 7,
+                default_max_earner_seats: 5,
+                default_qualified_majority_threshold: 0.7,
+                default_quorum: 0.0,
+                counted_vote_points: 5,
+                uncounted_vote_points: 2,
+                strict_state_checksum: false,
+                unique_representatives: false,
+                undo_history_size: 10,
+                storage_backend: StorageBackend::Json,
+                ipfs_gateway: String::new(),
+                raffle_tie_break: crate::core::models::TieBreak::LowestTeamId,
+                raffle_block_timeout_secs: 300,
+                min_sponsors: 0,
+                default_decision_sla_days: 14,
+                revenue_tiers: Vec::new(),
+                audit_log: String::new(),
+                payout_signer: String::new(),
+                reward_rounding: crate::core::models::RewardRounding::None,
+                redistribute_withheld_rewards: true,
+                state_encryption_key: String::new(),
+                token_rates: HashMap::new(),
+                telegram: TelegramConfig {
+                    chat_id: "test_chat_id".to_string(),
+                    token: "test_token".to_string(),
+                },
+            };
+            BudgetSystem::new(config, mock_service, None).await.unwrap()
+        };
+        
+        // Setup block progression before executing command
+        if let Some(mock_service) = get_mock_service(&budget_system) {
+            setup_block_progression(mock_service).await;
+        }
+
+        // Setup test data
+        create_active_epoch(&mut budget_system).await;
+        
+        // Add test teams
+        budget_system.create_team("Team 1".to_string(), "Rep 1".to_string(), Some(vec![1000.0]), None).unwrap();
+        budget_system.create_team("Team 2".to_string(), "Rep 2".to_string(), Some(vec![2000.0]), None).unwrap();
+        
+        budget_system.add_proposal(
+            "Test Proposal".to_string(),
+            None,
+            None,
+            Some(Utc::now().date_naive()),
+            Some(Utc::now().date_naive()),
+            None
+        ).unwrap();
+
+        // Create and pin the stream
+        let progress_stream = budget_system.create_raffle_with_progress(
+            "Test Proposal".to_string(),
+            Some(2), // Small offset for testing
+            None,
+            true,
+        ).await;
+        pin_mut!(progress_stream);
+
+        // Collect updates with longer timeout
+        let mut updates = Vec::new();
+        while let Some(progress) = tokio::time::timeout(
+            Duration::from_secs(10), // Increased timeout
+            progress_stream.next()
+        ).await.unwrap() {
+            let progress = progress.unwrap();
+            println!("Received progress update: {:?}", progress);
+            updates.push(progress);
+            
+            if matches!(updates.last().unwrap(), RaffleProgress::Completed { .. }) {
+                break;
+            }
+        }
+
+        // Verify states
+        assert!(!updates.is_empty(), "Should have received updates");
+        assert!(matches!(updates[0], RaffleProgress::Preparing { .. }), "First update should be Preparing");
+        
+        let has_waiting = updates.iter().any(|p| matches!(p, RaffleProgress::WaitingForBlock { .. }));
+        assert!(has_waiting, "Should have WaitingForBlock state");
+        
+        let has_randomness = updates.iter().any(|p| matches!(p, RaffleProgress::RandomnessAcquired { .. }));
+        assert!(has_randomness, "Should have RandomnessAcquired state");
+        
+        assert!(matches!(updates.last().unwrap(), RaffleProgress::Completed { .. }), "Should end with Completed state");
+
+        if let RaffleProgress::Completed { counted, uncounted, .. } = updates.last().unwrap() {
+            assert!(!counted.is_empty() || !uncounted.is_empty(), "Raffle should contain teams");
+            println!("Final raffle result - Counted teams: {:?}, Uncounted teams: {:?}", counted, uncounted);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_raffle_with_progress() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        // Setup required state
+        create_active_epoch(&mut budget_system).await;
+        budget_system.add_proposal(
+            "Test Proposal".to_string(),
+            None,
+            None,
+            Some(Utc::now().date_naive()),
+            Some(Utc::now().date_naive()),
+            None
+        ).unwrap();
+
+        // Add some teams
+        budget_system.create_team("Team1".to_string(), "Rep1".to_string(), Some(vec![1000.0]), None).unwrap();
+        budget_system.create_team("Team2".to_string(), "Rep2".to_string(), Some(vec![2000.0]), None).unwrap();
+
+        // Setup block progression before executing command
+        if let Some(mock_service) = get_mock_service(&budget_system) {
+            setup_block_progression(mock_service).await;
+        }
+
+        // Create the progress stream and collect updates in their own scope
+        let updates = {
+            let progress_stream = budget_system.create_raffle_with_progress(
+                "Test Proposal".to_string(),
+                Some(1), // Small offset for testing
+                None,
+                true,
+            ).await;
+
+            let mut updates = Vec::new();
+            pin_mut!(progress_stream);
+            
+            while let Some(progress) = progress_stream.next().await {
+                match progress {
+                    Ok(update) => {
+                        updates.push(update.clone());
+                        if matches!(update, RaffleProgress::Completed { .. }) {
+                            break;
+                        }
+                    },
+                    Err(e) => panic!("Unexpected error: {}", e),
+                }
+            }
+            updates
+        }; // progress_stream is dropped here, releasing the mutable borrow
+
+        // Now we can borrow budget_system again
+        
+        // Verify progress sequence
+        assert!(matches!(updates[0], RaffleProgress::Preparing { .. }));
+        assert!(matches!(updates[1], RaffleProgress::WaitingForBlock { .. }));
+        assert!(matches!(updates[2], RaffleProgress::RandomnessAcquired { .. }));
+        assert!(matches!(updates[3], RaffleProgress::Completed { .. }));
+
+        // Verify final state
+        if let RaffleProgress::Completed { ref counted, ref uncounted, .. } = updates[3] {
+            assert_eq!(counted.len() + uncounted.len(), 2); // All teams should be assigned
+        } else {
+            panic!("Final update should be Completed");
+        }
+
+        // Verify raffle was created in system
+        assert_eq!(budget_system.state().raffles().len(), 1);
+    }
+
+    // Test error cases
+    #[tokio::test]
+    async fn test_create_raffle_with_progress_invalid_proposal() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        // Setup block progression before executing command
+        if let Some(mock_service) = get_mock_service(&budget_system) {
+            setup_block_progression(mock_service).await;
+        }
+
+        let progress_stream = budget_system.create_raffle_with_progress(
+            "NonExistent".to_string(),
+            None,
+            None,
+            true,
+        ).await;
+
+        pin_mut!(progress_stream);
+        
+        // Should fail on first update
+        let first_update = progress_stream.next().await.unwrap();
+        assert!(first_update.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_create_raffle_with_progress_times_out() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+        budget_system.config.raffle_block_timeout_secs = 0;
+
+        create_active_epoch(&mut budget_system).await;
+        budget_system.create_team("Team1".to_string(), "Rep1".to_string(), Some(vec![1000.0]), None).unwrap();
+        budget_system.add_proposal("Test Proposal".to_string(), None, None, None, None, None).unwrap();
+
+        // The mock chain never advances, so the randomness block is never reached
+        let error = {
+            let progress_stream = budget_system.create_raffle_with_progress(
+                "Test Proposal".to_string(),
+                Some(5),
+                None,
+                true,
+            ).await;
+            pin_mut!(progress_stream);
+
+            let mut error = None;
+            while let Some(progress) = progress_stream.next().await {
+                if let Err(e) = progress {
+                    error = Some(e);
+                    break;
+                }
+            }
+            error
+        };
+
+        assert!(error.unwrap().0.contains("Timed out"));
+        assert!(budget_system.state().raffles().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_generate_unpaid_requests_report() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        // Create an epoch
+        let epoch_id = create_active_epoch(&mut budget_system).await;
+
+        // Create a team
+        let team_id = budget_system.create_team(
+            "Test Team".to_string(),
+            "Representative".to_string(),
+            Some(vec![1000.0]),
+            None
+        ).unwrap();
+
+        // Create a proposal with budget request
+        let mut amounts = HashMap::new();
+        amounts.insert("ETH".to_string(), 100.0);
+        
+        let proposal_id = budget_system.add_proposal(
+            "Test Proposal".to_string(),
+            None,
+            Some(BudgetRequestDetails::new(
+                Some(team_id),
+                amounts,
+                None,
+                None,
+                Some(false),
+                Some("0x742d35Cc6634C0532925a3b844Bc454e4438f44e".to_string()),
+            ).unwrap()),
+            Some(Utc::now().date_naive()),
+            Some(Utc::now().date_naive()),
+            None,
+        ).unwrap();
+
+        // Approve the proposal
+        budget_system.close_with_reason(proposal_id, &Resolution::Approved).unwrap();
+
+        // Generate report
+        let output_path = temp_dir.path().join("test_report.json");
+        let result = budget_system.generate_unpaid_requests_report(
+            Some(output_path.to_str().unwrap()),
+            None,
+        );
+
+        assert!(result.is_ok());
+
+        // Verify report contents
+        let report_content = fs::read_to_string(output_path).unwrap();
+        let report: UnpaidRequestsReport = serde_json::from_str(&report_content).unwrap();
+        
+        assert_eq!(report.unpaid_requests.len(), 1);
+        assert_eq!(report.unpaid_requests[0].title, "Test Proposal");
+        assert_eq!(report.unpaid_requests[0].team_name, "Test Team");
+    }
+
+    #[tokio::test]
+    async fn test_action_items() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        // Snapshot an earner's revenue in a raffle during a previous epoch
+        let first_epoch = budget_system.create_epoch("Epoch 1", Utc::now() - Duration::days(60), Utc::now() - Duration::days(31)).unwrap();
+        budget_system.activate_epoch(first_epoch).unwrap();
+        let team_id = budget_system.create_team("Earner Team".to_string(), "Rep".to_string(), Some(vec![1000.0]), None).unwrap();
+        let old_proposal = budget_system.add_proposal("Old Proposal".to_string(), None, None, None, None, None).unwrap();
+        let config = budget_system.config().clone();
+        let (old_raffle, _) = budget_system.prepare_raffle("Old Proposal", None, &config).unwrap();
+        budget_system.finalize_raffle(old_raffle, 12345, 12355, "mock_randomness".to_string()).await.unwrap();
+        budget_system.close_with_reason(old_proposal, &Resolution::Rejected).unwrap();
+        budget_system.close_epoch(None, None).unwrap();
+
+        let second_epoch = budget_system.create_epoch("Epoch 2", Utc::now() - Duration::days(30), Utc::now() + Duration::days(30)).unwrap();
+        budget_system.activate_epoch(second_epoch).unwrap();
+
+        // Open proposal past its SLA
+        let overdue_id = budget_system.add_proposal(
+            "Overdue Proposal".to_string(), None, None,
+            Some((Utc::now() - Duration::days(30)).date_naive()), None, None
+        ).unwrap();
+
+        // Raffle prepared but never finalized
+        budget_system.add_proposal("Raffle Proposal".to_string(), None, None, None, None, None).unwrap();
+        let (pending_raffle, _) = budget_system.prepare_raffle("Raffle Proposal", None, &config).unwrap();
+
+        // Open vote close to the end of its window
+        let vote_proposal = budget_system.add_proposal("Vote Proposal".to_string(), None, None, None, None, None).unwrap();
+        let vote_id = budget_system.create_informal_vote(vote_proposal).unwrap();
+        budget_system.state.get_vote_mut(&vote_id).unwrap().set_opened_at(Utc::now() - Duration::days(6));
+
+        // Approved budget request without a recorded payment
+        let unpaid_id = budget_system.add_proposal(
+            "Unpaid Proposal".to_string(),
+            None,
+            Some(BudgetRequestDetails::new(
+                Some(team_id),
+                [("ETH".to_string(), 100.0)].iter().cloned().collect(),
+                None,
+                None,
+                Some(false),
+                None
+            ).unwrap()),
+            None,
+            None,
+            None
+        ).unwrap();
+        budget_system.close_with_reason(unpaid_id, &Resolution::Approved).unwrap();
+
+        let items = budget_system.action_items();
+        assert_eq!(items.len(), 5);
+        assert_eq!(items.overdue_proposals[0].entity_id, overdue_id);
+        assert_eq!(items.votes_nearing_deadline[0].entity_id, vote_id);
+        assert_eq!(items.unfinalized_raffles[0].entity_id, pending_raffle);
+        assert_eq!(items.approved_unpaid_proposals[0].entity_id, unpaid_id);
+        assert_eq!(items.stale_earner_revenue[0].entity_id, team_id);
+
+        let checklist = budget_system.execute_command(Command::ActionItems).await.unwrap();
+        assert!(checklist.contains("Overdue Proposal"));
+        assert!(checklist.contains("Vote Proposal"));
+        assert!(checklist.contains("Raffle Proposal"));
+        assert!(checklist.contains("Unpaid Proposal"));
+        assert!(checklist.contains("Earner Team"));
+        assert!(checklist.find("[HIGH]").unwrap() < checklist.find("[LOW]").unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_verify_raffle() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        create_active_epoch(&mut budget_system).await;
+        budget_system.create_team("Team 1".to_string(), "Rep 1".to_string(), Some(vec![1000.0]), None).unwrap();
+        budget_system.create_team("Team 2".to_string(), "Rep 2".to_string(), None, None).unwrap();
+        create_proposal_with_raffle(&mut budget_system, "Test Proposal").await;
+
+        let output = budget_system.execute_command(Command::VerifyRaffle {
+            proposal_name: "Test Proposal".to_string()
+        }).await.unwrap();
+        assert!(output.contains("Result: PASS"));
+
+        // Predefined raffles carry no randomness and cannot be verified
+        budget_system.add_proposal("Predefined Proposal".to_string(), None, None, None, None, None).unwrap();
+        budget_system.import_predefined_raffle("Predefined Proposal", vec!["Team 1".to_string()], vec!["Team 2".to_string()], 1, 1).unwrap();
+        assert!(budget_system.verify_raffle("Predefined Proposal").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_export_epoch_rewards_csv() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        create_active_epoch(&mut budget_system).await;
+        budget_system.set_epoch_reward("ETH", 100.0).unwrap();
+        let team_id = budget_system.create_team("Team, One".to_string(), "Rep".to_string(), Some(vec![1000.0]), None).unwrap();
+        let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, "Test Proposal").await;
+        let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
+        budget_system.cast_votes(vote_id, vec![(team_id, VoteChoice::Yes)]).unwrap();
+        budget_system.close_vote(vote_id).unwrap();
+        budget_system.close_with_reason(proposal_id, &Resolution::Approved).unwrap();
+        budget_system.close_epoch(None, None).unwrap();
+
+        let csv = budget_system.export_epoch_rewards_csv("Test Epoch").unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next().unwrap(), "team_name,status,counted_votes,uncounted_votes,total_points,percentage,reward_amount,reward_token");
+        let points = budget_system.config().counted_vote_points;
+        assert_eq!(lines.next().unwrap(), format!("\"Team, One\",Earner,1,0,{},100.00,100,ETH", points));
+
+        let output = budget_system.execute_command(Command::ExportEpochRewardsCsv {
+            epoch_name: "Test Epoch".to_string()
+        }).await.unwrap();
+        assert!(output.contains("epoch_rewards.csv"));
+        assert!(temp_dir.path().join("reports").join("Test_Epoch").exists());
+
+        assert!(budget_system.export_epoch_rewards_csv("Unknown Epoch").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_vote_report_with_abstentions() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        create_active_epoch(&mut budget_system).await;
+        let team_a = budget_system.create_team("Team A".to_string(), "Rep".to_string(), Some(vec![1000.0]), None).unwrap();
+        let team_b = budget_system.create_team("Team B".to_string(), "Rep".to_string(), Some(vec![1000.0]), None).unwrap();
+        let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, "Test Proposal").await;
+
+        let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
+        budget_system.cast_votes(vote_id, vec![(team_a, VoteChoice::Yes), (team_b, VoteChoice::Abstain)]).unwrap();
+        budget_system.close_vote(vote_id).unwrap();
+
+        let report = budget_system.generate_vote_report(vote_id).unwrap();
+        let total_seats = budget_system.config().default_total_counted_seats;
+        assert!(report.contains(&format!("1 in favor, 0 against, 1 abstained, {} absent", total_seats - 2)));
+        assert!(!report.contains("Team B absent"));
+    }
+
+    #[tokio::test]
+    async fn test_vote_report_quorum_not_reached() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+        budget_system.config.default_quorum = 0.5;
+
+        create_active_epoch(&mut budget_system).await;
+        let team_a = budget_system.create_team("Team A".to_string(), "Rep".to_string(), Some(vec![1000.0]), None).unwrap();
+        budget_system.create_team("Team B".to_string(), "Rep".to_string(), Some(vec![1000.0]), None).unwrap();
+        let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, "Test Proposal").await;
+
+        let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
+        budget_system.cast_votes(vote_id, vec![(team_a, VoteChoice::Yes)]).unwrap();
+        budget_system.close_vote(vote_id).unwrap();
+
+        let report = budget_system.generate_vote_report(vote_id).unwrap();
+        let total_seats = budget_system.config().default_total_counted_seats;
+        let required = (0.5 * total_seats as f64).ceil() as usize;
+        assert!(report.contains(&format!("Failed to reach quorum ({}/{} required)", required, total_seats)));
+    }
+
+    #[tokio::test]
+    async fn test_recompute_vote_outcome() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+        budget_system.config.default_quorum = 0.25;
+        budget_system.config.default_qualified_majority_threshold = 0.1;
+
+        create_active_epoch(&mut budget_system).await;
+        let team_a = budget_system.create_team("Team A".to_string(), "Rep".to_string(), Some(vec![1000.0]), None).unwrap();
+        let team_b = budget_system.create_team("Team B".to_string(), "Rep".to_string(), Some(vec![1000.0]), None).unwrap();
+        let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, "Test Proposal").await;
+
+        let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
+        budget_system.cast_votes(vote_id, vec![(team_a, VoteChoice::Yes)]).unwrap();
+        let passed = budget_system.close_vote_and_update_proposal(vote_id, proposal_id, None).unwrap();
+        assert!(!passed);
+        assert!(budget_system.get_proposal(&proposal_id).unwrap().is_rejected());
+
+        // Team B was wrongly left out of the counted participation
+        budget_system.state.get_vote_mut(&vote_id).unwrap().add_participant(team_b, true).unwrap();
+
+        assert_eq!(budget_system.recompute_vote_outcome(vote_id, false).unwrap(), (false, true));
+        assert!(budget_system.get_proposal(&proposal_id).unwrap().is_rejected());
+
+        assert_eq!(budget_system.recompute_vote_outcome(vote_id, true).unwrap(), (false, true));
+        assert!(budget_system.get_proposal(&proposal_id).unwrap().is_approved());
+        assert_eq!(budget_system.recompute_vote_outcome(vote_id, false).unwrap(), (true, true));
+
+        // Team A's yes vote stops counting once it is taken out of the participation
+        budget_system.state.get_vote_mut(&vote_id).unwrap().remove_participant(team_a);
+        assert_eq!(budget_system.recompute_vote_outcome(vote_id, true).unwrap(), (true, false));
+        assert!(budget_system.get_proposal(&proposal_id).unwrap().is_rejected());
+
+        budget_system.close_epoch(None, None).unwrap();
+        assert!(budget_system.recompute_vote_outcome(vote_id, true).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rename_team_keeps_points_history() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        create_active_epoch(&mut budget_system).await;
+        let team_id = budget_system.create_team("Old Name".to_string(), "Rep".to_string(), Some(vec![1000.0]), None).unwrap();
+        budget_system.create_team("Other Team".to_string(), "Rep".to_string(), None, None).unwrap();
+        let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, "Test Proposal").await;
+        let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
+        budget_system.cast_votes(vote_id, vec![(team_id, VoteChoice::Yes)]).unwrap();
+        budget_system.close_vote(vote_id).unwrap();
+
+        let history_before = budget_system.get_team_points_history(team_id).unwrap();
+        assert!(history_before.iter().any(|(_, points)| *points > 0));
+
+        let output = budget_system.execute_command(Command::RenameTeam {
+            old_name: "Old Name".to_string(),
+            new_name: "New Name".to_string(),
+        }).await.unwrap();
+        assert!(output.contains("New Name"));
+
+        assert_eq!(budget_system.get_team_id_by_name("New Name"), Some(team_id));
+        assert!(budget_system.get_team_id_by_name("Old Name").is_none());
+        assert_eq!(budget_system.get_team_points_history(team_id).unwrap(), history_before);
+
+        // Raffle snapshots keep the name the team had at raffle time
+        let raffle = budget_system.state.get_raffle(&raffle_id).unwrap();
+        assert!(raffle.team_snapshots().iter().any(|snapshot| snapshot.name() == "Old Name"));
+
+        assert!(budget_system.rename_team(team_id, "Other Team".to_string()).is_err());
+        assert!(budget_system.rename_team(team_id, " ".to_string()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_execute_command_json() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        let value = budget_system.execute_command_json(Command::AddTeam {
+            name: "Team A".to_string(),
+            representative: "Rep".to_string(),
+            trailing_monthly_revenue: None,
+            address: None,
+            contacts: Vec::new(),
+        }).await.unwrap();
+        assert_eq!(value["command"], "AddTeam");
+        assert_eq!(value["name"], "Team A");
+        let team_id = budget_system.get_team_id_by_name("Team A").unwrap();
+        assert_eq!(value["team_id"], team_id.to_string());
+
+        let value = budget_system.execute_command_json(Command::PrintTeamReport).await.unwrap();
+        assert_eq!(value["command"], "PrintTeamReport");
+        assert_eq!(value["teams"][0]["team"]["name"], "Team A");
+
+        let value = budget_system.execute_command_json(Command::ActionItems).await.unwrap();
+        assert!(value["action_items"].is_object());
+
+        assert!(budget_system.execute_command_json(Command::PrintEpochState).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_execute_command_json_reports() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+        budget_system.config.default_qualified_majority_threshold = 0.1;
+        let epoch_id = create_active_epoch(&mut budget_system).await;
+        let epoch_name = budget_system.get_epoch(&epoch_id).unwrap().name().to_string();
+        let team_id = budget_system.create_team("Team A".to_string(), "Rep".to_string(), Some(vec![1000.0]), None).unwrap();
+
+        let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, "Grant").await;
+        let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
+        budget_system.cast_votes(vote_id, vec![(team_id, VoteChoice::Yes)]).unwrap();
+        budget_system.close_vote_and_update_proposal(vote_id, proposal_id, None).unwrap();
+
+        let value = budget_system.execute_command_json(Command::PrintTeamVoteParticipation {
+            team_name: "Team A".to_string(),
+            epoch_name: None,
+        }).await.unwrap();
+        assert_eq!(value["command"], "PrintTeamVoteParticipation");
+        assert_eq!(value["epoch"], epoch_name.as_str());
+        assert_eq!(value["votes"][0]["vote_id"], vote_id.to_string());
+        assert_eq!(value["votes"][0]["proposal"], "Grant");
+        assert_eq!(value["total_points"], value["votes"][0]["points"]);
+
+        let value = budget_system.execute_command_json(Command::PrintPointReport { epoch_name: None }).await.unwrap();
+        assert_eq!(value["points"][0]["name"], "Team A");
+
+        let value = budget_system.execute_command_json(Command::VerifyRaffle { proposal_name: "Grant".to_string() }).await.unwrap();
+        assert_eq!(value["raffle_id"], raffle_id.to_string());
+        assert_eq!(value["passed"], true);
+
+        let output_path = temp_dir.path().join("unpaid.json");
+        let value = budget_system.execute_command_json(Command::GenerateUnpaidRequestsReport {
+            output_path: Some(output_path.to_str().unwrap().to_string()),
+            epoch_name: None,
+        }).await.unwrap();
+        assert_eq!(value["path"], output_path.to_str().unwrap());
+        assert!(value["unpaid_requests"].as_array().unwrap().is_empty());
+
+        budget_system.close_epoch(None, None).unwrap();
+        let value = budget_system.execute_command_json(Command::GenerateEndOfEpochReport {
+            epoch_name: epoch_name.clone(),
+            format: ReportFormat::Markdown,
+        }).await.unwrap();
+        assert_eq!(value["epoch"]["name"], epoch_name.as_str());
+        assert_eq!(value["proposals"][0]["title"], "Grant");
+        assert!(std::path::Path::new(value["path"].as_str().unwrap()).exists());
+        assert!(value.get("message").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cashflow_forecast() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        let epoch_id = create_active_epoch(&mut budget_system).await;
+        let mut amounts = HashMap::new();
+        amounts.insert("ETH".to_string(), 300.0);
+        let proposal_id = budget_system.add_proposal(
+            "Quarterly Budget".to_string(),
+            None,
+            Some(BudgetRequestDetails::new(
+                None,
+                amounts,
+                Some(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()),
+                Some(NaiveDate::from_ymd_opt(2024, 3, 31).unwrap()),
+                None,
+                None
+            ).unwrap()),
+            None,
+            None,
+            None
+        ).unwrap();
+
+        // Unapproved proposals are not part of the forecast
+        assert!(budget_system.cashflow_forecast(epoch_id).is_empty());
+
+        budget_system.close_with_reason(proposal_id, &Resolution::Approved).unwrap();
+        let forecast = budget_system.cashflow_forecast(epoch_id);
+        assert_eq!(forecast.len(), 3);
+        assert_eq!(forecast[1].0, NaiveDate::from_ymd_opt(2024, 2, 1).unwrap());
+        assert!(forecast.iter().all(|(_, amounts)| amounts.get("ETH") == Some(&100.0)));
+
+        let output = budget_system.execute_command(Command::CashflowForecast { epoch_name: None }).await.unwrap();
+        assert!(output.contains("2024-03-01: 100.00 ETH"));
+    }
+
+    #[tokio::test]
+    async fn test_dilution_report() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        let epoch_id = create_active_epoch(&mut budget_system).await;
+        let epoch_start = budget_system.get_epoch(&epoch_id).unwrap().start_date();
+
+        for name in ["Team A", "Team B"] {
+            let team_id = budget_system.create_team(name.to_string(), "Rep".to_string(), None, None).unwrap();
+            budget_system.state.get_team_mut(&team_id).unwrap()
+                .set_active_from(Some(epoch_start - chrono::Duration::days(10)));
+        }
+
+        let before = budget_system.dilution_report(epoch_id);
+        assert_eq!(before.len(), 2);
+        assert!(before.iter().all(|(_, lost)| *lost == 0.0));
+
+        let joiner_id = budget_system.create_team("Late Team".to_string(), "Rep".to_string(), None, None).unwrap();
+        budget_system.state.get_team_mut(&joiner_id).unwrap()
+            .set_active_from(Some(epoch_start + chrono::Duration::days(15)));
+
+        let after = budget_system.dilution_report(epoch_id);
+        assert_eq!(after.len(), 2);
+        assert_eq!(after[0].0, "Team A");
+        assert!(after.iter().all(|(_, lost)| *lost > 0.0 && *lost < 50.0));
+
+        let output = budget_system.execute_command(Command::DilutionReport { epoch_name: None }).await.unwrap();
+        assert!(output.contains("Team A"));
+        assert!(!output.contains("Late Team"));
+    }
+
+    #[tokio::test]
+    async fn test_to_script_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+        // Two yes votes carry, so the approval matches the vote the script replays
+        budget_system.config.default_qualified_majority_threshold = 0.1;
+
+        let epoch_id = create_active_epoch(&mut budget_system).await;
+        budget_system.set_epoch_reward("ETH", 100.0).unwrap();
+        let team_a = budget_system.create_team("Team A".to_string(), "Rep A".to_string(), Some(vec![1000.0, 2000.0, 3000.0]), None).unwrap();
+        let team_b = budget_system.create_team("Team B".to_string(), "Rep B".to_string(), None, None).unwrap();
+
+        let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, "Voted Proposal").await;
+        let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
+        budget_system.cast_votes(vote_id, vec![(team_a, VoteChoice::Yes), (team_b, VoteChoice::Yes)]).unwrap();
+        budget_system.close_vote(vote_id).unwrap();
+        budget_system.execute_command(Command::CloseProposal {
+            proposal_name: "Voted Proposal".to_string(),
+            resolution: "Approved".to_string(),
+        }).await.unwrap();
+
+        budget_system.add_proposal("Retracted Proposal".to_string(), None, None, None, None, None).unwrap();
+        budget_system.execute_command(Command::CloseProposal {
+            proposal_name: "Retracted Proposal".to_string(),
+            resolution: "Retracted".to_string(),
+        }).await.unwrap();
+
+        budget_system.close_epoch(None, None).unwrap();
+
+        let script = budget_system.to_script();
+        let serialized = serde_json::to_string(&script).unwrap();
+        let script: Vec<Command> = serde_json::from_str(&serialized).unwrap();
+
+        let replay_file = temp_dir.path().join("replay_state.json").to_str().unwrap().to_string();
+        let mut replayed = create_test_budget_system(&replay_file, None).await;
+        for command in script {
+            replayed.execute_command(command).await.unwrap();
+        }
+
+        let replayed_epoch_id = replayed.get_epoch_id_by_name("Test Epoch").unwrap();
+        let original_epoch = budget_system.state.get_epoch(&epoch_id).unwrap();
+        let replayed_epoch = replayed.state.get_epoch(&replayed_epoch_id).unwrap();
+        assert!(replayed_epoch.is_closed());
+        assert_eq!(replayed_epoch.start_date(), original_epoch.start_date());
+        assert_eq!(replayed_epoch.reward().map(|r| r.amount()), Some(100.0));
+
+        for proposal in budget_system.get_proposals_for_epoch(epoch_id) {
+            let replayed_id = replayed.get_proposal_id_by_name(proposal.title()).unwrap();
+            let replayed_proposal = replayed.state.get_proposal(&replayed_id).unwrap();
+            assert_eq!(replayed_proposal.status(), proposal.status());
+            assert_eq!(replayed_proposal.resolution(), proposal.resolution());
+        }
+
+        assert_eq!(replayed.state.raffles().len(), budget_system.state.raffles().len());
+        assert_eq!(replayed.state.votes().len(), budget_system.state.votes().len());
+
+        for (name, team_id) in [("Team A", team_a), ("Team B", team_b)] {
+            let replayed_team_id = replayed.get_team_id_by_name(name).unwrap();
+            let original: u32 = budget_system.get_team_points_history(team_id).unwrap().iter().map(|(_, p)| p).sum();
+            let copy: u32 = replayed.get_team_points_history(replayed_team_id).unwrap().iter().map(|(_, p)| p).sum();
+            assert_eq!(copy, original);
+            assert_eq!(
+                replayed.state.get_team(&replayed_team_id).unwrap().status(),
+                budget_system.state.get_team(&team_id).unwrap().status()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_team_approval_velocity() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        create_active_epoch(&mut budget_system).await;
+        let team_id = budget_system.create_team("Team A".to_string(), "Rep".to_string(), None, None).unwrap();
+        assert_eq!(budget_system.team_approval_velocity(team_id), None);
+
+        let announced = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        for (title, days, approve) in [("Fast", 4, true), ("Slow", 10, true), ("Rejected", 100, false), ("Undated", 0, true)] {
+            let details = BudgetRequestDetails::new(
+                Some(team_id),
+                HashMap::from([("ETH".to_string(), 1.0)]),
+                None,
+                None,
+                Some(false),
+                None,
+            ).unwrap();
+            let proposal_id = budget_system.add_proposal(title.to_string(), None, Some(details), Some(announced), None, None).unwrap();
+            let proposal = budget_system.state.get_proposal_mut(&proposal_id).unwrap();
+            if approve {
+                proposal.approve().unwrap();
+            } else {
+                proposal.reject().unwrap();
+            }
+            if title != "Undated" {
+                proposal.set_resolved_at(Some(announced + chrono::Duration::days(days)));
+            }
+        }
+
+        assert_eq!(budget_system.team_approval_velocity(team_id), Some(7.0));
+
+        let output = budget_system.execute_command(Command::TeamApprovalVelocity { team_name: "Team A".to_string() }).await.unwrap();
+        assert!(output.contains("7.0 days"));
+    }
+
+    #[tokio::test]
+    async fn test_record_payment() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        create_active_epoch(&mut budget_system).await;
+        let team_id = budget_system.create_team("Team A".to_string(), "Rep".to_string(), None, None).unwrap();
+        let details = BudgetRequestDetails::new(
+            Some(team_id),
+            HashMap::from([("ETH".to_string(), 10.0)]),
+            None,
+            None,
+            Some(false),
+            None,
+        ).unwrap();
+        let proposal_id = budget_system.add_proposal("Paid Proposal".to_string(), None, Some(details), None, None, None).unwrap();
+        let tx_hash = "0x742d35cc6634c0532925a3b844bc454e4438f44e4438f44e4438f44e4438f44e".to_string();
+
+        // Open proposals can't be paid
+        assert!(budget_system.record_payment("Paid Proposal", tx_hash.clone()).is_err());
+
+        budget_system.state.get_proposal_mut(&proposal_id).unwrap().approve().unwrap();
+        budget_system.execute_command(Command::RecordPayment {
+            proposal_name: "Paid Proposal".to_string(),
+            tx_hash: tx_hash.clone(),
+        }).await.unwrap();
+
+        let details = budget_system.get_proposal(&proposal_id).unwrap().budget_request_details().unwrap();
+        assert!(details.is_paid());
+        assert!(budget_system.record_payment("Paid Proposal", tx_hash.clone()).is_err());
+
+        let report = budget_system.generate_proposal_report(proposal_id).unwrap();
+        assert!(report.contains(&format!("https://etherscan.io/tx/{}", tx_hash)));
+    }
+
+    #[tokio::test]
+    async fn test_unique_representatives() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        budget_system.create_team("Team A".to_string(), "Alice".to_string(), None, None).unwrap();
+        budget_system.create_team("Team B".to_string(), "alice ".to_string(), None, None).unwrap();
+        assert_eq!(
+            budget_system.shared_representatives(),
+            vec![("Alice".to_string(), vec!["Team A".to_string(), "Team B".to_string()])]
+        );
+
+        budget_system.config.unique_representatives = true;
+        assert!(budget_system.create_team("Team C".to_string(), "Alice".to_string(), None, None).is_err());
+        let team_c = budget_system.create_team("Team C".to_string(), "Carol".to_string(), None, None).unwrap();
+
+        let updates = UpdateTeamDetails {
+            name: None,
+            representative: Some("Alice".to_string()),
+            status: None,
+            trailing_monthly_revenue: None,
+            address: None,
+            contacts: None,
+        };
+        assert!(budget_system.update_team(team_c, updates).is_err());
+        assert_eq!(budget_system.get_team(&team_c).unwrap().representative(), "Carol");
+
+        let output = budget_system.execute_command(Command::CheckRepresentatives).await.unwrap();
+        assert!(output.contains("Alice: Team A, Team B"));
+    }
+
+    #[tokio::test]
+    async fn test_weighted_vote() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        let epoch_id = create_active_epoch(&mut budget_system).await;
+        let team_a = budget_system.create_team("Team A".to_string(), "Rep A".to_string(), None, None).unwrap();
+        budget_system.create_team("Team B".to_string(), "Rep B".to_string(), None, None).unwrap();
+
+        // Only Team A earns points before the weighted vote
+        let (first_proposal, first_raffle) = create_proposal_with_raffle(&mut budget_system, "First Proposal").await;
+        let first_vote = budget_system.create_formal_vote(first_proposal, first_raffle, None).unwrap();
+        budget_system.cast_votes(first_vote, vec![(team_a, VoteChoice::Yes)]).unwrap();
+        budget_system.close_vote(first_vote).unwrap();
+
+        let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, "Weighted Proposal").await;
+        let vote_id = budget_system.create_weighted_vote(proposal_id, raffle_id, epoch_id).unwrap();
+        budget_system.cast_votes(vote_id, vec![(team_a, VoteChoice::Yes)]).unwrap();
+        assert!(budget_system.close_vote(vote_id).unwrap());
+
+        let report = budget_system.generate_vote_report(vote_id).unwrap();
+        assert!(report.contains("Team A: Yes (weight 5)"));
+        assert!(report.contains("Team B: Absent (weight 0)"));
+
+        // Participation points are credited as for formal votes
+        assert_eq!(budget_system.get_team_points_for_epoch(team_a, epoch_id).unwrap(), 10);
+    }
+
+    #[tokio::test]
+    async fn test_undo_last_mutating_command() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+        budget_system.config.undo_history_size = 2;
+
+        for name in ["Team A", "Team B", "Team C"] {
+            budget_system.execute_command(Command::AddTeam {
+                name: name.to_string(),
+                representative: "Rep".to_string(),
+                trailing_monthly_revenue: None,
+                address: None,
+                contacts: Vec::new(),
+            }).await.unwrap();
+        }
+        // Read-only and failed commands are not recorded, and the history stays bounded
+        budget_system.execute_command(Command::PrintTeamReport).await.unwrap();
+        assert!(budget_system.execute_command(Command::ActivateEpoch { name: "Missing".to_string() }).await.is_err());
+        assert_eq!(budget_system.undo_depth(), 2);
+
+        let output = budget_system.execute_command(Command::Undo).await.unwrap();
+        assert!(output.contains("Reverted"));
+        assert!(budget_system.get_team_id_by_name("Team C").is_none());
+        assert!(budget_system.get_team_id_by_name("Team B").is_some());
+
+        let saved = FileSystem::load_state(&state_file).unwrap();
+        assert_eq!(saved.current_state().teams().len(), 2);
+
+        budget_system.undo().unwrap();
+        assert!(budget_system.get_team_id_by_name("Team B").is_none());
+        assert_eq!(budget_system.undo(), Err("Nothing to undo"));
+    }
+
+    #[tokio::test]
+    async fn test_audit_log_records_mutating_commands() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+        assert!(budget_system.verify_audit_chain().is_err());
+        budget_system.config.audit_log = temp_dir.path().join("audit.log").to_str().unwrap().to_string();
+
+        for name in ["Team A", "Team B"] {
+            budget_system.execute_command(Command::AddTeam {
+                name: name.to_string(),
+                representative: "Rep".to_string(),
+                trailing_monthly_revenue: None,
+                address: None,
+                contacts: Vec::new(),
+            }).await.unwrap();
+        }
+        // Read-only and failed commands leave no entry
+        budget_system.execute_command(Command::PrintTeamReport).await.unwrap();
+        assert!(budget_system.execute_command(Command::ActivateEpoch { name: "Missing".to_string() }).await.is_err());
+        budget_system.execute_command(Command::Undo).await.unwrap();
+        assert_eq!(budget_system.verify_audit_chain().unwrap(), 3);
+
+        let log = std::fs::read_to_string(&budget_system.config.audit_log).unwrap();
+        let entries: Vec<audit::AuditEntry> = log.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(entries[0].operation, "AddTeam");
+        assert_eq!(entries[1].arguments["name"], "Team B");
+        assert_eq!(entries[2].operation, "Undo");
+    }
+
+    #[tokio::test]
+    async fn test_close_epoch_without_reward_warns() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        create_active_epoch(&mut budget_system).await;
+        let team_id = budget_system.create_team("Team A".to_string(), "Rep".to_string(), None, None).unwrap();
+        let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, "Test Proposal").await;
+        let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
+        budget_system.cast_votes(vote_id, vec![(team_id, VoteChoice::Yes)]).unwrap();
+        budget_system.close_vote(vote_id).unwrap();
+        budget_system.state.get_proposal_mut(&proposal_id).unwrap().approve().unwrap();
+
+        let output = budget_system.execute_command(Command::CloseEpoch {
+            epoch_name: None,
+            confirm_no_reward: false,
+            carry_over_to: None,
+        }).await.unwrap();
+        assert!(output.contains("WARNING"));
+        assert!(output.contains("without a reward"));
+        assert!(budget_system.get_epoch_id_by_name("Test Epoch")
+            .and_then(|id| budget_system.get_epoch(&id))
+            .unwrap()
+            .is_closed());
+
+        let second_epoch = budget_system.create_epoch("Second Epoch", Utc::now() + chrono::Duration::days(31), Utc::now() + chrono::Duration::days(60)).unwrap();
+        budget_system.activate_epoch(second_epoch).unwrap();
+        budget_system.create_team("Team B".to_string(), "Rep B".to_string(), None, None).unwrap();
+        let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, "Second Proposal").await;
+        let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
+        budget_system.cast_votes(vote_id, vec![(team_id, VoteChoice::Yes)]).unwrap();
+        budget_system.close_vote(vote_id).unwrap();
+        budget_system.state.get_proposal_mut(&proposal_id).unwrap().approve().unwrap();
+
+        let warning = budget_system.close_epoch_with_warnings(None, true, None).unwrap();
+        assert!(warning.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_proposal_tags_and_spend_summary() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        let epoch_id = create_active_epoch(&mut budget_system).await;
+        budget_system.create_team("Team A".to_string(), "Rep".to_string(), None, None).unwrap();
+
+        for (title, amount, tags) in [
+            ("Node Hosting", 10.0, vec!["infra".to_string()]),
+            ("Dev Grant", 5.0, vec!["Grants".to_string(), "infra".to_string()]),
+            ("Misc", 1.0, vec![]),
+        ] {
+            budget_system.execute_command(Command::AddProposal {
+                title: title.to_string(),
+                url: None,
+                budget_request_details: Some(BudgetRequestDetailsCommand {
+                    team: Some("Team A".to_string()),
+                    request_amounts: Some(HashMap::from([("ETH".to_string(), amount)])),
+                    start_date: None,
+                    end_date: None,
+                    is_loan: None,
+                    payment_address: None,
+                    monthly_breakdown: None,
+                }),
+                announced_at: None,
+                published_at: None,
+                is_historical: None,
+                is_draft: false,
+                kind: None,
+                tags,
+                depends_on: Vec::new(),
+            }).await.unwrap();
+            let proposal_id = budget_system.get_proposal_id_by_name(title).unwrap();
+            budget_system.state.get_proposal_mut(&proposal_id).unwrap().approve().unwrap();
+        }
+
+        let infra: Vec<&str> = budget_system.get_proposals_by_tag(epoch_id, "INFRA").iter().map(|p| p.title()).collect();
+        assert_eq!(infra.len(), 2);
+        assert!(infra.contains(&"Dev Grant"));
+        assert_eq!(budget_system.get_proposals_by_tag(epoch_id, "untagged")[0].title(), "Misc");
+
+        let epoch = budget_system.get_epoch(&epoch_id).unwrap().clone();
+        let summary = budget_system.generate_tag_spend_summary(&epoch);
+        assert!(summary.contains("| grants | 1 | 5 ETH |"));
+        assert!(summary.contains("| infra | 2 | 15 ETH |"));
+        assert!(summary.trim_end().ends_with("| untagged | 1 | 1 ETH |"));
+
+        budget_system.update_proposal("Misc", UpdateProposalDetails {
+            title: None,
+            url: None,
+            budget_request_details: None,
+            announced_at: None,
+            published_at: None,
+            resolved_at: None,
+            tags: Some(vec!["marketing".to_string()]),
+            decision_deadline: None,
+            depends_on: None,
+        }).unwrap();
+        assert!(budget_system.get_proposals_by_tag(epoch_id, "untagged").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_avg_competition_ratio() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        let epoch_id = create_active_epoch(&mut budget_system).await;
+        assert_eq!(budget_system.avg_competition_ratio(epoch_id), 0.0);
+
+        let seats = budget_system.config().default_total_counted_seats;
+        for i in 0..seats * 2 {
+            budget_system.create_team(format!("Team {}", i), format!("Rep {}", i), None, None).unwrap();
+        }
+        create_proposal_with_raffle(&mut budget_system, "Competitive Proposal").await;
+
+        assert_eq!(budget_system.avg_competition_ratio(epoch_id), 2.0);
+        let output = budget_system.execute_command(Command::CompetitionRatio { epoch_name: None }).await.unwrap();
+        assert!(output.contains(&format!("{} teams competed for {} counted seats (2.0x)", seats * 2, seats)));
+    }
+
+    #[tokio::test]
+    async fn test_reopen_proposal() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        create_active_epoch(&mut budget_system).await;
+        budget_system.create_team("Team A".to_string(), "Rep A".to_string(), None, None).unwrap();
+        let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, "Miscounted").await;
+
+        let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
+        budget_system.close_vote_and_update_proposal(vote_id, proposal_id, Some(Utc::now().date_naive())).unwrap();
+        assert!(budget_system.get_proposal(&proposal_id).unwrap().is_closed());
+
+        budget_system.execute_command(Command::ReopenProposal { proposal_name: "Miscounted".to_string() }).await.unwrap();
+
+        let proposal = budget_system.get_proposal(&proposal_id).unwrap();
+        assert!(proposal.is_actionable());
+        assert_eq!(proposal.resolution(), None);
+        assert_eq!(proposal.resolved_at(), None);
+        assert!(budget_system.state().votes().contains_key(&vote_id));
+        assert!(budget_system.reopen_proposal("Miscounted").is_err());
+
+        budget_system.close_with_reason(proposal_id, &Resolution::Approved).unwrap();
+        budget_system.state.get_proposal_mut(&proposal_id).unwrap().set_historical(true);
+        assert_eq!(budget_system.reopen_proposal("Miscounted").unwrap_err().to_string(), "Cannot reopen a historical proposal");
+    }
+
+    #[tokio::test]
+    async fn test_redact_team() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        let epoch_id = create_active_epoch(&mut budget_system).await;
+        let team_id = budget_system.create_team("Leaving Team".to_string(), "Alice".to_string(), Some(vec![123456.75]), None).unwrap();
+        let as_earner = Utc::now();
+        budget_system.create_team("Other Team".to_string(), "Bob".to_string(), None, None).unwrap();
+        let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, "Test Proposal").await;
+        let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
+        budget_system.cast_votes(vote_id, vec![(team_id, VoteChoice::Yes)]).unwrap();
+        budget_system.close_vote(vote_id).unwrap();
+
+        let history_before = budget_system.get_team_points_history(team_id).unwrap();
+        let points_before = budget_system.get_team_points_for_epoch(team_id, epoch_id).unwrap();
+
+        budget_system.execute_command(Command::RedactTeam { team_name: "Leaving Team".to_string() }).await.unwrap();
+
+        let team = budget_system.get_team(&team_id).unwrap();
+        assert!(team.name().starts_with("Redacted Team "));
+        assert_eq!(team.representative(), "Redacted");
+        assert_eq!(budget_system.get_team_points_history(team_id).unwrap(), history_before);
+        assert_eq!(budget_system.get_team_points_for_epoch(team_id, epoch_id).unwrap(), points_before);
+
+        // The raffle snapshot keeps the old name, but reports mask it
+        let raffle = budget_system.get_raffle(&raffle_id).unwrap().clone();
+        assert!(raffle.team_snapshots().iter().any(|snapshot| snapshot.name() == "Leaving Team"));
+        let table = budget_system.generate_team_snapshots_table(&raffle);
+        assert!(!table.contains("Leaving Team"));
+        assert!(table.contains(team.name()));
+
+        // Neither the change log nor the raffle snapshot keeps the revenue
+        let serialized = serde_json::to_string(budget_system.state()).unwrap();
+        assert!(!serialized.contains("123456.75"));
+        assert!(matches!(
+            budget_system.team_status_at(team_id, as_earner),
+            Some(TeamStatus::Earner { .. })
+        ));
+
+        assert!(budget_system.redact_team(team_id).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_archive_team_keeps_history() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        create_active_epoch(&mut budget_system).await;
+        let team_id = budget_system.create_team("Leaving Team".to_string(), "Alice".to_string(), None, None).unwrap();
+        let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, "Test Proposal").await;
+        let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
+        budget_system.cast_votes(vote_id, vec![(team_id, VoteChoice::Yes)]).unwrap();
+        budget_system.close_vote(vote_id).unwrap();
+
+        // Deleting would orphan the vote, so only archiving is allowed
+        let error = budget_system.remove_team(team_id).unwrap_err();
+        assert_eq!(error.to_string(), "Team has historical participation; archive it instead");
+        let unused = budget_system.create_team("Unused Team".to_string(), "Bob".to_string(), None, None).unwrap();
+        budget_system.remove_team(unused).unwrap();
+
+        let output = budget_system.execute_command(Command::ArchiveTeam { team_name: "Leaving Team".to_string() }).await.unwrap();
+        assert!(output.starts_with("Archived team: Leaving Team"));
+        assert!(budget_system.get_team(&team_id).is_none());
+        assert!(budget_system.get_team_id_by_name("Leaving Team").is_none());
+        assert_eq!(budget_system.state().find_team(&team_id).unwrap().name(), "Leaving Team");
+
+        let report = budget_system.generate_proposal_report(proposal_id).unwrap();
+        assert!(report.contains("| Leaving Team |"));
+
+        // New raffles no longer include the archived team
+        let (_, new_raffle_id) = create_proposal_with_raffle(&mut budget_system, "Later Proposal").await;
+        let new_raffle = budget_system.get_raffle(&new_raffle_id).unwrap();
+        assert!(new_raffle.team_snapshots().iter().all(|snapshot| snapshot.id() != team_id));
+
+        // The archive survives a save and load
+        let saved = serde_json::to_string(budget_system.state()).unwrap();
+        let reloaded: BudgetSystemState = serde_json::from_str(&saved).unwrap();
+        assert!(reloaded.archived_teams().contains_key(&team_id));
+    }
+
+    #[tokio::test]
+    async fn test_find_placeholder_raffles() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        create_active_epoch(&mut budget_system).await;
+        budget_system.create_team("Team A".to_string(), "Rep A".to_string(), None, None).unwrap();
+        create_proposal_with_raffle(&mut budget_system, "Real Raffle").await;
+
+        budget_system.add_proposal("Predefined".to_string(), None, None, None, None, None).unwrap();
+        budget_system.import_predefined_raffle("Predefined", vec!["Team A".to_string()], vec![], 1, 1).unwrap();
+        assert!(budget_system.find_placeholder_raffles().is_empty());
+
+        budget_system.add_proposal("Incomplete".to_string(), None, None, None, None, None).unwrap();
+        let config = budget_system.config().clone();
+        let (raffle_id, _) = budget_system.prepare_raffle("Incomplete", None, &config).unwrap();
+        budget_system.finalize_raffle(raffle_id, 0, 0, "N/A".to_string()).await.unwrap();
+
+        let flagged = budget_system.find_placeholder_raffles();
+        assert_eq!(flagged.len(), 1);
+        assert!(flagged[0].contains("Incomplete"));
+        assert_eq!(budget_system.action_items().placeholder_raffles[0].entity_id, raffle_id);
+    }
+
+    #[tokio::test]
+    async fn test_effective_team_count() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        let epoch_id = create_active_epoch(&mut budget_system).await;
+        assert_eq!(budget_system.effective_team_count(epoch_id), 0.0);
+
+        let team_a = budget_system.create_team("Team A".to_string(), "Rep A".to_string(), None, None).unwrap();
+        let team_b = budget_system.create_team("Team B".to_string(), "Rep B".to_string(), None, None).unwrap();
+
+        let informal_vote = |budget_system: &mut BudgetSystem, name: &str, teams: &[Uuid]| {
+            let proposal_id = budget_system.add_proposal(name.to_string(), None, None, None, None, None).unwrap();
+            let vote_id = budget_system.create_informal_vote(proposal_id).unwrap();
+            budget_system.cast_votes(vote_id, teams.iter().map(|&team| (team, VoteChoice::Yes)).collect()).unwrap();
+        };
+
+        // One team doing everything
+        informal_vote(&mut budget_system, "P1", &[team_a]);
+        informal_vote(&mut budget_system, "P2", &[team_a]);
+        informal_vote(&mut budget_system, "P3", &[team_a]);
+        assert!((budget_system.effective_team_count(epoch_id) - 1.0).abs() < 1e-9);
+
+        // 3:1 split, exp(-(0.75 ln 0.75 + 0.25 ln 0.25))
+        informal_vote(&mut budget_system, "P4", &[team_b]);
+        assert!((budget_system.effective_team_count(epoch_id) - 1.7548).abs() < 1e-4);
+
+        // Even participation
+        informal_vote(&mut budget_system, "P5", &[team_b]);
+        informal_vote(&mut budget_system, "P6", &[team_b]);
+        assert!((budget_system.effective_team_count(epoch_id) - 2.0).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_proposal_attachment_in_report() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+        budget_system.config.ipfs_gateway = "https://ipfs.io/ipfs/".to_string();
+
+        create_active_epoch(&mut budget_system).await;
+        let proposal_id = budget_system.add_proposal("Documented".to_string(), None, None, None, None, None).unwrap();
+        let cid = "QmYwAPJzv5CZsnA625s3Xf2nemtYgPpHdWEz79ojWnPbdG";
+
+        budget_system.execute_command(Command::AddAttachment {
+            proposal_name: "Documented".to_string(),
+            label: "Specification".to_string(),
+            cid: cid.to_string(),
+        }).await.unwrap();
+        assert!(budget_system.add_proposal_attachment("Documented", "Bad".to_string(), "xyz".to_string()).is_err());
+
+        let report = budget_system.generate_proposal_report(proposal_id).unwrap();
+        assert!(report.contains(&format!("- [Specification](https://ipfs.io/ipfs/{})", cid)));
+    }
+
+    #[tokio::test]
+    async fn test_pay_team_reward() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        let epoch_id = create_active_epoch(&mut budget_system).await;
+        let address = "0x742d35Cc6634C0532925a3b844Bc454e4438f44e";
+        let team_a = budget_system.create_team("Team A".to_string(), "Rep A".to_string(), None, Some(address.to_string())).unwrap();
+        let team_b = budget_system.create_team("Team B".to_string(), "Rep B".to_string(), None, None).unwrap();
+        let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, "Test Proposal").await;
+        let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
+        budget_system.cast_votes(vote_id, vec![(team_a, VoteChoice::Yes), (team_b, VoteChoice::Yes)]).unwrap();
+        budget_system.close_vote_and_update_proposal(vote_id, proposal_id, None).unwrap();
+        budget_system.set_epoch_reward("ETH", 100.0).unwrap();
+
+        let pay = Command::PayTeamReward { epoch_name: "Test Epoch".to_string(), team_name: "Team A".to_string() };
+        let error = budget_system.execute_command(pay.clone()).await.unwrap_err();
+        assert_eq!(error.to_string(), "Rewards can only be paid for closed epochs");
+
+        budget_system.close_epoch(None, None).unwrap();
+        let output = budget_system.execute_command(pay.clone()).await.unwrap();
+        assert!(output.starts_with("Sent 50 ETH to Team A for epoch Test Epoch: 0x"));
+
+        let sent = get_mock_service(&budget_system).unwrap().sent_payments();
+        assert_eq!(sent, vec![(address.parse::<ethers::types::Address>().unwrap(), "ETH".to_string(), 50.0)]);
+        let reward = &budget_system.get_epoch(&epoch_id).unwrap().team_rewards()[&team_a];
+        assert_eq!(reward.outstanding(), 0.0);
+        assert_eq!(reward.payout_txs().len(), 1);
+
+        // Paying twice or paying a team without an address sends nothing
+        assert!(budget_system.execute_command(pay).await.unwrap_err().to_string().contains("already paid"));
+        assert_eq!(
+            budget_system.pay_team_reward("Test Epoch", "Team B").await.unwrap_err().to_string(),
+            "Team Team B has no payment address"
+        );
+        assert_eq!(get_mock_service(&budget_system).unwrap().sent_payments().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_update_epoch_reward() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        let epoch_id = create_active_epoch(&mut budget_system).await;
+        let planned_id = budget_system.create_epoch(
+            "Planned Epoch",
+            Utc::now() + Duration::days(31),
+            Utc::now() + Duration::days(61),
+        ).unwrap();
+
+        budget_system.update_epoch_reward("Planned Epoch", "ETH", 40.0).unwrap();
+        assert_eq!(budget_system.get_epoch(&planned_id).unwrap().total_reward_amount(), 40.0);
+        budget_system.execute_command(Command::UpdateEpochReward {
+            epoch_name: "Test Epoch".to_string(),
+            token: "ETH".to_string(),
+            amount: 100.0,
+        }).await.unwrap();
+        assert_eq!(budget_system.get_epoch(&epoch_id).unwrap().total_reward_amount(), 100.0);
+        assert_eq!(budget_system.update_epoch_reward("Test Epoch", "ETH", -1.0).unwrap_err().to_string(), "Reward amount must be non-negative");
+
+        budget_system.close_epoch(None, None).unwrap();
+        assert_eq!(
+            budget_system.update_epoch_reward("Test Epoch", "ETH", 200.0).unwrap_err().to_string(),
+            "Cannot change the reward of a closed epoch"
+        );
+        assert_eq!(budget_system.get_epoch(&epoch_id).unwrap().total_reward_amount(), 100.0);
+        assert_eq!(budget_system.update_epoch_reward("Missing", "ETH", 1.0).unwrap_err().to_string(), "Epoch not found: Missing");
+    }
+
+    #[tokio::test]
+    async fn test_top_up_closed_epoch_reward() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        let epoch_id = create_active_epoch(&mut budget_system).await;
+        let team_a = budget_system.create_team("Team A".to_string(), "Rep A".to_string(), None, None).unwrap();
+        budget_system.create_team("Team B".to_string(), "Rep B".to_string(), None, None).unwrap();
+        let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, "Test Proposal").await;
+        let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
+        budget_system.cast_votes(vote_id, vec![(team_a, VoteChoice::Yes)]).unwrap();
+        budget_system.close_vote(vote_id).unwrap();
+        budget_system.state.get_proposal_mut(&proposal_id).unwrap().approve().unwrap();
+        budget_system.set_epoch_reward("ETH", 100.0).unwrap();
+
+        assert_eq!(budget_system.top_up_epoch_reward("Test Epoch", 50.0, "ETH").unwrap_err().to_string(), "Only closed epochs can be topped up");
+        budget_system.close_epoch(None, None).unwrap();
+
+        let before: HashMap<Uuid, f64> = budget_system.get_epoch(&epoch_id).unwrap().team_rewards().iter()
+            .map(|(team_id, reward)| (*team_id, reward.amount()))
+            .collect();
+        assert!(budget_system.top_up_epoch_reward("Test Epoch", 50.0, "USDC").is_err());
+
+        let output = budget_system.execute_command(Command::TopUpReward {
+            epoch_name: "Test Epoch".to_string(),
+            amount: 50.0,
+            token: "ETH".to_string(),
+        }).await.unwrap();
+        assert!(output.contains("Team A: +"));
+
+        let epoch = budget_system.get_epoch(&epoch_id).unwrap();
+        assert_eq!(epoch.total_reward_amount(), 150.0);
+        for (team_id, reward) in epoch.team_rewards() {
+            assert!((reward.amount() - before[team_id] * 1.5).abs() < 1e-9);
+        }
+        assert!(epoch.team_rewards()[&team_a].amount() > before[&team_a]);
+    }
+
+    #[tokio::test]
+    async fn test_team_status_at_replays_changes() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        let team_id = budget_system.create_team("Team A".to_string(), "Rep".to_string(), Some(vec![1000.0]), None).unwrap();
+        tokio::time::sleep(Dur::from_millis(5)).await;
+        let as_earner = Utc::now();
+        tokio::time::sleep(Dur::from_millis(5)).await;
+
+        budget_system.update_team(team_id, UpdateTeamDetails {
+            name: None,
+            representative: None,
+            status: Some("Inactive".to_string()),
+            trailing_monthly_revenue: None,
+            address: None,
+            contacts: None,
+        }).unwrap();
+
+        assert!(matches!(budget_system.team_status_at(team_id, as_earner), Some(TeamStatus::Earner { .. })));
+        assert_eq!(budget_system.team_status_at(team_id, Utc::now()), Some(TeamStatus::Inactive));
+
+        // The log is persisted with the state
+        let saved = FileSystem::load_state(&state_file).unwrap();
+        assert_eq!(saved.history().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_epoch_amendment_log() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        let epoch_id = create_active_epoch(&mut budget_system).await;
+        for title in ["First", "Second", "Untouched"] {
+            budget_system.add_proposal(title.to_string(), None, None, None, None, None).unwrap();
+        }
+
+        let url_update = |url: &str| UpdateProposalDetails {
+            title: None,
+            url: Some(url.to_string()),
+            budget_request_details: None,
+            announced_at: None,
+            published_at: None,
+            resolved_at: None,
+            tags: None,
+            decision_deadline: None,
+            depends_on: None,
+        };
+        budget_system.update_proposal("Second", url_update("https://example.com/second")).unwrap();
+        tokio::time::sleep(Dur::from_millis(5)).await;
+        budget_system.update_proposal("First", url_update("https://example.com/first")).unwrap();
+        tokio::time::sleep(Dur::from_millis(5)).await;
+        budget_system.update_proposal("Second", url_update("https://example.com/second-v2")).unwrap();
+
+        let log = budget_system.epoch_amendment_log(epoch_id);
+        let titles: Vec<_> = log.iter().map(|(title, _)| title.as_str()).collect();
+        assert_eq!(titles, vec!["Second", "First"]);
+        assert_eq!(log[0].1.len(), 2);
+
+        let output = budget_system.execute_command(Command::EpochAmendmentLog { epoch_name: None }).await.unwrap();
+        let first = output.find("'https://example.com/first'").unwrap();
+        let second = output.find("to 'https://example.com/second'").unwrap();
+        let second_v2 = output.find("'https://example.com/second-v2'").unwrap();
+        assert!(second < first && first < second_v2);
+        assert!(!output.contains("Untouched"));
+    }
+
+    #[tokio::test]
+    async fn test_proposal_amendments_in_report() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        create_active_epoch(&mut budget_system).await;
+        let team_id = budget_system.create_team("Team A".to_string(), "Rep".to_string(), None, None).unwrap();
+        let details = BudgetRequestDetails::new(Some(team_id), [("ETH".to_string(), 100.0)].into_iter().collect(), None, None, None, None).unwrap();
+        let proposal_id = budget_system.add_proposal("Funded".to_string(), None, Some(details), None, None, None).unwrap();
+        assert!(budget_system.get_proposal_amendments("Funded").is_empty());
+
+        budget_system.update_proposal("Funded", UpdateProposalDetails {
+            title: None,
+            url: None,
+            budget_request_details: Some(BudgetRequestDetailsCommand {
+                team: None,
+                request_amounts: Some([("ETH".to_string(), 150.0)].into_iter().collect()),
+                start_date: Some(NaiveDate::from_ymd_opt(2024, 2, 1).unwrap()),
+                end_date: None,
+                is_loan: None,
+                payment_address: None,
+                monthly_breakdown: None,
+            }),
+            announced_at: None,
+            published_at: None,
+            resolved_at: None,
+            tags: None,
+            decision_deadline: None,
+            depends_on: None,
+        }).unwrap();
+
+        let fields: Vec<&str> = budget_system.get_proposal_amendments("Funded").iter().map(|a| a.field.as_str()).collect();
+        assert_eq!(fields, vec!["request_amounts", "start_date"]);
+        assert!(budget_system.get_proposal_amendments("Missing").is_empty());
+
+        let report = budget_system.generate_proposal_report(proposal_id).unwrap();
+        assert!(report.contains("## Amendments"));
+        assert!(report.contains("request_amounts: ETH 100 -> 150"));
+        assert!(report.contains("start_date set to 2024-02-01"));
+    }
+
+    #[tokio::test]
+    async fn test_epoch_budget_cap() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        create_active_epoch(&mut budget_system).await;
+        budget_system.execute_command(Command::SetEpochBudgetCap {
+            epoch_name: None,
+            token: "USD".to_string(),
+            amount: 60000.0,
+        }).await.unwrap();
+
+        let mut proposal_ids = Vec::new();
+        for (title, amount) in [("Big", 45000.0), ("Too Big", 20000.0), ("Small", 15000.0)] {
+            let details = BudgetRequestDetails::new(
+                None,
+                [("USD".to_string(), amount)].into_iter().collect(),
+                None,
+                None,
+                None,
+                None,
+            ).unwrap();
+            proposal_ids.push(budget_system.add_proposal(title.to_string(), None, Some(details), None, None, None).unwrap());
+        }
+
+        budget_system.close_with_reason(proposal_ids[0], &Resolution::Approved).unwrap();
+        assert_eq!(
+            budget_system.close_with_reason(proposal_ids[1], &Resolution::Approved),
+            Err("Approving this proposal would exceed the epoch budget cap")
+        );
+        assert!(budget_system.get_proposal(&proposal_ids[1]).unwrap().is_open());
+        // Rejections are not capped, and the remaining budget still fits an exact match
+        budget_system.close_with_reason(proposal_ids[1], &Resolution::Rejected).unwrap();
+        budget_system.close_with_reason(proposal_ids[2], &Resolution::Approved).unwrap();
+
+        let state = budget_system.print_epoch_state().unwrap();
+        assert!(state.contains("Budget: `60000/60000 USD committed`"));
+
+        assert!(budget_system.to_script().iter().any(|command| matches!(command,
+            Command::SetEpochBudgetCap { token, amount, .. } if token == "USD" && *amount == 60000.0)));
+    }
+
+    #[tokio::test]
+    async fn test_budget_cap_applies_to_vote_approvals() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+        budget_system.config.default_qualified_majority_threshold = 0.1;
+
+        create_active_epoch(&mut budget_system).await;
+        budget_system.set_epoch_budget_cap(None, "USD", 100.0).unwrap();
+        let team_id = budget_system.create_team("Team A".to_string(), "Rep".to_string(), Some(vec![1000.0]), None).unwrap();
+        let details = BudgetRequestDetails::new(None, [("USD".to_string(), 150.0)].into_iter().collect(), None, None, None, None).unwrap();
+        let today = Utc::now().date_naive();
+        let proposal_id = budget_system.add_proposal("Capped".to_string(), None, Some(details), Some(today), Some(today), None).unwrap();
+        let config = budget_system.config().clone();
+        let (raffle_id, _) = budget_system.prepare_raffle("Capped", None, &config).unwrap();
+        budget_system.finalize_raffle(raffle_id, 12345, 12355, "mock_randomness".to_string()).await.unwrap();
+
+        let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
+        budget_system.cast_votes(vote_id, vec![(team_id, VoteChoice::Yes)]).unwrap();
+
+        // A passing vote cannot approve past the cap, and leaves the vote open
+        let error = budget_system.close_vote_and_update_proposal(vote_id, proposal_id, None).unwrap_err();
+        assert_eq!(error.to_string(), "Approving this proposal would exceed the epoch budget cap");
+        assert!(!budget_system.state.get_vote(&vote_id).unwrap().is_closed());
+        assert!(budget_system.get_proposal(&proposal_id).unwrap().is_open());
+
+        let error = budget_system.import_historical_vote("Capped", true, vec!["Team A".to_string()], Vec::new(), Vec::new(), None, None).unwrap_err();
+        assert_eq!(error.to_string(), "Approving this proposal would exceed the epoch budget cap");
+
+        budget_system.set_epoch_budget_cap(None, "USD", 150.0).unwrap();
+        assert!(budget_system.close_vote_and_update_proposal(vote_id, proposal_id, None).unwrap());
+        assert!(budget_system.get_proposal(&proposal_id).unwrap().is_approved());
+    }
+
+    #[tokio::test]
+    async fn test_query_proposals() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        let epoch_id = create_active_epoch(&mut budget_system).await;
+        let team_id = budget_system.create_team("Team A".to_string(), "Rep".to_string(), None, None).unwrap();
+        let date = |day| NaiveDate::from_ymd_opt(2024, 1, day).unwrap();
+
+        let details = BudgetRequestDetails::new(Some(team_id), [("ETH".to_string(), 10.0)].into_iter().collect(), None, None, None, None).unwrap();
+        let funded = budget_system.add_proposal("Funded".to_string(), None, Some(details), Some(date(5)), None, None).unwrap();
+        let early = budget_system.add_proposal("Early".to_string(), None, None, Some(date(1)), None, None).unwrap();
+        budget_system.add_proposal("Undated".to_string(), None, None, None, None, None).unwrap();
+        budget_system.close_with_reason(funded, &Resolution::Approved).unwrap();
+        budget_system.set_proposal_tags(early, vec!["infra".to_string()]).unwrap();
+
+        let titles = |filter: ProposalFilter| -> Vec<String> {
+            budget_system.query_proposals(filter).iter().map(|p| p.title().to_string()).collect()
+        };
+
+        assert_eq!(titles(ProposalFilter::default()), vec!["Undated", "Early", "Funded"]);
+        assert_eq!(titles(ProposalFilter { epoch_id: Some(epoch_id), status: Some("approved".to_string()), ..Default::default() }), vec!["Funded"]);
+        assert_eq!(titles(ProposalFilter { status: Some("open".to_string()), ..Default::default() }), vec!["Undated", "Early"]);
+        assert_eq!(titles(ProposalFilter { team_name: Some("Team A".to_string()), ..Default::default() }), vec!["Funded"]);
+        assert!(titles(ProposalFilter { team_name: Some("Missing".to_string()), ..Default::default() }).is_empty());
+        assert_eq!(titles(ProposalFilter { announced_after: Some(date(2)), ..Default::default() }), vec!["Funded"]);
+        assert_eq!(titles(ProposalFilter { announced_before: Some(date(5)), tag: Some("infra".to_string()), ..Default::default() }), vec!["Early"]);
+        assert!(titles(ProposalFilter { epoch_id: Some(Uuid::new_v4()), ..Default::default() }).is_empty());
+
+        let output = budget_system.execute_command(Command::ListProposals {
+            epoch_name: None,
+            status: Some("approved".to_string()),
+            team_name: None,
+            announced_after: None,
+            announced_before: None,
+            tag: None,
+        }).await.unwrap();
+        assert!(output.contains("| Funded | Test Epoch | Approved | Team A | 10 ETH | 2024-01-05 |"));
+    }
+
+    #[tokio::test]
+    async fn test_simulate_threshold_flips_outcomes() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        let epoch_id = create_active_epoch(&mut budget_system).await;
+        let teams: Vec<Uuid> = (1..=5)
+            .map(|i| budget_system.create_team(format!("Team {}", i), "Rep".to_string(), None, None).unwrap())
+            .collect();
+
+        // 5 and 2 yes votes of 7 seats, plus a vote nobody cast
+        for (name, yes_votes) in [("Broad", 5), ("Narrow", 2), ("Silent", 0)] {
+            let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, name).await;
+            let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
+            if yes_votes > 0 {
+                budget_system.cast_votes(vote_id, teams[..yes_votes].iter().map(|id| (*id, VoteChoice::Yes)).collect()).unwrap();
+            }
+            budget_system.close_vote(vote_id).unwrap();
+        }
+
+        assert_eq!(budget_system.simulate_threshold(epoch_id, 0.8), vec![
+            ("Broad".to_string(), true, false),
+            ("Narrow".to_string(), false, false),
+        ]);
+        assert_eq!(budget_system.simulate_threshold(epoch_id, 0.25), vec![
+            ("Broad".to_string(), true, true),
+            ("Narrow".to_string(), false, true),
+        ]);
+
+        let output = budget_system.execute_command(Command::SimulateThreshold { epoch_name: None, threshold: 0.8 }).await.unwrap();
+        assert!(output.contains("1 of 2 formal votes would flip"));
+        assert!(output.contains("- Broad: Passed -> Failed (flipped)"));
+        assert!(output.contains("Skipped without recorded counts: Silent"));
+        assert!(budget_system.format_threshold_simulation(None, 1.5).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_idempotent_script_upserts_by_name() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+        create_active_epoch(&mut budget_system).await;
+
+        let script = |representative: &str, url: &str| vec![
+            Command::AddTeam {
+                name: "Team A".to_string(),
+                representative: representative.to_string(),
+                trailing_monthly_revenue: Some(vec![1000.0]),
+                address: None,
+                contacts: Vec::new(),
             },
-            Command::CloseProposal { proposal_name, resolution } => {
-                let proposal_id = self.get_proposal_id_by_name(&proposal_name)
-                    .ok_or_else(|| format!("Proposal not found: {}", proposal_name))?;
-                let resolution = match resolution.to_lowercase().as_str() {
-                    "approved" => Resolution::Approved,
-                    "rejected" => Resolution::Rejected,
-                    "invalid" => Resolution::Invalid,
-                    "duplicate" => Resolution::Duplicate,
-                    "retracted" => Resolution::Retracted,
-                    _ => return Err(format!("Invalid resolution type: {}", resolution).into()),
-                };
-                self.close_with_reason(proposal_id, &resolution)?;
-                Ok(format!("Closed proposal '{}' with resolution: {:?}", proposal_name, resolution))
+            Command::AddProposal {
+                title: "Proposal A".to_string(),
+                url: Some(url.to_string()),
+                budget_request_details: None,
+                announced_at: None,
+                published_at: None,
+                is_historical: None,
+                tags: vec!["infra".to_string()],
+                is_draft: false,
+                kind: None,
+                depends_on: Vec::new(),
             },
-            // Command::CreateRaffle { proposal_name, block_offset, excluded_teams } => {
-            //     let mut output = Vec::new();
-            //     self.handle_create_raffle(proposal_name, block_offset, excluded_teams, &mut output).await?;
-            //     Ok(String::from_utf8(output)?)
-            // },
-            Command::CreateRaffle { proposal_name, block_offset, excluded_teams } => {
-                let progress_stream = self.create_raffle_with_progress(
-                    proposal_name,
-                    block_offset,
-                    excluded_teams,
-                ).await;
+        ];
 
-                let mut output = String::new();
-                pin_mut!(progress_stream);
-                
-                while let Some(progress) = progress_stream.next().await {
-                    match progress {
-                        Ok(progress) => {
-                            output.push_str(&format!("{}\n", progress.format_message()));
-                            if progress.is_complete() {
-                                break;
-                            }
-                        },
-                        Err(e) => return Err(Box::new(std::io::Error::new(std::io::ErrorKind::Other, e.0))),
-                    }
-                }
-                
-                Ok(output)
+        budget_system.set_idempotent(true);
+        for command in script("Rep A", "https://example.com/a") {
+            budget_system.execute_command(command).await.unwrap();
+        }
+        let mut outputs = Vec::new();
+        for command in script("Rep B", "https://example.com/b") {
+            outputs.push(budget_system.execute_command(command).await.unwrap());
+        }
+        assert!(outputs[0].starts_with("Updated team: Team A"));
+        assert!(outputs[1].starts_with("Updated proposal: Proposal A"));
+
+        assert_eq!(budget_system.state().current_state().teams().len(), 1);
+        assert_eq!(budget_system.state().proposals().len(), 1);
+        let team_id = budget_system.get_team_id_by_name("Team A").unwrap();
+        assert_eq!(budget_system.state().get_team(&team_id).unwrap().representative(), "Rep B");
+        let proposal_id = budget_system.get_proposal_id_by_name("Proposal A").unwrap();
+        assert_eq!(budget_system.state().get_proposal(&proposal_id).unwrap().url(), Some("https://example.com/b"));
+
+        // Without the mode a re-run adds duplicates, as before
+        budget_system.set_idempotent(false);
+        budget_system.execute_command(script("Rep B", "").remove(0)).await.unwrap();
+        assert_eq!(budget_system.state().current_state().teams().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_sandbox_run_reports_failing_command() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+        let saved_before = std::fs::read_to_string(&state_file).ok();
+        let audit_log = temp_dir.path().join("audit.log");
+        budget_system.config.audit_log = audit_log.to_str().unwrap().to_string();
+
+        let add_team = |name: &str| Command::AddTeam {
+            name: name.to_string(),
+            representative: "Rep".to_string(),
+            trailing_monthly_revenue: None,
+            address: None,
+            contacts: Vec::new(),
+        };
+        let commands = vec![
+            add_team("Team A"),
+            Command::ActivateEpoch { name: "Missing".to_string() },
+            add_team("Team B"),
+            // Fails only if the sandbox kept Team A from the first command
+            Command::RenameTeam { old_name: "Team A".to_string(), new_name: "Team C".to_string() },
+        ];
+
+        let results = budget_system.sandbox_run(&commands).await;
+        assert_eq!(results.len(), 4);
+        for (index, result) in &results {
+            assert_eq!(result.is_err(), *index == 1, "unexpected result for command {}: {:?}", index, result);
+        }
+
+        assert!(budget_system.state().current_state().teams().is_empty());
+        assert_eq!(std::fs::read_to_string(&state_file).ok(), saved_before);
+        assert!(!audit_log.exists());
+    }
+
+    #[tokio::test]
+    async fn test_epoch_funnel() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        let epoch_id = create_active_epoch(&mut budget_system).await;
+        let team_id = budget_system.create_team("Team A".to_string(), "Rep".to_string(), None, None).unwrap();
+
+        budget_system.add_proposal("Submitted Only".to_string(), None, None, None, None, None).unwrap();
+        create_proposal_with_raffle(&mut budget_system, "Raffled").await;
+        for (name, approve) in [("Voted", false), ("Approved", true)] {
+            let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, name).await;
+            let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
+            budget_system.cast_votes(vote_id, vec![(team_id, VoteChoice::Yes)]).unwrap();
+            budget_system.close_vote(vote_id).unwrap();
+            if approve {
+                budget_system.state.get_proposal_mut(&proposal_id).unwrap().approve().unwrap();
+            }
+        }
+
+        let funnel = budget_system.epoch_funnel(epoch_id);
+        assert_eq!(funnel, Funnel { submitted: 4, raffled: 3, voted: 2, approved: 1 });
+
+        let output = budget_system.execute_command(Command::EpochFunnel { epoch_name: None }).await.unwrap();
+        assert!(output.contains("Submitted: 4"));
+        assert!(output.contains("Raffled: 3 (25.0% drop-off)"));
+        assert!(output.contains("Approved: 1 (50.0% drop-off)"));
+    }
+
+    #[tokio::test]
+    async fn test_milestone_payments() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        create_active_epoch(&mut budget_system).await;
+        let details = BudgetRequestDetails::new(None, [("ETH".to_string(), 100.0)].into_iter().collect(), None, None, None, None).unwrap();
+        let proposal_id = budget_system.add_proposal("Grant".to_string(), None, Some(details), None, None, None).unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+
+        assert!(budget_system.add_milestone_payment("Grant", 40.0, "ETH", date, None).is_err(), "not approved yet");
+        budget_system.close_with_reason(proposal_id, &Resolution::Approved).unwrap();
+
+        let output = budget_system.execute_command(Command::AddMilestonePayment {
+            proposal_name: "Grant".to_string(),
+            amount: 40.0,
+            token: "ETH".to_string(),
+            date,
+            tx: None,
+        }).await.unwrap();
+        assert!(output.contains("partially paid"));
+        assert_eq!(budget_system.approved_unpaid_proposals().len(), 1);
+
+        let report = budget_system.generate_proposal_report(proposal_id).unwrap();
+        assert!(report.contains("- **Payment Status**: partially paid"));
+        assert!(report.contains("| 2024-03-01 | 40 | ETH | N/A |"));
+
+        budget_system.add_milestone_payment("Grant", 60.0, "ETH", date, None).unwrap();
+        assert!(budget_system.get_proposal(&proposal_id).unwrap().budget_request_details().unwrap().is_paid());
+        assert!(budget_system.approved_unpaid_proposals().is_empty());
+        assert!(budget_system.add_milestone_payment("Grant", 1.0, "ETH", date, None).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_redraw_raffle_supersedes_previous() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        create_active_epoch(&mut budget_system).await;
+        let team_a = budget_system.create_team("Team A".to_string(), "Rep A".to_string(), None, None).unwrap();
+        let team_b = budget_system.create_team("Team B".to_string(), "Rep B".to_string(), None, None).unwrap();
+        let (proposal_id, old_raffle_id) = create_proposal_with_raffle(&mut budget_system, "Redrawn").await;
+        let vote_id = budget_system.create_formal_vote(proposal_id, old_raffle_id, None).unwrap();
+        budget_system.cast_votes(vote_id, vec![(team_a, VoteChoice::Yes), (team_b, VoteChoice::Yes)]).unwrap();
+
+        // Team B should not have been in the roster
+        budget_system.state.get_team_mut(&team_b).unwrap().set_status(TeamStatus::Inactive).unwrap();
+        let output = budget_system.execute_command(Command::RedrawRaffle {
+            proposal_name: "Redrawn".to_string(),
+            randomness_block: 500,
+        }).await.unwrap();
+        assert!(output.contains(&format!("supersedes {}", old_raffle_id)));
+
+        let new_raffle = budget_system.active_raffle_for_proposal(proposal_id).unwrap();
+        let new_raffle_id = new_raffle.id();
+        assert_ne!(new_raffle_id, old_raffle_id);
+        assert_eq!(new_raffle.supersedes(), Some(old_raffle_id));
+        assert_eq!(new_raffle.config().block_randomness(), "mock_randomness_for_block_500");
+        assert_eq!(budget_system.get_raffle(&old_raffle_id).unwrap().superseded_by(), Some(new_raffle_id));
+        assert_eq!(budget_system.find_proposal_and_raffle("Redrawn").unwrap(), (proposal_id, new_raffle_id));
+
+        // The open vote follows the new raffle and loses the vote of the team that dropped out
+        let vote = budget_system.get_vote(&vote_id).unwrap();
+        assert_eq!(vote.raffle_id(), Some(new_raffle_id));
+        assert!(!vote.is_provisional());
+        assert!(matches!(vote.participation(), VoteParticipation::Formal { counted, .. } if counted == &vec![team_a]));
+
+        let report = budget_system.generate_proposal_report(proposal_id).unwrap();
+        assert!(report.contains(&format!("- **Raffle ID**: {}", new_raffle_id)));
+        assert!(report.contains(&format!("- **Supersedes**: {} (randomness block 12355, superseded by {})", old_raffle_id, new_raffle_id)));
+
+        budget_system.close_vote(vote_id).unwrap();
+        assert!(budget_system.redraw_raffle("Redrawn", 600).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rebind_provisional_vote_to_new_raffle() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        create_active_epoch(&mut budget_system).await;
+        let team_a = budget_system.create_team("Team A".to_string(), "Rep A".to_string(), None, None).unwrap();
+        let team_b = budget_system.create_team("Team B".to_string(), "Rep B".to_string(), None, None).unwrap();
+
+        let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, "Redrawn Proposal").await;
+        let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
+        budget_system.cast_votes(vote_id, vec![(team_a, VoteChoice::Yes), (team_b, VoteChoice::No)]).unwrap();
+        budget_system.mark_vote_provisional(vote_id).unwrap();
+        assert!(budget_system.close_vote(vote_id).is_err());
+
+        // Re-draw without Team B; an excluded team still holds an uncounted seat, so it leaves the roster instead
+        budget_system.state.get_team_mut(&team_b).unwrap().set_status(TeamStatus::Inactive).unwrap();
+        let config = budget_system.config().clone();
+        let (new_raffle_id, _) = budget_system.prepare_raffle("Redrawn Proposal", None, &config).unwrap();
+        budget_system.finalize_raffle(new_raffle_id, 12345, 12355, "mock_randomness".to_string()).await.unwrap();
+
+        let dropped = budget_system.rebind_vote_to_raffle(vote_id, new_raffle_id).unwrap();
+        assert_eq!(dropped, vec!["Team B".to_string()]);
+
+        let vote = budget_system.state().get_vote(&vote_id).unwrap();
+        assert!(!vote.is_provisional());
+        assert!(matches!(vote.vote_type(), VoteType::Formal { raffle_id, .. } if *raffle_id == new_raffle_id));
+        match vote.participation() {
+            VoteParticipation::Formal { counted, uncounted } => {
+                assert!(counted.contains(&team_a) || uncounted.contains(&team_a));
+                assert!(!counted.contains(&team_b) && !uncounted.contains(&team_b));
             },
-            Command::CreateAndProcessVote { proposal_name, counted_votes, uncounted_votes, vote_opened, vote_closed } => {
-                let mut output = format!("Executing CreateAndProcessVote command for proposal: {}\n", proposal_name);
-                
-                match self.create_and_process_vote(
-                    &proposal_name,
-                    counted_votes,
-                    uncounted_votes,
-                    vote_opened,
-                    vote_closed
-                ) {
-                    Ok(report) => {
-                        output += &format!("Vote processed successfully for proposal: {}\n", proposal_name);
-                        output += &format!("Vote report:\n{}\n", report);
-                    
-                        // Print point credits
-                        if let Some(vote_id) = self.state().votes().values()
-                            .find(|v| v.proposal_id() == self.get_proposal_id_by_name(&proposal_name).unwrap())
-                            .map(|v| v.id())
-                        {
-                            let vote = self.state().votes().get(&vote_id).unwrap();
-                            
-                            output += "\nPoints credited:\n";
-                            if let VoteParticipation::Formal { counted, uncounted } = &vote.participation() {
-                                for &team_id in counted {
-                                    if let Some(team) = self.state().current_state().teams().get(&team_id) {
-                                        output += &format!("  {} (+{} points)\n", team.name(), self.config.counted_vote_points);
-                                    }
-                                }
-                                for &team_id in uncounted {
-                                    if let Some(team) = self.state().current_state().teams().get(&team_id) {
-                                        output += &format!("  {} (+{} points)\n", team.name(), self.config.uncounted_vote_points);
-                                    }
-                                }
-                            }
-                        } else {
-                            output += "Warning: Vote not found after processing\n";
-                        }
-                    },
-                    Err(e) => {
-                        output += &format!("Error: Failed to process vote for proposal '{}'. Reason: {}\n", proposal_name, e);
-                    }
-                }
+            _ => panic!("Expected formal participation"),
+        }
+
+        budget_system.close_vote(vote_id).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_create_raffle_with_seed() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        create_active_epoch(&mut budget_system).await;
+        for name in ["Team A", "Team B", "Team C"] {
+            budget_system.create_team(name.to_string(), "Rep".to_string(), None, None).unwrap();
+        }
+        for title in ["First Proposal", "Second Proposal"] {
+            budget_system.add_proposal(title.to_string(), None, None, None, None, None).unwrap();
+        }
+
+        let output = budget_system.execute_command(Command::CreateRaffleWithSeed {
+            proposal_name: "First Proposal".to_string(),
+            seed: "local-seed".to_string(),
+        }).await.unwrap();
+        assert!(output.contains("Randomness: local-seed (seed-based, not block-derived)"));
+
+        let (_, first) = budget_system.create_seeded_raffle("Second Proposal", "local-seed").unwrap();
+        let (_, first_raffle_id) = budget_system.find_proposal_and_raffle("First Proposal").unwrap();
+        let stored = budget_system.state().get_raffle(&first_raffle_id).unwrap();
+        let scores = |raffle: &Raffle| raffle.tickets().iter().map(|t| t.score()).collect::<Vec<f64>>();
+        assert_eq!(scores(stored), scores(&first));
+        assert!(budget_system.placeholder_raffles().is_empty());
+
+        let report = budget_system.generate_proposal_report(budget_system.get_proposal_id_by_name("First Proposal").unwrap()).unwrap();
+        assert!(report.contains("- **Randomness Source**: seed-based, not block-derived"));
+
+        assert!(budget_system.create_seeded_raffle("Second Proposal", " ").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_projected_epoch_completion() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+        create_active_epoch(&mut budget_system).await;
+
+        let today = Utc::now().date_naive();
+        assert_eq!(budget_system.projected_epoch_completion(), Some(today));
+
+        // One resolution every two days
+        for (i, days_ago) in [6, 4, 2].iter().enumerate() {
+            let proposal_id = budget_system.add_proposal(format!("Resolved {}", i), None, None, None, None, None).unwrap();
+            let proposal = budget_system.state.get_proposal_mut(&proposal_id).unwrap();
+            proposal.approve().unwrap();
+            proposal.set_resolved_at(Some(today - chrono::Duration::days(*days_ago)));
+        }
+
+        budget_system.add_proposal("Open 1".to_string(), None, None, None, None, None).unwrap();
+        assert_eq!(budget_system.projected_epoch_completion(), Some(today + chrono::Duration::days(2)));
+
+        for i in 2..=3 {
+            budget_system.add_proposal(format!("Open {}", i), None, None, None, None, None).unwrap();
+        }
+        assert_eq!(budget_system.projected_epoch_completion(), Some(today + chrono::Duration::days(6)));
+
+        let output = budget_system.execute_command(Command::ProjectedCompletion).await.unwrap();
+        assert!(output.contains(&(today + chrono::Duration::days(6)).format("%Y-%m-%d").to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_close_vote_emits_event() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+        create_active_epoch(&mut budget_system).await;
+        let team_id = budget_system.create_team("Team A".to_string(), "Rep A".to_string(), None, None).unwrap();
+
+        // Closing without a subscriber is unaffected
+        let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, "Silent Proposal").await;
+        let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
+        budget_system.close_vote(vote_id).unwrap();
+
+        let (sender, mut receiver) = mpsc::channel(10);
+        budget_system.set_event_sender(Some(sender));
+
+        let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, "Announced Proposal").await;
+        let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
+        budget_system.cast_votes(vote_id, vec![(team_id, VoteChoice::Yes)]).unwrap();
+        let passed = budget_system.close_vote_and_update_proposal(vote_id, proposal_id, None).unwrap();
+
+        assert_eq!(receiver.try_recv().unwrap(), SystemEvent::VoteClosed { vote_id, passed });
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_remove_proposal() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+        let epoch_id = create_active_epoch(&mut budget_system).await;
+        budget_system.create_team("Team A".to_string(), "Rep A".to_string(), None, None).unwrap();
+
+        let mistake_id = budget_system.add_proposal("Mistake".to_string(), None, None, None, None, None).unwrap();
+        assert!(budget_system.state().get_epoch(&epoch_id).unwrap().associated_proposals().contains(&mistake_id));
+
+        budget_system.execute_command(Command::RemoveProposal { proposal_name: "Mistake".to_string() }).await.unwrap();
+        assert!(budget_system.state().get_proposal(&mistake_id).is_none());
+        assert!(!budget_system.state().get_epoch(&epoch_id).unwrap().associated_proposals().contains(&mistake_id));
+        assert!(budget_system.get_proposals_for_epoch(epoch_id).is_empty());
+        assert_eq!(budget_system.remove_proposal("Mistake").unwrap_err().to_string(), "Proposal not found: Mistake");
+        assert!(budget_system.remove_proposal("Mistake").unwrap_err().is::<NotFoundError>());
+
+        // Referenced by a raffle
+        create_proposal_with_raffle(&mut budget_system, "Raffled").await;
+        assert_eq!(budget_system.remove_proposal("Raffled").unwrap_err().to_string(), "Cannot remove a proposal referenced by a raffle");
+
+        // Closed
+        let closed_id = budget_system.add_proposal("Closed".to_string(), None, None, None, None, None).unwrap();
+        budget_system.state.get_proposal_mut(&closed_id).unwrap().reject().unwrap();
+        assert_eq!(budget_system.remove_proposal("Closed").unwrap_err().to_string(), "Cannot remove a closed proposal");
+
+        // Referenced by a vote, without a raffle
+        let voted_id = budget_system.add_proposal("Voted".to_string(), None, None, None, None, None).unwrap();
+        budget_system.create_informal_vote(voted_id).unwrap();
+        assert_eq!(budget_system.remove_proposal("Voted").unwrap_err().to_string(), "Cannot remove a proposal referenced by a vote");
+    }
+
+    #[tokio::test]
+    async fn test_find_reward_inconsistencies() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        let epoch_id = create_active_epoch(&mut budget_system).await;
+        let team_a = budget_system.create_team("Team A".to_string(), "Rep A".to_string(), None, None).unwrap();
+        let team_b = budget_system.create_team("Team B".to_string(), "Rep B".to_string(), None, None).unwrap();
+        let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, "Test Proposal").await;
+        let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
+        budget_system.cast_votes(vote_id, vec![(team_a, VoteChoice::Yes), (team_b, VoteChoice::No)]).unwrap();
+        budget_system.close_vote_and_update_proposal(vote_id, proposal_id, None).unwrap();
+        budget_system.set_epoch_reward("ETH", 100.0).unwrap();
+        budget_system.close_epoch(None, None).unwrap();
+        assert!(budget_system.find_reward_inconsistencies().is_empty());
+
+        let percentage = budget_system.get_epoch(&epoch_id).unwrap().team_rewards()[&team_a].percentage();
+        let expected = 100.0 * percentage / 100.0;
+        budget_system.state.get_epoch_mut(&epoch_id).unwrap()
+            .set_team_reward(team_a, percentage, expected + 5.0).unwrap();
+
+        let found = budget_system.find_reward_inconsistencies();
+        assert_eq!(found.len(), 1);
+        let (epoch_name, team_name, stored, computed) = &found[0];
+        assert_eq!(epoch_name, "Test Epoch");
+        assert_eq!(team_name, "Team A");
+        assert!((stored - (expected + 5.0)).abs() < 1e-9);
+        assert!((computed - expected).abs() < 1e-9);
+        assert_eq!(budget_system.action_items().reward_inconsistencies[0].entity_id, epoch_id);
+    }
 
-                Ok(output)
-            },
-            Command::GenerateReportsForClosedProposals { epoch_name } => {
-                let epoch_id = self.get_epoch_id_by_name(&epoch_name)
-                    .ok_or_else(|| format!("Epoch not found: {}", epoch_name))?;
-                
-                let closed_proposals: Vec<_> = self.get_proposals_for_epoch(epoch_id)
-                    .into_iter()
-                    .filter(|p| p.is_closed())
-                    .collect();
+    #[tokio::test]
+    async fn test_set_representatives() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+        let team_id = budget_system.create_team("Team A".to_string(), "Old Rep".to_string(), None, None).unwrap();
 
-                let mut report = String::new();
-                for proposal in closed_proposals {
-                    match self.generate_and_save_proposal_report(proposal.id(), &epoch_name) {
-                        Ok(file_path) => report.push_str(&format!("Report generated for proposal '{}' at {:?}\n", proposal.title(), file_path)),
-                        Err(e) => report.push_str(&format!("Failed to generate report for proposal '{}': {}\n", proposal.title(), e)),
-                    }
-                }
-                Ok(report)
-            },
-            Command::GenerateReportForProposal { proposal_name } => {
-                let current_epoch = self.get_current_epoch()
-                    .ok_or("No active epoch")?;
-                
-                let proposal = self.get_proposals_for_epoch(current_epoch.id())
-                    .into_iter()
-                    .find(|p| p.name_matches(&proposal_name))
-                    .ok_or_else(|| format!("Proposal not found in current epoch: {}", proposal_name))?;
+        let rep = |name: &str, role: &str| Representative { name: name.to_string(), role: role.to_string(), handle: None };
+        budget_system.execute_command(Command::SetRepresentatives {
+            team_name: "Team A".to_string(),
+            representatives: vec![rep("Bob", "treasurer"), rep("Alice", "lead"), rep("Carol", "backup")],
+        }).await.unwrap();
 
-                match self.generate_and_save_proposal_report(proposal.id(), &current_epoch.name()) {
-                    Ok(file_path) => Ok(format!("Report generated for proposal '{}' at {:?}", proposal.title(), file_path)),
-                    Err(e) => Err(format!("Failed to generate report for proposal '{}': {}", proposal.title(), e).into()),
-                }
-            },
-            Command::PrintPointReport { epoch_name } => {
-                self.generate_point_report(epoch_name.as_deref())
-                    .map_err(|e| Box::new(BudgetSystemError(e.to_string())) as Box<dyn Error>)
-            },
-            Command::CloseEpoch { epoch_name } => {
-                self.close_epoch(epoch_name.as_deref())?;
-                Ok(format!("Successfully closed epoch: {}", epoch_name.unwrap_or_else(|| "Active epoch".to_string())))
-            },
-            Command::GenerateEndOfEpochReport { epoch_name } => {
-                self.generate_end_of_epoch_report(&epoch_name)?;
-                Ok(format!("Generated End of Epoch Report for epoch: {}", epoch_name))
-            },
-            Command::RunScript { .. } => {
-                Err("RunScript command should be handled by the CLI, not the BudgetSystem".into())
-            },
-            Command::GenerateUnpaidRequestsReport { output_path, epoch_name } => {
-                self.generate_unpaid_requests_report(
-                    output_path.as_deref(),
-                    epoch_name.as_deref()
-                ).map(|s| format!("{}\n", s))
-            },
-        }
-    }
+        let team = budget_system.get_team(&team_id).unwrap();
+        assert_eq!(team.representative(), "Alice");
+        assert_eq!(team.representatives().len(), 3);
 
-    async fn execute_command_with_streaming<W: Write + Send + 'static>(
-        &mut self, 
-        command: Command, 
-        output: &mut W
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        match command {
-            Command::CreateRaffle { proposal_name, block_offset, excluded_teams } => {
-                let progress_stream = self.create_raffle_with_progress(
-                    proposal_name,
-                    block_offset,
-                    excluded_teams,
-                ).await;
-                
-                pin_mut!(progress_stream);
-                
-                while let Some(progress) = progress_stream.next().await {
-                    match progress {
-                        Ok(progress) => {
-                            writeln!(output, "{}", progress.format_message())?;
-                            output.flush()?;
-                            if progress.is_complete() {
-                                break;
-                            }
-                        },
-                        Err(e) => return Err(Box::new(std::io::Error::new(
-                            std::io::ErrorKind::Other, 
-                            e.0
-                        ))),
-                    }
-                }
-                Ok(())
-            },
-            // For commands that don't support streaming, fall back to the original implementation
-            _ => {
-                let result = self.execute_command(command).await?;
-                write!(output, "{}", result)?;
-                Ok(())
-            }
-        }
+        let report = budget_system.print_team_report();
+        assert!(report.contains("Representative: Alice"));
+        assert!(report.contains("  treasurer: Bob"));
+
+        assert!(budget_system.set_team_representatives(team_id, vec![rep("Bob", "treasurer")]).is_err());
+        assert_eq!(budget_system.get_team(&team_id).unwrap().representative(), "Alice");
+
+        let script = budget_system.to_script();
+        assert!(script.iter().any(|command| matches!(command, Command::SetRepresentatives { representatives, .. } if representatives.len() == 3)));
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use chrono::{Utc, Duration};
-    use std::sync::Arc;
-    use tempfile::TempDir;
-    use uuid::Uuid;
-    use async_trait::async_trait;
-    use futures::pin_mut;
-    use std::sync::atomic::{AtomicU64, Ordering};
-    use crate::app_config::TelegramConfig;
-    use crate::services::ethereum::MockEthereumService;
-    use tokio::time::Duration as Dur;
+    #[tokio::test]
+    async fn test_team_contacts() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
 
-    // Helpers
+        let telegram: Contact = "telegram:@alice_dev".parse().unwrap();
+        budget_system.execute_command(Command::AddTeam {
+            name: "Team A".to_string(),
+            representative: String::new(),
+            trailing_monthly_revenue: None,
+            address: None,
+            contacts: vec![telegram.clone(), "email:alice@example.com".parse().unwrap()],
+        }).await.unwrap();
 
-    async fn create_test_budget_system(state_file: &str, initial_state: Option<BudgetSystemState>) -> BudgetSystem {
-        let config = AppConfig {
-            state_file: state_file.to_string(),
-            ipc_path: "/tmp/test_reth.ipc".to_string(),
-            future_block_offset: 10,
-            script_file: "test_script.json".to_string(),
-            default_total_counted_seats: 7,
-            default_max_earner_seats: 5,
-            default_qualified_majority_threshold: 0.7,
-            counted_vote_points: 5,
-            uncounted_vote_points: 2,
-            telegram: TelegramConfig {
-                chat_id: "test_chat_id".to_string(),
-                token: "test_token".to_string(),
-            },
+        let team_id = budget_system.get_team_id_by_name("Team A").unwrap();
+        let team = budget_system.get_team(&team_id).unwrap();
+        assert_eq!(team.representative(), "@alice_dev");
+        assert_eq!(team.telegram_handle(), Some("@alice_dev"));
+
+        let report = budget_system.print_team_report();
+        assert!(report.contains("Contacts:\n  telegram: @alice_dev\n  email: alice@example.com"));
+
+        let invalid = Contact { kind: ContactKind::Email, value: "not-an-email".to_string() };
+        let updates = UpdateTeamDetails {
+            name: None,
+            representative: None,
+            status: None,
+            trailing_monthly_revenue: None,
+            address: None,
+            contacts: Some(vec![invalid]),
         };
-        let ethereum_service = Arc::new(MockEthereumService::new());
-        BudgetSystem::new(config, ethereum_service, initial_state).await.unwrap()
+        assert!(budget_system.update_team(team_id, updates).is_err());
+        assert_eq!(budget_system.get_team(&team_id).unwrap().contacts().len(), 2);
+
+        let script = budget_system.to_script();
+        assert!(script.iter().any(|command| matches!(command, Command::AddTeam { contacts, .. } if contacts[0] == telegram)));
     }
 
-    async fn create_active_epoch(budget_system: &mut BudgetSystem) -> Uuid {
-        let start_date = Utc::now();
-        let end_date = start_date + Duration::days(30);
-        let epoch_id = budget_system.create_epoch("Test Epoch", start_date, end_date).unwrap();
-        budget_system.activate_epoch(epoch_id).unwrap();
-        epoch_id
+    #[tokio::test]
+    async fn test_estimate_raffle_odds() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        // 8 earners compete for 5 earner seats: Big has 4 tickets, Small 1, the rest 2 each
+        budget_system.create_team("Big".to_string(), "Rep".to_string(), Some(vec![16000.0, 16000.0, 16000.0]), None).unwrap();
+        budget_system.create_team("Small".to_string(), "Rep".to_string(), Some(vec![1000.0, 1000.0, 1000.0]), None).unwrap();
+        for i in 0..6 {
+            budget_system.create_team(format!("Mid {}", i), "Rep".to_string(), Some(vec![4000.0, 4000.0, 4000.0]), None).unwrap();
+        }
+
+        let big = budget_system.estimate_raffle_odds("Big", None).unwrap();
+        let small = budget_system.estimate_raffle_odds("Small", None).unwrap();
+        assert!(big > 0.0 && big < 1.0);
+        assert!(small > 0.0 && small < 1.0);
+        assert!(big > small);
+
+        // Excluding competitors improves the odds; excluding the team itself zeroes them
+        let fewer_rivals = budget_system.estimate_raffle_odds("Small", Some(vec!["Mid 0".to_string(), "Mid 1".to_string()])).unwrap();
+        assert!(fewer_rivals > small);
+        assert_eq!(budget_system.estimate_raffle_odds("Small", Some(vec!["Small".to_string()])).unwrap(), 0.0);
+
+        assert!(budget_system.state().raffles().is_empty());
+        assert!(budget_system.estimate_raffle_odds("Unknown", None).is_err());
     }
 
-    async fn create_proposal_with_raffle(budget_system: &mut BudgetSystem, proposal_name: &str) -> (Uuid, Uuid) {
-        let proposal_id = budget_system.add_proposal(
-            proposal_name.to_string(),
-            None,
-            None,
-            Some(Utc::now().date_naive()),
-            Some(Utc::now().date_naive()),
-            None
+    #[tokio::test]
+    async fn test_create_and_process_multi_option_vote() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        let epoch_id = create_active_epoch(&mut budget_system).await;
+        let team_a = budget_system.create_team("Team A".to_string(), "Rep A".to_string(), None, None).unwrap();
+        budget_system.create_team("Team B".to_string(), "Rep B".to_string(), None, None).unwrap();
+        budget_system.create_team("Team C".to_string(), "Rep C".to_string(), None, None).unwrap();
+        let (proposal_id, _) = create_proposal_with_raffle(&mut budget_system, "Venue Proposal").await;
+
+        let counted_votes: HashMap<String, VoteChoice> = [
+            ("Team A".to_string(), VoteChoice::Option(1)),
+            ("Team B".to_string(), VoteChoice::Option(1)),
+            ("Team C".to_string(), VoteChoice::Option(0)),
+        ].into_iter().collect();
+
+        let output = budget_system.execute_command(Command::CreateAndProcessMultiOptionVote {
+            proposal_name: "Venue Proposal".to_string(),
+            options: vec!["Lisbon".to_string(), "Berlin".to_string()],
+            counted_votes,
+            uncounted_votes: HashMap::new(),
+            vote_opened: None,
+            vote_closed: None,
+        }).await.unwrap();
+
+        assert!(output.contains("Winner: Berlin"));
+        assert!(output.contains("1. Lisbon: 1 counted, 0 uncounted"));
+        assert!(output.contains("2. Berlin: 2 counted, 0 uncounted"));
+
+        let vote = budget_system.state.votes().values().find(|v| v.proposal_id() == proposal_id).unwrap();
+        assert!(matches!(vote.result(), Some(VoteResult::MultiOption { winner: 1, .. })));
+        assert!(budget_system.get_team_points_for_epoch(team_a, epoch_id).unwrap() > 0);
+
+        // A single option is not a choice
+        let (other_proposal, other_raffle) = create_proposal_with_raffle(&mut budget_system, "Other Proposal").await;
+        assert!(budget_system.create_multi_option_vote(other_proposal, other_raffle, vec!["Only".to_string()]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_close_epoch_carries_over_unallocated_reward() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        let epoch_id = create_active_epoch(&mut budget_system).await;
+        budget_system.set_epoch_reward("ETH", 100.0).unwrap();
+        let next_id = budget_system.create_epoch(
+            "Next Epoch",
+            Utc::now() + Duration::days(31),
+            Utc::now() + Duration::days(61),
         ).unwrap();
-    
-        let config = budget_system.config().clone();
-        let (raffle_id, _) = budget_system.prepare_raffle(proposal_name, None, &config).unwrap();
-        budget_system.finalize_raffle(
-            raffle_id,
-            12345,
-            12355,
-            "mock_randomness".to_string()
-        ).await.unwrap();
-    
-        (proposal_id, raffle_id)
+
+        // Only planned epochs other than the one closing can receive the carry-over
+        assert!(budget_system.close_epoch(None, Some(epoch_id)).is_err());
+        assert!(budget_system.get_epoch(&epoch_id).unwrap().is_active());
+
+        // No points were earned, so the whole pool rolls over instead of erroring
+        budget_system.execute_command(Command::CloseEpoch {
+            epoch_name: None,
+            confirm_no_reward: false,
+            carry_over_to: Some("Next Epoch".to_string()),
+        }).await.unwrap();
+
+        let closed = budget_system.get_epoch(&epoch_id).unwrap();
+        assert_eq!(closed.unallocated().get("ETH"), Some(&100.0));
+        assert_eq!(closed.carried_over_to(), Some(next_id));
+        assert_eq!(budget_system.get_epoch(&next_id).unwrap().total_reward_amount(), 100.0);
+
+        let summary = budget_system.generate_epoch_summary(closed).unwrap();
+        assert!(summary.contains("**Unallocated**: 100.00 ETH (carried over to Next Epoch)"));
     }
 
-    fn get_mock_service(budget_system: &BudgetSystem) -> Option<Arc<MockEthereumService>> {
-        budget_system.ethereum_service()
-            .clone() // Clone the Arc before downcasting
-            .downcast_arc::<MockEthereumService>()
-            .ok()
+    #[tokio::test]
+    async fn test_close_epoch_largest_remainder_sums_to_reward() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+        budget_system.config.reward_rounding = RewardRounding::LargestRemainder(6);
+        budget_system.config.counted_vote_points = 7;
+
+        let epoch_id = create_active_epoch(&mut budget_system).await;
+        let teams: Vec<Uuid> = ["Team A", "Team B", "Team C"].iter()
+            .map(|name| budget_system.create_team(name.to_string(), "Rep".to_string(), None, None).unwrap())
+            .collect();
+
+        // 21, 14 and 7 points: the third and the sixth of the pool never end in decimal
+        for (name, voters) in [("First", &teams[..]), ("Second", &teams[..2]), ("Third", &teams[..1])] {
+            let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, name).await;
+            let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
+            budget_system.cast_votes(vote_id, voters.iter().map(|id| (*id, VoteChoice::Yes)).collect()).unwrap();
+            budget_system.close_vote_and_update_proposal(vote_id, proposal_id, None).unwrap();
+        }
+        budget_system.set_epoch_reward("ETH", 10.0).unwrap();
+        budget_system.close_epoch(None, None).unwrap();
+
+        let epoch = budget_system.get_epoch(&epoch_id).unwrap();
+        let units: Vec<u64> = teams.iter()
+            .map(|id| (epoch.team_rewards()[id].amount() * 1e6).round() as u64)
+            .collect();
+        assert_eq!(units.iter().sum::<u64>(), 10_000_000);
+        // The leftover unit goes to Team C, whose remainder (.67) beats Team B's (.33)
+        assert_eq!(units, vec![5_000_000, 3_333_333, 1_666_667]);
+        assert_eq!(epoch.unallocated().get("ETH"), Some(&0.0));
+        assert_eq!(epoch.reward_rounding(), RewardRounding::LargestRemainder(6));
+
+        let summary = budget_system.generate_epoch_summary(epoch).unwrap();
+        assert!(summary.contains("**Reward Rounding**: largest_remainder:6"));
+        assert!(budget_system.reward_inconsistencies().is_empty());
     }
 
-    async fn setup_block_progression(mock_service: Arc<MockEthereumService>) {
-        let service = mock_service.clone();
-        tokio::spawn(async move {
-            for _ in 0..5 {
-                service.increment_block();
-                tokio::time::sleep(Dur::from_millis(100)).await;
+    #[tokio::test]
+    async fn test_reports_are_identical_across_loads() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+
+        let epoch_id = create_active_epoch(&mut budget_system).await;
+        let teams: Vec<Uuid> = ["Team F", "Team B", "Team D", "Team A", "Team E", "Team C"].iter()
+            .map(|name| budget_system.create_team(name.to_string(), "Rep".to_string(), None, None).unwrap())
+            .collect();
+        for (name, voters) in [("First", &teams[..]), ("Second", &teams[2..])] {
+            let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, name).await;
+            let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
+            budget_system.cast_votes(vote_id, voters.iter().map(|id| (*id, VoteChoice::Yes)).collect()).unwrap();
+            budget_system.close_vote_and_update_proposal(vote_id, proposal_id, None).unwrap();
+        }
+        budget_system.set_epoch_reward("ETH", 60.0).unwrap();
+        budget_system.close_epoch(None, None).unwrap();
+
+        // Each load gets fresh hash maps, so any iteration in hash order would show up as a diff
+        let serialized = serde_json::to_string(&budget_system.state).unwrap();
+        let mut reports = Vec::new();
+        for _ in 0..2 {
+            let state: BudgetSystemState = serde_json::from_str(&serialized).unwrap();
+            let loaded = create_test_budget_system(&state_file, Some(state)).await;
+            reports.push((
+                loaded.generate_end_of_epoch_report_markdown("Test Epoch").unwrap(),
+                loaded.generate_point_report_for_epoch(epoch_id).unwrap(),
+                loaded.print_team_report(),
+            ));
+        }
+        assert_eq!(reports[0], reports[1]);
+
+        let summary = &reports[0].0;
+        let positions: Vec<usize> = ["| Team A |", "| Team B |", "| Team C |", "| Team D |", "| Team E |", "| Team F |"].iter()
+            .map(|row| summary.find(row).unwrap())
+            .collect();
+        assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[tokio::test]
+    async fn test_close_epoch_with_reward_adjustment() {
+        async fn close_with_adjustment(redistribute: bool) -> (BudgetSystem, Uuid, Vec<Uuid>, TempDir) {
+            let temp_dir = TempDir::new().unwrap();
+            let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+            let mut budget_system = create_test_budget_system(&state_file, None).await;
+            budget_system.config.counted_vote_points = 7;
+            budget_system.config.redistribute_withheld_rewards = redistribute;
+
+            let epoch_id = create_active_epoch(&mut budget_system).await;
+            let teams: Vec<Uuid> = ["Team A", "Team B", "Team C"].iter()
+                .map(|name| budget_system.create_team(name.to_string(), "Rep".to_string(), None, None).unwrap())
+                .collect();
+            // 21, 14 and 7 points
+            for (name, voters) in [("First", &teams[..]), ("Second", &teams[..2]), ("Third", &teams[..1])] {
+                let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, name).await;
+                let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
+                budget_system.cast_votes(vote_id, voters.iter().map(|id| (*id, VoteChoice::Yes)).collect()).unwrap();
+                budget_system.close_vote_and_update_proposal(vote_id, proposal_id, None).unwrap();
             }
-        });
+            budget_system.set_epoch_reward("ETH", 42.0).unwrap();
+            assert_eq!(budget_system.set_reward_adjustment("Test Epoch", "Team D", 0.5, String::new()).unwrap_err().to_string(), "Team not found: Team D");
+            budget_system.execute_command(Command::SetRewardAdjustment {
+                epoch_name: "Test Epoch".to_string(),
+                team_name: "Team A".to_string(),
+                multiplier: 0.5,
+                reason: "Missed reporting".to_string(),
+            }).await.unwrap();
+            budget_system.close_epoch(None, None).unwrap();
+            (budget_system, epoch_id, teams, temp_dir)
+        }
+
+        let amounts = |budget_system: &BudgetSystem, epoch_id: Uuid, teams: &[Uuid]| -> Vec<f64> {
+            let epoch = budget_system.get_epoch(&epoch_id).unwrap();
+            teams.iter().map(|id| epoch.team_rewards()[id].amount()).collect()
+        };
+        let assert_close = |actual: Vec<f64>, expected: [f64; 3]| {
+            for (actual, expected) in actual.iter().zip(expected) {
+                assert!((actual - expected).abs() < 1e-9, "{} != {}", actual, expected);
+            }
+        };
+
+        // The withheld 10.5 goes to Team B and Team C by their 14 and 7 points
+        let (mut budget_system, epoch_id, teams, _temp_dir) = close_with_adjustment(true).await;
+        assert_close(amounts(&budget_system, epoch_id, &teams), [10.5, 21.0, 10.5]);
+        assert!(budget_system.reward_inconsistencies().is_empty());
+        let epoch = budget_system.get_epoch(&epoch_id).unwrap();
+        assert!(epoch.unallocated()["ETH"].abs() < 1e-9);
+        let summary = budget_system.generate_epoch_summary(epoch).unwrap();
+        assert!(summary.contains("**Reward Adjustments**: Team A x0.5 (Missed reporting)"));
+        assert!(budget_system.set_reward_adjustment("Test Epoch", "Team A", 0.0, String::new()).is_err());
+
+        let (budget_system, epoch_id, teams, _temp_dir) = close_with_adjustment(false).await;
+        assert_close(amounts(&budget_system, epoch_id, &teams), [10.5, 14.0, 7.0]);
+        assert!((budget_system.get_epoch(&epoch_id).unwrap().unallocated()["ETH"] - 10.5).abs() < 1e-9);
     }
-    
-    // Tests
 
     #[tokio::test]
-    async fn test_state_management() {
+    async fn test_check_payment() {
         let temp_dir = TempDir::new().unwrap();
         let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
-
-        // Test creating a new BudgetSystem
         let mut budget_system = create_test_budget_system(&state_file, None).await;
-        
-        // Modify state
-        let epoch_id = budget_system.create_epoch("Test Epoch", Utc::now(), Utc::now() + Duration::days(30)).unwrap();
-        let team_id = budget_system.create_team("Test Team".to_string(), "Representative".to_string(), Some(vec![1000, 2000, 3000]), None).unwrap();
-
-        // Save state
-        budget_system.save_state().unwrap();
 
-        // Test loading existing state
-        let loaded_state = FileSystem::try_load_state(&state_file).unwrap();
-        let loaded_system = create_test_budget_system(&state_file, Some(loaded_state)).await;
+        create_active_epoch(&mut budget_system).await;
+        let details = BudgetRequestDetails::new(
+            None,
+            HashMap::from([("ETH".to_string(), 10.0)]),
+            None,
+            None,
+            Some(false),
+            None,
+        ).unwrap();
+        let proposal_id = budget_system.add_proposal("Paid Proposal".to_string(), None, Some(details), None, None, None).unwrap();
+        let command = Command::CheckPayment { proposal_name: "Paid Proposal".to_string() };
+        assert!(budget_system.execute_command(command.clone()).await.is_err());
+
+        budget_system.state.get_proposal_mut(&proposal_id).unwrap().approve().unwrap();
+        let tx_hash = "0x742d35cc6634c0532925a3b844bc454e4438f44e4438f44e4438f44e4438f44e";
+        budget_system.record_payment("Paid Proposal", tx_hash.to_string()).unwrap();
+
+        let output = budget_system.execute_command(command.clone()).await.unwrap();
+        assert!(output.contains(tx_hash));
+        assert!(output.ends_with("pending"));
+
+        let mock_service = get_mock_service(&budget_system).unwrap();
+        mock_service.set_transaction_status(TxStatus::Confirmed { block: 12340, confirmations: 6 });
+        let output = budget_system.execute_command(command).await.unwrap();
+        assert!(output.contains("confirmed in block 12340 (6 confirmations)"));
+    }
 
-        // Verify loaded state
-        assert_eq!(loaded_system.state().epochs().len(), 1);
-        assert!(loaded_system.state().epochs().contains_key(&epoch_id));
-        assert_eq!(loaded_system.state().current_state().teams().len(), 1);
-        assert!(loaded_system.state().current_state().teams().contains_key(&team_id));
+    #[tokio::test]
+    async fn test_move_proposal() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
 
-        // Test loading from non-existent file (should create new system)
-        let non_existent_file = temp_dir.path().join("non_existent.json").to_str().unwrap().to_string();
-        let new_system = create_test_budget_system(&non_existent_file, None).await;
-        assert!(new_system.state().epochs().is_empty());
-        assert!(new_system.state().current_state().teams().is_empty());
+        let source_id = create_active_epoch(&mut budget_system).await;
+        let target_id = budget_system.create_epoch(
+            "Next Epoch",
+            Utc::now() + Duration::days(31),
+            Utc::now() + Duration::days(61),
+        ).unwrap();
+        let team_id = budget_system.create_team("Team A".to_string(), "Rep A".to_string(), None, None).unwrap();
+        let proposal_id = budget_system.add_proposal("Misfiled".to_string(), None, None, None, None, None).unwrap();
+
+        budget_system.execute_command(Command::MoveProposal {
+            proposal_name: "Misfiled".to_string(),
+            epoch_name: "Next Epoch".to_string(),
+        }).await.unwrap();
+
+        assert_eq!(budget_system.get_proposal(&proposal_id).unwrap().epoch_id(), target_id);
+        assert!(!budget_system.get_epoch(&source_id).unwrap().is_proposal_associated(proposal_id));
+        assert!(budget_system.get_epoch(&target_id).unwrap().is_proposal_associated(proposal_id));
+        assert_eq!(budget_system.move_proposal("Misfiled", "Next Epoch").unwrap_err().to_string(), "Proposal is already in the target epoch");
+
+        // Once voted on with a raffle of its epoch, the proposal stays put
+        let (voted_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, "Voted").await;
+        let vote_id = budget_system.create_formal_vote(voted_id, raffle_id, None).unwrap();
+        budget_system.cast_votes(vote_id, vec![(team_id, VoteChoice::Yes)]).unwrap();
+        assert!(budget_system.move_proposal("Voted", "Next Epoch").is_err());
+        assert_eq!(budget_system.get_proposal(&voted_id).unwrap().epoch_id(), source_id);
     }
 
     #[tokio::test]
-    async fn test_epoch_management() {
+    async fn test_team_participation_rate_and_streak() {
         let temp_dir = TempDir::new().unwrap();
         let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
         let mut budget_system = create_test_budget_system(&state_file, None).await;
 
-        // Test creating a new epoch
-        let start_date = Utc::now();
-        let end_date = start_date + Duration::days(30);
-        let epoch_id = budget_system.create_epoch("Test Epoch", start_date, end_date).unwrap();
-        let epoch = budget_system.get_epoch(&epoch_id).unwrap();
-        assert_eq!(epoch.name(), "Test Epoch");
-        assert_eq!(epoch.start_date(), start_date);
-        assert_eq!(epoch.end_date(), end_date);
-
-        // Test activating an epoch
-        budget_system.activate_epoch(epoch_id).unwrap();
-        assert_eq!(budget_system.state().current_epoch(), Some(epoch_id));
+        let epoch_id = create_active_epoch(&mut budget_system).await;
+        let team_a = budget_system.create_team("Team A".to_string(), "Rep A".to_string(), None, None).unwrap();
+        let team_b = budget_system.create_team("Team B".to_string(), "Rep B".to_string(), None, None).unwrap();
+        assert_eq!(budget_system.team_participation_rate(team_a, epoch_id), 0.0);
+
+        // Team A skips the first vote and joins the next two; Team B only votes in the first
+        for (index, voters) in [vec![team_b], vec![team_a], vec![team_a]].into_iter().enumerate() {
+            let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, &format!("Proposal {}", index)).await;
+            let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
+            let votes = voters.into_iter().map(|team_id| (team_id, VoteChoice::Yes)).collect();
+            budget_system.cast_votes(vote_id, votes).unwrap();
+            budget_system.close_vote(vote_id).unwrap();
+            let opened_at = Utc::now() + Duration::minutes(index as i64);
+            budget_system.state.get_vote_mut(&vote_id).unwrap().set_opened_at(opened_at);
+        }
 
-        // Test setting epoch reward
-        budget_system.set_epoch_reward("ETH", 100.0).unwrap();
-        let updated_epoch = budget_system.get_epoch(&epoch_id).unwrap();
-        assert_eq!(updated_epoch.reward().unwrap().token(), "ETH");
-        assert_eq!(updated_epoch.reward().unwrap().amount(), 100.0);
+        assert!((budget_system.team_participation_rate(team_a, epoch_id) - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(budget_system.team_vote_streak(team_a, epoch_id), 2);
+        assert_eq!(budget_system.team_vote_streak(team_b, epoch_id), 0);
 
-        // Test creating overlapping epoch (should fail)
-        let overlapping_start = start_date + Duration::days(15);
-        let overlapping_end = end_date + Duration::days(15);
-        assert!(budget_system.create_epoch("Overlapping Epoch", overlapping_start, overlapping_end).is_err());
+        let report = budget_system.print_team_vote_participation("Team A", None).unwrap();
+        assert!(report.contains("Participation Rate: 67% of 3 eligible votes"));
+        assert!(report.contains("Current Streak: 2"));
+    }
 
-        // Test activating an epoch when another is already active (should fail)
-        let another_epoch_id = budget_system.create_epoch("Another Epoch", end_date + Duration::days(1), end_date + Duration::days(31)).unwrap();
-        assert!(budget_system.activate_epoch(another_epoch_id).is_err());
+    #[tokio::test]
+    async fn test_validate_state() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
 
-        // Ensure points are earned before closing an epoch
-        let team_id = budget_system.create_team("Test Team".to_string(), "Rep".to_string(), Some(vec![1000]), None).unwrap();
+        let epoch_id = create_active_epoch(&mut budget_system).await;
+        budget_system.create_team("Team A".to_string(), "Rep A".to_string(), None, None).unwrap();
         let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, "Test Proposal").await;
         let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
-        budget_system.cast_votes(vote_id, vec![(team_id, VoteChoice::Yes)]).unwrap();
-        budget_system.close_vote(vote_id).unwrap();
+        assert!(budget_system.validate_state().is_empty());
+
+        // Simulate a botched manual edit that dropped the proposal
+        budget_system.state.remove_proposal(proposal_id);
+        let errors = budget_system.validate_state();
+        assert_eq!(errors, vec![
+            IntegrityError::VoteMissingProposal { vote_id, proposal_id },
+            IntegrityError::RaffleMissingProposal { raffle_id, proposal_id },
+            IntegrityError::EpochMissingProposal { epoch_id, proposal_id },
+        ]);
+
+        let output = budget_system.execute_command(Command::ValidateState).await.unwrap();
+        assert!(output.starts_with("Found 3 integrity errors"));
+    }
 
-        // Close the proposal before closing the epoch
-        budget_system.close_with_reason(proposal_id, &Resolution::Approved).unwrap();
+    #[tokio::test]
+    async fn test_import_csv() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+        create_active_epoch(&mut budget_system).await;
 
-        budget_system.close_epoch(Some("Test Epoch")).unwrap();
-        let closed_epoch = budget_system.get_epoch(&epoch_id).unwrap();
-        assert!(closed_epoch.is_closed());
-        assert_eq!(budget_system.state().current_epoch(), None);
+        let teams_csv = "name,representative,trailing_monthly_revenue\n\
+            Team A,Alice,1000|2000|3000\n\
+            \"Team, B\",Bob,\n\
+            Team C,Carol,lots\n\
+            Team A,Dave,\n";
+        let report = budget_system.import_teams_csv_report(teams_csv).unwrap();
+        assert_eq!(report.imported.len(), 2);
+        assert_eq!(report.failed.len(), 2);
+        assert_eq!(report.failed[0].0, 4);
+        assert!(report.failed[1].1.contains("already exists"));
+        let team_b = budget_system.get_team_id_by_name("Team, B").unwrap();
+        assert!(matches!(budget_system.state.get_team(&team_b).unwrap().status(), TeamStatus::Supporter));
+
+        let proposals_path = temp_dir.path().join("proposals.csv");
+        fs::write(&proposals_path, "title,team,amounts,announced_at\n\
+            Grant,Team A,ETH:10|USD:500,2024-01-15\n\
+            Orphan,Team Z,ETH:1,\n\
+            Discussion,,,\n").unwrap();
+        let output = budget_system.execute_command(Command::ImportProposalsCsv {
+            file_path: proposals_path.to_str().unwrap().to_string(),
+        }).await.unwrap();
+        assert!(output.starts_with("Imported 2 proposals"));
+        assert!(output.contains("- line 3: Team not found: Team Z"));
+
+        let grant_id = budget_system.get_proposal_id_by_name("Grant").unwrap();
+        let details = budget_system.get_proposal(&grant_id).unwrap().budget_request_details().unwrap();
+        assert_eq!(details.request_amounts().get("USD"), Some(&500.0));
+
+        assert!(budget_system.import_teams_csv("representative\nNobody\n").is_err());
     }
 
     #[tokio::test]
-    async fn test_team_management() {
+    async fn test_per_vote_point_overrides() {
         let temp_dir = TempDir::new().unwrap();
         let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
         let mut budget_system = create_test_budget_system(&state_file, None).await;
 
-        // Test creating a new team
-        let team_id = budget_system.create_team(
-            "Test Team".to_string(),
-            "Representative".to_string(),
-            Some(vec![1000, 2000, 3000]),
-            None
-        ).unwrap();
-        let team = budget_system.get_team(&team_id).unwrap();
-        assert_eq!(team.name(), "Test Team");
-        assert_eq!(team.representative(), "Representative");
-        assert!(matches!(team.status(), TeamStatus::Earner { .. }));
-
-        // Test getting team by name
-        let team_id_by_name = budget_system.get_team_id_by_name("Test Team").unwrap();
-        assert_eq!(team_id_by_name, team_id);
+        let epoch_id = create_active_epoch(&mut budget_system).await;
+        let team_a = budget_system.create_team("Team A".to_string(), "Rep A".to_string(), None, None).unwrap();
+        let team_b = budget_system.create_team("Team B".to_string(), "Rep B".to_string(), None, None).unwrap();
 
-        // Test removing a team
-        budget_system.remove_team(team_id).unwrap();
-        assert!(budget_system.get_team(&team_id).is_none());
+        // A regular vote at the configured 5 points
+        let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, "Regular").await;
+        let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
+        budget_system.cast_votes(vote_id, vec![(team_a, VoteChoice::Yes), (team_b, VoteChoice::No)]).unwrap();
+        budget_system.close_vote(vote_id).unwrap();
 
-        // Test creating a team with invalid data (should fail)
-        assert!(budget_system.create_team("".to_string(), "Representative".to_string(), None, None).is_err());
+        // A high-stakes vote worth double, where only Team A takes part
+        create_proposal_with_raffle(&mut budget_system, "High Stakes").await;
+        let output = budget_system.execute_command(Command::CreateAndProcessVote {
+            proposal_name: "High Stakes".to_string(),
+            counted_votes: HashMap::from([("Team A".to_string(), VoteChoice::Yes)]),
+            uncounted_votes: HashMap::new(),
+            vote_opened: None,
+            vote_closed: None,
+            counted_points: Some(10),
+            uncounted_points: Some(4),
+            threshold: None,
+        }).await.unwrap();
+        assert!(output.contains("Team A (+10 points)"));
+
+        assert_eq!(budget_system.get_team_points_for_epoch(team_a, epoch_id).unwrap(), 15);
+        assert_eq!(budget_system.get_team_points_for_epoch(team_b, epoch_id).unwrap(), 5);
     }
 
     #[tokio::test]
-    async fn test_update_team() {
+    async fn test_min_sponsors_for_formal_vote() {
         let temp_dir = TempDir::new().unwrap();
         let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
         let mut budget_system = create_test_budget_system(&state_file, None).await;
+        budget_system.config.min_sponsors = 1;
 
-        let team_id = budget_system.create_team("Test Team".to_string(), "John Doe".to_string(), Some(vec![1000]), None).unwrap();
-
-        let updates = UpdateTeamDetails {
-            name: Some("Updated Team".to_string()),
-            representative: Some("Jane Doe".to_string()),
-            status: Some("Supporter".to_string()),
-            trailing_monthly_revenue: None,
-            address: None
-        };
+        create_active_epoch(&mut budget_system).await;
+        budget_system.create_team("Team A".to_string(), "Rep A".to_string(), None, None).unwrap();
+        let team_b = budget_system.create_team("Team B".to_string(), "Rep B".to_string(), None, None).unwrap();
+        budget_system.state.get_team_mut(&team_b).unwrap().set_status(TeamStatus::Inactive).unwrap();
+        let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, "Sponsored").await;
+
+        assert_eq!(
+            budget_system.create_formal_vote(proposal_id, raffle_id, None),
+            Err("Proposal does not have enough sponsors for a formal vote")
+        );
+        assert_eq!(budget_system.add_sponsor("Sponsored", "Team B").unwrap_err().to_string(), "Only active teams can sponsor proposals");
 
-        budget_system.update_team(team_id, updates).unwrap();
+        budget_system.execute_command(Command::AddSponsor {
+            proposal_name: "Sponsored".to_string(),
+            team_name: "Team A".to_string(),
+        }).await.unwrap();
+        assert!(budget_system.add_sponsor("Sponsored", "Team A").is_err());
+        assert!(budget_system.create_formal_vote(proposal_id, raffle_id, None).is_ok());
 
-        let updated_team = budget_system.get_team(&team_id).unwrap();
-        assert_eq!(updated_team.name(), "Updated Team");
-        assert_eq!(updated_team.representative(), "Jane Doe");
-        assert!(matches!(updated_team.status(), TeamStatus::Supporter));
+        let report = budget_system.generate_proposal_report(proposal_id).unwrap();
+        assert!(report.contains("- **Sponsors**: Team A"));
     }
 
     #[tokio::test]
-    async fn test_update_team_earner_status() {
+    async fn test_proposal_dependencies() {
         let temp_dir = TempDir::new().unwrap();
         let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
         let mut budget_system = create_test_budget_system(&state_file, None).await;
 
-        let team_id = budget_system.create_team("Test Team".to_string(), "John Doe".to_string(), Some(vec![1000]), None).unwrap();
+        let epoch_id = create_active_epoch(&mut budget_system).await;
+        budget_system.create_team("Team A".to_string(), "Rep A".to_string(), None, None).unwrap();
+        let (audit_id, _) = create_proposal_with_raffle(&mut budget_system, "Audit").await;
+        let (phase_id, phase_raffle_id) = create_proposal_with_raffle(&mut budget_system, "Phase 1").await;
 
-        let updates = UpdateTeamDetails {
-            name: None,
-            representative: None,
-            status: Some("Earner".to_string()),
-            trailing_monthly_revenue: Some(vec![2000, 3000, 4000]),
-            address: None,
+        let add_dependent = |title: &str, depends_on: &[&str]| Command::AddProposal {
+            title: title.to_string(),
+            url: None,
+            budget_request_details: None,
+            announced_at: None,
+            published_at: None,
+            is_historical: None,
+            tags: Vec::new(),
+            is_draft: false,
+            kind: None,
+            depends_on: depends_on.iter().map(|name| name.to_string()).collect(),
         };
+        assert!(budget_system.execute_command(add_dependent("Phase 3", &["Missing"])).await.is_err());
+        assert!(budget_system.get_proposal_id_by_name("Phase 3").is_none());
+        budget_system.execute_command(add_dependent("Phase 2", &["Phase 1"])).await.unwrap();
+        let phase_2_id = budget_system.get_proposal_id_by_name("Phase 2").unwrap();
 
-        budget_system.update_team(team_id, updates).unwrap();
+        let update = |depends_on: &[&str]| UpdateProposalDetails {
+            title: None,
+            url: None,
+            budget_request_details: None,
+            announced_at: None,
+            published_at: None,
+            resolved_at: None,
+            tags: None,
+            decision_deadline: None,
+            depends_on: Some(depends_on.iter().map(|name| name.to_string()).collect()),
+        };
+        budget_system.update_proposal("Phase 1", update(&["Audit"])).unwrap();
+        assert_eq!(budget_system.update_proposal("Audit", update(&["Phase 2"])).unwrap_err().to_string(), "Proposal dependencies would form a cycle");
+        assert_eq!(budget_system.update_proposal("Audit", update(&["Audit"])).unwrap_err().to_string(), "Proposal dependencies would form a cycle");
 
-        let updated_team = budget_system.get_team(&team_id).unwrap();
-        if let TeamStatus::Earner { trailing_monthly_revenue } = updated_team.status() {
-            assert_eq!(trailing_monthly_revenue, &[2000, 3000, 4000]);
-        } else {
-            panic!("Expected Earner status");
-        }
+        assert_eq!(budget_system.proposal_dependency_order(epoch_id).unwrap(), vec![audit_id, phase_id, phase_2_id]);
+
+        assert_eq!(
+            budget_system.create_formal_vote(phase_id, phase_raffle_id, None),
+            Err("Proposal depends on proposals that are not approved")
+        );
+        let report = budget_system.generate_proposal_report(phase_id).unwrap();
+        assert!(report.contains("- **Dependencies**: Audit"));
+        assert!(report.contains("- **Unmet Dependencies**: Audit"));
+
+        budget_system.state.get_proposal_mut(&audit_id).unwrap().approve().unwrap();
+        assert!(budget_system.create_formal_vote(phase_id, phase_raffle_id, None).is_ok());
+        let report = budget_system.generate_proposal_report(phase_id).unwrap();
+        assert!(report.contains("- **Unmet Dependencies**: None"));
+
+        // A cycle that bypasses the checks is reported by the ordering
+        budget_system.state.get_proposal_mut(&audit_id).unwrap().set_depends_on(vec![phase_2_id]).unwrap();
+        assert_eq!(budget_system.proposal_dependency_order(epoch_id), Err("Proposal dependencies contain a cycle"));
+
+        budget_system.state.get_proposal_mut(&audit_id).unwrap().set_depends_on(Vec::new()).unwrap();
+
+        // A failing update leaves the dependencies as they were
+        let mut bad_dates = update(&["Audit"]);
+        bad_dates.announced_at = Some(NaiveDate::from_ymd_opt(2024, 2, 1).unwrap());
+        bad_dates.published_at = Some(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap());
+        assert!(budget_system.update_proposal("Phase 2", bad_dates).is_err());
+        assert_eq!(budget_system.get_proposal(&phase_2_id).unwrap().depends_on(), &[phase_id]);
+
+        // A dependency cannot be removed, and one removed behind the checks is flagged
+        budget_system.add_proposal("Phase 4".to_string(), None, None, None, None, None).unwrap();
+        budget_system.update_proposal("Phase 2", update(&["Phase 1", "Phase 4"])).unwrap();
+        assert_eq!(budget_system.remove_proposal("Phase 4").unwrap_err().to_string(), "Cannot remove a proposal that other proposals depend on");
+        let phase_4_id = budget_system.get_proposal_id_by_name("Phase 4").unwrap();
+        budget_system.state.remove_proposal(phase_4_id);
+        assert!(budget_system.validate_state().contains(&IntegrityError::ProposalMissingDependency { proposal_id: phase_2_id, dependency_id: phase_4_id }));
+        let report = budget_system.generate_proposal_report(phase_2_id).unwrap();
+        assert!(report.contains(&format!("Missing proposal {}", phase_4_id)));
     }
 
     #[tokio::test]
-    async fn test_update_team_invalid_status() {
+    async fn test_formal_vote_threshold_override() {
         let temp_dir = TempDir::new().unwrap();
         let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
         let mut budget_system = create_test_budget_system(&state_file, None).await;
 
-        let team_id = budget_system.create_team("Test Team".to_string(), "John Doe".to_string(), Some(vec![1000]), None).unwrap();
-
-        let updates = UpdateTeamDetails {
-            name: None,
-            representative: None,
-            status: Some("InvalidStatus".to_string()),
-            trailing_monthly_revenue: None,
-            address: None,
+        create_active_epoch(&mut budget_system).await;
+        let teams: Vec<Uuid> = ["Team A", "Team B", "Team C"].iter()
+            .map(|name| budget_system.create_team(name.to_string(), "Rep".to_string(), None, None).unwrap())
+            .collect();
+        budget_system.config.default_total_counted_seats = 3;
+        budget_system.config.default_max_earner_seats = 3;
+        let bare_majority = vec![(teams[0], VoteChoice::Yes), (teams[1], VoteChoice::Yes), (teams[2], VoteChoice::No)];
+        let passed = |budget_system: &BudgetSystem, vote_id: Uuid| {
+            matches!(budget_system.get_vote(&vote_id).unwrap().result(), Some(VoteResult::Formal { passed: true, .. }))
         };
 
-        assert!(budget_system.update_team(team_id, updates).is_err());
+        // Two of three seats fall short of the 0.7 default
+        let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, "Default").await;
+        let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
+        budget_system.cast_votes(vote_id, bare_majority.clone()).unwrap();
+        budget_system.close_vote(vote_id).unwrap();
+        assert!(!passed(&budget_system, vote_id));
+
+        let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, "Simple Majority").await;
+        assert_eq!(budget_system.create_formal_vote(proposal_id, raffle_id, Some(1.5)), Err("Threshold must be greater than 0 and at most 1"));
+        let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, Some(0.5)).unwrap();
+        assert!(matches!(budget_system.get_vote(&vote_id).unwrap().vote_type(), VoteType::Formal { threshold, .. } if *threshold == 0.5));
+        budget_system.cast_votes(vote_id, bare_majority).unwrap();
+        budget_system.close_vote(vote_id).unwrap();
+        assert!(passed(&budget_system, vote_id));
+
+        // The script command passes its threshold through
+        create_proposal_with_raffle(&mut budget_system, "Scripted").await;
+        budget_system.execute_command(Command::CreateAndProcessVote {
+            proposal_name: "Scripted".to_string(),
+            counted_votes: HashMap::from([
+                ("Team A".to_string(), VoteChoice::Yes),
+                ("Team B".to_string(), VoteChoice::Yes),
+                ("Team C".to_string(), VoteChoice::No),
+            ]),
+            uncounted_votes: HashMap::new(),
+            vote_opened: None,
+            vote_closed: None,
+            counted_points: None,
+            uncounted_points: None,
+            threshold: Some(0.5),
+        }).await.unwrap();
+        let proposal_id = budget_system.get_proposal_id_by_name("Scripted").unwrap();
+        assert!(budget_system.get_proposal(&proposal_id).unwrap().is_approved());
     }
 
     #[tokio::test]
-    async fn test_proposal_management() {
+    async fn test_overdue_proposals() {
         let temp_dir = TempDir::new().unwrap();
         let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
         let mut budget_system = create_test_budget_system(&state_file, None).await;
+        budget_system.config.default_decision_sla_days = 10;
 
-        // Create an active epoch
         let epoch_id = create_active_epoch(&mut budget_system).await;
-
-        // Test adding a new proposal
-        let proposal_id = budget_system.add_proposal(
-            "Test Proposal".to_string(),
-            Some("http://example.com".to_string()),
-            Some(BudgetRequestDetails::new(
-                None,
-                [("ETH".to_string(), 100.0)].iter().cloned().collect(),
-                Some(Utc::now().date_naive()),
-                Some((Utc::now() + Duration::days(30)).date_naive()),
-                Some(false),
-                None
-            ).unwrap()),
-            Some(Utc::now().date_naive()),
-            Some(Utc::now().date_naive()),
-            None
-        ).unwrap();
-
-        let proposal = budget_system.get_proposal(&proposal_id).unwrap();
-        assert_eq!(proposal.title(), "Test Proposal");
-
-        // Test updating a proposal
-        let updates = UpdateProposalDetails {
-            title: Some("Updated Proposal".to_string()),
+        let today = Utc::now().date_naive();
+        let by_sla = budget_system.add_proposal("By SLA".to_string(), None, None, Some(today - Duration::days(15)), None, None).unwrap();
+        budget_system.add_proposal("Within SLA".to_string(), None, None, Some(today - Duration::days(10)), None, None).unwrap();
+        budget_system.add_proposal("Never Announced".to_string(), None, None, None, None, None).unwrap();
+        let explicit = budget_system.add_proposal("Explicit".to_string(), None, None, Some(today), None, None).unwrap();
+        let closed = budget_system.add_proposal("Closed".to_string(), None, None, Some(today - Duration::days(30)), None, None).unwrap();
+        budget_system.close_with_reason(closed, &Resolution::Rejected).unwrap();
+
+        budget_system.update_proposal("Explicit", UpdateProposalDetails {
+            title: None,
             url: None,
             budget_request_details: None,
             announced_at: None,
             published_at: None,
             resolved_at: None,
-        };
-        budget_system.update_proposal("Test Proposal", updates).unwrap();
-        let updated_proposal = budget_system.get_proposal(&proposal_id).unwrap();
-        assert_eq!(updated_proposal.title(), "Updated Proposal");
+            tags: None,
+            decision_deadline: Some(today - Duration::days(20)),
+            depends_on: None,
+        }).unwrap();
 
-        // Test closing a proposal
-        budget_system.close_with_reason(proposal_id, &Resolution::Approved).unwrap();
-        let closed_proposal = budget_system.get_proposal(&proposal_id).unwrap();
-        assert!(closed_proposal.is_closed());
-        assert_eq!(closed_proposal.resolution(), Some(Resolution::Approved));
+        let overdue: Vec<Uuid> = budget_system.overdue_proposals(epoch_id).iter().map(|p| p.id()).collect();
+        assert_eq!(overdue, vec![explicit, by_sla]);
+        assert_eq!(budget_system.days_overdue(budget_system.get_proposal(&by_sla).unwrap()), Some(5));
 
-        // Test getting proposals for an epoch
-        let epoch_proposals = budget_system.get_proposals_for_epoch(epoch_id);
-        assert_eq!(epoch_proposals.len(), 1);
-        assert_eq!(epoch_proposals[0].id(), proposal_id);
+        let output = budget_system.execute_command(Command::OverdueProposals { epoch_name: None }).await.unwrap();
+        assert!(output.starts_with("Overdue proposals in epoch Test Epoch (2):"));
+        assert!(output.contains(&format!("- Explicit: 20 days overdue (deadline {})", today - Duration::days(20))));
 
-        // Test adding a proposal without an active epoch (should fail)
-        budget_system.close_epoch(None).unwrap();
-        assert!(budget_system.add_proposal(
-            "Failed Proposal".to_string(),
-            None,
-            None,
-            None,
-            None,
-            None
-        ).is_err());
+        let state = budget_system.print_epoch_state().unwrap();
+        assert!(state.contains("⚠️ *Overdue* by 5 days"));
+        assert_eq!(state.matches("⚠️ *Overdue*").count(), 2);
+
+        assert_eq!(budget_system.action_items().overdue_proposals.len(), 2);
     }
 
     #[tokio::test]
-    async fn test_raffle_management() {
+    async fn test_export_raffle_tickets() {
         let temp_dir = TempDir::new().unwrap();
         let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
         let mut budget_system = create_test_budget_system(&state_file, None).await;
 
-        // Create an active epoch and a proposal
-        let epoch_id = create_active_epoch(&mut budget_system).await;
-        let proposal_id = budget_system.add_proposal(
-            "Test Proposal".to_string(),
-            None,
-            None,
-            None,
-            None,
-            None
-        ).unwrap();
-
-        // Create some teams
-        let team_id1 = budget_system.create_team("Team 1".to_string(), "Rep 1".to_string(), Some(vec![1000]), None).unwrap();
-        let team_id2 = budget_system.create_team("Team 2".to_string(), "Rep 2".to_string(), None, None).unwrap();
-
-        // Test preparing a raffle
-        let config = budget_system.config().clone();
-        let (raffle_id, tickets) = budget_system.prepare_raffle(
-            "Test Proposal",
-            None,
-            &config
-        ).unwrap();
-        assert!(!tickets.is_empty());
-
-        // Test finalizing a raffle
-        let raffle = budget_system.finalize_raffle(
-            raffle_id,
-            12345,
-            12355,
-            "mock_randomness".to_string()
-        ).await.unwrap();
-        assert!(raffle.result().is_some());
+        create_active_epoch(&mut budget_system).await;
+        let renamed = budget_system.create_team("Old Name".to_string(), "Rep".to_string(), Some(vec![4000.0]), None).unwrap();
+        let removed = budget_system.create_team("Gone Team".to_string(), "Rep".to_string(), None, None).unwrap();
+        let (_, raffle_id) = create_proposal_with_raffle(&mut budget_system, "Test Proposal").await;
+        budget_system.rename_team(renamed, "New Name".to_string()).unwrap();
+        budget_system.archive_team(removed).unwrap();
+
+        let csv = budget_system.export_raffle_tickets("Test Proposal", ExportFormat::Csv).unwrap();
+        let raffle = budget_system.get_raffle(&raffle_id).unwrap();
+        assert_eq!(csv.lines().count(), raffle.tickets().len() + 1);
+        assert!(csv.contains(&format!("New Name,{}", renamed)));
+        assert!(csv.contains(&format!("Gone Team,{},", removed)));
+        assert!(!csv.contains("Old Name"));
+        assert!(csv.lines().skip(1).all(|line| line.ends_with(",true")));
+
+        let json = budget_system.export_raffle_tickets("Test Proposal", ExportFormat::Json).unwrap();
+        let records: Vec<serde_json::Value> = serde_json::from_str(&json).unwrap();
+        assert_eq!(records.len(), raffle.tickets().len());
+        assert_eq!(records[0]["index"], 0);
+        assert_eq!(records[0]["selected"], true);
+
+        let output = budget_system.execute_command(Command::ExportRaffleTickets {
+            proposal_name: "Test Proposal".to_string(),
+            format: ExportFormat::Json,
+        }).await.unwrap();
+        assert!(output.contains("-Test_Proposal-raffle_tickets.json"));
+        assert!(budget_system.export_raffle_tickets("Missing", ExportFormat::Csv).is_err());
+    }
 
-        // Test importing a predefined raffle
-        let imported_raffle_id = budget_system.import_predefined_raffle(
-            "Test Proposal",
-            vec!["Team 1".to_string()],
-            vec!["Team 2".to_string()],
-            1,
-            1
-        ).unwrap();
-        let imported_raffle = budget_system.get_raffle(&imported_raffle_id).unwrap();
-        assert_eq!(imported_raffle.result().unwrap().counted(), &[team_id1]);
-        assert_eq!(imported_raffle.result().unwrap().uncounted(), &[team_id2]);
+    #[tokio::test]
+    async fn test_search() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
 
-        // Test importing a historical raffle
-        let (historical_raffle_id, historical_raffle) = budget_system.import_historical_raffle(
-            "Test Proposal",
-            12345,
-            12355,
-            Some(vec!["Team 1".to_string(), "Team 2".to_string()]),
-            None,
-            Some(2),
-            Some(1)
-        ).await.unwrap();
-        assert_eq!(historical_raffle.config().initiation_block(), 12345);
-        assert_eq!(historical_raffle.config().randomness_block(), 12355);
-        assert!(historical_raffle.result().is_some());
+        let epoch_id = create_active_epoch(&mut budget_system).await;
+        let team_id = budget_system.create_team("Alpha Labs".to_string(), "Rep".to_string(), Some(vec![1000.0]), None).unwrap();
+        budget_system.create_team("Beta Guild".to_string(), "Rep".to_string(), None, None).unwrap();
+        let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, "Fund the ALPHA release").await;
+        let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
 
-        // Test raffle exclusions
-        let excluded_raffle_id = budget_system.import_predefined_raffle(
-            "Test Proposal",
-            vec!["Team 1".to_string()],
-            vec![],
-            1,
-            1
-        ).unwrap();
-        let excluded_raffle = budget_system.get_raffle(&excluded_raffle_id).unwrap();
-        assert_eq!(excluded_raffle.result().unwrap().counted(), &[team_id1]);
-        assert!(excluded_raffle.result().unwrap().uncounted().is_empty());
+        let results = budget_system.search("alpha");
+        assert_eq!(results.of_kind(SearchHitKind::Team).map(|hit| hit.id).collect::<Vec<_>>(), vec![team_id]);
+        assert_eq!(results.of_kind(SearchHitKind::Proposal).map(|hit| hit.id).collect::<Vec<_>>(), vec![proposal_id]);
+        assert_eq!(results.hits.len(), 2);
 
-        // Test invalid raffle creation (non-existent proposal)
-        assert!(budget_system.prepare_raffle(
-            "Non-existent Proposal",
-            None,
-            &config
-        ).is_err());
+        let results = budget_system.search("test epoch");
+        assert_eq!(results.of_kind(SearchHitKind::Epoch).map(|hit| hit.id).collect::<Vec<_>>(), vec![epoch_id]);
 
-        // Test invalid raffle finalization (non-existent raffle)
-        assert!(budget_system.finalize_raffle(
-            Uuid::new_v4(),
-            12345,
-            12355,
-            "mock_randomness".to_string()
-        ).await.is_err());
+        // Raffles and votes are only found by ID
+        let results = budget_system.search(&raffle_id.to_string());
+        assert_eq!(results.hits.len(), 1);
+        assert_eq!(results.hits[0].kind, SearchHitKind::Raffle);
+        assert_eq!(results.hits[0].name, "Raffle for Fund the ALPHA release");
+
+        let output = budget_system.execute_command(Command::Search { query: vote_id.to_string() }).await.unwrap();
+        assert!(output.contains(&format!("  - [Vote] Vote on Fund the ALPHA release ({})", vote_id)));
+
+        assert!(budget_system.search("gamma").is_empty());
+        assert!(budget_system.search("  ").is_empty());
     }
 
     #[tokio::test]
-    async fn test_vote_management() {
+    async fn test_open_vote_status() {
         let temp_dir = TempDir::new().unwrap();
         let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
         let mut budget_system = create_test_budget_system(&state_file, None).await;
 
         create_active_epoch(&mut budget_system).await;
-        let proposal_id = budget_system.add_proposal("Test Proposal".to_string(), None, None, None, None, None).unwrap();
-
-        // Create teams
-        let team_id1 = budget_system.create_team("Team 1".to_string(), "Rep 1".to_string(), Some(vec![1000]), None).unwrap();
-        let team_id2 = budget_system.create_team("Team 2".to_string(), "Rep 2".to_string(), Some(vec![2000]), None).unwrap();
-
-        // Prepare and finalize raffle
-        let config = budget_system.config().clone();
-        let (raffle_id, _) = budget_system.prepare_raffle("Test Proposal", None, &config).unwrap();
-        let mock_randomness = "mock_randomness".to_string();
-        budget_system.finalize_raffle(raffle_id, 12345, 12355, mock_randomness).await.unwrap();
+        let team_a = budget_system.create_team("Team A".to_string(), "Rep A".to_string(), None, None).unwrap();
+        let team_b = budget_system.create_team("Team B".to_string(), "Rep B".to_string(), None, None).unwrap();
+        budget_system.config.default_total_counted_seats = 2;
+        budget_system.config.default_max_earner_seats = 2;
+        let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, "Test Proposal").await;
+        let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
 
-        // Create and process a formal vote
-        let formal_vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
-        budget_system.cast_votes(formal_vote_id, vec![(team_id1, VoteChoice::Yes), (team_id2, VoteChoice::No)]).unwrap();
+        let status = budget_system.open_vote_status(vote_id).unwrap();
+        assert!(status.contains("Counted votes cast: 0/2 (outstanding: Team A, Team B)"));
 
-        // Test closing a vote
-        let vote_result = budget_system.close_vote(formal_vote_id).unwrap();
-        let closed_vote = budget_system.get_vote(&formal_vote_id).unwrap();
-        assert!(closed_vote.is_closed());
-        assert!(matches!(closed_vote.result(), Some(VoteResult::Formal { .. })));
+        budget_system.cast_votes(vote_id, vec![(team_a, VoteChoice::Yes)]).unwrap();
+        let status = budget_system.execute_command(Command::OpenVoteStatus { vote_id }).await.unwrap();
+        assert!(status.contains("Counted votes cast: 1/2 (outstanding: Team B)"));
+        assert!(status.contains("__1 in favor, 0 against, 0 abstained__"));
+        assert!(status.contains("**If closed now: Not Approved**"));
 
-        // Verify vote result
-        if let Some(VoteResult::Formal { counted, uncounted, passed }) = closed_vote.result() {
-            assert_eq!(counted.yes() + counted.no(), 2);
-            assert_eq!(uncounted.yes() + uncounted.no(), 0);
-            assert_eq!(*passed, vote_result);
-        } else {
-            panic!("Expected Formal vote result");
-        }
+        budget_system.cast_votes(vote_id, vec![(team_b, VoteChoice::Yes)]).unwrap();
+        let status = budget_system.open_vote_status(vote_id).unwrap();
+        assert!(status.contains("Counted votes cast: 2/2\n"));
+        assert!(status.contains("**If closed now: Approved**"));
+        assert!(budget_system.get_vote(&vote_id).unwrap().result().is_none());
 
-        // Test error case: closing an already closed vote
-        assert!(budget_system.close_vote(formal_vote_id).is_err());
+        budget_system.close_vote(vote_id).unwrap();
+        assert!(budget_system.open_vote_status(vote_id).unwrap().contains("The vote is closed"));
+        assert!(budget_system.open_vote_status(Uuid::new_v4()).is_err());
     }
 
     #[tokio::test]
-    async fn test_reporting() {
+    async fn test_outstanding_voters() {
         let temp_dir = TempDir::new().unwrap();
         let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
         let mut budget_system = create_test_budget_system(&state_file, None).await;
-    
-        let epoch_id = create_active_epoch(&mut budget_system).await;
-        let team_id = budget_system.create_team("Test Team".to_string(), "Rep".to_string(), Some(vec![1000]), None).unwrap();
-        
-        // Create proposal and raffle
-        let proposal_id = budget_system.add_proposal("Test Proposal".to_string(), None, None, None, None, None).unwrap();
-        let config = budget_system.config().clone();
-        let (raffle_id, _) = budget_system.prepare_raffle("Test Proposal", None, &config).unwrap();
-        
-        // Finalize raffle with the team included
-        let mock_randomness = "mock_randomness".to_string();
-        budget_system.finalize_raffle(raffle_id, 12345, 12355, mock_randomness).await.unwrap();
-    
-        // Create and process a vote
+
+        create_active_epoch(&mut budget_system).await;
+        for name in ["Team A", "Team B", "Team C"] {
+            budget_system.create_team(name.to_string(), "Rep".to_string(), None, None).unwrap();
+        }
+        budget_system.config.default_total_counted_seats = 2;
+        budget_system.config.default_max_earner_seats = 2;
+        let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, "Test Proposal").await;
         let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
-        budget_system.cast_votes(vote_id, vec![(team_id, VoteChoice::Yes)]).unwrap();
+
+        let (counted, uncounted) = budget_system.outstanding_voters(vote_id).unwrap();
+        assert_eq!((counted.len(), uncounted.len()), (2, 1));
+        let mut everyone: Vec<String> = counted.iter().chain(uncounted.iter()).cloned().collect();
+        everyone.sort();
+        assert_eq!(everyone, vec!["Team A", "Team B", "Team C"]);
+
+        let voter = budget_system.get_team_id_by_name(&counted[0]).unwrap();
+        budget_system.cast_votes(vote_id, vec![(voter, VoteChoice::Yes)]).unwrap();
+        let (remaining, _) = budget_system.outstanding_voters(vote_id).unwrap();
+        assert_eq!(remaining, vec![counted[1].clone()]);
+
+        let output = budget_system.execute_command(Command::OutstandingVoters { proposal_name: "Test Proposal".to_string() }).await.unwrap();
+        assert!(output.contains(&format!("Counted: {}", counted[1])));
+        assert!(output.contains(&format!("Uncounted: {}", uncounted[0])));
+        assert!(budget_system.execute_command(Command::OutstandingVoters { proposal_name: "Missing".to_string() }).await.is_err());
+
+        // The vote report names the same absentees
         budget_system.close_vote(vote_id).unwrap();
-    
-        // Generate reports
-        let team_report = budget_system.print_team_report();
-        assert!(team_report.contains("Test Team"));
-    
-        let epoch_state = budget_system.print_epoch_state().unwrap();
-        assert!(epoch_state.contains("Test Proposal"));
-    
-        let proposal_report = budget_system.generate_proposal_report(proposal_id).unwrap();
-        assert!(proposal_report.contains("Test Proposal"));
-    
-        let point_report = budget_system.generate_point_report(None).unwrap();
-        assert!(point_report.contains("Test Team"));
-    
-        // Close proposal before closing epoch
-        budget_system.close_with_reason(proposal_id, &Resolution::Approved).unwrap();
-    
-        budget_system.close_epoch(None).unwrap();
-        budget_system.generate_end_of_epoch_report(&budget_system.get_epoch(&epoch_id).unwrap().name()).unwrap();
+        let report = budget_system.generate_vote_report(vote_id).unwrap();
+        assert!(report.contains(&format!("Counted votes cast: 1/2 ({} absent)", counted[1])));
+
+        let informal_id = budget_system.add_proposal("Informal".to_string(), None, None, None, None, None).unwrap();
+        let informal_vote = budget_system.create_informal_vote(informal_id).unwrap();
+        assert!(budget_system.outstanding_voters(informal_vote).is_err());
     }
 
     #[tokio::test]
-    async fn test_integration() {
+    async fn test_non_budget_proposals_in_reports() {
         let temp_dir = TempDir::new().unwrap();
         let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
         let mut budget_system = create_test_budget_system(&state_file, None).await;
 
-        // Create and activate an epoch
         let epoch_id = create_active_epoch(&mut budget_system).await;
-        budget_system.set_epoch_reward("ETH", 1000.0).unwrap();
-
-        // Create teams
-        let team_id1 = budget_system.create_team("Team 1".to_string(), "Rep 1".to_string(), Some(vec![1000]), None).unwrap();
-        let team_id2 = budget_system.create_team("Team 2".to_string(), "Rep 2".to_string(), Some(vec![2000]), None).unwrap();
-        let team_id3 = budget_system.create_team("Team 3".to_string(), "Rep 3".to_string(), None, None).unwrap();
-
-        // Create a proposal
-        let proposal_id = budget_system.add_proposal(
-            "Test Proposal".to_string(),
-            Some("http://example.com".to_string()),
-            Some(BudgetRequestDetails::new(
-                Some(team_id1),
-                [("ETH".to_string(), 100.0)].iter().cloned().collect(),
-                Some(Utc::now().date_naive()),
-                Some((Utc::now() + Duration::days(30)).date_naive()),
-                Some(false),
-                None,
-            ).unwrap()),
-            Some(Utc::now().date_naive()),
-            Some(Utc::now().date_naive()),
-            None
-        ).unwrap();
+        let team_id = budget_system.create_team("Team A".to_string(), "Rep".to_string(), None, None).unwrap();
+        let details = BudgetRequestDetails::new(Some(team_id), [("ETH".to_string(), 10.0)].into_iter().collect(), None, None, None, None).unwrap();
+        let grant_id = budget_system.add_proposal("Grant".to_string(), None, Some(details), None, None, None).unwrap();
+        let add_policy = |title: &str, budget_request_details: Option<BudgetRequestDetailsCommand>| Command::AddProposal {
+            title: title.to_string(),
+            url: None,
+            budget_request_details,
+            announced_at: None,
+            published_at: None,
+            is_historical: None,
+            tags: Vec::new(),
+            is_draft: false,
+            kind: Some(ProposalKind::Policy),
+            depends_on: Vec::new(),
+        };
+        budget_system.execute_command(add_policy("Charter", None)).await.unwrap();
+        assert!(budget_system.execute_command(add_policy("Funded Charter", Some(BudgetRequestDetailsCommand {
+            team: None,
+            request_amounts: Some([("ETH".to_string(), 5.0)].into_iter().collect()),
+            start_date: None,
+            end_date: None,
+            is_loan: None,
+            payment_address: None,
+            monthly_breakdown: None,
+        }))).await.is_err());
+
+        let charter_id = budget_system.get_proposal_id_by_name("Charter").unwrap();
+        assert_eq!(budget_system.get_proposal(&charter_id).unwrap().kind(), ProposalKind::Policy);
+        budget_system.close_with_reason(grant_id, &Resolution::Approved).unwrap();
+        budget_system.close_with_reason(charter_id, &Resolution::Approved).unwrap();
+
+        let report = budget_system.generate_proposal_report(charter_id).unwrap();
+        assert!(report.contains("- **Kind**: policy"));
+        assert!(!report.contains("Budget Request Details"));
+
+        budget_system.close_epoch(None, None).unwrap();
+        let epoch = budget_system.get_epoch(&epoch_id).unwrap();
+        let tables = budget_system.generate_proposal_tables(epoch).unwrap();
+        assert!(tables.contains("### Approved Proposals\n"));
+        assert!(tables.contains("### Approved Non-Budget Proposals\n| Name | URL | Kind | Announced | Resolved | Report |"));
+        assert!(tables.contains("| Charter | N/A | policy |"));
+        assert!(!tables.contains("| Charter | N/A | N/A |"));
+        let summary = budget_system.generate_epoch_summary(epoch).unwrap();
+        assert!(summary.contains("- **Proposals by Kind**: 1 budget, 1 policy, 0 informational\n"));
+    }
 
-        // Conduct a raffle
-        let config = budget_system.config().clone();
-        let (raffle_id, _) = budget_system.prepare_raffle("Test Proposal", None, &config).unwrap();
-        budget_system.finalize_raffle(raffle_id, 12345, 12355, "mock_randomness".to_string()).await.unwrap();
-        
-        // Generate epoch report
-        let epoch_state = budget_system.print_epoch_state().unwrap();
-        assert!(epoch_state.contains("Test Proposal"));
+    #[tokio::test]
+    async fn test_generate_proposal_tables_paged() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
 
-        // Create and process a vote
-        let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
-        budget_system.cast_votes(vote_id, vec![
-            (team_id1, VoteChoice::Yes),
-            (team_id2, VoteChoice::Yes),
-            (team_id3, VoteChoice::No)
-        ]).unwrap();
-        let vote_result = budget_system.close_vote(vote_id).unwrap();
-        
-        // Verify the actual vote result
-        let vote = budget_system.get_vote(&vote_id).unwrap();
-        if let Some(VoteResult::Formal { passed, .. }) = vote.result() {
-            assert_eq!(*passed, vote_result);
-        } else {
-            panic!("Expected Formal vote result");
+        let epoch_id = create_active_epoch(&mut budget_system).await;
+        let team_id = budget_system.create_team("Team A".to_string(), "Rep".to_string(), None, None).unwrap();
+        for i in 10..30 {
+            let details = BudgetRequestDetails::new(Some(team_id), [("ETH".to_string(), 10.0)].into_iter().collect(), None, None, None, None).unwrap();
+            let proposal_id = budget_system.add_proposal(format!("Grant {}", i), None, Some(details), None, None, None).unwrap();
+            budget_system.close_with_reason(proposal_id, &Resolution::Approved).unwrap();
         }
+        budget_system.close_epoch(None, None).unwrap();
+        let epoch = budget_system.get_epoch(&epoch_id).unwrap();
 
-        // Close the proposal
-        budget_system.close_with_reason(proposal_id, &Resolution::Approved).unwrap();
-        
+        let tables = budget_system.generate_proposal_tables(epoch).unwrap();
+        let lines: Vec<&str> = tables.lines().collect();
+        let header_len: usize = lines[..3].iter().map(|line| line.len() + 1).sum();
+        let row_len = lines[3].len() + 1;
+        assert!(lines[3..23].iter().all(|line| line.len() + 1 == row_len));
+
+        // Room for the header and five rows, so the 20 rows take four pages
+        let page_size = header_len + 5 * row_len + 1;
+        let pages = budget_system.generate_proposal_tables_paged(epoch, page_size).unwrap();
+        assert_eq!(pages.len(), 4);
+        for page in &pages {
+            assert!(page.len() <= page_size);
+            assert!(page.starts_with(&tables[..header_len]));
+            assert!(page.ends_with("|\n\n"));
+            assert_eq!(page.lines().filter(|line| !line.is_empty()).count(), 3 + 5);
+        }
+        let rows: Vec<String> = pages.iter()
+            .flat_map(|page| page.lines().skip(3).filter(|line| !line.is_empty()).map(str::to_string).collect::<Vec<_>>())
+            .collect();
+        assert_eq!(rows, lines[3..23].iter().map(|line| line.to_string()).collect::<Vec<_>>());
 
-        // Close the epoch
-        budget_system.close_epoch(None).unwrap();
+        let pages = budget_system.generate_proposal_tables_paged(epoch, 100_000).unwrap();
+        assert_eq!(pages, vec![tables]);
+    }
 
-        // Generate other report
-        let team_report = budget_system.print_team_report();
-        let proposal_report = budget_system.generate_proposal_report(proposal_id).unwrap();
-        let point_report = budget_system.generate_point_report(Some("Test Epoch")).unwrap();
-        budget_system.generate_end_of_epoch_report(&budget_system.get_epoch(&epoch_id).unwrap().name()).unwrap();
+    #[tokio::test]
+    async fn test_generate_end_of_epoch_report_html() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
 
-        // Verify the integrations
-        assert!(team_report.contains("Team 1") && team_report.contains("Team 2") && team_report.contains("Team 3"));
-        assert!(proposal_report.contains("Approved"));
-        assert!(point_report.contains("Team 1") && point_report.contains("Team 2") && point_report.contains("Team 3"));
+        let epoch_id = create_active_epoch(&mut budget_system).await;
+        let team_id = budget_system.create_team("R&D <Labs>".to_string(), "Rep".to_string(), None, None).unwrap();
+        let details = BudgetRequestDetails::new(Some(team_id), [("ETH".to_string(), 10.0)].into_iter().collect(), None, None, None, None).unwrap();
+        let proposal_id = budget_system.add_proposal("Fix <script>".to_string(), None, Some(details), None, None, None).unwrap();
+        budget_system.close_with_reason(proposal_id, &Resolution::Approved).unwrap();
 
-        // Verify the final state
-        let closed_epoch = budget_system.get_epoch(&epoch_id).unwrap();
-        assert!(closed_epoch.is_closed());
-        let closed_proposal = budget_system.get_proposal(&proposal_id).unwrap();
-        assert!(closed_proposal.is_closed());
-        assert_eq!(closed_proposal.resolution(), Some(Resolution::Approved));
+        // Reports are only available once the epoch is closed
+        assert!(budget_system.generate_end_of_epoch_report_html("Test Epoch").is_err());
+        budget_system.close_epoch(None, None).unwrap();
+
+        let html = budget_system.generate_end_of_epoch_report_html("Test Epoch").unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<style>"));
+        assert!(html.contains("<li><strong>Approved Proposals</strong>: 1</li>"));
+        assert!(html.contains("<h3>Approved Proposals</h3>"));
+        assert!(html.contains("<h2>Team Summary</h2>"));
+        assert!(html.contains("<td>Fix &lt;script&gt;</td>"));
+        assert!(html.contains("<td>R&amp;D &lt;Labs&gt;</td>"));
+        assert!(!html.contains("<script>"));
+
+        // The Markdown report is unchanged and both formats are written side by side
+        let epoch = budget_system.get_epoch(&epoch_id).unwrap();
+        assert!(budget_system.generate_epoch_summary(epoch).unwrap().contains("- **Approved Proposals**: 1\n"));
+
+        budget_system.execute_command(Command::GenerateEndOfEpochReport {
+            epoch_name: "Test Epoch".to_string(),
+            format: ReportFormat::Html,
+        }).await.unwrap();
+        budget_system.generate_end_of_epoch_report("Test Epoch").unwrap();
+
+        let report_dir = temp_dir.path().join("reports").join(FileSystem::sanitize_filename("Test Epoch"));
+        let file_names: Vec<String> = fs::read_dir(report_dir).unwrap()
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .filter(|name| name.contains("epoch_report"))
+            .collect();
+        assert!(file_names.iter().any(|name| name.ends_with("-epoch_report.html")));
+        assert!(file_names.iter().any(|name| name.ends_with("-epoch_report.md")));
     }
 
     #[tokio::test]
-    async fn test_error_handling_and_edge_cases() {
+    async fn test_finalize_epoch() {
         let temp_dir = TempDir::new().unwrap();
         let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
         let mut budget_system = create_test_budget_system(&state_file, None).await;
 
-        // Test handling of non-existent entities
-        assert!(budget_system.get_team(&Uuid::new_v4()).is_none());
-        assert!(budget_system.get_proposal(&Uuid::new_v4()).is_none());
-        assert!(budget_system.get_epoch(&Uuid::new_v4()).is_none());
-        assert!(budget_system.get_raffle(&Uuid::new_v4()).is_none());
-        assert!(budget_system.get_vote(&Uuid::new_v4()).is_none());
+        create_active_epoch(&mut budget_system).await;
+        let closed_id = budget_system.add_proposal("Closed Proposal".to_string(), None, None, None, None, None).unwrap();
+        budget_system.close_with_reason(closed_id, &Resolution::Approved).unwrap();
+        let open_id = budget_system.add_proposal("Open Proposal".to_string(), None, None, None, None, None).unwrap();
+
+        // An actionable proposal blocks closing, so nothing is generated
+        let reports_dir = temp_dir.path().join("reports");
+        assert!(budget_system.finalize_epoch("Test Epoch").is_err());
+        assert!(!reports_dir.exists());
+        assert!(budget_system.get_current_epoch().is_some());
+
+        budget_system.close_with_reason(open_id, &Resolution::Rejected).unwrap();
+        let output = budget_system.execute_command(Command::FinalizeEpoch {
+            epoch_name: "Test Epoch".to_string(),
+        }).await.unwrap();
+
+        let archive_path = reports_dir.join(format!("{}-archive.zip", FileSystem::sanitize_filename("Test Epoch")));
+        assert!(output.contains("Finalized epoch Test Epoch"));
+        assert!(budget_system.get_current_epoch().is_none());
+
+        let mut archive = zip::ZipArchive::new(fs::File::open(&archive_path).unwrap()).unwrap();
+        let names: Vec<String> = archive.file_names().map(String::from).collect();
+        assert_eq!(names.len(), 3);
+        assert!(names.iter().any(|name| name.ends_with("-epoch_report.md")));
+        assert!(names.iter().any(|name| name.contains("Closed_Proposal")));
+        assert!(names.iter().any(|name| name.contains("Open_Proposal")));
+        assert!(archive.by_index(0).unwrap().size() > 0);
+
+        // A closed epoch cannot be finalized again
+        assert!(budget_system.finalize_epoch("Test Epoch").is_err());
+    }
 
-        // Test behavior with empty state
-        assert!(budget_system.print_epoch_state().is_err());
-        assert!(budget_system.generate_point_report(None).is_err());
+    #[tokio::test]
+    async fn test_recompute_epoch_points() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
 
-        // Test invalid inputs
-        assert!(budget_system.create_epoch("", Utc::now(), Utc::now()).is_err());
-        assert!(budget_system.create_team("".to_string(), "Rep".to_string(), None, None).is_err());
-        assert!(budget_system.set_epoch_reward("ETH", -100.0).is_err());
+        let epoch_id = create_active_epoch(&mut budget_system).await;
+        let team_a = budget_system.create_team("Team A".to_string(), "Rep".to_string(), Some(vec![1000.0]), None).unwrap();
+        let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, "Test Proposal").await;
+        let late_team = budget_system.create_team("Late Team".to_string(), "Rep".to_string(), Some(vec![1000.0]), None).unwrap();
 
-        // Test overlapping epochs
-        let epoch1_id = budget_system.create_epoch("Epoch 1", Utc::now(), Utc::now() + Duration::days(30)).unwrap();
-        assert!(budget_system.create_epoch("Epoch 2", Utc::now() + Duration::days(15), Utc::now() + Duration::days(45)).is_err());
+        let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
+        budget_system.cast_votes(vote_id, vec![(team_a, VoteChoice::Yes)]).unwrap();
+        budget_system.close_vote_and_update_proposal(vote_id, proposal_id, None).unwrap();
 
-        // Test activating multiple epochs
-        budget_system.activate_epoch(epoch1_id).unwrap();
-        let epoch2_id = budget_system.create_epoch("Epoch 2", Utc::now() + Duration::days(31), Utc::now() + Duration::days(61)).unwrap();
-        assert!(budget_system.activate_epoch(epoch2_id).is_err());
+        assert!(budget_system.recompute_epoch_points(epoch_id).unwrap().is_empty());
+        assert_eq!(budget_system.get_team_points_for_epoch(team_a, epoch_id).unwrap(), 5);
+        assert_eq!(budget_system.get_team_points_for_epoch(late_team, epoch_id).unwrap(), 0);
 
-        // Test closing an epoch with open proposals
-        let proposal_id = budget_system.add_proposal("Test Proposal".to_string(), None, None, None, None, None).unwrap();
-        assert!(budget_system.close_epoch(None).is_err());
+        // An edit that bypasses the save path leaves the cache stale until the state is saved
+        budget_system.state.get_vote_mut(&vote_id).unwrap().add_participant(late_team, true).unwrap();
+        assert_eq!(budget_system.get_team_points_for_epoch(late_team, epoch_id).unwrap(), 0);
+        budget_system.save_state().unwrap();
+        assert_eq!(budget_system.get_team_points_for_epoch(late_team, epoch_id).unwrap(), 5);
 
-        // Test updating a non-existent proposal
-        let updates = UpdateProposalDetails {
-            title: Some("Updated Title".to_string()),
-            url: None,
-            budget_request_details: None,
-            announced_at: None,
-            published_at: None,
-            resolved_at: None,
-        };
-        assert!(budget_system.update_proposal("Non-existent Proposal", updates).is_err());
+        let errors = budget_system.recompute_epoch_points(epoch_id).unwrap();
+        assert_eq!(errors, vec![IntegrityError::VoteUnknownParticipant { vote_id, team_id: late_team }]);
 
-        // Test creating a raffle for a non-existent proposal
-        let config = budget_system.config().clone();
-        assert!(budget_system.prepare_raffle("Non-existent Proposal", None, &config).is_err());
+        assert!(budget_system.recompute_epoch_points(Uuid::new_v4()).is_err());
+    }
 
-        // Test casting votes for a non-existent vote
-        assert!(budget_system.cast_votes(Uuid::new_v4(), vec![(Uuid::new_v4(), VoteChoice::Yes)]).is_err());
+    #[tokio::test]
+    async fn test_generate_epoch_comparison() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+        budget_system.config.default_qualified_majority_threshold = 0.1;
 
-        // Test closing a non-existent vote
-        assert!(budget_system.close_vote(Uuid::new_v4()).is_err());
+        create_active_epoch(&mut budget_system).await;
+        budget_system.set_epoch_reward("ETH", 100.0).unwrap();
+        let team_a = budget_system.create_team("Team A".to_string(), "Rep".to_string(), Some(vec![1000.0]), None).unwrap();
+        let team_b = budget_system.create_team("Team B".to_string(), "Rep".to_string(), Some(vec![1000.0]), None).unwrap();
+        let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, "First").await;
+        let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
+        budget_system.cast_votes(vote_id, vec![(team_a, VoteChoice::Yes), (team_b, VoteChoice::Yes)]).unwrap();
+        budget_system.close_vote_and_update_proposal(vote_id, proposal_id, None).unwrap();
+        budget_system.close_epoch(None, None).unwrap();
+
+        let next_id = budget_system.create_epoch("Next Epoch", Utc::now() + Duration::days(31), Utc::now() + Duration::days(61)).unwrap();
+        budget_system.activate_epoch(next_id).unwrap();
+        budget_system.set_epoch_reward("ETH", 150.0).unwrap();
+        let team_c = budget_system.create_team("Team C".to_string(), "Rep".to_string(), Some(vec![1000.0]), None).unwrap();
+        budget_system.create_team("Team D".to_string(), "Rep".to_string(), Some(vec![1000.0]), None).unwrap();
+        let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, "Second").await;
+        budget_system.add_proposal("Third".to_string(), None, None, None, None, None).unwrap();
+        let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
+        budget_system.cast_votes(vote_id, vec![(team_c, VoteChoice::No)]).unwrap();
+        budget_system.close_vote_and_update_proposal(vote_id, proposal_id, None).unwrap();
+
+        let report = budget_system.execute_command(Command::CompareEpochs {
+            epoch_a: "Test Epoch".to_string(),
+            epoch_b: "Next Epoch".to_string(),
+        }).await.unwrap();
+        assert!(report.contains("| Total Proposals | 1 | 2 | +1 |"));
+        assert!(report.contains("| Approved Proposals | 1 | 0 | -1 |"));
+        assert!(report.contains("| Rejected Proposals | 0 | 1 | +1 |"));
+        assert!(report.contains("| Total Reward | 100 ETH | 150 ETH | +50 ETH |"));
+        assert!(report.contains("| Team A | 5 | N/A | N/A |"));
+        assert!(report.contains("| Team B | 5 | N/A | N/A |"));
+        assert!(report.contains("| Team C | N/A | 5 | N/A |"));
+        // Team D never voted in either epoch
+        assert!(!report.contains("Team D"));
+
+        assert!(budget_system.generate_epoch_comparison("Test Epoch", "Missing").is_err());
     }
 
     #[tokio::test]
-    async fn test_ethereum_service_interaction() {
+    async fn test_draft_proposals() {
         let temp_dir = TempDir::new().unwrap();
         let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
-        
         let mut budget_system = create_test_budget_system(&state_file, None).await;
 
-        // Test successful interactions
-        assert_eq!(budget_system.get_current_block().await.unwrap(), 12345);
-        assert_eq!(budget_system.get_randomness(12355).await.unwrap(), "mock_randomness_for_block_12355");
-        
-        let (init_block, rand_block, randomness) = budget_system.get_raffle_randomness().await.unwrap();
-        assert_eq!(init_block, 12345);
-        assert_eq!(rand_block, 12355);
-        assert_eq!(randomness, "mock_randomness_for_block_12355");
+        let epoch_id = create_active_epoch(&mut budget_system).await;
+        budget_system.add_proposal("Visible".to_string(), None, None, None, None, None).unwrap();
+        let output = budget_system.execute_command(Command::AddProposal {
+            title: "Staged".to_string(),
+            url: None,
+            budget_request_details: None,
+            announced_at: None,
+            published_at: None,
+            is_historical: None,
+            tags: Vec::new(),
+            is_draft: true,
+            kind: None,
+            depends_on: Vec::new(),
+        }).await.unwrap();
+        assert!(output.starts_with("Added draft proposal: Staged"));
+        let draft_id = budget_system.get_proposal_id_by_name("Staged").unwrap();
+
+        let draft = budget_system.get_proposal(&draft_id).unwrap();
+        assert!(draft.is_draft());
+        assert!(!draft.is_actionable());
+        assert!(budget_system.print_epoch_state().unwrap().contains("Total: `1`"));
+        let epoch = budget_system.get_epoch(&epoch_id).unwrap();
+        assert!(budget_system.generate_epoch_summary(epoch).unwrap().contains("- **Total Proposals**: 1\n"));
 
-        // Test raffle creation with Ethereum service interaction
-        create_active_epoch(&mut budget_system).await;
-        budget_system.add_proposal("Test Proposal".to_string(), None, None, None, None, None).unwrap();
-        
         let config = budget_system.config().clone();
-        let (raffle_id, _) = budget_system.prepare_raffle("Test Proposal", None, &config).unwrap();
-        
-        let raffle = budget_system.finalize_raffle(raffle_id, 12345, 12355, "mock_randomness".to_string()).await.unwrap();
-        
-        assert_eq!(raffle.config().initiation_block(), 12345);
-        assert_eq!(raffle.config().randomness_block(), 12355);
-        assert_eq!(raffle.config().block_randomness(), "mock_randomness");
+        assert!(budget_system.prepare_raffle("Staged", None, &config).is_err());
+
+        budget_system.execute_command(Command::PublishProposal { proposal_name: "Staged".to_string() }).await.unwrap();
+        let published = budget_system.get_proposal(&draft_id).unwrap();
+        assert!(published.is_open());
+        assert_eq!(published.announced_at(), Some(Utc::now().date_naive()));
+        assert!(budget_system.print_epoch_state().unwrap().contains("Total: `2`"));
+
+        assert!(budget_system.publish_proposal("Staged").is_err());
     }
 
     #[tokio::test]
-    async fn test_raffle_creation_stream() {
-        use futures::pin_mut;
-        use std::sync::atomic::{AtomicU64, Ordering};
-        use std::time::Duration;
-        use std::sync::Arc;
-
-        // Create mock service
-        let mock_service = Arc::new(MockEthereumService::new());
-        let mock_service_clone = Arc::clone(&mock_service);
-        
+    async fn test_promote_informal_vote() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+        create_active_epoch(&mut budget_system).await;
+        let team_a = budget_system.create_team("Team A".to_string(), "Rep A".to_string(), Some(vec![1000.0]), None).unwrap();
+        let team_b = budget_system.create_team("Team B".to_string(), "Rep B".to_string(), None, None).unwrap();
+
+        let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, "Promoted").await;
+        let informal_id = budget_system.create_informal_vote(proposal_id).unwrap();
+        budget_system.cast_votes(informal_id, vec![(team_a, VoteChoice::Yes), (team_b, VoteChoice::No)]).unwrap();
+        assert_eq!(budget_system.promote_informal_vote(informal_id, raffle_id), Err("Informal vote must be closed before it is promoted"));
+        budget_system.close_vote(informal_id).unwrap();
+        assert_eq!(budget_system.promote_informal_vote(informal_id, Uuid::new_v4()), Err("Raffle not found"));
+        let (_, other_raffle_id) = create_proposal_with_raffle(&mut budget_system, "Other").await;
+        assert_eq!(budget_system.promote_informal_vote(informal_id, other_raffle_id), Err("Raffle belongs to a different proposal"));
+
+        let formal_id = budget_system.promote_informal_vote(informal_id, raffle_id).unwrap();
+        assert_eq!(budget_system.get_vote(&formal_id).unwrap().promoted_from(), Some(informal_id));
+        assert_eq!(budget_system.get_vote(&informal_id).unwrap().promoted_to(), Some(formal_id));
+        assert!(matches!(budget_system.get_vote(&formal_id).unwrap().vote_type(), VoteType::Formal { .. }));
+
+        assert_eq!(budget_system.promote_informal_vote(informal_id, raffle_id), Err("Informal vote has already been promoted"));
+        assert_eq!(budget_system.promote_informal_vote(formal_id, raffle_id), Err("Only informal votes can be promoted"));
+
+        // A second informal vote can't open another formal vote on the same proposal
+        let second_id = budget_system.create_informal_vote(proposal_id).unwrap();
+        budget_system.close_vote(second_id).unwrap();
+        assert_eq!(budget_system.promote_informal_vote(second_id, raffle_id), Err("Proposal already has a formal vote"));
+
+        let report = budget_system.generate_proposal_report(proposal_id).unwrap();
+        assert!(report.contains("It was promoted from an informal vote with 1 votes in favor and 1 votes against."));
+        assert!(report.contains(&format!("### Informal Stage\n\n- **Vote ID**: {}", informal_id)));
+        assert!(report.contains(&format!("### Vote Details\n\n- **Vote ID**: {}", formal_id)));
+    }
 
+    #[tokio::test]
+    async fn test_total_requested_usd() {
         let temp_dir = TempDir::new().unwrap();
-        
-        // Create budget system with our mock service
-        let mut budget_system = {
-            let config = AppConfig {
-                state_file: temp_dir.path().join("test_state.json").to_str().unwrap().to_string(),
-                ipc_path: "/tmp/test_reth.ipc".to_string(),
-                future_block_offset: 2, // Small offset for testing
-                script_file: "test_script.json".to_string(),
-                default_total_counted_seats: 7,
-                default_max_earner_seats: 5,
-                default_qualified_majority_threshold: 0.7,
-                counted_vote_points: 5,
-                uncounted_vote_points: 2,
-                telegram: TelegramConfig {
-                    chat_id: "test_chat_id".to_string(),
-                    token: "test_token".to_string(),
-                },
-            };
-            BudgetSystem::new(config, mock_service, None).await.unwrap()
-        };
-        
-        // Setup block progression before executing command
-        if let Some(mock_service) = get_mock_service(&budget_system) {
-            setup_block_progression(mock_service).await;
-        }
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+        let epoch_id = create_active_epoch(&mut budget_system).await;
 
-        // Setup test data
-        create_active_epoch(&mut budget_system).await;
-        
-        // Add test teams
-        budget_system.create_team("Team 1".to_string(), "Rep 1".to_string(), Some(vec![1000]), None).unwrap();
-        budget_system.create_team("Team 2".to_string(), "Rep 2".to_string(), Some(vec![2000]), None).unwrap();
-        
-        budget_system.add_proposal(
-            "Test Proposal".to_string(),
+        let request = |amounts: &[(&str, f64)]| Some(BudgetRequestDetails::new(
             None,
+            amounts.iter().map(|(token, amount)| (token.to_string(), *amount)).collect(),
             None,
-            Some(Utc::now().date_naive()),
-            Some(Utc::now().date_naive()),
+            None,
+            Some(false),
             None
-        ).unwrap();
+        ).unwrap());
+        budget_system.add_proposal("Mixed".to_string(), None, request(&[("ETH", 2.0), ("USD", 500.0)]), None, None, None).unwrap();
+        budget_system.add_proposal("Governance".to_string(), None, request(&[("GOV", 100.0)]), None, None, None).unwrap();
+
+        let requested = budget_system.requested_amounts(epoch_id);
+        assert_eq!(requested.get("ETH"), Some(&2.0));
+        assert_eq!(requested.get("GOV"), Some(&100.0));
+
+        budget_system.config.token_rates = HashMap::from([("eth".to_string(), 2000.0), ("USD".to_string(), 1.0)]);
+        assert_eq!(budget_system.total_requested_usd(epoch_id), None);
+        let epoch = budget_system.get_epoch(&epoch_id).unwrap().clone();
+        let summary = budget_system.generate_epoch_summary(&epoch).unwrap();
+        assert!(summary.contains("**Total Requested**: 2.00 ETH, 100.00 GOV, 500.00 USD"));
+        assert!(summary.contains("**Total Requested (USD)**: N/A (no rate for GOV)"));
+
+        budget_system.config.token_rates.insert("GOV".to_string(), 0.5);
+        assert_eq!(budget_system.total_requested_usd(epoch_id), Some(4550.0));
+        let summary = budget_system.generate_epoch_summary(&epoch).unwrap();
+        assert!(summary.contains("**Total Requested (USD)**: 4550.00 USD"));
+
+        budget_system.config.token_rates.insert("GOV".to_string(), f64::NAN);
+        assert_eq!(budget_system.total_requested_usd(epoch_id), None);
+    }
 
-        // Create and pin the stream
-        let progress_stream = budget_system.create_raffle_with_progress(
-            "Test Proposal".to_string(),
-            Some(2), // Small offset for testing
-            None
-        ).await;
-        pin_mut!(progress_stream);
+    #[tokio::test]
+    async fn test_reset_vote() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+        create_active_epoch(&mut budget_system).await;
+        let team_a = budget_system.create_team("Team A".to_string(), "Rep A".to_string(), Some(vec![1000.0]), None).unwrap();
+        let team_b = budget_system.create_team("Team B".to_string(), "Rep B".to_string(), Some(vec![2000.0]), None).unwrap();
 
-        // Collect updates with longer timeout
-        let mut updates = Vec::new();
-        while let Some(progress) = tokio::time::timeout(
-            Duration::from_secs(10), // Increased timeout
-            progress_stream.next()
-        ).await.unwrap() {
-            let progress = progress.unwrap();
-            println!("Received progress update: {:?}", progress);
-            updates.push(progress);
-            
-            if matches!(updates.last().unwrap(), RaffleProgress::Completed { .. }) {
-                break;
-            }
+        let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, "Reset").await;
+        let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
+        budget_system.cast_votes(vote_id, vec![(team_a, VoteChoice::No), (team_b, VoteChoice::No)]).unwrap();
+
+        budget_system.reset_vote(vote_id).unwrap();
+        match budget_system.get_vote(&vote_id).unwrap().participation() {
+            VoteParticipation::Formal { counted, uncounted } => assert!(counted.is_empty() && uncounted.is_empty()),
+            _ => panic!("Expected formal participation"),
         }
 
-        // Verify states
-        assert!(!updates.is_empty(), "Should have received updates");
-        assert!(matches!(updates[0], RaffleProgress::Preparing { .. }), "First update should be Preparing");
-        
-        let has_waiting = updates.iter().any(|p| matches!(p, RaffleProgress::WaitingForBlock { .. }));
-        assert!(has_waiting, "Should have WaitingForBlock state");
-        
-        let has_randomness = updates.iter().any(|p| matches!(p, RaffleProgress::RandomnessAcquired { .. }));
-        assert!(has_randomness, "Should have RandomnessAcquired state");
-        
-        assert!(matches!(updates.last().unwrap(), RaffleProgress::Completed { .. }), "Should end with Completed state");
+        budget_system.cast_votes(vote_id, vec![(team_a, VoteChoice::Yes), (team_b, VoteChoice::Yes)]).unwrap();
+        budget_system.close_vote(vote_id).unwrap();
+        let (counted, _) = budget_system.get_vote(&vote_id).unwrap().vote_counts().unwrap();
+        assert_eq!((counted.yes(), counted.no()), (2, 0));
 
-        if let RaffleProgress::Completed { counted, uncounted, .. } = updates.last().unwrap() {
-            assert!(!counted.is_empty() || !uncounted.is_empty(), "Raffle should contain teams");
-            println!("Final raffle result - Counted teams: {:?}, Uncounted teams: {:?}", counted, uncounted);
-        }
+        assert_eq!(budget_system.reset_vote(vote_id), Err("Vote is closed"));
+        assert_eq!(budget_system.reset_vote(Uuid::new_v4()), Err("Vote not found"));
     }
 
     #[tokio::test]
-    async fn test_create_raffle_with_progress() {
+    async fn test_preview_raffle_eligibility() {
         let temp_dir = TempDir::new().unwrap();
         let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
-        
         let mut budget_system = create_test_budget_system(&state_file, None).await;
-
-        // Setup required state
         create_active_epoch(&mut budget_system).await;
-        budget_system.add_proposal(
-            "Test Proposal".to_string(),
-            None,
-            None,
-            Some(Utc::now().date_naive()),
-            Some(Utc::now().date_naive()),
-            None
-        ).unwrap();
+        budget_system.create_team("Big".to_string(), "Rep".to_string(), Some(vec![16000.0, 16000.0, 16000.0]), None).unwrap();
+        budget_system.create_team("Helper".to_string(), "Rep".to_string(), None, None).unwrap();
+        budget_system.create_team("Skipped".to_string(), "Rep".to_string(), None, None).unwrap();
+        let dormant = budget_system.create_team("Dormant".to_string(), "Rep".to_string(), None, None).unwrap();
+        budget_system.state.get_team_mut(&dormant).unwrap().set_status(TeamStatus::Inactive).unwrap();
+        budget_system.add_proposal("Preview".to_string(), None, None, None, None, None).unwrap();
+
+        let eligible = budget_system.preview_raffle_eligibility("Preview", Some(vec!["Skipped".to_string()])).unwrap();
+        assert_eq!(eligible.len(), 2);
+        assert_eq!(eligible[0].0, "Big");
+        assert!(matches!(eligible[0].1, TeamStatus::Earner { .. }));
+        assert_eq!(eligible[0].2, 4);
+        assert_eq!((eligible[1].0.as_str(), &eligible[1].1, eligible[1].2), ("Helper", &TeamStatus::Supporter, 1));
+        assert!(budget_system.state().raffles().is_empty());
+
+        let output = budget_system.execute_command(Command::PreviewRaffleEligibility {
+            proposal_name: "Preview".to_string(),
+            excluded_teams: None,
+        }).await.unwrap();
+        assert!(output.contains("Teams eligible for a raffle on 'Preview': 3"));
+        assert!(output.contains("- Big (Earner): 4 tickets"));
+
+        assert!(budget_system.preview_raffle_eligibility("Unknown", None).is_err());
+    }
 
-        // Add some teams
-        budget_system.create_team("Team1".to_string(), "Rep1".to_string(), Some(vec![1000]), None).unwrap();
-        budget_system.create_team("Team2".to_string(), "Rep2".to_string(), Some(vec![2000]), None).unwrap();
+    #[tokio::test]
+    async fn test_create_recurring_epochs() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+        let start = DateTime::parse_from_rfc3339("2025-01-01T00:00:00Z").unwrap().with_timezone(&Utc);
+
+        let epoch_ids = budget_system.create_recurring_epochs("2025", start, 90, 4).unwrap();
+        assert_eq!(epoch_ids.len(), 4);
+        let epochs: Vec<&Epoch> = epoch_ids.iter().map(|id| budget_system.get_epoch(id).unwrap()).collect();
+        assert_eq!(epochs[0].name(), "2025 Q1");
+        assert_eq!(epochs[3].name(), "2025 Q4");
+        assert_eq!(epochs[0].start_date(), start);
+        assert_eq!(epochs[1].start_date(), epochs[0].end_date());
+        assert_eq!(epochs[3].end_date(), start + Duration::days(360));
+        assert!(epochs.iter().all(|epoch| epoch.status() == EpochStatus::Planned));
+
+        // The third epoch of the next run would overlap, so none of them is created
+        let before = budget_system.state().epochs().len();
+        let result = budget_system.create_recurring_epochs("Late", start - Duration::days(180), 90, 3);
+        assert_eq!(result, Err("New epoch overlaps with an existing epoch"));
+        assert_eq!(budget_system.state().epochs().len(), before);
+
+        assert!(budget_system.create_recurring_epochs("Empty", start + Duration::days(400), 90, 0).is_err());
+        assert!(budget_system.create_recurring_epochs("Instant", start + Duration::days(400), 0, 2).is_err());
+    }
 
-        // Setup block progression before executing command
-        if let Some(mock_service) = get_mock_service(&budget_system) {
-            setup_block_progression(mock_service).await;
+    #[tokio::test]
+    async fn test_generate_reports_since() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+        let epoch_id = create_active_epoch(&mut budget_system).await;
+        let epoch_name = budget_system.get_epoch(&epoch_id).unwrap().name().to_string();
+
+        let today = Utc::now().date_naive();
+        for (name, resolved) in [("Old", today - Duration::days(10)), ("Recent", today)] {
+            let proposal_id = budget_system.add_proposal(name.to_string(), None, None, None, None, None).unwrap();
+            let proposal = budget_system.state.get_proposal_mut(&proposal_id).unwrap();
+            proposal.approve().unwrap();
+            proposal.set_resolved_at(Some(resolved));
         }
 
-        // Create the progress stream and collect updates in their own scope
-        let updates = {
-            let progress_stream = budget_system.create_raffle_with_progress(
-                "Test Proposal".to_string(),
-                Some(1), // Small offset for testing
-                None,
-            ).await;
-
-            let mut updates = Vec::new();
-            pin_mut!(progress_stream);
-            
-            while let Some(progress) = progress_stream.next().await {
-                match progress {
-                    Ok(update) => {
-                        updates.push(update.clone());
-                        if matches!(update, RaffleProgress::Completed { .. }) {
-                            break;
-                        }
-                    },
-                    Err(e) => panic!("Unexpected error: {}", e),
-                }
-            }
-            updates
-        }; // progress_stream is dropped here, releasing the mutable borrow
-
-        // Now we can borrow budget_system again
-        
-        // Verify progress sequence
-        assert!(matches!(updates[0], RaffleProgress::Preparing { .. }));
-        assert!(matches!(updates[1], RaffleProgress::WaitingForBlock { .. }));
-        assert!(matches!(updates[2], RaffleProgress::RandomnessAcquired { .. }));
-        assert!(matches!(updates[3], RaffleProgress::Completed { .. }));
+        let command = |since| Command::GenerateReportsForClosedProposals { epoch_name: epoch_name.clone(), since };
+        let output = budget_system.execute_command(command(None)).await.unwrap();
+        assert!(output.contains("Regenerated 2 reports, skipped 0"));
+
+        // Nothing changed, so nothing is rewritten
+        let output = budget_system.execute_command(command(None)).await.unwrap();
+        assert!(output.contains("Regenerated 0 reports, skipped 2"));
+
+        let recent_id = budget_system.get_proposal_id_by_name("Recent").unwrap();
+        let old_id = budget_system.get_proposal_id_by_name("Old").unwrap();
+        budget_system.state.get_proposal_mut(&recent_id).unwrap().set_title("Recent Update".to_string());
+        budget_system.state.get_proposal_mut(&old_id).unwrap().set_title("Old Update".to_string());
+        let output = budget_system.execute_command(command(Some(today - Duration::days(1)))).await.unwrap();
+        assert!(output.contains("Report generated for proposal 'Recent Update'"));
+        assert!(!output.contains("Old Update"));
+        assert!(output.contains("Regenerated 1 reports, skipped 1"));
+    }
 
-        // Verify final state
-        if let RaffleProgress::Completed { ref counted, ref uncounted, .. } = updates[3] {
-            assert_eq!(counted.len() + uncounted.len(), 2); // All teams should be assigned
-        } else {
-            panic!("Final update should be Completed");
+    #[tokio::test]
+    async fn test_merge_teams() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+        let epoch_id = create_active_epoch(&mut budget_system).await;
+        let target = budget_system.create_team("Target".to_string(), "Rep A".to_string(), Some(vec![1000.0, 2000.0]), None).unwrap();
+        let source = budget_system.create_team("Source".to_string(), "Rep B".to_string(), Some(vec![500.0]), None).unwrap();
+        let other = budget_system.create_team("Other".to_string(), "Rep C".to_string(), None, None).unwrap();
+
+        for (name, voter) in [("First", target), ("Second", source), ("Third", source)] {
+            let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, name).await;
+            let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
+            budget_system.cast_votes(vote_id, vec![(voter, VoteChoice::Yes), (other, VoteChoice::No)]).unwrap();
+            budget_system.close_vote(vote_id).unwrap();
         }
-
-        // Verify raffle was created in system
-        assert_eq!(budget_system.state().raffles().len(), 1);
+        let target_points = budget_system.get_team_points_for_epoch(target, epoch_id).unwrap();
+        let source_points = budget_system.get_team_points_for_epoch(source, epoch_id).unwrap();
+        assert!(target_points > 0 && source_points > 0);
+
+        // Other voted alongside both of them, so folding it in would count those votes twice
+        let error = budget_system.merge_teams("Other", "Target").unwrap_err();
+        assert!(error.to_string().contains("would count it twice"));
+        assert!(budget_system.get_team(&other).is_some());
+
+        budget_system.merge_teams("Source", "Target").unwrap();
+        assert_eq!(budget_system.get_team_points_for_epoch(target, epoch_id).unwrap(), target_points + source_points);
+        assert_eq!(budget_system.get_team(&target).unwrap().status(), &TeamStatus::Earner { trailing_monthly_revenue: vec![1000.0, 2500.0] });
+        assert!(budget_system.get_team(&source).is_none());
+        assert!(budget_system.state().archived_teams().contains_key(&source));
+        assert!(budget_system.state().raffles().values()
+            .all(|raffle| raffle.result().is_some_and(|result| !result.contains(source) && result.contains(target))));
+
+        assert!(budget_system.merge_teams("Target", "Target").is_err());
+        assert!(budget_system.merge_teams("Source", "Target").is_err());
     }
 
-    // Test error cases
     #[tokio::test]
-    async fn test_create_raffle_with_progress_invalid_proposal() {
+    async fn test_merge_teams_rekeys_references() {
         let temp_dir = TempDir::new().unwrap();
         let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
-        
         let mut budget_system = create_test_budget_system(&state_file, None).await;
+        let epoch_id = create_active_epoch(&mut budget_system).await;
+        let target = budget_system.create_team("Target".to_string(), "Rep A".to_string(), Some(vec![1000.0]), None).unwrap();
+        let source = budget_system.create_team("Source".to_string(), "Rep B".to_string(), Some(vec![500.0]), None).unwrap();
+
+        let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, "Grant").await;
+        let formal_vote = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
+        budget_system.cast_votes(formal_vote, vec![(source, VoteChoice::Yes)]).unwrap();
+        budget_system.close_vote(formal_vote).unwrap();
+        let weighted_vote = budget_system.create_weighted_vote(proposal_id, raffle_id, epoch_id).unwrap();
+        let revenue_vote = budget_system.create_revenue_weighted_informal_vote(proposal_id).unwrap();
+        let source_weight = match budget_system.state().get_vote(&weighted_vote).unwrap().vote_type() {
+            VoteType::Weighted { weights, .. } => weights[&source],
+            _ => unreachable!(),
+        };
+        assert!(source_weight > 0);
 
-        // Setup block progression before executing command
-        if let Some(mock_service) = get_mock_service(&budget_system) {
-            setup_block_progression(mock_service).await;
+        budget_system.add_sponsor("Grant", "Source").unwrap();
+        let mut details = BudgetRequestDetails::default();
+        details.set_team(Some(source));
+        budget_system.state.get_proposal_mut(&proposal_id).unwrap().set_budget_request_details(Some(details));
+
+        let epoch = budget_system.state.get_epoch_mut(&epoch_id).unwrap();
+        epoch.set_team_reward(target, 60.0, 6.0).unwrap();
+        epoch.set_team_reward(source, 40.0, 4.0).unwrap();
+        epoch.record_team_payout(&source, 4.0, "0xabc".to_string()).unwrap();
+        epoch.set_reward_adjustment(source, 0.5, "Late report".to_string()).unwrap();
+
+        budget_system.merge_teams("Source", "Target").unwrap();
+
+        match budget_system.state().get_vote(&weighted_vote).unwrap().vote_type() {
+            VoteType::Weighted { weights, .. } => {
+                assert!(!weights.contains_key(&source));
+                assert!(weights[&target] >= source_weight);
+            },
+            _ => unreachable!(),
+        }
+        match budget_system.state().get_vote(&revenue_vote).unwrap().vote_type() {
+            VoteType::Informal { weighting: InformalWeighting::Revenue { weights } } => {
+                assert_eq!(weights.get(&source), None);
+                assert_eq!(weights[&target], 1500);
+            },
+            _ => unreachable!(),
         }
 
-        let progress_stream = budget_system.create_raffle_with_progress(
-            "NonExistent".to_string(),
-            None,
-            None,
-        ).await;
+        let proposal = budget_system.get_proposal(&proposal_id).unwrap();
+        assert_eq!(proposal.sponsors(), &HashSet::from([target]));
+        assert_eq!(proposal.budget_request_details().unwrap().team(), Some(target));
 
-        pin_mut!(progress_stream);
-        
-        // Should fail on first update
-        let first_update = progress_stream.next().await.unwrap();
-        assert!(first_update.is_err());
+        let epoch = budget_system.get_epoch(&epoch_id).unwrap();
+        assert!(!epoch.team_rewards().contains_key(&source));
+        let reward = &epoch.team_rewards()[&target];
+        assert_eq!((reward.percentage(), reward.amount(), reward.paid()), (100.0, 10.0, 4.0));
+        assert_eq!(reward.payout_txs(), ["0xabc".to_string()]);
+        assert_eq!(epoch.reward_adjustments().get(&target), Some(&0.5));
+        assert_eq!(epoch.reward_adjustment_reason(&target), Some("Late report"));
     }
 
     #[tokio::test]
-    async fn test_generate_unpaid_requests_report() {
+    async fn test_preview_epoch_rewards() {
         let temp_dir = TempDir::new().unwrap();
         let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
         let mut budget_system = create_test_budget_system(&state_file, None).await;
-
-        // Create an epoch
         let epoch_id = create_active_epoch(&mut budget_system).await;
+        let team_a = budget_system.create_team("Team A".to_string(), "Rep A".to_string(), Some(vec![1000.0]), None).unwrap();
+        let team_b = budget_system.create_team("Team B".to_string(), "Rep B".to_string(), Some(vec![1000.0]), None).unwrap();
+        assert!(budget_system.preview_epoch_rewards(epoch_id).unwrap().is_empty());
+
+        for (name, voters) in [("First", vec![team_a, team_b]), ("Second", vec![team_a])] {
+            let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, name).await;
+            let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
+            budget_system.cast_votes(vote_id, voters.into_iter().map(|team| (team, VoteChoice::Yes)).collect()).unwrap();
+            budget_system.close_vote(vote_id).unwrap();
+            budget_system.close_with_reason(proposal_id, &Resolution::Approved).unwrap();
+        }
 
-        // Create a team
-        let team_id = budget_system.create_team(
-            "Test Team".to_string(),
-            "Representative".to_string(),
-            Some(vec![1000]),
-            None
-        ).unwrap();
+        // Without a reward the shares are still shown, with nothing to pay
+        let preview = budget_system.preview_epoch_rewards(epoch_id).unwrap();
+        assert_eq!(preview.len(), 2);
+        assert_eq!(preview[0].0, "Team A");
+        assert!((preview[0].1 - 2.0 * preview[1].1).abs() < 1e-9);
+        assert!(preview.iter().all(|(_, _, amount)| *amount == 0.0));
+        let output = budget_system.execute_command(Command::PreviewEpochRewards { epoch_name: None }).await.unwrap();
+        assert!(output.contains("No reward is set for this epoch"));
+
+        budget_system.set_epoch_reward("ETH", 90.0).unwrap();
+        let preview = budget_system.preview_epoch_rewards(epoch_id).unwrap();
+        assert!(budget_system.get_epoch(&epoch_id).unwrap().is_active());
+
+        budget_system.close_epoch(None, None).unwrap();
+        let rewards = budget_system.get_epoch(&epoch_id).unwrap().team_rewards().clone();
+        for (team_id, (name, percentage, amount)) in [team_a, team_b].into_iter().zip(preview) {
+            assert_eq!(budget_system.get_team(&team_id).unwrap().name(), name);
+            assert_eq!(rewards[&team_id].percentage(), percentage);
+            assert_eq!(rewards[&team_id].amount(), amount);
+        }
+    }
 
-        // Create a proposal with budget request
-        let mut amounts = HashMap::new();
-        amounts.insert("ETH".to_string(), 100.0);
-        
-        let proposal_id = budget_system.add_proposal(
-            "Test Proposal".to_string(),
-            None,
-            Some(BudgetRequestDetails::new(
-                Some(team_id),
-                amounts,
-                None,
-                None,
-                Some(false),
-                Some("0x742d35Cc6634C0532925a3b844Bc454e4438f44e".to_string()),
-            ).unwrap()),
-            Some(Utc::now().date_naive()),
-            Some(Utc::now().date_naive()),
-            None,
-        ).unwrap();
+    #[tokio::test]
+    async fn test_archived_team_keeps_epoch_reward() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_file = temp_dir.path().join("test_state.json").to_str().unwrap().to_string();
+        let mut budget_system = create_test_budget_system(&state_file, None).await;
+        let epoch_id = create_active_epoch(&mut budget_system).await;
+        let team_a = budget_system.create_team("Team A".to_string(), "Rep A".to_string(), Some(vec![1000.0]), None).unwrap();
+        let team_b = budget_system.create_team("Team B".to_string(), "Rep B".to_string(), Some(vec![1000.0]), None).unwrap();
 
-        // Approve the proposal
+        let (proposal_id, raffle_id) = create_proposal_with_raffle(&mut budget_system, "First").await;
+        let vote_id = budget_system.create_formal_vote(proposal_id, raffle_id, None).unwrap();
+        budget_system.cast_votes(vote_id, vec![(team_a, VoteChoice::Yes), (team_b, VoteChoice::Yes)]).unwrap();
+        budget_system.close_vote(vote_id).unwrap();
         budget_system.close_with_reason(proposal_id, &Resolution::Approved).unwrap();
+        let total_points = budget_system.get_total_points_for_epoch(epoch_id);
 
-        // Generate report
-        let output_path = temp_dir.path().join("test_report.json");
-        let result = budget_system.generate_unpaid_requests_report(
-            Some(output_path.to_str().unwrap()),
-            None,
-        );
-
-        assert!(result.is_ok());
+        // Archiving Team B mid-epoch neither forfeits its share nor hands it to Team A
+        budget_system.archive_team(team_b).unwrap();
+        assert_eq!(budget_system.get_total_points_for_epoch(epoch_id), total_points);
+        budget_system.set_epoch_reward("ETH", 100.0).unwrap();
+        let preview = budget_system.preview_epoch_rewards(epoch_id).unwrap();
+        assert_eq!(preview.len(), 2);
 
-        // Verify report contents
-        let report_content = fs::read_to_string(output_path).unwrap();
-        let report: UnpaidRequestsReport = serde_json::from_str(&report_content).unwrap();
-        
-        assert_eq!(report.unpaid_requests.len(), 1);
-        assert_eq!(report.unpaid_requests[0].title, "Test Proposal");
-        assert_eq!(report.unpaid_requests[0].team_name, "Test Team");
+        budget_system.close_epoch(None, None).unwrap();
+        let rewards = budget_system.get_epoch(&epoch_id).unwrap().team_rewards().clone();
+        assert_eq!(rewards[&team_a].amount(), 50.0);
+        assert_eq!(rewards[&team_b].amount(), 50.0);
     }
-}
\ No newline at end of file
+}
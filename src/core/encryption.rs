@@ -0,0 +1,115 @@
+// src/core/encryption.rs
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use std::{env, error::Error, fmt, fs};
+
+/// Environment variable holding the hex state key when `state_encryption_key = "env"`
+pub const STATE_ENCRYPTION_KEY_VAR: &str = "STATE_ENCRYPTION_KEY";
+
+/// Starts every encrypted state file, followed by the nonce and the AES-256-GCM ciphertext
+const MAGIC: &[u8] = b"RKSTATE1";
+const NONCE_LEN: usize = 12;
+
+/// Key that encrypts the JSON state file at rest
+#[derive(Clone, PartialEq, Eq)]
+pub struct StateKey([u8; 32]);
+
+impl StateKey {
+    /// Parses a 256-bit key written as 64 hex characters
+    pub fn from_hex(hex: &str) -> Result<Self, &'static str> {
+        let hex = hex.trim();
+        if hex.len() != 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err("State encryption key must be 64 hex characters");
+        }
+        let mut key = [0u8; 32];
+        for (i, byte) in key.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16)
+                .map_err(|_| "State encryption key must be 64 hex characters")?;
+        }
+        Ok(Self(key))
+    }
+
+    /// Loads the key named by `state_encryption_key`: empty keeps the state in plaintext,
+    /// `env` reads the hex key from `STATE_ENCRYPTION_KEY`, and anything else is a file holding it
+    pub fn from_setting(setting: &str) -> Result<Option<Self>, Box<dyn Error>> {
+        let hex = match setting {
+            "" => return Ok(None),
+            "env" => env::var(STATE_ENCRYPTION_KEY_VAR)
+                .map_err(|_| format!("{} must be set when state_encryption_key is \"env\"", STATE_ENCRYPTION_KEY_VAR))?,
+            key_file => fs::read_to_string(key_file)
+                .map_err(|e| format!("Failed to read state encryption key file {}: {}", key_file, e))?,
+        };
+        Ok(Some(Self::from_hex(&hex)?))
+    }
+
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self.cipher().encrypt(&nonce, plaintext)
+            .map_err(|_| "Failed to encrypt the state")?;
+
+        let mut encrypted = Vec::with_capacity(MAGIC.len() + NONCE_LEN + ciphertext.len());
+        encrypted.extend_from_slice(MAGIC);
+        encrypted.extend_from_slice(&nonce);
+        encrypted.extend_from_slice(&ciphertext);
+        Ok(encrypted)
+    }
+
+    pub fn decrypt(&self, encrypted: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+        let body = encrypted.strip_prefix(MAGIC).ok_or("State file is not encrypted")?;
+        if body.len() < NONCE_LEN {
+            return Err("Encrypted state file is truncated".into());
+        }
+        let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+        let plaintext = self.cipher().decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| "Failed to decrypt the state file: wrong key or corrupted file")?;
+        Ok(plaintext)
+    }
+
+    fn cipher(&self) -> Aes256Gcm {
+        Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&self.0))
+    }
+}
+
+/// Never prints the key material
+impl fmt::Debug for StateKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("StateKey(..)")
+    }
+}
+
+/// Whether the file content was written by `StateKey::encrypt`
+pub fn is_encrypted(content: &[u8]) -> bool {
+    content.starts_with(MAGIC)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_key() -> StateKey {
+        StateKey::from_hex(&"0123456789abcdef".repeat(4)).unwrap()
+    }
+
+    #[test]
+    fn test_encrypt_round_trip() {
+        let key = test_key();
+        let encrypted = key.encrypt(b"{\"teams\":{}}").unwrap();
+
+        assert!(is_encrypted(&encrypted));
+        assert!(!encrypted.windows(5).any(|window| window == b"teams"));
+        assert_eq!(key.decrypt(&encrypted).unwrap(), b"{\"teams\":{}}");
+
+        let other = StateKey::from_hex(&"f".repeat(64)).unwrap();
+        assert!(other.decrypt(&encrypted).is_err());
+        assert!(key.decrypt(b"{\"teams\":{}}").is_err());
+    }
+
+    #[test]
+    fn test_key_parsing() {
+        assert!(StateKey::from_hex("abcd").is_err());
+        assert!(StateKey::from_hex(&"g".repeat(64)).is_err());
+        assert_eq!(StateKey::from_setting("").unwrap(), None);
+        assert_eq!(format!("{:?}", test_key()), "StateKey(..)");
+    }
+}
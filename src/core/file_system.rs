@@ -5,36 +5,168 @@ use crate::core::state::BudgetSystemState;
 use crate::app_config::AppConfig;
 use crate::services::ethereum::EthereumServiceTrait;
 use crate::commands::common::Command;
+use crate::core::store::open_store;
+use crate::core::encryption::{self, StateKey};
+use crate::core::migration::{self, MigrationError, CURRENT_SCHEMA_VERSION};
 
 use serde_json;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::error::Error;
-use log::{debug, info, error};
+use log::{debug, info, warn, error};
 use uuid::Uuid;
+use sha2::{Sha256, Digest};
 
 pub struct FileSystem;
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum StateChecksum {
+    Valid,
+    Mismatch { expected: String, actual: String },
+    Missing,
+}
+
 impl FileSystem {
     pub fn save_state(state: &BudgetSystemState, state_file: &str) -> Result<(), Box<dyn Error>> {
+        Self::save_state_with_key(state, state_file, None)
+    }
+
+    /// Like `save_state`, encrypting the file when a key is given
+    pub fn save_state_with_key(state: &BudgetSystemState, state_file: &str, key: Option<&StateKey>) -> Result<(), Box<dyn Error>> {
         let json = serde_json::to_string_pretty(state)?;
+        let content = match key {
+            Some(key) => key.encrypt(json.as_bytes())?,
+            None => json.into_bytes(),
+        };
         
         if let Some(parent) = Path::new(state_file).parent() {
             fs::create_dir_all(parent)?;
         }
         
         let temp_file = format!("{}.temp", state_file);
-        fs::write(&temp_file, &json)?;
+        fs::write(&temp_file, &content)?;
         fs::rename(&temp_file, state_file)?;
+
+        let checksum_file = Self::checksum_file_path(state_file);
+        let temp_checksum_file = format!("{}.temp", checksum_file);
+        fs::write(&temp_checksum_file, Self::compute_checksum(&content))?;
+        fs::rename(&temp_checksum_file, &checksum_file)?;
         
         Ok(())
     }
 
     pub fn load_state(path: &str) -> Result<BudgetSystemState, Box<dyn Error>> {
-        let json = fs::read_to_string(path)?;
-        let state: BudgetSystemState = serde_json::from_str(&json)?;
-        Ok(state)
+        Self::load_state_checked(path, false)
+    }
+
+    /// Loads state, checking it against the checksum sidecar. A mismatch is
+    /// logged as a warning, or returned as an error when `strict` is set.
+    pub fn load_state_checked(path: &str, strict: bool) -> Result<BudgetSystemState, Box<dyn Error>> {
+        Self::load_state_with_key(path, strict, None)
+    }
+
+    /// Like `load_state_checked`, decrypting the file with `key` when it is encrypted
+    pub fn load_state_with_key(path: &str, strict: bool, key: Option<&StateKey>) -> Result<BudgetSystemState, Box<dyn Error>> {
+        let content = fs::read(path)?;
+
+        match Self::check_content(path, &content)? {
+            StateChecksum::Mismatch { expected, actual } => {
+                if strict {
+                    return Err(format!("State file checksum mismatch for {}: expected {}, found {}", path, expected, actual).into());
+                }
+                warn!("State file checksum mismatch for {}: expected {}, found {}", path, expected, actual);
+            },
+            StateChecksum::Missing => debug!("No checksum file found for {}", path),
+            StateChecksum::Valid => {},
+        }
+
+        let json = Self::decode_content(path, content, key)?;
+        Ok(migration::migrate_state(&json)?)
+    }
+
+    /// The state JSON in a file's content, decrypted when the file is encrypted
+    fn decode_content(path: &str, content: Vec<u8>, key: Option<&StateKey>) -> Result<String, Box<dyn Error>> {
+        let json = if encryption::is_encrypted(&content) {
+            let key = key.ok_or_else(|| format!("State file {} is encrypted but no state_encryption_key is configured", path))?;
+            key.decrypt(&content)?
+        } else {
+            content
+        };
+        Ok(String::from_utf8(json)?)
+    }
+
+    /// Upgrades a state file to the current schema version in place, after copying the original
+    /// to `<path>.v<N>.bak`. With `dry_run`, only lists the migrations that would be applied.
+    pub fn migrate_state_file(path: &str, key: Option<&StateKey>, dry_run: bool) -> Result<String, Box<dyn Error>> {
+        let content = fs::read(path)?;
+        let encrypted = encryption::is_encrypted(&content);
+        let json = Self::decode_content(path, content, key)?;
+        let version = migration::schema_version(&json)?;
+
+        let pending = migration::pending_migrations(version);
+        if pending.is_empty() {
+            return Ok(format!("State file {} is already at schema version {}", path, version));
+        }
+
+        let mut output = format!("State file {} is at schema version {}; current is {}:\n", path, version, CURRENT_SCHEMA_VERSION);
+        for step in pending {
+            output.push_str(&format!("  v{} -> v{}: {}\n", step.from, step.from + 1, step.description));
+        }
+        if dry_run {
+            output.push_str("Dry run, nothing was written");
+            return Ok(output);
+        }
+
+        let state = migration::migrate_state(&json)?;
+        let backup = format!("{}.v{}.bak", path, version);
+        fs::copy(path, &backup)?;
+        // Keep the file encrypted only if it was, so a migration never changes how it is stored
+        Self::save_state_with_key(&state, path, key.filter(|_| encrypted))?;
+
+        output.push_str(&format!("Migrated to schema version {}; the original was saved to {}", CURRENT_SCHEMA_VERSION, backup));
+        Ok(output)
+    }
+
+    pub fn verify_state_checksum(path: &str) -> Result<StateChecksum, Box<dyn Error>> {
+        let content = fs::read(path)?;
+        Self::check_content(path, &content)
+    }
+
+    pub fn verify_state_file(path: &str) -> Result<String, Box<dyn Error>> {
+        match Self::verify_state_checksum(path)? {
+            StateChecksum::Valid => Ok(format!("State file {} matches its checksum", path)),
+            StateChecksum::Missing => Ok(format!("No checksum file found for {}", path)),
+            StateChecksum::Mismatch { expected, actual } => {
+                Err(format!("State file checksum mismatch for {}: expected {}, found {}", path, expected, actual).into())
+            }
+        }
+    }
+
+    pub fn checksum_file_path(state_file: &str) -> String {
+        format!("{}.sha256", state_file)
+    }
+
+    fn compute_checksum(content: &[u8]) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(content);
+        format!("{:x}", hasher.finalize())
+    }
+
+    fn check_content(path: &str, content: &[u8]) -> Result<StateChecksum, Box<dyn Error>> {
+        let checksum_file = Self::checksum_file_path(path);
+        if !Path::new(&checksum_file).exists() {
+            return Ok(StateChecksum::Missing);
+        }
+
+        let expected = fs::read_to_string(&checksum_file)?.trim().to_string();
+        let actual = Self::compute_checksum(content);
+
+        if expected == actual {
+            Ok(StateChecksum::Valid)
+        } else {
+            Ok(StateChecksum::Mismatch { expected, actual })
+        }
     }
 
     pub fn try_load_state(path: &str) -> Option<BudgetSystemState> {
@@ -47,12 +179,38 @@ impl FileSystem {
         }
     }
 
+    pub fn try_load_state_with_config(config: &AppConfig) -> Result<Option<BudgetSystemState>, Box<dyn Error>> {
+        let key = StateKey::from_setting(&config.state_encryption_key)?;
+        Self::try_load_state_strict(&config.state_file, config.strict_state_checksum, key.as_ref())
+    }
+
+    pub fn try_load_state_strict(path: &str, strict: bool, key: Option<&StateKey>) -> Result<Option<BudgetSystemState>, Box<dyn Error>> {
+        // Refuse to fall back to a fresh state when a strict checksum check fails,
+        // otherwise the next save would overwrite the tampered file
+        if strict && Path::new(path).exists() {
+            if let StateChecksum::Mismatch { expected, actual } = Self::verify_state_checksum(path)? {
+                return Err(format!("State file checksum mismatch for {}: expected {}, found {}", path, expected, actual).into());
+            }
+        }
+        if Path::new(path).exists() {
+            let content = fs::read(path)?;
+            // Likewise an encrypted file that can't be decrypted must not be replaced by a fresh state
+            if encryption::is_encrypted(&content) {
+                return Self::load_state_with_key(path, strict, key).map(Some);
+            }
+            // Nor a file written by a newer build
+            if let Err(e @ MigrationError::UnsupportedVersion { .. }) = migration::schema_version(&String::from_utf8_lossy(&content)) {
+                return Err(e.into());
+            }
+        }
+        Ok(Self::try_load_state(path))
+    }
+
     pub async fn initialize_budget_system(
         config: &AppConfig,
         ethereum_service: Arc<dyn EthereumServiceTrait>
     ) -> Result<BudgetSystem, Box<dyn Error>> {
-        let state = Self::try_load_state(&config.state_file);
-        BudgetSystem::new(config.clone(), ethereum_service, state).await
+        BudgetSystem::from_store(config.clone(), ethereum_service, open_store(config)?).await
     }
 
     pub fn generate_report_file_path(
@@ -100,13 +258,21 @@ impl FileSystem {
         state_file: &Path
     ) -> Result<PathBuf, Box<dyn std::error::Error>> {
         let file_path = Self::generate_report_file_path(proposal, epoch_name, state_file);
+        Self::save_report_to_file(&file_path, report_content)?;
+        Ok(file_path)
+    }
 
-        if let Some(parent) = file_path.parent() {
-            std::fs::create_dir_all(parent)?;
+    /// Writes a report unless the file already holds exactly `content`; returns whether it was written
+    pub fn save_report_to_file(path: &Path, content: &str) -> Result<bool, Box<dyn Error>> {
+        if fs::read(path).is_ok_and(|existing| existing == content.as_bytes()) {
+            return Ok(false);
         }
-        std::fs::write(&file_path, report_content)?;
 
-        Ok(file_path)
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, content)?;
+        Ok(true)
     }
 
     pub fn load_script(script_file: &str) -> Result<Vec<Command>, Box<dyn Error>> {
@@ -124,6 +290,39 @@ impl FileSystem {
             .collect()
     }
     
+    /// Writes every file under `dir` into a zip archive at `archive_path`, with paths relative to `dir`
+    pub fn zip_directory(dir: &Path, archive_path: &Path) -> Result<(), Box<dyn Error>> {
+        let mut files = Vec::new();
+        let mut pending = vec![dir.to_path_buf()];
+        while let Some(current) = pending.pop() {
+            for entry in fs::read_dir(&current)? {
+                let path = entry?.path();
+                if path.is_dir() {
+                    pending.push(path);
+                } else {
+                    files.push(path);
+                }
+            }
+        }
+        files.sort();
+
+        let mut zip = zip::ZipWriter::new(fs::File::create(archive_path)?);
+        let options = zip::write::FileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated);
+        for path in files {
+            let name = path.strip_prefix(dir)?
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            zip.start_file(name, options)?;
+            std::io::Write::write_all(&mut zip, &fs::read(&path)?)?;
+        }
+        zip.finish()?;
+
+        Ok(())
+    }
+
     pub fn sanitize_filename(name: &str) -> String {
         let sanitized: String = name.chars()
             .map(|c| match c {
@@ -161,7 +360,7 @@ mod tests {
         let team = Team::new(
             "Test Team".to_string(),
             "John Doe".to_string(),
-            Some(vec![1000, 2000, 3000]),
+            Some(vec![1000.0, 2000.0, 3000.0]),
             None
         ).unwrap();
         state.add_team(team);
@@ -211,6 +410,85 @@ mod tests {
             );
         }
 
+        #[test]
+        fn test_state_checksum_detects_corruption() {
+            let temp_dir = setup_temp_dir();
+            let state_file = temp_dir.path().join("test_state.json");
+            let path = state_file.to_str().unwrap();
+
+            FileSystem::save_state(&create_mock_state(), path).unwrap();
+            assert!(Path::new(&FileSystem::checksum_file_path(path)).exists());
+            assert_eq!(FileSystem::verify_state_checksum(path).unwrap(), StateChecksum::Valid);
+
+            // Corrupt the file while keeping it valid JSON
+            let mut file = fs::OpenOptions::new().append(true).open(&state_file).unwrap();
+            writeln!(file).unwrap();
+
+            assert!(matches!(FileSystem::verify_state_checksum(path).unwrap(), StateChecksum::Mismatch { .. }));
+            assert!(FileSystem::load_state_checked(path, true).is_err());
+            assert!(FileSystem::load_state_checked(path, false).is_ok());
+
+            assert!(FileSystem::verify_state_file(path).is_err());
+
+            fs::remove_file(FileSystem::checksum_file_path(path)).unwrap();
+            assert_eq!(FileSystem::verify_state_checksum(path).unwrap(), StateChecksum::Missing);
+        }
+
+        #[test]
+        fn test_encrypted_state_round_trip() {
+            let temp_dir = setup_temp_dir();
+            let state_file = temp_dir.path().join("test_state.json");
+            let path = state_file.to_str().unwrap();
+            let key = StateKey::from_hex(&"ab".repeat(32)).unwrap();
+            let state = create_mock_state();
+
+            FileSystem::save_state_with_key(&state, path, Some(&key)).unwrap();
+
+            let content = fs::read(&state_file).unwrap();
+            assert!(encryption::is_encrypted(&content));
+            assert!(serde_json::from_slice::<serde_json::Value>(&content).is_err());
+            assert_eq!(FileSystem::verify_state_checksum(path).unwrap(), StateChecksum::Valid);
+
+            let loaded = FileSystem::load_state_with_key(path, true, Some(&key)).unwrap();
+            assert_eq!(loaded.current_state().teams(), state.current_state().teams());
+
+            // A missing or wrong key is an error rather than a fresh state
+            let wrong_key = StateKey::from_hex(&"cd".repeat(32)).unwrap();
+            assert!(FileSystem::load_state(path).is_err());
+            assert!(FileSystem::try_load_state_strict(path, false, None).is_err());
+            assert!(FileSystem::try_load_state_strict(path, false, Some(&wrong_key)).is_err());
+
+            // Plaintext files still load with a key configured
+            FileSystem::save_state(&state, path).unwrap();
+            assert!(FileSystem::try_load_state_strict(path, false, Some(&key)).unwrap().is_some());
+        }
+
+        #[test]
+        fn test_migrate_state_file() {
+            let temp_dir = setup_temp_dir();
+            let state_file = temp_dir.path().join("test_state.json");
+            let path = state_file.to_str().unwrap();
+
+            let mut value = serde_json::to_value(create_mock_state()).unwrap();
+            value.as_object_mut().unwrap().remove("schema_version");
+            fs::write(&state_file, value.to_string()).unwrap();
+            assert_eq!(FileSystem::load_state(path).unwrap().schema_version(), CURRENT_SCHEMA_VERSION);
+
+            let output = FileSystem::migrate_state_file(path, None, true).unwrap();
+            assert!(output.contains("v0 -> v1"));
+            assert!(!Path::new(&format!("{}.v0.bak", path)).exists());
+
+            FileSystem::migrate_state_file(path, None, false).unwrap();
+            assert_eq!(fs::read_to_string(format!("{}.v0.bak", path)).unwrap(), value.to_string());
+            assert_eq!(migration::schema_version(&fs::read_to_string(path).unwrap()).unwrap(), CURRENT_SCHEMA_VERSION);
+            assert!(FileSystem::migrate_state_file(path, None, false).unwrap().contains("already at schema version"));
+
+            // A file from a newer build is refused rather than replaced by a fresh state
+            value["schema_version"] = serde_json::json!(CURRENT_SCHEMA_VERSION + 1);
+            fs::write(&state_file, value.to_string()).unwrap();
+            assert!(FileSystem::try_load_state_strict(path, false, None).is_err());
+        }
+
         #[test]
         fn test_try_load_state_non_existent_file() {
             let temp_dir = setup_temp_dir();
@@ -388,6 +666,21 @@ mod tests {
             assert_eq!(saved_content, new_content);
         }
 
+        #[test]
+        fn test_save_report_skips_identical_content() {
+            let temp_dir = setup_temp_dir();
+            let file_path = temp_dir.path().join("reports").join("report.md");
+
+            assert!(FileSystem::save_report_to_file(&file_path, "Report").unwrap());
+            let modified = std::fs::metadata(&file_path).unwrap().modified().unwrap();
+
+            assert!(!FileSystem::save_report_to_file(&file_path, "Report").unwrap());
+            assert_eq!(std::fs::metadata(&file_path).unwrap().modified().unwrap(), modified);
+
+            assert!(FileSystem::save_report_to_file(&file_path, "Updated report").unwrap());
+            assert_eq!(std::fs::read_to_string(&file_path).unwrap(), "Updated report");
+        }
+
         #[test]
         fn test_report_content_integrity() {
             let temp_dir = setup_temp_dir();
@@ -597,8 +890,25 @@ mod tests {
                 default_total_counted_seats: 7,
                 default_max_earner_seats: 5,
                 default_qualified_majority_threshold: 0.7,
+                default_quorum: 0.0,
                 counted_vote_points: 5,
                 uncounted_vote_points: 2,
+                strict_state_checksum: false,
+                unique_representatives: false,
+                undo_history_size: 10,
+                storage_backend: crate::app_config::StorageBackend::Json,
+                ipfs_gateway: String::new(),
+                raffle_tie_break: crate::core::models::TieBreak::LowestTeamId,
+                raffle_block_timeout_secs: 300,
+                min_sponsors: 0,
+                default_decision_sla_days: 14,
+                revenue_tiers: Vec::new(),
+                audit_log: String::new(),
+                payout_signer: String::new(),
+                reward_rounding: crate::core::models::RewardRounding::None,
+                redistribute_withheld_rewards: true,
+                state_encryption_key: String::new(),
+                token_rates: HashMap::new(),
                 telegram: crate::app_config::TelegramConfig {
                     chat_id: "test_chat_id".to_string(),
                     token: "test_token".to_string(),
@@ -614,7 +924,7 @@ mod tests {
             let mut budget_system = create_mock_budget_system(&temp_dir).await;
 
             // Modify the state
-            budget_system.create_team("Test Team".to_string(), "John Doe".to_string(), Some(vec![1000, 2000, 3000]), None).unwrap();
+            budget_system.create_team("Test Team".to_string(), "John Doe".to_string(), Some(vec![1000.0, 2000.0, 3000.0]), None).unwrap();
 
             // Save the state
             budget_system.save_state().unwrap();
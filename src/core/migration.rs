@@ -0,0 +1,191 @@
+// src/core/migration.rs
+
+use crate::core::state::BudgetSystemState;
+
+use serde_json::{json, Value};
+use std::{error::Error, fmt};
+
+/// Schema version written by this build
+pub const CURRENT_SCHEMA_VERSION: u32 = 1;
+
+/// Upgrades state JSON from `from` to `from + 1`
+pub struct Migration {
+    pub from: u32,
+    pub description: &'static str,
+    apply: fn(&mut Value) -> Result<(), String>,
+}
+
+/// Every migration in order; the one at index N upgrades version N
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        from: 0,
+        description: "record the schema version and give informal votes a weighting",
+        apply: v0_to_v1,
+    },
+];
+
+#[derive(Debug)]
+pub enum MigrationError {
+    /// The file is not a JSON object
+    InvalidJson(String),
+    /// Written by a newer build than this one
+    UnsupportedVersion { found: u32, supported: u32 },
+    /// A migration step rejected the state
+    Failed { from: u32, message: String },
+    /// The upgraded JSON still does not match the current schema
+    Deserialize { version: u32, message: String },
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MigrationError::InvalidJson(message) => write!(f, "State file is not valid JSON: {}", message),
+            MigrationError::UnsupportedVersion { found, supported } => write!(
+                f, "State file has schema version {}, but this build only supports up to version {}", found, supported
+            ),
+            MigrationError::Failed { from, message } => write!(
+                f, "Failed to migrate state from schema version {} to {}: {}", from, from + 1, message
+            ),
+            MigrationError::Deserialize { version, message } => write!(
+                f, "State file with schema version {} does not match the current schema: {}", version, message
+            ),
+        }
+    }
+}
+
+impl Error for MigrationError {}
+
+/// Schema version of the state JSON; files without a `schema_version` field are version 0
+pub fn schema_version(json: &str) -> Result<u32, MigrationError> {
+    version_of(&parse(json)?)
+}
+
+/// Migrations that would upgrade a state at `version` to the current one, oldest first
+pub fn pending_migrations(version: u32) -> &'static [Migration] {
+    MIGRATIONS.get(version as usize..).unwrap_or(&[])
+}
+
+/// Parses state JSON of any supported schema version, applying the migrations it is missing in order
+pub fn migrate_state(json: &str) -> Result<BudgetSystemState, MigrationError> {
+    let mut value = parse(json)?;
+    let version = version_of(&value)?;
+
+    for migration in pending_migrations(version) {
+        (migration.apply)(&mut value)
+            .map_err(|message| MigrationError::Failed { from: migration.from, message })?;
+    }
+
+    serde_json::from_value(value)
+        .map_err(|e| MigrationError::Deserialize { version, message: e.to_string() })
+}
+
+fn parse(json: &str) -> Result<Value, MigrationError> {
+    let value: Value = serde_json::from_str(json).map_err(|e| MigrationError::InvalidJson(e.to_string()))?;
+    if !value.is_object() {
+        return Err(MigrationError::InvalidJson("expected an object".to_string()));
+    }
+    Ok(value)
+}
+
+fn version_of(value: &Value) -> Result<u32, MigrationError> {
+    let version = match value.get("schema_version") {
+        None => 0,
+        Some(version) => version.as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| MigrationError::InvalidJson("schema_version must be a non-negative integer".to_string()))?,
+    };
+    if version > CURRENT_SCHEMA_VERSION {
+        return Err(MigrationError::UnsupportedVersion { found: version, supported: CURRENT_SCHEMA_VERSION });
+    }
+    Ok(version)
+}
+
+/// Version 0 is every state saved before versioning. Most of its older layouts are still read by
+/// the serde defaults on the state types; informal votes stored as the bare `"Informal"` variant
+/// are rewritten to the unweighted form here.
+fn v0_to_v1(value: &mut Value) -> Result<(), String> {
+    let state = value.as_object_mut().ok_or("expected an object")?;
+
+    if let Some(votes) = state.get_mut("votes").and_then(Value::as_object_mut) {
+        for vote in votes.values_mut() {
+            let vote_type = vote.get_mut("vote_type").ok_or("vote without a vote_type")?;
+            if vote_type.as_str() == Some("Informal") {
+                *vote_type = json!({ "Informal": { "weighting": "Unweighted" } });
+            }
+        }
+    }
+
+    state.insert("schema_version".to_string(), Value::from(1));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::models::{InformalWeighting, Team, Vote, VoteType};
+    use uuid::Uuid;
+
+    #[test]
+    fn test_migrations_cover_every_version() {
+        assert_eq!(MIGRATIONS.len() as u32, CURRENT_SCHEMA_VERSION);
+        for (index, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.from, index as u32);
+        }
+    }
+
+    #[test]
+    fn test_migrate_v0_state() {
+        let mut state = BudgetSystemState::new();
+        let team_id = state.add_team(Team::new("Team A".to_string(), "Rep".to_string(), None, None).unwrap());
+        let mut value = serde_json::to_value(&state).unwrap();
+        value.as_object_mut().unwrap().remove("schema_version");
+        let json = value.to_string();
+
+        assert_eq!(schema_version(&json).unwrap(), 0);
+        assert_eq!(pending_migrations(0).len(), 1);
+
+        let migrated = migrate_state(&json).unwrap();
+        assert_eq!(migrated.schema_version(), CURRENT_SCHEMA_VERSION);
+        assert_eq!(migrated.get_team(&team_id), state.get_team(&team_id));
+
+        let current = serde_json::to_string(&migrated).unwrap();
+        assert_eq!(schema_version(&current).unwrap(), CURRENT_SCHEMA_VERSION);
+        assert!(pending_migrations(CURRENT_SCHEMA_VERSION).is_empty());
+    }
+
+    #[test]
+    fn test_migrate_v0_informal_vote() {
+        let mut state = BudgetSystemState::new();
+        let vote_id = state.add_vote(&Vote::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            VoteType::Informal { weighting: InformalWeighting::Unweighted },
+            false,
+        ));
+        let mut value = serde_json::to_value(&state).unwrap();
+        let fixture = value.as_object_mut().unwrap();
+        fixture.remove("schema_version");
+        fixture["votes"][vote_id.to_string()]["vote_type"] = json!("Informal");
+        let json = value.to_string();
+
+        assert_eq!(schema_version(&json).unwrap(), 0);
+        let migrated = migrate_state(&json).unwrap();
+        assert_eq!(
+            migrated.get_vote(&vote_id).unwrap().vote_type(),
+            &VoteType::Informal { weighting: InformalWeighting::Unweighted }
+        );
+    }
+
+    #[test]
+    fn test_migration_errors() {
+        assert!(matches!(migrate_state("[]"), Err(MigrationError::InvalidJson(_))));
+        assert!(matches!(
+            migrate_state(r#"{"schema_version": 99}"#),
+            Err(MigrationError::UnsupportedVersion { found: 99, .. })
+        ));
+
+        let error = migrate_state(r#"{"proposals": {}}"#).err().unwrap();
+        assert!(matches!(error, MigrationError::Deserialize { version: 0, .. }));
+        assert!(error.to_string().contains("schema version 0"));
+    }
+}
@@ -3,4 +3,8 @@ pub mod models;
 pub mod progress;
 pub mod state;
 pub mod budget_system;
-pub mod file_system;
\ No newline at end of file
+pub mod file_system;
+pub mod store;
+pub mod audit;
+pub mod encryption;
+pub mod migration;
\ No newline at end of file
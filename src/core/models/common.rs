@@ -1,11 +1,30 @@
 use uuid::Uuid;
-use std::{collections::HashMap, str::FromStr};
+use std::{collections::{BTreeMap, HashMap}, fmt, path::PathBuf, str::FromStr};
 use chrono::{DateTime, Utc};
 use serde::{Serialize, Deserialize};
 use ethers::types::{Address, H256};
 
 pub trait NameMatches {
     fn name_matches(&self, name: &str) -> bool;
+
+    /// Looser match used by search; entities without a searchable name fall back to `name_matches`
+    fn name_contains(&self, query: &str) -> bool {
+        self.name_matches(query)
+    }
+}
+
+/// Quotes a CSV field when it contains a comma, quote or newline
+pub fn escape_csv(field: &str) -> String {
+    if field.contains(',') || field.contains('"') || field.contains('\n') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Case-insensitive substring test shared by the `name_contains` implementations
+pub fn contains_ignore_case(name: &str, query: &str) -> bool {
+    name.to_lowercase().contains(&query.to_lowercase())
 }
 
 pub fn get_id_by_name<T: NameMatches>(map: &HashMap<Uuid, T>, name: &str) -> Option<Uuid> {
@@ -27,7 +46,8 @@ pub struct UnpaidRequest {
     pub title: String,
     pub url: Option<String>,
     pub team_name: String,
-    pub amounts: HashMap<String, f64>,
+    /// Ordered by token so the report serializes the same way every time
+    pub amounts: BTreeMap<String, f64>,
     pub payment_address: Option<String>,
     pub approved_date: String,
     pub is_loan: bool,
@@ -35,6 +55,269 @@ pub struct UnpaidRequest {
     pub epoch_name: String,
 }
 
+/// A vote a team took part in, as listed in its participation report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VoteParticipationEntry {
+    pub vote_id: Uuid,
+    pub proposal: String,
+    pub opened_at: DateTime<Utc>,
+    pub vote_type: String,
+    pub participation: String,
+    pub result: String,
+    pub points: u32,
+}
+
+/// A team, epoch or proposal looked up by name that does not exist
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotFoundError {
+    pub kind: &'static str,
+    pub name: String,
+}
+
+impl fmt::Display for NotFoundError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} not found: {}", self.kind, self.name)
+    }
+}
+
+impl std::error::Error for NotFoundError {}
+
+/// A reference in the state that points at nothing
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum IntegrityError {
+    VoteMissingProposal { vote_id: Uuid, proposal_id: Uuid },
+    RaffleMissingProposal { raffle_id: Uuid, proposal_id: Uuid },
+    EpochMissingProposal { epoch_id: Uuid, proposal_id: Uuid },
+    ProposalMissingEpoch { proposal_id: Uuid, epoch_id: Uuid },
+    RaffleUnknownTeam { raffle_id: Uuid, team_id: Uuid },
+    VoteUnknownParticipant { vote_id: Uuid, team_id: Uuid },
+    ProposalMissingDependency { proposal_id: Uuid, dependency_id: Uuid },
+}
+
+impl fmt::Display for IntegrityError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IntegrityError::VoteMissingProposal { vote_id, proposal_id } =>
+                write!(f, "Vote {} refers to missing proposal {}", vote_id, proposal_id),
+            IntegrityError::RaffleMissingProposal { raffle_id, proposal_id } =>
+                write!(f, "Raffle {} refers to missing proposal {}", raffle_id, proposal_id),
+            IntegrityError::EpochMissingProposal { epoch_id, proposal_id } =>
+                write!(f, "Epoch {} lists missing proposal {}", epoch_id, proposal_id),
+            IntegrityError::ProposalMissingEpoch { proposal_id, epoch_id } =>
+                write!(f, "Proposal {} belongs to missing epoch {}", proposal_id, epoch_id),
+            IntegrityError::RaffleUnknownTeam { raffle_id, team_id } =>
+                write!(f, "Raffle {} has a snapshot of unknown team {}", raffle_id, team_id),
+            IntegrityError::VoteUnknownParticipant { vote_id, team_id } =>
+                write!(f, "Vote {} records team {} that was not drawn in its raffle", vote_id, team_id),
+            IntegrityError::ProposalMissingDependency { proposal_id, dependency_id } =>
+                write!(f, "Proposal {} depends on missing proposal {}", proposal_id, dependency_id),
+        }
+    }
+}
+
+/// Outcome of a CSV import: the rows that were added, and the line number and reason of each rejected row
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CsvImportReport {
+    pub imported: Vec<Uuid>,
+    pub failed: Vec<(usize, String)>,
+}
+
+/// Outcome of regenerating an epoch's proposal reports: the title and path of each report written,
+/// the title and reason of each failure, and how many were unchanged or resolved too early
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ProposalReportsSummary {
+    pub generated: Vec<(String, PathBuf)>,
+    pub failed: Vec<(String, String)>,
+    pub skipped: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ActionPriority {
+    High,
+    Medium,
+    Low,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionItem {
+    pub priority: ActionPriority,
+    pub entity_id: Uuid,
+    pub description: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ActionItems {
+    pub overdue_proposals: Vec<ActionItem>,
+    pub votes_nearing_deadline: Vec<ActionItem>,
+    pub unfinalized_raffles: Vec<ActionItem>,
+    #[serde(default)]
+    pub placeholder_raffles: Vec<ActionItem>,
+    pub approved_unpaid_proposals: Vec<ActionItem>,
+    pub stale_earner_revenue: Vec<ActionItem>,
+    #[serde(default)]
+    pub reward_inconsistencies: Vec<ActionItem>,
+}
+
+impl ActionItem {
+    pub fn new(priority: ActionPriority, entity_id: Uuid, description: String) -> Self {
+        Self { priority, entity_id, description }
+    }
+}
+
+impl ActionItems {
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.overdue_proposals.len()
+            + self.votes_nearing_deadline.len()
+            + self.unfinalized_raffles.len()
+            + self.placeholder_raffles.len()
+            + self.approved_unpaid_proposals.len()
+            + self.stale_earner_revenue.len()
+            + self.reward_inconsistencies.len()
+    }
+
+    /// All items across every category, highest priority first
+    pub fn prioritized(&self) -> Vec<&ActionItem> {
+        let mut items: Vec<&ActionItem> = self.overdue_proposals.iter()
+            .chain(self.votes_nearing_deadline.iter())
+            .chain(self.unfinalized_raffles.iter())
+            .chain(self.placeholder_raffles.iter())
+            .chain(self.approved_unpaid_proposals.iter())
+            .chain(self.stale_earner_revenue.iter())
+            .chain(self.reward_inconsistencies.iter())
+            .collect();
+        // Stable sort keeps category order within the same priority
+        items.sort_by_key(|item| item.priority);
+        items
+    }
+
+    pub fn format_checklist(&self) -> String {
+        if self.is_empty() {
+            return "Action Items: nothing requires attention\n".to_string();
+        }
+
+        let mut output = format!("Action Items ({}):\n", self.len());
+        for item in self.prioritized() {
+            let priority = match item.priority {
+                ActionPriority::High => "HIGH",
+                ActionPriority::Medium => "MEDIUM",
+                ActionPriority::Low => "LOW",
+            };
+            output.push_str(&format!("- [ ] [{}] {}\n", priority, item.description));
+        }
+        output
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum SearchHitKind {
+    Team,
+    Proposal,
+    Epoch,
+    Raffle,
+    Vote,
+}
+
+impl fmt::Display for SearchHitKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let label = match self {
+            SearchHitKind::Team => "Team",
+            SearchHitKind::Proposal => "Proposal",
+            SearchHitKind::Epoch => "Epoch",
+            SearchHitKind::Raffle => "Raffle",
+            SearchHitKind::Vote => "Vote",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub kind: SearchHitKind,
+    pub id: Uuid,
+    pub name: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SearchResults {
+    pub query: String,
+    pub hits: Vec<SearchHit>,
+}
+
+impl SearchResults {
+    pub fn new(query: &str) -> Self {
+        Self { query: query.to_string(), hits: Vec::new() }
+    }
+
+    pub fn push(&mut self, kind: SearchHitKind, id: Uuid, name: &str) {
+        self.hits.push(SearchHit { kind, id, name: name.to_string() });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hits.is_empty()
+    }
+
+    pub fn of_kind(&self, kind: SearchHitKind) -> impl Iterator<Item = &SearchHit> {
+        self.hits.iter().filter(move |hit| hit.kind == kind)
+    }
+
+    /// Hits grouped by kind, each group sorted by name
+    pub fn format_grouped(&self) -> String {
+        if self.is_empty() {
+            return format!("No matches for '{}'\n", self.query);
+        }
+
+        let mut hits: Vec<&SearchHit> = self.hits.iter().collect();
+        hits.sort_by(|a, b| a.kind.cmp(&b.kind).then_with(|| a.name.cmp(&b.name)).then_with(|| a.id.cmp(&b.id)));
+
+        let mut output = format!("Search results for '{}' ({}):\n", self.query, self.hits.len());
+        let mut current_kind = None;
+        for hit in hits {
+            if current_kind != Some(hit.kind) {
+                output.push_str(&format!("{}s:\n", hit.kind));
+                current_kind = Some(hit.kind);
+            }
+            output.push_str(&format!("  - [{}] {} ({})\n", hit.kind, hit.name, hit.id));
+        }
+        output
+    }
+}
+
+/// How many of an epoch's proposals reached each stage. Stages are counted independently,
+/// so a proposal closed without a raffle still counts as approved.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Funnel {
+    pub submitted: usize,
+    pub raffled: usize,
+    pub voted: usize,
+    pub approved: usize,
+}
+
+impl Funnel {
+    pub fn format_report(&self) -> String {
+        let stages = [
+            ("Raffled", self.raffled),
+            ("Voted", self.voted),
+            ("Approved", self.approved),
+        ];
+        let mut output = format!("Proposal funnel:\nSubmitted: {}\n", self.submitted);
+        let mut previous = self.submitted;
+        for (stage, count) in stages {
+            if previous > 0 {
+                let drop_off = (1.0 - count as f64 / previous as f64) * 100.0;
+                output.push_str(&format!("{}: {} ({:.1}% drop-off)\n", stage, count, drop_off));
+            } else {
+                output.push_str(&format!("{}: {}\n", stage, count));
+            }
+            previous = count;
+        }
+        output
+    }
+}
+
 impl UnpaidRequestsReport {
     pub fn new(unpaid_requests: Vec<UnpaidRequest>) -> Self {
         Self {
@@ -62,7 +345,7 @@ impl UnpaidRequest {
             title,
             url,
             team_name,
-            amounts,
+            amounts: amounts.into_iter().collect(),
             payment_address,
             approved_date: approved_date.format("%Y-%m-%d").to_string(),
             is_loan,
@@ -193,6 +476,44 @@ mod tests {
         assert_eq!(deserialized.unpaid_requests.len(), 1);
     }
 
+    #[test]
+    fn test_action_items_prioritized() {
+        let mut items = ActionItems::default();
+        assert!(items.is_empty());
+
+        items.stale_earner_revenue.push(ActionItem::new(ActionPriority::Low, Uuid::new_v4(), "stale".to_string()));
+        items.unfinalized_raffles.push(ActionItem::new(ActionPriority::Medium, Uuid::new_v4(), "raffle".to_string()));
+        items.approved_unpaid_proposals.push(ActionItem::new(ActionPriority::High, Uuid::new_v4(), "unpaid".to_string()));
+
+        let ordered: Vec<&str> = items.prioritized().iter().map(|i| i.description.as_str()).collect();
+        assert_eq!(ordered, vec!["unpaid", "raffle", "stale"]);
+
+        let checklist = items.format_checklist();
+        assert!(checklist.starts_with("Action Items (3):"));
+        assert!(checklist.contains("- [ ] [HIGH] unpaid"));
+    }
+
+    #[test]
+    fn test_search_results_grouping() {
+        let mut results = SearchResults::new("alpha");
+        assert_eq!(results.format_grouped(), "No matches for 'alpha'\n");
+
+        let (team_b, team_a, proposal) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        results.push(SearchHitKind::Proposal, proposal, "Alpha Launch");
+        results.push(SearchHitKind::Team, team_b, "Alpha Team B");
+        results.push(SearchHitKind::Team, team_a, "Alpha Team A");
+        assert_eq!(results.of_kind(SearchHitKind::Team).count(), 2);
+
+        let output = results.format_grouped();
+        assert_eq!(output, format!(
+            "Search results for 'alpha' (3):\nTeams:\n  - [Team] Alpha Team A ({})\n  - [Team] Alpha Team B ({})\nProposals:\n  - [Proposal] Alpha Launch ({})\n",
+            team_a, team_b, proposal
+        ));
+
+        assert!(contains_ignore_case("Alpha Team", "TEAM"));
+        assert!(!contains_ignore_case("Alpha Team", "beta"));
+    }
+
     #[cfg(test)]
 mod tests {
     use super::*;
@@ -2,7 +2,9 @@ use chrono::{DateTime, Utc};
 use serde::{Serialize, Deserialize};
 use uuid::Uuid;
 use std::collections::HashMap;
-use super::common::NameMatches;
+use std::fmt;
+use std::str::FromStr;
+use super::common::{NameMatches, contains_ignore_case};
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Epoch {
@@ -14,6 +16,23 @@ pub struct Epoch {
     associated_proposals: Vec<Uuid>,
     reward: Option<EpochReward>,
     team_rewards: HashMap<Uuid, TeamReward>,
+    /// Spending cap per token for approved budget requests; tokens without a cap are unlimited
+    #[serde(default)]
+    budget_cap: HashMap<String, f64>,
+    /// Reward left undistributed when the epoch closed, per token
+    #[serde(default)]
+    unallocated: HashMap<String, f64>,
+    /// Epoch the unallocated reward was rolled into
+    #[serde(default)]
+    carried_over_to: Option<Uuid>,
+    /// How team reward amounts were rounded when the epoch closed
+    #[serde(default)]
+    reward_rounding: RewardRounding,
+    /// Multiplier (0.0 to 1.0) applied to a team's reward when the epoch closes
+    #[serde(default)]
+    reward_adjustments: HashMap<Uuid, f64>,
+    #[serde(default)]
+    reward_adjustment_reasons: HashMap<Uuid, String>,
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
@@ -33,6 +52,11 @@ pub struct EpochReward {
 pub struct TeamReward {
     percentage: f64,
     amount: f64,
+    /// Amount sent on chain so far; a top-up after a payout leaves the difference outstanding
+    #[serde(default)]
+    paid: f64,
+    #[serde(default)]
+    payout_txs: Vec<String>,
 }
 
 impl Epoch {
@@ -55,6 +79,12 @@ impl Epoch {
             associated_proposals: Vec::new(),
             reward: None,
             team_rewards: HashMap::new(),
+            budget_cap: HashMap::new(),
+            unallocated: HashMap::new(),
+            carried_over_to: None,
+            reward_rounding: RewardRounding::None,
+            reward_adjustments: HashMap::new(),
+            reward_adjustment_reasons: HashMap::new(),
         })
     }
 
@@ -91,6 +121,30 @@ impl Epoch {
         &self.team_rewards
     }
 
+    pub fn budget_cap(&self) -> &HashMap<String, f64> {
+        &self.budget_cap
+    }
+
+    pub fn unallocated(&self) -> &HashMap<String, f64> {
+        &self.unallocated
+    }
+
+    pub fn carried_over_to(&self) -> Option<Uuid> {
+        self.carried_over_to
+    }
+
+    pub fn reward_rounding(&self) -> RewardRounding {
+        self.reward_rounding
+    }
+
+    pub fn reward_adjustments(&self) -> &HashMap<Uuid, f64> {
+        &self.reward_adjustments
+    }
+
+    pub fn reward_adjustment_reason(&self, team_id: &Uuid) -> Option<&str> {
+        self.reward_adjustment_reasons.get(team_id).map(String::as_str)
+    }
+
     // Setter methods
     pub fn set_name(&mut self, name: String) {
         self.name = name;
@@ -130,6 +184,67 @@ impl Epoch {
         self.reward = None;
     }
 
+    pub fn set_budget_cap(&mut self, token: String, amount: f64) -> Result<(), &'static str> {
+        if amount < 0.0 {
+            return Err("Budget cap must be non-negative");
+        }
+        self.budget_cap.insert(token, amount);
+        Ok(())
+    }
+
+    pub fn remove_budget_cap(&mut self, token: &str) {
+        self.budget_cap.remove(token);
+    }
+
+    pub fn set_unallocated(&mut self, token: String, amount: f64) {
+        self.unallocated.insert(token, amount.max(0.0));
+    }
+
+    pub fn set_carried_over_to(&mut self, epoch_id: Option<Uuid>) {
+        self.carried_over_to = epoch_id;
+    }
+
+    pub fn set_reward_rounding(&mut self, rounding: RewardRounding) {
+        self.reward_rounding = rounding;
+    }
+
+    /// Scales the team's reward by `multiplier` when the epoch closes; 1.0 clears the adjustment
+    pub fn set_reward_adjustment(&mut self, team_id: Uuid, multiplier: f64, reason: String) -> Result<(), &'static str> {
+        if self.is_closed() {
+            return Err("Cannot adjust the rewards of a closed epoch");
+        }
+        if !(0.0..=1.0).contains(&multiplier) {
+            return Err("Reward multiplier must be between 0 and 1");
+        }
+        if multiplier == 1.0 {
+            self.reward_adjustments.remove(&team_id);
+            self.reward_adjustment_reasons.remove(&team_id);
+        } else {
+            self.reward_adjustments.insert(team_id, multiplier);
+            self.reward_adjustment_reasons.insert(team_id, reason);
+        }
+        Ok(())
+    }
+
+    /// Adds funds left over from another epoch to the reward of this planned epoch,
+    /// creating the reward if none is set yet.
+    pub fn add_carry_over(&mut self, token: &str, amount: f64) -> Result<(), &'static str> {
+        if !self.is_planned() {
+            return Err("Carry-over can only go to a planned epoch");
+        }
+        if amount <= 0.0 {
+            return Err("Carry-over amount must be positive");
+        }
+        match self.reward.as_mut() {
+            Some(reward) if reward.token != token => Err("Carry-over token must match the epoch reward token"),
+            Some(reward) => {
+                reward.amount += amount;
+                Ok(())
+            },
+            None => self.set_reward(token.to_string(), amount),
+        }
+    }
+
     pub fn set_team_reward(&mut self, team_id: Uuid, percentage: f64, amount: f64) -> Result<(), &'static str> {
         if percentage < 0.0 || percentage > 100.0 {
             return Err("Percentage must be between 0 and 100");
@@ -137,7 +252,14 @@ impl Epoch {
         if amount < 0.0 {
             return Err("Amount must be non-negative");
         }
-        self.team_rewards.insert(team_id, TeamReward { percentage, amount });
+        self.team_rewards.insert(team_id, TeamReward::new(percentage, amount)?);
+        Ok(())
+    }
+
+    pub fn record_team_payout(&mut self, team_id: &Uuid, amount: f64, tx_hash: String) -> Result<(), &'static str> {
+        let team_reward = self.team_rewards.get_mut(team_id).ok_or("Team has no reward in this epoch")?;
+        team_reward.paid += amount;
+        team_reward.payout_txs.push(tx_hash);
         Ok(())
     }
 
@@ -145,6 +267,62 @@ impl Epoch {
         self.team_rewards.remove(team_id);
     }
 
+    /// Folds one team's reward into another's, for merging teams. Shares, amounts and payouts add up;
+    /// a reward adjustment moves over only when the target has none of its own.
+    pub fn reassign_team(&mut self, from: Uuid, to: Uuid) {
+        if let Some(source) = self.team_rewards.remove(&from) {
+            match self.team_rewards.get_mut(&to) {
+                Some(target) => {
+                    target.percentage += source.percentage;
+                    target.amount += source.amount;
+                    target.paid += source.paid;
+                    target.payout_txs.extend(source.payout_txs);
+                },
+                None => {
+                    self.team_rewards.insert(to, source);
+                },
+            }
+        }
+
+        let multiplier = self.reward_adjustments.remove(&from);
+        let reason = self.reward_adjustment_reasons.remove(&from);
+        if !self.reward_adjustments.contains_key(&to) {
+            if let Some(multiplier) = multiplier {
+                self.reward_adjustments.insert(to, multiplier);
+            }
+            if let Some(reason) = reason {
+                self.reward_adjustment_reasons.insert(to, reason);
+            }
+        }
+    }
+
+    /// Adds to the reward of a closed epoch, splitting the addition by the stored team percentages.
+    /// Returns the amount added per team.
+    pub fn top_up_reward(&mut self, token: &str, amount: f64) -> Result<Vec<(Uuid, f64)>, &'static str> {
+        if !self.is_closed() {
+            return Err("Only closed epochs can be topped up");
+        }
+        if amount <= 0.0 {
+            return Err("Top-up amount must be positive");
+        }
+        let reward = self.reward.as_mut().ok_or("Epoch has no reward to top up")?;
+        if reward.token != token {
+            return Err("Top-up token must match the epoch reward token");
+        }
+        if self.team_rewards.is_empty() {
+            return Err("Epoch has no team rewards to top up");
+        }
+
+        reward.amount += amount;
+        Ok(self.team_rewards.iter_mut()
+            .map(|(team_id, team_reward)| {
+                let added = amount * team_reward.percentage / 100.0;
+                team_reward.amount += added;
+                (*team_id, added)
+            })
+            .collect())
+    }
+
     // Helper methods
     pub fn activate(&mut self) -> Result<(), &'static str> {
         if self.is_planned() {
@@ -198,6 +376,114 @@ impl NameMatches for Epoch {
     fn name_matches(&self, name: &str) -> bool {
         self.name() == name
     }
+
+    fn name_contains(&self, query: &str) -> bool {
+        contains_ignore_case(self.name(), query)
+    }
+}
+
+/// How a reward pool is split into team amounts at token precision.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RewardRounding {
+    /// Amounts keep full `f64` precision
+    #[default]
+    None,
+    /// Amounts are rounded down to the given decimals; the dust stays unallocated
+    Floor(u32),
+    /// Amounts are rounded down to the given decimals, then the leftover units go to the
+    /// largest remainders so the allocations sum to the pool
+    LargestRemainder(u32),
+}
+
+/// More decimals than this would overflow the integer units of realistic pools
+const MAX_ROUNDING_DECIMALS: u32 = 12;
+
+impl RewardRounding {
+    pub fn decimals(&self) -> Option<u32> {
+        match self {
+            RewardRounding::None => None,
+            RewardRounding::Floor(decimals) | RewardRounding::LargestRemainder(decimals) => Some(*decimals),
+        }
+    }
+
+    /// Splits `pool` by `weights`, returning one amount per weight in the same order.
+    /// Leftover units under `LargestRemainder` go to earlier weights when remainders tie.
+    pub fn allocate(&self, pool: f64, weights: &[u32]) -> Vec<f64> {
+        let total: u64 = weights.iter().map(|&w| w as u64).sum();
+        if total == 0 {
+            return vec![0.0; weights.len()];
+        }
+
+        let Some(decimals) = self.decimals() else {
+            return weights.iter().map(|&w| pool * (w as f64 / total as f64)).collect();
+        };
+
+        let scale = 10f64.powi(decimals as i32);
+        let pool_units = (pool * scale).round().max(0.0) as u128;
+        let mut units: Vec<u128> = Vec::with_capacity(weights.len());
+        let mut remainders: Vec<(usize, u128)> = Vec::with_capacity(weights.len());
+        for (index, &weight) in weights.iter().enumerate() {
+            let exact = pool_units * weight as u128;
+            units.push(exact / total as u128);
+            remainders.push((index, exact % total as u128));
+        }
+
+        if let RewardRounding::LargestRemainder(_) = self {
+            let leftover = pool_units - units.iter().sum::<u128>();
+            remainders.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+            for (index, _) in remainders.into_iter().take(leftover as usize) {
+                units[index] += 1;
+            }
+        }
+
+        units.into_iter().map(|u| u as f64 / scale).collect()
+    }
+
+    /// Rounds a single amount to the configured precision, clearing float dust
+    pub fn round(&self, amount: f64) -> f64 {
+        match self.decimals() {
+            Some(decimals) => {
+                let scale = 10f64.powi(decimals as i32);
+                (amount * scale).round() / scale
+            },
+            None => amount,
+        }
+    }
+}
+
+impl fmt::Display for RewardRounding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RewardRounding::None => write!(f, "none"),
+            RewardRounding::Floor(decimals) => write!(f, "floor:{}", decimals),
+            RewardRounding::LargestRemainder(decimals) => write!(f, "largest_remainder:{}", decimals),
+        }
+    }
+}
+
+impl FromStr for RewardRounding {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized = s.trim().to_lowercase().replace('-', "_");
+        if normalized == "none" {
+            return Ok(RewardRounding::None);
+        }
+
+        let (method, decimals) = normalized.split_once(':')
+            .ok_or("Reward rounding needs decimals, e.g. floor:2 or largest_remainder:6")?;
+        let decimals: u32 = decimals.trim().parse()
+            .map_err(|_| "Reward rounding decimals must be a non-negative integer")?;
+        if decimals > MAX_ROUNDING_DECIMALS {
+            return Err("Reward rounding supports at most 12 decimals");
+        }
+
+        match method.trim() {
+            "floor" => Ok(RewardRounding::Floor(decimals)),
+            "largest_remainder" => Ok(RewardRounding::LargestRemainder(decimals)),
+            _ => Err("Unknown reward rounding method"),
+        }
+    }
 }
 
 impl EpochReward {
@@ -225,7 +511,7 @@ impl TeamReward {
         if amount < 0.0 {
             return Err("Amount must be non-negative");
         }
-        Ok(Self { percentage, amount })
+        Ok(Self { percentage, amount, paid: 0.0, payout_txs: Vec::new() })
     }
 
     pub fn percentage(&self) -> f64 {
@@ -235,6 +521,20 @@ impl TeamReward {
     pub fn amount(&self) -> f64 {
         self.amount
     }
+
+    pub fn paid(&self) -> f64 {
+        self.paid
+    }
+
+    pub fn payout_txs(&self) -> &[String] {
+        &self.payout_txs
+    }
+
+    /// What is left to send, ignoring float dust
+    pub fn outstanding(&self) -> f64 {
+        let outstanding = self.amount - self.paid;
+        if outstanding > 1e-9 { outstanding } else { 0.0 }
+    }
 }
 
 #[cfg(test)]
@@ -389,9 +689,119 @@ mod tests {
         assert_eq!(epoch.remaining_reward_amount(), 10.0);
     }
 
+    #[test]
+    fn test_top_up_reward() {
+        let mut epoch = create_test_epoch();
+        epoch.set_reward("ETH".to_string(), 100.0).unwrap();
+        let team1_id = Uuid::new_v4();
+        let team2_id = Uuid::new_v4();
+        epoch.set_team_reward(team1_id, 75.0, 75.0).unwrap();
+        epoch.set_team_reward(team2_id, 25.0, 25.0).unwrap();
+
+        assert_eq!(epoch.top_up_reward("ETH", 40.0), Err("Only closed epochs can be topped up"));
+
+        epoch.activate().unwrap();
+        epoch.close().unwrap();
+        assert!(epoch.top_up_reward("USDC", 40.0).is_err());
+        assert!(epoch.top_up_reward("ETH", 0.0).is_err());
+
+        let added: HashMap<Uuid, f64> = epoch.top_up_reward("ETH", 40.0).unwrap().into_iter().collect();
+        assert_eq!(added[&team1_id], 30.0);
+        assert_eq!(added[&team2_id], 10.0);
+        assert_eq!(epoch.team_rewards()[&team1_id].amount(), 105.0);
+        assert_eq!(epoch.team_rewards()[&team2_id].amount(), 35.0);
+        assert_eq!(epoch.total_reward_amount(), 140.0);
+    }
+
+    #[test]
+    fn test_team_payouts() {
+        let mut epoch = create_test_epoch();
+        epoch.set_reward("ETH".to_string(), 100.0).unwrap();
+        let team_id = Uuid::new_v4();
+        epoch.set_team_reward(team_id, 100.0, 100.0).unwrap();
+        assert!(epoch.record_team_payout(&Uuid::new_v4(), 1.0, "0x1".to_string()).is_err());
+
+        epoch.record_team_payout(&team_id, 100.0, "0x1".to_string()).unwrap();
+        assert_eq!(epoch.team_rewards()[&team_id].outstanding(), 0.0);
+
+        // A later top-up is owed on top of what was already sent
+        epoch.activate().unwrap();
+        epoch.close().unwrap();
+        epoch.top_up_reward("ETH", 20.0).unwrap();
+        let team_reward = &epoch.team_rewards()[&team_id];
+        assert_eq!(team_reward.outstanding(), 20.0);
+        assert_eq!(team_reward.paid(), 100.0);
+        assert_eq!(team_reward.payout_txs(), &["0x1".to_string()]);
+    }
+
+    #[test]
+    fn test_budget_cap() {
+        let mut epoch = create_test_epoch();
+        assert!(epoch.budget_cap().is_empty());
+
+        epoch.set_budget_cap("USD".to_string(), 60000.0).unwrap();
+        assert_eq!(epoch.budget_cap().get("USD"), Some(&60000.0));
+        assert!(epoch.set_budget_cap("USD".to_string(), -1.0).is_err());
+
+        epoch.remove_budget_cap("USD");
+        assert!(epoch.budget_cap().is_empty());
+    }
+
+    #[test]
+    fn test_add_carry_over() {
+        let mut epoch = create_test_epoch();
+        epoch.add_carry_over("ETH", 5.0).unwrap();
+        assert_eq!(epoch.reward().unwrap().amount(), 5.0);
+
+        epoch.add_carry_over("ETH", 2.5).unwrap();
+        assert_eq!(epoch.total_reward_amount(), 7.5);
+        assert!(epoch.add_carry_over("USDC", 1.0).is_err());
+        assert!(epoch.add_carry_over("ETH", 0.0).is_err());
+
+        epoch.activate().unwrap();
+        assert_eq!(epoch.add_carry_over("ETH", 1.0), Err("Carry-over can only go to a planned epoch"));
+    }
+
     fn create_test_epoch() -> Epoch {
         let start_date = Utc::now();
         let end_date = start_date + chrono::Duration::days(30);
         Epoch::new("Test Epoch".to_string(), start_date, end_date).unwrap()
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_reward_rounding_parse() {
+        assert_eq!("none".parse::<RewardRounding>(), Ok(RewardRounding::None));
+        assert_eq!("floor:2".parse::<RewardRounding>(), Ok(RewardRounding::Floor(2)));
+        assert_eq!("largest-remainder:6".parse::<RewardRounding>(), Ok(RewardRounding::LargestRemainder(6)));
+        assert!("floor".parse::<RewardRounding>().is_err());
+        assert!("floor:13".parse::<RewardRounding>().is_err());
+        assert!("ceil:2".parse::<RewardRounding>().is_err());
+        assert_eq!(RewardRounding::LargestRemainder(6).to_string(), "largest_remainder:6");
+    }
+
+    #[test]
+    fn test_reward_rounding_allocate() {
+        let weights = [1, 1, 1];
+        assert_eq!(RewardRounding::Floor(2).allocate(100.0, &weights), vec![33.33, 33.33, 33.33]);
+        assert_eq!(RewardRounding::LargestRemainder(2).allocate(100.0, &weights), vec![33.34, 33.33, 33.33]);
+        assert_eq!(RewardRounding::LargestRemainder(2).allocate(100.0, &[0, 0]), vec![0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_set_reward_adjustment() {
+        let mut epoch = create_test_epoch();
+        let team_id = Uuid::new_v4();
+
+        assert_eq!(epoch.set_reward_adjustment(team_id, 1.5, "Late report".to_string()), Err("Reward multiplier must be between 0 and 1"));
+        epoch.set_reward_adjustment(team_id, 0.5, "Late report".to_string()).unwrap();
+        assert_eq!(epoch.reward_adjustments().get(&team_id), Some(&0.5));
+        assert_eq!(epoch.reward_adjustment_reason(&team_id), Some("Late report"));
+
+        epoch.set_reward_adjustment(team_id, 1.0, String::new()).unwrap();
+        assert!(epoch.reward_adjustments().is_empty());
+        assert_eq!(epoch.reward_adjustment_reason(&team_id), None);
+
+        epoch.set_status(EpochStatus::Closed);
+        assert_eq!(epoch.set_reward_adjustment(team_id, 0.0, "Misconduct".to_string()), Err("Cannot adjust the rewards of a closed epoch"));
+    }
+}
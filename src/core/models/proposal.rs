@@ -1,8 +1,8 @@
 use crate::commands::common::{UpdateProposalDetails, BudgetRequestDetailsCommand};
-use super::common::NameMatches;
+use super::common::{NameMatches, contains_ignore_case};
 use uuid::Uuid;
-use chrono::{Utc, NaiveDate};
-use std::{collections::HashMap, str::FromStr};
+use chrono::{DateTime, Datelike, Utc, NaiveDate};
+use std::{collections::{HashMap, HashSet}, fmt, str::FromStr};
 use serde::{Serialize, Deserialize};
 use ethers::types::{Address, H256};
 use super::common::{address_serde, tx_hash_serde};
@@ -20,10 +20,85 @@ pub struct Proposal {
     published_at: Option<NaiveDate>,
     resolved_at: Option<NaiveDate>,
     is_historical: bool,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    attachments: Vec<Attachment>,
+    #[serde(default)]
+    amendments: Vec<Amendment>,
+    /// Teams endorsing the proposal before it goes to a formal vote
+    #[serde(default)]
+    sponsors: HashSet<Uuid>,
+    /// Unset on proposals saved before kinds existed; see `kind()`
+    #[serde(default)]
+    kind: Option<ProposalKind>,
+    /// Explicit date by which the proposal should be resolved; see `BudgetSystem::decision_deadline`
+    #[serde(default)]
+    decision_deadline: Option<NaiveDate>,
+    /// Proposals that must be approved before this one can go to a formal vote
+    #[serde(default)]
+    depends_on: Vec<Uuid>,
+}
+
+/// What a proposal asks for. Only budget proposals carry amounts, teams and payment details.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProposalKind {
+    Budget,
+    /// A governance or policy change that still goes to a formal vote
+    Policy,
+    Informational,
+}
+
+impl ProposalKind {
+    pub fn is_budget(&self) -> bool {
+        matches!(self, ProposalKind::Budget)
+    }
+}
+
+impl fmt::Display for ProposalKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            ProposalKind::Budget => "budget",
+            ProposalKind::Policy => "policy",
+            ProposalKind::Informational => "informational",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for ProposalKind {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "budget" => Ok(ProposalKind::Budget),
+            "policy" => Ok(ProposalKind::Policy),
+            "informational" | "info" => Ok(ProposalKind::Informational),
+            _ => Err("Unknown proposal kind"),
+        }
+    }
+}
+
+/// A change to one field of a proposal, with both values rendered as text
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Amendment {
+    pub timestamp: DateTime<Utc>,
+    pub field: String,
+    pub old_value: String,
+    pub new_value: String,
+}
+
+/// A supporting document pinned to IPFS
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Attachment {
+    pub label: String,
+    pub cid: String,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ProposalStatus {
+    /// Staged before announcement; not actionable and left out of epoch reports
+    Draft,
     Open,
     Closed,
     Reopened,
@@ -38,6 +113,18 @@ pub enum Resolution {
     Retracted
 }
 
+/// Criteria for listing proposals; every field that is set must match, and the date bounds are inclusive
+#[derive(Clone, Debug, Default)]
+pub struct ProposalFilter {
+    pub epoch_id: Option<Uuid>,
+    /// A status (open, closed, reopened) or resolution (approved, rejected, ...), case-insensitive
+    pub status: Option<String>,
+    pub team_name: Option<String>,
+    pub announced_after: Option<NaiveDate>,
+    pub announced_before: Option<NaiveDate>,
+    pub tag: Option<String>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct BudgetRequestDetails {
     team: Option<Uuid>,
@@ -50,6 +137,20 @@ pub struct BudgetRequestDetails {
     #[serde(with = "tx_hash_serde")]
     payment_tx: Option<H256>,
     payment_date: Option<NaiveDate>,
+    #[serde(default)]
+    monthly_breakdown: Option<Vec<HashMap<String, f64>>>,
+    /// Milestone payments made so far
+    #[serde(default)]
+    payments: Vec<Payment>,
+}
+
+/// One drawdown of a budget request
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Payment {
+    pub amount: f64,
+    pub token: String,
+    pub date: NaiveDate,
+    pub tx: Option<String>,
 }
 
 
@@ -76,6 +177,13 @@ impl Proposal {
             published_at,
             resolved_at: None,
             is_historical,
+            tags: Vec::new(),
+            attachments: Vec::new(),
+            amendments: Vec::new(),
+            sponsors: HashSet::new(),
+            kind: None,
+            decision_deadline: None,
+            depends_on: Vec::new(),
         }
     }
 
@@ -112,6 +220,32 @@ impl Proposal {
         self.announced_at
     }
 
+    pub fn decision_deadline(&self) -> Option<NaiveDate> {
+        self.decision_deadline
+    }
+
+    pub fn set_decision_deadline(&mut self, deadline: Option<NaiveDate>) {
+        self.decision_deadline = deadline;
+    }
+
+    pub fn depends_on(&self) -> &[Uuid] {
+        &self.depends_on
+    }
+
+    pub fn set_depends_on(&mut self, depends_on: Vec<Uuid>) -> Result<(), &'static str> {
+        if depends_on.contains(&self.id) {
+            return Err("A proposal cannot depend on itself");
+        }
+        let mut unique = Vec::with_capacity(depends_on.len());
+        for id in depends_on {
+            if !unique.contains(&id) {
+                unique.push(id);
+            }
+        }
+        self.depends_on = unique;
+        Ok(())
+    }
+
     pub fn published_at(&self) -> Option<NaiveDate> {
         self.published_at
     }
@@ -124,7 +258,47 @@ impl Proposal {
         self.is_historical
     }
 
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    pub fn attachments(&self) -> &[Attachment] {
+        &self.attachments
+    }
+
+    /// The explicit kind, or `Budget` when the proposal has budget details and `Policy` otherwise
+    pub fn kind(&self) -> ProposalKind {
+        self.kind.unwrap_or(if self.budget_request_details.is_some() {
+            ProposalKind::Budget
+        } else {
+            ProposalKind::Policy
+        })
+    }
+
+    pub fn sponsors(&self) -> &HashSet<Uuid> {
+        &self.sponsors
+    }
+
+    /// Whether the proposal has the given status or resolution name, ignoring case
+    pub fn has_status(&self, status: &str) -> bool {
+        let status = status.trim();
+        format!("{:?}", self.status).eq_ignore_ascii_case(status)
+            || self.resolution.as_ref().is_some_and(|r| format!("{:?}", r).eq_ignore_ascii_case(status))
+    }
+
+    pub fn amendments(&self) -> &[Amendment] {
+        &self.amendments
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag.trim()))
+    }
+
     // Setter methods
+    pub fn set_epoch_id(&mut self, epoch_id: Uuid) {
+        self.epoch_id = epoch_id;
+    }
+
     pub fn set_title(&mut self, title: String) {
         self.title = title;
     }
@@ -141,6 +315,14 @@ impl Proposal {
         self.resolution = resolution;
     }
 
+    pub fn set_kind(&mut self, kind: ProposalKind) -> Result<(), &'static str> {
+        if !kind.is_budget() && self.budget_request_details.is_some() {
+            return Err("Only budget proposals can have budget request details");
+        }
+        self.kind = Some(kind);
+        Ok(())
+    }
+
     pub fn set_budget_request_details(&mut self, details: Option<BudgetRequestDetails>) {
         self.budget_request_details = details;
     }
@@ -186,6 +368,46 @@ impl Proposal {
         self.is_historical = is_historical;
     }
 
+    pub fn add_sponsor(&mut self, team_id: Uuid) -> Result<(), &'static str> {
+        if !self.sponsors.insert(team_id) {
+            return Err("Team already sponsors this proposal");
+        }
+        Ok(())
+    }
+
+    /// Points sponsorship and the budget request at another team, for merging teams
+    pub fn reassign_team(&mut self, from: Uuid, to: Uuid) {
+        if self.sponsors.remove(&from) {
+            self.sponsors.insert(to);
+        }
+        if let Some(details) = self.budget_request_details.as_mut() {
+            if details.team() == Some(from) {
+                details.set_team(Some(to));
+            }
+        }
+    }
+
+    pub fn add_attachment(&mut self, label: String, cid: String) -> Result<(), &'static str> {
+        let attachment = Attachment::new(label, cid)?;
+        if self.attachments.iter().any(|a| a.cid == attachment.cid) {
+            return Err("Attachment with this CID already exists");
+        }
+        self.attachments.push(attachment);
+        Ok(())
+    }
+
+    /// Tags are stored trimmed and lowercase, without duplicates
+    pub fn set_tags(&mut self, tags: Vec<String>) {
+        let mut normalized: Vec<String> = Vec::new();
+        for tag in tags {
+            let tag = tag.trim().to_lowercase();
+            if !tag.is_empty() && !normalized.contains(&tag) {
+                normalized.push(tag);
+            }
+        }
+        self.tags = normalized;
+    }
+
     // Helper methods
     pub fn is_open(&self) -> bool {
         matches!(self.status, ProposalStatus::Open)
@@ -199,6 +421,22 @@ impl Proposal {
         matches!(self.status, ProposalStatus::Reopened)
     }
 
+    pub fn is_draft(&self) -> bool {
+        matches!(self.status, ProposalStatus::Draft)
+    }
+
+    /// Opens a draft, announcing it on `today` unless it already has an announcement date
+    pub fn publish(&mut self, today: NaiveDate) -> Result<(), &'static str> {
+        if !self.is_draft() {
+            return Err("Proposal is not a draft");
+        }
+        self.status = ProposalStatus::Open;
+        if self.announced_at.is_none() {
+            self.announced_at = Some(today);
+        }
+        Ok(())
+    }
+
     pub fn is_approved(&self) -> bool {
         matches!(self.resolution, Some(Resolution::Approved))
     }
@@ -240,7 +478,61 @@ impl Proposal {
         Ok(())
     }
 
+    /// Undoes a close: the resolution and resolution date are cleared and the proposal becomes actionable again
+    pub fn reopen(&mut self) -> Result<(), &'static str> {
+        if !self.is_closed() {
+            return Err("Proposal is not closed");
+        }
+        self.status = ProposalStatus::Reopened;
+        self.resolution = None;
+        self.resolved_at = None;
+        Ok(())
+    }
+
     pub fn update(&mut self, updates: UpdateProposalDetails, team_id: Option<Uuid>) -> Result<(), &'static str> {
+        let before = self.amendable_fields();
+        self.apply_updates(updates, team_id)?;
+
+        let timestamp = Utc::now();
+        for ((field, old_value), (_, new_value)) in before.into_iter().zip(self.amendable_fields()) {
+            if old_value != new_value {
+                self.amendments.push(Amendment { timestamp, field: field.to_string(), old_value, new_value });
+            }
+        }
+        Ok(())
+    }
+
+    /// Fields tracked in the amendment history, rendered for display
+    fn amendable_fields(&self) -> Vec<(&'static str, String)> {
+        fn date(date: Option<NaiveDate>) -> String {
+            date.map_or_else(|| "none".to_string(), |d| d.to_string())
+        }
+        let details = self.budget_request_details.as_ref();
+        let mut amounts: Vec<_> = details.map(|d| d.request_amounts().iter().collect()).unwrap_or_default();
+        amounts.sort_by(|a, b| a.0.cmp(b.0));
+        let amounts = amounts.iter().map(|(token, amount)| format!("{} {}", amount, token)).collect::<Vec<_>>().join(", ");
+
+        vec![
+            ("title", self.title.clone()),
+            ("url", self.url.clone().unwrap_or_else(|| "none".to_string())),
+            ("announced_at", date(self.announced_at)),
+            ("published_at", date(self.published_at)),
+            ("resolved_at", date(self.resolved_at)),
+            ("team", details.and_then(|d| d.team()).map_or_else(|| "none".to_string(), |id| id.to_string())),
+            ("request_amounts", if amounts.is_empty() { "none".to_string() } else { amounts }),
+            ("start_date", date(details.and_then(|d| d.start_date()))),
+            ("end_date", date(details.and_then(|d| d.end_date()))),
+            ("is_loan", details.is_some_and(|d| d.is_loan()).to_string()),
+            ("payment_address", details.and_then(|d| d.payment_address()).map_or_else(|| "none".to_string(), |a| format!("{:?}", a))),
+            ("tags", self.tags.join(", ")),
+            ("decision_deadline", date(self.decision_deadline)),
+        ]
+    }
+
+    fn apply_updates(&mut self, updates: UpdateProposalDetails, team_id: Option<Uuid>) -> Result<(), &'static str> {
+        if updates.budget_request_details.is_some() && self.kind.is_some_and(|kind| !kind.is_budget()) {
+            return Err("Only budget proposals can have budget request details");
+        }
         if let Some(title) = updates.title {
             self.set_title(title);
         }
@@ -257,6 +549,14 @@ impl Proposal {
         if let Some(budget_details) = updates.budget_request_details {
             self.update_budget_request_details(&budget_details, team_id)?;
         }
+
+        if let Some(tags) = updates.tags {
+            self.set_tags(tags);
+        }
+
+        if let Some(deadline) = updates.decision_deadline {
+            self.set_decision_deadline(Some(deadline));
+        }
  
         Ok(())
     }
@@ -285,6 +585,10 @@ impl Proposal {
         if let Some(address) = &updates.payment_address {
             details.set_payment_address(Some(address.clone()))?;
         }
+
+        if let Some(breakdown) = &updates.monthly_breakdown {
+            details.monthly_breakdown = Some(breakdown.clone());
+        }
  
         details.validate()?;
  
@@ -297,6 +601,87 @@ impl NameMatches for Proposal {
     fn name_matches(&self, name: &str) -> bool {
         self.title() == name
     }
+
+    fn name_contains(&self, query: &str) -> bool {
+        contains_ignore_case(self.title(), query)
+    }
+}
+
+impl Amendment {
+    /// Human-readable account of the change. Request amounts are compared per token and
+    /// dates are described as being set, moved or cleared.
+    pub fn describe(&self) -> String {
+        match self.field.as_str() {
+            "request_amounts" => {
+                let old = Self::parse_amounts(&self.old_value);
+                let new = Self::parse_amounts(&self.new_value);
+                let mut tokens: Vec<&str> = old.keys().chain(new.keys()).copied().collect();
+                tokens.sort();
+                tokens.dedup();
+
+                let changes: Vec<String> = tokens.into_iter()
+                    .filter_map(|token| match (old.get(token), new.get(token)) {
+                        (Some(before), Some(after)) if before != after => Some(format!("{} {} -> {}", token, before, after)),
+                        (None, Some(after)) => Some(format!("{} added ({})", token, after)),
+                        (Some(before), None) => Some(format!("{} removed (was {})", token, before)),
+                        _ => None,
+                    })
+                    .collect();
+                format!("request_amounts: {}", changes.join(", "))
+            },
+            field if field.ends_with("_at") || field.ends_with("_date") => {
+                match (self.old_value.as_str(), self.new_value.as_str()) {
+                    ("none", new) => format!("{} set to {}", field, new),
+                    (old, "none") => format!("{} cleared (was {})", field, old),
+                    (old, new) => format!("{} moved from {} to {}", field, old, new),
+                }
+            },
+            field => format!("{} changed from '{}' to '{}'", field, self.old_value, self.new_value),
+        }
+    }
+
+    /// Reads back the "100 ETH, 5 USD" form the amounts are recorded in
+    fn parse_amounts(value: &str) -> HashMap<&str, &str> {
+        value.split(", ")
+            .filter_map(|entry| entry.split_once(' '))
+            .map(|(amount, token)| (token, amount))
+            .collect()
+    }
+}
+
+impl Attachment {
+    pub fn new(label: String, cid: String) -> Result<Self, &'static str> {
+        let label = label.trim().to_string();
+        let cid = cid.trim().trim_start_matches("ipfs://").to_string();
+        if label.is_empty() {
+            return Err("Attachment label cannot be empty");
+        }
+        if !Self::is_valid_cid(&cid) {
+            return Err("Invalid IPFS CID");
+        }
+        Ok(Self { label, cid })
+    }
+
+    /// Loose check: a base58 CIDv0 ("Qm", 46 characters) or a base32 CIDv1 ("b" prefix)
+    pub fn is_valid_cid(cid: &str) -> bool {
+        const BASE58: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+        if cid.starts_with("Qm") {
+            cid.len() == 46 && cid.chars().all(|c| BASE58.contains(c))
+        } else if let Some(rest) = cid.strip_prefix('b') {
+            rest.len() >= 50 && rest.chars().all(|c| c.is_ascii_lowercase() || ('2'..='7').contains(&c))
+        } else {
+            false
+        }
+    }
+
+    /// `ipfs://<cid>`, or `<gateway>/<cid>` when a gateway is given
+    pub fn url(&self, gateway: &str) -> String {
+        if gateway.trim().is_empty() {
+            format!("ipfs://{}", self.cid)
+        } else {
+            format!("{}/{}", gateway.trim().trim_end_matches('/'), self.cid)
+        }
+    }
 }
 
 impl BudgetRequestDetails {
@@ -325,6 +710,8 @@ impl BudgetRequestDetails {
             payment_address,
             payment_tx: None,
             payment_date: None,
+            monthly_breakdown: None,
+            payments: Vec::new(),
         };
         brd.validate()?;
         Ok(brd)
@@ -348,6 +735,8 @@ impl BudgetRequestDetails {
             }
         }
 
+        self.validate_monthly_breakdown()?;
+
         // Ensure new proposals don't have payment details
         if self.payment_tx.is_some() || self.payment_date.is_some() {
             return Err("New budget requests cannot have payment details");
@@ -356,6 +745,24 @@ impl BudgetRequestDetails {
         Ok(())
     }
 
+    fn validate_monthly_breakdown(&self) -> Result<(), &'static str> {
+        if let Some(breakdown) = &self.monthly_breakdown {
+            if breakdown.len() != self.schedule_dates().len() {
+                return Err("Monthly breakdown must have one entry per month of the budget period");
+            }
+            for (token, &amount) in &self.request_amounts {
+                let scheduled: f64 = breakdown.iter().filter_map(|month| month.get(token)).sum();
+                if (scheduled - amount).abs() > 1e-6 {
+                    return Err("Monthly breakdown must add up to the request amounts");
+                }
+            }
+            if breakdown.iter().flat_map(|month| month.keys()).any(|token| !self.request_amounts.contains_key(token)) {
+                return Err("Monthly breakdown contains a token that is not requested");
+            }
+        }
+        Ok(())
+    }
+
     pub fn default() -> Self {
         BudgetRequestDetails {
             team: None,
@@ -365,7 +772,9 @@ impl BudgetRequestDetails {
             is_loan: None,
             payment_address: None,
             payment_tx: None,
-            payment_date: None
+            payment_date: None,
+            monthly_breakdown: None,
+            payments: Vec::new(),
         }
     }
 
@@ -402,6 +811,23 @@ impl BudgetRequestDetails {
         self.payment_date
     }
 
+    pub fn monthly_breakdown(&self) -> Option<&Vec<HashMap<String, f64>>> {
+        self.monthly_breakdown.as_ref()
+    }
+
+    pub fn payments(&self) -> &[Payment] {
+        &self.payments
+    }
+
+    /// Total of the milestone payments per token
+    pub fn paid_amounts(&self) -> HashMap<String, f64> {
+        let mut paid = HashMap::new();
+        for payment in &self.payments {
+            *paid.entry(payment.token.clone()).or_insert(0.0) += payment.amount;
+        }
+        paid
+    }
+
     // Setter methods
     pub fn set_team(&mut self, team: Option<Uuid>) {
         self.team = team;
@@ -430,6 +856,15 @@ impl BudgetRequestDetails {
         Ok(())
     }
 
+    pub fn set_monthly_breakdown(&mut self, breakdown: Option<Vec<HashMap<String, f64>>>) -> Result<(), &'static str> {
+        let previous = std::mem::replace(&mut self.monthly_breakdown, breakdown);
+        if let Err(e) = self.validate_monthly_breakdown() {
+            self.monthly_breakdown = previous;
+            return Err(e);
+        }
+        Ok(())
+    }
+
     pub fn set_is_loan(&mut self, is_loan: bool) {
         self.is_loan = Some(is_loan);
     }
@@ -452,6 +887,28 @@ impl BudgetRequestDetails {
         Ok(())
     }
 
+    /// Records a milestone payment, which may not take a token past its requested amount
+    pub fn add_payment(&mut self, payment: Payment) -> Result<(), &'static str> {
+        if payment.amount <= 0.0 {
+            return Err("Payment amount must be positive");
+        }
+        let requested = *self.request_amounts.get(&payment.token)
+            .ok_or("Payment token is not part of the request")?;
+        if let Some(tx) = &payment.tx {
+            H256::from_str(tx).map_err(|_| "Invalid transaction hash")?;
+        }
+        let paid = self.paid_amounts().get(&payment.token).copied().unwrap_or(0.0);
+        if paid + payment.amount > requested + 1e-6 {
+            return Err("Payment exceeds the remaining requested amount");
+        }
+        self.payments.push(payment);
+        Ok(())
+    }
+
+    pub fn payment_tx_url(&self) -> Option<String> {
+        self.payment_tx.map(|tx| format!("https://etherscan.io/tx/{:?}", tx))
+    }
+
     pub fn clear_payment(&mut self) {
         self.payment_tx = None;
         self.payment_date = None;
@@ -460,13 +917,84 @@ impl BudgetRequestDetails {
 
     // Helper methods
 
+    /// Paid in full, either by a single recorded payment or by milestone payments covering every requested token
     pub fn is_paid(&self) -> bool {
-        self.payment_tx.is_some() && self.payment_date.is_some()
+        if self.payment_tx.is_some() && self.payment_date.is_some() {
+            return true;
+        }
+        if self.payments.is_empty() {
+            return false;
+        }
+        let paid = self.paid_amounts();
+        self.request_amounts.iter()
+            .all(|(token, &amount)| paid.get(token).copied().unwrap_or(0.0) >= amount - 1e-6)
+    }
+
+    pub fn is_partially_paid(&self) -> bool {
+        !self.payments.is_empty() && !self.is_paid()
+    }
+
+    /// "paid", "partially paid" or "unpaid"
+    pub fn payment_status(&self) -> &'static str {
+        if self.is_paid() {
+            "paid"
+        } else if self.is_partially_paid() {
+            "partially paid"
+        } else {
+            "unpaid"
+        }
     }
 
     pub fn total_request_amount(&self) -> f64 {
         self.request_amounts.values().sum()
     }
+
+    /// Payment tranches per calendar month between `start_date` and `end_date`.
+    /// The first tranche falls on the start date, later ones on the first of the month.
+    /// Amounts come from `monthly_breakdown` when set, otherwise they are split evenly.
+    pub fn monthly_schedule(&self) -> Vec<(NaiveDate, HashMap<String, f64>)> {
+        let dates = self.schedule_dates();
+        if dates.is_empty() {
+            return Vec::new();
+        }
+
+        match &self.monthly_breakdown {
+            Some(breakdown) if breakdown.len() == dates.len() => {
+                dates.into_iter().zip(breakdown.iter().cloned()).collect()
+            },
+            _ => {
+                let months = dates.len() as f64;
+                let tranche: HashMap<String, f64> = self.request_amounts.iter()
+                    .map(|(token, amount)| (token.clone(), amount / months))
+                    .collect();
+                dates.into_iter().map(|date| (date, tranche.clone())).collect()
+            }
+        }
+    }
+
+    fn schedule_dates(&self) -> Vec<NaiveDate> {
+        let start = match self.start_date {
+            Some(start) => start,
+            None => return Vec::new(),
+        };
+        let end = self.end_date.unwrap_or(start);
+
+        let mut dates = vec![start];
+        let (mut year, mut month) = (start.year(), start.month());
+        loop {
+            if month == 12 {
+                year += 1;
+                month = 1;
+            } else {
+                month += 1;
+            }
+            match NaiveDate::from_ymd_opt(year, month, 1) {
+                Some(date) if date <= end => dates.push(date),
+                _ => break,
+            }
+        }
+        dates
+    }
 }
 
 #[cfg(test)]
@@ -496,6 +1024,33 @@ mod tests {
         assert!(proposal.resolution().is_none());
     }
 
+    #[test]
+    fn test_proposal_attachments() {
+        let mut proposal = create_test_proposal();
+        let cid = "QmYwAPJzv5CZsnA625s3Xf2nemtYgPpHdWEz79ojWnPbdG";
+
+        proposal.add_attachment("Spec".to_string(), format!("ipfs://{}", cid)).unwrap();
+        assert_eq!(proposal.attachments()[0].cid, cid);
+        assert_eq!(proposal.attachments()[0].url(""), format!("ipfs://{}", cid));
+        assert_eq!(proposal.attachments()[0].url("https://ipfs.io/ipfs/"), format!("https://ipfs.io/ipfs/{}", cid));
+
+        assert!(proposal.add_attachment("Again".to_string(), cid.to_string()).is_err());
+        assert!(proposal.add_attachment("Bad".to_string(), "not-a-cid".to_string()).is_err());
+        assert!(proposal.add_attachment(" ".to_string(), cid.to_string()).is_err());
+        assert!(Attachment::is_valid_cid("bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi"));
+    }
+
+    #[test]
+    fn test_proposal_tags() {
+        let mut proposal = create_test_proposal();
+        assert!(proposal.tags().is_empty());
+
+        proposal.set_tags(vec![" Infra ".to_string(), "infra".to_string(), "".to_string(), "Grants".to_string()]);
+        assert_eq!(proposal.tags(), ["infra".to_string(), "grants".to_string()]);
+        assert!(proposal.has_tag("INFRA"));
+        assert!(!proposal.has_tag("marketing"));
+    }
+
     #[test]
     fn test_proposal_status_changes() {
         let mut proposal = create_test_proposal();
@@ -512,6 +1067,29 @@ mod tests {
         assert!(proposal.is_rejected());
     }
 
+    #[test]
+    fn test_publish_draft() {
+        let today = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let mut proposal = create_test_proposal();
+        assert!(proposal.publish(today).is_err());
+
+        proposal.set_status(ProposalStatus::Draft);
+        proposal.set_announced_at(None);
+        assert!(!proposal.is_actionable());
+
+        proposal.publish(today).unwrap();
+        assert!(proposal.is_open());
+        assert!(proposal.is_actionable());
+        assert_eq!(proposal.announced_at(), Some(today));
+
+        // An existing announcement date is kept
+        let announced = NaiveDate::from_ymd_opt(2024, 2, 1).unwrap();
+        proposal.set_status(ProposalStatus::Draft);
+        proposal.set_announced_at(Some(announced));
+        proposal.publish(today).unwrap();
+        assert_eq!(proposal.announced_at(), Some(announced));
+    }
+
     #[test]
     fn test_proposal_resolution() {
         let mut proposal = create_test_proposal();
@@ -575,10 +1153,14 @@ mod tests {
                 end_date: Some(NaiveDate::from_ymd_opt(2023, 4, 30).unwrap()),
                 is_loan: None,
                 payment_address: None,
+                monthly_breakdown: None,
             }),
             announced_at: Some(NaiveDate::from_ymd_opt(2023, 3, 15).unwrap()),
             published_at: Some(NaiveDate::from_ymd_opt(2023, 3, 20).unwrap()),
             resolved_at: Some(NaiveDate::from_ymd_opt(2023, 3, 25).unwrap()),
+            tags: None,
+            decision_deadline: None,
+            depends_on: None,
         };
         
         proposal.update(updates, Some(Uuid::new_v4())).unwrap();
@@ -643,6 +1225,21 @@ mod tests {
         assert!(proposal.is_actionable());
     }
 
+    #[test]
+    fn test_proposal_reopen() {
+        let mut proposal = create_test_proposal();
+        assert!(proposal.reopen().is_err());
+
+        proposal.reject().unwrap();
+        proposal.set_resolved_at(Some(NaiveDate::from_ymd_opt(2024, 1, 10).unwrap()));
+        proposal.reopen().unwrap();
+
+        assert!(proposal.is_reopened());
+        assert!(proposal.is_actionable());
+        assert_eq!(proposal.resolution(), None);
+        assert_eq!(proposal.resolved_at(), None);
+    }
+
     #[test]
     fn test_budget_request_details_creation() {
         let mut amounts = HashMap::new();
@@ -760,6 +1357,31 @@ mod tests {
         assert!(details.payment_date().is_none());
     }
 
+    #[test]
+    fn test_milestone_payments() {
+        let amounts: HashMap<String, f64> = [("ETH".to_string(), 100.0), ("USDC".to_string(), 50.0)].into_iter().collect();
+        let mut details = BudgetRequestDetails::new(None, amounts, None, None, None, None).unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 2, 1).unwrap();
+        let payment = |amount: f64, token: &str| Payment { amount, token: token.to_string(), date, tx: None };
+
+        assert_eq!(details.payment_status(), "unpaid");
+        details.add_payment(payment(60.0, "ETH")).unwrap();
+        assert!(details.is_partially_paid());
+        assert_eq!(details.payment_status(), "partially paid");
+
+        assert!(details.add_payment(payment(50.0, "ETH")).is_err());
+        assert!(details.add_payment(payment(10.0, "DAI")).is_err());
+        assert!(details.add_payment(payment(0.0, "ETH")).is_err());
+        assert!(details.add_payment(Payment { tx: Some("not-a-hash".to_string()), ..payment(1.0, "ETH") }).is_err());
+
+        details.add_payment(payment(40.0, "ETH")).unwrap();
+        assert!(!details.is_paid(), "USDC is still outstanding");
+        details.add_payment(payment(50.0, "USDC")).unwrap();
+        assert!(details.is_paid());
+        assert_eq!(details.paid_amounts().get("ETH"), Some(&100.0));
+        assert_eq!(details.payments().len(), 3);
+    }
+
     #[test]
     fn test_budget_request_details_loan_defaults() {
         let mut amounts = HashMap::new();
@@ -824,4 +1446,139 @@ mod tests {
         details.set_is_loan(false);
         assert!(!details.is_loan());
     }
-}
\ No newline at end of file
+    #[test]
+    fn test_monthly_schedule() {
+        let mut amounts = HashMap::new();
+        amounts.insert("ETH".to_string(), 300.0);
+
+        let mut details = BudgetRequestDetails::new(
+            None,
+            amounts,
+            Some(NaiveDate::from_ymd_opt(2024, 1, 15).unwrap()),
+            Some(NaiveDate::from_ymd_opt(2024, 3, 31).unwrap()),
+            None,
+            None,
+        ).unwrap();
+
+        let schedule = details.monthly_schedule();
+        assert_eq!(schedule.len(), 3);
+        assert_eq!(schedule[0].0, NaiveDate::from_ymd_opt(2024, 1, 15).unwrap());
+        assert_eq!(schedule[2].0, NaiveDate::from_ymd_opt(2024, 3, 1).unwrap());
+        assert!(schedule.iter().all(|(_, tranche)| tranche.get("ETH") == Some(&100.0)));
+
+        let breakdown: Vec<HashMap<String, f64>> = [50.0, 50.0, 200.0].iter()
+            .map(|&amount| [("ETH".to_string(), amount)].into_iter().collect())
+            .collect();
+        details.set_monthly_breakdown(Some(breakdown)).unwrap();
+        assert_eq!(details.monthly_schedule()[2].1.get("ETH"), Some(&200.0));
+
+        // Breakdowns must cover every month and add up to the request
+        let short: Vec<HashMap<String, f64>> = vec![[("ETH".to_string(), 300.0)].into_iter().collect()];
+        assert!(details.set_monthly_breakdown(Some(short)).is_err());
+        assert_eq!(details.monthly_schedule()[2].1.get("ETH"), Some(&200.0));
+    }
+
+    #[test]
+    fn test_update_records_amendments() {
+        let mut proposal = Proposal::new(Uuid::new_v4(), "Original".to_string(), None, None, None, None, None);
+        proposal.update(UpdateProposalDetails {
+            title: Some("Renamed".to_string()),
+            url: None,
+            budget_request_details: None,
+            announced_at: None,
+            published_at: None,
+            resolved_at: None,
+            tags: Some(vec!["infra".to_string()]),
+            decision_deadline: None,
+            depends_on: None,
+        }, None).unwrap();
+
+        let fields: Vec<_> = proposal.amendments().iter().map(|a| a.field.as_str()).collect();
+        assert_eq!(fields, vec!["title", "tags"]);
+        assert_eq!(proposal.amendments()[0].old_value, "Original");
+        assert_eq!(proposal.amendments()[0].new_value, "Renamed");
+
+        // Updates that change nothing are not recorded
+        proposal.update(UpdateProposalDetails {
+            title: Some("Renamed".to_string()),
+            url: None,
+            budget_request_details: None,
+            announced_at: None,
+            published_at: None,
+            resolved_at: None,
+            tags: None,
+            decision_deadline: None,
+            depends_on: None,
+        }, None).unwrap();
+        assert_eq!(proposal.amendments().len(), 2);
+    }
+
+    #[test]
+    fn test_amendment_describe() {
+        let amendment = |field: &str, old_value: &str, new_value: &str| Amendment {
+            timestamp: Utc::now(),
+            field: field.to_string(),
+            old_value: old_value.to_string(),
+            new_value: new_value.to_string(),
+        };
+
+        assert_eq!(
+            amendment("request_amounts", "100 ETH, 5 USD", "150 ETH, 2 WBTC").describe(),
+            "request_amounts: ETH 100 -> 150, USD removed (was 5), WBTC added (2)"
+        );
+        assert_eq!(amendment("request_amounts", "none", "10 ETH").describe(), "request_amounts: ETH added (10)");
+        assert_eq!(amendment("start_date", "2024-01-01", "2024-02-01").describe(), "start_date moved from 2024-01-01 to 2024-02-01");
+        assert_eq!(amendment("announced_at", "none", "2024-01-01").describe(), "announced_at set to 2024-01-01");
+        assert_eq!(amendment("end_date", "2024-01-01", "none").describe(), "end_date cleared (was 2024-01-01)");
+        assert_eq!(amendment("title", "Old", "New").describe(), "title changed from 'Old' to 'New'");
+    }
+
+    #[test]
+    fn test_proposal_kind() {
+        let details = BudgetRequestDetails::new(None, [("ETH".to_string(), 10.0)].into_iter().collect(), None, None, None, None).unwrap();
+        let mut budget = Proposal::new(Uuid::new_v4(), "Grant".to_string(), None, Some(details), None, None, None);
+        let mut policy = Proposal::new(Uuid::new_v4(), "Charter".to_string(), None, None, None, None, None);
+
+        // Inferred from budget details until set explicitly
+        assert_eq!(budget.kind(), ProposalKind::Budget);
+        assert_eq!(policy.kind(), ProposalKind::Policy);
+        assert!(budget.set_kind(ProposalKind::Policy).is_err());
+
+        policy.set_kind(ProposalKind::Informational).unwrap();
+        assert_eq!(policy.kind(), ProposalKind::Informational);
+        assert!(policy.update(UpdateProposalDetails {
+            title: None,
+            url: None,
+            budget_request_details: Some(BudgetRequestDetailsCommand {
+                team: None,
+                request_amounts: Some([("ETH".to_string(), 5.0)].into_iter().collect()),
+                start_date: None,
+                end_date: None,
+                is_loan: None,
+                payment_address: None,
+                monthly_breakdown: None,
+            }),
+            announced_at: None,
+            published_at: None,
+            resolved_at: None,
+            tags: None,
+            decision_deadline: None,
+            depends_on: None,
+        }, None).is_err());
+        assert!(policy.budget_request_details().is_none());
+
+        assert_eq!("info".parse::<ProposalKind>(), Ok(ProposalKind::Informational));
+        assert!("grant".parse::<ProposalKind>().is_err());
+    }
+
+    #[test]
+    fn test_set_depends_on() {
+        let mut proposal = create_test_proposal();
+        let dependency = Uuid::new_v4();
+
+        proposal.set_depends_on(vec![dependency, dependency]).unwrap();
+        assert_eq!(proposal.depends_on(), &[dependency]);
+        assert_eq!(proposal.set_depends_on(vec![proposal.id()]), Err("A proposal cannot depend on itself"));
+        assert_eq!(proposal.depends_on(), &[dependency]);
+    }
+}
@@ -1,10 +1,14 @@
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
 use serde::{Serialize, Deserialize};
 use sha2::{Sha256, Digest};
 
 use super::team::{Team, TeamStatus};
+use super::common::escape_csv;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Raffle {
@@ -13,6 +17,12 @@ pub struct Raffle {
     team_snapshots: Vec<TeamSnapshot>,
     tickets: Vec<RaffleTicket>,
     result: Option<RaffleResult>,
+    /// The raffle this one re-draws
+    #[serde(default)]
+    supersedes: Option<Uuid>,
+    /// The re-draw that replaced this raffle; a superseded raffle no longer decides the vote
+    #[serde(default)]
+    superseded_by: Option<Uuid>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -28,6 +38,69 @@ pub struct RaffleConfig {
     custom_allocation: Option<HashMap<Uuid, u64>>,
     custom_team_order: Option<Vec<Uuid>>,
     is_historical: bool,
+    /// Drop inactive teams from the snapshot entirely; otherwise they are listed as excluded
+    #[serde(default = "default_exclude_inactive")]
+    exclude_inactive: bool,
+    /// When the randomness block was produced
+    #[serde(default)]
+    randomness_timestamp: Option<DateTime<Utc>>,
+    #[serde(default)]
+    tie_break: TieBreak,
+    /// The randomness is a caller-supplied seed rather than a block hash
+    #[serde(default)]
+    seeded: bool,
+    /// Ticket counts for earner teams by average monthly revenue; empty uses the square-root scale
+    #[serde(default)]
+    revenue_tiers: Vec<RevenueTier>,
+}
+
+/// Earner teams whose average monthly revenue is at least `min_revenue` get `tickets` tickets,
+/// taken from the highest tier they reach
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevenueTier {
+    pub min_revenue: u64,
+    pub tickets: u64,
+}
+
+fn default_exclude_inactive() -> bool {
+    true
+}
+
+/// How teams whose tickets have equal scores are ordered when seats are filled.
+/// Every policy falls back to the lowest team ID, so the order is always total.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TieBreak {
+    /// The lower team ID wins
+    #[default]
+    LowestTeamId,
+    /// The team with the higher sum of all its ticket scores wins
+    HighestScore,
+    /// Earners win over supporters, then the higher average trailing revenue wins
+    EarnerPriority,
+}
+
+impl fmt::Display for TieBreak {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            TieBreak::LowestTeamId => "lowest_team_id",
+            TieBreak::HighestScore => "highest_score",
+            TieBreak::EarnerPriority => "earner_priority",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for TieBreak {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().replace('-', "_").as_str() {
+            "lowest_team_id" => Ok(TieBreak::LowestTeamId),
+            "highest_score" => Ok(TieBreak::HighestScore),
+            "earner_priority" => Ok(TieBreak::EarnerPriority),
+            _ => Err("Unknown tie-break policy"),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -47,7 +120,18 @@ pub struct RaffleTicket {
     score: f64,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// One ticket as exported for auditors; `selected` is whether the ticket's team won a
+/// counted seat, and is unset until the raffle is drawn
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TicketRecord {
+    pub index: u64,
+    pub team_name: String,
+    pub team_id: Uuid,
+    pub score: f64,
+    pub selected: Option<bool>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct RaffleResult {
     counted: Vec<Uuid>,
     uncounted: Vec<Uuid>,
@@ -71,7 +155,7 @@ impl Raffle {
 
         // Create team snapshots
         let mut active_teams: Vec<_> = teams.values()
-            .filter(|team| team.is_active())
+            .filter(|team| team.is_active() || !config.exclude_inactive())
             .collect();
 
         // Sort teams based on custom order or by name
@@ -88,7 +172,7 @@ impl Raffle {
                 team.name().to_string(),
                 team.representative().to_string(),
                 team.status().clone(),
-                if config.excluded_teams().contains(&team.id()) {
+                if config.excluded_teams().contains(&team.id()) || !team.is_active() {
                     RaffleParticipationStatus::Excluded
                 } else {
                     RaffleParticipationStatus::Included
@@ -96,10 +180,19 @@ impl Raffle {
             );
             team_snapshots.push(snapshot);
 
+            // Supporters have no revenue and always get a single ticket, tiers or not
             let ticket_count = match team.status() {
+                TeamStatus::Earner { trailing_monthly_revenue } if !config.revenue_tiers().is_empty() => {
+                    let sum: f64 = trailing_monthly_revenue.iter().sum();
+                    let average = if trailing_monthly_revenue.is_empty() { 0.0 } else { sum / trailing_monthly_revenue.len() as f64 };
+                    config.revenue_tiers().iter()
+                        .filter(|tier| tier.min_revenue as f64 <= average)
+                        .max_by_key(|tier| tier.min_revenue)
+                        .map_or(1, |tier| tier.tickets.max(1))
+                },
                 TeamStatus::Earner { trailing_monthly_revenue } => {
-                    let sum: u64 = trailing_monthly_revenue.iter().sum();
-                    let quarterly_average = sum as f64 / trailing_monthly_revenue.len() as f64;
+                    let sum: f64 = trailing_monthly_revenue.iter().sum();
+                    let quarterly_average = sum / trailing_monthly_revenue.len() as f64;
                     let scaled_average = quarterly_average / 1000.0;
                     (scaled_average.sqrt().floor() as u64).max(1)
                 },
@@ -118,6 +211,8 @@ impl Raffle {
             team_snapshots,
             tickets,
             result: None,
+            supersedes: None,
+            superseded_by: None,
         })
     }
 
@@ -138,6 +233,13 @@ impl Raffle {
         &self.team_snapshots
     }
 
+    /// Drops the revenue from the team's snapshot; tickets already drawn are unaffected
+    pub fn clear_team_revenue(&mut self, team_id: Uuid) {
+        for snapshot in self.team_snapshots.iter_mut().filter(|snapshot| snapshot.id == team_id) {
+            snapshot.status.clear_revenue();
+        }
+    }
+
     pub fn tickets(&self) -> &[RaffleTicket] {
         &self.tickets
     }
@@ -146,12 +248,78 @@ impl Raffle {
         self.result.as_ref()
     }
 
+    /// Every ticket in index order. Teams are named from `names` when present there,
+    /// otherwise from the snapshot taken when the raffle was prepared.
+    pub fn ticket_records(&self, names: &HashMap<Uuid, String>) -> Vec<TicketRecord> {
+        let mut tickets: Vec<&RaffleTicket> = self.tickets.iter().collect();
+        tickets.sort_by_key(|ticket| ticket.index);
+
+        tickets.into_iter()
+            .map(|ticket| TicketRecord {
+                index: ticket.index,
+                team_name: names.get(&ticket.team_id).cloned()
+                    .or_else(|| self.team_snapshots.iter().find(|s| s.id == ticket.team_id).map(|s| s.name.clone()))
+                    .unwrap_or_else(|| format!("Unknown Team ({})", ticket.team_id)),
+                team_id: ticket.team_id,
+                score: ticket.score,
+                selected: self.result.as_ref().map(|result| result.counted.contains(&ticket.team_id)),
+            })
+            .collect()
+    }
+
+    /// Tickets as `index,team_name,team_id,score,selected`, named from the team snapshots
+    pub fn tickets_csv(&self) -> String {
+        self.tickets_csv_with_names(&HashMap::new())
+    }
+
+    pub fn tickets_csv_with_names(&self, names: &HashMap<Uuid, String>) -> String {
+        let mut csv = String::from("index,team_name,team_id,score,selected\n");
+        for record in self.ticket_records(names) {
+            csv.push_str(&format!("{},{},{},{},{}\n",
+                record.index,
+                escape_csv(&record.team_name),
+                record.team_id,
+                record.score,
+                record.selected.map_or_else(String::new, |selected| selected.to_string()),
+            ));
+        }
+        csv
+    }
+
+    pub fn supersedes(&self) -> Option<Uuid> {
+        self.supersedes
+    }
+
+    pub fn superseded_by(&self) -> Option<Uuid> {
+        self.superseded_by
+    }
+
+    pub fn is_superseded(&self) -> bool {
+        self.superseded_by.is_some()
+    }
+
     pub fn deciding_teams(&self) -> Vec<Uuid> {
         self.result.as_ref()
             .map(|result| result.counted.clone())
             .unwrap_or_default()
     }
 
+    /// Teams included in the draw
+    pub fn eligible_team_count(&self) -> usize {
+        self.team_snapshots.iter()
+            .filter(|snapshot| matches!(snapshot.raffle_status(), RaffleParticipationStatus::Included))
+            .count()
+    }
+
+    /// Eligible teams per counted seat; 0.0 when there are no seats
+    pub fn competition_ratio(&self) -> f64 {
+        let seats = self.config.total_counted_seats();
+        if seats == 0 {
+            return 0.0;
+        }
+        self.eligible_team_count() as f64 / seats as f64
+    }
+
     pub fn etherscan_url(&self) -> String {
         format!("https://etherscan.io/block/{}#consensusinfo", self.config.randomness_block)
     }
@@ -172,13 +340,15 @@ impl Raffle {
             .filter(|t| !self.config.excluded_teams.contains(&t.team_id))
             .filter(|t| self.team_snapshots.iter().any(|s| s.id == t.team_id && matches!(s.status, TeamStatus::Earner { .. })))
             .collect();
-        earner_tickets.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+        earner_tickets.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap()
+            .then_with(|| self.tie_break_order(a.team_id, b.team_id)));
 
         let mut supporter_tickets: Vec<_> = self.tickets.iter()
             .filter(|t| !self.config.excluded_teams.contains(&t.team_id))
             .filter(|t| self.team_snapshots.iter().any(|s| s.id == t.team_id && matches!(s.status, TeamStatus::Supporter)))
             .collect();
-        supporter_tickets.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+        supporter_tickets.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap()
+            .then_with(|| self.tie_break_order(a.team_id, b.team_id)));
 
         let mut counted = Vec::new();
         let mut uncounted = Vec::new();
@@ -208,6 +378,38 @@ impl Raffle {
         self.result = Some(RaffleResult { counted, uncounted });
     }
 
+    /// Order of two teams whose tickets tied, under the configured policy; `Less` ranks `a` first.
+    /// Scores come from sha256, so real ties are practically impossible and existing raffles verify unchanged.
+    fn tie_break_order(&self, a: Uuid, b: Uuid) -> Ordering {
+        let policy_order = match self.config.tie_break {
+            TieBreak::LowestTeamId => Ordering::Equal,
+            TieBreak::HighestScore => {
+                let total_score = |team_id: Uuid| -> f64 {
+                    self.tickets.iter().filter(|t| t.team_id == team_id).map(|t| t.score).sum()
+                };
+                total_score(b).partial_cmp(&total_score(a)).unwrap_or(Ordering::Equal)
+            },
+            TieBreak::EarnerPriority => {
+                // (is earner, average trailing revenue)
+                let rank = |team_id: Uuid| -> (bool, f64) {
+                    match self.team_snapshots.iter().find(|s| s.id == team_id).map(|s| &s.status) {
+                        Some(TeamStatus::Earner { trailing_monthly_revenue }) if !trailing_monthly_revenue.is_empty() => {
+                            let sum: f64 = trailing_monthly_revenue.iter().sum();
+                            (true, sum / trailing_monthly_revenue.len() as f64)
+                        },
+                        Some(TeamStatus::Earner { .. }) => (true, 0.0),
+                        _ => (false, 0.0),
+                    }
+                };
+                let (a_earner, a_revenue) = rank(a);
+                let (b_earner, b_revenue) = rank(b);
+                b_earner.cmp(&a_earner)
+                    .then_with(|| b_revenue.partial_cmp(&a_revenue).unwrap_or(Ordering::Equal))
+            },
+        };
+        policy_order.then_with(|| a.cmp(&b))
+    }
+
     fn generate_random_score_from_seed(randomness: &str, index: u64) -> f64 {
         let combined_seed = format!("{}_{}", randomness, index);
         let mut hasher = Sha256::new();
@@ -225,14 +427,64 @@ impl Raffle {
         self.result = Some(result);
     }
 
+    pub fn set_supersedes(&mut self, raffle_id: Option<Uuid>) {
+        self.supersedes = raffle_id;
+    }
+
+    pub fn set_superseded_by(&mut self, raffle_id: Option<Uuid>) {
+        self.superseded_by = raffle_id;
+    }
+
     // Helper methods
     pub fn is_historical(&self) -> bool {
         self.config.is_historical
     }
 
+    /// True when the randomness is empty or "N/A", or either block is zero, as for
+    /// predefined raffles. On a live raffle this points to an incomplete finalize.
+    pub fn has_placeholder_randomness(&self) -> bool {
+        let randomness = self.config.block_randomness().trim();
+        if self.config.is_seeded() {
+            return randomness.is_empty();
+        }
+        randomness.is_empty()
+            || randomness.eq_ignore_ascii_case("N/A")
+            || self.config.initiation_block() == 0
+            || self.config.randomness_block() == 0
+    }
+
+    pub fn randomness_source(&self) -> &'static str {
+        if self.config.is_seeded() {
+            "seed-based, not block-derived"
+        } else {
+            "block-derived"
+        }
+    }
+
     pub fn is_completed(&self) -> bool {
         self.result.is_some()
     }
+
+    /// Re-runs scoring and seat selection on a copy of this raffle using the
+    /// stored config and team snapshots, without touching the stored result.
+    pub fn recompute_result(&self) -> Result<RaffleResult, &'static str> {
+        let randomness = self.config.block_randomness();
+        if randomness.is_empty() || randomness == "N/A" {
+            return Err("Raffle has no block randomness to verify against");
+        }
+
+        let mut replay = self.clone();
+        replay.result = None;
+        replay.generate_ticket_scores()?;
+        replay.select_deciding_teams();
+        replay.result.ok_or("Failed to recompute raffle result")
+    }
+
+    /// Checks that the stored result matches an independent recomputation
+    pub fn verify(&self) -> Result<bool, &'static str> {
+        let stored = self.result.as_ref().ok_or("Raffle has no result to verify")?;
+        Ok(*stored == self.recompute_result()?)
+    }
 }
 
 impl RaffleConfig {
@@ -261,9 +513,40 @@ impl RaffleConfig {
             custom_allocation,
             custom_team_order,
             is_historical,
+            exclude_inactive: true,
+            randomness_timestamp: None,
+            tie_break: TieBreak::default(),
+            seeded: false,
+            revenue_tiers: Vec::new(),
         }
     }
 
+    /// A config whose randomness is `seed` itself, so identical seeds give identical scores
+    pub fn with_seed(
+        proposal_id: Uuid,
+        epoch_id: Uuid,
+        total_counted_seats: usize,
+        max_earner_seats: usize,
+        seed: String,
+        excluded_teams: Option<Vec<Uuid>>,
+    ) -> Self {
+        let mut config = Self::new(
+            proposal_id,
+            epoch_id,
+            total_counted_seats,
+            max_earner_seats,
+            Some(0),
+            Some(0),
+            Some(seed),
+            excluded_teams,
+            None,
+            None,
+            false
+        );
+        config.seeded = true;
+        config
+    }
+
     // Getter methods
     pub fn proposal_id(&self) -> Uuid { self.proposal_id }
     pub fn epoch_id(&self) -> Uuid { self.epoch_id }
@@ -276,6 +559,11 @@ impl RaffleConfig {
     pub fn custom_allocation(&self) -> Option<&HashMap<Uuid, u64>> { self.custom_allocation.as_ref() }
     pub fn custom_team_order(&self) -> Option<&[Uuid]> { self.custom_team_order.as_deref() }
     pub fn is_historical(&self) -> bool { self.is_historical }
+    pub fn exclude_inactive(&self) -> bool { self.exclude_inactive }
+    pub fn randomness_timestamp(&self) -> Option<DateTime<Utc>> { self.randomness_timestamp }
+    pub fn tie_break(&self) -> TieBreak { self.tie_break }
+    pub fn is_seeded(&self) -> bool { self.seeded }
+    pub fn revenue_tiers(&self) -> &[RevenueTier] { &self.revenue_tiers }
 
     // Setter methods
     pub fn set_initiation_block(&mut self, block: u64) { self.initiation_block = block; }
@@ -284,6 +572,10 @@ impl RaffleConfig {
     pub fn set_excluded_teams(&mut self, teams: Vec<Uuid>) { self.excluded_teams = teams; }
     pub fn set_custom_allocation(&mut self, allocation: Option<HashMap<Uuid, u64>>) { self.custom_allocation = allocation; }
     pub fn set_custom_team_order(&mut self, order: Option<Vec<Uuid>>) { self.custom_team_order = order; }
+    pub fn set_exclude_inactive(&mut self, exclude_inactive: bool) { self.exclude_inactive = exclude_inactive; }
+    pub fn set_randomness_timestamp(&mut self, timestamp: Option<DateTime<Utc>>) { self.randomness_timestamp = timestamp; }
+    pub fn set_tie_break(&mut self, tie_break: TieBreak) { self.tie_break = tie_break; }
+    pub fn set_revenue_tiers(&mut self, tiers: Vec<RevenueTier>) { self.revenue_tiers = tiers; }
 }
 
 impl RaffleTicket {
@@ -338,10 +630,40 @@ impl RaffleResult {
         Self { counted, uncounted }
     }
 
+    pub fn contains(&self, team_id: Uuid) -> bool {
+        self.counted.contains(&team_id) || self.uncounted.contains(&team_id)
+    }
+
+    /// Gives `from`'s seat to `to`. If `to` already held a seat, it keeps the better of the two.
+    pub fn reassign_team(&mut self, from: Uuid, to: Uuid) {
+        for team_id in self.counted.iter_mut().chain(self.uncounted.iter_mut()) {
+            if *team_id == from {
+                *team_id = to;
+            }
+        }
+        let mut seen = Vec::new();
+        self.counted.retain(|team_id| if seen.contains(team_id) { false } else { seen.push(*team_id); true });
+        self.uncounted.retain(|team_id| if seen.contains(team_id) { false } else { seen.push(*team_id); true });
+    }
+
     // Getter methods
     pub fn counted(&self) -> &[Uuid] { &self.counted }
     pub fn uncounted(&self) -> &[Uuid] { &self.uncounted }
 
+    /// First team whose seat differs between the two results, checking counted seats before uncounted
+    pub fn first_divergence(&self, other: &RaffleResult) -> Option<Uuid> {
+        fn diverge(a: &[Uuid], b: &[Uuid]) -> Option<Uuid> {
+            (0..a.len().max(b.len())).find_map(|i| match (a.get(i), b.get(i)) {
+                (Some(x), Some(y)) if x == y => None,
+                (Some(x), _) => Some(*x),
+                (None, y) => y.copied(),
+            })
+        }
+
+        diverge(&self.counted, &other.counted)
+            .or_else(|| diverge(&self.uncounted, &other.uncounted))
+    }
+
     // No setter methods as the result should not be modified after creation
 }
 
@@ -354,7 +676,7 @@ mod tests {
     // Helper function to create a mock team
     fn create_mock_team(name: &str, status: TeamStatus) -> Team {
         Team::new(name.to_string(), "Representative".to_string(), match status {
-            TeamStatus::Earner { .. } => Some(vec![1000, 2000, 3000]),
+            TeamStatus::Earner { .. } => Some(vec![1000.0, 2000.0, 3000.0]),
             _ => None,
         }, None).unwrap()
     }
@@ -363,7 +685,7 @@ mod tests {
     fn create_mock_teams() -> HashMap<Uuid, Team> {
         let mut teams = HashMap::new();
         for i in 1..=5 {
-            teams.insert(Uuid::new_v4(), create_mock_team(&format!("Earner{}", i), TeamStatus::Earner { trailing_monthly_revenue: vec![1000 * i as u64, 2000 * i as u64, 3000 * i as u64] }));
+            teams.insert(Uuid::new_v4(), create_mock_team(&format!("Earner{}", i), TeamStatus::Earner { trailing_monthly_revenue: vec![1000.0 * i as f64, 2000.0 * i as f64, 3000.0 * i as f64] }));
         }
         for i in 1..=4 {
             teams.insert(Uuid::new_v4(), create_mock_team(&format!("Supporter{}", i), TeamStatus::Supporter));
@@ -428,6 +750,94 @@ mod tests {
         assert_eq!(deciding_teams.len(), 7); // Based on total_counted_seats
     }
 
+    #[test]
+    fn test_competition_ratio() {
+        let mut teams = HashMap::new();
+        for i in 1..=10 {
+            let team = create_mock_team(&format!("Supporter{}", i), TeamStatus::Supporter);
+            teams.insert(team.id(), team);
+        }
+        let mut config = create_test_config();
+        config.total_counted_seats = 5;
+        config.max_earner_seats = 3;
+
+        let raffle = Raffle::new(config, &teams).unwrap();
+        assert_eq!(raffle.eligible_team_count(), 10);
+        assert_eq!(raffle.competition_ratio(), 2.0);
+    }
+
+    #[test]
+    fn test_has_placeholder_randomness() {
+        let mut raffle = create_test_raffle();
+        assert!(!raffle.has_placeholder_randomness());
+
+        raffle.config_mut().set_block_randomness("N/A".to_string());
+        assert!(raffle.has_placeholder_randomness());
+
+        raffle.config_mut().set_block_randomness("test_randomness".to_string());
+        raffle.config_mut().set_randomness_block(0);
+        assert!(raffle.has_placeholder_randomness());
+    }
+
+    #[test]
+    fn test_seeded_raffle_is_reproducible() {
+        let teams = create_mock_teams();
+        let (proposal_id, epoch_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let run = |seed: &str| {
+            let config = RaffleConfig::with_seed(proposal_id, epoch_id, 7, 5, seed.to_string(), None);
+            let mut raffle = Raffle::new(config, &teams).unwrap();
+            raffle.generate_ticket_scores().unwrap();
+            raffle.select_deciding_teams();
+            raffle
+        };
+
+        let first = run("dry-run-seed");
+        let second = run("dry-run-seed");
+        let scores = |raffle: &Raffle| raffle.tickets().iter().map(|t| t.score()).collect::<Vec<f64>>();
+        assert_eq!(scores(&first), scores(&second));
+        assert_eq!(first.result(), second.result());
+        assert_ne!(scores(&first), scores(&run("another-seed")));
+
+        assert!(first.config().is_seeded());
+        assert!(!first.has_placeholder_randomness());
+        assert_eq!(first.randomness_source(), "seed-based, not block-derived");
+    }
+
+    #[test]
+    fn test_tie_break_policies() {
+        let rich = Team::new("Rich".to_string(), "Rep".to_string(), Some(vec![9000.0, 9000.0, 9000.0]), None).unwrap();
+        let modest = Team::new("Modest".to_string(), "Rep".to_string(), Some(vec![1000.0, 1000.0, 1000.0]), None).unwrap();
+        let (rich_id, modest_id) = (rich.id(), modest.id());
+        let teams: HashMap<Uuid, Team> = [(rich_id, rich), (modest_id, modest)].into_iter().collect();
+
+        // One earner seat; both teams' best tickets score 0.5, and Modest has a second ticket
+        let winner = |tie_break: TieBreak| {
+            let mut config = create_test_config();
+            config.total_counted_seats = 1;
+            config.max_earner_seats = 1;
+            config.set_tie_break(tie_break);
+            let mut raffle = Raffle::new(config, &teams).unwrap();
+            raffle.tickets = vec![
+                RaffleTicket { team_id: rich_id, index: 0, score: 0.5 },
+                RaffleTicket { team_id: modest_id, index: 1, score: 0.5 },
+                RaffleTicket { team_id: modest_id, index: 2, score: 0.2 },
+            ];
+            raffle.select_deciding_teams();
+            raffle.result().unwrap().counted()[0]
+        };
+
+        assert_eq!(winner(TieBreak::LowestTeamId), rich_id.min(modest_id));
+        assert_eq!(winner(TieBreak::HighestScore), modest_id);
+        assert_eq!(winner(TieBreak::EarnerPriority), rich_id);
+    }
+
+    #[test]
+    fn test_tie_break_from_str() {
+        assert_eq!("earner-priority".parse::<TieBreak>().unwrap(), TieBreak::EarnerPriority);
+        assert_eq!(TieBreak::HighestScore.to_string().parse::<TieBreak>().unwrap(), TieBreak::HighestScore);
+        assert!("coin_flip".parse::<TieBreak>().is_err());
+    }
+
     #[test]
     fn test_get_etherscan_url() {
         let raffle = create_test_raffle();
@@ -462,6 +872,36 @@ mod tests {
         assert_eq!(snapshot_names.len(), 9, "There should be 9 team snapshots");
     }
 
+    #[test]
+    fn test_tickets_csv() {
+        let teams = create_mock_teams();
+        let mut raffle = Raffle::new(create_test_config(), &teams).unwrap();
+
+        let undrawn = raffle.tickets_csv();
+        assert!(undrawn.lines().skip(1).all(|line| line.ends_with(",0,")));
+
+        raffle.generate_ticket_scores().unwrap();
+        raffle.select_deciding_teams();
+
+        let csv = raffle.tickets_csv();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("index,team_name,team_id,score,selected"));
+        let rows: Vec<Vec<&str>> = lines.map(|line| line.split(',').collect()).collect();
+        assert_eq!(rows.len(), raffle.tickets().len());
+        for (position, row) in rows.iter().enumerate() {
+            assert_eq!(row[0], position.to_string());
+            let team_id: Uuid = row[2].parse().unwrap();
+            assert_eq!(row[1], teams.values().find(|team| team.id() == team_id).unwrap().name());
+            let counted = raffle.result().unwrap().counted().contains(&team_id);
+            assert_eq!(row[4], counted.to_string());
+        }
+
+        // Current names take precedence over the snapshot
+        let renamed_id = teams.values().next().unwrap().id();
+        let names: HashMap<Uuid, String> = [(renamed_id, "Renamed, Inc".to_string())].into_iter().collect();
+        assert!(raffle.tickets_csv_with_names(&names).contains(&format!("\"Renamed, Inc\",{}", renamed_id)));
+    }
+
     #[test]
     fn test_raffle_with_excluded_teams() {
         let teams = create_mock_teams();
@@ -510,6 +950,62 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_raffle_excludes_inactive_teams() {
+        let mut teams = create_mock_teams();
+        let mut inactive = create_mock_team("Dormant", TeamStatus::Supporter);
+        inactive.set_status(TeamStatus::Inactive).unwrap();
+        let inactive_id = inactive.id();
+        teams.insert(inactive_id, inactive);
+
+        let raffle = Raffle::new(create_test_config(), &teams).unwrap();
+        assert!(raffle.team_snapshots().iter().all(|s| s.id() != inactive_id));
+        assert!(raffle.tickets().iter().all(|t| t.team_id() != inactive_id));
+
+        // Kept in the snapshot as excluded, alongside explicit exclusions, but still without tickets
+        let explicit_id = teams.values().map(|team| team.id()).find(|&id| id != inactive_id).unwrap();
+        let mut config = create_test_config();
+        config.set_exclude_inactive(false);
+        config.set_excluded_teams(vec![explicit_id]);
+        let raffle = Raffle::new(config, &teams).unwrap();
+        for id in [inactive_id, explicit_id] {
+            let snapshot = raffle.team_snapshots().iter().find(|s| s.id() == id).unwrap();
+            assert!(matches!(snapshot.raffle_status(), RaffleParticipationStatus::Excluded));
+        }
+        assert!(raffle.tickets().iter().all(|t| t.team_id() != inactive_id));
+    }
+
+    #[test]
+    fn test_verify_raffle() {
+        let mut raffle = create_test_raffle();
+        raffle.generate_ticket_scores().unwrap();
+        raffle.select_deciding_teams();
+        assert!(raffle.verify().unwrap());
+
+        // Swap a counted and an uncounted team to simulate tampering
+        let original = raffle.result().unwrap().clone();
+        let mut counted = original.counted().to_vec();
+        let mut uncounted = original.uncounted().to_vec();
+        std::mem::swap(&mut counted[0], &mut uncounted[0]);
+        raffle.set_result(RaffleResult::new(counted.clone(), uncounted));
+
+        assert!(!raffle.verify().unwrap());
+        let recomputed = raffle.recompute_result().unwrap();
+        assert_eq!(raffle.result().unwrap().first_divergence(&recomputed), Some(counted[0]));
+    }
+
+    #[test]
+    fn test_verify_raffle_without_result_or_randomness() {
+        let raffle = create_test_raffle();
+        assert!(raffle.verify().is_err());
+
+        let mut config = create_test_config();
+        config.block_randomness = String::new();
+        let mut raffle = Raffle::new(config, &create_mock_teams()).unwrap();
+        raffle.set_result(RaffleResult::new(vec![], vec![]));
+        assert!(raffle.verify().is_err());
+    }
+
     // Helper function to create a test raffle
     fn create_test_raffle() -> Raffle {
         let teams = create_mock_teams();
@@ -531,6 +1027,42 @@ mod tests {
             custom_allocation: None,
             custom_team_order: None,
             is_historical: false,
+            exclude_inactive: true,
+            randomness_timestamp: None,
+            tie_break: TieBreak::LowestTeamId,
+            seeded: false,
+            revenue_tiers: Vec::new(),
         }
     }
+
+    #[test]
+    fn test_revenue_tier_ticket_counts() {
+        let mut teams = HashMap::new();
+        for (name, revenue) in [("Small", Some(vec![500.0, 1500.0])), ("Mid", Some(vec![5000.0, 5000.0, 5000.0])), ("Large", Some(vec![50000.0])), ("Supporter", None)] {
+            let team = Team::new(name.to_string(), "Rep".to_string(), revenue, None).unwrap();
+            teams.insert(team.id(), team);
+        }
+        let tickets_for = |raffle: &Raffle, name: &str| {
+            let team_id = teams.values().find(|t| t.name() == name).unwrap().id();
+            raffle.tickets().iter().filter(|t| t.team_id() == team_id).count()
+        };
+
+        let mut config = create_test_config();
+        // Listed out of order on purpose; the highest tier reached wins
+        config.set_revenue_tiers(vec![
+            RevenueTier { min_revenue: 20000, tickets: 5 },
+            RevenueTier { min_revenue: 0, tickets: 1 },
+            RevenueTier { min_revenue: 5000, tickets: 3 },
+        ]);
+        let raffle = Raffle::new(config, &teams).unwrap();
+        assert_eq!(tickets_for(&raffle, "Small"), 1);
+        assert_eq!(tickets_for(&raffle, "Mid"), 3);
+        assert_eq!(tickets_for(&raffle, "Large"), 5);
+        assert_eq!(tickets_for(&raffle, "Supporter"), 1);
+
+        // Without tiers the square-root scale applies: sqrt(50000 / 1000) rounds down to 7
+        let raffle = Raffle::new(create_test_config(), &teams).unwrap();
+        assert_eq!(tickets_for(&raffle, "Large"), 7);
+        assert_eq!(tickets_for(&raffle, "Mid"), 2);
+    }
 }
\ No newline at end of file
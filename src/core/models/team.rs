@@ -1,16 +1,129 @@
 use serde::{Serialize, Deserialize};
 use uuid::Uuid;
-use super::common::{NameMatches, address_serde};
+use super::common::{NameMatches, address_serde, contains_ignore_case};
 use ethers::types::Address;
+use std::fmt;
 use std::str::FromStr;
+use chrono::{DateTime, Utc};
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+/// Trailing revenue is stored as `f64` so sub-unit amounts survive; older state with
+/// whole-number revenue loads unchanged, since JSON integers deserialize as floats.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum TeamStatus {
-    Earner { trailing_monthly_revenue: Vec<u64>},
+    Earner { trailing_monthly_revenue: Vec<f64>},
     Supporter,
     Inactive,
 }
 
+impl TeamStatus {
+    /// Drops the revenue figures of an earner status, which stays an earner
+    pub fn clear_revenue(&mut self) {
+        if let TeamStatus::Earner { trailing_monthly_revenue } = self {
+            trailing_monthly_revenue.clear();
+        }
+    }
+}
+
+/// Role whose holder is mirrored into the legacy `representative` field
+pub const LEAD_ROLE: &str = "lead";
+/// Role contacted about payments, when a team has one
+pub const TREASURER_ROLE: &str = "treasurer";
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Representative {
+    pub name: String,
+    pub role: String,
+    pub handle: Option<String>,
+}
+
+/// How a contact is reached
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContactKind {
+    Telegram,
+    Email,
+    Other,
+}
+
+impl FromStr for ContactKind {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "telegram" => Ok(ContactKind::Telegram),
+            "email" => Ok(ContactKind::Email),
+            "other" => Ok(ContactKind::Other),
+            _ => Err("Unknown contact kind. Expected telegram, email or other"),
+        }
+    }
+}
+
+impl fmt::Display for ContactKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ContactKind::Telegram => write!(f, "telegram"),
+            ContactKind::Email => write!(f, "email"),
+            ContactKind::Other => write!(f, "other"),
+        }
+    }
+}
+
+/// A way to reach a team's representative
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Contact {
+    pub kind: ContactKind,
+    pub value: String,
+}
+
+impl Contact {
+    pub fn new(kind: ContactKind, value: &str) -> Result<Self, &'static str> {
+        let contact = Contact { kind, value: value.trim().to_string() };
+        contact.validate()?;
+        Ok(contact)
+    }
+
+    /// Checks that the value has the shape its kind needs
+    pub fn validate(&self) -> Result<(), &'static str> {
+        match self.kind {
+            ContactKind::Telegram => {
+                let handle = self.value.strip_prefix('@').ok_or("Telegram handle must start with @")?;
+                if handle.len() < 5 || !handle.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                    return Err("Telegram handle needs at least 5 letters, digits or underscores after the @");
+                }
+            },
+            ContactKind::Email => {
+                let (local, domain) = self.value.split_once('@').ok_or("Invalid email address")?;
+                let domain_ok = domain.contains('.') && !domain.starts_with('.') && !domain.ends_with('.') && !domain.contains('@');
+                if local.is_empty() || !domain_ok || self.value.contains(char::is_whitespace) {
+                    return Err("Invalid email address");
+                }
+            },
+            ContactKind::Other => {
+                if self.value.is_empty() {
+                    return Err("Contact cannot be empty");
+                }
+            },
+        }
+        Ok(())
+    }
+}
+
+/// Parses `kind:value`, e.g. `telegram:@alice` or `email:alice@example.com`
+impl FromStr for Contact {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, value) = s.split_once(':').ok_or("Invalid contact format. Expected kind:value")?;
+        Contact::new(kind.parse()?, value)
+    }
+}
+
+impl fmt::Display for Contact {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.kind, self.value)
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct Team {
     id: Uuid,
@@ -19,19 +132,70 @@ pub struct Team {
     status: TeamStatus,
     #[serde(with = "address_serde", default)]
     payment_address: Option<Address>,
+    #[serde(default)]
+    active_from: Option<DateTime<Utc>>,
+    #[serde(default)]
+    redacted: bool,
+    /// Representatives with roles; when set, `representative` holds the lead's name
+    #[serde(default)]
+    representatives: Vec<Representative>,
+    /// Earner revenue updates, oldest first, each dated from when it took effect
+    #[serde(default)]
+    revenue_history: Vec<(DateTime<Utc>, Vec<f64>)>,
+    /// Ways to reach the representative, primary first
+    #[serde(default)]
+    contacts: Vec<Contact>,
 }
 
-impl Team {
-    // Constructor
-    pub fn new(name: String, representative: String, trailing_monthly_revenue: Option<Vec<u64>>, address: Option<String>) -> Result<Self, &'static str> {
-        if name.trim().is_empty() {
+/// Builds a team with contacts. Without a representative name, the primary contact's value stands in for it.
+#[derive(Clone, Debug, Default)]
+pub struct TeamBuilder {
+    name: String,
+    representative: Option<String>,
+    contacts: Vec<Contact>,
+    trailing_monthly_revenue: Option<Vec<f64>>,
+    address: Option<String>,
+}
+
+impl TeamBuilder {
+    pub fn representative(mut self, representative: impl Into<String>) -> Self {
+        self.representative = Some(representative.into());
+        self
+    }
+
+    pub fn contact(mut self, contact: Contact) -> Self {
+        self.contacts.push(contact);
+        self
+    }
+
+    pub fn contacts(mut self, contacts: Vec<Contact>) -> Self {
+        self.contacts.extend(contacts);
+        self
+    }
+
+    pub fn trailing_monthly_revenue(mut self, revenue: Vec<f64>) -> Self {
+        self.trailing_monthly_revenue = Some(revenue);
+        self
+    }
+
+    pub fn payment_address(mut self, address: impl Into<String>) -> Self {
+        self.address = Some(address.into());
+        self
+    }
+
+    pub fn build(self) -> Result<Team, &'static str> {
+        if self.name.trim().is_empty() {
             return Err("Team name cannot be empty");
         }
-        if representative.trim().is_empty() {
-            return Err("Representative name cannot be empty");
+        for contact in &self.contacts {
+            contact.validate()?;
         }
+        let representative = self.representative
+            .filter(|representative| !representative.trim().is_empty())
+            .or_else(|| self.contacts.first().map(|contact| contact.value.clone()))
+            .ok_or("Representative name cannot be empty")?;
 
-        let payment_address = match address {
+        let payment_address = match self.address {
             Some(addr) => Some(
                 Address::from_str(&addr)
                     .map_err(|_| "Invalid Ethereum address")?
@@ -39,27 +203,74 @@ impl Team {
             None => None,
         };
 
-        let status = match trailing_monthly_revenue {
+        let status = match self.trailing_monthly_revenue {
             Some(revenue) => {
                 if revenue.is_empty() {
                     return Err("Revenue data cannot be empty");
-                } else if revenue.len() > 3 {
-                    return Err("Revenue data cannot exceed 3 entries");  
-                } 
+                }
+                validate_revenue(&revenue)?;
 
                 TeamStatus::Earner { trailing_monthly_revenue: revenue }
             },
             None => TeamStatus::Supporter,
         };
 
+        let now = Utc::now();
+        let revenue_history = match &status {
+            TeamStatus::Earner { trailing_monthly_revenue } => vec![(now, trailing_monthly_revenue.clone())],
+            _ => Vec::new(),
+        };
+
         Ok(Team {
             id: Uuid::new_v4(),
-            name,
+            name: self.name,
             representative,
             status,
             payment_address,
+            active_from: Some(now),
+            redacted: false,
+            representatives: Vec::new(),
+            revenue_history,
+            contacts: self.contacts,
         })
     }
+}
+
+const MAX_REVENUE: f64 = u64::MAX as f64;
+
+/// Checks trailing revenue against the limits shared by new teams and status changes
+fn validate_revenue(revenue: &[f64]) -> Result<(), &'static str> {
+    if revenue.len() > 3 {
+        return Err("Revenue data cannot exceed 3 entries");
+    }
+    if revenue.iter().any(|value| !value.is_finite() || *value < 0.0) {
+        return Err("Revenue values must be finite and non-negative");
+    }
+    // Raffle tickets grow with the square root of revenue, so the old whole-number range stays the limit
+    if revenue.iter().any(|value| *value > MAX_REVENUE) {
+        return Err("Revenue values are too large");
+    }
+    Ok(())
+}
+
+impl Team {
+    // Constructor
+    pub fn new(name: String, representative: String, trailing_monthly_revenue: Option<Vec<f64>>, address: Option<String>) -> Result<Self, &'static str> {
+        if representative.trim().is_empty() {
+            return Err("Representative name cannot be empty");
+        }
+        TeamBuilder {
+            name,
+            representative: Some(representative),
+            contacts: Vec::new(),
+            trailing_monthly_revenue,
+            address,
+        }.build()
+    }
+
+    pub fn builder(name: impl Into<String>) -> TeamBuilder {
+        TeamBuilder { name: name.into(), ..TeamBuilder::default() }
+    }
 
     // Getter methods
     pub fn id(&self) -> Uuid {
@@ -74,6 +285,26 @@ impl Team {
         &self.representative
     }
 
+    pub fn representatives(&self) -> &[Representative] {
+        &self.representatives
+    }
+
+    /// The first representative holding `role`, compared case-insensitively
+    pub fn representative_for_role(&self, role: &str) -> Option<&Representative> {
+        self.representatives.iter().find(|rep| rep.role.eq_ignore_ascii_case(role))
+    }
+
+    pub fn contacts(&self) -> &[Contact] {
+        &self.contacts
+    }
+
+    /// The first Telegram contact, for @-mentioning the representative
+    pub fn telegram_handle(&self) -> Option<&str> {
+        self.contacts.iter()
+            .find(|contact| contact.kind == ContactKind::Telegram)
+            .map(|contact| contact.value.as_str())
+    }
+
     pub fn status(&self) -> &TeamStatus {
         &self.status
     }
@@ -82,30 +313,112 @@ impl Team {
         self.payment_address.as_ref()
     }
 
+    /// When the team became eligible; `None` for teams that predate tracking
+    pub fn active_from(&self) -> Option<DateTime<Utc>> {
+        self.active_from
+    }
+
+    pub fn is_redacted(&self) -> bool {
+        self.redacted
+    }
+
+    pub fn revenue_history(&self) -> &[(DateTime<Utc>, Vec<f64>)] {
+        &self.revenue_history
+    }
+
+    /// The trailing revenue in effect at `at`, or `None` if no update had been recorded by then
+    pub fn revenue_at(&self, at: DateTime<Utc>) -> Option<&Vec<f64>> {
+        self.revenue_history.iter()
+            .rev()
+            .find(|(effective, _)| *effective <= at)
+            .map(|(_, revenue)| revenue)
+    }
+
     // Setter methods
     pub fn set_name(&mut self, name: String) {
         self.name = name;
     }
 
     pub fn set_representative(&mut self, representative: String) {
+        if let Some(lead) = self.representatives.iter_mut().find(|rep| rep.role.eq_ignore_ascii_case(LEAD_ROLE)) {
+            lead.name = representative.clone();
+        }
         self.representative = representative;
     }
 
+    /// Replaces the representatives. A non-empty list needs exactly one lead, whose name
+    /// becomes the legacy representative; an empty list keeps the legacy representative.
+    pub fn set_representatives(&mut self, representatives: Vec<Representative>) -> Result<(), &'static str> {
+        if representatives.iter().any(|rep| rep.name.trim().is_empty() || rep.role.trim().is_empty()) {
+            return Err("Representative name and role cannot be empty");
+        }
+
+        if !representatives.is_empty() {
+            let mut leads = representatives.iter().filter(|rep| rep.role.eq_ignore_ascii_case(LEAD_ROLE));
+            let lead = leads.next().ok_or("Representatives must include a lead")?;
+            if leads.next().is_some() {
+                return Err("A team can only have one lead representative");
+            }
+            self.representative = lead.name.clone();
+        }
+
+        self.representatives = representatives;
+        Ok(())
+    }
+
+    /// Replaces the contacts, validating each one; the first becomes the primary contact
+    pub fn set_contacts(&mut self, contacts: Vec<Contact>) -> Result<(), &'static str> {
+        for contact in &contacts {
+            contact.validate()?;
+        }
+        self.contacts = contacts;
+        Ok(())
+    }
+
     pub fn set_status(&mut self, new_status: TeamStatus) -> Result<(), &'static str> {
-        match new_status {
-            TeamStatus::Earner { ref trailing_monthly_revenue } if trailing_monthly_revenue.is_empty() => {
-                Err("Trailing revenue data must be provided when changing to Earner status")
-            },
-            TeamStatus::Earner { trailing_monthly_revenue } if trailing_monthly_revenue.len() > 3 => {
-                Err("Revenue data cannot exceed 3 entries")
-            },
-            _ => {
-                self.status = new_status;
-                Ok(())
+        if let TeamStatus::Earner { trailing_monthly_revenue } = &new_status {
+            if trailing_monthly_revenue.is_empty() {
+                return Err("Trailing revenue data must be provided when changing to Earner status");
+            }
+            validate_revenue(trailing_monthly_revenue)?;
+
+            if self.revenue_history.last().map(|(_, revenue)| revenue) != Some(trailing_monthly_revenue) {
+                self.revenue_history.push((Utc::now(), trailing_monthly_revenue.clone()));
+            }
+        }
+
+        self.status = new_status;
+        Ok(())
+    }
+
+    pub fn set_active_from(&mut self, active_from: Option<DateTime<Utc>>) {
+        self.active_from = active_from;
+    }
+
+    /// Teams saved before revenue history was tracked get their current revenue dated at `at`
+    pub fn seed_revenue_history(&mut self, at: DateTime<Utc>) {
+        if self.revenue_history.is_empty() {
+            if let TeamStatus::Earner { trailing_monthly_revenue } = &self.status {
+                self.revenue_history.push((at, trailing_monthly_revenue.clone()));
             }
         }
     }
 
+    /// Replaces the name with a placeholder derived from the ID and clears the representative,
+    /// contacts, payment address and revenue history. Earners become supporters, since revenue is required for earners.
+    pub fn redact(&mut self) {
+        self.name = format!("Redacted Team {}", &self.id.simple().to_string()[..8]);
+        self.representative = "Redacted".to_string();
+        self.representatives.clear();
+        self.contacts.clear();
+        self.payment_address = None;
+        self.revenue_history.clear();
+        if let TeamStatus::Earner { .. } = self.status {
+            self.status = TeamStatus::Supporter;
+        }
+        self.redacted = true;
+    }
+
     pub fn set_payment_address(&mut self, address: Option<String>) -> Result<(), &'static str> {
         self.payment_address = match address {
             Some(addr) => Some(Address::from_str(&addr).map_err(|_| "Invalid Ethereum address")?),
@@ -131,12 +444,20 @@ impl Team {
         matches!(self.status, TeamStatus::Inactive)
     }
 
+    pub fn is_active_at(&self, at: DateTime<Utc>) -> bool {
+        self.is_active() && self.active_from.map_or(true, |from| from <= at)
+    }
+
 }
 
 impl NameMatches for Team {
     fn name_matches(&self, name: &str) -> bool {
         self.name() == name
     }
+
+    fn name_contains(&self, query: &str) -> bool {
+        contains_ignore_case(self.name(), query)
+    }
 }
 
 #[cfg(test)]
@@ -146,7 +467,7 @@ mod tests {
 
     #[test]
     fn test_create_valid_team() {
-        let earner = Team::new("Earner Team".to_string(), "John Doe".to_string(), Some(vec![1000, 2000, 3000]), None).unwrap();
+        let earner = Team::new("Earner Team".to_string(), "John Doe".to_string(), Some(vec![1000.0, 2000.0, 3000.0]), None).unwrap();
         assert_eq!(earner.name(), "Earner Team");
         assert_eq!(earner.representative(), "John Doe");
         assert!(matches!(earner.status(), TeamStatus::Earner { .. }));
@@ -168,7 +489,7 @@ mod tests {
 
     #[test]
     fn test_getter_methods() {
-        let team = Team::new("Test Team".to_string(), "Test Rep".to_string(), Some(vec![1000]), None).unwrap();
+        let team = Team::new("Test Team".to_string(), "Test Rep".to_string(), Some(vec![1000.0]), None).unwrap();
         assert_eq!(team.name(), "Test Team");
         assert_eq!(team.representative(), "Test Rep");
         assert!(matches!(team.status(), TeamStatus::Earner { .. }));
@@ -184,7 +505,7 @@ mod tests {
         team.set_representative("New Rep".to_string());
         assert_eq!(team.representative(), "New Rep");
 
-        team.set_status(TeamStatus::Earner { trailing_monthly_revenue: vec![1000, 2000] }).unwrap();
+        team.set_status(TeamStatus::Earner { trailing_monthly_revenue: vec![1000.0, 2000.0] }).unwrap();
         assert!(matches!(team.status(), TeamStatus::Earner { .. }));
     }
 
@@ -192,7 +513,7 @@ mod tests {
     fn test_status_changes() {
         let mut team = Team::new("Test Team".to_string(), "Test Rep".to_string(), None, None).unwrap();
         
-        assert!(team.set_status(TeamStatus::Earner { trailing_monthly_revenue: vec![1000] }).is_ok());
+        assert!(team.set_status(TeamStatus::Earner { trailing_monthly_revenue: vec![1000.0] }).is_ok());
         assert!(team.is_earner());
 
         assert!(team.set_status(TeamStatus::Supporter).is_ok());
@@ -211,7 +532,7 @@ mod tests {
         assert!(!team.is_earner());
         assert!(!team.is_inactive());
 
-        team.set_status(TeamStatus::Earner { trailing_monthly_revenue: vec![1000] }).unwrap();
+        team.set_status(TeamStatus::Earner { trailing_monthly_revenue: vec![1000.0] }).unwrap();
         assert!(team.is_active());
         assert!(team.is_earner());
         assert!(!team.is_supporter());
@@ -228,11 +549,11 @@ mod tests {
     fn test_team_status_validation() {
         let mut team = Team::new("Test Team".to_string(), "Test Rep".to_string(), None, None).unwrap();
         
-        assert!(team.set_status(TeamStatus::Earner { trailing_monthly_revenue: vec![1000] }).is_ok());
-        assert!(team.set_status(TeamStatus::Earner { trailing_monthly_revenue: vec![1000, 2000, 3000] }).is_ok());
+        assert!(team.set_status(TeamStatus::Earner { trailing_monthly_revenue: vec![1000.0] }).is_ok());
+        assert!(team.set_status(TeamStatus::Earner { trailing_monthly_revenue: vec![1000.0, 2000.0, 3000.0] }).is_ok());
         
         assert!(team.set_status(TeamStatus::Earner { trailing_monthly_revenue: vec![] }).is_err());
-        assert!(team.set_status(TeamStatus::Earner { trailing_monthly_revenue: vec![1000, 2000, 3000, 4000] }).is_err());
+        assert!(team.set_status(TeamStatus::Earner { trailing_monthly_revenue: vec![1000.0, 2000.0, 3000.0, 4000.0] }).is_err());
     }
 
     #[test]
@@ -243,13 +564,21 @@ mod tests {
         assert_eq!(team.name(), long_name);
         assert_eq!(team.representative(), long_rep);
 
-        let max_revenue = u64::MAX;
+        let max_revenue = u64::MAX as f64;
         let team = Team::new("Max Revenue".to_string(), "Test Rep".to_string(), Some(vec![max_revenue]), None).unwrap();
         if let TeamStatus::Earner { trailing_monthly_revenue } = team.status() {
             assert_eq!(trailing_monthly_revenue[0], max_revenue);
         } else {
             panic!("Expected Earner status");
         }
+
+        // Larger values would make the raffle draw an unbounded number of tickets
+        for extreme in [1e30, f64::MAX] {
+            assert_eq!(
+                Team::new("Extreme Revenue".to_string(), "Test Rep".to_string(), Some(vec![extreme]), None).unwrap_err(),
+                "Revenue values are too large"
+            );
+        }
     }
 
     #[test]
@@ -257,7 +586,7 @@ mod tests {
         let original_team = Team::new(
             "Serialize Team".to_string(),
             "Serialize Rep".to_string(),
-            Some(vec![1000, 2000, 3000]),
+            Some(vec![1000.0, 2000.0, 3000.0]),
             None
         ).unwrap();
 
@@ -297,7 +626,7 @@ mod tests {
         let team = Team::new(
             "Test Team".to_string(),
             "Representative".to_string(),
-            Some(vec![1000]),
+            Some(vec![1000.0]),
             Some(valid_address.clone())
         ).unwrap();
         assert!(team.payment_address().is_some());
@@ -306,7 +635,7 @@ mod tests {
         let team_no_addr = Team::new(
             "Test Team 2".to_string(),
             "Representative".to_string(),
-            Some(vec![1000]),
+            Some(vec![1000.0]),
             None
         ).unwrap();
         assert!(team_no_addr.payment_address().is_none());
@@ -315,9 +644,184 @@ mod tests {
         let result = Team::new(
             "Test Team 3".to_string(),
             "Representative".to_string(),
-            Some(vec![1000]),
+            Some(vec![1000.0]),
             Some("invalid_address".to_string())
         );
         assert!(result.is_err());
     }
-}
\ No newline at end of file
+    #[test]
+    fn test_active_from() {
+        let mut team = Team::new("Test Team".to_string(), "Test Rep".to_string(), None, None).unwrap();
+        let joined = team.active_from().unwrap();
+        assert!(team.is_active_at(joined));
+        assert!(!team.is_active_at(joined - chrono::Duration::days(1)));
+
+        team.set_active_from(None);
+        assert!(team.is_active_at(joined - chrono::Duration::days(1)));
+
+        team.set_status(TeamStatus::Inactive).unwrap();
+        assert!(!team.is_active_at(joined));
+    }
+
+    #[test]
+    fn test_redact() {
+        let mut team = Team::new(
+            "Test Team".to_string(),
+            "Test Rep".to_string(),
+            Some(vec![1000.0]),
+            Some("0x742d35Cc6634C0532925a3b844Bc454e4438f44e".to_string())
+        ).unwrap();
+        let id = team.id();
+        team.redact();
+
+        assert!(team.is_redacted());
+        assert_eq!(team.id(), id);
+        assert!(team.name().starts_with("Redacted Team "));
+        assert!(id.simple().to_string().starts_with(&team.name()["Redacted Team ".len()..]));
+        assert_eq!(team.representative(), "Redacted");
+        assert_eq!(team.payment_address(), None);
+        assert_eq!(team.status(), &TeamStatus::Supporter);
+    }
+
+    #[test]
+    fn test_set_representatives() {
+        let mut team = Team::new("Test Team".to_string(), "Old Rep".to_string(), None, None).unwrap();
+        let rep = |name: &str, role: &str| Representative { name: name.to_string(), role: role.to_string(), handle: None };
+
+        assert!(team.set_representatives(vec![rep("Bob", "treasurer")]).is_err());
+        assert!(team.set_representatives(vec![rep("Alice", "lead"), rep("Bob", "Lead")]).is_err());
+        assert!(team.set_representatives(vec![rep("", "lead")]).is_err());
+        assert_eq!(team.representative(), "Old Rep");
+
+        team.set_representatives(vec![
+            rep("Bob", "treasurer"),
+            Representative { name: "Alice".to_string(), role: "Lead".to_string(), handle: Some("@alice".to_string()) },
+            rep("Carol", "backup"),
+        ]).unwrap();
+        assert_eq!(team.representative(), "Alice");
+        assert_eq!(team.representative_for_role(TREASURER_ROLE).unwrap().name, "Bob");
+        assert_eq!(team.representative_for_role("lead").unwrap().handle.as_deref(), Some("@alice"));
+
+        // The legacy setter renames the lead
+        team.set_representative("Dave".to_string());
+        assert_eq!(team.representative_for_role(LEAD_ROLE).unwrap().name, "Dave");
+
+        // Older state files have no representatives list
+        let mut value = serde_json::to_value(&team).unwrap();
+        value.as_object_mut().unwrap().remove("representatives");
+        let legacy: Team = serde_json::from_value(value).unwrap();
+        assert!(legacy.representatives().is_empty());
+        assert_eq!(legacy.representative(), "Dave");
+    }
+
+    #[test]
+    fn test_revenue_history() {
+        let mut team = Team::new("Earner Team".to_string(), "John Doe".to_string(), Some(vec![1000.0]), None).unwrap();
+        let created = team.revenue_history()[0].0;
+        assert_eq!(team.revenue_at(created), Some(&vec![1000.0]));
+        assert_eq!(team.revenue_at(created - chrono::Duration::seconds(1)), None);
+
+        team.set_status(TeamStatus::Earner { trailing_monthly_revenue: vec![1000.0, 2000.0] }).unwrap();
+        team.set_status(TeamStatus::Earner { trailing_monthly_revenue: vec![1000.0, 2000.0] }).unwrap();
+        assert_eq!(team.revenue_history().len(), 2);
+        let updated = team.revenue_history()[1].0;
+        assert_eq!(team.revenue_at(updated), Some(&vec![1000.0, 2000.0]));
+        assert_eq!(team.revenue_at(created), Some(&vec![1000.0]));
+
+        team.set_status(TeamStatus::Supporter).unwrap();
+        assert_eq!(team.revenue_history().len(), 2);
+
+        team.redact();
+        assert!(team.revenue_history().is_empty());
+    }
+
+    #[test]
+    fn test_seed_revenue_history_from_legacy_team() {
+        let team = Team::new("Earner Team".to_string(), "John Doe".to_string(), Some(vec![1000.0]), None).unwrap();
+        let mut json = serde_json::to_value(&team).unwrap();
+        json.as_object_mut().unwrap().remove("revenue_history");
+
+        let mut legacy: Team = serde_json::from_value(json).unwrap();
+        assert!(legacy.revenue_history().is_empty());
+
+        let loaded_at = Utc::now();
+        legacy.seed_revenue_history(loaded_at);
+        legacy.seed_revenue_history(loaded_at + chrono::Duration::days(1));
+        assert_eq!(legacy.revenue_history(), &[(loaded_at, vec![1000.0])]);
+    }
+
+    #[test]
+    fn test_reject_invalid_revenue() {
+        let fractional = Team::new("Fractional".to_string(), "Rep".to_string(), Some(vec![999.5, 0.25]), None).unwrap();
+        assert_eq!(fractional.status(), &TeamStatus::Earner { trailing_monthly_revenue: vec![999.5, 0.25] });
+
+        for revenue in [vec![-1.0], vec![1000.0, -0.01], vec![f64::NAN], vec![f64::INFINITY]] {
+            assert_eq!(
+                Team::new("Bad".to_string(), "Rep".to_string(), Some(revenue), None).unwrap_err(),
+                "Revenue values must be finite and non-negative"
+            );
+        }
+
+        let mut team = Team::new("Team".to_string(), "Rep".to_string(), Some(vec![1000.0]), None).unwrap();
+        assert_eq!(
+            team.set_status(TeamStatus::Earner { trailing_monthly_revenue: vec![1000.0, -500.0] }).unwrap_err(),
+            "Revenue values must be finite and non-negative"
+        );
+        assert_eq!(team.status(), &TeamStatus::Earner { trailing_monthly_revenue: vec![1000.0] });
+        assert_eq!(team.revenue_history().len(), 1);
+    }
+
+    #[test]
+    fn test_integer_revenue_still_loads() {
+        let json = r#"{"Earner": {"trailing_monthly_revenue": [1000, 2000, 3000]}}"#;
+        let status: TeamStatus = serde_json::from_str(json).unwrap();
+        assert_eq!(status, TeamStatus::Earner { trailing_monthly_revenue: vec![1000.0, 2000.0, 3000.0] });
+    }
+
+    #[test]
+    fn test_contact_validation() {
+        assert!(Contact::new(ContactKind::Telegram, "@alice_dev").is_ok());
+        assert!(Contact::new(ContactKind::Telegram, "alice_dev").is_err());
+        assert!(Contact::new(ContactKind::Telegram, "@al").is_err());
+        assert!(Contact::new(ContactKind::Email, "alice@example.com").is_ok());
+        assert!(Contact::new(ContactKind::Email, "alice@example").is_err());
+        assert!(Contact::new(ContactKind::Email, "@example.com").is_err());
+        assert!(Contact::new(ContactKind::Other, " ").is_err());
+
+        let contact: Contact = "email: alice@example.com".parse().unwrap();
+        assert_eq!(contact, Contact { kind: ContactKind::Email, value: "alice@example.com".to_string() });
+        assert!("fax:123".parse::<Contact>().is_err());
+        assert!("@alice".parse::<Contact>().is_err());
+    }
+
+    #[test]
+    fn test_team_builder_with_contacts() {
+        let team = Team::builder("Contact Team")
+            .contact(Contact::new(ContactKind::Telegram, "@alice_dev").unwrap())
+            .contact(Contact::new(ContactKind::Email, "alice@example.com").unwrap())
+            .trailing_monthly_revenue(vec![1000.0])
+            .build()
+            .unwrap();
+        assert_eq!(team.representative(), "@alice_dev");
+        assert_eq!(team.contacts().len(), 2);
+        assert_eq!(team.telegram_handle(), Some("@alice_dev"));
+        assert!(team.is_earner());
+
+        let named = Team::builder("Named Team").representative("Alice")
+            .contact(Contact::new(ContactKind::Email, "alice@example.com").unwrap())
+            .build()
+            .unwrap();
+        assert_eq!(named.representative(), "Alice");
+        assert_eq!(named.telegram_handle(), None);
+
+        assert!(Team::builder("No Contact").build().is_err());
+        let invalid = Contact { kind: ContactKind::Telegram, value: "alice".to_string() };
+        assert!(Team::builder("Bad Contact").contact(invalid.clone()).build().is_err());
+
+        let mut team = team;
+        assert!(team.set_contacts(vec![invalid]).is_err());
+        assert_eq!(team.contacts().len(), 2);
+        team.redact();
+        assert!(team.contacts().is_empty());
+    }
+}
@@ -10,6 +10,7 @@ pub struct Vote {
     id: Uuid,
     proposal_id: Uuid,
     epoch_id: Uuid,
+    #[serde(deserialize_with = "deserialize_vote_type")]
     vote_type: VoteType,
     status: VoteStatus,
     participation: VoteParticipation,
@@ -17,7 +18,16 @@ pub struct Vote {
     opened_at: DateTime<Utc>,
     closed_at: Option<DateTime<Utc>>,
     is_historical: bool,
-    votes: HashMap<Uuid, VoteChoice> // leave private, temporarily stored
+    /// Cast against a raffle that may still be re-drawn; must be re-bound before closing
+    #[serde(default)]
+    provisional: bool,
+    /// The informal vote this formal vote was promoted from
+    #[serde(default)]
+    promoted_from: Option<Uuid>,
+    /// The formal vote this informal vote was promoted to
+    #[serde(default)]
+    promoted_to: Option<Uuid>,
+    votes: HashMap<Uuid, VoteChoice> // leave private; kept after closing so the outcome can be recounted
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -26,12 +36,63 @@ pub enum VoteType {
         raffle_id: Uuid,
         total_eligible_seats: u32,
         threshold: f64,
+        /// Fraction of eligible seats that must cast counted votes
+        #[serde(default)]
+        quorum: f64,
         counted_points: u32,
         uncounted_points: u32,
     },
+    /// Counted teams vote with their epoch points, snapshotted when the vote is created
+    Weighted {
+        raffle_id: Uuid,
+        threshold: f64,
+        weights: HashMap<Uuid, u32>,
+        counted_points: u32,
+        uncounted_points: u32,
+    },
+    Informal {
+        #[serde(default)]
+        weighting: InformalWeighting,
+    },
+    /// Raffle-gated vote among several options; the option with the most counted votes wins
+    MultiOption {
+        raffle_id: Uuid,
+        options: Vec<String>,
+        total_eligible_seats: u32,
+        counted_points: u32,
+        uncounted_points: u32,
+    },
+}
+
+/// Informal votes were stored as the unit variant `"Informal"` before they had a weighting
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredVoteType {
+    Current(VoteType),
+    Legacy(LegacyVoteType),
+}
+
+#[derive(Deserialize)]
+enum LegacyVoteType {
     Informal,
 }
 
+fn deserialize_vote_type<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<VoteType, D::Error> {
+    Ok(match StoredVoteType::deserialize(deserializer)? {
+        StoredVoteType::Current(vote_type) => vote_type,
+        StoredVoteType::Legacy(LegacyVoteType::Informal) => VoteType::Informal { weighting: InformalWeighting::default() },
+    })
+}
+
+/// How informal votes are weighed in addition to the raw count
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum InformalWeighting {
+    #[default]
+    Unweighted,
+    /// Team weights from trailing monthly revenue, snapshotted when the vote is created
+    Revenue { weights: HashMap<Uuid, u64> },
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum VoteStatus {
     Open,
@@ -42,6 +103,9 @@ pub enum VoteStatus {
 pub enum VoteChoice {
     Yes,
     No,
+    Abstain,
+    /// Index into the options of a multi-option vote
+    Option(usize),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -59,16 +123,61 @@ pub enum VoteResult {
         counted: VoteCount,
         uncounted: VoteCount,
         passed: bool,
+        #[serde(default = "default_quorum_reached")]
+        quorum_reached: bool,
+        /// Passed with the yes fraction exactly on the threshold
+        #[serde(default)]
+        at_threshold: bool,
+    },
+    Weighted {
+        counted: WeightedTally,
+        uncounted: VoteCount,
+        passed: bool,
+        choices: HashMap<Uuid, VoteChoice>,
     },
     Informal {
         count: VoteCount,
+        #[serde(default)]
+        weighted: Option<WeightedTally>,
     },
+    /// Votes per option, by index; ties go to the lowest index
+    MultiOption {
+        tallies: Vec<u32>,
+        uncounted_tallies: Vec<u32>,
+        winner: usize,
+    },
+}
+
+fn default_quorum_reached() -> bool {
+    true
+}
+
+/// Yes fractions this close to the threshold count as exactly on it, so that e.g. 7 of 10 seats meets 0.7
+const THRESHOLD_TOLERANCE: f64 = 1e-9;
+
+/// Whether `yes` of the eligible seats meets the threshold, and whether it lands exactly on it.
+/// The comparison is inclusive: a fraction equal to the threshold passes, so an even split passes 0.5.
+fn compare_to_threshold(yes: u32, total_eligible_seats: u32, threshold: f64) -> (bool, bool) {
+    let fraction = yes as f64 / total_eligible_seats as f64;
+    let exact = (fraction - threshold).abs() < THRESHOLD_TOLERANCE;
+    (exact || fraction >= threshold, exact)
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct VoteCount {
     yes: u32,
     no: u32,
+    #[serde(default)]
+    abstain: u32,
+}
+
+/// Counted-seat totals for a weighted vote, in points rather than seats
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct WeightedTally {
+    yes: u64,
+    no: u64,
+    abstain: u64,
+    total_weight: u64,
 }
 
 impl Vote {
@@ -80,11 +189,11 @@ impl Vote {
         is_historical: bool,
     ) -> Self {
         let participation = match &vote_type {
-            VoteType::Formal { .. } => VoteParticipation::Formal { 
+            VoteType::Formal { .. } | VoteType::Weighted { .. } | VoteType::MultiOption { .. } => VoteParticipation::Formal { 
                 counted: Vec::new(), 
                 uncounted: Vec::new() 
             },
-            VoteType::Informal => VoteParticipation::Informal(Vec::new()),
+            VoteType::Informal { .. } => VoteParticipation::Informal(Vec::new()),
         };
 
         Self {
@@ -98,6 +207,9 @@ impl Vote {
             opened_at: Utc::now(),
             closed_at: None,
             is_historical,
+            provisional: false,
+            promoted_from: None,
+            promoted_to: None,
             votes: HashMap::new(),
         }
     }
@@ -113,6 +225,19 @@ impl Vote {
     pub fn opened_at(&self) -> DateTime<Utc> { self.opened_at }
     pub fn closed_at(&self) -> Option<DateTime<Utc>> { self.closed_at }
     pub fn is_historical(&self) -> bool { self.is_historical }
+    pub fn is_provisional(&self) -> bool { self.provisional }
+    pub fn promoted_from(&self) -> Option<Uuid> { self.promoted_from }
+    pub fn promoted_to(&self) -> Option<Uuid> { self.promoted_to }
+
+    /// Raffle that decided the counted seats; informal votes have none
+    pub fn raffle_id(&self) -> Option<Uuid> {
+        match &self.vote_type {
+            VoteType::Formal { raffle_id, .. }
+            | VoteType::Weighted { raffle_id, .. }
+            | VoteType::MultiOption { raffle_id, .. } => Some(*raffle_id),
+            VoteType::Informal { .. } => None,
+        }
+    }
 
     pub fn vote_counts(&self) -> Option<(VoteCount, VoteCount)> {
         match &self.result {
@@ -126,6 +251,9 @@ impl Vote {
     pub fn set_result(&mut self, result: Option<VoteResult>) { self.result = result; }
     pub fn set_opened_at(&mut self, date: DateTime<Utc>) { self.opened_at = date; }
     pub fn set_closed_at(&mut self, date: Option<DateTime<Utc>>) { self.closed_at = date; }
+    pub fn set_provisional(&mut self, provisional: bool) { self.provisional = provisional; }
+    pub fn set_promoted_from(&mut self, vote_id: Option<Uuid>) { self.promoted_from = vote_id; }
+    pub fn set_promoted_to(&mut self, vote_id: Option<Uuid>) { self.promoted_to = vote_id; }
 
     // Core functionality
     pub fn cast_vote(&mut self, team_id: Uuid, choice: VoteChoice, raffle_result: Option<&RaffleResult>) -> Result<(), &'static str> {
@@ -133,11 +261,21 @@ impl Vote {
             return Err("Vote is closed");
         }
 
+        match (&self.vote_type, choice) {
+            (VoteType::MultiOption { options, .. }, VoteChoice::Option(index)) if index >= options.len() => {
+                return Err("Option index out of range");
+            },
+            (VoteType::MultiOption { .. }, VoteChoice::Option(_)) => {},
+            (VoteType::MultiOption { .. }, _) => return Err("Multi-option votes take an option index"),
+            (_, VoteChoice::Option(_)) => return Err("Option choices are only valid for multi-option votes"),
+            _ => {},
+        }
+
         self.votes.insert(team_id, choice);
 
         match &mut self.participation {
             VoteParticipation::Formal { counted, uncounted } => {
-                if let (VoteType::Formal { .. }, Some(raffle_result)) = (&self.vote_type, raffle_result) {
+                if let (VoteType::Formal { .. } | VoteType::Weighted { .. } | VoteType::MultiOption { .. }, Some(raffle_result)) = (&self.vote_type, raffle_result) {
                     if raffle_result.counted().contains(&team_id) {
                         if !counted.contains(&team_id) {
                             counted.push(team_id);
@@ -168,15 +306,133 @@ impl Vote {
             return Err("Vote is already closed");
         }
 
+        if self.provisional {
+            return Err("Vote is provisional and must be re-bound to a raffle before closing");
+        }
+
         self.status = VoteStatus::Closed;
         self.closed_at = Some(Utc::now());
 
         self.calculate_result()?;
+
+        Ok(())
+    }
+
+    pub fn has_participant(&self, team_id: Uuid) -> bool {
+        match &self.participation {
+            VoteParticipation::Formal { counted, uncounted } => counted.contains(&team_id) || uncounted.contains(&team_id),
+            VoteParticipation::Informal(teams) => teams.contains(&team_id),
+        }
+    }
+
+    /// Moves a team's participation, recorded choice and snapshotted weight to another team, for merging teams.
+    /// Refused when both teams took part, since the merged team would be counted twice.
+    pub fn reassign_participant(&mut self, from: Uuid, to: Uuid) -> Result<(), &'static str> {
+        if self.has_participant(from) && self.has_participant(to) {
+            return Err("Both teams took part in the vote");
+        }
+
+        let replace = |teams: &mut Vec<Uuid>| {
+            for team_id in teams.iter_mut().filter(|team_id| **team_id == from) {
+                *team_id = to;
+            }
+        };
+        match &mut self.participation {
+            VoteParticipation::Formal { counted, uncounted } => {
+                replace(counted);
+                replace(uncounted);
+            },
+            VoteParticipation::Informal(teams) => replace(teams),
+        }
+
+        if let Some(choice) = self.votes.remove(&from) {
+            self.votes.insert(to, choice);
+        }
+        if let Some(VoteResult::Weighted { choices, .. }) = &mut self.result {
+            if let Some(choice) = choices.remove(&from) {
+                choices.insert(to, choice);
+            }
+        }
+
+        // Snapshotted weights cover teams that did not vote too, so the source's weight is added to the target's
+        match &mut self.vote_type {
+            VoteType::Weighted { weights, .. } => {
+                if let Some(weight) = weights.remove(&from) {
+                    *weights.entry(to).or_insert(0) += weight;
+                }
+            },
+            VoteType::Informal { weighting: InformalWeighting::Revenue { weights } } => {
+                if let Some(weight) = weights.remove(&from) {
+                    *weights.entry(to).or_insert(0) += weight;
+                }
+            },
+            _ => {},
+        }
+
+        Ok(())
+    }
+
+    /// Clears every cast vote and participant, returning an open vote to its freshly created state
+    pub fn reset(&mut self) -> Result<(), &'static str> {
+        if self.is_closed() {
+            return Err("Vote is closed");
+        }
+
         self.votes.clear();
+        self.participation = match self.participation {
+            VoteParticipation::Formal { .. } => VoteParticipation::Formal { counted: Vec::new(), uncounted: Vec::new() },
+            VoteParticipation::Informal(_) => VoteParticipation::Informal(Vec::new()),
+        };
+        self.result = None;
 
         Ok(())
     }
 
+    /// Moves a provisional formal vote onto a re-drawn raffle. Recorded votes are re-checked
+    /// against the new seats; returns the teams whose votes were dropped as no longer eligible.
+    pub fn rebind_to_raffle(&mut self, new_raffle_id: Uuid, new_total_eligible_seats: u32, raffle_result: &RaffleResult) -> Result<Vec<Uuid>, &'static str> {
+        if self.is_closed() {
+            return Err("Vote is closed");
+        }
+
+        if !self.provisional {
+            return Err("Only provisional votes can be re-bound");
+        }
+
+        match &mut self.vote_type {
+            VoteType::Formal { raffle_id, total_eligible_seats, .. } => {
+                *raffle_id = new_raffle_id;
+                *total_eligible_seats = new_total_eligible_seats;
+            },
+            _ => return Err("Only formal votes can be re-bound"),
+        }
+
+        let mut counted = Vec::new();
+        let mut uncounted = Vec::new();
+        let mut dropped = Vec::new();
+
+        self.votes.retain(|team_id, _| {
+            if raffle_result.counted().contains(team_id) {
+                counted.push(*team_id);
+            } else if raffle_result.uncounted().contains(team_id) {
+                uncounted.push(*team_id);
+            } else {
+                dropped.push(*team_id);
+                return false;
+            }
+            true
+        });
+
+        counted.sort();
+        uncounted.sort();
+        dropped.sort();
+
+        self.participation = VoteParticipation::Formal { counted, uncounted };
+        self.provisional = false;
+
+        Ok(dropped)
+    }
+
     pub fn add_participant(&mut self, team_id: Uuid, is_counted: bool) -> Result<(), &'static str> {
         match &mut self.participation {
             VoteParticipation::Formal { counted, uncounted } => {
@@ -199,6 +455,18 @@ impl Vote {
         Ok(())
     }
 
+    /// Takes a team out of the participation, for correcting a team that was wrongly included.
+    /// Its cast vote is kept but no longer counted.
+    pub fn remove_participant(&mut self, team_id: Uuid) {
+        match &mut self.participation {
+            VoteParticipation::Formal { counted, uncounted } => {
+                counted.retain(|id| *id != team_id);
+                uncounted.retain(|id| *id != team_id);
+            },
+            VoteParticipation::Informal(participants) => participants.retain(|id| *id != team_id),
+        }
+    }
+
     // Helper methods
     pub fn is_closed(&self) -> bool {
         matches!(self.status, VoteStatus::Closed)
@@ -208,18 +476,108 @@ impl Vote {
         self.result = Some(match &self.vote_type {
             VoteType::Formal { total_eligible_seats, threshold, .. } => {
                 let (counted, uncounted) = self.count_formal_votes();
-                let passed = (counted.yes() as f64 / *total_eligible_seats as f64) >= *threshold;
-                VoteResult::Formal { counted, uncounted, passed }
+                let (passed, quorum_reached, at_threshold) = self.evaluate_formal(&counted, *total_eligible_seats, *threshold);
+                VoteResult::Formal { counted, uncounted, passed, quorum_reached, at_threshold }
             },
-            VoteType::Informal => {
+            VoteType::Weighted { threshold, weights, .. } => {
+                let (counted, uncounted) = self.count_weighted_votes(weights);
+                // With no points in the epoch yet there is nothing to weigh, so the vote fails
+                let passed = counted.total_weight() > 0
+                    && (counted.yes() as f64 / counted.total_weight() as f64) >= *threshold;
+                VoteResult::Weighted { counted, uncounted, passed, choices: self.votes.clone() }
+            },
+            VoteType::Informal { weighting } => {
                 let count = self.count_informal_votes();
-                VoteResult::Informal { count }
+                let weighted = match weighting {
+                    InformalWeighting::Unweighted => None,
+                    InformalWeighting::Revenue { weights } => Some(self.count_weighted_informal_votes(weights)),
+                };
+                VoteResult::Informal { count, weighted }
+            },
+            VoteType::MultiOption { options, .. } => {
+                let (tallies, uncounted_tallies) = self.count_option_votes(options.len());
+                let winner = tallies.iter().enumerate()
+                    .max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(&a.0)))
+                    .map_or(0, |(index, _)| index);
+                VoteResult::MultiOption { tallies, uncounted_tallies, winner }
             },
         });
 
         Ok(())
     }
 
+    /// The stored result of a closed vote, or the result an open vote would get if it were closed now
+    pub fn projected_result(&self) -> Result<VoteResult, &'static str> {
+        if let Some(result) = &self.result {
+            return Ok(result.clone());
+        }
+        let mut preview = self.clone();
+        preview.calculate_result()?;
+        preview.result.ok_or("Vote has no result")
+    }
+
+    /// Recounts a closed formal vote's cast votes against its current participation, so a team
+    /// moved into or out of the counted seats changes the tallies as well as the quorum
+    pub fn recompute_outcome(&self) -> Result<VoteResult, &'static str> {
+        let (total_eligible_seats, threshold) = match &self.vote_type {
+            VoteType::Formal { total_eligible_seats, threshold, .. } => (*total_eligible_seats, *threshold),
+            VoteType::Weighted { .. } => return Err("Weighted votes are not recomputed"),
+            VoteType::MultiOption { .. } => return Err("Multi-option votes are not recomputed"),
+            VoteType::Informal { .. } => return Err("Informal votes have no outcome"),
+        };
+
+        let Some(VoteResult::Formal { counted: stored_counted, uncounted: stored_uncounted, .. }) = &self.result else {
+            return Err("Vote has no result to recompute");
+        };
+        if self.is_historical {
+            return Err("Historical votes have no cast votes to recount");
+        }
+        // Votes closed before cast votes were kept only have their tallies left
+        if self.votes.is_empty() && stored_counted.total_cast() + stored_uncounted.total_cast() > 0 {
+            return Err("Vote was closed without keeping its cast votes, so it cannot be recounted");
+        }
+
+        let (counted, uncounted) = self.count_formal_votes();
+        let (passed, quorum_reached, at_threshold) = self.evaluate_formal(&counted, total_eligible_seats, threshold);
+        Ok(VoteResult::Formal { counted, uncounted, passed, quorum_reached, at_threshold })
+    }
+
+    /// Whether a closed formal vote would have passed under another threshold, keeping its quorum
+    pub fn simulate_outcome(&self, threshold: f64) -> Result<bool, &'static str> {
+        let total_eligible_seats = match &self.vote_type {
+            VoteType::Formal { total_eligible_seats, .. } => *total_eligible_seats,
+            _ => return Err("Only formal votes can be simulated"),
+        };
+
+        match &self.result {
+            Some(VoteResult::Formal { counted, .. }) => Ok(self.evaluate_formal(counted, total_eligible_seats, threshold).0),
+            _ => Err("Vote has no result to simulate"),
+        }
+    }
+
+    // Abstentions count toward quorum, but only yes votes count toward the threshold
+    fn evaluate_formal(&self, counted: &VoteCount, total_eligible_seats: u32, threshold: f64) -> (bool, bool, bool) {
+        let present = match &self.participation {
+            VoteParticipation::Formal { counted: counted_teams, .. } => counted.total_cast().max(counted_teams.len() as u32),
+            VoteParticipation::Informal(_) => counted.total_cast(),
+        };
+        let quorum_reached = self.quorum_required()
+            .map_or(true, |required| present >= required);
+        let (met, exact) = compare_to_threshold(counted.yes(), total_eligible_seats, threshold);
+        let passed = quorum_reached && met;
+        (passed, quorum_reached, passed && exact)
+    }
+
+    /// Number of counted seats that must cast a vote, for formal votes
+    pub fn quorum_required(&self) -> Option<u32> {
+        match &self.vote_type {
+            VoteType::Formal { total_eligible_seats, quorum, .. } => {
+                Some((quorum * *total_eligible_seats as f64).ceil() as u32)
+            },
+            VoteType::Weighted { .. } | VoteType::Informal { .. } | VoteType::MultiOption { .. } => None,
+        }
+    }
+
     pub fn count_formal_votes(&self) -> (VoteCount, VoteCount) {
         let mut counted = VoteCount::new();
         let mut uncounted = VoteCount::new();
@@ -230,11 +588,47 @@ impl Vote {
                     match choice {
                         VoteChoice::Yes => counted.increment_yes(),
                         VoteChoice::No => counted.increment_no(),
+                        VoteChoice::Abstain => counted.increment_abstain(),
+                        VoteChoice::Option(_) => {},
+                    }
+                } else if uncounted_teams.contains(&team_id) {
+                    match choice {
+                        VoteChoice::Yes => uncounted.increment_yes(),
+                        VoteChoice::No => uncounted.increment_no(),
+                        VoteChoice::Abstain => uncounted.increment_abstain(),
+                        VoteChoice::Option(_) => {},
+                    }
+                }
+            }
+        }
+
+        (counted, uncounted)
+    }
+
+    /// Counted teams add their weight, uncounted teams are tallied by seat as in formal votes.
+    /// Every counted team's weight is in the total, so absent teams count against the vote.
+    pub fn count_weighted_votes(&self, weights: &HashMap<Uuid, u32>) -> (WeightedTally, VoteCount) {
+        let mut counted = WeightedTally::default();
+        let mut uncounted = VoteCount::new();
+
+        counted.total_weight = weights.values().map(|&weight| weight as u64).sum();
+
+        if let VoteParticipation::Formal { counted: counted_teams, uncounted: uncounted_teams } = &self.participation {
+            for (&team_id, &choice) in &self.votes {
+                if counted_teams.contains(&team_id) {
+                    let weight = weights.get(&team_id).copied().unwrap_or(0) as u64;
+                    match choice {
+                        VoteChoice::Yes => counted.yes += weight,
+                        VoteChoice::No => counted.no += weight,
+                        VoteChoice::Abstain => counted.abstain += weight,
+                        VoteChoice::Option(_) => {},
                     }
                 } else if uncounted_teams.contains(&team_id) {
                     match choice {
                         VoteChoice::Yes => uncounted.increment_yes(),
                         VoteChoice::No => uncounted.increment_no(),
+                        VoteChoice::Abstain => uncounted.increment_abstain(),
+                        VoteChoice::Option(_) => {},
                     }
                 }
             }
@@ -243,6 +637,28 @@ impl Vote {
         (counted, uncounted)
     }
 
+    /// Votes per option index from counted and uncounted seats
+    pub fn count_option_votes(&self, option_count: usize) -> (Vec<u32>, Vec<u32>) {
+        let mut counted = vec![0; option_count];
+        let mut uncounted = vec![0; option_count];
+
+        if let VoteParticipation::Formal { counted: counted_teams, uncounted: uncounted_teams } = &self.participation {
+            for (team_id, choice) in &self.votes {
+                let VoteChoice::Option(index) = *choice else { continue };
+                if index >= option_count {
+                    continue;
+                }
+                if counted_teams.contains(team_id) {
+                    counted[index] += 1;
+                } else if uncounted_teams.contains(team_id) {
+                    uncounted[index] += 1;
+                }
+            }
+        }
+
+        (counted, uncounted)
+    }
+
     fn count_informal_votes(&self) -> VoteCount {
         let mut count = VoteCount::new();
 
@@ -250,12 +666,34 @@ impl Vote {
             match choice {
                 VoteChoice::Yes => count.increment_yes(),
                 VoteChoice::No => count.increment_no(),
+                VoteChoice::Abstain => count.increment_abstain(),
+                VoteChoice::Option(_) => {},
             }
         }
 
         count
     }
 
+    /// Teams without a weight, such as supporters under revenue weighting, add nothing
+    fn count_weighted_informal_votes(&self, weights: &HashMap<Uuid, u64>) -> WeightedTally {
+        let mut tally = WeightedTally {
+            total_weight: weights.values().sum(),
+            ..WeightedTally::default()
+        };
+
+        for (team_id, &choice) in &self.votes {
+            let weight = weights.get(team_id).copied().unwrap_or(0);
+            match choice {
+                VoteChoice::Yes => tally.yes += weight,
+                VoteChoice::No => tally.no += weight,
+                VoteChoice::Abstain => tally.abstain += weight,
+                VoteChoice::Option(_) => {},
+            }
+        }
+
+        tally
+    }
+
     // pub fn get_result(&self) -> Option<bool> {
     //     self.result.as_ref().map(|r| match r {
     //         VoteResult::Formal { passed, .. } => *passed,
@@ -280,7 +718,7 @@ impl NameMatches for Vote {
 impl VoteCount {
     // Constructor
     pub fn new() -> Self {
-        Self { yes: 0, no: 0 }
+        Self { yes: 0, no: 0, abstain: 0 }
     }
 
     // Getter methods
@@ -292,6 +730,10 @@ impl VoteCount {
         self.no
     }
 
+    pub fn abstain(&self) -> u32 {
+        self.abstain
+    }
+
     // Increment methods
     pub fn increment_yes(&mut self) {
         self.yes += 1;
@@ -301,11 +743,21 @@ impl VoteCount {
         self.no += 1;
     }
 
+    pub fn increment_abstain(&mut self) {
+        self.abstain += 1;
+    }
+
     // Helper methods
+    /// Yes and no votes; abstentions are tracked separately
     pub fn total(&self) -> u32 {
         self.yes + self.no
     }
 
+    /// Every explicit choice, including abstentions
+    pub fn total_cast(&self) -> u32 {
+        self.yes + self.no + self.abstain
+    }
+
     pub fn yes_percentage(&self) -> f64 {
         if self.total() == 0 {
             0.0
@@ -315,6 +767,25 @@ impl VoteCount {
     }
 }
 
+impl WeightedTally {
+    pub fn yes(&self) -> u64 {
+        self.yes
+    }
+
+    pub fn no(&self) -> u64 {
+        self.no
+    }
+
+    pub fn abstain(&self) -> u64 {
+        self.abstain
+    }
+
+    /// Combined weight of every counted team, voting or not
+    pub fn total_weight(&self) -> u64 {
+        self.total_weight
+    }
+}
+
 impl Default for VoteCount {
     fn default() -> Self {
         Self::new()
@@ -343,20 +814,36 @@ mod tests {
             raffle_id: Uuid::new_v4(),
             total_eligible_seats: 10,
             threshold: 0.5,
+            quorum: 0.0,
             counted_points: 2,
             uncounted_points: 1,
         });
         assert!(matches!(formal_vote.vote_type(), VoteType::Formal { .. }));
         assert_eq!(formal_vote.status(), &VoteStatus::Open);
 
-        let informal_vote = create_test_vote(VoteType::Informal);
-        assert!(matches!(informal_vote.vote_type(), VoteType::Informal));
+        let informal_vote = create_test_vote(VoteType::Informal { weighting: InformalWeighting::Unweighted });
+        assert!(matches!(informal_vote.vote_type(), VoteType::Informal { .. }));
         assert_eq!(informal_vote.status(), &VoteStatus::Open);
     }
 
+    #[test]
+    fn test_legacy_informal_vote_deserializes() {
+        let vote = create_test_vote(VoteType::Informal { weighting: InformalWeighting::Unweighted });
+        let mut value = serde_json::to_value(&vote).unwrap();
+        value["vote_type"] = serde_json::json!("Informal");
+
+        // Informal votes saved before they had a weighting are unweighted
+        let legacy: Vote = serde_json::from_value(value).unwrap();
+        assert_eq!(legacy.vote_type(), &VoteType::Informal { weighting: InformalWeighting::Unweighted });
+        assert_eq!(legacy.id(), vote.id());
+
+        let current: Vote = serde_json::from_value(serde_json::to_value(&vote).unwrap()).unwrap();
+        assert_eq!(current.vote_type(), vote.vote_type());
+    }
+
     #[test]
     fn test_vote_type_and_status() {
-        let mut vote = create_test_vote(VoteType::Informal);
+        let mut vote = create_test_vote(VoteType::Informal { weighting: InformalWeighting::Unweighted });
         assert!(!vote.is_closed());
         
         vote.set_status(VoteStatus::Closed);
@@ -369,6 +856,7 @@ mod tests {
             raffle_id: Uuid::new_v4(),
             total_eligible_seats: 10,
             threshold: 0.5,
+            quorum: 0.0,
             counted_points: 2,
             uncounted_points: 1,
         });
@@ -390,6 +878,7 @@ mod tests {
             raffle_id: Uuid::new_v4(),
             total_eligible_seats: 10,
             threshold: 0.5,
+            quorum: 0.0,
             counted_points: 2,
             uncounted_points: 1,
         });
@@ -406,7 +895,7 @@ mod tests {
 
     #[test]
     fn test_vote_closing() {
-        let mut vote = create_test_vote(VoteType::Informal);
+        let mut vote = create_test_vote(VoteType::Informal { weighting: InformalWeighting::Unweighted });
         
         vote.cast_vote(Uuid::new_v4(), VoteChoice::Yes, None).unwrap();
         vote.cast_vote(Uuid::new_v4(), VoteChoice::No, None).unwrap();
@@ -423,6 +912,7 @@ mod tests {
             raffle_id: Uuid::new_v4(),
             total_eligible_seats: 10,
             threshold: 0.5,
+            quorum: 0.0,
             counted_points: 2,
             uncounted_points: 1,
         });
@@ -446,6 +936,7 @@ mod tests {
             raffle_id: Uuid::new_v4(),
             total_eligible_seats: 3,
             threshold: 0.5,
+            quorum: 0.0,
             counted_points: 2,
             uncounted_points: 1,
         });
@@ -465,12 +956,150 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_recompute_outcome_recounts_votes() {
+        let mut vote = create_test_vote(VoteType::Formal {
+            raffle_id: Uuid::new_v4(),
+            total_eligible_seats: 3,
+            threshold: 0.5,
+            quorum: 0.0,
+            counted_points: 2,
+            uncounted_points: 1,
+        });
+        let raffle_result = RaffleResult::new((0..3).map(|_| Uuid::new_v4()).collect(), vec![]);
+        let teams = raffle_result.counted().to_vec();
+        for (&team_id, choice) in teams.iter().zip([VoteChoice::Yes, VoteChoice::Yes, VoteChoice::No]) {
+            vote.cast_vote(team_id, choice, Some(&raffle_result)).unwrap();
+        }
+        vote.close().unwrap();
+        assert!(matches!(vote.result(), Some(VoteResult::Formal { passed: true, .. })));
+
+        // A wrongly included yes vote no longer counts toward the threshold
+        vote.remove_participant(teams[0]);
+        match vote.recompute_outcome().unwrap() {
+            VoteResult::Formal { counted, passed, .. } => {
+                assert_eq!(counted.yes(), 1);
+                assert!(!passed);
+            },
+            _ => panic!("Expected Formal vote result"),
+        }
+
+        vote.add_participant(teams[0], true).unwrap();
+        assert!(matches!(vote.recompute_outcome().unwrap(), VoteResult::Formal { passed: true, .. }));
+
+        // Closed before cast votes were kept, so only the tallies are left
+        let mut legacy = serde_json::to_value(&vote).unwrap();
+        legacy["votes"] = serde_json::json!({});
+        let legacy: Vote = serde_json::from_value(legacy).unwrap();
+        assert!(legacy.recompute_outcome().is_err());
+    }
+
+    #[test]
+    fn test_vote_at_threshold() {
+        let close_formal = |seats: usize, threshold: f64, yes: usize| {
+            let mut vote = create_test_vote(VoteType::Formal {
+                raffle_id: Uuid::new_v4(),
+                total_eligible_seats: seats as u32,
+                threshold,
+                quorum: 0.0,
+                counted_points: 2,
+                uncounted_points: 1,
+            });
+            let raffle_result = RaffleResult::new((0..seats).map(|_| Uuid::new_v4()).collect(), vec![]);
+            for (i, &team_id) in raffle_result.counted().iter().enumerate() {
+                let choice = if i < yes { VoteChoice::Yes } else { VoteChoice::No };
+                vote.cast_vote(team_id, choice, Some(&raffle_result)).unwrap();
+            }
+            vote.close().unwrap();
+            match vote.result() {
+                Some(VoteResult::Formal { passed, at_threshold, .. }) => (*passed, *at_threshold),
+                _ => panic!("Expected Formal vote result"),
+            }
+        };
+
+        // An even split meets a 0.5 threshold
+        assert_eq!(close_formal(4, 0.5, 2), (true, true));
+        assert_eq!(close_formal(10, 0.7, 7), (true, true));
+        assert_eq!(close_formal(10, 0.7, 8), (true, false));
+        assert_eq!(close_formal(10, 0.7, 6), (false, false));
+    }
+
+    #[test]
+    fn test_vote_quorum() {
+        let mut vote = create_test_vote(VoteType::Formal {
+            raffle_id: Uuid::new_v4(),
+            total_eligible_seats: 7,
+            threshold: 0.2,
+            quorum: 0.5,
+            counted_points: 2,
+            uncounted_points: 1,
+        });
+        assert_eq!(vote.quorum_required(), Some(4));
+
+        let raffle_result = RaffleResult::new((0..7).map(|_| Uuid::new_v4()).collect(), vec![]);
+        vote.cast_vote(raffle_result.counted()[0], VoteChoice::Yes, Some(&raffle_result)).unwrap();
+        vote.cast_vote(raffle_result.counted()[1], VoteChoice::Yes, Some(&raffle_result)).unwrap();
+        vote.close().unwrap();
+
+        // Two yes votes clear the threshold but not the quorum
+        if let Some(VoteResult::Formal { passed, quorum_reached, .. }) = vote.result() {
+            assert!(!passed);
+            assert!(!quorum_reached);
+        } else {
+            panic!("Expected Formal vote result");
+        }
+
+        // Votes stored before quorum existed default to no quorum requirement
+        let legacy: VoteType = serde_json::from_str(
+            &format!(r#"{{"Formal":{{"raffle_id":"{}","total_eligible_seats":7,"threshold":0.7,"counted_points":5,"uncounted_points":2}}}}"#, Uuid::new_v4())
+        ).unwrap();
+        assert!(matches!(legacy, VoteType::Formal { quorum, .. } if quorum == 0.0));
+    }
+
+    #[test]
+    fn test_abstain_votes() {
+        let mut vote = create_test_vote(VoteType::Formal {
+            raffle_id: Uuid::new_v4(),
+            total_eligible_seats: 3,
+            threshold: 0.5,
+            quorum: 0.0,
+            counted_points: 2,
+            uncounted_points: 1,
+        });
+
+        let raffle_result = RaffleResult::new(vec![Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4()], vec![]);
+
+        vote.cast_vote(raffle_result.counted()[0], VoteChoice::Yes, Some(&raffle_result)).unwrap();
+        vote.cast_vote(raffle_result.counted()[1], VoteChoice::Abstain, Some(&raffle_result)).unwrap();
+        vote.cast_vote(raffle_result.counted()[2], VoteChoice::Abstain, Some(&raffle_result)).unwrap();
+
+        let (counted, _) = vote.count_formal_votes();
+        assert_eq!(counted.abstain(), 2);
+        assert_eq!(counted.total(), 1);
+        assert_eq!(counted.total_cast(), 3);
+
+        vote.close().unwrap();
+
+        // Abstentions do not help reach the threshold
+        if let Some(VoteResult::Formal { passed, counted, .. }) = vote.result() {
+            assert!(!passed);
+            assert_eq!(counted.abstain(), 2);
+        } else {
+            panic!("Expected Formal vote result");
+        }
+
+        // Counts stored before abstentions existed still deserialize
+        let legacy: VoteCount = serde_json::from_str(r#"{"yes":1,"no":2}"#).unwrap();
+        assert_eq!(legacy.abstain(), 0);
+    }
+
     #[test]
     fn test_edge_cases_and_error_handling() {
         let mut vote = create_test_vote(VoteType::Formal {
             raffle_id: Uuid::new_v4(),
             total_eligible_seats: 3,
             threshold: 0.5,
+            quorum: 0.0,
             counted_points: 2,
             uncounted_points: 1,
         });
@@ -491,4 +1120,166 @@ mod tests {
         // Attempt to close an already closed vote
         assert!(vote.close().is_err());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_weighted_vote() {
+        let heavy = Uuid::new_v4();
+        let light = Uuid::new_v4();
+        let absent = Uuid::new_v4();
+        let uncounted = Uuid::new_v4();
+        let weights = HashMap::from([(heavy, 10), (light, 3), (absent, 2)]);
+
+        let mut vote = create_test_vote(VoteType::Weighted {
+            raffle_id: Uuid::new_v4(),
+            threshold: 0.6,
+            weights,
+            counted_points: 5,
+            uncounted_points: 2,
+        });
+        let raffle_result = RaffleResult::new(vec![heavy, light, absent], vec![uncounted]);
+
+        vote.cast_vote(heavy, VoteChoice::Yes, Some(&raffle_result)).unwrap();
+        vote.cast_vote(light, VoteChoice::No, Some(&raffle_result)).unwrap();
+        vote.cast_vote(uncounted, VoteChoice::No, Some(&raffle_result)).unwrap();
+        vote.close().unwrap();
+
+        match vote.result() {
+            Some(VoteResult::Weighted { counted, uncounted, passed, choices }) => {
+                assert_eq!(counted.yes(), 10);
+                assert_eq!(counted.no(), 3);
+                assert_eq!(counted.total_weight(), 15);
+                assert_eq!(uncounted.no(), 1);
+                // 10 of 15 points is above the 0.6 threshold, even though seats split 1-1
+                assert!(*passed);
+                assert_eq!(choices.get(&light), Some(&VoteChoice::No));
+            },
+            _ => panic!("Expected weighted result"),
+        }
+        assert!(vote.vote_counts().is_none());
+    }
+
+    #[test]
+    fn test_revenue_weighted_informal_vote() {
+        let big_earner = Uuid::new_v4();
+        let small_earners = [Uuid::new_v4(), Uuid::new_v4()];
+        let weights = HashMap::from([(big_earner, 50_000), (small_earners[0], 5_000), (small_earners[1], 5_000)]);
+
+        let mut vote = create_test_vote(VoteType::Informal { weighting: InformalWeighting::Revenue { weights } });
+        vote.cast_vote(big_earner, VoteChoice::Yes, None).unwrap();
+        for team_id in small_earners {
+            vote.cast_vote(team_id, VoteChoice::No, None).unwrap();
+        }
+        vote.close().unwrap();
+
+        match vote.result() {
+            Some(VoteResult::Informal { count, weighted: Some(weighted) }) => {
+                // No leads by head count, yes leads once revenue is weighed in
+                assert!(count.no() > count.yes());
+                assert!(weighted.yes() > weighted.no());
+                assert_eq!(weighted.total_weight(), 60_000);
+            },
+            _ => panic!("Expected weighted informal result"),
+        }
+    }
+
+    #[test]
+    fn test_rebind_provisional_vote() {
+        let stays = Uuid::new_v4();
+        let leaves = Uuid::new_v4();
+        let mut vote = create_test_vote(VoteType::Formal {
+            raffle_id: Uuid::new_v4(),
+            total_eligible_seats: 2,
+            threshold: 0.5,
+            quorum: 0.0,
+            counted_points: 2,
+            uncounted_points: 1,
+        });
+        let old_result = RaffleResult::new(vec![stays, leaves], vec![]);
+        vote.cast_vote(stays, VoteChoice::Yes, Some(&old_result)).unwrap();
+        vote.cast_vote(leaves, VoteChoice::No, Some(&old_result)).unwrap();
+
+        // Only provisional votes can be moved
+        let new_raffle_id = Uuid::new_v4();
+        let new_result = RaffleResult::new(vec![], vec![stays]);
+        assert!(vote.rebind_to_raffle(new_raffle_id, 1, &new_result).is_err());
+
+        vote.set_provisional(true);
+        assert!(vote.close().is_err());
+
+        let dropped = vote.rebind_to_raffle(new_raffle_id, 1, &new_result).unwrap();
+        assert_eq!(dropped, vec![leaves]);
+        assert!(!vote.is_provisional());
+        assert!(matches!(vote.vote_type(), VoteType::Formal { raffle_id, total_eligible_seats: 1, .. } if *raffle_id == new_raffle_id));
+        match vote.participation() {
+            VoteParticipation::Formal { counted, uncounted } => {
+                assert!(counted.is_empty());
+                assert_eq!(uncounted, &vec![stays]);
+            },
+            _ => panic!("Expected formal participation"),
+        }
+
+        vote.close().unwrap();
+    }
+
+    #[test]
+    fn test_multi_option_vote() {
+        let teams: Vec<Uuid> = (0..4).map(|_| Uuid::new_v4()).collect();
+        let outsider = Uuid::new_v4();
+        let raffle_result = RaffleResult::new(teams[..3].to_vec(), vec![teams[3]]);
+        let mut vote = create_test_vote(VoteType::MultiOption {
+            raffle_id: Uuid::new_v4(),
+            options: vec!["Option A".to_string(), "Option B".to_string(), "Option C".to_string()],
+            total_eligible_seats: 3,
+            counted_points: 2,
+            uncounted_points: 1,
+        });
+
+        assert_eq!(vote.cast_vote(teams[0], VoteChoice::Option(3), Some(&raffle_result)), Err("Option index out of range"));
+        assert!(vote.cast_vote(teams[0], VoteChoice::Yes, Some(&raffle_result)).is_err());
+        assert!(vote.cast_vote(outsider, VoteChoice::Option(0), Some(&raffle_result)).is_err());
+
+        vote.cast_vote(teams[0], VoteChoice::Option(1), Some(&raffle_result)).unwrap();
+        vote.cast_vote(teams[1], VoteChoice::Option(1), Some(&raffle_result)).unwrap();
+        vote.cast_vote(teams[2], VoteChoice::Option(2), Some(&raffle_result)).unwrap();
+        // Uncounted seats are tallied but do not pick the winner
+        vote.cast_vote(teams[3], VoteChoice::Option(2), Some(&raffle_result)).unwrap();
+        vote.close().unwrap();
+
+        match vote.result() {
+            Some(VoteResult::MultiOption { tallies, uncounted_tallies, winner }) => {
+                assert_eq!(tallies, &vec![0, 2, 1]);
+                assert_eq!(uncounted_tallies, &vec![0, 0, 1]);
+                assert_eq!(*winner, 1);
+            },
+            _ => panic!("Expected multi-option result"),
+        }
+    }
+
+    #[test]
+    fn test_multi_option_tie_goes_to_lowest_index() {
+        let teams: Vec<Uuid> = (0..2).map(|_| Uuid::new_v4()).collect();
+        let raffle_result = RaffleResult::new(teams.clone(), vec![]);
+        let mut vote = create_test_vote(VoteType::MultiOption {
+            raffle_id: Uuid::new_v4(),
+            options: vec!["A".to_string(), "B".to_string(), "C".to_string()],
+            total_eligible_seats: 2,
+            counted_points: 2,
+            uncounted_points: 1,
+        });
+        vote.cast_vote(teams[0], VoteChoice::Option(2), Some(&raffle_result)).unwrap();
+        vote.cast_vote(teams[1], VoteChoice::Option(1), Some(&raffle_result)).unwrap();
+        vote.close().unwrap();
+        assert!(matches!(vote.result(), Some(VoteResult::MultiOption { winner: 1, .. })));
+
+        // Option choices are rejected by yes/no votes
+        let mut formal = create_test_vote(VoteType::Formal {
+            raffle_id: Uuid::new_v4(),
+            total_eligible_seats: 2,
+            threshold: 0.5,
+            quorum: 0.0,
+            counted_points: 2,
+            uncounted_points: 1,
+        });
+        assert!(formal.cast_vote(teams[0], VoteChoice::Option(0), Some(&raffle_result)).is_err());
+    }
+}
@@ -1,28 +1,149 @@
 // src/core/state.rs
 
 use chrono::{DateTime, Utc};
-use serde::{Serialize, Deserialize};
-use std::collections::HashMap;
+use serde::{Serialize, Deserialize, Deserializer};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
 use uuid::Uuid;
 
-use crate::core::models::{Team, Proposal, Raffle, Vote, Epoch};
+use crate::core::models::{Team, TeamStatus, Proposal, Raffle, Vote, Epoch};
+use crate::core::migration::CURRENT_SCHEMA_VERSION;
 
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct SystemState {
+    #[serde(deserialize_with = "deserialize_teams")]
     teams: HashMap<Uuid, Team>,
     timestamp: DateTime<Utc>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct BudgetSystemState {
+    /// Set by `migrate_state` when a file is loaded; states built in memory are current
+    #[serde(default = "current_schema_version")]
+    schema_version: u32,
     current_state: SystemState,
-    history: Vec<SystemState>,
+    #[serde(default, deserialize_with = "deserialize_history")]
+    history: Vec<(DateTime<Utc>, StateChange)>,
     proposals: HashMap<Uuid, Proposal>,
     raffles: HashMap<Uuid, Raffle>,
     votes: HashMap<Uuid, Vote>,
     epochs: HashMap<Uuid, Epoch>,
     current_epoch: Option<Uuid>,
+    /// Teams taken off the roster that historical raffles, votes and rewards still refer to
+    #[serde(default, deserialize_with = "deserialize_teams")]
+    archived_teams: HashMap<Uuid, Team>,
+    #[serde(skip)]
+    dirty: DirtyTracker,
+}
+
+/// The entity maps of the state, as row-based stores split them
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StateTable {
+    Teams,
+    Proposals,
+    Raffles,
+    Votes,
+    Epochs,
+    ArchivedTeams,
+}
+
+/// What changed since a store last wrote the state. Handing out a mutable reference counts as a change.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DirtyEntities {
+    /// Nothing is known about what the store holds, so everything must be written
+    all: bool,
+    /// The current epoch, roster timestamp or change log
+    meta: bool,
+    entities: HashSet<(StateTable, Uuid)>,
+}
+
+impl Default for DirtyEntities {
+    fn default() -> Self {
+        Self { all: true, meta: true, entities: HashSet::new() }
+    }
+}
+
+impl DirtyEntities {
+    fn clean() -> Self {
+        Self { all: false, meta: false, entities: HashSet::new() }
+    }
+
+    pub fn is_all(&self) -> bool {
+        self.all
+    }
+
+    pub fn is_meta(&self) -> bool {
+        self.all || self.meta
+    }
+
+    /// Entities added, changed or removed; removed ones are no longer in the state
+    pub fn entities(&self) -> impl Iterator<Item = &(StateTable, Uuid)> {
+        self.entities.iter()
+    }
+
+    fn merge(&mut self, other: DirtyEntities) {
+        self.all |= other.all;
+        self.meta |= other.meta;
+        self.entities.extend(other.entities);
+    }
+}
+
+/// Behind a lock so stores can take the changes from a shared reference to the state.
+/// A copy is not known to match what any store holds, so clones and loaded states start fully dirty.
+#[derive(Debug, Default)]
+struct DirtyTracker(Mutex<DirtyEntities>);
+
+impl Clone for DirtyTracker {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+/// A change to the team roster, logged so past statuses can be replayed without snapshots
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum StateChange {
+    TeamAdded { team_id: Uuid, status: TeamStatus },
+    TeamStatusChanged { team_id: Uuid, from: TeamStatus, to: TeamStatus },
+    TeamRemoved { team_id: Uuid },
+}
+
+impl StateChange {
+    pub fn team_id(&self) -> Uuid {
+        match self {
+            StateChange::TeamAdded { team_id, .. }
+            | StateChange::TeamStatusChanged { team_id, .. }
+            | StateChange::TeamRemoved { team_id } => *team_id,
+        }
+    }
+}
+
+fn current_schema_version() -> u32 {
+    CURRENT_SCHEMA_VERSION
+}
+
+/// Older state files stored whole `SystemState` snapshots in `history`; those entries are dropped
+fn deserialize_history<'de, D>(deserializer: D) -> Result<Vec<(DateTime<Utc>, StateChange)>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let entries: Vec<serde_json::Value> = Vec::deserialize(deserializer)?;
+    Ok(entries.into_iter()
+        .filter_map(|entry| serde_json::from_value(entry).ok())
+        .collect())
+}
+
+/// Earners saved before revenue history was tracked get one entry dated at load time
+fn deserialize_teams<'de, D>(deserializer: D) -> Result<HashMap<Uuid, Team>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let mut teams: HashMap<Uuid, Team> = HashMap::deserialize(deserializer)?;
+    let loaded_at = Utc::now();
+    for team in teams.values_mut() {
+        team.seed_revenue_history(loaded_at);
+    }
+    Ok(teams)
 }
 
 impl SystemState {
@@ -84,6 +205,7 @@ impl SystemState {
 impl BudgetSystemState {
     pub fn new() -> Self {
         Self {
+            schema_version: CURRENT_SCHEMA_VERSION,
             current_state: SystemState::new(HashMap::new()),
             history: Vec::new(),
             proposals: HashMap::new(),
@@ -91,15 +213,21 @@ impl BudgetSystemState {
             votes: HashMap::new(),
             epochs: HashMap::new(),
             current_epoch: None,
+            archived_teams: HashMap::new(),
+            dirty: DirtyTracker::default(),
         }
     }
 
     // Getters
+    pub fn schema_version(&self) -> u32 {
+        self.schema_version
+    }
+
     pub fn current_state(&self) -> &SystemState {
         &self.current_state
     }
 
-    pub fn history(&self) -> &[SystemState] {
+    pub fn history(&self) -> &[(DateTime<Utc>, StateChange)] {
         &self.history
     }
 
@@ -123,23 +251,156 @@ impl BudgetSystemState {
         self.current_epoch
     }
 
+    pub fn archived_teams(&self) -> &HashMap<Uuid, Team> {
+        &self.archived_teams
+    }
+
+    /// Returns what changed since the last call and starts tracking afresh
+    pub fn take_dirty(&self) -> DirtyEntities {
+        std::mem::replace(&mut *self.dirty.0.lock().unwrap(), DirtyEntities::clean())
+    }
+
+    /// Puts back changes a store took but failed to write
+    pub fn restore_dirty(&self, dirty: DirtyEntities) {
+        self.dirty.0.lock().unwrap().merge(dirty);
+    }
+
+    fn mark_dirty(&self, table: StateTable, id: Uuid) {
+        self.dirty.0.lock().unwrap().entities.insert((table, id));
+    }
+
+    fn mark_meta_dirty(&self) {
+        self.dirty.0.lock().unwrap().meta = true;
+    }
+
     // Setters and modifiers
+    /// Replaces the team roster, logging the differences to the current one
     pub fn update_current_state(&mut self, new_state: SystemState) {
-        self.history.push(self.current_state.clone());
+        let mut changes = Vec::new();
+        for (id, team) in new_state.teams() {
+            match self.current_state.get_team(id) {
+                None => changes.push(StateChange::TeamAdded { team_id: *id, status: team.status().clone() }),
+                Some(old) if old.status() != team.status() => changes.push(StateChange::TeamStatusChanged {
+                    team_id: *id,
+                    from: old.status().clone(),
+                    to: team.status().clone(),
+                }),
+                Some(_) => {},
+            }
+        }
+        for id in self.current_state.teams().keys() {
+            if !new_state.teams().contains_key(id) {
+                changes.push(StateChange::TeamRemoved { team_id: *id });
+            }
+        }
+        for id in self.current_state.teams().keys().chain(new_state.teams().keys()) {
+            self.mark_dirty(StateTable::Teams, *id);
+        }
+        self.mark_meta_dirty();
+
         self.current_state = new_state;
         self.current_state.update_timestamp();
+        for change in changes {
+            self.record_change(change);
+        }
+    }
+
+    pub fn record_change(&mut self, change: StateChange) {
+        self.history.push((Utc::now(), change));
+        self.mark_meta_dirty();
     }
 
     pub fn add_team(&mut self, team: Team) -> Uuid {
-        self.current_state.add_team(team)
+        let status = team.status().clone();
+        let id = self.current_state.add_team(team);
+        self.mark_dirty(StateTable::Teams, id);
+        self.record_change(StateChange::TeamAdded { team_id: id, status });
+        id
     }
 
     pub fn remove_team(&mut self, id: Uuid) -> Option<Team> {
-        self.current_state.remove_team(id)
+        let removed = self.current_state.remove_team(id);
+        if removed.is_some() {
+            self.mark_dirty(StateTable::Teams, id);
+            self.record_change(StateChange::TeamRemoved { team_id: id });
+        }
+        removed
     }
 
     pub fn update_team(&mut self, id: Uuid, updated_team: Team) -> Result<(), &'static str> {
-        self.current_state.update_team(id, updated_team)
+        let from = self.current_state.get_team(&id).ok_or("Team not found")?.status().clone();
+        let to = updated_team.status().clone();
+        self.current_state.update_team(id, updated_team)?;
+        self.mark_dirty(StateTable::Teams, id);
+        if from != to {
+            self.record_change(StateChange::TeamStatusChanged { team_id: id, from, to });
+        }
+        Ok(())
+    }
+
+    pub fn update_team_status(&mut self, id: Uuid, status: TeamStatus) -> Result<(), &'static str> {
+        let team = self.current_state.get_team_mut(&id).ok_or("Team not found")?;
+        let from = team.status().clone();
+        team.set_status(status.clone())?;
+        self.mark_dirty(StateTable::Teams, id);
+        if from != status {
+            self.record_change(StateChange::TeamStatusChanged { team_id: id, from, to: status });
+        }
+        Ok(())
+    }
+
+    /// Drops the team's revenue from the change log and raffle snapshots, so redaction leaves none behind
+    pub fn clear_team_revenue(&mut self, team_id: Uuid) {
+        for (_, change) in self.history.iter_mut().filter(|(_, change)| change.team_id() == team_id) {
+            match change {
+                StateChange::TeamAdded { status, .. } => status.clear_revenue(),
+                StateChange::TeamStatusChanged { from, to, .. } => {
+                    from.clear_revenue();
+                    to.clear_revenue();
+                }
+                StateChange::TeamRemoved { .. } => {}
+            }
+        }
+        self.mark_meta_dirty();
+        for raffle in self.raffles.values_mut() {
+            raffle.clear_team_revenue(team_id);
+        }
+        let raffle_ids: Vec<Uuid> = self.raffles.keys().copied().collect();
+        for id in raffle_ids {
+            self.mark_dirty(StateTable::Raffles, id);
+        }
+    }
+
+    /// Status of a team at the given time, replayed from the change log.
+    /// Teams with no logged changes predate the log, so their current status is returned.
+    pub fn team_status_at(&self, team_id: Uuid, at: DateTime<Utc>) -> Option<TeamStatus> {
+        let mut changes = self.history.iter()
+            .filter(|(_, change)| change.team_id() == team_id)
+            .peekable();
+        if changes.peek().is_none() {
+            return self.get_team(&team_id).map(|team| team.status().clone());
+        }
+
+        let mut status = None;
+        let mut replayed_any = false;
+        for (timestamp, change) in changes {
+            if *timestamp > at {
+                // A team that predates the log had the pre-change status before its first logged change
+                if !replayed_any {
+                    if let StateChange::TeamStatusChanged { from, .. } = change {
+                        status = Some(from.clone());
+                    }
+                }
+                break;
+            }
+            replayed_any = true;
+            status = match change {
+                StateChange::TeamAdded { status, .. } => Some(status.clone()),
+                StateChange::TeamStatusChanged { to, .. } => Some(to.clone()),
+                StateChange::TeamRemoved { .. } => None,
+            };
+        }
+        status
     }
 
     pub fn get_team(&self, id: &Uuid) -> Option<&Team> {
@@ -147,51 +408,74 @@ impl BudgetSystemState {
     }
 
     pub fn get_team_mut(&mut self, id: &Uuid) -> Option<&mut Team> {
+        self.mark_dirty(StateTable::Teams, *id);
         self.current_state.get_team_mut(id)
     }
 
+    /// Looks a team up on the roster, then among archived teams; for resolving historical references
+    pub fn find_team(&self, id: &Uuid) -> Option<&Team> {
+        self.current_state.get_team(id).or_else(|| self.archived_teams.get(id))
+    }
+
+    /// Takes the team off the roster, keeping it for historical lookups
+    pub fn archive_team(&mut self, id: Uuid) -> Result<(), &'static str> {
+        let team = self.remove_team(id).ok_or("Team not found")?;
+        self.archived_teams.insert(id, team);
+        self.mark_dirty(StateTable::ArchivedTeams, id);
+        Ok(())
+    }
+
     pub fn add_proposal(&mut self, proposal: &Proposal) -> Uuid {
         let id = proposal.id();
         self.proposals.insert(id, proposal.clone());
+        self.mark_dirty(StateTable::Proposals, id);
         id
     }
 
     pub fn remove_proposal(&mut self, id: Uuid) -> Option<Proposal> {
+        self.mark_dirty(StateTable::Proposals, id);
         self.proposals.remove(&id)
     }
 
     pub fn add_raffle(&mut self, raffle: &Raffle) -> Uuid {
         let id = raffle.id();
         self.raffles.insert(id, raffle.clone());
+        self.mark_dirty(StateTable::Raffles, id);
         id
     }
 
     pub fn remove_raffle(&mut self, id: Uuid) -> Option<Raffle> {
+        self.mark_dirty(StateTable::Raffles, id);
         self.raffles.remove(&id)
     }
 
     pub fn add_vote(&mut self, vote: &Vote) -> Uuid {
         let id = vote.id();
         self.votes.insert(id, vote.clone());
+        self.mark_dirty(StateTable::Votes, id);
         id
     }
 
     pub fn remove_vote(&mut self, id: Uuid) -> Option<Vote> {
+        self.mark_dirty(StateTable::Votes, id);
         self.votes.remove(&id)
     }
 
     pub fn add_epoch(&mut self, epoch: &Epoch) -> Uuid {
         let id = epoch.id();
         self.epochs.insert(id, epoch.clone());
+        self.mark_dirty(StateTable::Epochs, id);
         id
     }
 
     pub fn remove_epoch(&mut self, id: Uuid) -> Option<Epoch> {
+        self.mark_dirty(StateTable::Epochs, id);
         self.epochs.remove(&id)
     }
 
     pub fn set_current_epoch(&mut self, epoch_id: Option<Uuid>) {
         self.current_epoch = epoch_id;
+        self.mark_meta_dirty();
     }
 
     // Helper methods
@@ -200,6 +484,7 @@ impl BudgetSystemState {
     }
 
     pub fn get_proposal_mut(&mut self, id: &Uuid) -> Option<&mut Proposal> {
+        self.mark_dirty(StateTable::Proposals, *id);
         self.proposals.get_mut(id)
     }
 
@@ -208,6 +493,7 @@ impl BudgetSystemState {
     }
 
     pub fn get_raffle_mut(&mut self, id: &Uuid) -> Option<&mut Raffle> {
+        self.mark_dirty(StateTable::Raffles, *id);
         self.raffles.get_mut(id)
     }
 
@@ -216,6 +502,7 @@ impl BudgetSystemState {
     }
 
     pub fn get_vote_mut(&mut self, id: &Uuid) -> Option<&mut Vote> {
+        self.mark_dirty(StateTable::Votes, *id);
         self.votes.get_mut(id)
     }
 
@@ -224,6 +511,7 @@ impl BudgetSystemState {
     }
 
     pub fn get_epoch_mut(&mut self, id: &Uuid) -> Option<&mut Epoch> {
+        self.mark_dirty(StateTable::Epochs, *id);
         self.epochs.get_mut(id)
     }
 
@@ -249,11 +537,11 @@ mod tests {
     use super::*;
     use chrono::Utc;
     use uuid::Uuid;
-    use crate::core::models::{TeamStatus, RaffleConfig, VoteType};
+    use crate::core::models::{TeamStatus, RaffleConfig, VoteType, InformalWeighting};
 
     // Helper functions to create test entities
     fn create_test_team(name: &str) -> Team {
-        Team::new(name.to_string(), "Representative".to_string(), Some(vec![1000, 2000, 3000]), None).unwrap()
+        Team::new(name.to_string(), "Representative".to_string(), Some(vec![1000.0, 2000.0, 3000.0]), None).unwrap()
     }
 
     fn create_test_raffle() -> Raffle {
@@ -277,7 +565,7 @@ mod tests {
         Vote::new(
             Uuid::new_v4(),
             Uuid::new_v4(),
-            VoteType::Informal,
+            VoteType::Informal { weighting: InformalWeighting::Unweighted },
             false
         )
     }
@@ -629,6 +917,36 @@ mod tests {
     }
 
     // Edge Case Tests
+    #[test]
+    fn test_mutators_mark_entities_dirty() {
+        let mut state = BudgetSystemState::new();
+        assert!(state.take_dirty().is_all());
+        assert_eq!(state.take_dirty(), DirtyEntities::clean());
+
+        let team_id = state.add_team(create_test_team("Team A"));
+        let raffle = create_test_raffle();
+        let raffle_id = state.add_raffle(&raffle);
+        let dirty = state.take_dirty();
+        assert!(dirty.is_meta());
+        let entities: HashSet<_> = dirty.entities().copied().collect();
+        assert_eq!(entities, HashSet::from([(StateTable::Teams, team_id), (StateTable::Raffles, raffle_id)]));
+
+        state.get_raffle_mut(&raffle_id).unwrap();
+        state.archive_team(team_id).unwrap();
+        let dirty = state.take_dirty();
+        let entities: HashSet<_> = dirty.entities().copied().collect();
+        assert_eq!(entities, HashSet::from([
+            (StateTable::Raffles, raffle_id),
+            (StateTable::Teams, team_id),
+            (StateTable::ArchivedTeams, team_id),
+        ]));
+
+        // A store that failed to write hands the changes back
+        state.restore_dirty(dirty.clone());
+        assert_eq!(state.take_dirty(), dirty);
+        assert!(state.clone().take_dirty().is_all());
+    }
+
     #[test]
     fn test_remove_non_existent_team() {
         let mut state = SystemState::new(HashMap::new());
@@ -702,7 +1020,7 @@ mod tests {
         let empty_system_state = SystemState::new(HashMap::new());
         state.update_current_state(empty_system_state);
         assert!(state.current_state().teams().is_empty());
-        assert_eq!(state.history().len(), 1);
+        assert!(state.history().is_empty());
     }
 
     // Error Handling Tests
@@ -780,20 +1098,55 @@ mod tests {
         assert_eq!(state.history().len(), 0, "Initial history should be empty");
 
         let team = create_test_team("Test Team");
-        state.add_team(team);
-        assert_eq!(state.history().len(), 0, "Adding a team should not affect history");
+        let team_id = state.add_team(team);
+        assert_eq!(state.history().len(), 1, "Adding a team should be logged");
 
         let current_state = state.current_state().clone();
         state.update_current_state(current_state);
-        assert_eq!(state.history().len(), 1, "Updating current state should add to history");
+        assert_eq!(state.history().len(), 1, "An unchanged roster should not be logged");
 
-        let another_team = create_test_team("Another Team");
-        state.add_team(another_team);
-        assert_eq!(state.history().len(), 1, "Adding another team should not affect history");
+        state.update_team_status(team_id, TeamStatus::Supporter).unwrap();
+        state.update_team_status(team_id, TeamStatus::Supporter).unwrap();
+        assert_eq!(state.history().len(), 2, "Only actual status changes should be logged");
 
-        let new_current_state = state.current_state().clone();
+        let mut new_current_state = state.current_state().clone();
+        new_current_state.remove_team(team_id);
+        new_current_state.add_team(create_test_team("Another Team"));
         state.update_current_state(new_current_state);
-        assert_eq!(state.history().len(), 2, "Updating current state again should add to history");
+        assert_eq!(state.history().len(), 4, "Roster differences should be logged");
+        assert!(matches!(state.history()[1].1, StateChange::TeamStatusChanged { to: TeamStatus::Supporter, .. }));
+    }
+
+    #[test]
+    fn test_team_status_at() {
+        let mut state = BudgetSystemState::new();
+        let team = create_test_team("Test Team");
+        let team_id = team.id();
+        let earner = team.status().clone();
+
+        // A team that predates the log reports its current status
+        state.current_state.add_team(team);
+        assert_eq!(state.team_status_at(team_id, Utc::now()), Some(earner.clone()));
+
+        let t0 = Utc::now() - chrono::Duration::hours(3);
+        let t1 = Utc::now() - chrono::Duration::hours(2);
+        let t2 = Utc::now() - chrono::Duration::hours(1);
+        state.history.push((t1, StateChange::TeamStatusChanged { team_id, from: earner.clone(), to: TeamStatus::Supporter }));
+        state.history.push((t2, StateChange::TeamRemoved { team_id }));
+
+        assert_eq!(state.team_status_at(team_id, t0), Some(earner));
+        assert_eq!(state.team_status_at(team_id, t1), Some(TeamStatus::Supporter));
+        assert_eq!(state.team_status_at(team_id, t2), None);
+        assert_eq!(state.team_status_at(Uuid::new_v4(), t2), None);
+    }
+
+    #[test]
+    fn test_legacy_history_snapshots_are_dropped() {
+        let state = BudgetSystemState::new();
+        let mut value = serde_json::to_value(&state).unwrap();
+        value["history"] = serde_json::json!([serde_json::to_value(SystemState::new(HashMap::new())).unwrap()]);
+        let loaded: BudgetSystemState = serde_json::from_value(value).unwrap();
+        assert!(loaded.history().is_empty());
     }
 
     #[test]
@@ -0,0 +1,362 @@
+// src/core/store.rs
+
+use crate::app_config::{AppConfig, StorageBackend};
+use crate::core::file_system::FileSystem;
+use crate::core::encryption::StateKey;
+use crate::core::state::BudgetSystemState;
+
+use std::error::Error;
+
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStore;
+
+/// Where the budget system state is persisted
+pub trait StateStore: Send + Sync {
+    /// Loads the saved state, or `None` when there is nothing to load yet
+    fn load(&self) -> Result<Option<BudgetSystemState>, Box<dyn Error>>;
+
+    /// Writes the whole state
+    fn save(&self, state: &BudgetSystemState) -> Result<(), Box<dyn Error>>;
+
+    /// Writes what changed since the last load or save. Stores that can't tell fall back to `save`.
+    fn save_incremental(&self, state: &BudgetSystemState) -> Result<(), Box<dyn Error>> {
+        self.save(state)
+    }
+}
+
+/// Opens the store selected by `storage_backend`, at `state_file`
+pub fn open_store(config: &AppConfig) -> Result<Box<dyn StateStore>, Box<dyn Error>> {
+    let encryption_key = StateKey::from_setting(&config.state_encryption_key)?;
+    if encryption_key.is_some() && config.storage_backend != StorageBackend::Json {
+        return Err("state_encryption_key is only supported by the json storage backend".into());
+    }
+
+    match config.storage_backend {
+        StorageBackend::Json => Ok(Box::new(
+            JsonFileStore::new(&config.state_file, config.strict_state_checksum).with_encryption_key(encryption_key)
+        )),
+        #[cfg(feature = "sqlite")]
+        StorageBackend::Sqlite => Ok(Box::new(SqliteStore::open(&config.state_file)?)),
+        #[cfg(not(feature = "sqlite"))]
+        StorageBackend::Sqlite => Err("storage_backend = \"sqlite\" needs robokitty built with the sqlite feature".into()),
+    }
+}
+
+/// Keeps nothing: loads find no state and saves are discarded. Used for sandbox runs.
+pub struct NullStore;
+
+impl StateStore for NullStore {
+    fn load(&self) -> Result<Option<BudgetSystemState>, Box<dyn Error>> {
+        Ok(None)
+    }
+
+    fn save(&self, _state: &BudgetSystemState) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
+
+/// The whole state as one pretty-printed JSON file with a checksum sidecar
+pub struct JsonFileStore {
+    state_file: String,
+    strict_checksum: bool,
+    encryption_key: Option<StateKey>,
+}
+
+impl JsonFileStore {
+    pub fn new(state_file: &str, strict_checksum: bool) -> Self {
+        Self {
+            state_file: state_file.to_string(),
+            strict_checksum,
+            encryption_key: None,
+        }
+    }
+
+    /// Encrypts the file on save and decrypts it on load; `None` keeps it in plaintext
+    pub fn with_encryption_key(mut self, key: Option<StateKey>) -> Self {
+        self.encryption_key = key;
+        self
+    }
+}
+
+impl StateStore for JsonFileStore {
+    fn load(&self) -> Result<Option<BudgetSystemState>, Box<dyn Error>> {
+        FileSystem::try_load_state_strict(&self.state_file, self.strict_checksum, self.encryption_key.as_ref())
+    }
+
+    fn save(&self, state: &BudgetSystemState) -> Result<(), Box<dyn Error>> {
+        FileSystem::save_state_with_key(state, &self.state_file, self.encryption_key.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::models::Team;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_json_file_store_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("state.json").to_str().unwrap().to_string();
+        let store = JsonFileStore::new(&path, true);
+        assert!(store.load().unwrap().is_none());
+
+        let mut state = BudgetSystemState::new();
+        let team_id = state.add_team(Team::new("Team A".to_string(), "Rep A".to_string(), None, None).unwrap());
+        store.save_incremental(&state).unwrap();
+
+        let loaded = store.load().unwrap().unwrap();
+        assert_eq!(loaded.get_team(&team_id), state.get_team(&team_id));
+    }
+
+    #[cfg(not(feature = "sqlite"))]
+    #[test]
+    fn test_sqlite_backend_requires_feature() {
+        let config = AppConfig { storage_backend: StorageBackend::Sqlite, ..AppConfig::default() };
+        assert!(open_store(&config).is_err());
+    }
+}
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use super::StateStore;
+    use crate::core::state::{BudgetSystemState, DirtyEntities, StateTable};
+
+    use rusqlite::{params, Connection};
+    use serde::Serialize;
+    use serde_json::{json, Map, Value};
+    use std::collections::HashMap;
+    use std::error::Error;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Mutex;
+    use uuid::Uuid;
+
+    const TABLES: [&str; 7] = ["teams", "proposals", "raffles", "votes", "epochs", "archived_teams", "meta"];
+
+    /// (table, key, JSON data)
+    type Row = (&'static str, String, String);
+
+    /// Keeps each team, proposal, raffle, vote and epoch as a JSON row keyed by its UUID,
+    /// plus a `meta` table for the current epoch, state timestamp and history.
+    /// Incremental saves only serialize and write the entities the state marked as changed.
+    pub struct SqliteStore {
+        conn: Mutex<Connection>,
+        /// Set once this store has loaded or fully saved a state, so the database matches what its changes apply to
+        synced: AtomicBool,
+    }
+
+    impl SqliteStore {
+        pub fn open(path: &str) -> Result<Self, Box<dyn Error>> {
+            let conn = Connection::open(path)?;
+            for table in TABLES {
+                conn.execute(
+                    &format!("CREATE TABLE IF NOT EXISTS {} (id TEXT PRIMARY KEY, data TEXT NOT NULL)", table),
+                    [],
+                )?;
+            }
+            Ok(Self {
+                conn: Mutex::new(conn),
+                synced: AtomicBool::new(false),
+            })
+        }
+
+        fn table_name(table: StateTable) -> &'static str {
+            match table {
+                StateTable::Teams => "teams",
+                StateTable::Proposals => "proposals",
+                StateTable::Raffles => "raffles",
+                StateTable::Votes => "votes",
+                StateTable::Epochs => "epochs",
+                StateTable::ArchivedTeams => "archived_teams",
+            }
+        }
+
+        /// The entity's JSON, or `None` when it is no longer in the state
+        fn entity_data(state: &BudgetSystemState, table: StateTable, id: &Uuid) -> Result<Option<String>, serde_json::Error> {
+            match table {
+                StateTable::Teams => state.get_team(id).map(serde_json::to_string),
+                StateTable::Proposals => state.get_proposal(id).map(serde_json::to_string),
+                StateTable::Raffles => state.get_raffle(id).map(serde_json::to_string),
+                StateTable::Votes => state.get_vote(id).map(serde_json::to_string),
+                StateTable::Epochs => state.get_epoch(id).map(serde_json::to_string),
+                StateTable::ArchivedTeams => state.archived_teams().get(id).map(serde_json::to_string),
+            }.transpose()
+        }
+
+        fn meta_rows(state: &BudgetSystemState) -> Result<Vec<Row>, serde_json::Error> {
+            Ok(vec![
+                ("meta", "current_epoch".to_string(), serde_json::to_string(&state.current_epoch())?),
+                ("meta", "timestamp".to_string(), serde_json::to_string(&state.current_state().timestamp())?),
+                ("meta", "history".to_string(), serde_json::to_string(state.history())?),
+            ])
+        }
+
+        /// Writes only the entities in `dirty`, deleting the rows of those no longer in the state
+        fn save_changes(&self, state: &BudgetSystemState, dirty: &DirtyEntities) -> Result<(), Box<dyn Error>> {
+            let mut conn = self.conn.lock().map_err(|_| "State store lock poisoned")?;
+            let tx = conn.transaction()?;
+            for (table, id) in dirty.entities() {
+                let name = Self::table_name(*table);
+                match Self::entity_data(state, *table, id)? {
+                    Some(data) => tx.execute(&format!("INSERT OR REPLACE INTO {} (id, data) VALUES (?1, ?2)", name), params![id.to_string(), data])?,
+                    None => tx.execute(&format!("DELETE FROM {} WHERE id = ?1", name), params![id.to_string()])?,
+                };
+            }
+            if dirty.is_meta() {
+                for (table, key, data) in Self::meta_rows(state)? {
+                    tx.execute(&format!("INSERT OR REPLACE INTO {} (id, data) VALUES (?1, ?2)", table), params![key, data])?;
+                }
+            }
+            tx.commit()?;
+            Ok(())
+        }
+
+        fn rows(state: &BudgetSystemState) -> Result<Vec<Row>, serde_json::Error> {
+            fn push<T: Serialize>(rows: &mut Vec<Row>, table: &'static str, items: impl Iterator<Item = (String, T)>) -> Result<(), serde_json::Error> {
+                for (key, item) in items {
+                    rows.push((table, key, serde_json::to_string(&item)?));
+                }
+                Ok(())
+            }
+
+            let mut rows = Vec::new();
+            push(&mut rows, "teams", state.current_state().teams().iter().map(|(id, t)| (id.to_string(), t)))?;
+            push(&mut rows, "proposals", state.proposals().iter().map(|(id, p)| (id.to_string(), p)))?;
+            push(&mut rows, "raffles", state.raffles().iter().map(|(id, r)| (id.to_string(), r)))?;
+            push(&mut rows, "votes", state.votes().iter().map(|(id, v)| (id.to_string(), v)))?;
+            push(&mut rows, "epochs", state.epochs().iter().map(|(id, e)| (id.to_string(), e)))?;
+            push(&mut rows, "archived_teams", state.archived_teams().iter().map(|(id, t)| (id.to_string(), t)))?;
+            rows.extend(Self::meta_rows(state)?);
+            Ok(rows)
+        }
+    }
+
+    impl StateStore for SqliteStore {
+        fn load(&self) -> Result<Option<BudgetSystemState>, Box<dyn Error>> {
+            let conn = self.conn.lock().map_err(|_| "State store lock poisoned")?;
+
+            let mut tables: HashMap<&str, Map<String, Value>> = HashMap::new();
+            for table in TABLES {
+                let mut stmt = conn.prepare(&format!("SELECT id, data FROM {}", table))?;
+                let mut entries = Map::new();
+                for row in stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))? {
+                    let (id, data) = row?;
+                    entries.insert(id, serde_json::from_str(&data)?);
+                }
+                tables.insert(table, entries);
+            }
+
+            let mut meta = tables.remove("meta").unwrap_or_default();
+            if meta.is_empty() {
+                return Ok(None);
+            }
+
+            // Reassemble the JSON shape of `BudgetSystemState` so serde stays the single source of truth
+            let mut table = |name: &str| Value::Object(tables.remove(name).unwrap_or_default());
+            let state: BudgetSystemState = serde_json::from_value(json!({
+                "current_state": {
+                    "teams": table("teams"),
+                    "timestamp": meta.remove("timestamp").unwrap_or(Value::Null),
+                },
+                "history": meta.remove("history").unwrap_or_else(|| json!([])),
+                "proposals": table("proposals"),
+                "raffles": table("raffles"),
+                "votes": table("votes"),
+                "epochs": table("epochs"),
+                "current_epoch": meta.remove("current_epoch").unwrap_or(Value::Null),
+                "archived_teams": table("archived_teams"),
+            }))?;
+
+            // What was just read is what the store holds
+            state.take_dirty();
+            self.synced.store(true, Ordering::SeqCst);
+            Ok(Some(state))
+        }
+
+        fn save(&self, state: &BudgetSystemState) -> Result<(), Box<dyn Error>> {
+            let rows = Self::rows(state)?;
+            let mut conn = self.conn.lock().map_err(|_| "State store lock poisoned")?;
+
+            let tx = conn.transaction()?;
+            for table in TABLES {
+                tx.execute(&format!("DELETE FROM {}", table), [])?;
+            }
+            for (table, key, data) in &rows {
+                tx.execute(&format!("INSERT INTO {} (id, data) VALUES (?1, ?2)", table), params![key, data])?;
+            }
+            tx.commit()?;
+
+            state.take_dirty();
+            self.synced.store(true, Ordering::SeqCst);
+            Ok(())
+        }
+
+        fn save_incremental(&self, state: &BudgetSystemState) -> Result<(), Box<dyn Error>> {
+            let dirty = state.take_dirty();
+            let result = if dirty.is_all() || !self.synced.load(Ordering::SeqCst) {
+                self.save(state)
+            } else {
+                self.save_changes(state, &dirty)
+            };
+            if result.is_err() {
+                state.restore_dirty(dirty);
+            }
+            result
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::core::models::Team;
+        use tempfile::TempDir;
+
+        #[test]
+        fn test_sqlite_round_trip_and_incremental_save() {
+            let temp_dir = TempDir::new().unwrap();
+            let path = temp_dir.path().join("state.db").to_str().unwrap().to_string();
+
+            let store = SqliteStore::open(&path).unwrap();
+            assert!(store.load().unwrap().is_none());
+
+            let mut state = BudgetSystemState::new();
+            let first = state.add_team(Team::new("Team A".to_string(), "Rep A".to_string(), None, None).unwrap());
+            store.save(&state).unwrap();
+
+            let second = state.add_team(Team::new("Team B".to_string(), "Rep B".to_string(), Some(vec![1000.0]), None).unwrap());
+            state.remove_team(first);
+            store.save_incremental(&state).unwrap();
+
+            let loaded = SqliteStore::open(&path).unwrap().load().unwrap().unwrap();
+            assert_eq!(loaded.current_state().team_count(), 1);
+            assert_eq!(loaded.get_team(&second), state.get_team(&second));
+        }
+
+        #[test]
+        fn test_sqlite_incremental_save_writes_only_changed_entities() {
+            let temp_dir = TempDir::new().unwrap();
+            let path = temp_dir.path().join("state.db").to_str().unwrap().to_string();
+
+            let store = SqliteStore::open(&path).unwrap();
+            let mut state = BudgetSystemState::new();
+            let untouched = state.add_team(Team::new("Team A".to_string(), "Rep A".to_string(), None, None).unwrap());
+            let changed = state.add_team(Team::new("Team B".to_string(), "Rep B".to_string(), None, None).unwrap());
+            store.save_incremental(&state).unwrap();
+
+            // Edited behind the store's back, so a rewrite of this row would show
+            let mut row = serde_json::to_value(state.get_team(&untouched).unwrap()).unwrap();
+            row["name"] = json!("Edited Directly");
+            Connection::open(&path).unwrap()
+                .execute("UPDATE teams SET data = ?1 WHERE id = ?2", params![row.to_string(), untouched.to_string()])
+                .unwrap();
+
+            state.get_team_mut(&changed).unwrap().set_name("Team C".to_string());
+            store.save_incremental(&state).unwrap();
+            assert!(state.take_dirty().entities().next().is_none());
+
+            let loaded = SqliteStore::open(&path).unwrap().load().unwrap().unwrap();
+            assert_eq!(loaded.get_team(&untouched).unwrap().name(), "Edited Directly");
+            assert_eq!(loaded.get_team(&changed).unwrap().name(), "Team C");
+        }
+    }
+}
@@ -18,15 +18,23 @@ pub fn initialize_environment() {
 
 pub async fn initialize_system() -> Result<(BudgetSystem, AppConfig), Box<dyn std::error::Error>> {
     let config = AppConfig::new()?;
-    let ethereum_service = Arc::new(EthereumService::new(&config.ipc_path, config.future_block_offset).await?);
-    let state = crate::core::file_system::FileSystem::try_load_state(&config.state_file);
-    let budget_system = BudgetSystem::new(config.clone(), ethereum_service, state).await?;
+    if let Err(errors) = config.validate() {
+        let problems: Vec<String> = errors.iter().map(|error| format!("- {}", error)).collect();
+        return Err(format!("Invalid configuration:\n{}", problems.join("\n")).into());
+    }
+    let ethereum_service = Arc::new(EthereumService::new(
+        &config.ipc_path,
+        config.future_block_offset,
+        std::time::Duration::from_secs(config.raffle_block_timeout_secs),
+    ).await?.with_payout_signer(&config.payout_signer)?);
+    let store = crate::core::store::open_store(&config)?;
+    let budget_system = BudgetSystem::from_store(config.clone(), ethereum_service, store).await?;
     Ok((budget_system, config))
 }
 
 pub async fn run_script_commands(command: Command) -> Result<(), Box<dyn std::error::Error>> {
     let (mut budget_system, config) = initialize_system().await?;
-    lock::create_lock_file()?;
+    lock::create_lock_file().map_err(commands::cli::CliError::from_lock_error)?;
     
     let mut stdout = std::io::stdout();
     let result = commands::cli::execute_command(&mut budget_system, command, &config, &mut stdout).await;
@@ -34,16 +42,25 @@ pub async fn run_script_commands(command: Command) -> Result<(), Box<dyn std::er
     budget_system.save_state()?;
     lock::remove_lock_file()?;
     
-    result
+    Ok(result?)
 }
 
 pub async fn run_telegram_bot() -> Result<(), Box<dyn std::error::Error>> {
     let (budget_system, config) = initialize_system().await?;
     let (command_sender, command_receiver) = tokio::sync::mpsc::channel(100);
-    
-    crate::services::telegram::spawn_command_executor(budget_system, command_receiver);
-    
     let bot = teloxide::Bot::new(&config.telegram.token);
+
+    // Closed votes are announced only when a chat is configured
+    let announcement_sender = if config.telegram.chat_id.is_empty() {
+        None
+    } else {
+        let (announcement_sender, announcement_receiver) = tokio::sync::mpsc::channel(100);
+        crate::services::telegram::spawn_announcer(bot.clone(), config.telegram.chat_id.clone(), announcement_receiver);
+        Some(announcement_sender)
+    };
+
+    crate::services::telegram::spawn_command_executor_with_announcements(budget_system, command_receiver, announcement_sender);
+    
     let telegram_bot = crate::services::telegram::TelegramBot::new(bot, command_sender);
     
     telegram_bot.run().await;
@@ -62,6 +79,21 @@ pub fn escape_markdown(text: &str) -> String {
     escaped
 }
 
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -96,6 +128,13 @@ mod tests {
         assert_eq!(escape_markdown(input), expected);
     }
 
+    #[test]
+    fn test_escape_html() {
+        let input = "<a href=\"x\">Tom & Jerry's</a>";
+        let expected = "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#39;s&lt;/a&gt;";
+        assert_eq!(escape_html(input), expected);
+    }
+
     #[test]
     fn test_escape_markdown_without_special_characters() {
         let input = "Hello World This is a test";
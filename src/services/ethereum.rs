@@ -1,17 +1,71 @@
 use ethers::prelude::*;
-use std::sync::{Arc, atomic::{AtomicU64, Ordering}};
+use std::sync::{Arc, Mutex, atomic::{AtomicU64, Ordering}};
 use async_trait::async_trait;
 use tokio::{
     self,
     time::Duration,
 };
 use downcast_rs::{impl_downcast, DowncastSync};
+use chrono::{DateTime, TimeZone, Utc};
+use log::info;
+use std::env;
+
+/// The node could not deliver what was asked of it, as opposed to a bad request
+#[derive(Debug)]
+pub struct EthereumError(pub String);
+
+impl std::fmt::Display for EthereumError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Ethereum error: {}", self.0)
+    }
+}
+
+impl std::error::Error for EthereumError {}
+
+/// Environment variable holding the payout private key when `payout_signer = "env"`
+pub const PAYOUT_PRIVATE_KEY_VAR: &str = "PAYOUT_PRIVATE_KEY";
+/// Environment variable holding the password of the payout keystore
+pub const PAYOUT_KEYSTORE_PASSWORD_VAR: &str = "PAYOUT_KEYSTORE_PASSWORD";
 
 #[async_trait]
 pub trait EthereumServiceTrait: DowncastSync {
     async fn get_current_block(&self) -> Result<u64, Box<dyn std::error::Error>>;
     async fn get_randomness(&self, block_number: u64) -> Result<String, Box<dyn std::error::Error>>;
     async fn get_raffle_randomness(&self) -> Result<(u64, u64, String), Box<dyn std::error::Error>>;
+    async fn get_block_timestamp(&self, block_number: u64) -> Result<DateTime<Utc>, Box<dyn std::error::Error>>;
+    async fn get_transaction_status(&self, tx_hash: &str) -> Result<TxStatus, Box<dyn std::error::Error>>;
+    /// Sends `amount` of `token` to `to` and returns the transaction hash without waiting for it to confirm
+    async fn send_payment(&self, to: Address, token: &str, amount: f64) -> Result<String, Box<dyn std::error::Error>>;
+
+    /// Polls once a second until `target` is reached, failing once `timeout` has passed
+    async fn wait_for_block(&self, target: u64, timeout: Duration) -> Result<(), Box<dyn std::error::Error>> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let current = self.get_current_block().await?;
+            if current >= target {
+                return Ok(());
+            }
+
+            let now = tokio::time::Instant::now();
+            if now >= deadline {
+                return Err(EthereumError(format!(
+                    "Timed out after {}s waiting for block {} (current block {})",
+                    timeout.as_secs(), target, current
+                )).into());
+            }
+
+            info!("Waiting for block {}: current block {}, {} to go", target, current, target - current);
+            tokio::time::sleep(Duration::from_secs(1).min(deadline - now)).await;
+        }
+    }
+}
+
+/// Where a submitted transaction stands on chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxStatus {
+    Pending,
+    Confirmed { block: u64, confirmations: u64 },
+    Failed,
 }
 
 impl_downcast!(sync EthereumServiceTrait);
@@ -19,30 +73,61 @@ impl_downcast!(sync EthereumServiceTrait);
 pub struct EthereumService {
     client: Arc<Provider<Ipc>>,
     future_block_offset: u64,
+    block_timeout: Duration,
+    /// Only set when payouts are explicitly configured
+    payout_signer: Option<LocalWallet>,
 }
 
 pub struct MockEthereumService {
     current_block: Arc<AtomicU64>,
+    transaction_status: Mutex<TxStatus>,
+    sent_payments: Mutex<Vec<(Address, String, f64)>>,
 }
 
 impl EthereumService {
-    pub async fn new(ipc_path: &str, future_block_offset: u64) -> Result<Self, Box<dyn std::error::Error>> {
+    pub async fn new(ipc_path: &str, future_block_offset: u64, block_timeout: Duration) -> Result<Self, Box<dyn std::error::Error>> {
         let provider = Provider::connect_ipc(ipc_path).await?;
         Ok(Self {
             client: Arc::new(provider),
             future_block_offset,
+            block_timeout,
+            payout_signer: None,
         })
     }
 
+    /// Loads the wallet that signs payouts. An empty `payout_signer` leaves payouts disabled,
+    /// `env` reads the private key from `PAYOUT_PRIVATE_KEY`, and anything else is a keystore
+    /// path unlocked with `PAYOUT_KEYSTORE_PASSWORD`. Only the signer's address is ever logged.
+    pub fn with_payout_signer(mut self, payout_signer: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let wallet = match payout_signer {
+            "" => return Ok(self),
+            "env" => env::var(PAYOUT_PRIVATE_KEY_VAR)
+                .map_err(|_| format!("{} must be set when payout_signer is \"env\"", PAYOUT_PRIVATE_KEY_VAR))?
+                .trim()
+                .parse::<LocalWallet>()
+                .map_err(|_| format!("{} is not a valid private key", PAYOUT_PRIVATE_KEY_VAR))?,
+            keystore_path => {
+                let password = env::var(PAYOUT_KEYSTORE_PASSWORD_VAR)
+                    .map_err(|_| format!("{} must be set to unlock the payout keystore", PAYOUT_KEYSTORE_PASSWORD_VAR))?;
+                LocalWallet::decrypt_keystore(keystore_path, password)
+                    .map_err(|_| format!("Failed to unlock payout keystore: {}", keystore_path))?
+            },
+        };
+
+        info!("Payouts enabled from {:?}", wallet.address());
+        self.payout_signer = Some(wallet);
+        Ok(self)
+    }
+
     async fn get_current_block(&self) -> Result<u64, Box<dyn std::error::Error>> {
         Ok(self.client.get_block_number().await?.as_u64())
     }
 
     async fn get_randomness(&self, block_number: u64) -> Result<String, Box<dyn std::error::Error>> {
         let block = self.client.get_block(block_number).await?
-            .ok_or("Block not found")?;
+            .ok_or_else(|| EthereumError(format!("Block {} not found", block_number)))?;
         block.mix_hash
-            .ok_or_else(|| "Randomness not found".into())
+            .ok_or_else(|| EthereumError(format!("Randomness not found in block {}", block_number)).into())
             .map(|hash| format!("0x{:x}", hash))
     }
 
@@ -50,10 +135,7 @@ impl EthereumService {
         let initiation_block = self.get_current_block().await?;
         let randomness_block = initiation_block + self.future_block_offset;
 
-        // Wait for the randomness block
-        while self.get_current_block().await? < randomness_block {
-            tokio::time::sleep(Duration::from_secs(1)).await;
-        }
+        self.wait_for_block(randomness_block, self.block_timeout).await?;
 
         let randomness = self.get_randomness(randomness_block).await?;
 
@@ -65,12 +147,24 @@ impl MockEthereumService {
     pub fn new() -> Self {
         Self {
             current_block: Arc::new(AtomicU64::new(12345)),
+            transaction_status: Mutex::new(TxStatus::Pending),
+            sent_payments: Mutex::new(Vec::new()),
         }
     }
 
+    /// Payments passed to `send_payment`, in order, as (recipient, token, amount)
+    pub fn sent_payments(&self) -> Vec<(Address, String, f64)> {
+        self.sent_payments.lock().unwrap().clone()
+    }
+
     pub fn increment_block(&self) {
         self.current_block.fetch_add(1, Ordering::SeqCst);
     }
+
+    /// Status returned for every transaction hash
+    pub fn set_transaction_status(&self, status: TxStatus) {
+        *self.transaction_status.lock().unwrap() = status;
+    }
 }
 
 #[async_trait]
@@ -81,9 +175,9 @@ impl EthereumServiceTrait for EthereumService {
 
     async fn get_randomness(&self, block_number: u64) -> Result<String, Box<dyn std::error::Error>> {
         let block = self.client.get_block(block_number).await?
-            .ok_or("Block not found")?;
+            .ok_or_else(|| EthereumError(format!("Block {} not found", block_number)))?;
         block.mix_hash
-            .ok_or_else(|| "Randomness not found".into())
+            .ok_or_else(|| EthereumError(format!("Randomness not found in block {}", block_number)).into())
             .map(|hash| format!("0x{:x}", hash))
     }
 
@@ -91,14 +185,70 @@ impl EthereumServiceTrait for EthereumService {
         let initiation_block = self.get_current_block().await?;
         let randomness_block = initiation_block + self.future_block_offset;
 
-        while self.get_current_block().await? < randomness_block {
-            tokio::time::sleep(Duration::from_secs(1)).await;
-        }
+        self.wait_for_block(randomness_block, self.block_timeout).await?;
 
         let randomness = self.get_randomness(randomness_block).await?;
 
         Ok((initiation_block, randomness_block, randomness))
     }
+
+    async fn get_block_timestamp(&self, block_number: u64) -> Result<DateTime<Utc>, Box<dyn std::error::Error>> {
+        let block = self.client.get_block(block_number).await?
+            .ok_or_else(|| EthereumError(format!("Block {} not found", block_number)))?;
+        Utc.timestamp_opt(block.timestamp.as_u64() as i64, 0)
+            .single()
+            .ok_or_else(|| "Invalid block timestamp".into())
+    }
+
+    async fn get_transaction_status(&self, tx_hash: &str) -> Result<TxStatus, Box<dyn std::error::Error>> {
+        let hash: H256 = tx_hash.parse()
+            .map_err(|_| format!("Invalid transaction hash: {}", tx_hash))?;
+
+        let receipt = match self.client.get_transaction_receipt(hash).await? {
+            Some(receipt) => receipt,
+            None => {
+                // No receipt yet: either still in the mempool or unknown to the node
+                return match self.client.get_transaction(hash).await? {
+                    Some(_) => Ok(TxStatus::Pending),
+                    None => Err(format!("Transaction not found: {}", tx_hash).into()),
+                };
+            }
+        };
+
+        if receipt.status == Some(U64::zero()) {
+            return Ok(TxStatus::Failed);
+        }
+
+        match receipt.block_number {
+            Some(block) => {
+                let block = block.as_u64();
+                let current = self.get_current_block().await?;
+                Ok(TxStatus::Confirmed { block, confirmations: current.saturating_sub(block) + 1 })
+            },
+            None => Ok(TxStatus::Pending),
+        }
+    }
+
+    /// Only native ETH transfers are supported
+    async fn send_payment(&self, to: Address, token: &str, amount: f64) -> Result<String, Box<dyn std::error::Error>> {
+        let signer = self.payout_signer.as_ref()
+            .ok_or("Payouts are disabled: set payout_signer in the config")?;
+        if !token.eq_ignore_ascii_case("ETH") {
+            return Err(format!("Cannot pay out {}: only ETH payouts are supported", token).into());
+        }
+        if amount.is_nan() || amount <= 0.0 {
+            return Err("Payout amount must be positive".into());
+        }
+
+        let chain_id = self.client.get_chainid().await?.as_u64();
+        let client = SignerMiddleware::new(self.client.clone(), signer.clone().with_chain_id(chain_id));
+        let tx = TransactionRequest::new()
+            .to(to)
+            .value(ethers::utils::parse_ether(amount)?);
+        let pending = client.send_transaction(tx, None).await?;
+
+        Ok(format!("{:?}", pending.tx_hash()))
+    }
 }
 
 #[async_trait::async_trait]
@@ -115,4 +265,20 @@ impl EthereumServiceTrait for MockEthereumService {
         let current = self.current_block.load(Ordering::SeqCst);
         Ok((current, current + 10, format!("mock_randomness_for_block_{}", current + 10)))
     }
+
+    /// Twelve-second slots counted from 2020-09-13T12:26:40Z
+    async fn get_block_timestamp(&self, block_number: u64) -> Result<DateTime<Utc>, Box<dyn std::error::Error>> {
+        Ok(Utc.timestamp_opt(1_600_000_000 + block_number as i64 * 12, 0).unwrap())
+    }
+
+    async fn get_transaction_status(&self, _tx_hash: &str) -> Result<TxStatus, Box<dyn std::error::Error>> {
+        Ok(*self.transaction_status.lock().unwrap())
+    }
+
+    /// Records the payment and returns a hash numbered by how many payments came before it
+    async fn send_payment(&self, to: Address, token: &str, amount: f64) -> Result<String, Box<dyn std::error::Error>> {
+        let mut sent = self.sent_payments.lock().unwrap();
+        sent.push((to, token.to_string(), amount));
+        Ok(format!("0x{:064x}", sent.len()))
+    }
 }
\ No newline at end of file
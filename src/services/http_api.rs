@@ -0,0 +1,128 @@
+// src/services/http_api.rs
+
+//! Read-only JSON view of the loaded state for dashboards. Handlers only take read locks,
+//! so the server never writes the state file.
+
+use std::{error::Error, net::SocketAddr, sync::Arc};
+
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    routing::get,
+    Json, Router,
+};
+use log::info;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::core::budget_system::BudgetSystem;
+use crate::core::models::{Epoch, Proposal, Team};
+
+pub type SharedBudgetSystem = Arc<RwLock<BudgetSystem>>;
+
+pub fn router(budget_system: SharedBudgetSystem) -> Router {
+    Router::new()
+        .route("/epochs", get(list_epochs))
+        .route("/epochs/:id/proposals", get(list_epoch_proposals))
+        .route("/teams", get(list_teams))
+        .route("/proposals/:id/report", get(proposal_report))
+        .with_state(budget_system)
+}
+
+/// There is no authentication and `/teams` returns full team records, so `addr` should be
+/// loopback unless the server sits behind something that restricts access.
+pub async fn serve(budget_system: BudgetSystem, addr: SocketAddr) -> Result<(), Box<dyn Error>> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    info!("Serving read-only HTTP API on {}", addr);
+    axum::serve(listener, router(Arc::new(RwLock::new(budget_system)))).await?;
+    Ok(())
+}
+
+async fn list_epochs(State(budget_system): State<SharedBudgetSystem>) -> Json<Vec<Epoch>> {
+    let budget_system = budget_system.read().await;
+    let mut epochs: Vec<Epoch> = budget_system.state().epochs().values().cloned().collect();
+    epochs.sort_by_key(|epoch| epoch.start_date());
+    Json(epochs)
+}
+
+async fn list_epoch_proposals(
+    State(budget_system): State<SharedBudgetSystem>,
+    Path(epoch_id): Path<Uuid>,
+) -> Result<Json<Vec<Proposal>>, (StatusCode, String)> {
+    let budget_system = budget_system.read().await;
+    if budget_system.get_epoch(&epoch_id).is_none() {
+        return Err((StatusCode::NOT_FOUND, format!("Epoch not found: {}", epoch_id)));
+    }
+    let mut proposals: Vec<Proposal> = budget_system.get_proposals_for_epoch(epoch_id).into_iter().cloned().collect();
+    proposals.sort_by(|a, b| a.title().cmp(b.title()));
+    Ok(Json(proposals))
+}
+
+async fn list_teams(State(budget_system): State<SharedBudgetSystem>) -> Json<Vec<Team>> {
+    let budget_system = budget_system.read().await;
+    let mut teams: Vec<Team> = budget_system.state().current_state().teams().values().cloned().collect();
+    teams.sort_by(|a, b| a.name().cmp(b.name()));
+    Json(teams)
+}
+
+async fn proposal_report(
+    State(budget_system): State<SharedBudgetSystem>,
+    Path(proposal_id): Path<Uuid>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let budget_system = budget_system.read().await;
+    if budget_system.get_proposal(&proposal_id).is_none() {
+        return Err((StatusCode::NOT_FOUND, format!("Proposal not found: {}", proposal_id)));
+    }
+    let report = budget_system.generate_proposal_report(proposal_id)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(([(header::CONTENT_TYPE, "text/markdown; charset=utf-8")], report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_config::AppConfig;
+    use crate::services::ethereum::MockEthereumService;
+    use chrono::{Duration, Utc};
+    use tempfile::TempDir;
+
+    async fn create_shared_budget_system(temp_dir: &TempDir) -> SharedBudgetSystem {
+        let config = AppConfig {
+            state_file: temp_dir.path().join("state.json").to_str().unwrap().to_string(),
+            ..AppConfig::default()
+        };
+        let mut budget_system = BudgetSystem::new(config, Arc::new(MockEthereumService::new()), None).await.unwrap();
+        budget_system.create_team("Team A".to_string(), "Rep".to_string(), None, None).unwrap();
+        let epoch_id = budget_system.create_epoch("Epoch 1", Utc::now(), Utc::now() + Duration::days(30)).unwrap();
+        budget_system.activate_epoch(epoch_id).unwrap();
+        budget_system.add_proposal("Proposal A".to_string(), None, None, None, None, None).unwrap();
+        Arc::new(RwLock::new(budget_system))
+    }
+
+    #[tokio::test]
+    async fn test_read_only_endpoints() {
+        let temp_dir = TempDir::new().unwrap();
+        let budget_system = create_shared_budget_system(&temp_dir).await;
+
+        let Json(teams) = list_teams(State(budget_system.clone())).await;
+        assert_eq!(teams.len(), 1);
+        assert_eq!(teams[0].name(), "Team A");
+
+        let Json(epochs) = list_epochs(State(budget_system.clone())).await;
+        assert_eq!(epochs.len(), 1);
+
+        let Json(proposals) = list_epoch_proposals(State(budget_system.clone()), Path(epochs[0].id())).await.unwrap();
+        assert_eq!(proposals.len(), 1);
+        assert_eq!(proposals[0].title(), "Proposal A");
+
+        assert!(proposal_report(State(budget_system.clone()), Path(proposals[0].id())).await.is_ok());
+
+        let (status, _) = list_epoch_proposals(State(budget_system.clone()), Path(Uuid::new_v4())).await.unwrap_err();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let Err((status, _)) = proposal_report(State(budget_system), Path(Uuid::new_v4())).await else {
+            panic!("Expected a missing proposal to be rejected");
+        };
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
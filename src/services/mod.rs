@@ -1,2 +1,4 @@
 pub mod ethereum;
-pub mod telegram;
\ No newline at end of file
+pub mod telegram;
+#[cfg(feature = "http-api")]
+pub mod http_api;
\ No newline at end of file
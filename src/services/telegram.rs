@@ -1,9 +1,9 @@
-use crate::core::budget_system::BudgetSystem;
+use crate::core::budget_system::{BudgetSystem, SystemEvent};
 use crate::commands::telegram::{TelegramCommand, execute_command};
 use teloxide::{
     prelude::*,
     utils::command::BotCommands,
-    types::{LinkPreviewOptions, ParseMode},
+    types::{LinkPreviewOptions, ParseMode, Recipient},
     dispatching::{
         UpdateFilterExt,
         dialogue::{InMemStorage, Storage},
@@ -79,10 +79,60 @@ impl TelegramBot {
     }
 }
 
+/// Posts each announcement to `chat_id`, a numeric chat ID or a channel username
+pub fn spawn_announcer(bot: Bot, chat_id: String, mut announcements: mpsc::Receiver<String>) {
+    let recipient: Recipient = match chat_id.parse::<i64>() {
+        Ok(id) => ChatId(id).into(),
+        Err(_) => Recipient::ChannelUsername(chat_id),
+    };
+
+    tokio::spawn(async move {
+        while let Some(text) = announcements.recv().await {
+            if let Err(e) = bot.send_message(recipient.clone(), text)
+                .parse_mode(ParseMode::MarkdownV2)
+                .await
+            {
+                log::error!("Failed to post announcement: {}", e);
+            }
+        }
+    });
+}
+
+fn format_announcement(budget_system: &BudgetSystem, event: &SystemEvent) -> Option<String> {
+    match event {
+        SystemEvent::VoteClosed { vote_id, passed } => {
+            let report = match budget_system.generate_vote_report(*vote_id) {
+                Ok(report) => report,
+                Err(e) => {
+                    log::error!("Failed to generate vote report for {}: {}", vote_id, e);
+                    return None;
+                }
+            };
+            let outcome = if *passed { "passed" } else { "failed" };
+            Some(format!("Vote closed and {}\n\n{}", outcome, report))
+        },
+    }
+}
+
 pub fn spawn_command_executor(
+    budget_system: BudgetSystem,
+    command_receiver: mpsc::Receiver<(TelegramCommand, oneshot::Sender<String>)>,
+) {
+    spawn_command_executor_with_announcements(budget_system, command_receiver, None);
+}
+
+/// Like `spawn_command_executor`; closed votes are also formatted and sent to `announcement_sender`
+pub fn spawn_command_executor_with_announcements(
     mut budget_system: BudgetSystem,
     mut command_receiver: mpsc::Receiver<(TelegramCommand, oneshot::Sender<String>)>,
+    announcement_sender: Option<mpsc::Sender<String>>,
 ) {
+    let mut event_receiver = announcement_sender.as_ref().map(|_| {
+        let (event_sender, event_receiver) = mpsc::channel(100);
+        budget_system.set_event_sender(Some(event_sender));
+        event_receiver
+    });
+
     tokio::spawn(async move {
         while let Some((telegram_command, response_sender)) = command_receiver.recv().await {
             let response = match execute_command(telegram_command, &mut budget_system).await {
@@ -97,6 +147,16 @@ pub fn spawn_command_executor(
             if let Err(e) = budget_system.save_state() {
                 log::error!("Error saving state: {}", e);
             }
+
+            if let (Some(events), Some(announcements)) = (event_receiver.as_mut(), announcement_sender.as_ref()) {
+                while let Ok(event) = events.try_recv() {
+                    if let Some(text) = format_announcement(&budget_system, &event) {
+                        if announcements.send(crate::escape_markdown(&text)).await.is_err() {
+                            log::error!("Failed to queue announcement");
+                        }
+                    }
+                }
+            }
         }
     });
 }